@@ -3,7 +3,7 @@
 fn main() {
     println!("Hello, MCP!");
     
-    let numbers = vec![1, 2, 3, 4, 5];
+    let numbers = [1, 2, 3, 4, 5];
     let sum: i32 = numbers.iter().sum();
     
     println!("Sum: {}", sum);
@@ -26,6 +26,7 @@ impl Person {
         println!("Hello, my name is {} and I'm {} years old", self.name, self.age);
     }
     
+    #[allow(dead_code)]
     fn birthday(&mut self) {
         self.age += 1;
         println!("Happy birthday! Now I'm {} years old", self.age);
@@ -5,11 +5,46 @@ use tracing::info;
 use tokio::sync::Mutex;
 use serde_json::{json, Value};
 
-use crate::lsp_client::{LspClient, LspClientConfig};
+use crate::analyzer_status::{self, AnalyzerStatus};
+use crate::call_hierarchy::{self, CallHierarchy, Direction};
+use crate::code_actions;
+use crate::config::Config;
+use crate::locations::{self, Location};
+use crate::lsp_client::{self, LspClient, LspClientConfig};
+use crate::outline::{self, OutlineSymbol};
+use crate::path_guard;
+use crate::sessions::{CloseReport, FailedDocument, OpenReport, Sessions};
+use crate::source_files::{FileIndex, SourceFile};
+use crate::timings::{self, Phase};
+use crate::type_hierarchy::{self, Direction as TypeDirection, TypeHierarchy};
 
-// Temporary stub types for testing
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct FileId(u32);
+pub use crate::source_files::{FileId, TextRange, TextSize};
+
+/// A file over `max_lsp_file_size` was given to a query that needs
+/// rust-analyzer. Callers can tell it apart with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileTooLarge {
+    pub file: String,
+    pub size: u64,
+    pub limit: u64,
+}
+
+impl std::fmt::Display for FileTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "File too large for LSP analysis: {} ({} bytes, limit {})", self.file, self.size, self.limit)
+    }
+}
+
+impl std::error::Error for FileTooLarge {}
+
+/// `result`, with `FileTooLarge` turned into an empty answer, for callers
+/// that fall back to reading the source when rust-analyzer has nothing
+pub fn skip_too_large<T: Default>(result: Result<T>) -> Result<T> {
+    match result {
+        Err(e) if e.is::<FileTooLarge>() => Ok(T::default()),
+        result => result,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct FilePosition {
@@ -23,40 +58,22 @@ pub struct FileRange {
     pub range: TextRange,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct TextSize(u32);
-
-#[derive(Debug, Clone)]
-pub struct TextRange {
-    start: TextSize,
-    end: TextSize,
-}
-
-impl TextRange {
-    pub fn new(start: TextSize, end: TextSize) -> Self {
-        Self { start, end }
-    }
-}
-
-// Stub types that will be replaced by real rust-analyzer types
-pub struct Analysis;
-pub struct AnalysisHost;
-pub struct Vfs;
-pub struct VfsPath;
-
 pub struct RustAnalyzer {
     project_root: PathBuf,
     lsp_client: Arc<Mutex<Option<LspClient>>>,
     use_lsp: bool,
-    // Legacy fields for compatibility
-    host: AnalysisHost,
-    analysis: Analysis,
-    vfs: Arc<Vfs>,
-    file_counter: u32,
+    config: Config,
+    files: std::sync::Mutex<FileIndex>,
+    sessions: std::sync::Mutex<Sessions>,
 }
 
 impl RustAnalyzer {
     pub async fn new(project_path: &str) -> Result<Self> {
+        let config = Config::resolve(Path::new(project_path))?;
+        Self::with_config(project_path, config).await
+    }
+    
+    pub async fn with_config(project_path: &str, config: Config) -> Result<Self> {
         info!("Initializing Rust Analyzer for project: {}", project_path);
         
         let project_root = PathBuf::from(project_path);
@@ -66,32 +83,27 @@ impl RustAnalyzer {
             anyhow::bail!("No Cargo.toml found in project root");
         }
         
-        // Check if we should use LSP client (default: true)
-        let use_lsp = std::env::var("USE_LSP")
-            .map(|v| v == "true")
-            .unwrap_or(true);  // Default to true
+        // Check if we should use LSP client (default: true, see Config for overrides)
+        let use_lsp = config.use_lsp;
         
         // Don't initialize LSP client during construction
         // It will be initialized lazily on first use
         let lsp_client = None;
-        
-        // Temporary stub implementation
-        let host = AnalysisHost;
-        let vfs = Vfs;
-        let analysis = Analysis;
+        let files = std::sync::Mutex::new(FileIndex::with_ignore(&project_root, &config.ignore)?);
         
         Ok(Self {
             project_root,
             lsp_client: Arc::new(Mutex::new(lsp_client)),
             use_lsp,
-            host,
-            analysis,
-            vfs: Arc::new(vfs),
-            file_counter: 0,
+            config,
+            files,
+            sessions: std::sync::Mutex::new(Sessions::new()),
         })
     }
     
     pub async fn hover(&self, file_path: &str, line: u32, column: u32) -> Result<Option<String>> {
+        self.ensure_lsp_sized(file_path)?;
+        
         // Initialize LSP client lazily if needed
         if self.use_lsp {
             let mut lsp_guard = self.lsp_client.lock().await;
@@ -178,6 +190,8 @@ impl RustAnalyzer {
     }
     
     pub async fn completions(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<Value>> {
+        self.ensure_lsp_sized(file_path)?;
+        
         // Initialize LSP client lazily if needed
         if self.use_lsp {
             let mut lsp_guard = self.lsp_client.lock().await;
@@ -243,6 +257,11 @@ impl RustAnalyzer {
                             transformed["sortText"] = sort_text.clone();
                         }
                         
+                        // Keep data so the item can be resolved later
+                        if let Some(data) = item.get("data") {
+                            transformed["data"] = data.clone();
+                        }
+                        
                         transformed
                     }).collect();
                     
@@ -260,6 +279,8 @@ impl RustAnalyzer {
     }
     
     pub async fn find_references(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<Value>> {
+        self.ensure_lsp_sized(file_path)?;
+        
         if let Some(client) = self.lsp_client.lock().await.as_mut() {
             // Ensure document is open with absolute path
             let full_path = if file_path.starts_with('/') {
@@ -309,6 +330,8 @@ impl RustAnalyzer {
     }
     
     pub async fn rename(&self, file: &str, line: u32, column: u32, new_name: &str) -> Result<Value> {
+        self.ensure_lsp_sized(file)?;
+        
         if let Some(client) = self.lsp_client.lock().await.as_mut() {
             // Ensure document is open
             let full_path = if file.starts_with(&self.project_root.to_string_lossy().to_string()) {
@@ -334,19 +357,17 @@ impl RustAnalyzer {
                 Ok(result) => Ok(result),
                 Err(e) => {
                     info!("LSP rename failed: {}", e);
-                    Ok(json!({
-                        "error": format!("Rename failed: {}", e)
-                    }))
+                    Err(anyhow::anyhow!("Rename failed: {}", e))
                 }
             }
         } else {
-            Ok(json!({
-                "error": "LSP not available"
-            }))
+            anyhow::bail!("LSP not available")
         }
     }
     
     pub async fn signature_help(&self, file_path: &str, line: u32, column: u32) -> Result<Value> {
+        self.ensure_lsp_sized(file_path)?;
+        
         if let Some(client) = self.lsp_client.lock().await.as_mut() {
             // Ensure document is open with absolute path
             let full_path = if file_path.starts_with('/') {
@@ -387,6 +408,8 @@ impl RustAnalyzer {
     }
     
     pub async fn find_implementations(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<Value>> {
+        self.ensure_lsp_sized(file_path)?;
+        
         if let Some(client) = self.lsp_client.lock().await.as_mut() {
             // Ensure document is open with absolute path
             let full_path = if file_path.starts_with('/') {
@@ -432,14 +455,531 @@ impl RustAnalyzer {
         }
     }
     
+    /// Definitions of the symbol at a position, with 1-based lines and columns.
+    /// Empty when rust-analyzer is unavailable or finds none.
+    pub async fn goto_definition(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<Location>> {
+        self.ensure_lsp_sized(file_path)?;
+        
+        // Initialize LSP client lazily if needed
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        if let Some(client) = self.lsp_client.lock().await.as_mut() {
+            let full_path = if file_path.starts_with('/') {
+                std::path::PathBuf::from(file_path)
+            } else {
+                self.project_root.join(file_path)
+            };
+            
+            let canonical_path = full_path.canonicalize()
+                .unwrap_or_else(|_| full_path.clone());
+            
+            let file_uri = format!("file://{}", canonical_path.to_string_lossy());
+            
+            let _ = client.did_open(&canonical_path.to_string_lossy()).await;
+            
+            let params = json!({
+                "textDocument": {
+                    "uri": file_uri
+                },
+                "position": {
+                    "line": line - 1,  // LSP uses 0-based
+                    "character": column - 1
+                }
+            });
+            
+            match client.goto_definition(params).await {
+                Ok(result) => Ok(locations::from_lsp(&result, &self.project_root)),
+                Err(e) => {
+                    info!("LSP goto definition failed: {}", e);
+                    Ok(Vec::new())
+                }
+            }
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    /// Symbols of a file, nested as they are in the source. Empty when
+    /// rust-analyzer is unavailable.
+    pub async fn document_symbols(&self, file_path: &str) -> Result<Vec<OutlineSymbol>> {
+        self.ensure_lsp_sized(file_path)?;
+        
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        if let Some(client) = self.lsp_client.lock().await.as_mut() {
+            let full_path = self.project_root.join(file_path);
+            let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+            let _ = client.did_open(&canonical_path.to_string_lossy()).await;
+            
+            let params = json!({
+                "textDocument": {
+                    "uri": format!("file://{}", canonical_path.to_string_lossy())
+                }
+            });
+            
+            match client.document_symbol(params).await {
+                Ok(result) => Ok(outline::from_lsp(&result)),
+                Err(e) => {
+                    info!("LSP document symbols failed: {}", e);
+                    Ok(Vec::new())
+                }
+            }
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    /// Call hierarchy items for the function at a position. Empty when
+    /// rust-analyzer is unavailable or there is no function there.
+    pub async fn prepare_call_hierarchy(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<Value>> {
+        self.ensure_lsp_sized(file_path)?;
+        
+        // Initialize LSP client lazily if needed
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        if let Some(client) = self.lsp_client.lock().await.as_mut() {
+            let full_path = self.project_root.join(file_path);
+            let canonical_path = full_path.canonicalize()
+                .unwrap_or_else(|_| full_path.clone());
+            
+            let _ = client.did_open(&canonical_path.to_string_lossy()).await;
+            
+            let params = json!({
+                "textDocument": {
+                    "uri": format!("file://{}", canonical_path.to_string_lossy())
+                },
+                "position": {
+                    "line": line - 1,  // LSP uses 0-based
+                    "character": column - 1
+                }
+            });
+            
+            match client.prepare_call_hierarchy(params).await {
+                Ok(Value::Array(items)) => Ok(items),
+                Ok(_) => Ok(Vec::new()),
+                Err(e) => {
+                    info!("LSP prepare call hierarchy failed: {}", e);
+                    Ok(Vec::new())
+                }
+            }
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    /// `CallHierarchyIncomingCall`s of an item from `prepare_call_hierarchy`
+    pub async fn incoming_calls(&self, item: Value) -> Result<Vec<Value>> {
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("rust-analyzer is not available");
+        };
+        match client.incoming_calls(item).await? {
+            Value::Array(calls) => Ok(calls),
+            _ => Ok(Vec::new()),
+        }
+    }
+    
+    /// `CallHierarchyOutgoingCall`s of an item from `prepare_call_hierarchy`
+    pub async fn outgoing_calls(&self, item: Value) -> Result<Vec<Value>> {
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("rust-analyzer is not available");
+        };
+        match client.outgoing_calls(item).await? {
+            Value::Array(calls) => Ok(calls),
+            _ => Ok(Vec::new()),
+        }
+    }
+    
+    /// Supertypes or subtypes of the type at a position. `type` is null and
+    /// `types` empty when rust-analyzer is unavailable or there is no type
+    /// there; a server without type hierarchy support answers with an error.
+    pub async fn type_hierarchy(&self, file_path: &str, line: u32, column: u32, direction: TypeDirection) -> Result<TypeHierarchy> {
+        self.ensure_lsp_sized(file_path)?;
+        let mut hierarchy = TypeHierarchy { root: None, direction, types: Vec::new() };
+        
+        // Initialize LSP client lazily if needed
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            return Ok(hierarchy);
+        };
+        let full_path = self.project_root.join(file_path);
+        let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+        let _ = client.did_open(&canonical_path.to_string_lossy()).await;
+        
+        let params = json!({
+            "textDocument": {
+                "uri": format!("file://{}", canonical_path.to_string_lossy())
+            },
+            "position": {
+                "line": line - 1,  // LSP uses 0-based
+                "character": column - 1
+            }
+        });
+        let Some(item) = client.prepare_type_hierarchy(params).await?.as_array().and_then(|items| items.first()).cloned() else {
+            return Ok(hierarchy);
+        };
+        let related = match direction {
+            TypeDirection::Supertypes => client.supertypes(item.clone()).await?,
+            TypeDirection::Subtypes => client.subtypes(item.clone()).await?,
+        };
+        hierarchy.root = type_hierarchy::item_from_lsp(&item, &self.project_root);
+        hierarchy.types = type_hierarchy::types_from_lsp(&related, &self.project_root);
+        Ok(hierarchy)
+    }
+    
+    /// Callers or callees of the function at a position, with 1-based lines
+    /// and columns. `function` is null and `calls` empty when rust-analyzer is
+    /// unavailable or there is no function there.
+    pub async fn call_hierarchy(&self, file_path: &str, line: u32, column: u32, direction: Direction) -> Result<CallHierarchy> {
+        let mut hierarchy = CallHierarchy { function: None, direction, calls: Vec::new() };
+        let Some(item) = self.prepare_call_hierarchy(file_path, line, column).await?.into_iter().next() else {
+            return Ok(hierarchy);
+        };
+        let calls = match direction {
+            Direction::Incoming => self.incoming_calls(item.clone()).await?,
+            Direction::Outgoing => self.outgoing_calls(item.clone()).await?,
+        };
+        hierarchy.function = call_hierarchy::item_from_lsp(&item, &self.project_root);
+        hierarchy.calls = calls.iter()
+            .filter_map(|call| call_hierarchy::call_from_lsp(call, &item, direction, &self.project_root))
+            .collect();
+        Ok(hierarchy)
+    }
+    
+    /// Push `changes` (LSP `{range, text}` objects) to rust-analyzer's buffer
+    /// for `file_path`; the file on disk is left alone. Returns the new document
+    /// version and whether the edits were sent as ranges.
+    pub async fn apply_change(&self, file_path: &str, changes: Vec<Value>) -> Result<(i32, bool)> {
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("LSP not available");
+        };
+        let full_path = self.project_root.join(file_path);
+        let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+        let version = client.did_change(&canonical_path.to_string_lossy(), changes).await?;
+        Ok((version, client.incremental_sync()))
+    }
+    
+    /// Replace rust-analyzer's buffer for `file_path` with an editor's unsaved
+    /// `text` at `version`, so later queries on the file see it. Returns the
+    /// tracked version afterwards and whether the text was applied, which it
+    /// isn't when `version` is older than the tracked one.
+    pub async fn sync_document(&self, file_path: &str, version: i32, text: String) -> Result<(i32, bool)> {
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("LSP not available");
+        };
+        let full_path = self.project_root.join(file_path);
+        let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+        let path = canonical_path.to_string_lossy();
+        let applied = client.did_change_text(&path, version, text).await?;
+        Ok((client.document_version(&path).unwrap_or(version), applied))
+    }
+    
+    /// Open `files` on rust-analyzer and keep them open for `session` (a new
+    /// one when `None`) until it is closed. Files that can't be read, are over
+    /// the LSP size cap or that the server rejects are reported as failed.
+    pub async fn open_documents(&self, session: Option<&str>, files: &[String]) -> Result<OpenReport> {
+        let root = self.project_root.canonicalize().unwrap_or_else(|_| self.project_root.clone());
+        let mut report = OpenReport::default();
+        let mut candidates = Vec::new();
+        for file in files {
+            let fail = |error: String| FailedDocument { file: file.clone(), error };
+            let resolved = match path_guard::resolve_within(file, &self.project_root, &self.config.allowed_roots) {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    report.failed.push(fail(e.to_string()));
+                    continue;
+                }
+            };
+            if !resolved.is_file() {
+                report.failed.push(fail("No such file".to_string()));
+            } else if let Some(size) = self.oversized_file(file) {
+                report.failed.push(fail(format!("File too large for LSP analysis ({} bytes)", size)));
+            } else {
+                let relative = resolved.strip_prefix(&root).unwrap_or(&resolved).display().to_string();
+                candidates.push((relative, resolved));
+            }
+        }
+        
+        if !candidates.is_empty() && self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        let mut lsp_guard = self.lsp_client.lock().await;
+        for (file, resolved) in candidates {
+            let opened = match lsp_guard.as_mut() {
+                Some(client) => timings::measure(Phase::LspRequest, client.did_open(&resolved.to_string_lossy())).await,
+                None => Err(anyhow::anyhow!("LSP not available")),
+            };
+            match opened {
+                Ok(()) => report.opened.push(file),
+                Err(e) => report.failed.push(FailedDocument { file, error: e.to_string() }),
+            }
+        }
+        drop(lsp_guard);
+        
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        report.session = session.map(str::to_string).unwrap_or_else(|| sessions.new_id());
+        report.pinned = sessions.pin(&report.session, report.opened.iter().cloned());
+        Ok(report)
+    }
+    
+    /// End `session`, closing on rust-analyzer the documents no other session
+    /// keeps open
+    pub async fn close_session(&self, session: &str) -> Result<CloseReport> {
+        let (closed, kept_open) = {
+            let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+            sessions.close(session).ok_or_else(|| match sessions.ids() {
+                ids if ids.is_empty() => anyhow::anyhow!("Unknown session: {}; no session is open", session),
+                ids => anyhow::anyhow!("Unknown session: {}; open sessions: {}", session, ids.join(", ")),
+            })?
+        };
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        if let Some(client) = lsp_guard.as_mut() {
+            for file in &closed {
+                let full_path = self.project_root.join(file);
+                let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+                let _ = client.did_close(&canonical_path.to_string_lossy()).await;
+            }
+        }
+        Ok(CloseReport { session: session.to_string(), closed, kept_open })
+    }
+    
+    /// Shut rust-analyzer down and wait for its process to exit, so none is
+    /// left behind when the server stops. A later request starts it again.
+    pub async fn shutdown_lsp(&self) {
+        let client = self.lsp_client.lock().await.take();
+        if let Some(mut client) = client {
+            if let Err(e) = client.shutdown().await {
+                info!("LSP shutdown failed: {}", e);
+            }
+        }
+    }
+    
+    /// Send `method` to rust-analyzer as is and return the raw result
+    pub async fn lsp_request(&self, method: &str, params: Value) -> Result<Value> {
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("LSP not available");
+        };
+        client.send_request(method, params).await
+    }
+    
+    /// Code actions of the given kinds for a range of `file_path` (LSP range:
+    /// 0-based lines, UTF-16 characters)
+    pub async fn code_actions(&self, file_path: &str, range: Value, only: Vec<String>) -> Result<Vec<Value>> {
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("LSP not available");
+        };
+        let full_path = self.project_root.join(file_path);
+        let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+        let full_path = canonical_path.to_string_lossy();
+        client.did_open(&full_path).await?;
+        
+        let response = client.code_action(json!({
+            "textDocument": { "uri": format!("file://{}", full_path) },
+            "range": range,
+            "context": { "diagnostics": [], "only": only }
+        })).await?;
+        Ok(code_actions::code_actions(&response))
+    }
+    
+    /// `action` with its `edit` filled in, resolving it lazily when needed
+    pub async fn resolve_code_action(&self, action: Value) -> Result<Value> {
+        if action.get("edit").is_some() {
+            return Ok(action);
+        }
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("LSP not available");
+        };
+        client.code_action_resolve(action).await
+    }
+    
+    /// `item` with its lazily computed fields (documentation, detail) filled in.
+    /// Items without resolve `data` come back as they are.
+    pub async fn resolve_completion(&self, item: Value) -> Result<Value> {
+        if item.get("data").is_none() {
+            return Ok(item);
+        }
+        
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            anyhow::bail!("LSP not available");
+        };
+        client.completion_resolve(item).await
+    }
+    
+    /// Stream `workspace/symbol` results for `query` into `batches`.
+    /// Returns the symbol count and whether the server sent partial results.
+    pub async fn workspace_symbols_streaming(
+        &self,
+        query: &str,
+        batches: tokio::sync::mpsc::Sender<Vec<Value>>,
+    ) -> Result<(usize, bool)> {
+        if self.use_lsp {
+            let mut lsp_guard = self.lsp_client.lock().await;
+            if lsp_guard.is_none() {
+                *lsp_guard = self.try_initialize_lsp().await;
+            }
+        }
+        
+        match self.lsp_client.lock().await.as_mut() {
+            Some(client) => client.workspace_symbol_streaming(query, batches).await,
+            None => anyhow::bail!("LSP not available"),
+        }
+    }
+    
+    /// Status and memory use of the running rust-analyzer. Doesn't start one:
+    /// while background initialization is still going, or with the LSP
+    /// disabled, the report says rust-analyzer isn't available.
+    pub async fn analyzer_status(&self, file_path: Option<&str>, include_memory: bool) -> AnalyzerStatus {
+        let mut report = AnalyzerStatus::default();
+        let mut lsp_guard = self.lsp_client.lock().await;
+        let Some(client) = lsp_guard.as_mut() else {
+            report.errors.push(if self.use_lsp {
+                "rust-analyzer is not running yet; it may still be starting up".to_string()
+            } else {
+                "LSP is disabled by configuration (use_lsp = false)".to_string()
+            });
+            return report;
+        };
+        report.lsp_available = true;
+        
+        let params = match file_path {
+            Some(file) => {
+                let full_path = self.project_root.join(file);
+                let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+                json!({ "textDocument": { "uri": format!("file://{}", canonical_path.display()) } })
+            }
+            None => json!({}),
+        };
+        match client.analyzer_status(params).await {
+            Ok(status) => report.status = status.as_str().map(str::to_string),
+            Err(e) => report.errors.push(format!("analyzerStatus failed: {}", e)),
+        }
+        if include_memory {
+            match client.memory_usage().await {
+                Ok(usage) => {
+                    report.memory = analyzer_status::parse_memory_usage(usage.as_str().unwrap_or(""));
+                    report.total_bytes = report.memory.iter().map(|m| m.bytes).sum();
+                }
+                Err(e) => report.errors.push(format!("memoryUsage failed: {}", e)),
+            }
+        }
+        report
+    }
+    
     pub fn project_root(&self) -> &Path {
         &self.project_root
     }
     
-    // Legacy methods for compatibility
-    pub fn get_file_id(&self, _file_path: &str) -> Result<FileId> {
-        // Temporary stub implementation
-        Ok(FileId(0))
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+    
+    /// Size of `file_path` in bytes when it exceeds `max_lsp_file_size`.
+    /// Such files are never opened in rust-analyzer.
+    pub fn oversized_file(&self, file_path: &str) -> Option<u64> {
+        let limit = self.config.max_lsp_file_size;
+        if limit == 0 {
+            return None;
+        }
+        let size = std::fs::metadata(self.project_root.join(file_path)).ok()?.len();
+        (size > limit).then_some(size)
+    }
+    
+    /// Fails with `FileTooLarge` when `file_path` is over the size cap, so
+    /// LSP queries on it are refused rather than answered with nothing
+    fn ensure_lsp_sized(&self, file_path: &str) -> Result<()> {
+        match self.oversized_file(file_path) {
+            Some(size) => Err(FileTooLarge {
+                file: file_path.to_string(),
+                size,
+                limit: self.config.max_lsp_file_size,
+            }.into()),
+            None => Ok(()),
+        }
+    }
+    
+    /// Result returned by position-based commands instead of querying LSP
+    /// when the file is over the size cap
+    pub fn file_too_large(&self, file_path: &str) -> Option<Value> {
+        let size = self.oversized_file(file_path)?;
+        Some(json!({
+            "file": file_path,
+            "skipped": true,
+            "reason": "file too large for LSP analysis",
+            "file_size": size,
+            "max_lsp_file_size": self.config.max_lsp_file_size
+        }))
+    }
+    
+    /// Stable id of a file, relative to the project root or absolute
+    pub fn get_file_id(&self, file_path: &str) -> Result<FileId> {
+        self.file_index().file_id(Path::new(file_path))
+    }
+    
+    /// Contents of an indexed file, cached until it changes on disk
+    pub fn source_file(&self, file_id: FileId) -> Result<Arc<SourceFile>> {
+        timings::measure_sync(Phase::FileScan, || self.file_index().source(file_id))
     }
     
     pub fn get_file_position(&self, file_path: &str, line: u32, column: u32) -> Result<FilePosition> {
@@ -460,24 +1000,26 @@ impl RustAnalyzer {
         })
     }
     
-    fn line_col_to_offset(&self, _file_id: FileId, _line: u32, _column: u32) -> Result<TextSize> {
-        // Temporary stub implementation
-        Ok(TextSize(0))
-    }
-    
-    pub fn analysis(&self) -> &Analysis {
-        &self.analysis
+    /// Byte offset of a 1-based line and column
+    fn line_col_to_offset(&self, file_id: FileId, line: u32, column: u32) -> Result<TextSize> {
+        self.source_file(file_id)?.offset(line, column)
     }
     
+    /// Rescan the workspace after files were added or removed
     pub fn reload_workspace(&mut self) -> Result<()> {
         info!("Reloading workspace");
-        // Implementation for reloading workspace after file changes
+        self.files.get_mut().unwrap_or_else(|e| e.into_inner()).scan();
         Ok(())
     }
     
+    /// The workspace's `.rs` files with their ids, skipping `target`, hidden
+    /// directories, gitignored paths and the config's `ignore` globs
     pub fn get_all_files(&self) -> Vec<(FileId, PathBuf)> {
-        // Temporary stub implementation
-        vec![(FileId(0), self.project_root.join("src/lib.rs"))]
+        timings::measure_sync(Phase::FileScan, || self.file_index().files())
+    }
+    
+    fn file_index(&self) -> std::sync::MutexGuard<'_, FileIndex> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
     }
     
     pub async fn get_lsp_client(&self) -> Option<tokio::sync::MutexGuard<'_, Option<LspClient>>> {
@@ -487,14 +1029,15 @@ impl RustAnalyzer {
     async fn try_initialize_lsp(&self) -> Option<LspClient> {
         info!("Attempting to initialize LSP client");
         let config = LspClientConfig {
-            server_path: "rust-analyzer".to_string(),
+            server_path: self.config.rust_analyzer_path.clone(),
             server_args: vec![],
             root_path: self.project_root.clone(),
+            timeout: lsp_client::request_timeout_from_env(),
         };
         
-        match LspClient::new(config) {
+        match LspClient::new(config).map(|c| c.with_content_format(self.config.content_format)) {
             Ok(mut client) => {
-                match client.initialize().await {
+                match timings::measure(Phase::LspInit, client.initialize()).await {
                     Ok(_) => {
                         info!("LSP client initialized successfully");
                         Some(client)
@@ -520,18 +1063,21 @@ impl RustAnalyzer {
         
         let lsp_client = self.lsp_client.clone();
         let project_root = self.project_root.clone();
+        let server_path = self.config.rust_analyzer_path.clone();
+        let content_format = self.config.content_format;
         
         // Spawn background task to initialize LSP
         tokio::spawn(async move {
             info!("Starting background LSP initialization for project: {}", project_root.display());
             let config = LspClientConfig {
-                server_path: "rust-analyzer".to_string(),
+                server_path,
                 server_args: vec![],
                 root_path: project_root.clone(),
+                timeout: lsp_client::request_timeout_from_env(),
             };
             
             info!("Creating LSP client...");
-            match LspClient::new(config) {
+            match LspClient::new(config).map(|c| c.with_content_format(content_format)) {
                 Ok(mut client) => {
                     info!("LSP client created, initializing...");
                     match client.initialize().await {
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+/// One line of rust-analyzer's `memoryUsage` report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    /// Salsa query name, or `Remaining` for memory not attributed to a query
+    pub name: String,
+    /// Size as rust-analyzer prints it, e.g. `12.5mb`
+    pub size: String,
+    pub bytes: u64,
+    /// Number of memoized entries, when the server reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entries: Option<u64>,
+}
+
+/// Index and memory state of the running rust-analyzer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnalyzerStatus {
+    /// Whether a rust-analyzer process answered; the rest is empty otherwise
+    pub lsp_available: bool,
+    /// `rust-analyzer/analyzerStatus` text as returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Largest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub memory: Vec<MemoryEntry>,
+    pub total_bytes: u64,
+    /// Why a part of the report is missing
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Parse a size such as `512b`, `3.4kb`, `12mb` or `1.1gb` into bytes
+pub fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim().to_ascii_lowercase();
+    let split = size.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = size.split_at(split);
+    let factor = match unit {
+        "b" => 1u64,
+        "kb" => 1 << 10,
+        "mb" => 1 << 20,
+        "gb" => 1 << 30,
+        _ => return None,
+    };
+    let number: f64 = number.trim().parse().ok()?;
+    Some((number * factor as f64).round() as u64)
+}
+
+/// Parse the `memoryUsage` text: lines of `<size> [<entries>] <name>`.
+/// Lines that don't start with a size are skipped. Entries are sorted
+/// largest first.
+pub fn parse_memory_usage(text: &str) -> Vec<MemoryEntry> {
+    let mut entries: Vec<MemoryEntry> = text.lines().filter_map(|line| {
+        let mut words = line.split_whitespace();
+        let size = words.next()?;
+        let bytes = parse_size(size)?;
+        let rest: Vec<&str> = words.collect();
+        let (count, name) = match rest.split_first() {
+            Some((first, name)) if !name.is_empty() && first.parse::<u64>().is_ok() => (first.parse().ok(), name.join(" ")),
+            _ => (None, rest.join(" ")),
+        };
+        (!name.is_empty()).then(|| MemoryEntry { name, size: size.to_string(), bytes, entries: count })
+    }).collect();
+    entries.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    entries
+}
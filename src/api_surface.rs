@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::metrics::ModuleInfo;
+use crate::module_tree::{self, ItemCounts};
+use crate::reexports;
+use crate::source_files;
+
+/// Share of public items at or above which a module is flagged for review
+pub const DEFAULT_MAX_PUBLIC_RATIO: f64 = 0.75;
+
+/// Modules with fewer items are never flagged: a two-item module that is all
+/// `pub` says little about encapsulation
+pub const DEFAULT_MIN_ITEMS: usize = 5;
+
+/// A module's API surface
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleSurface {
+    #[serde(flatten)]
+    pub info: ModuleInfo,
+    /// Public items over all items, 0 for an empty module
+    pub public_ratio: f64,
+    /// `lib.rs` or `main.rs`, which exist to expose the crate and are never flagged
+    pub crate_root: bool,
+    /// Flagged as an encapsulation-review candidate
+    pub review: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiSurfaceReport {
+    pub max_public_ratio: f64,
+    pub min_items: usize,
+    pub totals: ItemCounts,
+    /// Public ratio over every item of the crate
+    pub public_ratio: f64,
+    /// Module paths of the flagged modules, largest public surface first
+    pub review_candidates: Vec<String>,
+    /// Every module, largest public surface first
+    pub modules: Vec<ModuleSurface>,
+}
+
+/// Public and private top-level item counts of every module file under
+/// `src`, counted as `module_tree` does. Binaries under `src/bin` are left out.
+pub fn modules(project_root: &Path) -> Vec<ModuleInfo> {
+    let src = project_root.join("src");
+    source_files::rust_files(&src).into_iter()
+        .filter_map(|file| {
+            let name = reexports::module_path_for(&src, &file)?.join("::");
+            let counts = module_tree::count_items(&std::fs::read_to_string(&file).ok()?);
+            Some(ModuleInfo {
+                name,
+                path: file.strip_prefix(project_root).unwrap_or(&file).display().to_string(),
+                public_items: counts.public,
+                private_items: counts.private,
+            })
+        })
+        .collect()
+}
+
+/// Rate each module's public surface. A module with at least `min_items`
+/// items whose public share reaches `max_public_ratio` is a review candidate.
+pub fn analyze(project_root: &Path, max_public_ratio: f64, min_items: usize) -> ApiSurfaceReport {
+    let mut totals = ItemCounts::default();
+    let mut modules: Vec<ModuleSurface> = modules(project_root).into_iter()
+        .map(|info| {
+            totals.public += info.public_items;
+            totals.private += info.private_items;
+            let items = info.public_items + info.private_items;
+            let public_ratio = ratio(info.public_items, items);
+            let crate_root = info.name == "crate";
+            ModuleSurface {
+                review: !crate_root && items >= min_items && public_ratio >= max_public_ratio,
+                info,
+                public_ratio,
+                crate_root,
+            }
+        })
+        .collect();
+    modules.sort_by(|a, b| b.info.public_items.cmp(&a.info.public_items).then_with(|| a.info.path.cmp(&b.info.path)));
+
+    ApiSurfaceReport {
+        max_public_ratio,
+        min_items,
+        totals,
+        public_ratio: ratio(totals.public, totals.public + totals.private),
+        review_candidates: modules.iter().filter(|m| m.review).map(|m| m.info.name.clone()).collect(),
+        modules,
+    }
+}
+
+/// `part / whole` rounded to two decimals
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        return 0.0;
+    }
+    (part as f64 / whole as f64 * 100.0).round() / 100.0
+}
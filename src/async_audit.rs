@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::module_graph;
+use crate::reexports::{self, UseLeaf};
+use crate::source_files;
+
+/// Blocking APIs by path: category and what to use instead
+const BLOCKING_PATHS: &[(&str, &str, &str)] = &[
+    ("std::fs", "fs", "use tokio::fs or move the call into spawn_blocking"),
+    ("std::thread::sleep", "sleep", "use tokio::time::sleep"),
+    ("std::io::stdin", "io", "use tokio::io::stdin"),
+    ("std::net::TcpStream", "net", "use tokio::net::TcpStream"),
+    ("std::net::TcpListener", "net", "use tokio::net::TcpListener"),
+    ("std::net::UdpSocket", "net", "use tokio::net::UdpSocket"),
+    ("std::process::Command", "process", "use tokio::process::Command"),
+    ("reqwest::blocking", "http", "use the async reqwest::Client"),
+];
+
+/// Locks whose `.lock()` blocks the executor thread while waiting
+const BLOCKING_LOCKS: &[&str] = &["std::sync::Mutex", "parking_lot::Mutex"];
+
+/// Methods that block, or panic, whatever the receiver when called on a runtime thread
+const BLOCKING_METHODS: &[(&str, &str, &str)] = &[
+    ("blocking_lock", "runtime", "await .lock() instead"),
+    ("blocking_recv", "runtime", "await .recv() instead"),
+    ("blocking_send", "runtime", "await .send() instead"),
+    ("block_on", "runtime", "await the future instead of nesting a runtime"),
+];
+
+/// Calls whose closure runs off the async executor, where blocking is fine
+const SYNC_CONTEXTS: &[&str] = &["spawn_blocking(", "block_in_place(", "thread::spawn("];
+
+/// A likely-blocking call inside an async function or block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockingCall {
+    pub file: String,
+    /// 1-based
+    pub line: usize,
+    /// 1-based
+    pub column: usize,
+    /// Enclosing async fn; `async block in f` for blocks inside other functions
+    pub function: String,
+    /// `fs`, `sleep`, `io`, `net`, `process`, `http`, `mutex` or `runtime`
+    pub category: String,
+    /// The call as matched, e.g. `fs::read_to_string`
+    pub call: String,
+    pub suggestion: String,
+    pub code: String,
+}
+
+/// An async function and how many blocking calls it makes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsyncFunction {
+    pub file: String,
+    pub name: String,
+    /// 1-based line of the signature
+    pub line: usize,
+    pub blocking_calls: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AsyncAuditReport {
+    pub files_scanned: usize,
+    pub async_functions: Vec<AsyncFunction>,
+    pub total: usize,
+    pub by_category: BTreeMap<String, usize>,
+    pub findings: Vec<BlockingCall>,
+}
+
+/// A function body or block being scanned
+struct Context {
+    /// Function name, or how an async block is reported
+    name: String,
+    is_async: bool,
+    /// Brace depth just outside the body
+    depth: i32,
+    /// Index into the file's async functions, for counting
+    function: Option<usize>,
+}
+
+/// Scan the `.rs` files under `dir` for blocking calls in async code. Test
+/// modules and `tests/` directories are skipped unless `include_tests` is set.
+pub fn audit(project_root: &Path, dir: &Path, include_tests: bool) -> AsyncAuditReport {
+    let mut report = AsyncAuditReport::default();
+    for file in source_files::rust_files(dir) {
+        let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+        if !include_tests && Path::new(&display).starts_with("tests") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        report.files_scanned += 1;
+
+        let (functions, findings) = scan_source(&content, include_tests);
+        report.async_functions.extend(functions.into_iter().map(|f| AsyncFunction { file: display.clone(), ..f }));
+        report.findings.extend(findings.into_iter().map(|f| BlockingCall { file: display.clone(), ..f }));
+    }
+
+    report.async_functions.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    report.findings.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)).then(a.column.cmp(&b.column)));
+    for finding in &report.findings {
+        *report.by_category.entry(finding.category.clone()).or_default() += 1;
+    }
+    report.total = report.findings.len();
+    report
+}
+
+/// Async functions of `source` and the blocking calls made in async code.
+/// `file` is left empty for the caller to fill in.
+pub fn scan_source(source: &str, include_tests: bool) -> (Vec<AsyncFunction>, Vec<BlockingCall>) {
+    let patterns = blocking_patterns(source);
+    let blocking_locks = uses_blocking_lock(source);
+
+    let mut functions: Vec<AsyncFunction> = Vec::new();
+    let mut findings = Vec::new();
+    let mut stack: Vec<Context> = Vec::new();
+    let mut pending: Option<Context> = None;
+    let mut depth = 0i32;
+    let mut brackets = 0i32;
+
+    for (index, line) in source.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        let trimmed = code.trim();
+        if !include_tests && trimmed.starts_with("#[cfg(test)]") {
+            break;
+        }
+        if trimmed.starts_with("use ") || trimmed.starts_with("pub use ") {
+            continue;
+        }
+
+        if let Some((name, is_async)) = fn_header(trimmed) {
+            let function = is_async.then(|| {
+                functions.push(AsyncFunction { file: String::new(), name: name.clone(), line: index + 1, blocking_calls: 0 });
+                functions.len() - 1
+            });
+            pending = Some(Context { name, is_async, depth: 0, function });
+        }
+
+        // Where on this line contexts open: async blocks and closures run elsewhere
+        let mut openings: Vec<(usize, Context)> = Vec::new();
+        for at in code.match_indices("async").map(|(at, _)| at) {
+            let rest = code[at + 5..].trim_start();
+            let rest = rest.strip_prefix("move").map_or(rest, str::trim_start);
+            if rest.starts_with('{') {
+                let brace = code.len() - rest.len();
+                // Blocks in an async fn count towards it; elsewhere they are reported on their own
+                let (name, function) = match pending.as_ref().or_else(|| stack.iter().rev().find(|c| !c.name.is_empty())) {
+                    Some(outer) if outer.function.is_some() => (outer.name.clone(), outer.function),
+                    Some(outer) => (format!("async block in {}", outer.name.trim_start_matches("async block in ")), None),
+                    None => ("async block".to_string(), None),
+                };
+                openings.push((brace, Context { name, is_async: true, depth: 0, function }));
+            }
+        }
+        // Closures without braces end on this line: everything after the marker is exempt
+        let mut sync_from: Option<usize> = None;
+        for marker in SYNC_CONTEXTS {
+            let Some(at) = code.find(marker) else { continue };
+            match code[at..].find('{') {
+                Some(brace) => openings.push((at + brace, Context { name: String::new(), is_async: false, depth: 0, function: None })),
+                None => sync_from = Some(sync_from.map_or(at, |from| from.min(at))),
+            }
+        }
+
+        let mut matches = find_calls(code, &patterns, blocking_locks);
+        matches.sort_by_key(|m| m.0);
+        let mut matches = matches.into_iter().peekable();
+
+        for (column, c) in code.char_indices() {
+            while let Some((at, ..)) = matches.peek() {
+                if *at > column {
+                    break;
+                }
+                let (at, call, category, suggestion) = matches.next().unwrap();
+                if sync_from.is_some_and(|from| at > from) {
+                    continue;
+                }
+                let Some(context) = stack.last().filter(|c| c.is_async) else { continue };
+                if let Some(function) = context.function {
+                    functions[function].blocking_calls += 1;
+                }
+                findings.push(BlockingCall {
+                    file: String::new(),
+                    line: index + 1,
+                    column: at + 1,
+                    function: context.name.clone(),
+                    category: category.to_string(),
+                    call,
+                    suggestion: suggestion.to_string(),
+                    code: trimmed.to_string(),
+                });
+            }
+
+            match c {
+                '{' => {
+                    let opened = openings.iter().position(|(at, _)| *at == column).map(|i| openings.remove(i).1).or_else(|| pending.take());
+                    if let Some(mut context) = opened {
+                        context.depth = depth;
+                        stack.push(context);
+                    }
+                    depth += 1;
+                }
+                '}' => {
+                    depth -= 1;
+                    while stack.last().is_some_and(|c| c.depth >= depth) {
+                        stack.pop();
+                    }
+                }
+                '(' | '[' => brackets += 1,
+                ')' | ']' => brackets -= 1,
+                // A declaration without a body, e.g. in a trait
+                ';' if brackets == 0 => pending = None,
+                _ => {}
+            }
+        }
+    }
+    (functions, findings)
+}
+
+/// Name of the function a line declares, and whether it is async
+pub(crate) fn fn_header(code: &str) -> Option<(String, bool)> {
+    let at = code.find("fn ")?;
+    let before = &code[..at];
+    if !(before.is_empty() || before.ends_with(' ')) {
+        return None;
+    }
+    let qualifiers = before.split_whitespace()
+        .all(|word| word.starts_with("pub") || matches!(word, "async" | "const" | "unsafe" | "extern" | "default") || word.starts_with('"'));
+    if !qualifiers {
+        return None;
+    }
+    let name: String = code[at + 3..].chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+    (!name.is_empty()).then(|| (name, before.split_whitespace().any(|word| word == "async")))
+}
+
+/// How the blocking APIs can be spelled in `source`: their full paths, and
+/// shorter names brought in by its `use` declarations. Each pattern maps to
+/// its category and suggestion.
+fn blocking_patterns(source: &str) -> Vec<(String, &'static str, &'static str)> {
+    let mut leaves: Vec<UseLeaf> = ["std", "reqwest"].iter()
+        .map(|root| UseLeaf { path: vec![root.to_string()], alias: None })
+        .collect();
+    for (_, statement) in module_graph::use_statements(source) {
+        leaves.extend(reexports::parse_use_tree(&statement));
+    }
+
+    let mut patterns = Vec::new();
+    for (path, category, suggestion) in BLOCKING_PATHS {
+        let blocking: Vec<&str> = path.split("::").collect();
+        for leaf in &leaves {
+            let Some(local) = leaf.alias.as_ref().or(leaf.path.last()) else { continue };
+            let path: Vec<&str> = leaf.path.iter().map(String::as_str).collect();
+            let pattern = if path.starts_with(&blocking) {
+                local.clone()
+            } else if blocking.starts_with(&path) {
+                std::iter::once(local.as_str()).chain(blocking[path.len()..].iter().copied()).collect::<Vec<_>>().join("::")
+            } else {
+                continue;
+            };
+            if local != "_" && local != "*" && !patterns.iter().any(|(p, _, _)| *p == pattern) {
+                patterns.push((pattern, *category, *suggestion));
+            }
+        }
+    }
+    patterns
+}
+
+/// Whether `source` uses a mutex whose `.lock()` blocks, by path or import
+fn uses_blocking_lock(source: &str) -> bool {
+    BLOCKING_LOCKS.iter().any(|lock| source.contains(lock))
+        || module_graph::use_statements(source).iter()
+            .flat_map(|(_, statement)| reexports::parse_use_tree(statement))
+            .any(|leaf| BLOCKING_LOCKS.contains(&leaf.path.join("::").as_str()))
+}
+
+/// Byte offset, call text, category and suggestion of each blocking call on a line
+fn find_calls(code: &str, patterns: &[(String, &'static str, &'static str)], blocking_locks: bool) -> Vec<(usize, String, &'static str, &'static str)> {
+    let mut calls = Vec::new();
+    for (pattern, category, suggestion) in patterns {
+        for (at, _) in code.match_indices(pattern.as_str()) {
+            let before = code[..at].chars().next_back();
+            let after = &code[at + pattern.len()..];
+            let boundary = !before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == ':');
+            if boundary && (after.starts_with("::") || after.starts_with('(')) {
+                let call = after.strip_prefix("::").map_or(pattern.clone(), |rest| {
+                    let name: String = rest.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+                    format!("{}::{}", pattern, name)
+                });
+                calls.push((at, call, *category, *suggestion));
+            }
+        }
+    }
+
+    if blocking_locks {
+        for (at, _) in code.match_indices(".lock()") {
+            if !code[at + 7..].trim_start().starts_with(".await") {
+                calls.push((at + 1, "lock".to_string(), "mutex", "hold a tokio::sync::Mutex across awaits, or keep the std lock out of async code"));
+            }
+        }
+    }
+    for (method, category, suggestion) in BLOCKING_METHODS {
+        for (at, _) in code.match_indices(&format!("{}(", method)) {
+            let before = code[..at].chars().next_back();
+            if !before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                calls.push((at, method.to_string(), *category, *suggestion));
+            }
+        }
+    }
+    calls
+}
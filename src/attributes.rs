@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::source_files;
+use crate::visibility::parse_item;
+
+/// One `#[...]` or `#![...]` attribute as written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attribute {
+    /// Path of the attribute, e.g. `derive`, `cfg` or `tokio::main`
+    pub path: String,
+    /// Full text from `#` to the closing `]`, line breaks included
+    pub text: String,
+    /// `#![...]`, applying to the enclosing item
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub inner: bool,
+    /// 1-based lines of the `#` and of the closing `]`
+    pub line: usize,
+    pub end_line: usize,
+    /// Byte range of the attribute in the source
+    #[serde(skip)]
+    pub span: (usize, usize),
+}
+
+/// The outer attributes attached to one item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemAttributes {
+    /// `fn`, `struct`, `module`, ...; absent for fields, variants and other
+    /// declarations that aren't items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The line the attributes apply to, trimmed
+    pub declaration: String,
+    /// 1-based line of the declaration
+    pub line: usize,
+    /// In source order
+    pub attributes: Vec<Attribute>,
+}
+
+/// How often one attribute path is used
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeCount {
+    pub path: String,
+    pub count: usize,
+    /// Files using it at least once
+    pub files: usize,
+}
+
+/// Attribute tallies across a source tree
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttributeUsage {
+    pub files_scanned: usize,
+    pub total: usize,
+    /// Most used first
+    pub attributes: Vec<AttributeCount>,
+}
+
+/// Every attribute of `source`, in order. Attributes may span several lines,
+/// share a line with others or with the item, and contain brackets inside
+/// string literals; those in comments and strings are ignored.
+pub fn attributes(source: &str) -> Vec<Attribute> {
+    let bytes = source.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = skip_literal_or_comment(bytes, i) {
+            i = end;
+            continue;
+        }
+        if bytes[i] == b'#' {
+            let mut open = i + 1;
+            let inner = bytes.get(open) == Some(&b'!');
+            if inner {
+                open += 1;
+            }
+            while bytes.get(open).is_some_and(u8::is_ascii_whitespace) {
+                open += 1;
+            }
+            if bytes.get(open) == Some(&b'[') {
+                if let Some(close) = closing_bracket(bytes, open) {
+                    let text = &source[i..=close];
+                    let body = source[open + 1..close].trim_start();
+                    let path: String = body.chars().take_while(|c| c.is_alphanumeric() || *c == '_' || *c == ':').collect();
+                    found.push(Attribute {
+                        path,
+                        text: text.to_string(),
+                        inner,
+                        line: line_of(source, i),
+                        end_line: line_of(source, close),
+                        span: (i, close + 1),
+                    });
+                    i = close + 1;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+/// The outer attributes of the item declared at, or annotated at, 1-based
+/// `line`: a line inside the attributes or doc comments above an item finds
+/// that item
+pub fn item_attributes(source: &str, line: usize) -> Option<ItemAttributes> {
+    let all = attributes(source);
+    let bytes = source.as_bytes();
+    let mut pos = source.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum::<usize>();
+    loop {
+        if let Some(attribute) = all.iter().find(|a| a.span.0 <= pos && pos < a.span.1) {
+            pos = attribute.span.1;
+        } else if bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+            pos += 1;
+        } else if source[pos..].starts_with("//") || source[pos..].starts_with("/*") {
+            pos = skip_literal_or_comment(bytes, pos)?;
+        } else {
+            break;
+        }
+    }
+    if pos >= source.len() || matches!(bytes[pos], b'}' | b')' | b']') {
+        return None;
+    }
+
+    let mut attributes = Vec::new();
+    let mut boundary = pos;
+    for attribute in all.iter().rev().filter(|a| a.span.1 <= pos) {
+        if attribute.inner || !is_trivia(&source[attribute.span.1..boundary]) {
+            break;
+        }
+        boundary = attribute.span.0;
+        attributes.push(attribute.clone());
+    }
+    attributes.reverse();
+
+    let declaration = source[pos..].lines().next().unwrap_or("").trim().to_string();
+    let item = parse_item(&declaration);
+    Some(ItemAttributes {
+        kind: item.as_ref().map(|(_, kind, _)| kind.to_string()),
+        name: item.map(|(_, _, name)| name),
+        declaration,
+        line: line_of(source, pos),
+        attributes,
+    })
+}
+
+/// Tally attribute paths in every `.rs` file under `dir`, or in `dir` itself
+/// when it is a file
+pub fn attribute_usage(dir: &Path) -> AttributeUsage {
+    let files = if dir.is_file() { vec![dir.to_path_buf()] } else { source_files::rust_files(dir) };
+    let mut usage = AttributeUsage::default();
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        usage.files_scanned += 1;
+        let found = attributes(&content);
+        usage.total += found.len();
+        let paths: BTreeSet<&str> = found.iter().map(|a| a.path.as_str()).collect();
+        for attribute in &found {
+            counts.entry(attribute.path.clone()).or_default().0 += 1;
+        }
+        for path in paths {
+            counts.entry(path.to_string()).or_default().1 += 1;
+        }
+    }
+    usage.attributes = counts.into_iter()
+        .map(|(path, (count, files))| AttributeCount { path, count, files })
+        .collect();
+    usage.attributes.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.path.cmp(&b.path)));
+    usage
+}
+
+/// The end of the string, char literal or comment starting at `i`, if any
+fn skip_literal_or_comment(bytes: &[u8], i: usize) -> Option<usize> {
+    let rest = &bytes[i..];
+    if rest.starts_with(b"//") {
+        return Some(bytes[i..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |p| i + p));
+    }
+    if rest.starts_with(b"/*") {
+        let mut depth = 0;
+        let mut j = i;
+        while j + 1 < bytes.len() {
+            match &bytes[j..j + 2] {
+                b"/*" => { depth += 1; j += 2; }
+                b"*/" => {
+                    depth -= 1;
+                    j += 2;
+                    if depth == 0 {
+                        return Some(j);
+                    }
+                }
+                _ => j += 1,
+            }
+        }
+        return Some(bytes.len());
+    }
+    // Raw strings: r"...", r#"..."#, br"..."
+    let prefix = if rest.starts_with(b"br") { 2 } else if rest.starts_with(b"r") { 1 } else { 0 };
+    let word_start = i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+    if prefix > 0 && word_start {
+        let hashes = rest[prefix..].iter().take_while(|&&b| b == b'#').count();
+        if rest.get(prefix + hashes) == Some(&b'"') {
+            let mut closing = vec![b'"'];
+            closing.extend(std::iter::repeat_n(b'#', hashes));
+            let body = i + prefix + hashes + 1;
+            return Some(bytes[body..].windows(closing.len()).position(|w| w == closing.as_slice())
+                .map_or(bytes.len(), |p| body + p + closing.len()));
+        }
+    }
+    match bytes[i] {
+        b'"' => {
+            let mut j = i + 1;
+            while j < bytes.len() {
+                match bytes[j] {
+                    b'\\' => j += 2,
+                    b'"' => return Some(j + 1),
+                    _ => j += 1,
+                }
+            }
+            Some(bytes.len())
+        }
+        // A char literal rather than a lifetime: 'x', '\n' or '\u{..}'
+        b'\'' => {
+            if rest.get(1) == Some(&b'\\') {
+                rest[2..].iter().position(|&b| b == b'\'').map(|p| i + 3 + p)
+            } else {
+                // At most four bytes make a char; cut where they stop being valid
+                let head = &rest[1..rest.len().min(5)];
+                let valid = std::str::from_utf8(head).map_or_else(|e| &head[..e.valid_up_to()], str::as_bytes);
+                let width = std::str::from_utf8(valid).ok()
+                    .and_then(|s| s.chars().next())
+                    .map_or(1, char::len_utf8);
+                (rest.get(1 + width) == Some(&b'\'')).then_some(i + 2 + width)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Index of the `]` closing the `[` at `open`
+fn closing_bracket(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        if let Some(end) = skip_literal_or_comment(bytes, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether `text` holds only whitespace and comments
+fn is_trivia(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else if text[i..].starts_with("//") || text[i..].starts_with("/*") {
+            i = skip_literal_or_comment(bytes, i).unwrap_or(bytes.len());
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::run_example;
+
+/// One benchmark's timing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub name: String,
+    /// libtest's ns/iter, or criterion's point estimate, in nanoseconds
+    pub ns: f64,
+    /// Bounds of criterion's confidence interval
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_ns: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high_ns: Option<f64>,
+    /// libtest's `+/-` spread
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deviation_ns: Option<f64>,
+    /// criterion's estimated change against the previous run, in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_percent: Option<f64>,
+    /// criterion's reading of that change: `improved`, `regressed` or `no_change`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<String>,
+}
+
+/// Outcome of one `cargo bench`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchRun {
+    pub command: String,
+    /// `libtest`, `criterion`, or `unknown` when the output matched neither
+    pub harness: String,
+    /// `None` when the process was killed or ended by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub benchmarks: Vec<BenchResult>,
+    /// Benchmark names, when listing instead of running
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub listed: Vec<String>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// What to pass to `cargo bench`
+#[derive(Debug, Clone, Default)]
+pub struct BenchOptions {
+    /// Only benchmarks whose name contains this
+    pub filter: Option<String>,
+    /// Only this bench target, `--bench <target>`
+    pub target: Option<String>,
+    pub features: Vec<String>,
+    /// List the benchmarks without running them
+    pub list: bool,
+}
+
+/// Run `cargo bench` with `options`, killing it once `timeout` elapses
+/// (building included), and parse the timings it printed
+pub async fn run_benchmark(project_root: &Path, options: &BenchOptions, timeout: Duration) -> Result<BenchRun> {
+    let mut cargo_args = vec!["bench".to_string()];
+    if let Some(target) = &options.target {
+        cargo_args.extend(["--bench".to_string(), target.clone()]);
+    }
+    if !options.features.is_empty() {
+        cargo_args.extend(["--features".to_string(), options.features.join(",")]);
+    }
+    let mut harness_args: Vec<String> = options.filter.iter().cloned().collect();
+    if options.list {
+        harness_args.push("--list".to_string());
+    }
+    if !harness_args.is_empty() {
+        cargo_args.push("--".to_string());
+        cargo_args.extend(harness_args);
+    }
+
+    let run = run_example::run_cargo(project_root, &cargo_args, timeout).await?;
+    let (harness, benchmarks) = parse_output(&run.stdout);
+    Ok(BenchRun {
+        command: format!("cargo {}", cargo_args.join(" ")),
+        harness: harness.to_string(),
+        exit_code: run.exit_code,
+        success: run.success,
+        timed_out: run.timed_out,
+        duration_ms: run.duration_ms,
+        benchmarks,
+        listed: if options.list { listed(&run.stdout) } else { Vec::new() },
+        stdout: run.stdout,
+        stderr: run.stderr,
+    })
+}
+
+/// The harness that printed `output`, told apart by its format, and the
+/// timings it reported
+pub fn parse_output(output: &str) -> (&'static str, Vec<BenchResult>) {
+    let criterion = parse_criterion(output);
+    if !criterion.is_empty() {
+        return ("criterion", criterion);
+    }
+    let libtest = parse_libtest(output);
+    if !libtest.is_empty() || output.lines().any(|line| line.starts_with("test result:") || line.ends_with(": benchmark")) {
+        return ("libtest", libtest);
+    }
+    ("unknown", Vec::new())
+}
+
+/// Lines such as `test bench_parse ... bench:       1,234 ns/iter (+/- 56)`
+pub fn parse_libtest(output: &str) -> Vec<BenchResult> {
+    output.lines()
+        .filter_map(|line| {
+            let (name, rest) = line.strip_prefix("test ")?.split_once(" ... bench:")?;
+            let (ns, rest) = rest.trim_start().split_once(" ns/iter")?;
+            let deviation = rest.split_once("+/-").and_then(|(_, d)| d.trim().trim_end_matches(')').trim().replace(',', "").parse().ok());
+            Some(BenchResult {
+                name: name.trim().to_string(),
+                ns: ns.trim().replace(',', "").parse().ok()?,
+                low_ns: None,
+                high_ns: None,
+                deviation_ns: deviation,
+                change_percent: None,
+                verdict: None,
+            })
+        })
+        .collect()
+}
+
+/// Blocks such as
+///
+/// ```text
+/// fib 20                  time:   [26.029 µs 26.251 µs 26.505 µs]
+///                         change: [-1.9% -0.2% +1.5%] (p = 0.84 > 0.05)
+///                         No change in performance detected.
+/// ```
+///
+/// where a long name sits alone on the line before `time:`
+pub fn parse_criterion(output: &str) -> Vec<BenchResult> {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut results: Vec<BenchResult> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((before, estimates)) = line.split_once("time:") {
+            let values = bracketed(estimates).unwrap_or_default();
+            let Some([low, ns, high]) = durations(&values) else {
+                // With throughput, the time change sits on its own line under `change:`
+                if let Some(last) = results.last_mut().filter(|last| last.change_percent.is_none()) {
+                    last.change_percent = percent(&values);
+                }
+                continue;
+            };
+            let name = match before.trim() {
+                "" => i.checked_sub(1).map(|previous| lines[previous].trim()).unwrap_or(""),
+                name => name,
+            };
+            results.push(BenchResult {
+                name: name.to_string(),
+                ns,
+                low_ns: Some(low),
+                high_ns: Some(high),
+                deviation_ns: None,
+                change_percent: None,
+                verdict: None,
+            });
+        } else if let Some(last) = results.last_mut() {
+            let trimmed = line.trim();
+            if let Some((_, changes)) = trimmed.split_once("change:") {
+                // The time change comes first; a `thrpt` change may follow
+                if last.change_percent.is_none() {
+                    last.change_percent = bracketed(changes).and_then(|values| percent(&values));
+                }
+            } else if trimmed.starts_with("Performance has improved") {
+                last.verdict = Some("improved".to_string());
+            } else if trimmed.starts_with("Performance has regressed") {
+                last.verdict = Some("regressed".to_string());
+            } else if trimmed.starts_with("No change in performance") || trimmed.starts_with("Change within noise") {
+                last.verdict = Some("no_change".to_string());
+            }
+        }
+    }
+    results
+}
+
+/// Names from a harness's `--list` output, e.g. `parse_large: benchmark`
+pub fn listed(output: &str) -> Vec<String> {
+    output.lines().filter_map(|line| line.strip_suffix(": benchmark")).map(str::to_string).collect()
+}
+
+/// The whitespace-separated values between the first `[` and `]`
+fn bracketed(text: &str) -> Option<Vec<&str>> {
+    let (_, rest) = text.split_once('[')?;
+    let (inside, _) = rest.split_once(']')?;
+    Some(inside.split_whitespace().collect())
+}
+
+/// The middle of three percentages, criterion's estimate of a change
+fn percent(values: &[&str]) -> Option<f64> {
+    match values {
+        [_, estimate, _] => estimate.strip_suffix('%')?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Three `<value> <unit>` pairs, in nanoseconds
+fn durations(values: &[&str]) -> Option<[f64; 3]> {
+    if values.len() != 6 {
+        return None;
+    }
+    let mut ns = [0.0; 3];
+    for (slot, pair) in ns.iter_mut().zip(values.chunks(2)) {
+        let value: f64 = pair[0].parse().ok()?;
+        let scale = match pair[1] {
+            "ps" => 0.001,
+            "ns" => 1.0,
+            "µs" | "us" => 1_000.0,
+            "ms" => 1_000_000.0,
+            "s" => 1_000_000_000.0,
+            _ => return None,
+        };
+        *slot = value * scale;
+    }
+    Some(ns)
+}
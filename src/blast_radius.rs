@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::reexports::ITEM_KEYWORDS;
+use crate::related_tests::{self, RelatedTest};
+use crate::toggle_async::{self, code_mask, line_col, FunctionSpan};
+
+/// Functions visited before the expansion stops and the report is marked truncated
+pub const AFFECTED_LIMIT: usize = 500;
+
+/// Spread beyond which changing a symbol counts as `high` risk: affected
+/// functions, then files
+const HIGH_RISK: (usize, usize) = (20, 10);
+
+/// A function whose behavior may change along with the symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AffectedFunction {
+    pub name: String,
+    pub file: String,
+    /// 1-based line of the signature
+    pub line: usize,
+    /// 1 when it uses the symbol itself, 2 when it calls such a function, and so on
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlastRadius {
+    pub symbol: String,
+    /// `call_hierarchy`, or `text_search` when rust-analyzer couldn't answer
+    pub method: String,
+    pub max_depth: usize,
+    /// Places the symbol is used, its definition excluded
+    pub references: usize,
+    pub file_count: usize,
+    pub function_count: usize,
+    pub test_count: usize,
+    /// `low` within one file and at most 3 functions, `high` beyond 20
+    /// functions or 10 files, `medium` in between
+    pub risk: String,
+    pub files: Vec<String>,
+    pub functions: Vec<AffectedFunction>,
+    pub tests: Vec<RelatedTest>,
+    /// Callers were left unvisited, beyond `max_depth` or the visit limit
+    pub truncated: bool,
+}
+
+impl BlastRadius {
+    pub fn new(symbol: &str, method: &str, max_depth: usize) -> Self {
+        Self { symbol: symbol.to_string(), method: method.to_string(), max_depth, ..Self::default() }
+    }
+
+    /// Sort the affected items and fill in the counts and risk
+    pub fn finish(mut self) -> Self {
+        self.files.extend(self.functions.iter().map(|f| f.file.clone()).chain(self.tests.iter().map(|t| t.file.clone())));
+        self.files = std::mem::take(&mut self.files).into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+        self.functions.sort_by(|a, b| a.depth.cmp(&b.depth).then_with(|| a.file.cmp(&b.file)).then(a.line.cmp(&b.line)));
+        self.tests.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        self.file_count = self.files.len();
+        self.function_count = self.functions.len();
+        self.test_count = self.tests.len();
+        self.risk = if self.function_count > HIGH_RISK.0 || self.file_count > HIGH_RISK.1 {
+            "high"
+        } else if self.function_count > 3 || self.file_count > 1 {
+            "medium"
+        } else {
+            "low"
+        }.to_string();
+        self
+    }
+}
+
+/// The innermost function of `source` whose signature or body spans the 1-based `line`
+pub fn enclosing_function(source: &str, line: usize) -> Option<FunctionSpan> {
+    toggle_async::functions(source).into_iter()
+        .filter(|span| line_col(source, span.start).0 <= line && line <= line_col(source, span.body.1).0)
+        .max_by_key(|span| span.start)
+}
+
+/// Whether the name at a 1-based position is the one an item declares,
+/// e.g. `Config` in `pub struct Config {`
+pub fn is_definition(source: &str, line: usize, column: usize) -> bool {
+    let Some(text) = source.lines().nth(line.saturating_sub(1)) else { return false };
+    let before: String = text.chars().take(column.saturating_sub(1)).collect();
+    let keyword = before.split_whitespace().next_back().unwrap_or("");
+    ITEM_KEYWORDS.iter().any(|(item, _)| *item == keyword)
+}
+
+/// Blast radius of `name` from text alone: its whole-word occurrences in
+/// code, then callers found by name in function bodies, `max_depth` levels up
+pub fn text_search(project_root: &Path, files: &[(PathBuf, String)], name: &str, max_depth: usize) -> BlastRadius {
+    let mut radius = BlastRadius::new(name, "text_search", max_depth);
+    let relative = |file: &Path| file.strip_prefix(project_root).unwrap_or(file).display().to_string();
+    let mut seen = HashSet::new();
+    // Functions to visit by file index, with their depth and the functions
+    // between them and the symbol
+    let mut queue = VecDeque::new();
+
+    for (index, (file, source)) in files.iter().enumerate() {
+        for at in occurrences(source, name) {
+            let (line, column) = line_col(source, at);
+            if is_definition(source, line, column) {
+                continue;
+            }
+            radius.references += 1;
+            radius.files.push(relative(file));
+            if let Some(span) = enclosing_function(source, line) {
+                queue.push_back((index, span, 1, Vec::new()));
+            }
+        }
+    }
+
+    while let Some((index, span, depth, chain)) = queue.pop_front() {
+        let (file, source) = &files[index];
+        if !seen.insert((index, span.start)) {
+            continue;
+        }
+        if seen.len() > AFFECTED_LIMIT {
+            radius.truncated = true;
+            break;
+        }
+        let line = line_col(source, span.name_offset).0;
+        if let Some(test) = related_tests::test_at(source, line) {
+            radius.tests.push(related_tests::related(project_root, file, &test, chain));
+            continue;
+        }
+        radius.functions.push(AffectedFunction { name: span.name.clone(), file: relative(file), line, depth });
+
+        let mut next = chain.clone();
+        next.insert(0, span.name.clone());
+        for (caller_index, (_, caller_source)) in files.iter().enumerate() {
+            for caller in toggle_async::functions(caller_source) {
+                let body = &caller_source[caller.body.0..caller.body.1];
+                if caller.name == span.name || !related_tests::calls(body, &span.name) {
+                    continue;
+                }
+                if depth >= max_depth {
+                    radius.truncated = true;
+                } else {
+                    queue.push_back((caller_index, caller, depth + 1, next.clone()));
+                }
+            }
+        }
+    }
+    radius.finish()
+}
+
+/// Byte offsets of `name` as a whole word in the code of `source`
+fn occurrences(source: &str, name: &str) -> Vec<usize> {
+    let mask = code_mask(source);
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    source.match_indices(name)
+        .map(|(at, _)| at)
+        .filter(|&at| {
+            mask[at]
+                && !source[..at].chars().next_back().is_some_and(is_ident)
+                && !source[at + name.len()..].chars().next().is_some_and(is_ident)
+        })
+        .collect()
+}
@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::cfg_eval::CfgContext;
+
+/// Profile of the server's `cargo check`, `cargo clippy` and `cargo run`
+/// invocations; only `run_benchmark` builds with `bench`
+pub const PROFILE: &str = "dev";
+
+/// `rustc -vV`, run where the project's toolchain file applies
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RustcVersion {
+    pub release: String,
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llvm_version: Option<String>,
+}
+
+/// Settings of `PROFILE` that change what gets compiled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Sets `cfg(debug_assertions)`
+    pub debug_assertions: bool,
+    pub opt_level: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildContext {
+    /// Triple cargo builds for
+    pub target: String,
+    /// `host`, `CARGO_BUILD_TARGET` or the cargo config file setting `build.target`
+    pub target_source: String,
+    pub rustc: RustcVersion,
+    pub profile: Profile,
+    /// Features considered enabled: the package's defaults plus configured ones
+    pub features: Vec<String>,
+    /// Every active cfg as written in `#[cfg]`: `unix`, `target_os = "linux"`,
+    /// `feature = "serde"`, ...
+    pub cfg: Vec<String>,
+}
+
+/// Resolve what the server's cargo invocations build for
+pub async fn resolve(project_root: &Path, features: &[String]) -> Result<BuildContext> {
+    let output = Command::new("rustc")
+        .arg("-vV")
+        .current_dir(project_root)
+        .output()
+        .await
+        .context("Failed to run rustc")?;
+    if !output.status.success() {
+        anyhow::bail!("rustc -vV failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let rustc = parse_rustc_version(&String::from_utf8_lossy(&output.stdout));
+
+    let (target, target_source) = match configured_target(project_root) {
+        Some((target, source)) => (target, source),
+        None => (rustc.host.clone(), "host".to_string()),
+    };
+    let manifest = std::fs::read_to_string(project_root.join("Cargo.toml")).unwrap_or_default();
+    let profile = dev_profile(&manifest);
+
+    let ctx = CfgContext::resolve(project_root, Some(&target), features).await;
+    let mut features: Vec<String> = ctx.features.iter().cloned().collect();
+    features.sort();
+    let mut cfg: Vec<String> = ctx.options.iter()
+        // `rustc --print cfg` always sets it; cargo follows the profile
+        .filter(|(name, _)| name != "debug_assertions")
+        .map(|(name, value)| match value {
+            Some(value) => format!("{} = \"{}\"", name, value),
+            None => name.clone(),
+        })
+        .collect();
+    if profile.debug_assertions {
+        cfg.push("debug_assertions".to_string());
+    }
+    cfg.sort();
+    cfg.extend(features.iter().map(|feature| format!("feature = \"{}\"", feature)));
+
+    Ok(BuildContext { target, target_source, rustc, profile, features, cfg })
+}
+
+/// Parse `rustc -vV` output
+pub fn parse_rustc_version(output: &str) -> RustcVersion {
+    let mut version = RustcVersion::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(": ") else { continue };
+        let value = value.trim().to_string();
+        match key {
+            "release" => version.release = value,
+            "host" => version.host = value,
+            "commit-hash" if value != "unknown" => version.commit_hash = Some(value),
+            "LLVM version" => version.llvm_version = Some(value),
+            _ => {}
+        }
+    }
+    version
+}
+
+/// Target set by `CARGO_BUILD_TARGET` or by `build.target` in the cargo
+/// config files cargo reads for `project_root` (its ancestors, then
+/// `$CARGO_HOME`), with where it came from
+pub fn configured_target(project_root: &Path) -> Option<(String, String)> {
+    if let Ok(target) = std::env::var("CARGO_BUILD_TARGET") {
+        if !target.is_empty() {
+            return Some((target, "CARGO_BUILD_TARGET".to_string()));
+        }
+    }
+    let cargo_home = std::env::var_os("CARGO_HOME").map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")));
+    project_root.ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home)
+        .flat_map(|dir| [dir.join("config.toml"), dir.join("config")])
+        .find_map(|file| {
+            let target = config_target(&std::fs::read_to_string(&file).ok()?)?;
+            Some((target, file.display().to_string()))
+        })
+}
+
+/// `build.target` of a cargo config file; the first one when it lists several
+pub fn config_target(config: &str) -> Option<String> {
+    let config: toml::Table = toml::from_str(config).ok()?;
+    match config.get("build")?.get("target")? {
+        toml::Value::String(target) => Some(target.clone()),
+        toml::Value::Array(targets) => targets.first()?.as_str().map(String::from),
+        _ => None,
+    }
+}
+
+/// `PROFILE` with the `[profile.dev]` overrides of `manifest` applied
+pub fn dev_profile(manifest: &str) -> Profile {
+    let mut profile = Profile { name: PROFILE.to_string(), debug_assertions: true, opt_level: "0".to_string() };
+    let Ok(manifest) = toml::from_str::<toml::Table>(manifest) else { return profile };
+    let Some(dev) = manifest.get("profile").and_then(|p| p.get(PROFILE)) else { return profile };
+    if let Some(debug_assertions) = dev.get("debug-assertions").and_then(|d| d.as_bool()) {
+        profile.debug_assertions = debug_assertions;
+    }
+    match dev.get("opt-level") {
+        Some(toml::Value::Integer(level)) => profile.opt_level = level.to_string(),
+        Some(toml::Value::String(level)) => profile.opt_level = level.clone(),
+        _ => {}
+    }
+    profile
+}
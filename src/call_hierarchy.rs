@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::locations::{self, Location};
+use crate::outline;
+
+/// Which side of a function's calls to list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Functions calling it
+    #[default]
+    Incoming,
+    /// Functions it calls
+    Outgoing,
+}
+
+/// A function of the call hierarchy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallItem {
+    pub name: String,
+    /// Kind name as `outline::symbol_kind` gives it, e.g. `function` or `method`
+    pub kind: String,
+    /// Signature rust-analyzer shows next to the name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Where its name is
+    #[serde(flatten)]
+    pub location: Location,
+}
+
+/// A caller or callee, with where the calls between the two are made
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Call {
+    #[serde(flatten)]
+    pub item: CallItem,
+    /// Start of each call expression; inside the caller either way, so in
+    /// this item's file for incoming calls and the queried function's for
+    /// outgoing ones
+    pub call_sites: Vec<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallHierarchy {
+    /// The function at the position, or null when there is none
+    pub function: Option<CallItem>,
+    pub direction: Direction,
+    pub calls: Vec<Call>,
+}
+
+/// A `CallHierarchyItem`, its file relative to the project root when inside it
+pub fn item_from_lsp(item: &Value, project_root: &Path) -> Option<CallItem> {
+    let location = json!({ "uri": item["uri"], "range": item["selectionRange"] });
+    Some(CallItem {
+        name: item["name"].as_str()?.to_string(),
+        kind: outline::symbol_kind(item["kind"].as_u64().unwrap_or(0)).to_string(),
+        detail: item["detail"].as_str().filter(|d| !d.is_empty()).map(String::from),
+        location: locations::from_lsp(&location, project_root).into_iter().next()?,
+    })
+}
+
+/// A `CallHierarchyIncomingCall` or `CallHierarchyOutgoingCall` of `item`.
+/// Outgoing calls carry their `fromRanges` in `item`'s file.
+pub fn call_from_lsp(call: &Value, item: &Value, direction: Direction, project_root: &Path) -> Option<Call> {
+    let (other, sites_uri) = match direction {
+        Direction::Incoming => (&call["from"], &call["from"]["uri"]),
+        Direction::Outgoing => (&call["to"], &item["uri"]),
+    };
+    let ranges = call["fromRanges"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let sites: Vec<Value> = ranges.iter().map(|range| json!({ "uri": sites_uri, "range": range })).collect();
+    Some(Call {
+        item: item_from_lsp(other, project_root)?,
+        call_sites: locations::from_lsp(&Value::Array(sites), project_root),
+    })
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::health;
+use crate::timings::{self, Phase};
+
+/// The set of cfg options considered enabled when evaluating `#[cfg(...)]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CfgContext {
+    pub target: Option<String>,
+    pub features: HashSet<String>,
+    /// Name-only options (`unix`) and `key="value"` pairs (`target_os="linux"`)
+    pub options: HashSet<(String, Option<String>)>,
+}
+
+impl CfgContext {
+    /// Build the context from `rustc --print cfg` for the given (or host) target
+    /// and the package's enabled features.
+    pub async fn resolve(project_root: &Path, target: Option<&str>, extra_features: &[String]) -> Self {
+        let mut ctx = Self {
+            target: target.map(|t| t.to_string()),
+            ..Self::default()
+        };
+
+        let mut cmd = Command::new("rustc");
+        cmd.args(["--print", "cfg"]).current_dir(project_root);
+        if let Some(target) = target {
+            cmd.args(["--target", target]);
+        }
+        match cmd.output().await {
+            Ok(output) if output.status.success() => {
+                ctx.options = parse_rustc_cfg(&String::from_utf8_lossy(&output.stdout));
+            }
+            Ok(output) => debug!("rustc --print cfg failed: {}", String::from_utf8_lossy(&output.stderr)),
+            Err(e) => debug!("Failed to run rustc: {}", e),
+        }
+
+        ctx.features = enabled_features(project_root, extra_features).await;
+        ctx
+    }
+
+    fn is_set(&self, name: &str, value: Option<&str>) -> bool {
+        match (name, value) {
+            ("feature", Some(feature)) => self.features.contains(feature),
+            _ => self.options.contains(&(name.to_string(), value.map(|v| v.to_string()))),
+        }
+    }
+}
+
+/// A cfg predicate as written inside `#[cfg(...)]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum CfgExpr {
+    Option(String, Option<String>),
+    All(Vec<CfgExpr>),
+    Any(Vec<CfgExpr>),
+    Not(Box<CfgExpr>),
+}
+
+impl CfgExpr {
+    pub fn eval(&self, ctx: &CfgContext) -> bool {
+        match self {
+            CfgExpr::Option(name, value) => ctx.is_set(name, value.as_deref()),
+            CfgExpr::All(items) => items.iter().all(|e| e.eval(ctx)),
+            CfgExpr::Any(items) => items.iter().any(|e| e.eval(ctx)),
+            CfgExpr::Not(inner) => !inner.eval(ctx),
+        }
+    }
+}
+
+/// One `#[cfg]` / `#[cfg_attr]` occurrence in a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CfgOccurrence {
+    /// 1-based line of the attribute
+    pub line: usize,
+    /// `cfg` or `cfg_attr`
+    pub kind: String,
+    pub condition: String,
+    pub active: bool,
+}
+
+/// Find every cfg attribute in `source` and evaluate it against `ctx`
+pub fn scan_cfgs(source: &str, ctx: &CfgContext) -> Vec<CfgOccurrence> {
+    let mut found = Vec::new();
+    let lines: Vec<&str> = source.lines().collect();
+
+    for (i, line) in lines.iter().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        let mut search = 0;
+        while let Some(pos) = code[search..].find("cfg") {
+            let abs = search + pos;
+            search = abs + 3;
+
+            let before = code[..abs].trim_end();
+            if !(before.ends_with("#[") || before.ends_with("#![")) {
+                continue;
+            }
+            let kind = if code[abs..].starts_with("cfg_attr") { "cfg_attr" } else { "cfg" };
+            let after = code[abs + kind.len()..].trim_start();
+            if !after.starts_with('(') {
+                continue;
+            }
+
+            // The attribute may span several lines; gather until parens balance
+            let mut text = after.to_string();
+            let mut next = i + 1;
+            while paren_balance(&text) > 0 && next < lines.len() {
+                text.push(' ');
+                text.push_str(lines[next].split("//").next().unwrap_or("").trim());
+                next += 1;
+            }
+            let Some(end) = matching_paren(&text) else { continue };
+            let inner = &text[1..end];
+
+            let condition = if kind == "cfg_attr" {
+                split_args(inner).into_iter().next().unwrap_or_default()
+            } else {
+                inner.trim().to_string()
+            };
+            let active = parse_cfg(&condition).is_some_and(|expr| expr.eval(ctx));
+
+            found.push(CfgOccurrence {
+                line: i + 1,
+                kind: kind.to_string(),
+                condition: normalize(&condition),
+                active,
+            });
+        }
+    }
+
+    found
+}
+
+/// Parse a cfg predicate such as `all(unix, not(feature = "foo"))`
+pub fn parse_cfg(text: &str) -> Option<CfgExpr> {
+    let text = text.trim();
+    for (name, ctor) in [
+        ("all", CfgExpr::All as fn(Vec<CfgExpr>) -> CfgExpr),
+        ("any", CfgExpr::Any as fn(Vec<CfgExpr>) -> CfgExpr),
+    ] {
+        if let Some(args) = call_args(text, name) {
+            let items = split_args(args)
+                .iter()
+                .filter(|a| !a.is_empty())
+                .map(|a| parse_cfg(a))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(ctor(items));
+        }
+    }
+    if let Some(args) = call_args(text, "not") {
+        return Some(CfgExpr::Not(Box::new(parse_cfg(args)?)));
+    }
+
+    match text.split_once('=') {
+        Some((name, value)) => {
+            let value = value.trim().trim_matches('"');
+            Some(CfgExpr::Option(name.trim().to_string(), Some(value.to_string())))
+        }
+        None if !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+            Some(CfgExpr::Option(text.to_string(), None))
+        }
+        None => None,
+    }
+}
+
+/// `name(args)` -> `args`
+fn call_args<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let rest = text.strip_prefix(name)?.trim_start();
+    if !rest.starts_with('(') {
+        return None;
+    }
+    let end = matching_paren(rest)?;
+    Some(&rest[1..end])
+}
+
+fn paren_balance(text: &str) -> i32 {
+    text.chars().fold(0, |depth, c| match c {
+        '(' => depth + 1,
+        ')' => depth - 1,
+        _ => depth,
+    })
+}
+
+/// Index of the paren closing the one at position 0, skipping string literals
+fn matching_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_str = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_str = !in_str,
+            '(' if !in_str => depth += 1,
+            ')' if !in_str => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split comma-separated arguments at paren depth 0
+fn split_args(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut in_str = false;
+    for c in text.chars() {
+        match c {
+            '"' => in_str = !in_str,
+            '(' if !in_str => depth += 1,
+            ')' if !in_str => depth -= 1,
+            ',' if !in_str && depth == 0 => {
+                args.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    args.push(current.trim().to_string());
+    args
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parse `rustc --print cfg` output (`unix`, `target_os="linux"`, ...)
+pub fn parse_rustc_cfg(output: &str) -> HashSet<(String, Option<String>)> {
+    output
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| match l.split_once('=') {
+            Some((k, v)) => (k.to_string(), Some(v.trim_matches('"').to_string())),
+            None => (l.to_string(), None),
+        })
+        .collect()
+}
+
+/// Features enabled by the root package's `default` feature and by `extra`,
+/// expanded transitively as cargo does
+async fn enabled_features(project_root: &Path, extra: &[String]) -> HashSet<String> {
+    let output = timings::measure(Phase::Cargo, Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(project_root)
+        .output())
+        .await;
+
+    let metadata = output.ok().and_then(|output| serde_json::from_slice::<Value>(&output.stdout).ok());
+    let package = metadata.as_ref().and_then(|metadata| health::root_package(metadata, project_root));
+    let features = package.and_then(|p| p.get("features")).and_then(|f| f.as_object());
+
+    let mut enabled = HashSet::new();
+    let mut pending = extra.to_vec();
+    pending.push("default".to_string());
+    while let Some(feature) = pending.pop() {
+        if feature != "default" && !enabled.insert(feature.clone()) {
+            continue;
+        }
+        let Some(implied) = features.and_then(|f| f.get(&feature)).and_then(|f| f.as_array()) else { continue };
+        for item in implied.iter().filter_map(|i| i.as_str()) {
+            // `dep:foo` and `foo/bar` enable dependencies, not features of this package
+            if !item.contains(':') && !item.contains('/') {
+                pending.push(item.to_string());
+            }
+        }
+    }
+    enabled
+}
@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::workspace_edit::{apply_edits_to_text, uri_to_path};
+
+/// Unchanged lines shown around each hunk of a diff
+const DIFF_CONTEXT: usize = 3;
+
+/// The text changes a `WorkspaceEdit` makes to one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// Relative to the project root when inside it
+    pub file: String,
+    /// Unified diff of the file before and after the edit
+    pub diff: String,
+}
+
+/// `CodeAction`s of a `textDocument/codeAction` response; bare `Command`s are dropped
+pub fn code_actions(response: &Value) -> Vec<Value> {
+    response.as_array()
+        .map(|actions| actions.iter()
+            .filter(|action| action.get("title").is_some() && !action["command"].is_string())
+            .cloned()
+            .collect())
+        .unwrap_or_default()
+}
+
+/// rust-analyzer's assist id, e.g. `replace_match_with_if_let` from the
+/// resolve data `replace_match_with_if_let:RefactorRewrite:0`
+pub fn assist_id(action: &Value) -> Option<&str> {
+    action["data"]["id"].as_str().and_then(|id| id.split(':').next())
+}
+
+/// Whether `action` is of `kind`: an LSP kind or one of its sub-kinds
+/// (`refactor.rewrite` matches `refactor.rewrite.x`), or an assist id
+pub fn matches_kind(action: &Value, kind: &str) -> bool {
+    let action_kind = action["kind"].as_str().unwrap_or("");
+    action_kind == kind
+        || action_kind.strip_prefix(kind).is_some_and(|rest| rest.starts_with('.'))
+        || assist_id(action) == Some(kind)
+}
+
+/// Title, kind and assist id of an action, for listing
+pub fn describe(action: &Value) -> Value {
+    json!({
+        "title": action["title"],
+        "kind": action["kind"],
+        "id": assist_id(action),
+    })
+}
+
+/// Pick the action titled `name` (case-insensitively; an unambiguous
+/// substring also matches) among those of `kind`. Without a name the kind
+/// must single out one action.
+pub fn select_action<'a>(actions: &'a [Value], name: Option<&str>, kind: Option<&str>) -> Result<&'a Value> {
+    let candidates: Vec<&Value> = actions.iter()
+        .filter(|action| kind.is_none_or(|kind| matches_kind(action, kind)))
+        .collect();
+    let titles = || candidates.iter().filter_map(|a| a["title"].as_str()).collect::<Vec<_>>().join(", ");
+
+    let matching: Vec<&Value> = match name {
+        Some(name) => {
+            let name = name.to_lowercase();
+            let title = |action: &&Value| action["title"].as_str().unwrap_or("").to_lowercase();
+            let exact: Vec<&Value> = candidates.iter().copied().filter(|a| title(a) == name).collect();
+            if exact.is_empty() {
+                candidates.iter().copied().filter(|a| title(a).contains(&name)).collect()
+            } else {
+                exact
+            }
+        }
+        None => candidates.clone(),
+    };
+
+    match matching.as_slice() {
+        [action] => Ok(action),
+        [] if candidates.is_empty() => anyhow::bail!("No assist available here"),
+        [] => anyhow::bail!("No assist matches; available: {}", titles()),
+        _ => anyhow::bail!(
+            "Several assists match; pick one by name: {}",
+            matching.iter().filter_map(|a| a["title"].as_str()).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+/// Diffs of the text edits in a `WorkspaceEdit`, computed against the files
+/// on disk without writing anything. Resource operations (create, rename,
+/// delete) are not diffed.
+pub async fn edit_diffs(edit: &Value, project_root: &Path) -> Result<Vec<FileDiff>> {
+    let mut files: Vec<(PathBuf, String, String)> = Vec::new();
+    let mut text_edits: Vec<(&str, Vec<Value>)> = Vec::new();
+
+    if let Some(document_changes) = edit.get("documentChanges").and_then(|d| d.as_array()) {
+        for change in document_changes.iter().filter(|c| c.get("kind").is_none()) {
+            let uri = change["textDocument"]["uri"].as_str().context("TextDocumentEdit is missing textDocument.uri")?;
+            text_edits.push((uri, change["edits"].as_array().cloned().unwrap_or_default()));
+        }
+    } else if let Some(changes) = edit.get("changes").and_then(|c| c.as_object()) {
+        for (uri, edits) in changes {
+            text_edits.push((uri, edits.as_array().cloned().unwrap_or_default()));
+        }
+    }
+
+    for (uri, edits) in text_edits {
+        let path = uri_to_path(uri);
+        let index = match files.iter().position(|(p, _, _)| *p == path) {
+            Some(index) => index,
+            None => {
+                let content = tokio::fs::read_to_string(&path).await
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                files.push((path, content.clone(), content));
+                files.len() - 1
+            }
+        };
+        let edited = apply_edits_to_text(&files[index].2, &edits)?;
+        files[index].2 = edited;
+    }
+
+    Ok(files.into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(path, before, after)| {
+            let file = path.strip_prefix(project_root).unwrap_or(&path).display().to_string();
+            let diff = unified_diff(&file, &before, &after);
+            FileDiff { file, diff }
+        })
+        .collect())
+}
+
+/// Unified diff of `before` and `after`, both labelled `file`
+pub fn unified_diff(file: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    // Assists touch a small region, so only the middle between the common
+    // prefix and suffix goes through the quadratic LCS
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    // (tag, old index, new index) for every line of both files
+    let mut ops: Vec<(char, usize, usize)> = (0..prefix).map(|k| (' ', k, k)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            ops.push((' ', prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if j < new_mid.len() && (i == old_mid.len() || lcs[i][j + 1] > lcs[i + 1][j]) {
+            ops.push(('+', prefix + i, prefix + j));
+            j += 1;
+        } else {
+            ops.push(('-', prefix + i, prefix + j));
+            i += 1;
+        }
+    }
+    ops.extend((0..suffix).map(|k| (' ', old.len() - suffix + k, new.len() - suffix + k)));
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", file, file);
+    let changed: Vec<usize> = ops.iter().enumerate().filter(|(_, op)| op.0 != ' ').map(|(k, _)| k).collect();
+    let mut k = 0;
+    while k < changed.len() {
+        // Extend the hunk while the next change is within two contexts' reach
+        let start = changed[k].saturating_sub(DIFF_CONTEXT);
+        let mut last = changed[k];
+        while k + 1 < changed.len() && changed[k + 1] <= last + 2 * DIFF_CONTEXT + 1 {
+            k += 1;
+            last = changed[k];
+        }
+        let end = (last + DIFF_CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        let old_count = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_count = hunk.iter().filter(|op| op.0 != '-').count();
+        let (_, old_start, new_start) = hunk[0];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 { old_start } else { old_start + 1 }, old_count,
+            if new_count == 0 { new_start } else { new_start + 1 }, new_count,
+        ));
+        for &(tag, o, n) in hunk {
+            let line = if tag == '+' { new[n] } else { old[o] };
+            out.push(tag);
+            out.push_str(line);
+            out.push('\n');
+        }
+        k += 1;
+    }
+    out
+}
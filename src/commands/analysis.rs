@@ -2,14 +2,63 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::debug;
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 
-use crate::analyzer::RustAnalyzer;
+use crate::analyzer::{skip_too_large, RustAnalyzer};
+use crate::async_audit;
+use crate::attributes;
+use crate::blast_radius::{self, AffectedFunction, BlastRadius};
+use crate::call_hierarchy::Direction;
 use crate::server::CommandHandler;
+use crate::build_context;
+use crate::cfg_eval::{self, CfgContext};
+use crate::diagnostics_stream;
+use crate::error_handling;
+use crate::ffi;
+use crate::file_summary;
+use crate::fix_plan;
+use crate::lsp_passthrough;
+use crate::placement;
+use crate::generics;
+use crate::import_suggest;
+use crate::macros::{self, MacroDefinition};
+use crate::markup::ContentFormat;
+use crate::metrics_diff;
+use crate::read_files::{self, FileRequest};
+use crate::reexports;
+use crate::sarif;
+use crate::related_tests::{self, RelatedTest, RelatedTestsReport};
+use crate::shadowing;
+use crate::signature;
+use crate::source_files;
+use crate::outline;
+use crate::param_validation;
+use crate::symbol_search::{self, MatchOptions, WorkspaceSymbol};
+use crate::test_code_audit;
+use crate::timings::{self, Phase};
+use crate::toggle_async;
+use crate::type_hierarchy;
+use crate::trait_objects;
+use crate::unhandled_results;
+use crate::visibility;
+use crate::workspace_edit::uri_to_path;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SymbolParams {
     name: String,
+    /// Overall time budget; sources still running when it ends are left out
+    timeout_ms: Option<u64>,
+    /// Match whole identifiers only
+    #[serde(default = "default_true")]
+    whole_word: bool,
+    #[serde(default = "default_true")]
+    case_sensitive: bool,
+    /// Locations per page
+    limit: Option<usize>,
+    /// Locations to skip, for the pages after the first
+    #[serde(default)]
+    offset: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,11 +68,229 @@ struct PositionParams {
     column: u32,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct CallHierarchyParams {
+    file: String,
+    line: u32,
+    column: u32,
+    #[serde(default)]
+    direction: Direction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TypeHierarchyParams {
+    file: String,
+    line: u32,
+    column: u32,
+    #[serde(default)]
+    direction: type_hierarchy::Direction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HoverParams {
+    file: String,
+    line: u32,
+    column: u32,
+    content_format: Option<ContentFormat>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ImplementationParams {
+    file: String,
+    line: u32,
+    column: u32,
+    /// Also list the methods defined in each impl block
+    #[serde(default)]
+    with_members: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct FileParams {
     file: Option<String>,
+    /// How to aggregate diagnostics: "lint", "file" or "none" (default)
+    group_by: Option<String>,
+    /// Only report diagnostics in files changed since this git ref; empty means HEAD
+    changed: Option<String>,
+    /// "native" (default) or "sarif" for a SARIF 2.1.0 log
+    format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SummaryParams {
+    file: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CfgParams {
+    file: String,
+    /// Target triple to evaluate against; defaults to the host
+    target: Option<String>,
+    /// Extra features to treat as enabled on top of the defaults
+    #[serde(default)]
+    features: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UnhandledParams {
+    /// Directory to scan relative to the project root; defaults to `src`
+    module: Option<String>,
+    #[serde(default)]
+    include_tests: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AsyncAuditParams {
+    /// Directory to scan relative to the project root; defaults to `src`
+    module: Option<String>,
+    #[serde(default)]
+    include_tests: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FindDynParams {
+    /// Directory to scan relative to the project root; defaults to `src`
+    module: Option<String>,
+    #[serde(default)]
+    include_tests: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SuggestLocationParams {
+    /// What the new item does
+    description: String,
+    /// A type or function the new item belongs with
+    symbol: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FindFfiParams {
+    /// Directory to scan relative to the project root; defaults to `src`
+    module: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorHandlingParams {
+    /// Directory to scan relative to the project root; defaults to `src`
+    module: Option<String>,
+    #[serde(default)]
+    include_tests: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LspPassthroughParams {
+    /// LSP method, e.g. `textDocument/foldingRange`; `method` itself names the command
+    lsp_method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReadFilesParams {
+    files: Vec<FileRequest>,
+    /// Capped at the configured `max_read_bytes`
+    max_bytes: Option<usize>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct ShadowingParams {
+    /// Directory to scan relative to the project root; defaults to `src`
+    module: Option<String>,
+    #[serde(default)]
+    include_tests: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RelatedTestsParams {
+    /// Function name; taken from the call hierarchy when a position is given
+    name: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    /// Call edges followed from a test down to the function
+    #[serde(default = "default_related_depth")]
+    max_depth: usize,
+}
+
+fn default_related_depth() -> usize {
+    3
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BlastRadiusParams {
+    file: String,
+    line: u32,
+    column: u32,
+    /// Levels of callers followed from the functions using the symbol
+    #[serde(default = "default_related_depth")]
+    max_depth: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenDocumentsParams {
+    files: Vec<String>,
+    /// Session to add the files to; a new one when absent
+    session: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CloseSessionParams {
+    session: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemAttributesParams {
+    /// With `line`, report the attributes of one item; alone, tally the file
+    file: Option<String>,
+    line: Option<u32>,
+    /// Lets a position on a use of the item resolve to its definition
+    column: Option<u32>,
+    /// Directory to tally when no file is given; defaults to `src`
+    module: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AnalyzerStatusParams {
+    /// Include the crates this file belongs to in the status
+    file: Option<String>,
+    #[serde(default = "default_true")]
+    include_memory: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DiagnoseParams {
+    #[serde(default = "default_true")]
+    include_warnings: bool,
+    /// Keep only the first steps of the plan
+    max_steps: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkspaceSymbolsParams {
+    query: String,
+    /// Kind name such as `function` or `struct`, as the results report it
+    kind: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Maximum number of sample locations kept per aggregated diagnostic group
+const GROUP_SAMPLE_LIMIT: usize = 5;
+
+/// Distinct example messages kept per lint group
+const GROUP_MESSAGE_LIMIT: usize = 3;
+
+/// Callers visited by `related_tests` before giving up on the call hierarchy walk
+const RELATED_CALLER_LIMIT: usize = 200;
+
+/// Default time budget of `analyze_symbol`, shared by the text scan and the LSP lookup
+const ANALYZE_SYMBOL_TIMEOUT_MS: u64 = 10_000;
+
+/// Locations `analyze_symbol` returns per page when the caller sets no limit
+const SYMBOL_PAGE_LIMIT: usize = 50;
+
 pub struct AnalysisCommands;
 
 #[async_trait::async_trait]
@@ -37,10 +304,40 @@ impl CommandHandler for AnalysisCommands {
             
         match method {
             "analyze_symbol" => self.analyze_symbol(params, analyzer).await,
+            "search_workspace_symbols" => self.search_workspace_symbols(params, analyzer).await,
             "find_references" => self.find_references(params, analyzer).await,
+            "call_hierarchy" => self.call_hierarchy(params, analyzer).await,
+            "type_hierarchy" => self.type_hierarchy(params, analyzer).await,
+            "goto_definition" => self.goto_definition(params, analyzer).await,
             "get_diagnostics" => self.get_diagnostics(params, analyzer).await,
             "get_hover" => self.get_hover(params, analyzer).await,
             "find_implementations" => self.find_implementations(params, analyzer).await,
+            "signature_at" => self.signature_at(params, analyzer).await,
+            "cfg_status" => self.cfg_status(params, analyzer).await,
+            "build_context" => self.build_context(analyzer).await,
+            "reexports" => self.reexports(analyzer).await,
+            "file_summary" => self.file_summary(params, analyzer).await,
+            "document_outline" => self.document_outline(params, analyzer).await,
+            "macro_definition" => self.macro_definition(params, analyzer).await,
+            "visibility" => self.visibility(params, analyzer).await,
+            "find_unhandled_results" => self.find_unhandled_results(params, analyzer).await,
+            "diagnose" => self.diagnose(params, analyzer).await,
+            "generics_of" => self.generics_of(params, analyzer).await,
+            "async_audit" => self.async_audit(params, analyzer).await,
+            "read_files" => self.read_files(params, analyzer).await,
+            "analyzer_status" => self.analyzer_status(params, analyzer).await,
+            "find_shadowing" => self.find_shadowing(params, analyzer).await,
+            "related_tests" => self.related_tests(params, analyzer).await,
+            "blast_radius" => self.blast_radius(params, analyzer).await,
+            "item_attributes" => self.item_attributes(params, analyzer).await,
+            "open_documents" => self.open_documents(params, analyzer).await,
+            "close_session" => self.close_session(params, analyzer).await,
+            "error_handling_report" => self.error_handling_report(params, analyzer).await,
+            "lsp_passthrough" => self.lsp_passthrough(params, analyzer).await,
+            "test_code_audit" => self.test_code_audit(analyzer).await,
+            "find_dyn" => self.find_dyn(params, analyzer).await,
+            "find_ffi" => self.find_ffi(params, analyzer).await,
+            "suggest_location" => self.suggest_location(params, analyzer).await,
             _ => anyhow::bail!("Unknown analysis method: {}", method),
         }
     }
@@ -56,49 +353,63 @@ impl AnalysisCommands {
             obj.remove("method");
         }
         
-        let params: SymbolParams = serde_json::from_value(params_value)?;
+        let params: SymbolParams = param_validation::parse_params(params_value)?;
         
         debug!("Analyzing symbol: {}", params.name);
         
-        // Use both LSP workspace symbols and local file search for comprehensive analysis
-        let project_root = analyzer.project_root();
-        let mut symbol_info = self.search_symbol_in_project(&params.name, project_root).await?;
-        
-        // Try to get additional info via LSP workspace symbols if available
-        if let Some(mut lsp_guard) = analyzer.get_lsp_client().await {
-            if let Some(client) = lsp_guard.as_mut() {
-            if let Ok(lsp_symbols) = client.workspace_symbol(&params.name).await {
-                if let Some(symbols) = lsp_symbols.as_array() {
-                    for symbol in symbols {
-                        if let Some(location) = symbol.get("location") {
-                            if let Some(uri) = location.get("uri").and_then(|u| u.as_str()) {
-                                // Convert file URI to relative path
-                                let file_path = uri.strip_prefix("file://")
-                                    .unwrap_or(uri)
-                                    .strip_prefix(&project_root.to_string_lossy().to_string())
-                                    .unwrap_or(uri);
-                                    
-                                let range = location.get("range");
-                                let line = range.and_then(|r| r.get("start"))
-                                    .and_then(|s| s.get("line"))
-                                    .and_then(|l| l.as_u64())
-                                    .unwrap_or(0) + 1; // Convert from 0-based to 1-based
+        // The text scan and the LSP lookup run side by side within one budget
+        let project_root = analyzer.project_root().to_path_buf();
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_millis(params.timeout_ms.unwrap_or(ANALYZE_SYMBOL_TIMEOUT_MS));
+        let options = MatchOptions { whole_word: params.whole_word, case_sensitive: params.case_sensitive };
+        let (name, root, scan_deadline) = (params.name.clone(), project_root.clone(), deadline.into_std());
+        let text_scan = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || search_symbol_in_project(&name, &root, options, scan_deadline)));
+        let lsp_lookup = tokio::time::timeout_at(deadline, async {
+            let mut lsp_guard = analyzer.get_lsp_client().await?;
+            let client = lsp_guard.as_mut()?;
+            Some(client.workspace_symbol(&params.name).await)
+        });
+        let (text_scan, lsp_symbols) = tokio::join!(text_scan, lsp_lookup);
+        let (mut symbol_info, scan_complete) = text_scan?;
+        
+        let lsp_status = match &lsp_symbols {
+            Ok(Some(Ok(_))) => "complete",
+            Ok(Some(Err(e))) => {
+                debug!("workspace/symbol failed: {}", e);
+                "failed"
+            }
+            Ok(None) => "unavailable",
+            Err(_) => "timed_out",
+        };
+        if let Ok(Some(Ok(lsp_symbols))) = lsp_symbols {
+            if let Some(symbols) = lsp_symbols.as_array() {
+                for symbol in symbols {
+                    if let Some(location) = symbol.get("location") {
+                        if let Some(uri) = location.get("uri").and_then(|u| u.as_str()) {
+                            // Relative to the project root, like the text scan's paths
+                            let path = uri_to_path(uri);
+                            let file_path = path.strip_prefix(&project_root).unwrap_or(&path).display().to_string();
                                 
-                                symbol_info.push(json!({
-                                    "file": file_path,
-                                    "line": line,
-                                    "content": symbol.get("name").unwrap_or(&json!("")),
-                                    "context": "lsp_workspace_symbol",
-                                    "kind": symbol.get("kind").unwrap_or(&json!("unknown")),
-                                    "container": symbol.get("containerName").unwrap_or(&json!(""))
-                                }));
-                            }
+                            let range = location.get("range");
+                            let line = range.and_then(|r| r.get("start"))
+                                .and_then(|s| s.get("line"))
+                                .and_then(|l| l.as_u64())
+                                .unwrap_or(0) + 1; // Convert from 0-based to 1-based
+                            
+                            symbol_info.push(json!({
+                                "file": file_path,
+                                "line": line,
+                                "content": symbol.get("name").unwrap_or(&json!("")),
+                                "context": "lsp_workspace_symbol",
+                                "kind": symbol.get("kind").unwrap_or(&json!("unknown")),
+                                "container": symbol.get("containerName").unwrap_or(&json!(""))
+                            }));
                         }
                     }
                 }
             }
         }
-        }
+        let scan_status = if scan_complete { "complete" } else { "timed_out" };
+        let partial = !scan_complete || lsp_status == "timed_out";
         
         // Deduplicate results
         let mut unique_locations = Vec::new();
@@ -115,22 +426,14 @@ impl AnalysisCommands {
             }
         }
         
-        // Analyze symbol type based on patterns
-        let symbol_type = if params.name.chars().next().unwrap_or('a').is_uppercase() {
+        // A definition the scan found says what the symbol is; otherwise guess from the name
+        let definition = unique_locations.iter()
+            .find_map(|loc| loc.get("context").and_then(|c| c.as_str())?.strip_suffix(" definition"));
+        let symbol_type = if let Some(kind) = definition {
+            symbol_search::symbol_type(kind)
+        } else if params.name.chars().next().unwrap_or('a').is_uppercase() {
             if params.name.contains("Error") || params.name.contains("Exception") {
                 "Error Type"
-            } else if unique_locations.iter().any(|loc| 
-                loc.get("context").and_then(|c| c.as_str()).unwrap_or("").contains("struct")
-            ) {
-                "Struct"
-            } else if unique_locations.iter().any(|loc| 
-                loc.get("context").and_then(|c| c.as_str()).unwrap_or("").contains("enum")
-            ) {
-                "Enum"
-            } else if unique_locations.iter().any(|loc| 
-                loc.get("context").and_then(|c| c.as_str()).unwrap_or("").contains("trait")
-            ) {
-                "Trait"
             } else {
                 "Type"
             }
@@ -142,31 +445,90 @@ impl AnalysisCommands {
             "Function/Variable"
         };
         
-        let lsp_status = {
-            let lsp_client_guard = analyzer.get_lsp_client().await;
-            let has_lsp = lsp_client_guard.as_ref().map(|g| g.as_ref().is_some()).unwrap_or(false);
-            if has_lsp { "lsp_workspace_symbols" } else { "lsp_unavailable" }
-        };
+        let total_matched = unique_locations.len();
+        let limit = params.limit.unwrap_or(SYMBOL_PAGE_LIMIT);
+        let page: Vec<Value> = unique_locations.into_iter().skip(params.offset).take(limit).collect();
+        let truncated = params.offset + page.len() < total_matched;
         
         Ok(json!({
             "symbol": params.name,
-            "occurrences": unique_locations.len(),
-            "locations": unique_locations,
+            "occurrences": total_matched,
+            "total_matched": total_matched,
+            "offset": params.offset,
+            "limit": limit,
+            "truncated": truncated,
+            "locations": page,
+            "partial": partial,
+            "sources": {
+                "file_content_search": scan_status,
+                "lsp_workspace_symbols": lsp_status
+            },
             "analysis": {
                 "type": symbol_type,
-                "status": "enhanced_analysis_complete",
+                "status": if partial { "partial_analysis" } else { "enhanced_analysis_complete" },
                 "search_methods": [
                     "file_content_search",
-                    lsp_status
+                    if lsp_status == "unavailable" { "lsp_unavailable" } else { "lsp_workspace_symbols" }
                 ]
             }
         }))
     }
     
+    async fn search_workspace_symbols(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: WorkspaceSymbolsParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Searching workspace symbols for {:?} (kind: {:?})", params.query, params.kind);
+        
+        let kind_number = match &params.kind {
+            Some(kind) => Some(outline::symbol_kind_number(kind)
+                .ok_or_else(|| anyhow::anyhow!("Unknown symbol kind: {}", kind))?),
+            None => None,
+        };
+        
+        let project_root = analyzer.project_root().to_path_buf();
+        let lsp_symbols = match analyzer.get_lsp_client().await {
+            Some(mut lsp_guard) => match lsp_guard.as_mut() {
+                Some(client) => Some(client.workspace_symbol(&params.query).await?),
+                None => None,
+            },
+            None => None,
+        };
+        
+        let (source, symbols) = match lsp_symbols {
+            Some(lsp_symbols) => {
+                let symbols: Vec<WorkspaceSymbol> = lsp_symbols.as_array().into_iter().flatten()
+                    .filter(|symbol| kind_number.is_none_or(|kind| symbol["kind"].as_u64() == Some(kind)))
+                    .filter_map(|symbol| symbol_search::from_lsp(symbol, &project_root))
+                    .collect();
+                ("lsp", symbols)
+            }
+            None => {
+                let (query, kind) = (params.query.clone(), params.kind.clone());
+                let symbols = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || {
+                    symbol_search::search_definitions(&project_root, &query, kind.as_deref())
+                })).await?;
+                ("text_search", symbols)
+            }
+        };
+        
+        Ok(json!({
+            "query": params.query,
+            "kind": params.kind,
+            "source": source,
+            "count": symbols.len(),
+            "symbols": symbols
+        }))
+    }
+    
     async fn find_references(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: PositionParams = serde_json::from_value(
+        let params: PositionParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
         
         // Use the new LSP-based references functionality
         let references = analyzer.find_references(&params.file, params.line, params.column).await?;
@@ -181,6 +543,77 @@ impl AnalysisCommands {
         }))
     }
     
+    async fn call_hierarchy(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: CallHierarchyParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        
+        debug!("Listing {:?} calls at {}:{}:{}", params.direction, params.file, params.line, params.column);
+        
+        let hierarchy = analyzer.call_hierarchy(&params.file, params.line, params.column, params.direction).await?;
+        
+        Ok(json!({
+            "file": params.file,
+            "position": {
+                "line": params.line,
+                "column": params.column
+            },
+            "direction": hierarchy.direction,
+            "function": hierarchy.function,
+            "count": hierarchy.calls.len(),
+            "calls": hierarchy.calls
+        }))
+    }
+    
+    async fn type_hierarchy(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: TypeHierarchyParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        
+        debug!("Listing {:?} at {}:{}:{}", params.direction, params.file, params.line, params.column);
+        
+        let hierarchy = analyzer.type_hierarchy(&params.file, params.line, params.column, params.direction).await?;
+        
+        Ok(json!({
+            "file": params.file,
+            "position": {
+                "line": params.line,
+                "column": params.column
+            },
+            "direction": hierarchy.direction,
+            "type": hierarchy.root,
+            "count": hierarchy.types.len(),
+            "types": hierarchy.types
+        }))
+    }
+    
+    async fn goto_definition(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: PositionParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        debug!("Going to definition at {}:{}:{}", params.file, params.line, params.column);
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        
+        let definitions = analyzer.goto_definition(&params.file, params.line, params.column).await?;
+        
+        Ok(json!({
+            "file": params.file,
+            "position": {
+                "line": params.line,
+                "column": params.column
+            },
+            "definitions": definitions
+        }))
+    }
+    
     async fn get_diagnostics(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
         let mut params_value = params.unwrap_or(json!({}));
         
@@ -189,9 +622,27 @@ impl AnalysisCommands {
             obj.remove("method");
         }
         
-        let params: FileParams = serde_json::from_value(params_value)?;
+        let params: FileParams = param_validation::parse_params(params_value)?;
+        
+        let group_by = params.group_by.as_deref().unwrap_or("none");
+        if !matches!(group_by, "lint" | "file" | "none") {
+            anyhow::bail!("Invalid group_by value '{}': expected 'lint', 'file' or 'none'", group_by);
+        }
+        let format = params.format.as_deref().unwrap_or("native");
+        if !matches!(format, "native" | "sarif") {
+            anyhow::bail!("Invalid format value '{}': expected 'native' or 'sarif'", format);
+        }
+        if format == "sarif" && group_by != "none" {
+            anyhow::bail!("group_by can't be combined with format 'sarif'");
+        }
+        
+        debug!("Getting diagnostics for file: {:?} (group_by: {})", params.file, group_by);
         
-        debug!("Getting diagnostics for file: {:?}", params.file);
+        let changed_since = params.changed.as_deref().map(|r| if r.is_empty() { "HEAD" } else { r });
+        let changed = match changed_since {
+            Some(git_ref) => Some(metrics_diff::changed_files(analyzer.project_root(), git_ref).await?),
+            None => None,
+        };
         
         let mut diagnostics = Vec::new();
         let mut sources: Vec<String> = Vec::new();
@@ -219,10 +670,10 @@ impl AnalysisCommands {
         // Always try cargo check for comprehensive diagnostics
         use tokio::process::Command;
         
-        let cargo_output = Command::new("cargo")
-            .args(&["check", "--message-format=json"])
+        let cargo_output = timings::measure(Phase::Cargo, Command::new("cargo")
+            .args(["check", "--message-format=json"])
             .current_dir(analyzer.project_root())
-            .output()
+            .output())
             .await;
             
         match cargo_output {
@@ -233,27 +684,15 @@ impl AnalysisCommands {
                 // Parse cargo output for diagnostics
                 for line in stdout.lines() {
                     if let Ok(json_msg) = serde_json::from_str::<Value>(line) {
-                        if json_msg.get("reason") == Some(&json!("compiler-message")) {
-                            if let Some(message) = json_msg.get("message") {
-                                if let Some(spans) = message.get("spans").and_then(|s| s.as_array()) {
-                                    for span in spans {
-                                        if let Some(file_name) = span.get("file_name").and_then(|f| f.as_str()) {
-                                            // Filter by file if specified
-                                            if params.file.is_none() || 
-                                               params.file.as_ref().map_or(false, |f| file_name.contains(f) || f.contains(file_name)) {
-                                                diagnostics.push(json!({
-                                                    "file": file_name,
-                                                    "line": span.get("line_start"),
-                                                    "column": span.get("column_start"),
-                                                    "level": message.get("level").unwrap_or(&json!("error")),
-                                                    "message": message.get("message").unwrap_or(&json!("")),
-                                                    "code": message.get("code"),
-                                                    "source": "cargo"
-                                                }));
-                                            }
-                                        }
-                                    }
-                                }
+                        for diagnostic in diagnostics_stream::cargo_message_diagnostics(&json_msg) {
+                            let file_name = diagnostic["file"].as_str().unwrap_or("");
+                            // Filter by file if specified
+                            if changed.as_ref().is_some_and(|changed| !changed.contains(file_name.trim_start_matches("./"))) {
+                                continue;
+                            }
+                            if params.file.is_none() || 
+                               params.file.as_ref().is_some_and(|f| file_name.contains(f) || f.contains(file_name)) {
+                                diagnostics.push(diagnostic);
                             }
                         }
                     }
@@ -264,23 +703,142 @@ impl AnalysisCommands {
             }
         }
         
-        Ok(json!({ 
+        if format == "sarif" {
+            return Ok(sarif::to_sarif(&diagnostics));
+        }
+        
+        let mut response = json!({ 
             "file": params.file,
             "diagnostics": diagnostics,
             "total_diagnostics": diagnostics.len(),
             "sources": sources,
             "status": "cargo_check_complete",
             "note": "LSP real-time diagnostics require notification handling"
-        }))
+        });
+        if let (Some(git_ref), Some(changed)) = (changed_since, &changed) {
+            response["changed_since"] = json!(git_ref);
+            response["changed_files"] = json!(changed);
+        }
+        
+        // Collapse the flat list into groups when requested
+        if group_by != "none" {
+            let groups = Self::group_diagnostics(&diagnostics, group_by);
+            response["group_by"] = json!(group_by);
+            response["total_groups"] = json!(groups.len());
+            response["groups"] = json!(groups);
+            response["diagnostics"] = json!([]);
+        }
+        
+        Ok(response)
+    }
+    
+    /// Aggregate diagnostics, either by lint or by file. A lint group collects every
+    /// diagnostic with that code, whatever its message; diagnostics without a code
+    /// group by message. Groups are sorted by descending count so the noisiest
+    /// issues come first.
+    fn group_diagnostics(diagnostics: &[Value], group_by: &str) -> Vec<Value> {
+        let mut order: Vec<String> = Vec::new();
+        let mut groups: std::collections::HashMap<String, Vec<&Value>> = std::collections::HashMap::new();
+        
+        for diagnostic in diagnostics {
+            let file = diagnostic.get("file").and_then(|f| f.as_str()).unwrap_or("");
+            let key = if group_by == "file" {
+                file.to_string()
+            } else {
+                let code = diagnostic.get("code")
+                    .and_then(|c| c.get("code"))
+                    .and_then(|c| c.as_str());
+                match code {
+                    Some(code) => format!("code:{}", code),
+                    None => format!("message:{}", diagnostic.get("message").and_then(|m| m.as_str()).unwrap_or("")),
+                }
+            };
+            
+            let entry = groups.entry(key.clone()).or_default();
+            if entry.is_empty() {
+                order.push(key);
+            }
+            entry.push(diagnostic);
+        }
+        
+        let mut result: Vec<Value> = order.iter().map(|key| {
+            let members = &groups[key];
+            let first = members[0];
+            let mut files: Vec<&str> = members.iter()
+                .filter_map(|d| d.get("file").and_then(|f| f.as_str()))
+                .collect();
+            files.sort_unstable();
+            files.dedup();
+            
+            let samples: Vec<Value> = members.iter()
+                .take(GROUP_SAMPLE_LIMIT)
+                .map(|d| json!({
+                    "file": d.get("file"),
+                    "line": d.get("line"),
+                    "column": d.get("column")
+                }))
+                .collect();
+            
+            if group_by == "file" {
+                json!({
+                    "file": first.get("file"),
+                    "count": members.len(),
+                    "errors": members.iter().filter(|d| d.get("level") == Some(&json!("error"))).count(),
+                    "warnings": members.iter().filter(|d| d.get("level") == Some(&json!("warning"))).count(),
+                    "diagnostics": members
+                })
+            } else {
+                let code = first.get("code")
+                    .and_then(|c| c.get("code"))
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                let mut messages: Vec<&str> = Vec::new();
+                for message in members.iter().filter_map(|d| d.get("message").and_then(|m| m.as_str())) {
+                    if messages.len() == GROUP_MESSAGE_LIMIT {
+                        break;
+                    }
+                    if !messages.contains(&message) {
+                        messages.push(message);
+                    }
+                }
+                json!({
+                    "code": code,
+                    "message": first.get("message"),
+                    "example_messages": messages,
+                    "level": first.get("level"),
+                    "count": members.len(),
+                    "file_count": files.len(),
+                    "summary": format!("{}x {} across {} file(s)", 
+                        members.len(),
+                        code.as_str().unwrap_or_else(|| first.get("message").and_then(|m| m.as_str()).unwrap_or("")),
+                        files.len()
+                    ),
+                    "sample_locations": samples
+                })
+            }
+        }).collect();
+        
+        result.sort_by(|a, b| {
+            let count_a = a.get("count").and_then(|c| c.as_u64()).unwrap_or(0);
+            let count_b = b.get("count").and_then(|c| c.as_u64()).unwrap_or(0);
+            count_b.cmp(&count_a)
+        });
+        
+        result
     }
     
     async fn get_hover(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: PositionParams = serde_json::from_value(
+        let params: HoverParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        let format = params.content_format.unwrap_or(analyzer.config().content_format);
         
         // Use the new LSP-based hover functionality
-        let hover_text = analyzer.hover(&params.file, params.line, params.column).await?;
+        let hover_text = analyzer.hover(&params.file, params.line, params.column).await?
+            .map(|text| format.render(&text));
         
         Ok(json!({ 
             "contents": hover_text,
@@ -291,14 +849,24 @@ impl AnalysisCommands {
     }
     
     async fn find_implementations(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: PositionParams = serde_json::from_value(
+        let params: ImplementationParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
         
         debug!("Finding implementations at {}:{}:{}", params.file, params.line, params.column);
         
         // Use the new LSP-based implementations functionality
-        let implementations = analyzer.find_implementations(&params.file, params.line, params.column).await?;
+        let mut implementations = analyzer.find_implementations(&params.file, params.line, params.column).await?;
+        
+        if params.with_members {
+            for implementation in implementations.iter_mut() {
+                let members = Self::impl_members(implementation).await;
+                implementation["members"] = json!(members);
+            }
+        }
         
         Ok(json!({
             "file": params.file,
@@ -310,52 +878,720 @@ impl AnalysisCommands {
         }))
     }
     
-    async fn search_symbol_in_project(&self, symbol: &str, project_root: &std::path::Path) -> Result<Vec<Value>> {
-        let mut locations = Vec::new();
+    /// Read the source around an implementation location (Location or LocationLink)
+    /// and list the methods defined in that impl block.
+    async fn impl_members(location: &Value) -> Vec<Value> {
+        let uri = location.get("uri")
+            .or_else(|| location.get("targetUri"))
+            .and_then(|u| u.as_str())
+            .unwrap_or("");
+        let line = location.get("range")
+            .or_else(|| location.get("targetRange"))
+            .and_then(|r| r.get("start"))
+            .and_then(|s| s.get("line"))
+            .and_then(|l| l.as_u64())
+            .unwrap_or(0) as usize;
+        
+        let path = uri.strip_prefix("file://").unwrap_or(uri);
+        let Ok(content) = tokio::fs::read_to_string(path).await else {
+            return Vec::new();
+        };
+        
+        signature::impl_block_methods(&content, line)
+            .into_iter()
+            .map(|(method_line, text)| {
+                let parsed = signature::parse_signature(&text);
+                json!({
+                    "name": parsed.as_ref().map(|s| s.name.clone()),
+                    "line": method_line + 1,
+                    "signature": parsed.map(|s| s.rendered).unwrap_or(text)
+                })
+            })
+            .collect()
+    }
+    
+    async fn signature_at(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: PositionParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Getting signature at {}:{}:{}", params.file, params.line, params.column);
+        
+        // Prefer rust-analyzer's hover, which also resolves call sites to their definition
+        let mut source = "hover";
+        let mut text = skip_too_large(analyzer.hover(&params.file, params.line, params.column).await)?
+            .and_then(|hover| signature::extract_from_hover(&hover));
+        
+        // Fall back to reading the definition straight from the source file
+        if text.is_none() {
+            source = "source";
+            let path = analyzer.project_root().join(&params.file);
+            let content = tokio::fs::read_to_string(&path).await
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+            text = signature::extract_signature_text(&content, params.line.saturating_sub(1) as usize);
+        }
+        
+        let parsed = text.as_deref().and_then(signature::parse_signature);
+        
+        Ok(json!({
+            "file": params.file,
+            "position": {
+                "line": params.line,
+                "column": params.column
+            },
+            "source": if parsed.is_some() { source } else { "none" },
+            "signature": parsed
+        }))
+    }
+    
+    async fn cfg_status(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: CfgParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Evaluating cfg attributes in {}", params.file);
+        
+        let path = analyzer.project_root().join(&params.file);
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        
+        let mut features = analyzer.config().features.clone();
+        features.extend(params.features);
+        let ctx = CfgContext::resolve(analyzer.project_root(), params.target.as_deref(), &features).await;
+        let cfgs = cfg_eval::scan_cfgs(&content, &ctx);
+        
+        let mut enabled_features: Vec<&String> = ctx.features.iter().collect();
+        enabled_features.sort();
+        Ok(json!({
+            "file": params.file,
+            "target": params.target.as_deref().unwrap_or("host"),
+            "features": enabled_features,
+            "total": cfgs.len(),
+            "active": cfgs.iter().filter(|c| c.active).count(),
+            "cfgs": cfgs
+        }))
+    }
+    
+    async fn build_context(&self, analyzer: &RustAnalyzer) -> Result<Value> {
+        debug!("Resolving the build target and cfg context");
+        
+        let context = build_context::resolve(analyzer.project_root(), &analyzer.config().features).await?;
+        Ok(serde_json::to_value(context)?)
+    }
+    
+    async fn reexports(&self, analyzer: &RustAnalyzer) -> Result<Value> {
+        debug!("Collecting pub use re-exports");
+        
+        let root = analyzer.project_root().to_path_buf();
+        let exports = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || reexports::find_reexports(&root))).await?;
+        
+        let unresolved = exports.iter()
+            .filter(|e| e.external_crate.is_none() && e.definition.is_none())
+            .count();
+        let external = exports.iter().filter(|e| e.external_crate.is_some()).count();
+        
+        Ok(json!({
+            "total": exports.len(),
+            "external": external,
+            "unresolved": unresolved,
+            "reexports": exports
+        }))
+    }
+    
+    async fn document_outline(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: SummaryParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        debug!("Outlining file: {}", params.file);
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        
+        let symbols = analyzer.document_symbols(&params.file).await?;
+        
+        Ok(json!({
+            "file": params.file,
+            "symbols": symbols
+        }))
+    }
+    
+    async fn file_summary(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: SummaryParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Summarizing file: {}", params.file);
+        
+        let path = analyzer.project_root().join(&params.file);
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        
+        let summary = file_summary::summarize(&content);
+        let mut result = serde_json::to_value(&summary)?;
+        result["file"] = json!(params.file);
+        Ok(result)
+    }
+    
+    async fn visibility(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: PositionParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Computing visibility at {}:{}:{}", params.file, params.line, params.column);
+        
+        let mut path = analyzer.project_root().join(&params.file);
+        let mut line = params.line.saturating_sub(1) as usize;
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        let mut source = content.clone();
+        
+        // A position on a use of the item rather than its declaration: jump to the definition
+        let on_declaration = content.lines().nth(line).and_then(visibility::parse_item).is_some();
+        if !on_declaration {
+            let locations = skip_too_large(analyzer.goto_definition(&params.file, params.line, params.column).await)?;
+            if let Some(location) = locations.first() {
+                path = location.path(analyzer.project_root());
+                line = location.line as usize - 1;
+                source = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+            }
+        }
+        
+        let report = visibility::item_visibility(analyzer.project_root(), &path, &source, line)
+            .ok_or_else(|| anyhow::anyhow!("No item declaration found at {}:{}", params.file, params.line))?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn generics_of(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: PositionParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Reading generics at {}:{}:{}", params.file, params.line, params.column);
+        
+        let mut path = analyzer.project_root().join(&params.file);
+        let mut line = params.line.saturating_sub(1) as usize;
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        let mut header = generics::item_header_at(&content, line);
+        
+        // A position on a use of the item rather than its declaration: jump to the definition
+        if header.is_none() {
+            let locations = skip_too_large(analyzer.goto_definition(&params.file, params.line, params.column).await)?;
+            if let Some(location) = locations.first() {
+                path = location.path(analyzer.project_root());
+                line = location.line as usize - 1;
+                let source = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+                header = generics::item_header_at(&source, line);
+            }
+        }
+        
+        let generics = header.as_deref().and_then(generics::parse_generics)
+            .ok_or_else(|| anyhow::anyhow!("No item declaration found at {}:{}", params.file, params.line))?;
+        let mut response = serde_json::to_value(generics)?;
+        response["file"] = json!(path.strip_prefix(analyzer.project_root()).unwrap_or(&path).display().to_string());
+        response["line"] = json!(line + 1);
+        Ok(response)
+    }
+    
+    async fn diagnose(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: DiagnoseParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        debug!("Building fix plan (warnings: {})", params.include_warnings);
+        
+        let mut plan = fix_plan::diagnose(analyzer.project_root(), params.include_warnings).await?;
+        let total_steps = plan.steps.len();
+        if let Some(max) = params.max_steps {
+            plan.steps.truncate(max);
+        }
+        
+        let mut response = serde_json::to_value(plan)?;
+        response["total_steps"] = json!(total_steps);
+        Ok(response)
+    }
+    
+    async fn find_unhandled_results(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: UnhandledParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let root = analyzer.project_root();
+        let dir = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Looking for unhandled results under {}", dir.display());
+        
+        let report = unhandled_results::find_unhandled_results(root, &dir, params.include_tests).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn async_audit(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: AsyncAuditParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let root = analyzer.project_root().to_path_buf();
+        let dir = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Auditing async code under {}", dir.display());
+        
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || async_audit::audit(&root, &dir, params.include_tests))).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn error_handling_report(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ErrorHandlingParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let root = analyzer.project_root().to_path_buf();
+        let dir = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Tallying error handling under {}", dir.display());
+        
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || error_handling::report(&root, &dir, params.include_tests))).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn test_code_audit(&self, analyzer: &RustAnalyzer) -> Result<Value> {
+        debug!("Auditing test-only code");
+        
+        let root = analyzer.project_root().to_path_buf();
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || test_code_audit::audit(&root))).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn find_dyn(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: FindDynParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let root = analyzer.project_root().to_path_buf();
+        let dir = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Looking for trait objects under {}", dir.display());
+        
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || trait_objects::find_dyn(&root, &dir, params.include_tests))).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn find_ffi(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: FindFfiParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let root = analyzer.project_root().to_path_buf();
+        let dir = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Mapping the FFI boundary under {}", dir.display());
+        
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || ffi::find_ffi(&root, &dir))).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn suggest_location(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: SuggestLocationParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        let keywords = placement::keywords(&params.description);
+        if keywords.is_empty() && params.symbol.is_none() {
+            anyhow::bail!("Describe the new item or name a related symbol");
+        }
+        debug!("Suggesting a location for {:?} (symbol {:?})", params.description, params.symbol);
+        
+        let root = analyzer.project_root().to_path_buf();
+        let limit = params.limit.unwrap_or(placement::DEFAULT_LIMIT);
+        let symbol = params.symbol.clone();
+        let description = params.description.clone();
+        let candidates = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || {
+            placement::suggest(&root, &description, symbol.as_deref(), limit)
+        })).await?;
+        
+        Ok(json!({
+            "description": params.description,
+            "symbol": params.symbol,
+            "keywords": keywords,
+            "candidates": candidates
+        }))
+    }
+    
+    async fn lsp_passthrough(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let config = analyzer.config();
+        if !config.lsp_passthrough {
+            anyhow::bail!("lsp_passthrough is disabled; enable it with lsp_passthrough = true in .mcp-analyzer.toml or --lsp-passthrough");
+        }
+        let params: LspPassthroughParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        lsp_passthrough::check_method(&params.lsp_method, &config.tool_categories)?;
+        
+        debug!("Passing {} through to rust-analyzer", params.lsp_method);
+        
+        let result = analyzer.lsp_request(&params.lsp_method, params.params).await?;
+        Ok(json!({
+            "lsp_method": params.lsp_method,
+            "result": result
+        }))
+    }
+    
+    async fn read_files(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ReadFilesParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Reading {} files", params.files.len());
+        
+        let config = analyzer.config();
+        let max_bytes = match (params.max_bytes, config.max_read_bytes) {
+            (Some(0), _) => anyhow::bail!("max_bytes must be greater than 0"),
+            (Some(bytes), 0) => bytes,
+            (Some(bytes), max) => bytes.min(max),
+            (None, max) => max,
+        };
+        let result = read_files::read_files(analyzer.project_root(), &config.allowed_roots, &params.files, max_bytes).await;
+        Ok(serde_json::to_value(result)?)
+    }
+    
+    async fn analyzer_status(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: AnalyzerStatusParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        debug!("Querying rust-analyzer status");
+        
+        let status = analyzer.analyzer_status(params.file.as_deref(), params.include_memory).await;
+        Ok(serde_json::to_value(status)?)
+    }
+    
+    async fn find_shadowing(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ShadowingParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let root = analyzer.project_root().to_path_buf();
+        let dir = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Finding shadowed bindings under {}", dir.display());
+        
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || shadowing::find(&root, &dir, params.include_tests))).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn related_tests(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: RelatedTestsParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        let items = match (&params.file, params.line, params.column) {
+            (Some(file), Some(line), Some(column)) => skip_too_large(analyzer.prepare_call_hierarchy(file, line, column).await)?,
+            (None, None, None) => Vec::new(),
+            _ => anyhow::bail!("file, line and column must be given together"),
+        };
+        let name = params.name.clone()
+            .or_else(|| items.first().and_then(|item| item["name"].as_str()).map(str::to_string))
+            .ok_or_else(|| anyhow::anyhow!("Give the function's name, or the file, line and column of its definition"))?;
+        
+        debug!("Finding tests related to {}", name);
+        
+        let mut report = RelatedTestsReport { function: name.clone(), ..RelatedTestsReport::default() };
+        if let Some(item) = items.into_iter().next() {
+            match self.tests_calling(item, params.max_depth, analyzer).await {
+                Ok((tests, truncated)) => {
+                    report.method = "call_hierarchy".to_string();
+                    report.tests = tests;
+                    report.truncated = truncated;
+                }
+                Err(e) => debug!("Call hierarchy walk failed, falling back to text search: {}", e),
+            }
+        }
+        if report.method.is_empty() {
+            let files: Vec<_> = analyzer.get_all_files().into_iter()
+                .filter_map(|(id, path)| analyzer.source_file(id).ok().map(|file| (path, file.text().to_string())))
+                .collect();
+            report.method = "text_search".to_string();
+            report.tests = related_tests::search(analyzer.project_root(), &files, &name);
+        }
+        
+        report.tests.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        report.total = report.tests.len();
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    /// Tests among the transitive callers of a call hierarchy item, walked
+    /// breadth first so each test is reported through its shortest chain
+    async fn tests_calling(&self, item: Value, max_depth: usize, analyzer: &RustAnalyzer) -> Result<(Vec<RelatedTest>, bool)> {
+        let key = |item: &Value| format!("{}:{}", item["uri"], item["selectionRange"]["start"]);
+        let mut seen = HashSet::from([key(&item)]);
+        let mut queue = VecDeque::from([(item, Vec::<String>::new())]);
+        let mut tests = Vec::new();
+        let mut truncated = false;
         
-        // Search in src directory
-        let src_path = project_root.join("src");
-        if src_path.is_dir() {
-            self.search_symbol_in_directory(symbol, &src_path, &mut locations).await?;
+        while let Some((item, chain)) = queue.pop_front() {
+            for call in analyzer.incoming_calls(item).await? {
+                let caller = &call["from"];
+                if !seen.insert(key(caller)) {
+                    continue;
+                }
+                if seen.len() > RELATED_CALLER_LIMIT {
+                    return Ok((tests, true));
+                }
+                let file = uri_to_path(caller["uri"].as_str().unwrap_or_default());
+                let line = caller["selectionRange"]["start"]["line"].as_u64().unwrap_or(0) as usize + 1;
+                let source = std::fs::read_to_string(&file).unwrap_or_default();
+                if let Some(test) = related_tests::test_at(&source, line) {
+                    let via = chain.iter().rev().cloned().collect();
+                    tests.push(related_tests::related(analyzer.project_root(), &file, &test, via));
+                } else if chain.len() + 2 <= max_depth {
+                    let mut next = chain.clone();
+                    next.push(caller["name"].as_str().unwrap_or_default().to_string());
+                    queue.push_back((caller.clone(), next));
+                } else {
+                    truncated = true;
+                }
+            }
         }
+        Ok((tests, truncated))
+    }
+    
+    async fn blast_radius(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: BlastRadiusParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        let path = analyzer.project_root().join(&params.file);
+        let source = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        let name = import_suggest::identifier_at(&source, params.line.saturating_sub(1) as usize, params.column.saturating_sub(1) as usize)
+            .ok_or_else(|| anyhow::anyhow!("No identifier at {}:{}:{}", params.file, params.line, params.column))?;
+        
+        debug!("Computing blast radius of {} (depth {})", name, params.max_depth);
         
-        Ok(locations)
+        let references = skip_too_large(analyzer.find_references(&params.file, params.line, params.column).await)?;
+        let mut radius = None;
+        if !references.is_empty() {
+            match self.radius_from_references(&name, &references, params.max_depth, analyzer).await {
+                Ok(found) => radius = Some(found),
+                Err(e) => debug!("Call hierarchy walk failed, falling back to text search: {}", e),
+            }
+        }
+        let radius = match radius {
+            Some(radius) => radius,
+            None => {
+                let files: Vec<_> = analyzer.get_all_files().into_iter()
+                    .filter_map(|(id, path)| analyzer.source_file(id).ok().map(|file| (path, file.text().to_string())))
+                    .collect();
+                blast_radius::text_search(analyzer.project_root(), &files, &name, params.max_depth)
+            }
+        };
+        Ok(serde_json::to_value(radius)?)
     }
     
-    async fn search_symbol_in_directory(&self, symbol: &str, dir: &Path, locations: &mut Vec<Value>) -> Result<()> {
-        use std::fs;
+    /// Blast radius from rust-analyzer's references, expanded breadth first
+    /// through the call hierarchy of the functions holding them
+    async fn radius_from_references(&self, name: &str, references: &[Value], max_depth: usize, analyzer: &RustAnalyzer) -> Result<BlastRadius> {
+        let root = analyzer.project_root();
+        let relative = |file: &Path| file.strip_prefix(root).unwrap_or(file).display().to_string();
+        let mut radius = BlastRadius::new(name, "call_hierarchy", max_depth);
+        let mut sources: HashMap<PathBuf, String> = HashMap::new();
+        // Functions to visit by the 1-based position of their name, with their
+        // depth and the functions between them and the symbol
+        let mut queue: VecDeque<(PathBuf, String, usize, usize, usize, Vec<String>)> = VecDeque::new();
         
-        let entries = fs::read_dir(dir)?;
+        for reference in references {
+            let file = uri_to_path(reference["uri"].as_str().unwrap_or_default());
+            let start = &reference["range"]["start"];
+            let line = start["line"].as_u64().unwrap_or(0) as usize + 1;
+            let column = start["character"].as_u64().unwrap_or(0) as usize + 1;
+            let source = sources.entry(file.clone()).or_insert_with(|| std::fs::read_to_string(&file).unwrap_or_default());
+            if blast_radius::is_definition(source, line, column) {
+                continue;
+            }
+            radius.references += 1;
+            radius.files.push(relative(&file));
+            if let Some(span) = blast_radius::enclosing_function(source, line) {
+                let (line, column) = toggle_async::line_col(source, span.name_offset);
+                queue.push_back((file, span.name, line, column, 1, Vec::new()));
+            }
+        }
         
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+        let mut seen = HashSet::new();
+        while let Some((file, function, line, column, depth, chain)) = queue.pop_front() {
+            if !seen.insert((file.clone(), line)) {
+                continue;
+            }
+            if seen.len() > blast_radius::AFFECTED_LIMIT {
+                radius.truncated = true;
+                break;
+            }
+            let source = sources.entry(file.clone()).or_insert_with(|| std::fs::read_to_string(&file).unwrap_or_default());
+            if let Some(test) = related_tests::test_at(source, line) {
+                radius.tests.push(related_tests::related(root, &file, &test, chain));
+                continue;
+            }
+            radius.functions.push(AffectedFunction { name: function.clone(), file: relative(&file), line, depth });
             
-            if path.is_dir() {
-                // Recursively search subdirectories
-                Box::pin(self.search_symbol_in_directory(symbol, &path, locations)).await?;
-            } else if path.extension().map_or(false, |ext| ext == "rs") {
-                // Search in Rust files
-                if let Ok(content) = fs::read_to_string(&path) {
-                    for (line_num, line) in content.lines().enumerate() {
-                        if line.contains(symbol) {
-                            locations.push(json!({
-                                "file": path.strip_prefix(path.parent().unwrap().parent().unwrap()).unwrap_or(&path).display().to_string(),
-                                "line": line_num + 1,
-                                "content": line.trim(),
-                                "context": "code"
-                            }));
-                            
-                            // Limit results to avoid too much data
-                            if locations.len() >= 50 {
-                                return Ok(());
-                            }
-                        }
-                    }
+            let items = analyzer.prepare_call_hierarchy(&file.to_string_lossy(), line as u32, column as u32).await?;
+            let Some(item) = items.into_iter().next() else { continue };
+            let calls = analyzer.incoming_calls(item).await?;
+            if depth >= max_depth {
+                radius.truncated |= !calls.is_empty();
+                continue;
+            }
+            let mut next = chain.clone();
+            next.insert(0, function);
+            for call in calls {
+                let caller = &call["from"];
+                let start = &caller["selectionRange"]["start"];
+                queue.push_back((
+                    uri_to_path(caller["uri"].as_str().unwrap_or_default()),
+                    caller["name"].as_str().unwrap_or_default().to_string(),
+                    start["line"].as_u64().unwrap_or(0) as usize + 1,
+                    start["character"].as_u64().unwrap_or(0) as usize + 1,
+                    depth + 1,
+                    next.clone(),
+                ));
+            }
+        }
+        Ok(radius.finish())
+    }
+    
+    async fn open_documents(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: OpenDocumentsParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Opening {} documents", params.files.len());
+        
+        let report = analyzer.open_documents(params.session.as_deref(), &params.files).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn close_session(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: CloseSessionParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Closing session {}", params.session);
+        
+        let report = analyzer.close_session(&params.session).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn item_attributes(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ItemAttributesParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let root = analyzer.project_root().to_path_buf();
+        
+        let (Some(file), Some(line)) = (params.file.as_deref(), params.line) else {
+            let target = root.join(params.file.as_deref().or(params.module.as_deref()).unwrap_or("src"));
+            debug!("Tallying attributes under {}", target.display());
+            let usage = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || attributes::attribute_usage(&target))).await?;
+            return Ok(serde_json::to_value(usage)?);
+        };
+        
+        debug!("Reading attributes at {}:{}", file, line);
+        
+        let mut path = root.join(file);
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+        let mut item = attributes::item_attributes(&content, line as usize);
+        
+        // A position on a use of the item rather than its declaration: jump to the definition
+        if let (Some(column), true) = (params.column, item.as_ref().is_none_or(|item| item.kind.is_none())) {
+            let locations = analyzer.goto_definition(file, line, column).await.unwrap_or_default();
+            if let Some(location) = locations.first() {
+                let definition = location.path(&root);
+                let source = tokio::fs::read_to_string(&definition).await.unwrap_or_default();
+                if let Some(found) = attributes::item_attributes(&source, location.line as usize) {
+                    path = definition;
+                    item = Some(found);
                 }
             }
         }
         
-        Ok(())
+        let item = item.ok_or_else(|| anyhow::anyhow!("No item declaration found at {}:{}", file, line))?;
+        let mut response = serde_json::to_value(item)?;
+        response["file"] = json!(path.strip_prefix(&root).unwrap_or(&path).display().to_string());
+        Ok(response)
     }
-}
\ No newline at end of file
+    
+    async fn macro_definition(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: PositionParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Resolving macro at {}:{}:{}", params.file, params.line, params.column);
+        
+        let path = analyzer.project_root().join(&params.file);
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        let name = macros::macro_name_at(
+            &content,
+            params.line.saturating_sub(1) as usize,
+            params.column.saturating_sub(1) as usize,
+        );
+        
+        // rust-analyzer resolves both macro_rules! and proc-macro crates
+        let mut source = "lsp";
+        let mut definitions = Vec::new();
+        for location in skip_too_large(analyzer.goto_definition(&params.file, params.line, params.column).await)? {
+            let def_source = tokio::fs::read_to_string(location.path(analyzer.project_root())).await.unwrap_or_default();
+            let (kind, text) = macros::describe_definition(&def_source, location.line as usize - 1);
+            definitions.push(MacroDefinition {
+                name: name.clone().unwrap_or_default(),
+                kind,
+                file: location.file,
+                line: location.line as usize,
+                source: text,
+            });
+        }
+        
+        if definitions.is_empty() {
+            if let Some(name) = &name {
+                source = "workspace_search";
+                let root = analyzer.project_root().to_path_buf();
+                let name = name.clone();
+                definitions = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || macros::find_macro_rules(&root, &name))).await?;
+            }
+        }
+        
+        if name.is_none() && definitions.is_empty() {
+            anyhow::bail!("No macro invocation found at {}:{}:{}", params.file, params.line, params.column);
+        }
+        
+        Ok(json!({
+            "name": name,
+            "source": if definitions.is_empty() { "none" } else { source },
+            "definitions": definitions
+        }))
+    }
+}
+
+/// Every line under `src/` mentioning `symbol`, and whether the scan
+/// finished before `deadline`. Files are read in the order
+/// `source_files::walker` lists them, so ignored ones are skipped. Each line's
+/// `context` is `<kind> definition`, e.g. `fn definition`, or `usage`.
+fn search_symbol_in_project(symbol: &str, project_root: &Path, options: MatchOptions, deadline: std::time::Instant) -> (Vec<Value>, bool) {
+    let mut locations = Vec::new();
+    let files = source_files::walker(&project_root.join("src"), false).build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"));
+    for path in files {
+        if std::time::Instant::now() >= deadline {
+            return (locations, false);
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else { continue };
+        for (line_num, line) in content.lines().enumerate() {
+            if symbol_search::matches(line, symbol, options) {
+                let context = match symbol_search::definition_kind(line, symbol, options) {
+                    Some(kind) => format!("{} definition", kind),
+                    None => "usage".to_string(),
+                };
+                locations.push(json!({
+                    "file": path.strip_prefix(project_root).unwrap_or(&path).display().to_string(),
+                    "line": line_num + 1,
+                    "content": line.trim(),
+                    "context": context
+                }));
+            }
+        }
+    }
+    (locations, true)
+}
@@ -4,13 +4,40 @@ use serde_json::{json, Value};
 use tracing::debug;
 
 use crate::analyzer::RustAnalyzer;
+use crate::completion_list;
+use crate::fix_plan;
+use crate::import_suggest::{self, NameKind};
+use crate::markup::{self, ContentFormat};
+use crate::param_validation;
 use crate::server::CommandHandler;
+use crate::signature::{self, Receiver};
+use crate::timings::{self, Phase};
+use crate::unresolved_imports::{self, Candidates, UnresolvedImportsReport};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct CompletionParams {
     file: String,
     line: u32,
     column: u32,
+    content_format: Option<ContentFormat>,
+    /// Overrides the configured `max_completions`; 0 returns every item
+    max_results: Option<usize>,
+    /// Overrides the configured `completion_doc_max_len`; 0 keeps whole documentation
+    doc_max_len: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CompletionDetailParams {
+    /// Name of the method (`method` itself names the command)
+    name: String,
+    /// The method's completion item as returned by `complete`
+    item: Option<Value>,
+    /// Or the position to complete at, e.g. just after `receiver.`, to pick
+    /// the method from
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    content_format: Option<ContentFormat>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,6 +48,25 @@ struct ContextParams {
 #[derive(Debug, Serialize, Deserialize)]
 struct ImportParams {
     symbol: String,
+    /// File to import into: paths it already imports are left out, and the
+    /// edits insert after its imports
+    file: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UnresolvedParams {
+    file: String,
+    /// A diagnostic as returned by `get_diagnostics` (uses its message, line and column)
+    diagnostic: Option<Value>,
+    message: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UnresolvedImportsParams {
+    /// Suggestions kept per import (default: 5)
+    max_suggestions: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,8 +88,11 @@ impl CommandHandler for CompletionCommands {
         match method {
             "complete" => self.complete(params, analyzer).await,
             "signature_help" => self.signature_help(params, analyzer).await,
+            "completion_detail" => self.completion_detail(params, analyzer).await,
             "get_completions" => self.get_completions(params, analyzer).await,
             "resolve_import" => self.resolve_import(params, analyzer).await,
+            "resolve_unresolved" => self.resolve_unresolved(params, analyzer).await,
+            "unresolved_imports" => self.unresolved_imports(params, analyzer).await,
             "expand_snippet" => self.expand_snippet(params, analyzer).await,
             _ => anyhow::bail!("Unknown completion method: {}", method),
         }
@@ -52,34 +101,57 @@ impl CommandHandler for CompletionCommands {
 
 impl CompletionCommands {
     async fn complete(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: CompletionParams = serde_json::from_value(
+        let params: CompletionParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
         
         debug!("Getting completions at {}:{}:{}", params.file, params.line, params.column);
         
+        let format = params.content_format.unwrap_or(analyzer.config().content_format);
+        
         // Use the new LSP-based completion functionality
-        let completions = analyzer.completions(&params.file, params.line, params.column).await?;
+        let doc_max_len = params.doc_max_len.unwrap_or(analyzer.config().completion_doc_max_len);
+        let mut completions = analyzer.completions(&params.file, params.line, params.column).await?;
+        for item in &mut completions {
+            if format == ContentFormat::Plaintext {
+                plaintext_documentation(item);
+            }
+            truncate_documentation(item, doc_max_len);
+        }
         
-        Ok(json!({
+        let list = completion_list::summarize(completions, params.max_results.unwrap_or(analyzer.config().max_completions));
+        let mut response = json!({
             "file": params.file,
             "position": {
                 "line": params.line,
                 "column": params.column
             },
-            "completions": completions
-        }))
+            "total": list.total,
+            "truncated": list.truncated,
+            "completions": list.items
+        });
+        if let Some(by_kind) = list.by_kind {
+            response["by_kind"] = json!(by_kind);
+        }
+        Ok(response)
     }
     
     async fn signature_help(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: CompletionParams = serde_json::from_value(
+        let params: CompletionParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
         
         debug!("Getting signature help at {}:{}:{}", params.file, params.line, params.column);
         
-        // Use the new LSP-based signature help functionality
-        let signatures = analyzer.signature_help(&params.file, params.line, params.column).await?;
+        let format = params.content_format.unwrap_or(analyzer.config().content_format);
+        let result = analyzer.signature_help(&params.file, params.line, params.column).await?;
+        let help = signature::from_signature_help(&result, format);
         
         Ok(json!({
             "file": params.file,
@@ -87,12 +159,70 @@ impl CompletionCommands {
                 "line": params.line,
                 "column": params.column
             },
-            "signatures": signatures
+            "signatures": help.signatures,
+            "activeSignature": help.active_signature,
+            "activeParameter": help.active_parameter
+        }))
+    }
+    
+    async fn completion_detail(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: CompletionDetailParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        let name = params.name;
+        let (item, source) = match (params.item, params.file, params.line, params.column) {
+            (Some(item), ..) => (item, "item"),
+            (None, Some(file), Some(line), Some(column)) => {
+                if let Some(too_large) = analyzer.file_too_large(&file) {
+                    return Ok(too_large);
+                }
+                debug!("Picking completion {} at {}:{}:{}", name, file, line, column);
+                
+                let completions = analyzer.completions(&file, line, column).await?;
+                let item = completions.into_iter()
+                    .find(|item| label_name(item["label"].as_str().unwrap_or("")) == name)
+                    .ok_or_else(|| anyhow::anyhow!("No completion named {} at {}:{}:{}", name, file, line, column))?;
+                (item, "completion")
+            }
+            _ => anyhow::bail!("Pass either item, or file, line and column"),
+        };
+        
+        let has_data = item.get("data").is_some();
+        let (item, resolved) = match analyzer.resolve_completion(item.clone()).await {
+            Ok(resolved) => (resolved, has_data),
+            Err(e) => {
+                debug!("Completion resolve failed: {}", e);
+                (item, false)
+            }
+        };
+        
+        let detail = item["detail"].as_str().unwrap_or("");
+        let parsed = signature::parse_completion_detail(&name, detail);
+        let receiver = parsed.as_ref().and_then(|s| s.receiver());
+        
+        let format = params.content_format.unwrap_or(analyzer.config().content_format);
+        let documentation = match item.get("documentation") {
+            Some(Value::String(text)) => Some(text.as_str()),
+            Some(Value::Object(markup)) => markup.get("value").and_then(|v| v.as_str()),
+            _ => None,
+        }.map(|text| format.render(text));
+        
+        Ok(json!({
+            "name": name,
+            "source": source,
+            "resolved": resolved,
+            "detail": detail,
+            "signature": parsed,
+            "receiver": receiver,
+            "requires_mut": receiver == Some(Receiver::RefMut),
+            "consumes_self": receiver == Some(Receiver::Owned),
+            "documentation": documentation
         }))
     }
     
     async fn get_completions(&self, params: Option<Value>, _analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: ContextParams = serde_json::from_value(
+        let params: ContextParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
         
@@ -115,22 +245,135 @@ impl CompletionCommands {
         }))
     }
     
-    async fn resolve_import(&self, params: Option<Value>, _analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: ImportParams = serde_json::from_value(
+    async fn resolve_import(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ImportParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
         
         debug!("Resolving import for symbol: {}", params.symbol);
         
-        // Stub implementation for testing
-        Ok(json!({
+        let (path, source) = match &params.file {
+            Some(file) => {
+                let path = analyzer.project_root().join(file);
+                let source = tokio::fs::read_to_string(&path).await
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+                (path, source)
+            }
+            None => (std::path::PathBuf::new(), String::new()),
+        };
+        
+        let mut lsp_symbols = Vec::new();
+        if let Some(mut lsp_guard) = analyzer.get_lsp_client().await {
+            if let Some(client) = lsp_guard.as_mut() {
+                if let Ok(Value::Array(symbols)) = client.workspace_symbol(&params.symbol).await {
+                    lsp_symbols = symbols;
+                }
+            }
+        }
+        
+        let suggestions = import_suggest::suggest_imports(analyzer.project_root(), &path, &source, &params.symbol, NameKind::Any, &lsp_symbols);
+        let imported = import_suggest::imported_paths(&source);
+        let (present, missing): (Vec<_>, Vec<_>) = suggestions.into_iter().partition(|s| imported.contains(&s.path));
+        let imports: Vec<Value> = missing.into_iter()
+            .map(|suggestion| {
+                let mut import = serde_json::to_value(&suggestion).unwrap_or_default();
+                import["use"] = json!(format!("use {};", suggestion.path));
+                import
+            })
+            .collect();
+        
+        let mut result = json!({
             "symbol": params.symbol,
-            "imports": []
+            "imports": imports
+        });
+        if let Some(file) = params.file {
+            result["file"] = json!(file);
+            result["already_imported"] = json!(present.into_iter().map(|s| s.path).collect::<Vec<_>>());
+        }
+        Ok(result)
+    }
+    
+    async fn resolve_unresolved(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: UnresolvedParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        let diagnostic = params.diagnostic.as_ref();
+        let field = |name: &str| diagnostic.and_then(|d| d.get(name)).and_then(|v| v.as_u64()).map(|v| v as u32);
+        let message = params.message.clone()
+            .or_else(|| diagnostic.and_then(|d| d.get("message")).and_then(|m| m.as_str()).map(String::from));
+        let line = params.line.or_else(|| field("line"));
+        let column = params.column.or_else(|| field("column"));
+        
+        let path = analyzer.project_root().join(&params.file);
+        let source = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        
+        let (kind, name) = match message.as_deref().and_then(import_suggest::unresolved_name) {
+            Some(found) => found,
+            None => {
+                let (Some(line), Some(column)) = (line, column) else {
+                    anyhow::bail!("Provide an unresolved-name diagnostic, a message, or a line and column");
+                };
+                let name = import_suggest::identifier_at(&source, line.saturating_sub(1) as usize, column.saturating_sub(1) as usize)
+                    .ok_or_else(|| anyhow::anyhow!("No identifier at {}:{}:{}", params.file, line, column))?;
+                (NameKind::Any, name)
+            }
+        };
+        
+        debug!("Suggesting imports for unresolved {:?} `{}`", kind, name);
+        
+        let mut lsp_symbols = Vec::new();
+        if let Some(mut lsp_guard) = analyzer.get_lsp_client().await {
+            if let Some(client) = lsp_guard.as_mut() {
+                if let Ok(Value::Array(symbols)) = client.workspace_symbol(&name).await {
+                    lsp_symbols = symbols;
+                }
+            }
+        }
+        
+        let suggestions = import_suggest::suggest_imports(analyzer.project_root(), &path, &source, &name, kind, &lsp_symbols);
+        
+        Ok(json!({
+            "file": params.file,
+            "name": name,
+            "kind": kind,
+            "suggestions": suggestions
         }))
     }
     
+    async fn unresolved_imports(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: UnresolvedImportsParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        debug!("Collecting unresolved imports");
+        
+        let (success, messages) = fix_plan::cargo_check(analyzer.project_root()).await?;
+        let mut imports = unresolved_imports::collect(&messages);
+        if !imports.is_empty() {
+            let root = analyzer.project_root().to_path_buf();
+            let candidates = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || Candidates::scan(&root))).await?;
+            for import in &mut imports {
+                let mut lsp_symbols = Vec::new();
+                if let Some(mut lsp_guard) = analyzer.get_lsp_client().await {
+                    if let Some(client) = lsp_guard.as_mut() {
+                        let name = import.unresolved.rsplit("::").next().unwrap_or(&import.unresolved);
+                        if let Ok(Value::Array(symbols)) = client.workspace_symbol(name).await {
+                            lsp_symbols = symbols;
+                        }
+                    }
+                }
+                candidates.suggest(analyzer.project_root(), import, &lsp_symbols);
+                import.suggestions.truncate(params.max_suggestions.unwrap_or(5));
+            }
+        }
+        
+        Ok(serde_json::to_value(UnresolvedImportsReport { success, count: imports.len(), imports })?)
+    }
+    
     async fn expand_snippet(&self, params: Option<Value>, _analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: SnippetParams = serde_json::from_value(
+        let params: SnippetParams = param_validation::parse_params(
             params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
         )?;
         
@@ -170,4 +413,35 @@ fn ${1:test_name}() {
             "snippet": snippet
         }))
     }
-}
\ No newline at end of file
+}
+
+/// The name a completion label starts with: `push(…)` and `into_iter() (as IntoIterator)`
+/// both name their method
+fn label_name(label: &str) -> &str {
+    let end = label.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(label.len());
+    &label[..end]
+}
+
+/// Replace a completion item's markdown documentation with stripped plain text
+fn plaintext_documentation(item: &mut Value) {
+    let text = match item.get("documentation") {
+        Some(Value::String(text)) => text.clone(),
+        // MarkupContent: { kind, value }
+        Some(Value::Object(markup)) => markup.get("value").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        _ => return,
+    };
+    item["documentation"] = json!(ContentFormat::Plaintext.render(&text));
+}
+
+/// Cut a completion item's documentation, plain or MarkupContent, down to `max_len` characters
+fn truncate_documentation(item: &mut Value, max_len: usize) {
+    let text = match item.get_mut("documentation") {
+        Some(Value::String(text)) => text,
+        Some(Value::Object(markup)) => match markup.get_mut("value") {
+            Some(Value::String(text)) => text,
+            _ => return,
+        },
+        _ => return,
+    };
+    *text = markup::truncate(text, max_len);
+}
@@ -6,18 +6,156 @@ use std::path::Path;
 use tokio::fs;
 
 use crate::analyzer::RustAnalyzer;
+use crate::api_surface;
+use crate::benchmarks::{self, BenchOptions};
+use crate::complexity::{self, FunctionComplexity};
+use crate::derives;
+use crate::duplicates;
+use crate::entry_points;
+use crate::feature_check::{self, FeatureCombination};
+use crate::feature_graph;
+use crate::formatting;
+use crate::manifest::{self, Manifest};
+use crate::metrics_diff;
+use crate::missing_docs;
+use crate::module_graph::{self, ReadingDirection};
+use crate::module_tree;
+use crate::outdated;
+use crate::param_validation;
+use crate::run_example;
 use crate::server::CommandHandler;
+use crate::source_files;
+use crate::timings::{self, Phase};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ModuleParams {
     module: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct CodeMetricsParams {
+    module: Option<String>,
+    /// How many of the most complex functions to list
+    top: Option<usize>,
+    /// Also count hidden and gitignored files
+    #[serde(default)]
+    include_hidden: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiSurfaceParams {
+    /// Public share from which a module is flagged (default: 0.75)
+    max_public_ratio: Option<f64>,
+    /// Fewest items a flagged module has (default: 5)
+    min_items: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MissingDocsParams {
+    /// Take the missing items from `cargo check` with the `missing_docs` lint
+    #[serde(default)]
+    lint: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FeatureGraphParams {
+    /// Manifest to read; the root Cargo.toml when omitted
+    file: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct FileParams {
+    /// Restrict suggestions to this file or module; the whole project when omitted
+    file: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DependenciesParams {
+    /// Compare requirements against the latest published versions
+    #[serde(default)]
+    check_outdated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FeaturePowersetParams {
+    /// Explicit feature sets to check; defaults to each feature alone plus
+    /// no-default-features and all-features
+    combinations: Option<Vec<Vec<String>>>,
+    max_combinations: Option<usize>,
+    concurrency: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryPointsParams {
+    /// Also report examples, tests, benches and build scripts
+    #[serde(default)]
+    all_targets: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReadingOrderParams {
+    #[serde(default)]
+    order: ReadingDirection,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FindDuplicatesParams {
+    module: Option<String>,
+    /// Minimum similarity (0.0 to 1.0) for two bodies to count as duplicates
+    threshold: Option<f64>,
+    /// Bodies with fewer normalized tokens are ignored
+    min_tokens: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RunExampleParams {
+    example: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    features: Vec<String>,
+    /// Capped at the configured `max_run_seconds`
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RunBenchmarkParams {
+    /// Benchmark name filter passed to the harness
+    name: Option<String>,
+    /// Bench target, e.g. the file stem under benches/
+    bench: Option<String>,
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    list: bool,
+    /// Capped at the configured `max_run_seconds`
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckFormattingParams {
+    /// File relative to the project root; the whole workspace when absent
+    file: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MetricsDiffParams {
     file: String,
+    /// Revision to compare the working tree against
+    #[serde(default = "default_git_ref")]
+    git_ref: String,
 }
 
+fn default_git_ref() -> String {
+    "HEAD".to_string()
+}
+
+/// Upper bound on `cargo check` runs per request unless the caller raises it
+const DEFAULT_MAX_COMBINATIONS: usize = 16;
+const DEFAULT_CHECK_CONCURRENCY: usize = 2;
+const DEFAULT_DUPLICATE_THRESHOLD: f64 = 0.9;
+const DEFAULT_DUPLICATE_MIN_TOKENS: usize = 30;
+
 pub struct MetricsCommands;
 
 #[async_trait::async_trait]
@@ -31,10 +169,23 @@ impl CommandHandler for MetricsCommands {
             
         match method {
             "project_structure" => self.project_structure(analyzer).await,
-            "analyze_dependencies" => self.analyze_dependencies(analyzer).await,
+            "analyze_dependencies" => self.analyze_dependencies(params, analyzer).await,
             "code_metrics" => self.code_metrics(params, analyzer).await,
             "find_dead_code" => self.find_dead_code(analyzer).await,
             "suggest_improvements" => self.suggest_improvements(params, analyzer).await,
+            "check_feature_powerset" => self.check_feature_powerset(params, analyzer).await,
+            "derive_usage" => self.derive_usage(params, analyzer).await,
+            "metrics_diff" => self.metrics_diff(params, analyzer).await,
+            "find_duplicates" => self.find_duplicates(params, analyzer).await,
+            "run_example" => self.run_example(params, analyzer).await,
+            "run_benchmark" => self.run_benchmark(params, analyzer).await,
+            "check_formatting" => self.check_formatting(params, analyzer).await,
+            "entry_points" => self.entry_points(params, analyzer).await,
+            "reading_order" => self.reading_order(params, analyzer).await,
+            "module_tree" => self.module_tree(params, analyzer).await,
+            "api_surface" => self.api_surface(params, analyzer).await,
+            "missing_docs" => self.missing_docs(params, analyzer).await,
+            "feature_graph" => self.feature_graph(params, analyzer).await,
             _ => anyhow::bail!("Unknown metrics method: {}", method),
         }
     }
@@ -55,21 +206,28 @@ impl MetricsCommands {
         }
         
         // Check for workspace
-        let cargo_toml = analyzer.project_root().join("Cargo.toml");
-        if cargo_toml.exists() {
-            if let Ok(content) = fs::read_to_string(&cargo_toml).await {
-                if content.contains("[workspace]") {
+        if let Ok(content) = fs::read_to_string(analyzer.project_root().join("Cargo.toml")).await {
+            match manifest::parse(&content).map(|manifest| manifest.workspace) {
+                Ok(Some(workspace)) => {
                     structure["type"] = json!("workspace");
-                    structure["members"] = json!(self.find_workspace_members(analyzer.project_root()).await?);
-                } else {
-                    structure["type"] = json!("package");
+                    let mut members = Vec::new();
+                    for member in manifest::workspace_members(analyzer.project_root(), &workspace) {
+                        let src = analyzer.project_root().join(&member.path).join("src");
+                        let mut entry = serde_json::to_value(&member)?;
+                        entry["modules"] = json!(self.analyze_directory(&src).await?);
+                        members.push(entry);
+                    }
+                    structure["members"] = json!(members);
                 }
+                Ok(None) => structure["type"] = json!("package"),
+                Err(e) => debug!("Cargo.toml unreadable: {:#}", e),
             }
         }
         
         Ok(structure)
     }
     
+    /// Module tree of `path`, leaving out what `source_files::walker` skips
     async fn analyze_directory(&self, path: &Path) -> Result<Vec<Value>> {
         let mut modules = Vec::new();
         
@@ -77,20 +235,26 @@ impl MetricsCommands {
             return Ok(modules);
         }
         
-        let mut entries = fs::read_dir(path).await?;
-        while let Some(entry) = entries.next_entry().await? {
+        let dir = path.to_path_buf();
+        let entries: Vec<ignore::DirEntry> = tokio::task::spawn_blocking(move || {
+            source_files::walker(&dir, false).max_depth(Some(1)).build()
+                .flatten()
+                .filter(|entry| entry.depth() == 1)
+                .collect()
+        }).await?;
+        for entry in entries {
             let path = entry.path();
             let file_name = entry.file_name().to_string_lossy().to_string();
             
-            if path.is_dir() && !file_name.starts_with('.') {
-                let submodules = Box::pin(self.analyze_directory(&path)).await?;
+            if entry.file_type().is_some_and(|t| t.is_dir()) {
+                let submodules = Box::pin(self.analyze_directory(path)).await?;
                 modules.push(json!({
                     "name": file_name,
                     "type": "directory",
                     "path": path.display().to_string(),
                     "submodules": submodules
                 }));
-            } else if path.extension().map_or(false, |ext| ext == "rs") {
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
                 let is_mod = file_name == "mod.rs" || file_name == "lib.rs" || file_name == "main.rs";
                 modules.push(json!({
                     "name": file_name,
@@ -103,63 +267,170 @@ impl MetricsCommands {
         Ok(modules)
     }
     
-    async fn find_workspace_members(&self, root: &Path) -> Result<Vec<String>> {
-        let mut members = Vec::new();
-        
-        let cargo_toml = root.join("Cargo.toml");
-        if let Ok(content) = fs::read_to_string(cargo_toml).await {
-            // Simple parsing - in real implementation would use toml crate
-            for line in content.lines() {
-                if line.trim().starts_with("members") {
-                    // Extract members from the line or following array
-                    members.push("(workspace member detection not fully implemented)".to_string());
-                    break;
+    async fn module_tree(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ModuleParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let module = params.module.unwrap_or_else(|| "src".to_string());
+        debug!("Outlining module tree of {}", module);
+
+        let target_path = analyzer.project_root().join(&module);
+        if !target_path.is_dir() {
+            anyhow::bail!("Not a directory: {}", module);
+        }
+        let entries = self.analyze_directory(&target_path).await?;
+        Ok(serde_json::to_value(module_tree::render(&module, &entries))?)
+    }
+    
+    async fn api_surface(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ApiSurfaceParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let max_public_ratio = params.max_public_ratio.unwrap_or(api_surface::DEFAULT_MAX_PUBLIC_RATIO);
+        let min_items = params.min_items.unwrap_or(api_surface::DEFAULT_MIN_ITEMS);
+        debug!("Measuring API surface (max public ratio {}, min items {})", max_public_ratio, min_items);
+
+        let root = analyzer.project_root().to_path_buf();
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || {
+            api_surface::analyze(&root, max_public_ratio, min_items)
+        })).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn missing_docs(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: MissingDocsParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        debug!("Finding undocumented public items (lint: {})", params.lint);
+
+        let root = analyzer.project_root().to_path_buf();
+        // A failed build reports only part of the crate, so it falls back too
+        let fallback_reason = if params.lint {
+            match missing_docs::run_lint(&root).await {
+                Ok((true, messages)) => {
+                    let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || {
+                        missing_docs::from_lint(&root, &messages)
+                    })).await?;
+                    return Ok(serde_json::to_value(report)?);
                 }
+                Ok((false, _)) => Some("cargo check failed; fix the build for lint results".to_string()),
+                Err(e) => Some(format!("{:#}", e)),
             }
-        }
+        } else {
+            None
+        };
+
+        let mut report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || missing_docs::scan(&root))).await?;
+        report.fallback_reason = fallback_reason;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn feature_graph(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: FeatureGraphParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let file = params.file.unwrap_or_else(|| "Cargo.toml".to_string());
+        debug!("Building feature graph of {}", file);
         
-        Ok(members)
+        let content = fs::read_to_string(analyzer.project_root().join(&file)).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+        let graph = feature_graph::feature_graph(&content)?;
+        let mut result = serde_json::to_value(graph)?;
+        result["file"] = json!(file);
+        Ok(result)
     }
     
-    async fn analyze_dependencies(&self, analyzer: &RustAnalyzer) -> Result<Value> {
+    async fn analyze_dependencies(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: DependenciesParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
         debug!("Analyzing dependencies");
         
         let cargo_toml = analyzer.project_root().join("Cargo.toml");
-        let mut deps = json!({
-            "dependencies": {},
-            "dev_dependencies": {},
-            "build_dependencies": {}
-        });
+        let manifest = match fs::read_to_string(&cargo_toml).await {
+            Ok(content) => manifest::parse(&content)?,
+            Err(_) => Manifest::default(),
+        };
+        let mut deps = json!({});
+        for (section, dependencies) in manifest.sections() {
+            let by_name: serde_json::Map<String, Value> = dependencies.iter()
+                .map(|dependency| Ok((dependency.name.clone(), serde_json::to_value(dependency)?)))
+                .collect::<Result<_>>()?;
+            deps[section] = Value::Object(by_name);
+        }
         
-        if let Ok(content) = fs::read_to_string(cargo_toml).await {
-            // Simple parsing - in real implementation would use toml crate
-            let mut current_section = "";
-            
-            for line in content.lines() {
-                let trimmed = line.trim();
-                if trimmed == "[dependencies]" {
-                    current_section = "dependencies";
-                } else if trimmed == "[dev-dependencies]" {
-                    current_section = "dev_dependencies";
-                } else if trimmed == "[build-dependencies]" {
-                    current_section = "build_dependencies";
-                } else if trimmed.starts_with('[') {
-                    current_section = "";
-                } else if !current_section.is_empty() && trimmed.contains('=') {
-                    if let Some((name, version)) = trimmed.split_once('=') {
-                        let name = name.trim();
-                        let version = version.trim().trim_matches('"');
-                        deps[current_section][name] = json!(version);
-                    }
-                }
-            }
+        if params.check_outdated {
+            let offline = std::env::var("CARGO_NET_OFFLINE").is_ok_and(|v| v == "true");
+            let report = outdated::check_outdated(analyzer.project_root(), &manifest, offline).await;
+            deps["outdated"] = serde_json::to_value(report)?;
         }
         
         Ok(deps)
     }
     
+    async fn check_feature_powerset(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: FeaturePowersetParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        let mut combinations = match params.combinations {
+            Some(sets) => sets.into_iter()
+                .map(|features| FeatureCombination {
+                    label: if features.is_empty() { "no-default-features".to_string() } else { features.join(",") },
+                    no_default_features: true,
+                    all_features: false,
+                    features,
+                })
+                .collect(),
+            None => {
+                let features = feature_check::package_features(analyzer.project_root()).await?;
+                feature_check::default_combinations(&features)
+            }
+        };
+        
+        let max = params.max_combinations.unwrap_or(DEFAULT_MAX_COMBINATIONS);
+        let skipped = combinations.len().saturating_sub(max);
+        combinations.truncate(max);
+        
+        debug!("Checking {} feature combinations ({} skipped)", combinations.len(), skipped);
+        
+        let results = feature_check::check_combinations(
+            analyzer.project_root(),
+            combinations,
+            params.concurrency.unwrap_or(DEFAULT_CHECK_CONCURRENCY),
+        ).await;
+        
+        let failed: Vec<&String> = results.iter()
+            .filter(|r| !r.success)
+            .map(|r| &r.combination.label)
+            .collect();
+        
+        Ok(json!({
+            "checked": results.len(),
+            "skipped": skipped,
+            "failed": failed,
+            "all_passed": failed.is_empty(),
+            "results": results
+        }))
+    }
+    
+    async fn derive_usage(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ModuleParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        let root = analyzer.project_root().to_path_buf();
+        let target_path = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Tallying derives under {}", target_path.display());
+        
+        let usage = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || derives::derive_usage(&target_path, &root))).await?;
+        Ok(serde_json::to_value(usage)?)
+    }
+    
     async fn code_metrics(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
-        let params: ModuleParams = serde_json::from_value(
+        let params: CodeMetricsParams = param_validation::parse_params(
             params.unwrap_or_else(|| json!({}))
         )?;
         
@@ -171,7 +442,16 @@ impl MetricsCommands {
             analyzer.project_root().join("src")
         };
         
-        let metrics = self.calculate_metrics(&target_path).await?;
+        let mut functions = Vec::new();
+        let mut metrics = self.calculate_metrics(&target_path, params.include_hidden, &mut functions).await?;
+        for function in &mut functions {
+            if let Ok(file) = Path::new(&function.file).strip_prefix(analyzer.project_root()) {
+                function.file = file.display().to_string();
+            }
+        }
+        metrics["average_complexity"] = json!(complexity::average(&functions));
+        metrics["max_complexity"] = json!(functions.iter().map(|f| f.complexity).max().unwrap_or(0));
+        metrics["most_complex"] = json!(complexity::most_complex(&functions, params.top.unwrap_or(complexity::DEFAULT_TOP)));
         
         Ok(json!({
             "path": target_path.display().to_string(),
@@ -179,119 +459,228 @@ impl MetricsCommands {
         }))
     }
     
-    async fn calculate_metrics(&self, path: &Path) -> Result<Value> {
-        let mut total_lines = 0u64;
-        let mut code_lines = 0u64;
-        let mut comment_lines = 0u64;
-        let mut blank_lines = 0u64;
+    async fn entry_points(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: EntryPointsParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        debug!("Listing entry points (all targets: {})", params.all_targets);
+        
+        let entries = entry_points::entry_points(analyzer.project_root(), params.all_targets).await?;
+        Ok(json!({
+            "total": entries.len(),
+            "entry_points": entries
+        }))
+    }
+    
+    async fn reading_order(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ReadingOrderParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        debug!("Ordering modules for reading ({:?})", params.order);
+        
+        let root = analyzer.project_root().to_path_buf();
+        let graph = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || module_graph::build(&root))).await?;
+        let steps = module_graph::reading_order(&graph, params.order);
+        Ok(json!({
+            "order": params.order,
+            "total_files": graph.modules.len(),
+            "cycles": steps.iter().filter(|step| step.cycle).count(),
+            "steps": steps
+        }))
+    }
+    
+    async fn find_duplicates(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: FindDuplicatesParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let threshold = params.threshold.unwrap_or(DEFAULT_DUPLICATE_THRESHOLD);
+        if !(0.0..=1.0).contains(&threshold) {
+            anyhow::bail!("threshold must be between 0.0 and 1.0, got {}", threshold);
+        }
+        let min_tokens = params.min_tokens.unwrap_or(DEFAULT_DUPLICATE_MIN_TOKENS);
+        
+        let root = analyzer.project_root().to_path_buf();
+        let target_path = root.join(params.module.as_deref().unwrap_or("src"));
+        
+        debug!("Finding duplicate functions under {} (threshold {})", target_path.display(), threshold);
+        
+        let report = timings::measure(Phase::FileScan, tokio::task::spawn_blocking(move || {
+            duplicates::find_duplicates(&target_path, &root, threshold, min_tokens)
+        })).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn run_example(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: RunExampleParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        let max = analyzer.config().max_run_seconds;
+        let timeout = params.timeout_secs.map_or(max, |secs| secs.min(max));
+        
+        debug!("Running example {} with a {}s timeout", params.example, timeout);
+        
+        let outcome = run_example::run_example(
+            analyzer.project_root(),
+            &params.example,
+            &params.args,
+            &params.features,
+            std::time::Duration::from_secs(timeout),
+        ).await?;
+        Ok(serde_json::to_value(outcome)?)
+    }
+    
+    async fn run_benchmark(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: RunBenchmarkParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        let max = analyzer.config().max_run_seconds;
+        let timeout = params.timeout_secs.map_or(max, |secs| secs.min(max));
+        
+        debug!("Running benchmarks {:?} with a {}s timeout", params.name, timeout);
+        
+        let options = BenchOptions {
+            filter: params.name,
+            target: params.bench,
+            features: params.features,
+            list: params.list,
+        };
+        let run = benchmarks::run_benchmark(analyzer.project_root(), &options, std::time::Duration::from_secs(timeout)).await?;
+        Ok(serde_json::to_value(run)?)
+    }
+    
+    async fn check_formatting(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: CheckFormattingParams = param_validation::parse_params(
+            params.unwrap_or_else(|| json!({}))
+        )?;
+        
+        debug!("Checking formatting of {}", params.file.as_deref().unwrap_or("the workspace"));
+        
+        let report = formatting::check(analyzer.project_root(), params.file.as_deref()).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+    
+    async fn metrics_diff(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: MetricsDiffParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        let root = analyzer.project_root();
+        
+        debug!("Comparing metrics for {} against {}", params.file, params.git_ref);
+        
+        let old = metrics_diff::content_at_ref(root, &params.file, &params.git_ref).await?;
+        // A file deleted from the working tree compares against empty content
+        let new = fs::read_to_string(root.join(&params.file)).await.ok();
+        if old.is_none() && new.is_none() {
+            anyhow::bail!("{} exists neither in the working tree nor at {}", params.file, params.git_ref);
+        }
+        let old_content = old.as_deref().unwrap_or("");
+        let new_content = new.as_deref().unwrap_or("");
+        
+        let mut before = self.analyze_file_content(old_content, &mut Vec::new());
+        before["cyclomatic_complexity"] = json!(metrics_diff::cyclomatic_complexity(old_content));
+        let mut after = self.analyze_file_content(new_content, &mut Vec::new());
+        after["cyclomatic_complexity"] = json!(metrics_diff::cyclomatic_complexity(new_content));
+        
+        // git does not diff files that are untracked at the ref
+        let (lines_added, lines_removed) = match old {
+            Some(_) => metrics_diff::line_changes(root, &params.file, &params.git_ref).await?,
+            None => (new_content.lines().count(), 0),
+        };
+        let (functions_added, functions_removed) = metrics_diff::functions_changed(
+            &metrics_diff::function_names(old_content),
+            &metrics_diff::function_names(new_content),
+        );
+        let delta = metrics_diff::metric_deltas(&before, &after);
+        
+        Ok(json!({
+            "file": params.file,
+            "git_ref": params.git_ref,
+            "exists_at_ref": old.is_some(),
+            "exists_in_working_tree": new.is_some(),
+            "before": before,
+            "after": after,
+            "delta": delta,
+            "lines_added": lines_added,
+            "lines_removed": lines_removed,
+            "functions_added": functions_added,
+            "functions_removed": functions_removed,
+            "complexity_increased": delta["cyclomatic_complexity"].as_i64().unwrap_or(0) > 0
+        }))
+    }
+    
+    /// Line and declaration counts of the `.rs` files under `path`, as
+    /// `source_files::walker` lists them; the complexity of every function
+    /// found is added to `functions`
+    async fn calculate_metrics(&self, path: &Path, include_hidden: bool, functions: &mut Vec<FunctionComplexity>) -> Result<Value> {
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
+            let content = fs::read_to_string(path).await?;
+            let found = functions.len();
+            let stats = self.analyze_file_content(&content, functions);
+            for function in &mut functions[found..] {
+                function.file = path.display().to_string();
+            }
+            return Ok(stats);
+        }
+        
+        let root = path.to_path_buf();
+        let files: Vec<std::path::PathBuf> = tokio::task::spawn_blocking(move || {
+            source_files::walker(&root, include_hidden).build()
+                .flatten()
+                .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+                .map(|entry| entry.into_path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+                .collect()
+        }).await?;
+        
         let mut file_count = 0u64;
-        let mut function_count = 0u64;
-        let mut struct_count = 0u64;
-        let mut enum_count = 0u64;
-        let mut trait_count = 0u64;
-        
-        if path.is_file() {
-            if path.extension().map_or(false, |ext| ext == "rs") {
-                let content = fs::read_to_string(path).await?;
-                let stats = self.analyze_file_content(&content);
-                return Ok(stats);
+        let mut totals = serde_json::Map::new();
+        for file in &files {
+            let Ok(content) = fs::read_to_string(file).await else { continue };
+            file_count += 1;
+            let found = functions.len();
+            let stats = self.analyze_file_content(&content, functions);
+            for function in &mut functions[found..] {
+                function.file = file.display().to_string();
             }
-        } else if path.is_dir() {
-            let mut entries = fs::read_dir(path).await?;
-            while let Some(entry) = entries.next_entry().await? {
-                let path = entry.path();
-                if path.is_file() && path.extension().map_or(false, |ext| ext == "rs") {
-                    file_count += 1;
-                    if let Ok(content) = fs::read_to_string(&path).await {
-                        let stats = self.analyze_file_content(&content);
-                        if let Some(lines) = stats.get("total_lines").and_then(|v| v.as_u64()) {
-                            total_lines += lines;
-                        }
-                        if let Some(lines) = stats.get("code_lines").and_then(|v| v.as_u64()) {
-                            code_lines += lines;
-                        }
-                        if let Some(lines) = stats.get("comment_lines").and_then(|v| v.as_u64()) {
-                            comment_lines += lines;
-                        }
-                        if let Some(lines) = stats.get("blank_lines").and_then(|v| v.as_u64()) {
-                            blank_lines += lines;
-                        }
-                        if let Some(count) = stats.get("functions").and_then(|v| v.as_u64()) {
-                            function_count += count;
-                        }
-                        if let Some(count) = stats.get("structs").and_then(|v| v.as_u64()) {
-                            struct_count += count;
-                        }
-                        if let Some(count) = stats.get("enums").and_then(|v| v.as_u64()) {
-                            enum_count += count;
-                        }
-                        if let Some(count) = stats.get("traits").and_then(|v| v.as_u64()) {
-                            trait_count += count;
-                        }
-                    }
-                } else if path.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
-                    // Recursively analyze subdirectories
-                    if let Ok(submetrics) = Box::pin(self.calculate_metrics(&path)).await {
-                        if let Some(count) = submetrics.get("file_count").and_then(|v| v.as_u64()) {
-                            file_count += count;
-                        }
-                        if let Some(lines) = submetrics.get("total_lines").and_then(|v| v.as_u64()) {
-                            total_lines += lines;
-                        }
-                        if let Some(lines) = submetrics.get("code_lines").and_then(|v| v.as_u64()) {
-                            code_lines += lines;
-                        }
-                        if let Some(lines) = submetrics.get("comment_lines").and_then(|v| v.as_u64()) {
-                            comment_lines += lines;
-                        }
-                        if let Some(lines) = submetrics.get("blank_lines").and_then(|v| v.as_u64()) {
-                            blank_lines += lines;
-                        }
-                        if let Some(count) = submetrics.get("functions").and_then(|v| v.as_u64()) {
-                            function_count += count;
-                        }
-                        if let Some(count) = submetrics.get("structs").and_then(|v| v.as_u64()) {
-                            struct_count += count;
-                        }
-                        if let Some(count) = submetrics.get("enums").and_then(|v| v.as_u64()) {
-                            enum_count += count;
-                        }
-                        if let Some(count) = submetrics.get("traits").and_then(|v| v.as_u64()) {
-                            trait_count += count;
-                        }
-                    }
-                }
+            for (name, value) in stats.as_object().into_iter().flatten() {
+                let total = totals.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+                totals.insert(name.clone(), json!(total + value.as_u64().unwrap_or(0)));
             }
         }
         
+        let count = |name: &str| totals.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        let (total_lines, code_lines) = (count("total_lines"), count("code_lines"));
         Ok(json!({
             "file_count": file_count,
             "total_lines": total_lines,
             "code_lines": code_lines,
-            "comment_lines": comment_lines,
-            "blank_lines": blank_lines,
+            "comment_lines": count("comment_lines"),
+            "blank_lines": count("blank_lines"),
             "code_percentage": if total_lines > 0 { 
                 format!("{:.1}%", (code_lines as f64 / total_lines as f64) * 100.0) 
             } else { 
                 "0.0%".to_string() 
             },
-            "functions": function_count,
-            "structs": struct_count,
-            "enums": enum_count,
-            "traits": trait_count
+            "functions": count("functions"),
+            "structs": count("structs"),
+            "enums": count("enums"),
+            "traits": count("traits"),
+            "impls": count("impls"),
+            "type_aliases": count("type_aliases")
         }))
     }
     
-    fn analyze_file_content(&self, content: &str) -> Value {
+    /// Line counts of a file and the items it declares, counted on the parsed
+    /// file. Its functions' complexity goes to `functions`; a file that does
+    /// not parse counts no items.
+    fn analyze_file_content(&self, content: &str, functions: &mut Vec<FunctionComplexity>) -> Value {
         let mut total_lines = 0;
         let mut code_lines = 0;
         let mut comment_lines = 0;
         let mut blank_lines = 0;
         let mut in_block_comment = false;
-        let mut function_count = 0;
-        let mut struct_count = 0;
-        let mut enum_count = 0;
-        let mut trait_count = 0;
         
         for line in content.lines() {
             total_lines += 1;
@@ -313,31 +702,24 @@ impl MetricsCommands {
                 blank_lines += 1;
             } else {
                 code_lines += 1;
-                
-                // Simple pattern matching for declarations
-                if trimmed.starts_with("fn ") || trimmed.starts_with("pub fn ") ||
-                   trimmed.starts_with("async fn ") || trimmed.starts_with("pub async fn ") {
-                    function_count += 1;
-                } else if trimmed.starts_with("struct ") || trimmed.starts_with("pub struct ") {
-                    struct_count += 1;
-                } else if trimmed.starts_with("enum ") || trimmed.starts_with("pub enum ") {
-                    enum_count += 1;
-                } else if trimmed.starts_with("trait ") || trimmed.starts_with("pub trait ") {
-                    trait_count += 1;
-                }
             }
         }
         
-        json!({
+        let items = complexity::analyze(content).unwrap_or_default();
+        let stats = json!({
             "total_lines": total_lines,
             "code_lines": code_lines,
             "comment_lines": comment_lines,
             "blank_lines": blank_lines,
-            "functions": function_count,
-            "structs": struct_count,
-            "enums": enum_count,
-            "traits": trait_count
-        })
+            "functions": items.functions.len(),
+            "structs": items.structs,
+            "enums": items.enums,
+            "traits": items.traits,
+            "impls": items.impls,
+            "type_aliases": items.type_aliases
+        });
+        functions.extend(items.functions);
+        stats
     }
     
     async fn find_dead_code(&self, analyzer: &RustAnalyzer) -> Result<Value> {
@@ -346,11 +728,11 @@ impl MetricsCommands {
         use tokio::process::Command;
         
         // Run cargo check with dead code detection
-        let output = Command::new("cargo")
-            .args(&["check", "--all-targets", "--message-format=json"])
+        let output = timings::measure(Phase::Cargo, Command::new("cargo")
+            .args(["check", "--all-targets", "--message-format=json"])
             .current_dir(analyzer.project_root())
             .env("RUSTFLAGS", "-W dead_code")
-            .output()
+            .output())
             .await;
             
         match output {
@@ -409,19 +791,19 @@ impl MetricsCommands {
             }
         }
         
-        let params: FileParams = serde_json::from_value(params_value)?;
+        let params: FileParams = param_validation::parse_params(params_value)?;
         
-        debug!("Suggesting improvements for {}", params.file);
+        debug!("Suggesting improvements for {:?}", params.file);
         
         use tokio::process::Command;
         
         let mut suggestions = Vec::new();
         
         // Run clippy for the project (clippy doesn't support file-specific analysis)
-        let clippy_output = Command::new("cargo")
-            .args(&["clippy", "--message-format=json"])
+        let clippy_output = timings::measure(Phase::Cargo, Command::new("cargo")
+            .args(["clippy", "--message-format=json"])
             .current_dir(analyzer.project_root())
-            .output()
+            .output())
             .await;
             
         match clippy_output {
@@ -436,7 +818,7 @@ impl MetricsCommands {
                                 if let Some(spans) = message.get("spans").and_then(|s| s.as_array()) {
                                     for span in spans {
                                         if let Some(file_name) = span.get("file_name").and_then(|f| f.as_str()) {
-                                            if file_name.contains(&params.file) || params.file.contains(file_name) {
+                                            if params.file.as_ref().is_none_or(|f| file_name.contains(f.as_str()) || f.contains(file_name)) {
                                                 suggestions.push(json!({
                                                     "type": "clippy",
                                                     "level": message.get("level").unwrap_or(&json!("suggestion")),
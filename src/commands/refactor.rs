@@ -1,9 +1,18 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use tracing::debug;
 
-use crate::analyzer::RustAnalyzer;
+use crate::analyzer::{skip_too_large, RustAnalyzer};
+use crate::code_actions;
+use crate::edit_check;
+use crate::extract_module::{self, Selection};
+use crate::param_validation;
+use crate::refactor;
+use crate::toggle_async;
+use crate::workspace_edit::{self, uri_to_path};
 use crate::server::CommandHandler;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,6 +21,9 @@ struct RenameParams {
     line: u32,
     column: u32,
     new_name: String,
+    /// Write the edits to disk; otherwise return the `WorkspaceEdit` for preview
+    #[serde(default = "default_true")]
+    apply: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,6 +48,81 @@ struct OrganizeImportsParams {
     file: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct ApplyAndCheckParams {
+    file: String,
+    /// LSP range: 0-based lines, UTF-16 characters
+    range: Value,
+    new_text: String,
+    #[serde(default)]
+    revert_on_error: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApplyChangeParams {
+    file: String,
+    /// LSP range: 0-based lines, UTF-16 characters
+    range: Value,
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncDocumentParams {
+    file: String,
+    /// The editor's document version; must increase with each sync
+    version: i32,
+    /// Full unsaved content of the file
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConvertControlFlowParams {
+    file: String,
+    line: u32,
+    column: u32,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+    assist: Option<String>,
+    kind: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExtractModuleParams {
+    file: String,
+    module_name: String,
+    items: Option<Vec<String>>,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    /// Create `module_name/mod.rs` instead of `module_name.rs`
+    #[serde(default)]
+    mod_rs: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ToggleAsyncParams {
+    file: String,
+    line: usize,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Code action kind asked for when the `kind` hint is an assist id or absent
+const REWRITE_KIND: &str = "refactor.rewrite";
+
+/// Kind and title of rust-analyzer's extract function assist
+const EXTRACT_KIND: &str = "refactor.extract";
+const EXTRACT_FUNCTION_TITLE: &str = "Extract into function";
+
+const ORGANIZE_IMPORTS_KIND: &str = "source.organizeImports";
+
 pub struct RefactorCommands;
 
 #[async_trait::async_trait]
@@ -52,6 +139,12 @@ impl CommandHandler for RefactorCommands {
             "extract_function" => self.extract_function(params, analyzer).await,
             "inline" => self.inline(params, analyzer).await,
             "organize_imports" => self.organize_imports(params, analyzer).await,
+            "apply_and_check" => self.apply_and_check(params, analyzer).await,
+            "apply_change" => self.apply_change(params, analyzer).await,
+            "sync_document" => self.sync_document(params, analyzer).await,
+            "convert_control_flow" => self.convert_control_flow(params, analyzer).await,
+            "extract_module" => self.extract_module(params, analyzer).await,
+            "toggle_async" => self.toggle_async(params, analyzer).await,
             _ => anyhow::bail!("Unknown refactor method: {}", method),
         }
     }
@@ -67,13 +160,35 @@ impl RefactorCommands {
             obj.remove("method");
         }
         
-        let params: RenameParams = serde_json::from_value(params_value)?;
+        let params: RenameParams = param_validation::parse_params(params_value)?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
         
         debug!("Renaming at {}:{}:{} to {}", params.file, params.line, params.column, params.new_name);
         
         // Use the LSP-based rename functionality
         let changes = analyzer.rename(&params.file, params.line, params.column, &params.new_name).await?;
         
+        if !params.apply {
+            return Ok(json!({
+                "file": params.file,
+                "position": {
+                    "line": params.line,
+                    "column": params.column
+                },
+                "new_name": params.new_name,
+                "applied": false,
+                "changes": changes
+            }));
+        }
+        
+        if changes.is_null() {
+            anyhow::bail!("Nothing to rename at {}:{}:{}", params.file, params.line, params.column);
+        }
+        let files = refactor::file_edits(&changes, analyzer.project_root());
+        let summary = refactor::apply_workspace_edit(&changes, analyzer.project_root()).await?;
+        
         Ok(json!({
             "file": params.file,
             "position": {
@@ -81,7 +196,10 @@ impl RefactorCommands {
                 "column": params.column
             },
             "new_name": params.new_name,
-            "changes": changes
+            "applied": true,
+            "files": files,
+            "edits_applied": summary.edits_applied,
+            "files_renamed": summary.files_renamed
         }))
     }
     
@@ -94,7 +212,10 @@ impl RefactorCommands {
             obj.remove("method");
         }
         
-        let params: ExtractFunctionParams = serde_json::from_value(params_value)?;
+        let params: ExtractFunctionParams = param_validation::parse_params(params_value)?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
         
         debug!("Extracting function {} from {}:{}:{} to {}:{}", 
             params.function_name, params.file, 
@@ -102,80 +223,79 @@ impl RefactorCommands {
             params.end_line, params.end_column
         );
         
-        // Try to use LSP code actions for extract function
-        if let Some(mut lsp_guard) = analyzer.get_lsp_client().await {
-            if let Some(client) = lsp_guard.as_mut() {
-            let full_path = if params.file.starts_with(&analyzer.project_root().to_string_lossy().to_string()) {
-                params.file.clone()
-            } else {
-                analyzer.project_root().join(&params.file).to_string_lossy().to_string()
-            };
-            
-            let _ = client.did_open(&full_path).await;
-            
-            let code_action_params = json!({
-                "textDocument": {
-                    "uri": format!("file://{}", full_path)
-                },
-                "range": {
-                    "start": {
-                        "line": params.start_line - 1,
-                        "character": params.start_column - 1
-                    },
-                    "end": {
-                        "line": params.end_line - 1,
-                        "character": params.end_column - 1
+        let range = json!({
+            "start": {
+                "line": params.start_line.saturating_sub(1),
+                "character": params.start_column.saturating_sub(1)
+            },
+            "end": {
+                "line": params.end_line.saturating_sub(1),
+                "character": params.end_column.saturating_sub(1)
+            }
+        });
+        let actions = analyzer.code_actions(&params.file, range, vec![EXTRACT_KIND.to_string()]).await?;
+        let action = code_actions::select_action(&actions, Some(EXTRACT_FUNCTION_TITLE), Some(EXTRACT_KIND))?;
+        let resolved = analyzer.resolve_code_action(action.clone()).await?;
+        let edit = resolved.get("edit")
+            .ok_or_else(|| anyhow::anyhow!("Assist \"{}\" has no edit", action["title"].as_str().unwrap_or("")))?;
+        let generated = refactor::new_function_name(edit)
+            .ok_or_else(|| anyhow::anyhow!("The extract edit defines no function"))?;
+        
+        let root = analyzer.project_root();
+        let mut summary = refactor::apply_workspace_edit(edit, root).await?;
+        
+        // rust-analyzer always names the function itself; rename it as asked
+        let mut function_name = generated.clone();
+        let mut rename_error = None;
+        if params.function_name != generated {
+            match self.rename_generated(analyzer, &summary.files_changed, &generated, &params.function_name).await {
+                Ok(renamed) => {
+                    for file in renamed.files_changed {
+                        if !summary.files_changed.contains(&file) {
+                            summary.files_changed.push(file);
+                        }
                     }
-                },
-                "context": {
-                    "diagnostics": [],
-                    "only": ["refactor.extract"]
-                }
-            });
-            
-            match client.code_action(code_action_params).await {
-                Ok(actions) => {
-                    return Ok(json!({
-                        "status": "lsp_code_actions_available",
-                        "function_name": params.function_name,
-                        "file": params.file,
-                        "range": {
-                            "start": {
-                                "line": params.start_line,
-                                "column": params.start_column
-                            },
-                            "end": {
-                                "line": params.end_line,
-                                "column": params.end_column
-                            }
-                        },
-                        "available_actions": actions
-                    }));
-                }
-                Err(e) => {
-                    debug!("LSP code action failed: {}", e);
+                    summary.edits_applied += renamed.edits_applied;
+                    function_name = params.function_name.clone();
                 }
+                Err(e) => rename_error = Some(format!("{:#}", e)),
             }
         }
-        }
         
-        // Fallback: Basic extract function implementation
-        Ok(json!({
-            "status": "basic_extract_function_placeholder",
-            "function_name": params.function_name,
+        let mut result = json!({
             "file": params.file,
             "range": {
-                "start": {
-                    "line": params.start_line,
-                    "column": params.start_column
-                },
-                "end": {
-                    "line": params.end_line,
-                    "column": params.end_column
-                }
+                "start": { "line": params.start_line, "column": params.start_column },
+                "end": { "line": params.end_line, "column": params.end_column }
             },
-            "note": "Full LSP integration would provide automatic code generation"
-        }))
+            "assist": code_actions::describe(action),
+            "function_name": function_name,
+            "generated_name": generated,
+            "files_changed": summary.files_changed,
+            "edits_applied": summary.edits_applied
+        });
+        if let Some(error) = rename_error {
+            result["rename_error"] = json!(error);
+        }
+        Ok(result)
+    }
+    
+    /// Rename the function `generated` that an extraction just wrote to one
+    /// of `files` (relative to the project root)
+    async fn rename_generated(&self, analyzer: &RustAnalyzer, files: &[String], generated: &str, name: &str) -> Result<workspace_edit::EditSummary> {
+        let declaration = format!("fn {}", generated);
+        for file in files {
+            let content = tokio::fs::read_to_string(analyzer.project_root().join(file)).await.unwrap_or_default();
+            let Some((line, column)) = content.lines().enumerate().find_map(|(i, text)| {
+                let at = text.find(&declaration)?;
+                let column = text[..at + 3].encode_utf16().count();
+                Some((i as u32 + 1, column as u32 + 1))
+            }) else { continue };
+            
+            let edit = analyzer.rename(file, line, column, name).await?;
+            return refactor::apply_workspace_edit(&edit, analyzer.project_root()).await;
+        }
+        anyhow::bail!("Could not find {} after the extraction", declaration)
     }
     
     async fn inline(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
@@ -187,7 +307,7 @@ impl RefactorCommands {
             obj.remove("method");
         }
         
-        let params: InlineParams = serde_json::from_value(params_value)?;
+        let params: InlineParams = param_validation::parse_params(params_value)?;
         
         debug!("Inlining at {}:{}:{}", params.file, params.line, params.column);
         
@@ -261,60 +381,286 @@ impl RefactorCommands {
             obj.remove("method");
         }
         
-        let params: OrganizeImportsParams = serde_json::from_value(params_value)?;
+        let params: OrganizeImportsParams = param_validation::parse_params(params_value)?;
         
         debug!("Organizing imports in {}", params.file);
         
-        // Try to use LSP code actions for organize imports
-        if let Some(mut lsp_guard) = analyzer.get_lsp_client().await {
-            if let Some(client) = lsp_guard.as_mut() {
-            let full_path = if params.file.starts_with(&analyzer.project_root().to_string_lossy().to_string()) {
-                params.file.clone()
-            } else {
-                analyzer.project_root().join(&params.file).to_string_lossy().to_string()
-            };
-            
-            let _ = client.did_open(&full_path).await;
-            
-            let code_action_params = json!({
-                "textDocument": {
-                    "uri": format!("file://{}", full_path)
-                },
-                "range": {
-                    "start": {
-                        "line": 0,
-                        "character": 0
+        let path = analyzer.project_root().join(&params.file);
+        let before = tokio::fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", params.file))?;
+        // The whole file, so the action is offered wherever the imports are
+        let range = json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": before.lines().count(), "character": 0 }
+        });
+        let actions = analyzer.code_actions(&params.file, range, vec![ORGANIZE_IMPORTS_KIND.to_string()]).await?;
+        let Some(action) = actions.iter().find(|action| code_actions::matches_kind(action, ORGANIZE_IMPORTS_KIND)) else {
+            return Ok(json!({ "status": "no_changes", "file": params.file }));
+        };
+        let resolved = analyzer.resolve_code_action(action.clone()).await?;
+        let Some(edit) = resolved.get("edit") else {
+            return Ok(json!({ "status": "no_changes", "file": params.file }));
+        };
+        
+        let summary = refactor::apply_workspace_edit(edit, analyzer.project_root()).await?;
+        if summary.edits_applied == 0 {
+            return Ok(json!({ "status": "no_changes", "file": params.file }));
+        }
+        let after = tokio::fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", params.file))?;
+        
+        Ok(json!({
+            "status": "applied",
+            "file": params.file,
+            "action": code_actions::describe(action),
+            "before": refactor::import_stats(&before),
+            "after": refactor::import_stats(&after),
+            "files_changed": summary.files_changed,
+            "edits_applied": summary.edits_applied
+        }))
+    }
+    
+    async fn apply_and_check(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ApplyAndCheckParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Applying edit to {} and running cargo check", params.file);
+        
+        let path = analyzer.project_root().join(&params.file);
+        let edit = json!({ "range": params.range, "newText": params.new_text });
+        let result = edit_check::apply_and_check(analyzer.project_root(), &path, &[edit], params.revert_on_error).await?;
+        
+        Ok(serde_json::to_value(result)?)
+    }
+    
+    async fn apply_change(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ApplyChangeParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        
+        debug!("Pushing incremental change to {}", params.file);
+        
+        let change = json!({ "range": params.range, "text": params.text });
+        let (version, incremental) = analyzer.apply_change(&params.file, vec![change]).await?;
+        
+        Ok(json!({
+            "file": params.file,
+            "version": version,
+            "incremental": incremental
+        }))
+    }
+    
+    async fn sync_document(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: SyncDocumentParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        
+        debug!("Syncing unsaved buffer of {} at version {}", params.file, params.version);
+        
+        let (version, applied) = analyzer.sync_document(&params.file, params.version, params.text).await?;
+        
+        Ok(json!({
+            "file": params.file,
+            "version": version,
+            "applied": applied
+        }))
+    }
+    
+    async fn convert_control_flow(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ConvertControlFlowParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        if let Some(too_large) = analyzer.file_too_large(&params.file) {
+            return Ok(too_large);
+        }
+        
+        debug!("Looking up rewrite assists at {}:{}:{}", params.file, params.line, params.column);
+        
+        let position = |line: u32, column: u32| json!({
+            "line": line.saturating_sub(1),
+            "character": column.saturating_sub(1)
+        });
+        let range = json!({
+            "start": position(params.line, params.column),
+            "end": position(params.end_line.unwrap_or(params.line), params.end_column.unwrap_or(params.column))
+        });
+        // A kind hint that is an LSP kind narrows the request itself; assist ids are matched afterwards
+        let only = match params.kind.as_deref() {
+            Some(kind) if kind.contains('.') || matches!(kind, "refactor" | "quickfix" | "source") => kind.to_string(),
+            _ => REWRITE_KIND.to_string(),
+        };
+        let actions = analyzer.code_actions(&params.file, range, vec![only]).await?;
+        
+        if params.assist.is_none() && params.kind.is_none() {
+            return Ok(json!({
+                "file": params.file,
+                "position": { "line": params.line, "column": params.column },
+                "assists": actions.iter().map(code_actions::describe).collect::<Vec<_>>()
+            }));
+        }
+        
+        let action = code_actions::select_action(&actions, params.assist.as_deref(), params.kind.as_deref())?;
+        let resolved = analyzer.resolve_code_action(action.clone()).await?;
+        let edit = resolved.get("edit")
+            .ok_or_else(|| anyhow::anyhow!("Assist \"{}\" has no edit", action["title"].as_str().unwrap_or("")))?;
+        
+        let diffs = code_actions::edit_diffs(edit, analyzer.project_root()).await?;
+        let summary = if params.dry_run {
+            None
+        } else {
+            Some(refactor::apply_workspace_edit(edit, analyzer.project_root()).await?)
+        };
+        
+        Ok(json!({
+            "file": params.file,
+            "position": { "line": params.line, "column": params.column },
+            "assist": code_actions::describe(action),
+            "applied": summary.is_some(),
+            "diff": diffs.iter().map(|d| d.diff.as_str()).collect::<String>(),
+            "files": diffs,
+            "edits_applied": summary.map(|s| s.edits_applied)
+        }))
+    }
+    
+    async fn extract_module(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ExtractModuleParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        let selection = match (params.items, params.start_line, params.end_line) {
+            (Some(items), None, None) => Selection::Names(items),
+            (None, Some(start), end) => Selection::Lines(start, end.unwrap_or(start)),
+            _ => anyhow::bail!("Pass either items or start_line (with an optional end_line)"),
+        };
+        
+        debug!("Extracting {:?} from {} into module {}", selection, params.file, params.module_name);
+        
+        let root = analyzer.project_root();
+        let path = root.join(&params.file);
+        let source = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        let parent = path.strip_prefix(root).unwrap_or(&path);
+        let plan = extract_module::plan(&source, parent, &selection, &params.module_name, params.mod_rs)?;
+        
+        // Either layout of the module would clash with the new file
+        for layout in [false, true] {
+            let existing = root.join(extract_module::new_module_path(parent, &params.module_name, layout));
+            if existing.exists() {
+                anyhow::bail!("Cannot create module {}: {} already exists", params.module_name, existing.display());
+            }
+        }
+        
+        let file = parent.display().to_string();
+        let new_file = plan.new_file.display().to_string();
+        let diffs = vec![
+            code_actions::FileDiff { diff: code_actions::unified_diff(&file, &source, &plan.parent_content), file },
+            code_actions::FileDiff { diff: code_actions::unified_diff(&new_file, "", &plan.new_content), file: new_file.clone() },
+        ];
+        let summary = if params.dry_run {
+            None
+        } else {
+            let edit = extract_module::workspace_edit(&plan, &path, &root.join(&plan.new_file));
+            Some(refactor::apply_workspace_edit(&edit, root).await?)
+        };
+        
+        Ok(json!({
+            "file": params.file,
+            "module": params.module_name,
+            "new_file": new_file,
+            "items": plan.items,
+            "visibility_changes": plan.visibility_changes,
+            "applied": summary.is_some(),
+            "diff": diffs.iter().map(|d| d.diff.as_str()).collect::<String>(),
+            "files": diffs,
+            "edits_applied": summary.map(|s| s.edits_applied)
+        }))
+    }
+    
+    async fn toggle_async(&self, params: Option<Value>, analyzer: &RustAnalyzer) -> Result<Value> {
+        let params: ToggleAsyncParams = param_validation::parse_params(
+            params.ok_or_else(|| anyhow::anyhow!("Missing parameters"))?
+        )?;
+        
+        debug!("Toggling async on the function at {}:{}", params.file, params.line);
+        
+        let root = analyzer.project_root();
+        let canonical = |path: PathBuf| path.canonicalize().unwrap_or(path);
+        let path = canonical(root.join(&params.file));
+        let source = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", params.file, e))?;
+        let function = toggle_async::function_at(&source, params.line)
+            .ok_or_else(|| anyhow::anyhow!("No function with a body at {}:{}", params.file, params.line))?;
+        let (line, column) = toggle_async::line_col(&source, function.name_offset);
+        
+        // rust-analyzer resolves calls through any path or alias; without it calls are matched by name
+        let mut sources = BTreeMap::from([(path.clone(), source)]);
+        let mut references = Vec::new();
+        let mut method = "references";
+        for location in skip_too_large(analyzer.find_references(&params.file, line as u32, column as u32).await)? {
+            let Some(uri) = location["uri"].as_str() else { continue };
+            let file = canonical(uri_to_path(uri));
+            if !sources.contains_key(&file) {
+                let Ok(text) = tokio::fs::read_to_string(&file).await else { continue };
+                sources.insert(file.clone(), text);
+            }
+            let start = &location["range"]["start"];
+            let offset = toggle_async::lsp_offset(
+                &sources[&file],
+                start["line"].as_u64().unwrap_or(0) as usize,
+                start["character"].as_u64().unwrap_or(0) as usize,
+            );
+            references.extend(offset.map(|offset| (file, offset)));
+        }
+        if references.is_empty() {
+            method = "text_search";
+            for (_, file) in analyzer.get_all_files() {
+                let file = canonical(file);
+                let text = match sources.get(&file) {
+                    Some(text) => text.clone(),
+                    None => match std::fs::read_to_string(&file) {
+                        Ok(text) => text,
+                        Err(_) => continue,
                     },
-                    "end": {
-                        "line": 0,
-                        "character": 0
-                    }
-                },
-                "context": {
-                    "diagnostics": [],
-                    "only": ["source.organizeImports"]
-                }
-            });
-            
-            match client.code_action(code_action_params).await {
-                Ok(actions) => {
-                    return Ok(json!({
-                        "status": "lsp_code_actions_available",
-                        "file": params.file,
-                        "available_actions": actions
-                    }));
-                }
-                Err(e) => {
-                    debug!("LSP code action failed: {}", e);
+                };
+                let calls = toggle_async::call_sites(&text, &function.name);
+                if !calls.is_empty() {
+                    references.extend(calls.into_iter().map(|offset| (file.clone(), offset)));
+                    sources.insert(file, text);
                 }
             }
         }
-        }
+        
+        let plan = toggle_async::plan(root, &path, &function, &sources, &references)?;
+        let diffs: Vec<code_actions::FileDiff> = plan.changes.iter()
+            .map(|(path, after)| {
+                let file = path.strip_prefix(root).unwrap_or(path).display().to_string();
+                let diff = code_actions::unified_diff(&file, &sources[path], after);
+                code_actions::FileDiff { file, diff }
+            })
+            .collect();
+        let summary = if params.dry_run {
+            None
+        } else {
+            Some(refactor::apply_workspace_edit(&toggle_async::workspace_edit(&plan), root).await?)
+        };
         
         Ok(json!({
-            "status": "organize_imports_placeholder",
             "file": params.file,
-            "note": "Full LSP integration would provide automatic import organization"
+            "function": plan.function,
+            "async": plan.now_async,
+            "unwrapped_future": plan.unwrapped_future,
+            "method": method,
+            "call_sites": plan.call_sites,
+            "applied": summary.is_some(),
+            "diff": diffs.iter().map(|d| d.diff.as_str()).collect::<String>(),
+            "files": diffs,
+            "edits_applied": summary.map(|s| s.edits_applied)
         }))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A completion list cut down to its most relevant items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionList {
+    /// Number of items rust-analyzer returned
+    pub total: usize,
+    pub truncated: bool,
+    /// Item count per kind over the full list, only reported when truncated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_kind: Option<BTreeMap<String, usize>>,
+    pub items: Vec<Value>,
+}
+
+/// Keep at most `limit` items (0 keeps all). A list over the limit is ranked
+/// by `sortText`, which rust-analyzer derives from relevance, falling back to
+/// the server's order; a list within it is returned untouched.
+pub fn summarize(items: Vec<Value>, limit: usize) -> CompletionList {
+    let total = items.len();
+    if limit == 0 || total <= limit {
+        return CompletionList { total, truncated: false, by_kind: None, items };
+    }
+
+    let mut by_kind = BTreeMap::new();
+    for item in &items {
+        *by_kind.entry(kind_name(item["kind"].as_u64().unwrap_or(0)).to_string()).or_insert(0) += 1;
+    }
+
+    let mut ranked = items;
+    // Stable, so equal or missing sort texts keep the server's order
+    ranked.sort_by(|a, b| match (a["sortText"].as_str(), b["sortText"].as_str()) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    ranked.truncate(limit);
+
+    CompletionList {
+        total,
+        truncated: true,
+        by_kind: Some(by_kind),
+        items: ranked,
+    }
+}
+
+/// Name of an LSP `CompletionItemKind`
+pub fn kind_name(kind: u64) -> &'static str {
+    match kind {
+        1 => "text",
+        2 => "method",
+        3 => "function",
+        4 => "constructor",
+        5 => "field",
+        6 => "variable",
+        7 => "class",
+        8 => "interface",
+        9 => "module",
+        10 => "property",
+        11 => "unit",
+        12 => "value",
+        13 => "enum",
+        14 => "keyword",
+        15 => "snippet",
+        16 => "color",
+        17 => "file",
+        18 => "reference",
+        19 => "folder",
+        20 => "enum_member",
+        21 => "constant",
+        22 => "struct",
+        23 => "event",
+        24 => "operator",
+        25 => "type_parameter",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use syn::visit::{self, Visit};
+
+/// Most complex functions `code_metrics` lists when the caller sets no limit
+pub const DEFAULT_TOP: usize = 10;
+
+/// Cyclomatic complexity of one function body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionComplexity {
+    /// `name`, or `Type::name` for methods and trait default methods
+    pub name: String,
+    /// Left empty for the caller to fill in
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub file: String,
+    /// 1-based line of the function's name
+    pub line: usize,
+    pub complexity: usize,
+}
+
+/// What a parsed file declares
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileItems {
+    /// Functions with a body, nested ones included, in source order
+    pub functions: Vec<FunctionComplexity>,
+    pub structs: usize,
+    pub enums: usize,
+    pub traits: usize,
+    pub impls: usize,
+    pub type_aliases: usize,
+}
+
+/// Parse `source`, count its items wherever they are nested and measure every
+/// function body in it. A function starts at one and gains one per `if`,
+/// `while`, `for`, match arm beyond the first, match guard, `&&`, `||` and
+/// `?`. Closures count towards the function holding them; macro bodies are
+/// not parsed and count for nothing.
+pub fn analyze(source: &str) -> Result<FileItems> {
+    let file = syn::parse_file(source).context("Failed to parse Rust source")?;
+    let mut collector = Collector::default();
+    collector.visit_file(&file);
+    collector.file.functions.sort_by_key(|f| f.line);
+    Ok(collector.file)
+}
+
+/// Average complexity of `functions`, to two decimals
+pub fn average(functions: &[FunctionComplexity]) -> f64 {
+    if functions.is_empty() {
+        return 0.0;
+    }
+    let total: usize = functions.iter().map(|f| f.complexity).sum();
+    (total as f64 / functions.len() as f64 * 100.0).round() / 100.0
+}
+
+/// The `limit` most complex of `functions`, ties in file and line order
+pub fn most_complex(functions: &[FunctionComplexity], limit: usize) -> Vec<FunctionComplexity> {
+    let mut ranked = functions.to_vec();
+    ranked.sort_by(|a, b| b.complexity.cmp(&a.complexity).then_with(|| a.file.cmp(&b.file)).then_with(|| a.line.cmp(&b.line)));
+    ranked.truncate(limit);
+    ranked
+}
+
+#[derive(Default)]
+struct Collector {
+    file: FileItems,
+    /// Type of the impl or trait being visited
+    owner: Option<String>,
+    /// Running complexity of the functions being visited, innermost last
+    open: Vec<usize>,
+}
+
+impl Collector {
+    fn add(&mut self, branches: usize) {
+        if let Some(complexity) = self.open.last_mut() {
+            *complexity += branches;
+        }
+    }
+
+    fn function(&mut self, ident: &syn::Ident, body: impl FnOnce(&mut Self)) {
+        let name = match &self.owner {
+            Some(owner) => format!("{}::{}", owner, ident),
+            None => ident.to_string(),
+        };
+        // Items nested in the body belong to no impl
+        let owner = self.owner.take();
+        self.open.push(1);
+        body(self);
+        let complexity = self.open.pop().unwrap_or(1);
+        self.owner = owner;
+        self.file.functions.push(FunctionComplexity { name, file: String::new(), line: ident.span().start().line, complexity });
+    }
+}
+
+impl<'ast> Visit<'ast> for Collector {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.function(&item.sig.ident, |this| visit::visit_item_fn(this, item));
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        self.function(&item.sig.ident, |this| visit::visit_impl_item_fn(this, item));
+    }
+
+    fn visit_trait_item_fn(&mut self, item: &'ast syn::TraitItemFn) {
+        // Declarations without a default body have nothing to measure
+        if item.default.is_some() {
+            self.function(&item.sig.ident, |this| visit::visit_trait_item_fn(this, item));
+        }
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        let owner = match &*item.self_ty {
+            syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+            _ => None,
+        };
+        self.file.impls += 1;
+        let outer = std::mem::replace(&mut self.owner, owner);
+        visit::visit_item_impl(self, item);
+        self.owner = outer;
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        self.file.traits += 1;
+        let outer = self.owner.replace(item.ident.to_string());
+        visit::visit_item_trait(self, item);
+        self.owner = outer;
+    }
+
+    fn visit_item_struct(&mut self, item: &'ast syn::ItemStruct) {
+        self.file.structs += 1;
+        visit::visit_item_struct(self, item);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast syn::ItemEnum) {
+        self.file.enums += 1;
+        visit::visit_item_enum(self, item);
+    }
+
+    fn visit_item_type(&mut self, item: &'ast syn::ItemType) {
+        self.file.type_aliases += 1;
+        visit::visit_item_type(self, item);
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast syn::ExprIf) {
+        self.add(1);
+        visit::visit_expr_if(self, expr);
+    }
+
+    fn visit_expr_match(&mut self, expr: &'ast syn::ExprMatch) {
+        let guards = expr.arms.iter().filter(|arm| arm.guard.is_some()).count();
+        self.add(expr.arms.len().saturating_sub(1) + guards);
+        visit::visit_expr_match(self, expr);
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+        self.add(1);
+        visit::visit_expr_while(self, expr);
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast syn::ExprForLoop) {
+        self.add(1);
+        visit::visit_expr_for_loop(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+        if matches!(expr.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            self.add(1);
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_try(&mut self, expr: &'ast syn::ExprTry) {
+        self.add(1);
+        visit::visit_expr_try(self, expr);
+    }
+}
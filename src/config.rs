@@ -0,0 +1,211 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::markup::ContentFormat;
+use crate::tool_access::ToolCategory;
+
+/// Name of the per-project configuration file, looked up in the project root
+pub const CONFIG_FILE_NAME: &str = ".mcp-analyzer.toml";
+
+/// Default for `max_lsp_file_size`: 2 MiB, well above hand-written sources
+pub const DEFAULT_MAX_LSP_FILE_SIZE: u64 = 2 * 1024 * 1024;
+
+/// Default for `max_run_seconds`, long enough to build and run a typical example
+pub const DEFAULT_MAX_RUN_SECONDS: u64 = 120;
+
+/// Default for `max_completions`: enough for the likely candidates without
+/// listing every method in scope
+pub const DEFAULT_MAX_COMPLETIONS: usize = 50;
+
+/// Default for `completion_doc_max_len`: a summary paragraph or two, where
+/// std items can carry pages of documentation each
+pub const DEFAULT_COMPLETION_DOC_MAX_LEN: usize = 500;
+
+/// Default for `max_read_bytes`: a few hundred KiB of source, well within
+/// what an agent can take in at once
+pub const DEFAULT_MAX_READ_BYTES: usize = 256 * 1024;
+
+const KNOWN_KEYS: &[&str] = &["rust_analyzer_path", "use_lsp", "features", "ignore", "content_format", "max_lsp_file_size", "max_run_seconds", "allowed_roots", "max_completions", "completion_doc_max_len", "tool_categories", "max_read_bytes", "timings", "lsp_passthrough"];
+
+/// Project-level configuration.
+///
+/// Values are resolved with the following precedence (highest first):
+/// 1. CLI flags
+/// 2. Environment variables (`RUST_ANALYZER_PATH`, `USE_LSP`, `MCP_TOOL_CATEGORIES`, `MCP_TIMINGS`,
+///    `MCP_LSP_PASSTHROUGH`)
+/// 3. `.mcp-analyzer.toml` in the project root
+/// 4. Built-in defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Path or command name of the rust-analyzer binary
+    pub rust_analyzer_path: String,
+    /// Whether to start rust-analyzer at all
+    pub use_lsp: bool,
+    /// Cargo features considered enabled for analysis
+    pub features: Vec<String>,
+    /// Gitignore-style globs, relative to the project root, of files left
+    /// out of the workspace file index
+    pub ignore: Vec<String>,
+    /// Default format of hover and completion documentation; also the
+    /// format preferred when negotiating with rust-analyzer
+    pub content_format: ContentFormat,
+    /// Files larger than this many bytes are not sent to rust-analyzer;
+    /// position-based commands report them as too large instead. 0 disables the cap.
+    pub max_lsp_file_size: u64,
+    /// Upper bound on how long `run_example` may build and run before the
+    /// process group is killed
+    pub max_run_seconds: u64,
+    /// Directories outside the project root that commands may read files
+    /// from, absolute or relative to the project root
+    pub allowed_roots: Vec<String>,
+    /// `complete` returns at most this many items, the most relevant first,
+    /// with a count by kind of the full list. 0 disables the cap.
+    pub max_completions: usize,
+    /// `complete` cuts each item's documentation down to about this many
+    /// characters, keeping whole paragraphs while they fit. 0 disables the cap.
+    pub completion_doc_max_len: usize,
+    /// Kinds of tools the server offers: `read_only`, `write` and `execute`.
+    /// Tools in any other category are neither listed nor run.
+    pub tool_categories: Vec<ToolCategory>,
+    /// `read_files` returns at most this many bytes of content per call,
+    /// flagging the files it cut short. 0 disables the cap.
+    pub max_read_bytes: usize,
+    /// Add `_meta.timings` to results: milliseconds spent starting and
+    /// querying rust-analyzer, running cargo and scanning files
+    pub timings: bool,
+    /// Offer `lsp_passthrough`, which forwards any LSP request to
+    /// rust-analyzer without the checks dedicated commands make
+    pub lsp_passthrough: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rust_analyzer_path: "rust-analyzer".to_string(),
+            use_lsp: true,
+            features: Vec::new(),
+            ignore: Vec::new(),
+            content_format: ContentFormat::default(),
+            max_lsp_file_size: DEFAULT_MAX_LSP_FILE_SIZE,
+            max_run_seconds: DEFAULT_MAX_RUN_SECONDS,
+            allowed_roots: Vec::new(),
+            max_completions: DEFAULT_MAX_COMPLETIONS,
+            completion_doc_max_len: DEFAULT_COMPLETION_DOC_MAX_LEN,
+            tool_categories: ToolCategory::ALL.to_vec(),
+            max_read_bytes: DEFAULT_MAX_READ_BYTES,
+            timings: false,
+            lsp_passthrough: false,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file from the project root (if any) and apply
+    /// environment variable overrides on top of it.
+    pub fn resolve(project_root: &Path) -> Result<Self> {
+        let mut config = Self::load_file(project_root)?;
+        config.apply_env();
+        Ok(config)
+    }
+
+    /// Load `.mcp-analyzer.toml` from the project root, falling back to
+    /// defaults when the file does not exist. Unknown keys are warned about.
+    pub fn load_file(project_root: &Path) -> Result<Self> {
+        let path = project_root.join(CONFIG_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config = Self::parse(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        info!("Loaded project config from {}", path.display());
+        Ok(config)
+    }
+
+    /// Parse config file contents, warning about keys we don't recognize
+    pub fn parse(content: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(content)?;
+        for key in table.keys() {
+            if !KNOWN_KEYS.contains(&key.as_str()) {
+                warn!("Unknown key '{}' in {} will be ignored", key, CONFIG_FILE_NAME);
+            }
+        }
+
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+
+    /// Override file values with environment variables
+    pub fn apply_env(&mut self) {
+        if let Ok(path) = std::env::var("RUST_ANALYZER_PATH") {
+            self.rust_analyzer_path = path;
+        }
+        if let Ok(value) = std::env::var("USE_LSP") {
+            self.use_lsp = value == "true";
+        }
+        if let Ok(value) = std::env::var("MCP_TIMINGS") {
+            self.timings = value == "true";
+        }
+        if let Ok(value) = std::env::var("MCP_LSP_PASSTHROUGH") {
+            self.lsp_passthrough = value == "true";
+        }
+        if let Ok(value) = std::env::var("MCP_TOOL_CATEGORIES") {
+            match ToolCategory::parse_list(&value) {
+                Ok(categories) => self.tool_categories = categories,
+                Err(e) => warn!("Ignoring MCP_TOOL_CATEGORIES: {}", e),
+            }
+        }
+    }
+
+    /// Override file and environment values with CLI flags
+    pub fn apply_overrides(&mut self, overrides: &Overrides) {
+        if let Some(path) = &overrides.rust_analyzer_path {
+            self.rust_analyzer_path = path.clone();
+        }
+        if let Some(categories) = &overrides.tool_categories {
+            self.tool_categories = categories.clone();
+        }
+        if overrides.timings {
+            self.timings = true;
+        }
+        if overrides.lsp_passthrough {
+            self.lsp_passthrough = true;
+        }
+    }
+}
+
+/// Settings given as CLI flags, which take precedence over everything else
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub rust_analyzer_path: Option<String>,
+    pub tool_categories: Option<Vec<ToolCategory>>,
+    pub timings: bool,
+    pub lsp_passthrough: bool,
+}
+
+impl Overrides {
+    /// The environment variables `Config::apply_env` reads these settings
+    /// from, for handing them to a spawned daemon
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = Vec::new();
+        if let Some(path) = &self.rust_analyzer_path {
+            vars.push(("RUST_ANALYZER_PATH", path.clone()));
+        }
+        if let Some(categories) = &self.tool_categories {
+            let names: Vec<&str> = categories.iter().map(|c| c.name()).collect();
+            vars.push(("MCP_TOOL_CATEGORIES", names.join(",")));
+        }
+        if self.timings {
+            vars.push(("MCP_TIMINGS", "true".to_string()));
+        }
+        if self.lsp_passthrough {
+            vars.push(("MCP_LSP_PASSTHROUGH", "true".to_string()));
+        }
+        vars
+    }
+}
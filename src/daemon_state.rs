@@ -4,9 +4,15 @@ use std::collections::HashMap;
 use std::fs;
 use std::net::TcpListener;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{debug, info, warn};
 
+/// How long a daemon gets to exit after SIGTERM before it is sent SIGKILL
+pub const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pause between checks whether a process has exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonInfo {
     pub port: u16,
@@ -15,7 +21,13 @@ pub struct DaemonInfo {
     pub started_at: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy)]
+enum Signal {
+    Term,
+    Kill,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct DaemonState {
     daemons: HashMap<String, DaemonInfo>,
 }
@@ -133,12 +145,18 @@ impl DaemonState {
         debug!("Loaded state with {} registered daemons", state.daemons.len());
         
         if let Some(daemon_info) = state.daemons.get(&normalized_path) {
-            // Verify the daemon is still running
-            if Self::is_daemon_running(daemon_info.port) {
+            // Verify the daemon is still running; a recorded process that is
+            // gone settles it without a request
+            let exited = daemon_info.pid.is_some_and(|pid| !Self::is_process_alive(pid));
+            if !exited && Self::is_daemon_running(daemon_info.port) {
                 debug!("Found running daemon for {} on port {}", normalized_path, daemon_info.port);
                 return Ok(Some(daemon_info.clone()));
             } else {
-                warn!("Daemon registered for {} on port {} is not responding", normalized_path, daemon_info.port);
+                if exited {
+                    warn!("Daemon registered for {} on port {} has exited", normalized_path, daemon_info.port);
+                } else {
+                    warn!("Daemon registered for {} on port {} is not responding", normalized_path, daemon_info.port);
+                }
                 // Clean up dead daemon
                 let mut state = state;
                 state.daemons.remove(&normalized_path);
@@ -151,6 +169,116 @@ impl DaemonState {
         Ok(None)
     }
 
+    /// The daemon registered for the current directory, whether or not it
+    /// is still running
+    pub fn registered_daemon_for_current_dir() -> Result<Option<DaemonInfo>> {
+        let current_dir = std::env::current_dir()?;
+        let normalized_path = current_dir.canonicalize()?.to_string_lossy().to_string();
+        Ok(Self::load()?.daemons.get(&normalized_path).cloned())
+    }
+
+    /// Kill the process of the daemon registered for the current directory:
+    /// SIGTERM, then SIGKILL if it is still there after `KILL_TIMEOUT`. For a
+    /// daemon that didn't answer `POST /shutdown`. Whether it was signalled;
+    /// the daemon is only unregistered then. Without a recorded PID, or when
+    /// it no longer belongs to a daemon, nothing is signalled and the
+    /// registration is kept.
+    pub fn kill_daemon_for_current_dir() -> Result<bool> {
+        let Some(daemon_info) = Self::registered_daemon_for_current_dir()? else {
+            return Ok(false);
+        };
+        match daemon_info.pid {
+            Some(pid) if Self::is_daemon_process(pid) => Self::terminate(pid, KILL_TIMEOUT)?,
+            Some(pid) => {
+                warn!("Process {} is not a running daemon, nothing to kill", pid);
+                return Ok(false);
+            }
+            None => {
+                warn!("No PID recorded for the daemon on port {}, it can't be killed", daemon_info.port);
+                return Ok(false);
+            }
+        }
+        Self::unregister_daemon_for_current_dir()?;
+        Ok(true)
+    }
+
+    /// Send `pid` SIGTERM, and SIGKILL if it hasn't exited within `timeout`
+    pub fn terminate(pid: u32, timeout: Duration) -> Result<()> {
+        Self::signal(pid, Signal::Term)?;
+        if Self::wait_for_exit(pid, timeout) {
+            return Ok(());
+        }
+        warn!("Process {} ignored SIGTERM for {:?}, sending SIGKILL", pid, timeout);
+        Self::signal(pid, Signal::Kill)?;
+        if !Self::wait_for_exit(pid, timeout) {
+            anyhow::bail!("Process {} is still running after SIGKILL", pid);
+        }
+        Ok(())
+    }
+
+    /// Wait up to `timeout` for `pid` to exit; whether it did
+    pub fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Self::is_process_alive(pid) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(EXIT_POLL_INTERVAL);
+        }
+        true
+    }
+
+    /// Whether a process with this PID exists
+    #[cfg(unix)]
+    pub fn is_process_alive(pid: u32) -> bool {
+        let Ok(pid) = libc::pid_t::try_from(pid) else { return false };
+        // Signal 0 only checks; EPERM means it exists under another user
+        let found = unsafe { libc::kill(pid, 0) } == 0;
+        found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    /// Without a way to probe processes, the HTTP check alone decides
+    #[cfg(not(unix))]
+    pub fn is_process_alive(_pid: u32) -> bool {
+        true
+    }
+
+    /// Whether `pid` is a running daemon rather than an unrelated process
+    /// that was given the PID after the daemon exited. Where `/proc` is
+    /// available the command line must hold the `--server` flag daemons are
+    /// started with.
+    fn is_daemon_process(pid: u32) -> bool {
+        if !Self::is_process_alive(pid) {
+            return false;
+        }
+        match fs::read(format!("/proc/{}/cmdline", pid)) {
+            Ok(cmdline) => cmdline.split(|b| *b == 0).any(|arg| arg == b"--server"),
+            Err(_) => !Path::new("/proc/self").exists(),
+        }
+    }
+
+    #[cfg(unix)]
+    fn signal(pid: u32, signal: Signal) -> Result<()> {
+        let pid = libc::pid_t::try_from(pid).context("PID out of range")?;
+        let signal = match signal {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+        };
+        if unsafe { libc::kill(pid, signal) } != 0 {
+            let error = std::io::Error::last_os_error();
+            // Exiting between the check and the signal is fine
+            if error.raw_os_error() != Some(libc::ESRCH) {
+                return Err(error).context(format!("Failed to signal process {}", pid));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn signal(pid: u32, _signal: Signal) -> Result<()> {
+        anyhow::bail!("Killing process {} is only supported on Unix", pid)
+    }
+
     /// Check if daemon is running by trying to connect
     fn is_daemon_running(port: u16) -> bool {
         let client = reqwest::blocking::Client::builder()
@@ -158,7 +286,7 @@ impl DaemonState {
             .build()
             .unwrap();
             
-        match client.get(&format!("http://localhost:{}/", port)).send() {
+        match client.get(format!("http://localhost:{}/", port)).send() {
             Ok(response) => response.status().is_success(),
             Err(_) => false,
         }
@@ -203,7 +331,8 @@ impl DaemonState {
         let mut dead_daemons = Vec::new();
         
         for (path, daemon_info) in &self.daemons {
-            if !Self::is_daemon_running(daemon_info.port) {
+            let exited = daemon_info.pid.is_some_and(|pid| !Self::is_process_alive(pid));
+            if exited || !Self::is_daemon_running(daemon_info.port) {
                 dead_daemons.push(path.clone());
             }
         }
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::source_files;
+
+/// Derives provided by the standard library
+pub const STD_DERIVES: &[&str] = &[
+    "Debug", "Clone", "Copy", "PartialEq", "Eq", "PartialOrd", "Ord", "Hash", "Default",
+];
+
+/// How often one derive is used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeriveCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// A type declared with `#[derive]` but without `Debug`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeLocation {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Derive tallies across a source tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeriveUsage {
+    pub files_scanned: usize,
+    pub derived_types: usize,
+    pub std: Vec<DeriveCount>,
+    pub custom: Vec<DeriveCount>,
+    pub missing_debug: Vec<TypeLocation>,
+}
+
+/// One `#[derive(...)]` attribute and the type it applies to
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeriveSite {
+    /// Derive names with any path prefix removed (`serde::Serialize` -> `Serialize`)
+    pub derives: Vec<String>,
+    pub type_name: Option<String>,
+    /// 1-based line of the attribute
+    pub line: usize,
+}
+
+/// Tally derives in every `.rs` file under `dir`
+pub fn derive_usage(dir: &Path, project_root: &Path) -> DeriveUsage {
+    let mut usage = DeriveUsage::default();
+    let mut std_counts: HashMap<String, usize> = HashMap::new();
+    let mut custom_counts: HashMap<String, usize> = HashMap::new();
+
+    for file in source_files::rust_files(dir) {
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        usage.files_scanned += 1;
+        let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+
+        // A type may carry several derive attributes; merge them per type
+        let mut per_type: Vec<DeriveSite> = Vec::new();
+        for site in derive_sites(&content) {
+            match per_type.last_mut() {
+                Some(last) if last.type_name.is_some() && last.type_name == site.type_name => {
+                    last.derives.extend(site.derives);
+                }
+                _ => per_type.push(site),
+            }
+        }
+
+        for site in per_type {
+            usage.derived_types += 1;
+            for derive in &site.derives {
+                let counts = if STD_DERIVES.contains(&derive.as_str()) { &mut std_counts } else { &mut custom_counts };
+                *counts.entry(derive.clone()).or_default() += 1;
+            }
+
+            if let Some(name) = site.type_name {
+                let manual_debug = content.contains(&format!("Debug for {}", name));
+                if !site.derives.iter().any(|d| d == "Debug") && !manual_debug {
+                    usage.missing_debug.push(TypeLocation { name, file: display.clone(), line: site.line });
+                }
+            }
+        }
+    }
+
+    usage.std = sorted_counts(std_counts);
+    usage.custom = sorted_counts(custom_counts);
+    usage
+}
+
+fn sorted_counts(counts: HashMap<String, usize>) -> Vec<DeriveCount> {
+    let mut counts: Vec<DeriveCount> = counts.into_iter()
+        .map(|(name, count)| DeriveCount { name, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    counts
+}
+
+/// Find every `#[derive(...)]` in `source`, including ones split over several lines
+pub fn derive_sites(source: &str) -> Vec<DeriveSite> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut sites = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+        let Some(rest) = trimmed.strip_prefix("#[derive(") else {
+            i += 1;
+            continue;
+        };
+
+        let start = i;
+        let mut text = rest.to_string();
+        while !text.contains(")]") && i + 1 < lines.len() {
+            i += 1;
+            text.push_str(lines[i].split("//").next().unwrap_or("").trim());
+        }
+        let inner = text.split(")]").next().unwrap_or("");
+        let derives = inner.split(',')
+            .map(|d| d.trim())
+            .filter(|d| !d.is_empty())
+            .map(|d| d.rsplit("::").next().unwrap_or(d).to_string())
+            .collect();
+
+        sites.push(DeriveSite {
+            derives,
+            type_name: following_type_name(&lines[i + 1..]),
+            line: start + 1,
+        });
+        i += 1;
+    }
+
+    sites
+}
+
+/// Name of the struct/enum/union declared after a run of attributes and doc comments
+fn following_type_name(lines: &[&str]) -> Option<String> {
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with("#[") || trimmed.starts_with("//") {
+            continue;
+        }
+        let words: Vec<&str> = trimmed.split_whitespace().collect();
+        let keyword = words.iter().position(|w| matches!(*w, "struct" | "enum" | "union"))?;
+        let name = words.get(keyword + 1)?;
+        let name: String = name.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        return (!name.is_empty()).then_some(name);
+    }
+    None
+}
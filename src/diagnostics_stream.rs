@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Frames buffered between cargo and a slow consumer before cargo is paused
+pub const STREAM_BUFFER: usize = 32;
+
+/// Turn one `compiler-message` from `cargo check --message-format=json` into
+/// a diagnostic per primary-or-secondary span
+pub fn cargo_message_diagnostics(json_msg: &Value) -> Vec<Value> {
+    if json_msg.get("reason") != Some(&json!("compiler-message")) {
+        return Vec::new();
+    }
+    let Some(message) = json_msg.get("message") else { return Vec::new() };
+    let Some(spans) = message.get("spans").and_then(|s| s.as_array()) else { return Vec::new() };
+
+    spans.iter()
+        .filter_map(|span| {
+            let file_name = span.get("file_name").and_then(|f| f.as_str())?;
+            Some(json!({
+                "file": file_name,
+                "line": span.get("line_start"),
+                "column": span.get("column_start"),
+                "level": message.get("level").unwrap_or(&json!("error")),
+                "message": message.get("message").unwrap_or(&json!("")),
+                "code": message.get("code"),
+                "source": "cargo"
+            }))
+        })
+        .collect()
+}
+
+/// A running `cargo check` whose diagnostics are delivered through a bounded
+/// channel. When the consumer stops reading, the channel fills, the reader
+/// stops draining cargo's stdout and cargo blocks on its pipe, so memory use
+/// stays bounded regardless of how much output the build produces.
+pub struct DiagnosticsStream {
+    child: Child,
+    rx: mpsc::Receiver<Value>,
+}
+
+impl DiagnosticsStream {
+    /// Spawn `cargo check` in `project_root`. Frames are
+    /// `{"type": "diagnostic", ...}` followed by one `{"type": "summary", ...}`.
+    pub fn start(project_root: &Path, buffer: usize) -> Result<Self> {
+        let mut child = Command::new("cargo")
+            .args(["check", "--message-format=json"])
+            .current_dir(project_root)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to spawn cargo check")?;
+
+        let stdout = child.stdout.take().context("cargo check has no stdout")?;
+        let (tx, rx) = mpsc::channel(buffer.max(1));
+
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let mut lines = BufReader::new(stdout).lines();
+            let mut errors = 0usize;
+            let mut warnings = 0usize;
+            let mut success = None;
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Ok(msg) = serde_json::from_str::<Value>(&line) else { continue };
+                if msg.get("reason") == Some(&json!("build-finished")) {
+                    success = msg.get("success").and_then(|s| s.as_bool());
+                }
+                let diagnostics = cargo_message_diagnostics(&msg);
+                // One message can span several places; it's still one error or warning
+                if !diagnostics.is_empty() {
+                    match msg["message"]["level"].as_str() {
+                        Some("error") => errors += 1,
+                        Some("warning") => warnings += 1,
+                        _ => {}
+                    }
+                }
+                for mut diagnostic in diagnostics {
+                    diagnostic["type"] = json!("diagnostic");
+                    // Waits while the channel is full; fails once the consumer is gone
+                    if tx.send(diagnostic).await.is_err() {
+                        debug!("Diagnostics consumer went away, stopping reader");
+                        return;
+                    }
+                }
+            }
+
+            let _ = tx.send(json!({
+                "type": "summary",
+                "errors": errors,
+                "warnings": warnings,
+                "success": success.unwrap_or(false),
+                "duration_ms": started.elapsed().as_millis() as u64
+            })).await;
+        });
+
+        Ok(Self { child, rx })
+    }
+
+    /// Next frame, or `None` once the summary has been delivered
+    pub async fn next(&mut self) -> Option<Value> {
+        self.rx.recv().await
+    }
+
+    /// Stop the build, e.g. because the client disconnected mid-stream
+    pub async fn cancel(&mut self) {
+        self.rx.close();
+        if let Err(e) = self.child.kill().await {
+            debug!("Failed to kill cargo check: {}", e);
+        }
+    }
+
+    /// Whether the cargo process is still running
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
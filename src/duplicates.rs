@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use crate::source_files;
+
+/// Tokens per shingle when comparing function bodies
+const SHINGLE_SIZE: usize = 5;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while",
+];
+
+/// A function whose body takes part in a duplicate group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionLocation {
+    pub name: String,
+    pub file: String,
+    /// 1-based lines of the `fn` keyword and the closing brace
+    pub line: usize,
+    pub end_line: usize,
+    /// Normalized tokens in the body
+    pub tokens: usize,
+}
+
+/// Functions whose bodies are identical or similar once identifiers and
+/// literals are normalized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Whether every body in the group normalizes to the same tokens
+    pub identical: bool,
+    /// Lowest pairwise similarity that linked the group, 0.0 to 1.0
+    pub similarity: f64,
+    pub functions: Vec<FunctionLocation>,
+}
+
+/// Duplicate groups across a source tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DuplicateReport {
+    pub files_scanned: usize,
+    pub functions_compared: usize,
+    pub groups: Vec<DuplicateGroup>,
+}
+
+/// One function body reduced to normalized tokens
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionBody {
+    pub name: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub tokens: Vec<String>,
+}
+
+struct Candidate {
+    location: FunctionLocation,
+    hash: u64,
+    shingles: HashSet<u64>,
+}
+
+/// Group functions under `dir` whose bodies are at least `threshold` similar
+/// (Jaccard similarity of token shingles). Bodies shorter than `min_tokens`
+/// are skipped so trivial getters do not drown out real copies.
+pub fn find_duplicates(dir: &Path, project_root: &Path, threshold: f64, min_tokens: usize) -> DuplicateReport {
+    let mut report = DuplicateReport::default();
+    let mut candidates = Vec::new();
+
+    for file in source_files::rust_files(dir) {
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        report.files_scanned += 1;
+        let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+
+        for body in function_bodies(&content) {
+            if body.tokens.len() < min_tokens {
+                continue;
+            }
+            candidates.push(Candidate {
+                hash: hash(&body.tokens),
+                shingles: shingles(&body.tokens),
+                location: FunctionLocation {
+                    name: body.name,
+                    file: display.clone(),
+                    line: body.line,
+                    end_line: body.end_line,
+                    tokens: body.tokens.len(),
+                },
+            });
+        }
+    }
+    report.functions_compared = candidates.len();
+
+    // Similarity can only reach the threshold between sets of comparable size,
+    // so after sorting by size each candidate is compared with a short window
+    candidates.sort_by_key(|c| c.shingles.len());
+    let mut groups = UnionFind::new(candidates.len());
+    let mut weakest_link: HashMap<(usize, usize), f64> = HashMap::new();
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            let (a, b) = (&candidates[i], &candidates[j]);
+            if (a.shingles.len() as f64) < threshold * b.shingles.len() as f64 {
+                break;
+            }
+            let similarity = if a.hash == b.hash { 1.0 } else { jaccard(&a.shingles, &b.shingles) };
+            if similarity >= threshold {
+                weakest_link.insert((i, j), similarity);
+                groups.union(i, j);
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..candidates.len() {
+        members.entry(groups.find(i)).or_default().push(i);
+    }
+    for (_, indices) in members {
+        if indices.len() < 2 {
+            continue;
+        }
+        let root = groups.find(indices[0]);
+        let similarity = weakest_link.iter()
+            .filter(|((i, _), _)| groups.find(*i) == root)
+            .map(|(_, s)| *s)
+            .fold(1.0, f64::min);
+        let mut functions: Vec<FunctionLocation> = indices.iter().map(|&i| candidates[i].location.clone()).collect();
+        functions.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        report.groups.push(DuplicateGroup {
+            identical: indices.iter().all(|&i| candidates[i].hash == candidates[indices[0]].hash),
+            similarity,
+            functions,
+        });
+    }
+
+    // Largest copies first: they are the most worthwhile to merge
+    report.groups.sort_by(|a, b| {
+        b.functions[0].tokens.cmp(&a.functions[0].tokens)
+            .then_with(|| a.functions[0].file.cmp(&b.functions[0].file))
+            .then_with(|| a.functions[0].line.cmp(&b.functions[0].line))
+    });
+    report
+}
+
+/// Every function with a body in `source`, with identifiers, lifetimes and
+/// literals normalized away and comments and whitespace dropped
+pub fn function_bodies(source: &str) -> Vec<FunctionBody> {
+    let tokens = tokenize(source);
+    let mut bodies = Vec::new();
+
+    for (i, (token, line)) in tokens.iter().enumerate() {
+        if token != "fn" {
+            continue;
+        }
+        let Some((name, _)) = tokens.get(i + 1) else { continue };
+        if !is_identifier(name) {
+            continue;
+        }
+        // Trait method declarations end in `;` before any body
+        let Some(open) = tokens[i + 2..].iter().position(|(t, _)| t == "{" || t == ";").map(|p| p + i + 2) else { continue };
+        if tokens[open].0 == ";" {
+            continue;
+        }
+
+        let mut depth = 0;
+        let mut close = None;
+        for (j, (t, _)) in tokens.iter().enumerate().skip(open) {
+            match t.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(j);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let Some(close) = close else { continue };
+
+        bodies.push(FunctionBody {
+            name: name.clone(),
+            line: *line,
+            end_line: tokens[close].1,
+            tokens: tokens[open + 1..close].iter().map(|(t, _)| normalize(t)).collect(),
+        });
+    }
+    bodies
+}
+
+fn normalize(token: &str) -> String {
+    if KEYWORDS.contains(&token) {
+        token.to_string()
+    } else if token.starts_with(|c: char| c.is_ascii_digit() || c == '"' || c == '\'') {
+        "LIT".to_string()
+    } else if is_identifier(token) {
+        "ID".to_string()
+    } else {
+        token.to_string()
+    }
+}
+
+fn is_identifier(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_alphabetic() || c == '_')
+}
+
+/// Split `source` into tokens with their 1-based line, skipping comments and
+/// keeping string and char literals whole
+fn tokenize(source: &str) -> Vec<(String, usize)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' {
+            let start = (i, line);
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&'\n') {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((chars[start.0..i.min(chars.len())].iter().collect(), start.1));
+        } else if c == '\'' {
+            // Char literal ('a', '\n') or lifetime ('a)
+            let end = match (next, chars.get(i + 2)) {
+                (Some('\\'), _) => chars.get(i + 3..)
+                    .and_then(|rest| rest.iter().position(|&c| c == '\''))
+                    .map(|p| i + 4 + p),
+                (Some(_), Some('\'')) => Some(i + 3),
+                _ => None,
+            };
+            match end {
+                Some(end) => {
+                    tokens.push((chars[i..end].iter().collect(), line));
+                    i = end;
+                }
+                None => {
+                    let start = i;
+                    i += 1;
+                    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                        i += 1;
+                    }
+                    // Lifetimes are names, so they normalize like identifiers
+                    tokens.push((chars[start + 1..i].iter().collect(), line));
+                }
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((chars[start..i].iter().collect(), line));
+        } else {
+            tokens.push((c.to_string(), line));
+            i += 1;
+        }
+    }
+    tokens
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn shingles(tokens: &[String]) -> HashSet<u64> {
+    if tokens.len() < SHINGLE_SIZE {
+        return std::iter::once(hash(tokens)).collect();
+    }
+    tokens.windows(SHINGLE_SIZE).map(hash).collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 { 1.0 } else { shared as f64 / total as f64 }
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self { parent: (0..len).collect() }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        if self.parent[i] != i {
+            let root = self.find(self.parent[i]);
+            self.parent[i] = root;
+        }
+        self.parent[i]
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a] = b;
+        }
+    }
+}
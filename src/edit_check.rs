@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::diagnostics_stream;
+use crate::timings::{self, Phase};
+use crate::workspace_edit;
+
+/// Outcome of a single `cargo check` run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckOutcome {
+    pub success: bool,
+    pub diagnostics: Vec<Value>,
+}
+
+/// Result of trying an edit against the compiler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditCheck {
+    pub file: String,
+    /// Whether the crate still compiles with the edit in place
+    pub success: bool,
+    /// Whether the crate compiled before the edit
+    pub compiled_before: bool,
+    /// Diagnostics that were not reported before the edit
+    pub new_diagnostics: Vec<Value>,
+    /// Whether the original content was restored
+    pub reverted: bool,
+    /// Manifest the check was scoped to
+    pub manifest: String,
+}
+
+/// Apply `edits` (LSP `TextEdit`s) to `file`, check the owning package and
+/// optionally restore the original content when it no longer compiles
+pub async fn apply_and_check(project_root: &Path, file: &Path, edits: &[Value], revert_on_error: bool) -> Result<EditCheck> {
+    let original = tokio::fs::read_to_string(file).await
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let edited = workspace_edit::apply_edits_to_text(&original, edits)?;
+    let manifest = owning_manifest(file, project_root);
+
+    let before = cargo_check(&manifest).await?;
+
+    tokio::fs::write(file, &edited).await
+        .with_context(|| format!("Failed to write {}", file.display()))?;
+    let after = match cargo_check(&manifest).await {
+        Ok(after) => after,
+        Err(e) => {
+            // Never leave an unchecked edit behind when the check itself failed to run
+            tokio::fs::write(file, &original).await?;
+            return Err(e);
+        }
+    };
+
+    let reverted = !after.success && revert_on_error;
+    if reverted {
+        tokio::fs::write(file, &original).await
+            .with_context(|| format!("Failed to restore {}", file.display()))?;
+    }
+
+    Ok(EditCheck {
+        file: file.strip_prefix(project_root).unwrap_or(file).display().to_string(),
+        success: after.success,
+        compiled_before: before.success,
+        new_diagnostics: new_diagnostics(&before.diagnostics, after.diagnostics),
+        reverted,
+        manifest: manifest.strip_prefix(project_root).unwrap_or(&manifest).display().to_string(),
+    })
+}
+
+/// Run `cargo check` for the package described by `manifest`
+pub async fn cargo_check(manifest: &Path) -> Result<CheckOutcome> {
+    let output = timings::measure(Phase::Cargo, Command::new("cargo")
+        .args(["check", "--message-format=json", "--manifest-path"])
+        .arg(manifest)
+        .output())
+        .await
+        .context("Failed to run cargo check")?;
+
+    let diagnostics = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .flat_map(|msg| diagnostics_stream::cargo_message_diagnostics(&msg))
+        .collect();
+
+    Ok(CheckOutcome { success: output.status.success(), diagnostics })
+}
+
+/// Nearest `Cargo.toml` above `file`, so a workspace only checks the edited member
+pub fn owning_manifest(file: &Path, project_root: &Path) -> PathBuf {
+    let mut dir = file.parent();
+    while let Some(current) = dir {
+        let manifest = current.join("Cargo.toml");
+        if manifest.exists() {
+            return manifest;
+        }
+        if current == project_root {
+            break;
+        }
+        dir = current.parent();
+    }
+    project_root.join("Cargo.toml")
+}
+
+/// Diagnostics in `after` that have no counterpart in `before`.
+///
+/// Lines shift when code is edited, so diagnostics are matched on file,
+/// level, code and message only, counting duplicates.
+pub fn new_diagnostics(before: &[Value], after: Vec<Value>) -> Vec<Value> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for diagnostic in before {
+        *seen.entry(diagnostic_key(diagnostic)).or_default() += 1;
+    }
+
+    after.into_iter()
+        .filter(|diagnostic| match seen.get_mut(&diagnostic_key(diagnostic)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+fn diagnostic_key(diagnostic: &Value) -> String {
+    let field = |name: &str| diagnostic.get(name).map(|v| v.to_string()).unwrap_or_default();
+    format!("{}|{}|{}|{}", field("file"), field("level"), field("code"), field("message"))
+}
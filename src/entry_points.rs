@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::file_summary::{self, SummaryItem};
+use crate::timings::{self, Phase};
+
+/// Target kinds that are entry points of a package; examples, tests and
+/// benches are only reported on request
+const ROOT_KINDS: &[&str] = &["lib", "rlib", "dylib", "cdylib", "staticlib", "proc-macro", "bin"];
+
+/// One crate root: a library, binary or other cargo target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryPoint {
+    pub package: String,
+    pub name: String,
+    /// `lib`, `bin`, `proc-macro`, `example`, ...
+    pub kind: String,
+    /// Root file relative to the project root
+    pub file: String,
+    /// 1-based line of `fn main` for executables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_line: Option<usize>,
+    /// Public top-level items of the root file, without their methods
+    pub public_items: Vec<SummaryItem>,
+    /// Non-public top-level items by kind
+    pub private_counts: BTreeMap<String, usize>,
+}
+
+/// A target as declared by cargo, before its root file is read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub package: String,
+    pub name: String,
+    pub kind: String,
+    pub src_path: PathBuf,
+}
+
+/// Entry points of every workspace package. Falls back to the conventional
+/// `src/lib.rs`, `src/main.rs` and `src/bin/*.rs` layout when `cargo metadata` fails.
+pub async fn entry_points(project_root: &Path, all_targets: bool) -> Result<Vec<EntryPoint>> {
+    let targets = match metadata_targets(project_root).await {
+        Ok(targets) => targets,
+        Err(e) => {
+            debug!("Using conventional layout for entry points: {}", e);
+            conventional_targets(project_root)
+        }
+    };
+
+    let root = project_root.canonicalize().unwrap_or_else(|_| project_root.to_path_buf());
+    let mut entries = Vec::new();
+    for target in targets {
+        if !all_targets && !ROOT_KINDS.contains(&target.kind.as_str()) {
+            continue;
+        }
+        let source = tokio::fs::read_to_string(&target.src_path).await.unwrap_or_default();
+        entries.push(describe(&root, target, &source));
+    }
+    Ok(entries)
+}
+
+/// Summarize a target's root file
+pub fn describe(project_root: &Path, target: Target, source: &str) -> EntryPoint {
+    let summary = file_summary::summarize(source);
+    let executable = matches!(target.kind.as_str(), "bin" | "example" | "test" | "bench" | "custom-build");
+    let file = target.src_path.strip_prefix(project_root).unwrap_or(&target.src_path).display().to_string();
+
+    EntryPoint {
+        package: target.package,
+        name: target.name,
+        kind: target.kind,
+        file,
+        main_line: if executable { main_line(source) } else { None },
+        public_items: summary.public_items.into_iter()
+            .map(|item| SummaryItem { methods: Vec::new(), ..item })
+            .collect(),
+        private_counts: summary.private_counts,
+    }
+}
+
+/// Targets of every workspace member from `cargo metadata`
+async fn metadata_targets(project_root: &Path) -> Result<Vec<Target>> {
+    let output = timings::measure(Phase::Cargo, Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(project_root)
+        .output())
+        .await
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        anyhow::bail!("cargo metadata failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let metadata: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(targets_from_metadata(&metadata))
+}
+
+/// Flatten the `packages[].targets[]` of a `cargo metadata` document
+pub fn targets_from_metadata(metadata: &Value) -> Vec<Target> {
+    let empty = Vec::new();
+    let packages = metadata.get("packages").and_then(|p| p.as_array()).unwrap_or(&empty);
+
+    packages.iter()
+        .flat_map(|package| {
+            let package_name = package["name"].as_str().unwrap_or("").to_string();
+            package["targets"].as_array().unwrap_or(&empty).iter().filter_map(move |target| {
+                Some(Target {
+                    package: package_name.clone(),
+                    name: target["name"].as_str()?.to_string(),
+                    kind: target["kind"].as_array()?.first()?.as_str()?.to_string(),
+                    src_path: PathBuf::from(target["src_path"].as_str()?),
+                })
+            })
+        })
+        .collect()
+}
+
+fn conventional_targets(project_root: &Path) -> Vec<Target> {
+    let package = project_root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let target = |name: &str, kind: &str, src_path: PathBuf| Target {
+        package: package.clone(),
+        name: name.to_string(),
+        kind: kind.to_string(),
+        src_path,
+    };
+
+    let src = project_root.join("src");
+    let mut targets = Vec::new();
+    if src.join("lib.rs").exists() {
+        targets.push(target(&package, "lib", src.join("lib.rs")));
+    }
+    if src.join("main.rs").exists() {
+        targets.push(target(&package, "bin", src.join("main.rs")));
+    }
+    let mut bins: Vec<PathBuf> = std::fs::read_dir(src.join("bin"))
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    bins.sort();
+    for path in bins {
+        let root = if path.is_dir() { path.join("main.rs") } else { path.clone() };
+        if root.extension().is_some_and(|ext| ext == "rs") && root.exists() {
+            let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            targets.push(target(&name, "bin", root));
+        }
+    }
+    targets
+}
+
+fn main_line(source: &str) -> Option<usize> {
+    source.lines().position(|line| {
+        let line = line.trim_start();
+        let line = line.strip_prefix("pub ").unwrap_or(line);
+        let line = line.strip_prefix("async ").unwrap_or(line);
+        line.starts_with("fn main(") || line.starts_with("fn main (")
+    }).map(|index| index + 1)
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::edit_check;
+use crate::reexports::module_path_for;
+use crate::source_files;
+use crate::toggle_async::{self, code_mask, line_col, return_type};
+
+/// Calls that panic on failure, by how they are reported
+const PANIC_SITES: &[(&str, &str)] = &[(".unwrap()", "unwrap"), (".expect(", "expect"), ("panic!", "panic")];
+
+/// How one function deals with failure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionErrorHandling {
+    pub name: String,
+    /// 1-based line of the signature
+    pub line: usize,
+    /// `Result` or `Option` when the function returns one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub returns: Option<String>,
+    /// `unwrap`, `expect` and `panic!` calls in its body, nested functions excluded
+    pub panics: usize,
+}
+
+/// Error handling tallies of one module file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModuleErrorHandling {
+    /// e.g. `crate::commands::analysis`; the file path for files outside a crate's `src`
+    pub module: String,
+    pub file: String,
+    pub functions: usize,
+    pub returning_result: usize,
+    pub returning_option: usize,
+    /// Functions with at least one panic site
+    pub panicking: usize,
+    /// Functions returning `Result` or `Option` that also panic
+    pub mixed: usize,
+    /// Panic sites by kind: `unwrap`, `expect`, `panic`
+    pub panic_sites: BTreeMap<String, usize>,
+    /// `result` when most functions that pick a strategy return `Result` or
+    /// `Option` without panicking, `panic` when most panic, `none` when no
+    /// function does either
+    pub dominant: String,
+    /// Some functions propagate failures while others panic
+    pub inconsistent: bool,
+    /// Functions going against the dominant strategy, when inconsistent
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub outliers: Vec<FunctionErrorHandling>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorHandlingReport {
+    pub files_scanned: usize,
+    pub functions: usize,
+    pub inconsistent_modules: usize,
+    /// Modules with at least one function, inconsistent ones first
+    pub modules: Vec<ModuleErrorHandling>,
+}
+
+/// Tally error handling strategies per module for the `.rs` files under
+/// `dir`. Test modules and `tests/` directories are skipped unless
+/// `include_tests` is set.
+pub fn report(project_root: &Path, dir: &Path, include_tests: bool) -> ErrorHandlingReport {
+    let mut report = ErrorHandlingReport::default();
+    for file in source_files::rust_files(dir) {
+        let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+        if !include_tests && Path::new(&display).starts_with("tests") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        report.files_scanned += 1;
+
+        let (functions, panic_sites) = scan_source(&content, include_tests);
+        if functions.is_empty() {
+            continue;
+        }
+        let manifest = edit_check::owning_manifest(&file, project_root);
+        let src = manifest.parent().unwrap_or(project_root).join("src");
+        let module = module_path_for(&src, &file).map_or_else(|| display.clone(), |path| path.join("::"));
+        report.functions += functions.len();
+        report.modules.push(summarize(module, display, functions, panic_sites));
+    }
+    report.inconsistent_modules = report.modules.iter().filter(|m| m.inconsistent).count();
+    report.modules.sort_by(|a, b| b.inconsistent.cmp(&a.inconsistent).then_with(|| a.file.cmp(&b.file)));
+    report
+}
+
+/// Error handling of each function of `source`, and the panic sites inside
+/// functions by kind. Everything from the first `#[cfg(test)]` line on is
+/// skipped unless `include_tests` is set.
+pub fn scan_source(source: &str, include_tests: bool) -> (Vec<FunctionErrorHandling>, BTreeMap<String, usize>) {
+    let source = match source.lines().position(|l| l.trim().starts_with("#[cfg(test)]")).filter(|_| !include_tests) {
+        Some(line) => &source[..source.split_inclusive('\n').take(line).map(str::len).sum::<usize>()],
+        None => source,
+    };
+    let mask = code_mask(source);
+    let spans = toggle_async::functions(source);
+    let mut functions: Vec<FunctionErrorHandling> = spans.iter()
+        .map(|span| FunctionErrorHandling {
+            name: span.name.clone(),
+            line: line_col(source, span.start).0,
+            returns: return_type(source, &mask, span).and_then(|(_, _, ty)| fallible_type(&ty)),
+            panics: 0,
+        })
+        .collect();
+
+    let mut by_kind = BTreeMap::new();
+    for (at, kind) in panic_sites(source, &mask) {
+        // The innermost function holding the call
+        let owner = spans.iter().enumerate()
+            .filter(|(_, span)| span.body.0 < at && at < span.body.1)
+            .max_by_key(|(_, span)| span.body.0);
+        if let Some((index, _)) = owner {
+            functions[index].panics += 1;
+            *by_kind.entry(kind.to_string()).or_default() += 1;
+        }
+    }
+    (functions, by_kind)
+}
+
+/// Offsets and kinds of the `unwrap`, `expect` and `panic!` calls in code
+fn panic_sites(source: &str, mask: &[bool]) -> Vec<(usize, &'static str)> {
+    let mut sites: Vec<(usize, &'static str)> = PANIC_SITES.iter()
+        .flat_map(|(pattern, kind)| source.match_indices(pattern).map(move |(at, _)| (at, *kind)))
+        .filter(|&(at, kind)| {
+            let before = source[..at].chars().next_back();
+            mask[at] && !(kind == "panic" && before.is_some_and(|c| c.is_alphanumeric() || c == '_'))
+        })
+        .collect();
+    sites.sort();
+    sites
+}
+
+/// `Result` or `Option` when a return type is one, aliases such as
+/// `io::Result<T>` and `anyhow::Result<T>` included
+fn fallible_type(ty: &str) -> Option<String> {
+    let path = ty.split('<').next().unwrap_or(ty).trim();
+    let name = path.rsplit("::").next().unwrap_or(path);
+    matches!(name, "Result" | "Option").then(|| name.to_string())
+}
+
+fn summarize(module: String, file: String, functions: Vec<FunctionErrorHandling>, panic_sites: BTreeMap<String, usize>) -> ModuleErrorHandling {
+    let mut summary = ModuleErrorHandling { module, file, functions: functions.len(), panic_sites, ..ModuleErrorHandling::default() };
+    for function in &functions {
+        match function.returns.as_deref() {
+            Some("Result") => summary.returning_result += 1,
+            Some(_) => summary.returning_option += 1,
+            None => {}
+        }
+        if function.panics > 0 {
+            summary.panicking += 1;
+            if function.returns.is_some() {
+                summary.mixed += 1;
+            }
+        }
+    }
+
+    let propagating = summary.returning_result + summary.returning_option - summary.mixed;
+    summary.dominant = match (propagating, summary.panicking) {
+        (0, 0) => "none",
+        (propagating, panicking) if propagating >= panicking => "result",
+        _ => "panic",
+    }.to_string();
+    summary.inconsistent = propagating > 0 && summary.panicking > 0;
+    if summary.inconsistent {
+        let outlier = |f: &FunctionErrorHandling| match summary.dominant.as_str() {
+            "result" => f.panics > 0,
+            _ => f.returns.is_some() && f.panics == 0,
+        };
+        summary.outliers = functions.iter().filter(|f| outlier(f)).cloned().collect();
+    }
+    summary
+}
@@ -0,0 +1,382 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::module_graph;
+use crate::reexports;
+use crate::signature::find_matching;
+use crate::visibility::{self, Visibility};
+
+/// A top-level item and the lines it spans, attributes and doc comments included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopLevelItem {
+    /// Kind as reported by `visibility`, plus `impl`
+    pub kind: String,
+    /// For impls, the name of the implementing type
+    pub name: String,
+    /// 1-based line of the declaration itself
+    pub line: usize,
+    /// 0-based first line, including leading attributes and doc comments
+    #[serde(skip)]
+    pub start: usize,
+    /// 0-based last line
+    #[serde(skip)]
+    pub end: usize,
+}
+
+/// Which items of a file to move
+#[derive(Debug, Clone)]
+pub enum Selection {
+    Names(Vec<String>),
+    /// 1-based, inclusive; items overlapping the range are taken
+    Lines(usize, usize),
+}
+
+/// A visibility widened so the parent module keeps access after the move
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VisibilityChange {
+    /// `Type`, `Type::method` or `Type.field`
+    pub item: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// The new module's file and both files' contents after extraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractPlan {
+    pub new_file: PathBuf,
+    pub new_content: String,
+    pub parent_content: String,
+    pub items: Vec<TopLevelItem>,
+    pub visibility_changes: Vec<VisibilityChange>,
+}
+
+/// Where the file of module `name` goes when declared in `parent`: next to
+/// `lib.rs`, `main.rs` and `mod.rs`, under a directory named after any other file
+pub fn new_module_path(parent: &Path, name: &str, mod_rs: bool) -> PathBuf {
+    let dir = match parent.file_name().and_then(|f| f.to_str()) {
+        Some("lib.rs" | "main.rs" | "mod.rs") => parent.parent().map(Path::to_path_buf).unwrap_or_default(),
+        _ => parent.with_extension(""),
+    };
+    if mod_rs {
+        dir.join(name).join("mod.rs")
+    } else {
+        dir.join(format!("{}.rs", name))
+    }
+}
+
+/// Top-level items of `source`, in order. `use` declarations and macro
+/// invocations are skipped.
+pub fn top_level_items(source: &str) -> Vec<TopLevelItem> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut items = Vec::new();
+    let mut prelude: Option<usize> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+        if trimmed.is_empty() {
+            prelude = None;
+            i += 1;
+            continue;
+        }
+        if trimmed.starts_with("///") || trimmed.starts_with("#[") {
+            prelude.get_or_insert(i);
+            i = if trimmed.starts_with('#') { statement_end(&lines, i) } else { i } + 1;
+            continue;
+        }
+        if trimmed.starts_with("//") {
+            i += 1;
+            continue;
+        }
+
+        let end = statement_end(&lines, i);
+        let item = match visibility::parse_item(trimmed) {
+            Some((_, kind, name)) => Some((kind.to_string(), name)),
+            None => impl_self_type(trimmed).map(|name| ("impl".to_string(), name)),
+        };
+        if let Some((kind, name)) = item {
+            items.push(TopLevelItem { kind, name, line: i + 1, start: prelude.unwrap_or(i), end });
+        }
+        prelude = None;
+        i = end + 1;
+    }
+    items
+}
+
+/// Plan moving the selected items of `source` (the file `parent`, relative
+/// to the project root) into a new child module `name`
+pub fn plan(source: &str, parent: &Path, selection: &Selection, name: &str, mod_rs: bool) -> Result<ExtractPlan> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') || name.starts_with(|c: char| c.is_ascii_digit()) {
+        anyhow::bail!("Invalid module name: {}", name);
+    }
+    let all = top_level_items(source);
+    if all.iter().any(|item| item.kind == "module" && item.name == name) {
+        anyhow::bail!("Module {} is already declared in {}", name, parent.display());
+    }
+
+    let movable = |item: &&TopLevelItem| item.kind != "module";
+    let items: Vec<TopLevelItem> = match selection {
+        Selection::Names(names) => {
+            let available = || all.iter().filter(movable).filter(|i| i.kind != "impl").map(|i| i.name.as_str()).collect::<Vec<_>>().join(", ");
+            for wanted in names {
+                if !all.iter().filter(movable).any(|item| item.kind != "impl" && item.name == *wanted) {
+                    anyhow::bail!("No item named {} in {}; available: {}", wanted, parent.display(), available());
+                }
+            }
+            // Impls follow the types they implement
+            all.iter().filter(movable).filter(|item| names.contains(&item.name)).cloned().collect()
+        }
+        Selection::Lines(start, end) => all.iter()
+            .filter(movable)
+            .filter(|item| item.start < *end && item.end + 1 >= *start)
+            .cloned()
+            .collect(),
+    };
+    if items.is_empty() {
+        anyhow::bail!("No items selected in {}", parent.display());
+    }
+
+    let lines: Vec<&str> = source.lines().collect();
+    let mut visibility_changes = Vec::new();
+    let moved: Vec<String> = items.iter()
+        .map(|item| widen(&lines[item.start..=item.end], item, &mut visibility_changes).join("\n"))
+        .collect();
+    let mut body = moved.join("\n\n");
+    body.push('\n');
+
+    // The remaining file, with the module declared where the first item was
+    let mut remaining: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(item) = items.iter().find(|item| item.start == i) else {
+            remaining.push(lines[i].to_string());
+            i += 1;
+            continue;
+        };
+        if item.start == items[0].start {
+            remaining.extend(declaration(&items, name, &lines));
+        }
+        i = item.end + 1;
+        let after_blank = remaining.last().is_none_or(|l| l.trim().is_empty());
+        if after_blank && lines.get(i).is_some_and(|l| l.trim().is_empty()) {
+            i += 1;
+        }
+    }
+    let mut parent_content = remaining.join("\n");
+    if source.ends_with('\n') {
+        parent_content.push('\n');
+    }
+
+    let moved_names: Vec<&str> = items.iter().map(|item| item.name.as_str()).chain([name]).collect();
+    let new_content = if uses_parent_names(&body, &parent_content, &moved_names) {
+        format!("use super::*;\n\n{}", body)
+    } else {
+        body
+    };
+
+    Ok(ExtractPlan {
+        new_file: new_module_path(parent, name, mod_rs),
+        new_content,
+        parent_content,
+        items,
+        visibility_changes,
+    })
+}
+
+/// `WorkspaceEdit` creating `new_file` with its content and rewriting `parent`
+pub fn workspace_edit(plan: &ExtractPlan, parent: &Path, new_file: &Path) -> Value {
+    let uri = |path: &Path| format!("file://{}", path.display());
+    // Positions past the end of a document clamp to its end
+    let whole = json!({
+        "start": { "line": 0, "character": 0 },
+        "end": { "line": u32::MAX, "character": 0 }
+    });
+    json!({
+        "documentChanges": [
+            { "kind": "create", "uri": uri(new_file) },
+            {
+                "textDocument": { "uri": uri(new_file), "version": null },
+                "edits": [{ "range": whole, "newText": plan.new_content }]
+            },
+            {
+                "textDocument": { "uri": uri(parent), "version": null },
+                "edits": [{ "range": whole, "newText": plan.parent_content }]
+            }
+        ]
+    })
+}
+
+/// `mod name;` and the imports keeping moved items reachable under their old paths
+fn declaration(items: &[TopLevelItem], name: &str, lines: &[&str]) -> Vec<String> {
+    let mut declaration = Vec::new();
+    if items.iter().any(|item| item.kind == "macro") {
+        declaration.push("#[macro_use]".to_string());
+    }
+    declaration.push(format!("mod {};", name));
+
+    // Private items are only imported when the rest of the file still uses them
+    let rest: String = lines.iter().enumerate()
+        .filter(|(i, _)| !items.iter().any(|item| (item.start..=item.end).contains(i)))
+        .map(|(_, l)| format!("{}\n", l))
+        .collect();
+    let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+    for item in items.iter().filter(|item| !matches!(item.kind.as_str(), "impl" | "macro")) {
+        let (visibility, _) = Visibility::parse(lines[item.line - 1]);
+        let prefix = match visibility {
+            Visibility::Private if !mentions(&rest, &item.name) => continue,
+            Visibility::Private => String::new(),
+            visibility => format!("{} ", visibility.as_str()),
+        };
+        match groups.iter_mut().find(|(p, _)| *p == prefix) {
+            Some((_, names)) => names.push(&item.name),
+            None => groups.push((prefix, vec![&item.name])),
+        }
+    }
+    for (prefix, names) in groups {
+        declaration.push(match names.as_slice() {
+            [single] => format!("{}use {}::{};", prefix, name, single),
+            _ => format!("{}use {}::{{{}}};", prefix, name, names.join(", ")),
+        });
+    }
+    declaration
+}
+
+/// Lines of a moved item with the visibilities the parent relies on widened:
+/// the item itself, methods of inherent impls and named struct fields become
+/// `pub(super)` when private, and `pub(super)` becomes `pub(in super::super)`
+fn widen(lines: &[&str], item: &TopLevelItem, changes: &mut Vec<VisibilityChange>) -> Vec<String> {
+    let declaration = item.line - 1 - item.start;
+    let inherent_impl = item.kind == "impl" && !lines[declaration].contains(" for ");
+    let has_fields = matches!(item.kind.as_str(), "struct" | "union");
+
+    let mut out = Vec::with_capacity(lines.len());
+    let mut depth = 0i32;
+    for (i, line) in lines.iter().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        let trimmed = code.trim_start();
+        let member = depth == 1 && !trimmed.starts_with('#') && !trimmed.is_empty();
+
+        let target = if i == declaration && !matches!(item.kind.as_str(), "impl" | "macro") {
+            Some(item.name.clone())
+        } else if member && inherent_impl {
+            visibility::parse_item(trimmed)
+                .filter(|(_, kind, _)| *kind == "function")
+                .map(|(_, _, method)| format!("{}::{}", item.name, method))
+        } else if member && has_fields {
+            field_name(trimmed).map(|field| format!("{}.{}", item.name, field))
+        } else {
+            None
+        };
+
+        match target.and_then(|target| widened(line, target)) {
+            Some((text, change)) => {
+                out.push(text);
+                changes.push(change);
+            }
+            None => out.push(line.to_string()),
+        }
+        for c in code.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+fn widened(line: &str, item: String) -> Option<(String, VisibilityChange)> {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let (visibility, rest) = Visibility::parse(line);
+    let to = match visibility {
+        Visibility::Private => "pub(super)",
+        Visibility::Super => "pub(in super::super)",
+        _ => return None,
+    };
+    let from = visibility.as_str();
+    Some((format!("{}{} {}", indent, to, rest), VisibilityChange { item, from, to: to.to_string() }))
+}
+
+/// `name` of a `name: Type,` struct field line
+fn field_name(line: &str) -> Option<&str> {
+    let (name, _) = line.split_once(':')?;
+    let name = name.trim();
+    (!name.is_empty() && !line[name.len()..].trim_start().starts_with("::")
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')).then_some(name)
+}
+
+/// Implementing type of an `impl` header, e.g. `Foo` for `impl<T> Display for Foo<T>`
+pub(crate) fn impl_self_type(line: &str) -> Option<String> {
+    let rest = line.strip_prefix("unsafe ").unwrap_or(line).strip_prefix("impl")?;
+    let rest = if rest.starts_with('<') {
+        &rest[find_matching(rest, '<', '>')? + 1..]
+    } else if rest.starts_with(char::is_whitespace) {
+        rest
+    } else {
+        return None;
+    };
+    let self_type = rest.split_once(" for ").map_or(rest, |(_, ty)| ty).trim().trim_start_matches('&');
+    let self_type = self_type.strip_prefix("dyn ").unwrap_or(self_type).trim_start();
+    let path = self_type.split(|c: char| c == '<' || c == '{' || c.is_whitespace()).next().unwrap_or("");
+    let name = path.rsplit("::").next().unwrap_or("");
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// 0-based last line of the item, statement or attribute starting at
+/// `start`: where its braces or attribute brackets close, or its terminating
+/// `;` outside any brackets
+fn statement_end(lines: &[&str], start: usize) -> usize {
+    let attribute = lines[start].trim_start().starts_with("#[");
+    let mut braces = 0i32;
+    let mut brackets = 0i32;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let code = line.split("//").next().unwrap_or("");
+        for c in code.chars() {
+            match c {
+                '{' => braces += 1,
+                '}' => {
+                    braces -= 1;
+                    if braces == 0 && brackets == 0 {
+                        return i;
+                    }
+                }
+                '(' | '[' => brackets += 1,
+                ')' | ']' => {
+                    brackets -= 1;
+                    if attribute && braces == 0 && brackets == 0 {
+                        return i;
+                    }
+                }
+                ';' if braces == 0 && brackets == 0 => return i,
+                _ => {}
+            }
+        }
+    }
+    lines.len().saturating_sub(1)
+}
+
+/// Whether the moved code refers to anything the parent declares or imports,
+/// other than the moved items themselves
+fn uses_parent_names(moved: &str, parent: &str, moved_names: &[&str]) -> bool {
+    let mut names: Vec<String> = top_level_items(parent).into_iter()
+        .filter(|item| item.kind != "impl")
+        .map(|item| item.name)
+        .collect();
+    for (_, statement) in module_graph::use_statements(parent) {
+        for leaf in reexports::parse_use_tree(&statement) {
+            names.extend(leaf.alias.or_else(|| leaf.path.last().cloned()));
+        }
+    }
+    names.iter().any(|name| name != "self" && !moved_names.contains(&name.as_str()) && mentions(moved, name))
+}
+
+/// Whether `text` contains `name` as a whole identifier
+fn mentions(text: &str, name: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(name).any(|(at, _)| {
+        !text[..at].ends_with(is_ident) && !text[at + name.len()..].starts_with(is_ident)
+    })
+}
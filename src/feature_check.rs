@@ -0,0 +1,170 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tracing::debug;
+
+use crate::diagnostics_stream::cargo_message_diagnostics;
+use crate::health;
+use crate::timings::{self, Phase};
+
+/// Errors kept per failing combination
+const MAX_ERRORS_PER_COMBINATION: usize = 10;
+
+/// One set of feature flags to pass to `cargo check`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureCombination {
+    pub label: String,
+    pub no_default_features: bool,
+    pub all_features: bool,
+    pub features: Vec<String>,
+}
+
+impl FeatureCombination {
+    fn cargo_args(&self) -> Vec<String> {
+        let mut args = vec!["check".to_string(), "--message-format=json".to_string()];
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        args
+    }
+}
+
+/// Outcome of checking one combination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CombinationResult {
+    #[serde(flatten)]
+    pub combination: FeatureCombination,
+    pub success: bool,
+    pub errors: Vec<Value>,
+}
+
+/// Features declared by the root package, excluding `default`
+pub async fn package_features(project_root: &Path) -> Result<Vec<String>> {
+    let output = timings::measure(Phase::Cargo, Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(project_root)
+        .output())
+        .await
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        anyhow::bail!("cargo metadata failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let metadata: Value = serde_json::from_slice(&output.stdout)?;
+    let mut features: Vec<String> = health::root_package(&metadata, project_root)
+        .and_then(|p| p.get("features"))
+        .and_then(|f| f.as_object())
+        .map(|f| f.keys().filter(|k| *k != "default").cloned().collect())
+        .unwrap_or_default();
+    features.sort();
+    Ok(features)
+}
+
+/// The default plan: no default features, each feature alone, then all features
+pub fn default_combinations(features: &[String]) -> Vec<FeatureCombination> {
+    let mut combinations = vec![FeatureCombination {
+        label: "no-default-features".to_string(),
+        no_default_features: true,
+        all_features: false,
+        features: Vec::new(),
+    }];
+
+    for feature in features {
+        combinations.push(FeatureCombination {
+            label: format!("only {}", feature),
+            no_default_features: true,
+            all_features: false,
+            features: vec![feature.clone()],
+        });
+    }
+
+    combinations.push(FeatureCombination {
+        label: "all-features".to_string(),
+        no_default_features: false,
+        all_features: true,
+        features: Vec::new(),
+    });
+    combinations
+}
+
+/// Run `cargo check` for each combination, at most `concurrency` at a time.
+/// Every run gets its own target directory under `target/feature-check/` so
+/// parallel builds don't block on cargo's build directory lock.
+pub async fn check_combinations(
+    project_root: &Path,
+    combinations: Vec<FeatureCombination>,
+    concurrency: usize,
+) -> Vec<CombinationResult> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut handles = Vec::new();
+
+    for (index, combination) in combinations.into_iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let root = project_root.to_path_buf();
+        let target_dir = root.join("target").join("feature-check").join(index.to_string());
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            check_one(&root, &target_dir, combination).await
+        }));
+    }
+
+    let mut results = Vec::new();
+    for handle in handles {
+        if let Ok(result) = handle.await {
+            results.push(result);
+        }
+    }
+    results
+}
+
+async fn check_one(project_root: &Path, target_dir: &Path, combination: FeatureCombination) -> CombinationResult {
+    debug!("Checking feature combination: {}", combination.label);
+
+    let output = timings::measure(Phase::Cargo, Command::new("cargo")
+        .args(combination.cargo_args())
+        .env("CARGO_TARGET_DIR", target_dir)
+        .current_dir(project_root)
+        .kill_on_drop(true)
+        .output())
+        .await;
+
+    match output {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut errors: Vec<Value> = stdout.lines()
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                .flat_map(|msg| cargo_message_diagnostics(&msg))
+                .filter(|d| d["level"] == "error")
+                .take(MAX_ERRORS_PER_COMBINATION)
+                .collect();
+
+            // Failures before compilation (e.g. an unknown feature) only show up on stderr
+            if !output.status.success() && errors.is_empty() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let message = stderr.lines()
+                    .filter(|l| l.starts_with("error"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                errors.push(serde_json::json!({ "level": "error", "message": message, "source": "cargo" }));
+            }
+
+            CombinationResult { combination, success: output.status.success(), errors }
+        }
+        Err(e) => CombinationResult {
+            combination,
+            success: false,
+            errors: vec![serde_json::json!({ "level": "error", "message": format!("Failed to run cargo: {}", e), "source": "cargo" })],
+        },
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Dependency tables that can hold optional dependencies
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "build-dependencies"];
+
+/// A feature, or a dependency that features enable or set features of
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureNode {
+    /// Feature name, or `dep:name` for a dependency
+    pub id: String,
+    /// `feature` or `dependency`
+    pub kind: String,
+    /// A feature Cargo creates for an optional dependency never named with `dep:`
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub implicit: bool,
+    /// For dependencies: whether a feature has to enable it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optional: Option<bool>,
+}
+
+/// One entry of a feature's list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureEdge {
+    pub from: String,
+    pub to: String,
+    /// `feature` when enabling another feature, `dependency` when enabling a
+    /// dependency or one of its features
+    pub kind: String,
+    /// Feature of the dependency enabled along with it, from `dep/feature`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependency_feature: Option<String>,
+    /// `dep?/feature`: sets the feature only if something else enables the dependency
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub weak: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeatureGraph {
+    pub nodes: Vec<FeatureNode>,
+    pub edges: Vec<FeatureEdge>,
+    /// Entries of the `default` feature as written
+    pub default: Vec<String>,
+    /// Features enabled by default, following `default` transitively
+    pub default_enabled: Vec<String>,
+    /// Optional dependencies enabled by default
+    pub default_dependencies: Vec<String>,
+    /// Entries naming neither a feature nor an optional dependency
+    pub unresolved: Vec<String>,
+}
+
+/// Build the feature graph of the package described by `manifest`, the
+/// content of a Cargo.toml
+pub fn feature_graph(manifest: &str) -> Result<FeatureGraph> {
+    let manifest: toml::Table = toml::from_str(manifest).context("Failed to parse Cargo.toml")?;
+    let features: BTreeMap<String, Vec<String>> = match manifest.get("features") {
+        Some(features) => features.clone().try_into().context("[features] must map names to lists of strings")?,
+        None => BTreeMap::new(),
+    };
+    let optional = optional_dependencies(&manifest);
+
+    // Optional dependencies named with `dep:` anywhere get no implicit feature
+    let explicit: BTreeSet<&str> = features.values().flatten().filter_map(|entry| entry.strip_prefix("dep:")).collect();
+    let mut graph = FeatureGraph::default();
+    for name in features.keys() {
+        graph.nodes.push(FeatureNode { id: name.clone(), kind: "feature".to_string(), implicit: false, optional: None });
+    }
+    for name in &optional {
+        graph.nodes.push(FeatureNode { id: format!("dep:{}", name), kind: "dependency".to_string(), implicit: false, optional: Some(true) });
+        if !explicit.contains(name.as_str()) && !features.contains_key(name) {
+            graph.nodes.push(FeatureNode { id: name.clone(), kind: "feature".to_string(), implicit: true, optional: None });
+            graph.edges.push(FeatureEdge {
+                from: name.clone(),
+                to: format!("dep:{}", name),
+                kind: "dependency".to_string(),
+                dependency_feature: None,
+                weak: false,
+            });
+        }
+    }
+
+    let is_feature = |name: &str| features.contains_key(name) || (optional.contains(name) && !explicit.contains(name));
+    let mut edges = Vec::new();
+    for (feature, entries) in &features {
+        for entry in entries {
+            let edge = |to: String, kind: &str, dependency_feature: Option<&str>, weak: bool| FeatureEdge {
+                from: feature.clone(),
+                to,
+                kind: kind.to_string(),
+                dependency_feature: dependency_feature.map(str::to_string),
+                weak,
+            };
+            if let Some(dependency) = entry.strip_prefix("dep:") {
+                if optional.contains(dependency) {
+                    edges.push(edge(entry.clone(), "dependency", None, false));
+                } else {
+                    graph.unresolved.push(entry.clone());
+                }
+            } else if let Some((dependency, dependency_feature)) = entry.split_once('/') {
+                let (dependency, weak) = match dependency.strip_suffix('?') {
+                    Some(dependency) => (dependency, true),
+                    None => (dependency, false),
+                };
+                // A required dependency only gets the feature set
+                if !optional.contains(dependency) && !graph.nodes.iter().any(|node| node.id == format!("dep:{}", dependency)) {
+                    graph.nodes.push(FeatureNode { id: format!("dep:{}", dependency), kind: "dependency".to_string(), implicit: false, optional: Some(false) });
+                }
+                edges.push(edge(format!("dep:{}", dependency), "dependency", Some(dependency_feature), weak));
+            } else if is_feature(entry) {
+                edges.push(edge(entry.clone(), "feature", None, false));
+            } else {
+                graph.unresolved.push(entry.clone());
+            }
+        }
+    }
+    graph.edges.extend(edges);
+
+    graph.default = features.get("default").cloned().unwrap_or_default();
+    let mut enabled = BTreeSet::new();
+    let mut dependencies = BTreeSet::new();
+    let mut queue: Vec<String> = features.contains_key("default").then(|| "default".to_string()).into_iter().collect();
+    while let Some(feature) = queue.pop() {
+        if !enabled.insert(feature.clone()) {
+            continue;
+        }
+        for edge in graph.edges.iter().filter(|edge| edge.from == feature) {
+            match edge.kind.as_str() {
+                "feature" => queue.push(edge.to.clone()),
+                _ if !edge.weak => {
+                    if let Some(dependency) = edge.to.strip_prefix("dep:").filter(|d| optional.contains(*d)) {
+                        dependencies.insert(dependency.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    enabled.remove("default");
+    graph.default_enabled = enabled.into_iter().collect();
+    graph.default_dependencies = dependencies.into_iter().collect();
+    Ok(graph)
+}
+
+/// Names of the optional dependencies, target-specific ones included
+fn optional_dependencies(manifest: &toml::Table) -> BTreeSet<String> {
+    let mut tables: Vec<&toml::Value> = DEPENDENCY_TABLES.iter().filter_map(|table| manifest.get(*table)).collect();
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        for target in targets.values() {
+            tables.extend(DEPENDENCY_TABLES.iter().filter_map(|table| target.get(*table)));
+        }
+    }
+    tables.into_iter()
+        .filter_map(|table| table.as_table())
+        .flat_map(|table| table.iter())
+        .filter(|(_, spec)| spec.get("optional").and_then(|o| o.as_bool()).unwrap_or(false))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::attributes::{self, Attribute};
+use crate::source_files;
+use crate::toggle_async::{code_mask, line_col, matching};
+use crate::trait_objects::keyword_offsets;
+use crate::visibility::parse_item;
+
+/// Words that may come between a visibility and `extern`, or before a foreign item
+const MODIFIERS: &[&str] = &["pub", "unsafe", "safe", "const", "async"];
+
+/// One piece of the FFI boundary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FfiItem {
+    pub file: String,
+    /// 1-based line of the declaration, after its attributes
+    pub line: usize,
+    /// `extern_block`, `function`, `static`, `struct`, `enum` or `union`
+    pub kind: String,
+    /// Absent for extern blocks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Linker symbol when `#[export_name]` or `#[link_name]` renames it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// `exported` (callable from C: `#[no_mangle]`, `#[export_name]` or an
+    /// `extern fn` with a body), `imported` (declared in an `extern` block) or
+    /// `shared` (`#[repr(C)]` types, whose layout both sides rely on)
+    pub direction: String,
+    /// ABI string, `C` when `extern` has none; absent for Rust-ABI
+    /// `#[no_mangle]` functions and for types
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abi: Option<String>,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FfiReport {
+    pub files_scanned: usize,
+    pub total: usize,
+    pub exported: usize,
+    pub imported: usize,
+    pub shared: usize,
+    pub items: Vec<FfiItem>,
+}
+
+/// Map the FFI boundary of the `.rs` files under `dir`
+pub fn find_ffi(project_root: &Path, dir: &Path) -> FfiReport {
+    let mut report = FfiReport::default();
+    for file in source_files::rust_files(dir) {
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+        report.files_scanned += 1;
+        report.items.extend(scan_source(&content).into_iter().map(|item| FfiItem { file: display.clone(), ..item }));
+    }
+
+    for item in &report.items {
+        match item.direction.as_str() {
+            "exported" => report.exported += 1,
+            "imported" => report.imported += 1,
+            _ => report.shared += 1,
+        }
+    }
+    report.total = report.items.len();
+    report
+}
+
+/// FFI items of `source` in source order. `file` is left empty for the caller to fill in.
+pub fn scan_source(source: &str) -> Vec<FfiItem> {
+    let mask = code_mask(source);
+    // Comments and strings blanked out, offsets unchanged
+    let blanked: Vec<u8> = source.bytes().zip(&mask).map(|(b, code)| if *code { b } else { b' ' }).collect();
+    let blanked = &String::from_utf8(blanked).unwrap_or_else(|_| source.to_string());
+    let attributes = attributes::attributes(source);
+    let lines: Vec<&str> = source.lines().collect();
+    // Keyed by the offset the declaration starts at, so an item found twice
+    // (an exported `extern fn` that is also `#[no_mangle]`) is reported once
+    let mut items: BTreeMap<usize, FfiItem> = BTreeMap::new();
+    let mut add = |start: usize, kind: &str, name: Option<String>, direction: &str, abi: Option<String>| {
+        let (line, _) = line_col(source, start);
+        let symbol = renamed_symbol(&attributes_before(&attributes, blanked, start));
+        let item = items.entry(start).or_insert_with(|| FfiItem {
+            file: String::new(),
+            line,
+            kind: kind.to_string(),
+            name: name.clone(),
+            symbol: None,
+            direction: direction.to_string(),
+            abi: abi.clone(),
+            code: lines.get(line - 1).map_or("", |l| l.trim()).to_string(),
+        });
+        item.symbol = symbol.filter(|symbol| Some(symbol) != item.name.as_ref());
+    };
+
+    for at in keyword_offsets(blanked, &mask, "extern") {
+        let (abi, after) = abi(source, at + "extern".len());
+        let rest = &blanked[after..];
+        let next = after + rest.len() - rest.trim_start().len();
+        let start = modifiers_start(blanked, at);
+        if blanked[next..].starts_with('{') {
+            add(start, "extern_block", None, "imported", Some(abi.clone()));
+            let Some(close) = matching(blanked, &mask, next) else { continue };
+            for (keyword, kind) in [("fn", "function"), ("static", "static")] {
+                for inner in keyword_offsets(&blanked[..close], &mask, keyword).into_iter().filter(|&i| i > next) {
+                    let name = item_name(&blanked[inner + keyword.len()..]);
+                    if !name.is_empty() {
+                        add(modifiers_start(blanked, inner), kind, Some(name), "imported", Some(abi.clone()));
+                    }
+                }
+            }
+        } else if let Some(after_fn) = blanked[next..].strip_prefix("fn") {
+            // `extern "C" fn(i32)` is a function pointer type, not an item
+            let name = item_name(after_fn);
+            if after_fn.starts_with(char::is_whitespace) && !name.is_empty() {
+                add(start, "function", Some(name), "exported", Some(abi));
+            }
+        }
+    }
+
+    for attribute in &attributes {
+        let (path, body) = unwrap_unsafe(attribute);
+        let exported = matches!(path, "no_mangle" | "export_name");
+        // `repr(C)`, `repr(C, packed)`, `repr(u8, C)`
+        let repr_c = path == "repr" && body.split(|c: char| !c.is_alphanumeric()).any(|word| word == "C");
+        if !(exported || repr_c) {
+            continue;
+        }
+        let start = item_after(&attributes, blanked, attribute.span.1);
+        let Some((_, kind, name)) = parse_item(blanked[start..].lines().next().unwrap_or("")) else { continue };
+        match (kind, exported) {
+            ("function" | "static", true) => add(start, kind, Some(name), "exported", None),
+            ("struct" | "enum" | "union", false) => add(start, kind, Some(name), "shared", None),
+            _ => {}
+        }
+    }
+
+    items.into_values().collect()
+}
+
+/// The ABI string following `extern` at `from`, and the offset after it
+fn abi(source: &str, from: usize) -> (String, usize) {
+    let rest = &source[from..];
+    let quote = from + rest.len() - rest.trim_start().len();
+    if let Some(literal) = source[quote..].strip_prefix('"') {
+        if let Some(end) = literal.find('"') {
+            return (literal[..end].to_string(), quote + end + 2);
+        }
+    }
+    ("C".to_string(), from)
+}
+
+/// Start of the declaration whose keyword is at `at`, taking in the
+/// visibility and modifiers before it on the same line
+fn modifiers_start(source: &str, at: usize) -> usize {
+    let line_start = source[..at].rfind('\n').map_or(0, |n| n + 1);
+    let mut start = at;
+    loop {
+        let before = source[line_start..start].trim_end();
+        let word_start = before.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |n| n + 1);
+        if before.ends_with(')') {
+            // `pub(crate)`
+            let Some(open) = before.rfind('(') else { break };
+            if !before[..open].trim_end().ends_with("pub") {
+                break;
+            }
+            start = line_start + before[..open].trim_end().len() - "pub".len();
+        } else if MODIFIERS.contains(&&before[word_start..]) {
+            start = line_start + word_start;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+/// The identifier at the start of `text`, after whitespace and `mut`
+fn item_name(text: &str) -> String {
+    let text = text.trim_start();
+    let text = text.strip_prefix("mut ").unwrap_or(text).trim_start();
+    text.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect()
+}
+
+/// Offset of the item following the attribute ending at `from`, past any
+/// further attributes, comments and whitespace
+fn item_after(attributes: &[Attribute], blanked: &str, mut from: usize) -> usize {
+    loop {
+        from += blanked[from..].len() - blanked[from..].trim_start().len();
+        match attributes.iter().find(|a| a.span.0 == from) {
+            Some(next) => from = next.span.1,
+            None => return from,
+        }
+    }
+}
+
+/// Outer attributes directly above the declaration starting at `start`
+fn attributes_before<'a>(attributes: &'a [Attribute], blanked: &str, start: usize) -> Vec<&'a Attribute> {
+    let mut found = Vec::new();
+    let mut boundary = start;
+    for attribute in attributes.iter().rev().filter(|a| a.span.1 <= start) {
+        if attribute.inner || !blanked[attribute.span.1..boundary].trim().is_empty() {
+            break;
+        }
+        boundary = attribute.span.0;
+        found.push(attribute);
+    }
+    found
+}
+
+/// Symbol set by `#[export_name = "..."]` or `#[link_name = "..."]`
+fn renamed_symbol(attributes: &[&Attribute]) -> Option<String> {
+    attributes.iter().find_map(|attribute| {
+        let (path, body) = unwrap_unsafe(attribute);
+        if !matches!(path, "export_name" | "link_name") {
+            return None;
+        }
+        let value = body.split_once('"')?.1;
+        Some(value[..value.find('"')?].to_string())
+    })
+}
+
+/// Path and body of an attribute, looking inside `#[unsafe(...)]`
+fn unwrap_unsafe(attribute: &Attribute) -> (&str, &str) {
+    let body = attribute.text.trim_start_matches(['#', '!']).trim_start().trim_start_matches('[');
+    let body = body.strip_suffix(']').unwrap_or(body).trim();
+    let body = match body.strip_prefix("unsafe") {
+        Some(inner) if inner.trim_start().starts_with('(') => {
+            let inner = inner.trim_start()[1..].trim();
+            inner.strip_suffix(')').unwrap_or(inner).trim()
+        }
+        _ => body,
+    };
+    let path_len = body.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).unwrap_or(body.len());
+    (&body[..path_len], body)
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::signature;
+
+/// Leading keywords that introduce an item, mapped to the reported kind
+const ITEM_KINDS: &[(&str, &str)] = &[
+    ("fn", "function"),
+    ("struct", "struct"),
+    ("enum", "enum"),
+    ("union", "union"),
+    ("trait", "trait"),
+    ("type", "type_alias"),
+    ("const", "constant"),
+    ("static", "static"),
+    ("mod", "module"),
+    ("impl", "impl"),
+    ("macro_rules!", "macro"),
+    ("use", "use"),
+];
+
+/// Modifiers that may appear between the visibility and the item keyword
+const MODIFIERS: &[&str] = &["async", "const", "unsafe", "extern", "default"];
+
+/// A public item reduced to its declaration line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryItem {
+    pub kind: String,
+    pub name: String,
+    /// 1-based line of the declaration
+    pub line: usize,
+    pub signature: String,
+    /// First line of the item's doc comment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    /// Public methods of an `impl` block (all methods for trait impls)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<SummaryItem>,
+}
+
+/// Compact, body-free view of a source file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileSummary {
+    pub module_doc: Option<String>,
+    pub public_items: Vec<SummaryItem>,
+    /// Non-public items by kind, including private methods
+    pub private_counts: BTreeMap<String, usize>,
+    pub total_lines: usize,
+}
+
+/// Summarize `source` into module docs, public item signatures and private item counts
+pub fn summarize(source: &str) -> FileSummary {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut summary = FileSummary {
+        total_lines: lines.len(),
+        ..FileSummary::default()
+    };
+
+    let module_doc: Vec<&str> = lines.iter()
+        .map(|l| l.trim())
+        .take_while(|l| l.starts_with("//!") || l.is_empty() || l.starts_with("#!["))
+        .filter_map(|l| l.strip_prefix("//!"))
+        .map(|l| l.trim())
+        .collect();
+    if !module_doc.is_empty() {
+        summary.module_doc = Some(module_doc.join("\n").trim().to_string());
+    }
+
+    let mut depth = 0i32;
+    // (index into public_items, is trait impl) for the impl block we are inside
+    let mut current_impl: Option<(usize, bool)> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        let trimmed = code.trim();
+
+        if depth == 0 {
+            current_impl = None;
+            if let Some((public, kind, name)) = parse_item_header(trimmed) {
+                if kind == "impl" {
+                    // Impl blocks carry no visibility; keep the ones that expose methods
+                    let is_trait_impl = name.contains(" for ");
+                    summary.public_items.push(item(&lines, i, kind, name));
+                    current_impl = Some((summary.public_items.len() - 1, is_trait_impl));
+                } else if public {
+                    summary.public_items.push(item(&lines, i, kind, name));
+                } else if kind != "use" {
+                    *summary.private_counts.entry(kind.to_string()).or_default() += 1;
+                }
+            }
+        } else if depth == 1 {
+            if let (Some((index, is_trait_impl)), Some((public, "function", name))) = (current_impl, parse_item_header(trimmed)) {
+                if public || is_trait_impl {
+                    let method = item(&lines, i, "method", name);
+                    summary.public_items[index].methods.push(method);
+                } else {
+                    *summary.private_counts.entry("method".to_string()).or_default() += 1;
+                }
+            }
+        }
+
+        for c in code.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    summary.public_items.retain(|item| item.kind != "impl" || !item.methods.is_empty());
+    summary
+}
+
+/// Recognize an item declaration; returns (is public, kind, name)
+fn parse_item_header(line: &str) -> Option<(bool, &'static str, String)> {
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut rest = line;
+    let public = rest.starts_with("pub ");
+    // `pub(crate)`/`pub(super)` items are not part of the public API
+    if let Some(after) = rest.strip_prefix("pub") {
+        if after.starts_with('(') {
+            rest = after.split_once(')').map(|(_, r)| r).unwrap_or(after);
+        } else if public {
+            rest = after;
+        }
+    }
+    rest = rest.trim_start();
+
+    while let Some(modifier) = MODIFIERS.iter().find(|m| {
+        rest.strip_prefix(**m).is_some_and(|r| r.starts_with(' '))
+    }) {
+        // `const fn` is a function, but `const NAME: T` is a constant
+        if *modifier == "const" && !rest["const".len()..].trim_start().starts_with("fn ") {
+            break;
+        }
+        rest = rest[modifier.len()..].trim_start();
+        // `extern "C" fn`
+        if rest.starts_with('"') {
+            rest = rest[1..].split_once('"').map(|(_, r)| r.trim_start()).unwrap_or(rest);
+        }
+    }
+
+    for (keyword, kind) in ITEM_KINDS {
+        let Some(after) = rest.strip_prefix(keyword) else { continue };
+        if !(after.starts_with(' ') || after.starts_with('<') || (*kind == "macro")) {
+            continue;
+        }
+        let after = after.trim_start();
+        let name = if *kind == "impl" {
+            impl_name(after)
+        } else {
+            after.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .next()
+                .unwrap_or("")
+                .to_string()
+        };
+        if name.is_empty() {
+            return None;
+        }
+        return Some((public, kind, name));
+    }
+    None
+}
+
+/// `impl<T> Display for Wrapper<T> {` -> `Display for Wrapper<T>`
+fn impl_name(after_impl: &str) -> String {
+    let mut text = after_impl;
+    if text.starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in text.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        text = &text[i + 1..];
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let text = text.split(" where ").next().unwrap_or(text);
+    text.trim_end_matches('{').trim().to_string()
+}
+
+fn item(lines: &[&str], index: usize, kind: &str, name: String) -> SummaryItem {
+    SummaryItem {
+        kind: kind.to_string(),
+        name,
+        line: index + 1,
+        signature: declaration(lines, index, kind),
+        doc: first_doc_line(lines, index),
+        methods: Vec::new(),
+    }
+}
+
+/// The item's declaration without its body or initializer
+fn declaration(lines: &[&str], index: usize, kind: &str) -> String {
+    if kind == "function" || kind == "method" {
+        let source = lines[index..].join("\n");
+        if let Some(text) = signature::extract_signature_text(&source, 0) {
+            return signature::parse_signature(&text).map(|s| s.rendered).unwrap_or(text);
+        }
+    }
+
+    let mut text = String::new();
+    for line in &lines[index..] {
+        let code = line.split("//").next().unwrap_or("").trim();
+        let end = code.find(['{', ';']).or_else(|| {
+            // Drop the value of consts and statics
+            matches!(kind, "constant" | "static").then(|| code.find(" =")).flatten()
+        });
+        match end {
+            Some(end) => {
+                text.push_str(&code[..end]);
+                break;
+            }
+            None => {
+                text.push_str(code);
+                text.push(' ');
+            }
+        }
+    }
+    if matches!(kind, "constant" | "static") {
+        text = text.split(" =").next().unwrap_or(&text).to_string();
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// First line of the `///` block directly above `index`, skipping attributes
+fn first_doc_line(lines: &[&str], index: usize) -> Option<String> {
+    let mut doc = None;
+    for line in lines[..index].iter().rev() {
+        let trimmed = line.trim();
+        if let Some(text) = trimmed.strip_prefix("///") {
+            doc = Some(text.trim().to_string());
+        } else if !trimmed.starts_with("#[") {
+            break;
+        }
+    }
+    doc.filter(|d| !d.is_empty())
+}
@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::import_suggest;
+use crate::timings::{self, Phase};
+
+/// Locations kept per step beyond the primary span
+const LOCATION_SAMPLE_LIMIT: usize = 5;
+
+/// Where a diagnostic points
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpanLocation {
+    pub file: String,
+    pub line: u64,
+    pub column: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// A machine-applicable fix offered by the compiler
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replacement {
+    pub file: String,
+    pub line_start: u64,
+    pub column_start: u64,
+    pub line_end: u64,
+    pub column_end: u64,
+    pub text: String,
+}
+
+/// Diagnostics sharing one root cause, with what to do about them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixStep {
+    pub step: usize,
+    pub level: String,
+    pub code: Option<String>,
+    /// What the diagnostics in this step have in common
+    pub cause: String,
+    pub message: String,
+    pub count: usize,
+    pub package: String,
+    pub primary_span: Option<SpanLocation>,
+    /// Further occurrences, up to a small sample
+    pub locations: Vec<SpanLocation>,
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<Replacement>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FixPlan {
+    pub success: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    pub steps: Vec<FixStep>,
+}
+
+/// Run `cargo check` in `project_root` and turn its output into a fix plan
+pub async fn diagnose(project_root: &Path, include_warnings: bool) -> Result<FixPlan> {
+    let (success, messages) = cargo_check(project_root).await?;
+    let mut plan = build_plan(&messages, include_warnings);
+    plan.success = success;
+    Ok(plan)
+}
+
+/// Run `cargo check --message-format=json` in `project_root`: whether it
+/// succeeded, and the JSON messages it printed
+pub async fn cargo_check(project_root: &Path) -> Result<(bool, Vec<Value>)> {
+    let output = timings::measure(Phase::Cargo, Command::new("cargo")
+        .args(["check", "--message-format=json"])
+        .current_dir(project_root)
+        .output())
+        .await
+        .context("Failed to run cargo check")?;
+
+    let messages = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .collect();
+    Ok((output.status.success(), messages))
+}
+
+/// Cluster `cargo check --message-format=json` messages by root cause and
+/// order the clusters by fix priority: errors before warnings, unresolved
+/// names before the errors they cascade into, then packages in the order
+/// cargo built them (dependencies first), then position in the source.
+pub fn build_plan(messages: &[Value], include_warnings: bool) -> FixPlan {
+    let mut plan = FixPlan::default();
+    let mut package_order: HashMap<String, usize> = HashMap::new();
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+
+    for msg in messages {
+        if msg.get("reason").and_then(|r| r.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let package = msg.get("package_id").and_then(|p| p.as_str()).map(package_name).unwrap_or_default();
+        let order = package_order.len();
+        let order = *package_order.entry(package.clone()).or_insert(order);
+
+        let message = &msg["message"];
+        let level = message["level"].as_str().unwrap_or("");
+        match level {
+            "error" => plan.errors += 1,
+            "warning" => plan.warnings += 1,
+            _ => continue,
+        }
+        if level == "warning" && !include_warnings {
+            continue;
+        }
+        let text = message["message"].as_str().unwrap_or("");
+        // Summary lines such as "aborting due to 2 previous errors" have no spans
+        let Some(primary) = primary_span(message) else { continue };
+
+        let (rank, cause) = root_cause(message, &primary);
+        let key = (package.clone(), cause.clone());
+        let cluster = match index.get(&key) {
+            Some(&i) => &mut clusters[i],
+            None => {
+                index.insert(key, clusters.len());
+                clusters.push(Cluster {
+                    level: level.to_string(),
+                    code: message["code"]["code"].as_str().map(String::from),
+                    cause,
+                    message: text.to_string(),
+                    rank,
+                    package,
+                    package_order: order,
+                    spans: Vec::new(),
+                    action: suggested_action(message, &primary),
+                    replacement: replacement(message),
+                });
+                clusters.last_mut().unwrap()
+            }
+        };
+        cluster.spans.push(primary);
+    }
+
+    clusters.sort_by(|a, b| {
+        level_rank(&a.level).cmp(&level_rank(&b.level))
+            .then(a.rank.cmp(&b.rank))
+            .then(a.package_order.cmp(&b.package_order))
+            .then_with(|| a.spans[0].file.cmp(&b.spans[0].file))
+            .then(a.spans[0].line.cmp(&b.spans[0].line))
+    });
+
+    plan.steps = clusters.into_iter().enumerate().map(|(i, cluster)| {
+        let count = cluster.spans.len();
+        let mut spans = cluster.spans.into_iter();
+        FixStep {
+            step: i + 1,
+            level: cluster.level,
+            code: cluster.code,
+            cause: cluster.cause,
+            message: cluster.message,
+            count,
+            package: cluster.package,
+            primary_span: spans.next(),
+            locations: spans.take(LOCATION_SAMPLE_LIMIT).collect(),
+            action: cluster.action,
+            replacement: cluster.replacement,
+        }
+    }).collect();
+    plan
+}
+
+struct Cluster {
+    level: String,
+    code: Option<String>,
+    cause: String,
+    message: String,
+    rank: u8,
+    package: String,
+    package_order: usize,
+    spans: Vec<SpanLocation>,
+    action: String,
+    replacement: Option<Replacement>,
+}
+
+fn level_rank(level: &str) -> u8 {
+    if level == "error" { 0 } else { 1 }
+}
+
+/// `path+file:///x/y#name@0.1.0` or the legacy `name 0.1.0 (path+file://...)`
+fn package_name(package_id: &str) -> String {
+    if let Some((_, fragment)) = package_id.rsplit_once('#') {
+        let fragment = fragment.split('@').next().unwrap_or(fragment);
+        // `#0.1.0` alone means the name is the last path segment
+        if fragment.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+            let path = package_id.split('#').next().unwrap_or("");
+            return path.rsplit('/').next().unwrap_or(path).to_string();
+        }
+        return fragment.to_string();
+    }
+    package_id.split_whitespace().next().unwrap_or(package_id).to_string()
+}
+
+fn primary_span(message: &Value) -> Option<SpanLocation> {
+    let spans = message["spans"].as_array()?;
+    let span = spans.iter().find(|s| s["is_primary"] == true).or_else(|| spans.first())?;
+    Some(SpanLocation {
+        file: span["file_name"].as_str()?.to_string(),
+        line: span["line_start"].as_u64().unwrap_or(0),
+        column: span["column_start"].as_u64().unwrap_or(0),
+        label: span["label"].as_str().map(String::from),
+    })
+}
+
+/// Grouping key and priority rank (lower first) for one diagnostic
+fn root_cause(message: &Value, primary: &SpanLocation) -> (u8, String) {
+    let text = message["message"].as_str().unwrap_or("");
+    let code = message["code"]["code"].as_str().unwrap_or("");
+
+    if let Some((_, name)) = import_suggest::unresolved_name(text) {
+        return (0, format!("unresolved name `{}`", name));
+    }
+    if code == "E0308" {
+        // "expected `u32`, found `String`" lives in the span label
+        let label = primary.label.as_deref().unwrap_or(text);
+        return (1, format!("type mismatch: {}", label));
+    }
+    if message["level"] == "warning" && !code.is_empty() {
+        return (1, format!("lint `{}`", code));
+    }
+    (1, if code.is_empty() { text.to_string() } else { format!("{}: {}", code, text) })
+}
+
+fn suggested_action(message: &Value, primary: &SpanLocation) -> String {
+    let text = message["message"].as_str().unwrap_or("");
+    let children = message["children"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+
+    // The compiler's own help is the most specific advice there is
+    let help = children.iter()
+        .filter(|child| child["level"] == "help")
+        .filter_map(|child| child["message"].as_str())
+        .next();
+
+    if let Some((_, name)) = import_suggest::unresolved_name(text) {
+        return match help {
+            Some(help) => format!("Import or define `{}`: {}", name, help),
+            None => format!("Import or define `{}`; resolve_unresolved can suggest imports", name),
+        };
+    }
+    if message["code"]["code"] == "E0308" {
+        let label = primary.label.as_deref().unwrap_or("the expected type");
+        return match help {
+            Some(help) => format!("Reconcile the types ({}): {}", label, help),
+            None => format!("Reconcile the types: {}", label),
+        };
+    }
+    match help {
+        Some(help) => format!("{} ({})", capitalize(help), text),
+        None => format!("Fix: {}", text),
+    }
+}
+
+/// First suggested replacement in the diagnostic's children
+fn replacement(message: &Value) -> Option<Replacement> {
+    message["children"].as_array()?.iter()
+        .flat_map(|child| child["spans"].as_array().into_iter().flatten())
+        .find_map(|span| Some(Replacement {
+            file: span["file_name"].as_str()?.to_string(),
+            line_start: span["line_start"].as_u64()?,
+            column_start: span["column_start"].as_u64()?,
+            line_end: span["line_end"].as_u64()?,
+            column_end: span["column_end"].as_u64()?,
+            text: span["suggested_replacement"].as_str()?.to_string(),
+        }))
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::timings::{self, Phase};
+
+/// One stretch of a file that rustfmt would rewrite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormatHunk {
+    /// 1-based line the hunk starts at
+    pub line: usize,
+    /// Lines as rustfmt prints them: ` ` kept, `-` removed, `+` added
+    pub diff: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnformattedFile {
+    pub file: String,
+    pub hunks: Vec<FormatHunk>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormattingReport {
+    /// The command run, `cargo fmt --all --check` or `rustfmt --check ...`
+    pub command: String,
+    /// Every checked file is formatted and rustfmt raised no error
+    pub formatted: bool,
+    pub count: usize,
+    pub files: Vec<UnformattedFile>,
+    /// What rustfmt printed when it couldn't check everything, e.g. a file
+    /// that doesn't parse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check the formatting of the whole workspace, or of `file` alone, without
+/// writing anything
+pub async fn check(project_root: &Path, file: Option<&str>) -> Result<FormattingReport> {
+    let (program, args) = match file {
+        Some(file) => {
+            let path = project_root.join(file);
+            let edition = edition(project_root, &path);
+            ("rustfmt", vec![
+                "--check".to_string(), "--color".to_string(), "never".to_string(),
+                "--edition".to_string(), edition, path.display().to_string(),
+            ])
+        }
+        None => ("cargo", ["fmt", "--all", "--check", "--", "--color", "never"].map(String::from).to_vec()),
+    };
+
+    let output = timings::measure(Phase::Cargo, Command::new(program).args(&args).current_dir(project_root).output())
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let mut files = parse_diff(&stdout, project_root);
+    // rustfmt also checks the out-of-line modules of the file it is given
+    if let Some(file) = file {
+        files.retain(|f| Path::new(&f.file) == Path::new(file));
+    }
+    let error = (!output.status.success() && !stderr.trim().is_empty()).then(|| stderr.trim().to_string());
+    Ok(FormattingReport {
+        command: format!("{} {}", program, args.join(" ")),
+        formatted: files.is_empty() && error.is_none(),
+        count: files.len(),
+        files,
+        error,
+    })
+}
+
+/// Files and hunks from `--check` output, whose hunks start with
+/// `Diff in /abs/path.rs:12:` (older rustfmt: `Diff in /abs/path.rs at line 12:`).
+/// Paths are made relative to `project_root`.
+pub fn parse_diff(output: &str, project_root: &Path) -> Vec<UnformattedFile> {
+    let mut files: Vec<UnformattedFile> = Vec::new();
+    for line in output.lines() {
+        if let Some((path, start)) = hunk_header(line) {
+            let path = Path::new(path);
+            let file = path.strip_prefix(project_root).unwrap_or(path).display().to_string();
+            if files.last().is_none_or(|f| f.file != file) {
+                files.push(UnformattedFile { file, hunks: Vec::new() });
+            }
+            files.last_mut().unwrap().hunks.push(FormatHunk { line: start, diff: String::new() });
+        } else if let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) {
+            hunk.diff.push_str(line);
+            hunk.diff.push('\n');
+        }
+    }
+    for hunk in files.iter_mut().flat_map(|f| f.hunks.iter_mut()) {
+        let kept = hunk.diff.trim_end().len();
+        hunk.diff.truncate(kept);
+    }
+    files
+}
+
+fn hunk_header(line: &str) -> Option<(&str, usize)> {
+    let header = line.strip_prefix("Diff in ")?.strip_suffix(':')?;
+    let (path, start) = header.rsplit_once(" at line ").or_else(|| header.rsplit_once(':'))?;
+    Some((path, start.trim().parse().ok()?))
+}
+
+/// Edition of the package owning `file`, from the nearest manifest above it,
+/// following `edition.workspace = true` to the workspace root
+fn edition(project_root: &Path, file: &Path) -> String {
+    let manifest = |dir: &Path| -> Option<toml::Table> {
+        toml::from_str(&std::fs::read_to_string(dir.join("Cargo.toml")).ok()?).ok()
+    };
+    let package_edition = file.ancestors().skip(1)
+        .take_while(|dir| dir.starts_with(project_root))
+        .find_map(|dir| manifest(dir)?.get("package")?.get("edition").cloned());
+    match package_edition {
+        Some(toml::Value::String(edition)) => edition,
+        Some(_) => manifest(project_root)
+            .and_then(|root| root.get("workspace")?.get("package")?.get("edition")?.as_str().map(String::from))
+            .unwrap_or_else(|| "2015".to_string()),
+        None => "2015".to_string(),
+    }
+}
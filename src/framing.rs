@@ -0,0 +1,174 @@
+use serde_json::{json, Value};
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+
+/// How a message on stdin was delimited, and how its response is written back
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// One JSON message per line
+    #[default]
+    Lines,
+    /// `Content-Length` headers, a blank line, then exactly that many bytes,
+    /// as LSP frames its messages
+    ContentLength,
+}
+
+/// Largest `Content-Length` accepted; a frame claiming more is treated as
+/// corrupt rather than buffered
+pub const MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+/// A message whose framing couldn't be read: a `Content-Length` that is
+/// unreadable or over `MAX_CONTENT_LENGTH`, or input ending inside a frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidFrame {
+    pub reason: String,
+}
+
+impl InvalidFrame {
+    fn new(reason: impl Into<String>) -> Self {
+        Self { reason: reason.into() }
+    }
+
+    /// The `-32700` response it gets; no id could be read to answer under
+    pub fn response(&self) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": {
+                "code": -32700,
+                "message": format!("Parse error: {}", self.reason)
+            }
+        })
+    }
+}
+
+impl fmt::Display for InvalidFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+/// Reads messages in either framing, deciding for each one by whether it
+/// starts with a `Content-Length:` header. Blank lines between messages are
+/// skipped.
+pub struct MessageReader<R> {
+    reader: R,
+    framing: Framing,
+    /// Start of the next message, found while skipping past a broken one
+    pending: Option<String>,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, framing: Framing::default(), pending: None }
+    }
+
+    /// Framing of the last message read, to answer it in
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// The next message, or `None` at the end of the input. A frame that
+    /// can't be read is returned as an `InvalidFrame`; when its length is
+    /// unusable the input is skipped up to the next `Content-Length` header,
+    /// so reading carries on with the message after it. Only I/O failures
+    /// are errors.
+    pub fn next_message(&mut self) -> io::Result<Option<Result<String, InvalidFrame>>> {
+        let line = match self.pending.take() {
+            Some(line) => line,
+            None => loop {
+                match self.read_line()? {
+                    None => return Ok(None),
+                    Some(line) if line.trim().is_empty() => continue,
+                    Some(line) => break line,
+                }
+            },
+        };
+        let Some(length) = content_length(&line) else {
+            self.framing = Framing::Lines;
+            return Ok(Some(Ok(line.trim_end_matches(['\r', '\n']).to_string())));
+        };
+        self.framing = Framing::ContentLength;
+        let Some(length) = self.read_headers(length)? else {
+            return Ok(Some(Err(InvalidFrame::new("input ended inside message headers"))));
+        };
+        let length = match length {
+            Ok(length) if length <= MAX_CONTENT_LENGTH => length,
+            Ok(length) => {
+                self.skip_to_next_frame()?;
+                let reason = format!("Content-Length {} exceeds the limit of {} bytes", length, MAX_CONTENT_LENGTH);
+                return Ok(Some(Err(InvalidFrame::new(reason))));
+            }
+            Err(reason) => {
+                self.skip_to_next_frame()?;
+                return Ok(Some(Err(InvalidFrame::new(reason))));
+            }
+        };
+        let mut content = Vec::new();
+        (&mut self.reader).take(length as u64).read_to_end(&mut content)?;
+        if content.len() < length {
+            return Ok(Some(Err(InvalidFrame::new("input ended inside message body"))));
+        }
+        Ok(Some(Ok(String::from_utf8_lossy(&content).into_owned())))
+    }
+
+    /// The next line with its line ending, invalid UTF-8 replaced, or `None`
+    /// at the end of the input
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = Vec::new();
+        if self.reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+    }
+
+    /// Read the rest of a header block up to its blank line. A later
+    /// `Content-Length` overrides `length`. `None` if the input ends first.
+    fn read_headers(&mut self, mut length: Result<usize, String>) -> io::Result<Option<Result<usize, String>>> {
+        loop {
+            let Some(line) = self.read_line()? else {
+                return Ok(None);
+            };
+            if line.trim().is_empty() {
+                return Ok(Some(length));
+            }
+            if let Some(value) = content_length(&line) {
+                length = value;
+            }
+        }
+    }
+
+    /// Skip the body of a frame whose length is unknown, up to the next
+    /// `Content-Length` header, which may follow the body on the same line
+    fn skip_to_next_frame(&mut self) -> io::Result<()> {
+        while let Some(line) = self.read_line()? {
+            if let Some(at) = line.to_ascii_lowercase().find("content-length:") {
+                self.pending = Some(line[at..].to_string());
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The length a `Content-Length` header line gives, or `None` for any other
+/// line; a header with an unreadable value gives the reason instead
+fn content_length(line: &str) -> Option<Result<usize, String>> {
+    let (key, value) = line.split_once(':')?;
+    if !key.trim().eq_ignore_ascii_case("Content-Length") {
+        return None;
+    }
+    Some(value.trim().parse().map_err(|_| format!("invalid Content-Length: {}", value.trim())))
+}
+
+/// Write `message` in `framing`. Empty messages, the answer to a
+/// notification, are written as an empty line in line mode and not at all
+/// with `Content-Length` framing.
+pub fn write_message(writer: &mut impl Write, framing: Framing, message: &str) -> io::Result<()> {
+    match framing {
+        Framing::Lines => writeln!(writer, "{}", message)?,
+        Framing::ContentLength if message.is_empty() => return Ok(()),
+        Framing::ContentLength => write!(writer, "Content-Length: {}\r\n\r\n{}", message.len(), message)?,
+    }
+    writer.flush()
+}
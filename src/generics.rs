@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+use crate::signature::{find_body_start, find_matching, find_top_level_keyword, normalize_whitespace, split_top_level};
+
+/// Keywords introducing items that can declare generic parameters
+const ITEM_KEYWORDS: &[&str] = &["fn", "struct", "enum", "union", "trait", "impl", "type"];
+
+/// One parameter of a generic parameter list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericParam {
+    /// `lifetime`, `type` or `const`
+    pub kind: String,
+    /// Including the leading `'` for lifetimes
+    pub name: String,
+    /// Inline bounds, split on `+`
+    pub bounds: Vec<String>,
+    /// Type of a const parameter
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// One `where` clause predicate, e.g. `T::Item: Debug + Send`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WherePredicate {
+    pub bounded: String,
+    pub bounds: Vec<String>,
+}
+
+/// Generic parameters and where-clauses declared by an item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemGenerics {
+    /// `fn`, `struct`, `enum`, `union`, `trait`, `impl` or `type`
+    pub item_kind: String,
+    /// Item name; for impls, the implemented trait and self type
+    pub name: String,
+    pub lifetimes: Vec<GenericParam>,
+    pub type_params: Vec<GenericParam>,
+    pub const_params: Vec<GenericParam>,
+    pub where_predicates: Vec<WherePredicate>,
+    /// The parameter list as written, e.g. `<'a, T: Clone>`; empty when there is none
+    pub rendered: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub where_clause: Option<String>,
+}
+
+/// Header of the item declared at `line` (0-based): from its keyword up to the
+/// opening brace or semicolon. Attributes and doc comments at `line` are
+/// skipped; anything else there means no item is declared at the line.
+pub fn item_header_at(source: &str, line: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let start = (line..lines.len()).find(|&i| {
+        let trimmed = lines[i].trim();
+        !(trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with("#["))
+    })?;
+    item_keyword(lines[start].trim_start())?;
+
+    let mut text = String::new();
+    for l in &lines[start..] {
+        let code = l.split("//").next().unwrap_or("").trim();
+        if let Some(end) = find_body_start(code) {
+            text.push_str(&code[..end]);
+            return Some(normalize_whitespace(&text));
+        }
+        text.push_str(code);
+        text.push(' ');
+    }
+    Some(normalize_whitespace(&text))
+}
+
+/// Parse the generics of an item header such as
+/// `pub fn get<'a, T: Clone>(x: &'a T) -> T where T: Debug`
+pub fn parse_generics(header: &str) -> Option<ItemGenerics> {
+    let header = normalize_whitespace(header);
+    let (keyword, position) = item_keyword(&header)?;
+    let mut rest = header[position + keyword.len()..].trim_start();
+
+    let name = if keyword == "impl" {
+        String::new()
+    } else {
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let name = rest[..len].to_string();
+        rest = rest[len..].trim_start();
+        name
+    };
+
+    let mut rendered = String::new();
+    let mut params = Vec::new();
+    if rest.starts_with('<') {
+        let end = find_matching(rest, '<', '>')?;
+        rendered = rest[..=end].to_string();
+        params = split_top_level(&rest[1..end], ',')
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .map(|p| parse_param(&p))
+            .collect();
+        rest = rest[end + 1..].trim_start();
+    }
+
+    let (before_where, where_clause) = match find_top_level_keyword(rest, "where") {
+        Some(pos) => (rest[..pos].trim(), Some(rest[pos..].trim().trim_end_matches(',').to_string())),
+        None => (rest.trim(), None),
+    };
+    let name = if keyword == "impl" { before_where.to_string() } else { name };
+    let where_predicates = where_clause.as_deref()
+        .map(|clause| split_top_level(clause.trim_start_matches("where"), ',')
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .map(|p| parse_predicate(&p))
+            .collect())
+        .unwrap_or_default();
+
+    let of_kind = |kind: &str| params.iter().filter(|p: &&GenericParam| p.kind == kind).cloned().collect();
+    Some(ItemGenerics {
+        item_kind: keyword.to_string(),
+        name,
+        lifetimes: of_kind("lifetime"),
+        type_params: of_kind("type"),
+        const_params: of_kind("const"),
+        where_predicates,
+        rendered,
+        where_clause,
+    })
+}
+
+/// The item keyword of a declaration line and its byte position, skipping
+/// visibility and qualifiers such as `pub(crate) unsafe`
+fn item_keyword(line: &str) -> Option<(&'static str, usize)> {
+    let mut position = 0;
+    for word in line.split(' ') {
+        let bare = word.split(['<', '(', '{', ';']).next().unwrap_or(word);
+        if let Some(keyword) = ITEM_KEYWORDS.iter().find(|k| **k == bare) {
+            return Some((keyword, position));
+        }
+        let qualifier = word.starts_with("pub")
+            || matches!(word, "unsafe" | "async" | "const" | "default" | "extern" | "auto")
+            || word.starts_with('"');
+        if !qualifier {
+            return None;
+        }
+        position += word.len() + 1;
+    }
+    None
+}
+
+fn parse_param(param: &str) -> GenericParam {
+    let (param, default) = match split_top_level(param, '=').as_slice() {
+        [param, default] => (param.clone(), Some(default.clone())),
+        _ => (param.to_string(), None),
+    };
+
+    if let Some(rest) = param.strip_prefix("const ") {
+        let (name, ty) = rest.split_once(':').unwrap_or((rest, ""));
+        return GenericParam {
+            kind: "const".to_string(),
+            name: name.trim().to_string(),
+            bounds: Vec::new(),
+            ty: Some(ty.trim().to_string()).filter(|t| !t.is_empty()),
+            default,
+        };
+    }
+
+    let parts = split_top_level(&param, ':');
+    let name = parts[0].clone();
+    let bounds = parts.get(1).map(|b| split_bounds(b)).unwrap_or_default();
+    GenericParam {
+        kind: if name.starts_with('\'') { "lifetime" } else { "type" }.to_string(),
+        name,
+        bounds,
+        ty: None,
+        default,
+    }
+}
+
+fn parse_predicate(predicate: &str) -> WherePredicate {
+    let parts = split_top_level(predicate, ':');
+    WherePredicate {
+        bounded: parts[0].clone(),
+        bounds: parts.get(1).map(|b| split_bounds(b)).unwrap_or_default(),
+    }
+}
+
+fn split_bounds(bounds: &str) -> Vec<String> {
+    split_top_level(bounds, '+').into_iter().filter(|b| !b.is_empty()).collect()
+}
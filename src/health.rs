@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use tokio::process::Command;
+use tracing::debug;
+
+/// Result of probing whether cargo is usable in a project directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CargoHealth {
+    pub cargo_available: bool,
+    pub cargo_version: Option<String>,
+    pub metadata_ok: bool,
+    /// The package whose manifest is the project root's; `None` for a virtual workspace
+    pub package_name: Option<String>,
+    /// The workspace's packages when the root manifest has none of its own
+    pub workspace_members: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl CargoHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.cargo_available && self.metadata_ok
+    }
+}
+
+/// Run `cargo --version` and `cargo metadata --no-deps` in the project root.
+/// Catches missing toolchains, wrong directories and broken manifests before
+/// any analysis request is issued.
+pub async fn check_cargo(project_root: &Path) -> CargoHealth {
+    let mut health = CargoHealth {
+        cargo_available: false,
+        cargo_version: None,
+        metadata_ok: false,
+        package_name: None,
+        workspace_members: Vec::new(),
+        error: None,
+    };
+    
+    match Command::new("cargo").arg("--version").current_dir(project_root).output().await {
+        Ok(output) if output.status.success() => {
+            health.cargo_available = true;
+            health.cargo_version = Some(String::from_utf8_lossy(&output.stdout).trim().to_string());
+        }
+        Ok(output) => {
+            health.error = Some(String::from_utf8_lossy(&output.stderr).trim().to_string());
+            return health;
+        }
+        Err(e) => {
+            health.error = Some(format!("Failed to run cargo: {}", e));
+            return health;
+        }
+    }
+    
+    let metadata = Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(project_root)
+        .output()
+        .await;
+    
+    match metadata {
+        Ok(output) if output.status.success() => {
+            health.metadata_ok = true;
+            if let Ok(json) = serde_json::from_slice::<Value>(&output.stdout) {
+                health.package_name = manifest_package(&json, project_root)
+                    .and_then(|p| p.get("name"))
+                    .and_then(|n| n.as_str())
+                    .map(|n| n.to_string());
+                if health.package_name.is_none() {
+                    health.workspace_members = json.get("packages")
+                        .and_then(|p| p.as_array())
+                        .map(|packages| packages.iter()
+                            .filter_map(|p| p.get("name").and_then(|n| n.as_str()))
+                            .map(|n| n.to_string())
+                            .collect())
+                        .unwrap_or_default();
+                }
+            }
+        }
+        Ok(output) => {
+            health.error = Some(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Err(e) => {
+            health.error = Some(format!("Failed to run cargo metadata: {}", e));
+        }
+    }
+    
+    debug!("Cargo health for {}: {:?}", project_root.display(), health);
+    health
+}
+
+/// Pick the package whose manifest lives in the project root, falling back to
+/// the first workspace package.
+pub fn root_package<'a>(metadata: &'a Value, project_root: &Path) -> Option<&'a Value> {
+    manifest_package(metadata, project_root)
+        .or_else(|| metadata.get("packages")?.as_array()?.first())
+}
+
+/// The package whose manifest lives in the project root, if it isn't a
+/// virtual workspace
+fn manifest_package<'a>(metadata: &'a Value, project_root: &Path) -> Option<&'a Value> {
+    let packages = metadata.get("packages")?.as_array()?;
+    let root_manifest = project_root.join("Cargo.toml");
+    let root_manifest = root_manifest.canonicalize().unwrap_or(root_manifest);
+    
+    packages.iter().find(|p| {
+        p.get("manifest_path")
+            .and_then(|m| m.as_str())
+            .is_some_and(|m| Path::new(m) == root_manifest)
+    })
+}
@@ -1,28 +1,206 @@
 use anyhow::{Result, Context};
 use serde_json::{json, Value};
+use std::fmt;
 use std::time::Duration;
 use tracing::{debug, warn};
 
+use crate::config::Overrides;
+use crate::server::McpServer;
+use crate::tool_access::{self, ToolCategory};
+
+/// Attempts made to connect to the daemon
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry, doubled for each later one
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// How long `stop_daemon` waits for the daemon to close its socket
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause between checks whether a stopping daemon is still up
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Why forwarding a request to the daemon failed
+#[derive(Debug)]
+pub enum DaemonError {
+    /// No response: nothing listens on the port or the connection dropped,
+    /// as when the daemon exits or restarts
+    Unreachable { url: String, source: reqwest::Error },
+    /// No response within the client's timeout; the daemon may still be
+    /// working on the request
+    Timeout { url: String, source: reqwest::Error },
+    /// The daemon answered with an HTTP error status
+    Status { status: u16, body: String },
+}
+
+impl fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DaemonError::Unreachable { url, source } => write!(f, "Daemon at {} is unreachable: {}", url, source),
+            DaemonError::Timeout { url, source } => write!(f, "Daemon at {} did not respond in time: {}", url, source),
+            DaemonError::Status { status, body } => write!(f, "Daemon returned HTTP {}: {}", status, body),
+        }
+    }
+}
+
+impl std::error::Error for DaemonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DaemonError::Unreachable { source, .. } | DaemonError::Timeout { source, .. } => Some(source),
+            DaemonError::Status { .. } => None,
+        }
+    }
+}
+
 pub struct HttpClient {
     base_url: String,
+    /// Shared for every request, so connections to the daemon are pooled and reused
     client: reqwest::Client,
+    /// Categories this client was started with. The daemon only enforces its
+    /// own, so tools outside these are refused before forwarding.
+    tool_categories: Vec<ToolCategory>,
 }
 
 impl HttpClient {
     pub fn new(port: u16) -> Self {
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(30))
             .build()
             .expect("Failed to create HTTP client");
             
         Self {
             base_url: format!("http://localhost:{}", port),
             client,
+            tool_categories: ToolCategory::ALL.to_vec(),
+        }
+    }
+    
+    /// Only forward calls to tools in `categories`, and only list those
+    pub fn with_tool_categories(mut self, categories: Vec<ToolCategory>) -> Self {
+        self.tool_categories = categories;
+        self
+    }
+    
+    /// The error response to a request for a tool outside this client's
+    /// categories, shaped like the daemon's own refusal
+    fn refusal(&self, method: &str, params: Option<&Value>, id: Option<&Value>) -> Option<Value> {
+        let (tool, context) = match method {
+            "tools/call" => (params.and_then(|p| p.get("name")).and_then(|n| n.as_str())?, "Tool execution failed"),
+            "initialize" | "tools/list" | "resources/list" | "resources/read" | "prompts/list" | "prompts/get" | "completion/complete" => return None,
+            method => (method, "Command failed"),
+        };
+        let e = tool_access::check_enabled(tool, &self.tool_categories).err()?;
+        Some(json!({
+            "jsonrpc": "2.0",
+            "id": id.cloned().unwrap_or(Value::Null),
+            "error": {
+                "code": -32603,
+                "message": format!("{}: {}", context, e)
+            }
+        }))
+    }
+    
+    /// Drop the tools outside this client's categories from a `tools/list` response
+    fn filter_tools(&self, response: &mut Value) {
+        if let Some(tools) = response.pointer_mut("/result/tools").and_then(Value::as_array_mut) {
+            tools.retain(|tool| tool["name"].as_str()
+                .is_some_and(|name| tool_access::disabled_category(name, &self.tool_categories).is_none()));
+        }
+    }
+    
+    /// Send a JSON-RPC batch to `/jsonrpc` as it is, answering the entries
+    /// for tools outside this client's categories here instead. Responses
+    /// keep the batch's order.
+    async fn forward_batch(&self, batch: Vec<Value>) -> Result<String> {
+        let method = |message: &Value| message.get("method").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+        // Per entry, the response it gets here, if refused
+        let mut refusals = Vec::new();
+        let mut forwarded = Vec::new();
+        for message in batch {
+            match self.refusal(&method(&message), message.get("params"), message.get("id")) {
+                // A refused notification gets no response either way
+                Some(refusal) => refusals.push(Some(message.get("id").map(|_| refusal))),
+                None => {
+                    refusals.push(None);
+                    forwarded.push(message);
+                }
+            }
+        }
+        
+        let mut answers = Vec::new();
+        if !forwarded.is_empty() || refusals.is_empty() {
+            let body = Value::Array(forwarded.clone());
+            let response = self.send(|| self.client.post(format!("{}/jsonrpc", self.base_url)).json(&body)).await?;
+            let response_text = response.text().await
+                .context("Failed to read HTTP response")?;
+            // Empty when only notifications were forwarded
+            if !response_text.is_empty() {
+                match serde_json::from_str::<Value>(&response_text).context("Invalid batch response from daemon")? {
+                    Value::Array(list) => answers = list,
+                    // An empty batch is answered with one error, not an array
+                    answer => return Ok(answer.to_string()),
+                }
+            }
+        }
+        
+        // The daemon answers every forwarded entry but notifications, in order
+        let mut answers = answers.into_iter();
+        let mut forwarded = forwarded.iter();
+        let mut responses = Vec::new();
+        for refusal in refusals {
+            match refusal {
+                Some(refusal) => responses.extend(refusal),
+                None => {
+                    let Some(message) = forwarded.next() else { break };
+                    if message.is_object() && message.get("id").is_none() {
+                        continue;
+                    }
+                    let Some(mut answer) = answers.next() else { continue };
+                    if method(message) == "tools/list" {
+                        self.filter_tools(&mut answer);
+                    }
+                    responses.push(answer);
+                }
+            }
         }
+        
+        if responses.is_empty() {
+            return Ok(String::new());
+        }
+        serde_json::to_string(&responses).context("Failed to serialize response")
+    }
+    
+    /// Send the request `build` makes, retrying with backoff while no
+    /// connection to the daemon can be opened. Failures after the request
+    /// may have reached the daemon, timeouts included, are not retried.
+    async fn send(&self, build: impl Fn() -> reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut attempt = 1;
+        let response = loop {
+            match build().send().await {
+                Ok(response) => break response,
+                Err(e) if attempt < MAX_ATTEMPTS && e.is_connect() => {
+                    let delay = RETRY_BACKOFF * 2u32.pow(attempt - 1);
+                    debug!("Request to daemon failed ({}), retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) if e.is_timeout() => return Err(DaemonError::Timeout { url: self.base_url.clone(), source: e }.into()),
+                Err(e) => return Err(DaemonError::Unreachable { url: self.base_url.clone(), source: e }.into()),
+            }
+        };
+        
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(DaemonError::Status { status, body }.into());
+        }
+        Ok(response)
     }
     
     pub async fn is_server_running(&self) -> bool {
-        match self.client.get(&format!("{}/", self.base_url)).send().await {
+        match self.client.get(format!("{}/", self.base_url)).send().await {
             Ok(response) => response.status().is_success(),
             Err(_) => false,
         }
@@ -31,9 +209,18 @@ impl HttpClient {
     pub async fn handle_jsonrpc_request(&self, request: &str) -> Result<String> {
         debug!("Forwarding JSON-RPC request to HTTP server");
         
-        // Parse the request to determine the method
-        let request_json: Value = serde_json::from_str(request)
-            .context("Failed to parse JSON-RPC request")?;
+        // Parse the request to determine the method; malformed input is
+        // answered here rather than sent on to the daemon
+        let request_json: Value = match serde_json::from_str(request) {
+            Ok(value) => value,
+            Err(e) => return serde_json::to_string(&McpServer::parse_error(request, &e)).context("Failed to serialize response"),
+        };
+            
+        // Batches go to `/jsonrpc` whole, to be answered as the daemon's own
+        // stdin would answer them
+        if let Value::Array(batch) = request_json {
+            return self.forward_batch(batch).await;
+        }
             
         let method = request_json.get("method")
             .and_then(|m| m.as_str())
@@ -41,54 +228,21 @@ impl HttpClient {
             
         let params = request_json.get("params").cloned();
         let id = request_json.get("id").cloned();
+        if let Some(refusal) = self.refusal(method, params.as_ref(), id.as_ref()) {
+            return serde_json::to_string(&refusal).context("Failed to serialize response");
+        }
         
         // Route to appropriate HTTP endpoint
+        let body = params.clone().unwrap_or(json!({}));
+        let url = |path: &str| format!("{}/{}", self.base_url, path);
         let response = match method {
-            "initialize" => {
-                self.client
-                    .post(&format!("{}/initialize", self.base_url))
-                    .send()
-                    .await?
-            }
-            "tools/list" => {
-                self.client
-                    .get(&format!("{}/tools/list", self.base_url))
-                    .send()
-                    .await?
-            }
-            "tools/call" => {
-                self.client
-                    .post(&format!("{}/tools/call", self.base_url))
-                    .json(&params.unwrap_or(json!({})))
-                    .send()
-                    .await?
-            }
-            "resources/list" => {
-                self.client
-                    .get(&format!("{}/resources/list", self.base_url))
-                    .send()
-                    .await?
-            }
-            "resources/read" => {
-                self.client
-                    .post(&format!("{}/resources/read", self.base_url))
-                    .json(&params.unwrap_or(json!({})))
-                    .send()
-                    .await?
-            }
-            "prompts/list" => {
-                self.client
-                    .get(&format!("{}/prompts/list", self.base_url))
-                    .send()
-                    .await?
-            }
-            "prompts/get" => {
-                self.client
-                    .post(&format!("{}/prompts/get", self.base_url))
-                    .json(&params.unwrap_or(json!({})))
-                    .send()
-                    .await?
-            }
+            "initialize" => self.send(|| self.client.post(url("initialize"))).await?,
+            "tools/list" => self.send(|| self.client.get(url("tools/list"))).await?,
+            "tools/call" => self.send(|| self.client.post(url("tools/call")).json(&body)).await?,
+            "resources/list" => self.send(|| self.client.get(url("resources/list"))).await?,
+            "resources/read" => self.send(|| self.client.post(url("resources/read")).json(&body)).await?,
+            "prompts/list" => self.send(|| self.client.get(url("prompts/list"))).await?,
+            "prompts/get" => self.send(|| self.client.post(url("prompts/get")).json(&body)).await?,
             _ => {
                 // Fallback to generic JSON-RPC endpoint
                 let full_request = json!({
@@ -98,55 +252,65 @@ impl HttpClient {
                     "params": params
                 });
                 
-                self.client
-                    .post(&format!("{}/jsonrpc", self.base_url))
-                    .json(&full_request)
-                    .send()
-                    .await?
+                self.send(|| self.client.post(url("jsonrpc")).json(&full_request)).await?
             }
         };
         
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!("HTTP request failed with status {}: {}", status, error_text);
-        }
-        
-        let response_text = response.text().await
+        let mut response_text = response.text().await
             .context("Failed to read HTTP response")?;
+        if method == "tools/list" {
+            if let Ok(mut listing) = serde_json::from_str::<Value>(&response_text) {
+                self.filter_tools(&mut listing);
+                response_text = listing.to_string();
+            }
+        }
             
         debug!("Received response from HTTP server");
-        Ok(response_text)
+        Ok(Self::restore_id(&response_text, id))
     }
     
-    pub async fn start_daemon(&self, project_path: &str) -> Result<()> {
+    /// The REST endpoints build their own request with an internal id, so put
+    /// the caller's original id (string, number or null) back on the response.
+    pub fn restore_id(response_text: &str, id: Option<Value>) -> String {
+        match serde_json::from_str::<Value>(response_text) {
+            Ok(mut response) if response.is_object() => {
+                response["id"] = id.unwrap_or(Value::Null);
+                response.to_string()
+            }
+            _ => response_text.to_string(),
+        }
+    }
+    
+    /// Spawn a daemon serving `project_path`, with the CLI `overrides` passed
+    /// in its environment, and wait for it to answer. Returns its PID, or
+    /// `None` when a server was already running.
+    pub async fn start_daemon(&self, project_path: &str, overrides: &Overrides) -> Result<Option<u32>> {
         if self.is_server_running().await {
             warn!("Server is already running on {}", self.base_url);
-            return Ok(());
+            return Ok(None);
         }
         
         // Extract port from base_url
-        let port = self.base_url.split(':').last()
+        let port = self.base_url.split(':').next_back()
             .and_then(|p| p.parse::<u16>().ok())
             .unwrap_or(3000);
             
         // Start the daemon process
         let mut cmd = tokio::process::Command::new(std::env::current_exe()?);
-        cmd.args(&[
+        cmd.args([
             "--server",
             "--port", &port.to_string(),
             "--project-path", project_path
         ]);
+        cmd.envs(overrides.env_vars());
         
         // Spawn as daemon (detached from parent)
         #[cfg(unix)]
-        {
-            use std::os::unix::process::CommandExt;
-            cmd.process_group(0);
-        }
+        cmd.process_group(0);
         
         let child = cmd.spawn()
             .context("Failed to start daemon process")?;
+        let pid = child.id();
             
         // Give it a moment to start
         tokio::time::sleep(Duration::from_secs(3)).await;
@@ -155,7 +319,7 @@ impl HttpClient {
         for attempt in 1..=10 {
             if self.is_server_running().await {
                 println!("✅ MCP server daemon started successfully on {}", self.base_url);
-                return Ok(());
+                return Ok(pid);
             }
             
             if attempt < 10 {
@@ -166,15 +330,23 @@ impl HttpClient {
         anyhow::bail!("Failed to start daemon - server not responding after 10 attempts");
     }
     
+    /// Ask the daemon to shut down through `POST /shutdown` and wait until it
+    /// stops accepting connections
     pub async fn stop_daemon(&self) -> Result<()> {
         if !self.is_server_running().await {
             println!("Server is not running");
             return Ok(());
         }
         
-        // For now, we'll just indicate that manual stopping is needed
-        // In a production version, you might implement a shutdown endpoint
-        println!("To stop the daemon, run: pkill -f 'mcp-rust-analyzer.*--server'");
+        self.send(|| self.client.post(format!("{}/shutdown", self.base_url))).await?;
+        
+        let deadline = tokio::time::Instant::now() + STOP_TIMEOUT;
+        while self.is_server_running().await {
+            if tokio::time::Instant::now() >= deadline {
+                anyhow::bail!("Daemon at {} still running {:?} after being asked to shut down", self.base_url, STOP_TIMEOUT);
+            }
+            tokio::time::sleep(STOP_POLL_INTERVAL).await;
+        }
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,50 +1,50 @@
 use anyhow::Result;
 use axum::{
-    extract::{Json, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Json, Query, State,
+    },
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::{json, Value};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{oneshot, Mutex, RwLock};
 use tower_http::cors::CorsLayer;
-use tracing::info;
+use tracing::{debug, info};
 
+use crate::diagnostics_stream::{DiagnosticsStream, STREAM_BUFFER};
+use crate::run_example::{self, OutputLine};
 use crate::server::McpServer;
+use crate::tool_access;
+
+/// Tools whose cargo output `/ws/run` streams
+const STREAMED_TOOLS: &[&str] = &["run_example", "run_benchmark"];
 
 #[derive(Clone)]
 pub struct AppState {
     mcp_server: Arc<RwLock<McpServer>>,
+    /// Fired once by `POST /shutdown`
+    shutdown: Arc<Mutex<Option<oneshot::Sender<()>>>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct JsonRpcRequest {
-    jsonrpc: String,
-    id: Option<Value>,
-    method: String,
-    params: Option<Value>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct JsonRpcResponse {
-    jsonrpc: String,
-    id: Option<Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    result: Option<Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<Value>,
-}
-
+/// Serve until `POST /shutdown`, then let requests in flight finish and stop
+/// rust-analyzer before returning
 pub async fn start_http_server(mcp_server: McpServer, port: u16) -> Result<()> {
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
     let state = AppState {
         mcp_server: Arc::new(RwLock::new(mcp_server)),
+        shutdown: Arc::new(Mutex::new(Some(shutdown_tx))),
     };
+    let mcp_server = state.mcp_server.clone();
 
     let app = Router::new()
         .route("/", get(health_check))
+        .route("/shutdown", post(handle_shutdown))
         .route("/jsonrpc", post(handle_jsonrpc))
         .route("/initialize", post(handle_initialize))
         .route("/tools/list", get(handle_tools_list))
@@ -53,6 +53,9 @@ pub async fn start_http_server(mcp_server: McpServer, port: u16) -> Result<()> {
         .route("/resources/read", post(handle_resources_read))
         .route("/prompts/list", get(handle_prompts_list))
         .route("/prompts/get", post(handle_prompts_get))
+        .route("/ws/diagnostics", get(handle_diagnostics_ws))
+        .route("/ws/symbols", get(handle_symbols_ws))
+        .route("/ws/run", get(handle_run_ws))
         .layer(CorsLayer::permissive())
         .with_state(state);
 
@@ -60,7 +63,14 @@ pub async fn start_http_server(mcp_server: McpServer, port: u16) -> Result<()> {
     info!("HTTP server listening on http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        })
+        .await?;
+
+    info!("HTTP server stopped, shutting down rust-analyzer");
+    mcp_server.read().await.analyzer().shutdown_lsp().await;
 
     Ok(())
 }
@@ -73,45 +83,58 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
+async fn handle_shutdown(State(state): State<AppState>) -> impl IntoResponse {
+    if let Some(shutdown) = state.shutdown.lock().await.take() {
+        info!("Shutdown requested");
+        let _ = shutdown.send(());
+    }
+    Json(json!({ "status": "shutting_down" }))
+}
+
 async fn handle_jsonrpc(
     State(state): State<AppState>,
     Json(request): Json<Value>,
-) -> impl IntoResponse {
+) -> Response {
     let request_str = serde_json::to_string(&request).unwrap_or_default();
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
     
     match state.mcp_server.read().await.handle_request(&request_str).await {
+        // A notification, or a batch of nothing else, gets no response
+        Ok(response) if response.is_empty() => StatusCode::NO_CONTENT.into_response(),
         Ok(response) => {
             match serde_json::from_str::<Value>(&response) {
-                Ok(json_response) => (StatusCode::OK, Json(json_response)),
+                Ok(json_response) => (StatusCode::OK, Json(json_response)).into_response(),
                 Err(_) => (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(json!({
                         "jsonrpc": "2.0",
+                        "id": id,
                         "error": {
                             "code": -32603,
                             "message": "Invalid response format"
                         }
                     }))
-                ),
+                ).into_response(),
             }
         }
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
                 "jsonrpc": "2.0",
+                "id": id,
                 "error": {
                     "code": -32603,
                     "message": format!("Internal error: {}", e)
                 }
             }))
-        ),
+        ).into_response(),
     }
 }
 
 async fn handle_initialize(State(state): State<AppState>) -> impl IntoResponse {
     let request = json!({
         "jsonrpc": "2.0",
-        "id": 1,
+        "id": McpServer::internal_request_id(),
         "method": "initialize",
         "params": {}
     });
@@ -122,7 +145,7 @@ async fn handle_initialize(State(state): State<AppState>) -> impl IntoResponse {
 async fn handle_tools_list(State(state): State<AppState>) -> impl IntoResponse {
     let request = json!({
         "jsonrpc": "2.0",
-        "id": 1,
+        "id": McpServer::internal_request_id(),
         "method": "tools/list",
         "params": {}
     });
@@ -136,7 +159,7 @@ async fn handle_tools_call(
 ) -> impl IntoResponse {
     let request = json!({
         "jsonrpc": "2.0",
-        "id": 1,
+        "id": McpServer::internal_request_id(),
         "method": "tools/call",
         "params": params
     });
@@ -147,7 +170,7 @@ async fn handle_tools_call(
 async fn handle_resources_list(State(state): State<AppState>) -> impl IntoResponse {
     let request = json!({
         "jsonrpc": "2.0",
-        "id": 1,
+        "id": McpServer::internal_request_id(),
         "method": "resources/list",
         "params": {}
     });
@@ -161,7 +184,7 @@ async fn handle_resources_read(
 ) -> impl IntoResponse {
     let request = json!({
         "jsonrpc": "2.0",
-        "id": 1,
+        "id": McpServer::internal_request_id(),
         "method": "resources/read",
         "params": params
     });
@@ -172,7 +195,7 @@ async fn handle_resources_read(
 async fn handle_prompts_list(State(state): State<AppState>) -> impl IntoResponse {
     let request = json!({
         "jsonrpc": "2.0",
-        "id": 1,
+        "id": McpServer::internal_request_id(),
         "method": "prompts/list",
         "params": {}
     });
@@ -186,10 +209,202 @@ async fn handle_prompts_get(
 ) -> impl IntoResponse {
     let request = json!({
         "jsonrpc": "2.0",
-        "id": 1,
+        "id": McpServer::internal_request_id(),
         "method": "prompts/get",
         "params": params
     });
     
     handle_jsonrpc(State(state), Json(request)).await
-}
\ No newline at end of file
+}
+
+async fn handle_diagnostics_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> Response {
+    let server = state.mcp_server.read().await;
+    // Runs `cargo check` like `get_diagnostics`, so it needs the same categories
+    if let Err(e) = tool_access::check_enabled("get_diagnostics", &server.analyzer().config().tool_categories) {
+        return (StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+    let project_root = server.project_root().to_path_buf();
+    ws.on_upgrade(move |socket| stream_diagnostics(socket, project_root))
+}
+
+/// Forward `cargo check` diagnostics to the client as they are produced.
+/// A frame is only pulled from the stream once the previous one has been
+/// written to the socket, so a slow client throttles cargo instead of growing
+/// a buffer. The build is killed if the client goes away before the summary.
+async fn stream_diagnostics(mut socket: WebSocket, project_root: PathBuf) {
+    let mut stream = match DiagnosticsStream::start(&project_root, STREAM_BUFFER) {
+        Ok(stream) => stream,
+        Err(e) => {
+            let frame = json!({ "type": "error", "message": e.to_string() });
+            let _ = socket.send(Message::Text(frame.to_string())).await;
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            frame = stream.next() => {
+                let Some(frame) = frame else { break };
+                let is_summary = frame["type"] == "summary";
+                if socket.send(Message::Text(frame.to_string())).await.is_err() {
+                    debug!("Diagnostics client disconnected, stopping cargo check");
+                    stream.cancel().await;
+                    return;
+                }
+                if is_summary {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                if matches!(message, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    debug!("Diagnostics client closed the stream, stopping cargo check");
+                    stream.cancel().await;
+                    return;
+                }
+            }
+        }
+    }
+
+    let _ = socket.close().await;
+}
+
+#[derive(Debug, Deserialize)]
+struct SymbolQuery {
+    #[serde(default)]
+    query: String,
+}
+
+async fn handle_symbols_ws(
+    ws: WebSocketUpgrade,
+    Query(params): Query<SymbolQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| stream_symbols(socket, state, params.query))
+}
+
+/// Forward `workspace/symbol` results as `{"type": "symbols", ...}` frames as
+/// rust-analyzer reports partial results, then a `{"type": "summary", ...}`
+/// frame. Without partial-result support the whole answer is one frame.
+async fn stream_symbols(mut socket: WebSocket, state: AppState, query: String) {
+    let started = std::time::Instant::now();
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<Value>>(STREAM_BUFFER);
+
+    let producer = async {
+        let server = state.mcp_server.read().await;
+        server.analyzer().workspace_symbols_streaming(&query, tx).await
+    };
+    let consumer = async {
+        while let Some(symbols) = rx.recv().await {
+            let frame = json!({ "type": "symbols", "symbols": symbols });
+            if socket.send(Message::Text(frame.to_string())).await.is_err() {
+                debug!("Symbol client disconnected");
+                // A closed channel makes the producer cancel the request
+                rx.close();
+                return false;
+            }
+        }
+        true
+    };
+    let (result, connected) = tokio::join!(producer, consumer);
+    if !connected {
+        return;
+    }
+
+    let frame = match result {
+        Ok((total, partial)) => json!({
+            "type": "summary",
+            "total": total,
+            "partial_results": partial,
+            "duration_ms": started.elapsed().as_millis() as u64
+        }),
+        Err(e) => json!({ "type": "error", "message": e.to_string() }),
+    };
+    let _ = socket.send(Message::Text(frame.to_string())).await;
+    let _ = socket.close().await;
+}
+
+/// The first frame of a `/ws/run` stream, shaped like `tools/call` params
+#[derive(Debug, Deserialize)]
+struct RunRequest {
+    name: String,
+    #[serde(default)]
+    arguments: Option<Value>,
+}
+
+async fn handle_run_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| stream_run(socket, state))
+}
+
+/// Run the tool named in the client's first frame, forwarding each line cargo
+/// prints as an `{"type": "output", "stream", "line"}` frame, then the tool's
+/// result as a `{"type": "result", "result": ...}` frame. Disconnecting kills
+/// the run, which is otherwise bounded by the tool's own timeout.
+async fn stream_run(mut socket: WebSocket, state: AppState) {
+    let request = match socket.recv().await {
+        Some(Ok(Message::Text(text))) => serde_json::from_str::<RunRequest>(&text).map_err(|e| e.to_string()),
+        Some(Ok(_)) => Err("Expected a text frame with the tool's name and arguments".to_string()),
+        None | Some(Err(_)) => return,
+    };
+    let request = match request {
+        Ok(request) if STREAMED_TOOLS.contains(&request.name.as_str()) => request,
+        other => {
+            let message = match other {
+                Ok(request) => format!("{} does not stream output; expected one of {}", request.name, STREAMED_TOOLS.join(", ")),
+                Err(message) => message,
+            };
+            let frame = json!({ "type": "error", "message": message });
+            let _ = socket.send(Message::Text(frame.to_string())).await;
+            return;
+        }
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<OutputLine>(STREAM_BUFFER);
+    let server = state.mcp_server.read().await;
+    let run = run_example::stream_output(tx, server.call_tool(&request.name, request.arguments));
+    tokio::pin!(run);
+
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            line = rx.recv() => {
+                let Some(line) = line else { continue };
+                let frame = json!({ "type": "output", "stream": line.stream, "line": line.line });
+                if socket.send(Message::Text(frame.to_string())).await.is_err() {
+                    debug!("Run client disconnected, stopping {}", request.name);
+                    // A closed channel makes the run kill cargo and return
+                    rx.close();
+                    let _ = run.await;
+                    return;
+                }
+            }
+            message = socket.recv() => {
+                if matches!(message, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    debug!("Run client closed the stream, stopping {}", request.name);
+                    rx.close();
+                    let _ = run.await;
+                    return;
+                }
+            }
+        }
+    };
+
+    // Lines read just before cargo exited
+    while let Some(line) = rx.recv().await {
+        let frame = json!({ "type": "output", "stream": line.stream, "line": line.line });
+        if socket.send(Message::Text(frame.to_string())).await.is_err() {
+            return;
+        }
+    }
+    let frame = match result {
+        Ok(result) => json!({ "type": "result", "result": result }),
+        Err(e) => json!({ "type": "error", "message": e.to_string() }),
+    };
+    let _ = socket.send(Message::Text(frame.to_string())).await;
+    let _ = socket.close().await;
+}
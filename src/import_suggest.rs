@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::module_graph;
+use crate::reexports;
+use crate::source_files;
+use crate::workspace_edit::uri_to_path;
+
+/// Commonly imported std items, by name
+pub(crate) const STD_ITEMS: &[(&str, &str, &str)] = &[
+    ("HashMap", "std::collections::HashMap", "struct"),
+    ("HashSet", "std::collections::HashSet", "struct"),
+    ("BTreeMap", "std::collections::BTreeMap", "struct"),
+    ("BTreeSet", "std::collections::BTreeSet", "struct"),
+    ("VecDeque", "std::collections::VecDeque", "struct"),
+    ("BinaryHeap", "std::collections::BinaryHeap", "struct"),
+    ("Entry", "std::collections::hash_map::Entry", "enum"),
+    ("Rc", "std::rc::Rc", "struct"),
+    ("Arc", "std::sync::Arc", "struct"),
+    ("Mutex", "std::sync::Mutex", "struct"),
+    ("RwLock", "std::sync::RwLock", "struct"),
+    ("OnceLock", "std::sync::OnceLock", "struct"),
+    ("mpsc", "std::sync::mpsc", "module"),
+    ("AtomicBool", "std::sync::atomic::AtomicBool", "struct"),
+    ("AtomicUsize", "std::sync::atomic::AtomicUsize", "struct"),
+    ("AtomicU64", "std::sync::atomic::AtomicU64", "struct"),
+    ("Ordering", "std::cmp::Ordering", "enum"),
+    ("Reverse", "std::cmp::Reverse", "struct"),
+    ("Cell", "std::cell::Cell", "struct"),
+    ("RefCell", "std::cell::RefCell", "struct"),
+    ("Cow", "std::borrow::Cow", "enum"),
+    ("Borrow", "std::borrow::Borrow", "trait"),
+    ("Path", "std::path::Path", "struct"),
+    ("PathBuf", "std::path::PathBuf", "struct"),
+    ("File", "std::fs::File", "struct"),
+    ("fs", "std::fs", "module"),
+    ("io", "std::io", "module"),
+    ("Read", "std::io::Read", "trait"),
+    ("Write", "std::io::Write", "trait"),
+    ("BufRead", "std::io::BufRead", "trait"),
+    ("BufReader", "std::io::BufReader", "struct"),
+    ("BufWriter", "std::io::BufWriter", "struct"),
+    ("Duration", "std::time::Duration", "struct"),
+    ("Instant", "std::time::Instant", "struct"),
+    ("SystemTime", "std::time::SystemTime", "struct"),
+    ("Display", "std::fmt::Display", "trait"),
+    ("Debug", "std::fmt::Debug", "trait"),
+    ("fmt", "std::fmt", "module"),
+    ("FromStr", "std::str::FromStr", "trait"),
+    ("Hash", "std::hash::Hash", "trait"),
+    ("Hasher", "std::hash::Hasher", "trait"),
+    ("PhantomData", "std::marker::PhantomData", "struct"),
+    ("Pin", "std::pin::Pin", "struct"),
+    ("Future", "std::future::Future", "trait"),
+    ("Deref", "std::ops::Deref", "trait"),
+    ("DerefMut", "std::ops::DerefMut", "trait"),
+    ("Add", "std::ops::Add", "trait"),
+    ("Range", "std::ops::Range", "struct"),
+    ("Any", "std::any::Any", "trait"),
+    ("TypeId", "std::any::TypeId", "struct"),
+    ("Error", "std::error::Error", "trait"),
+    ("NonZeroUsize", "std::num::NonZeroUsize", "struct"),
+    ("ParseIntError", "std::num::ParseIntError", "struct"),
+    ("Command", "std::process::Command", "struct"),
+    ("Stdio", "std::process::Stdio", "struct"),
+    ("thread", "std::thread", "module"),
+    ("env", "std::env", "module"),
+    ("mem", "std::mem", "module"),
+    ("Infallible", "std::convert::Infallible", "enum"),
+    ("TryFrom", "std::convert::TryFrom", "trait"),
+    ("Iterator", "std::iter::Iterator", "trait"),
+    ("FromIterator", "std::iter::FromIterator", "trait"),
+    ("Peekable", "std::iter::Peekable", "struct"),
+    ("SocketAddr", "std::net::SocketAddr", "enum"),
+    ("TcpListener", "std::net::TcpListener", "struct"),
+    ("TcpStream", "std::net::TcpStream", "struct"),
+    ("IpAddr", "std::net::IpAddr", "enum"),
+];
+
+/// What kind of name rustc could not find
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameKind {
+    Type,
+    Value,
+    Macro,
+    Trait,
+    Any,
+}
+
+impl NameKind {
+    /// Whether an item of `kind` can satisfy a lookup for this kind of name
+    fn accepts(self, kind: &str) -> bool {
+        if kind == "unknown" {
+            return true;
+        }
+        match self {
+            NameKind::Type => matches!(kind, "struct" | "enum" | "union" | "trait" | "type_alias" | "module"),
+            // Tuple and unit structs are values too
+            NameKind::Value => matches!(kind, "function" | "constant" | "static" | "struct" | "module"),
+            NameKind::Macro => kind == "macro",
+            NameKind::Trait => kind == "trait",
+            NameKind::Any => true,
+        }
+    }
+}
+
+/// One way to bring an unresolved name into scope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSuggestion {
+    /// Full path for the `use` declaration
+    pub path: String,
+    pub kind: String,
+    /// `workspace`, `reexport`, `lsp` or `std`
+    pub source: String,
+    pub score: i32,
+    /// LSP `TextEdit` inserting the `use` declaration
+    pub edit: Value,
+}
+
+/// Extract the missing name and its kind from a rustc "cannot find" message
+pub fn unresolved_name(message: &str) -> Option<(NameKind, String)> {
+    let patterns: &[(&str, NameKind)] = &[
+        ("cannot find type `", NameKind::Type),
+        ("cannot find struct, variant or union type `", NameKind::Type),
+        ("cannot find trait `", NameKind::Trait),
+        ("cannot find derive macro `", NameKind::Macro),
+        ("cannot find attribute `", NameKind::Macro),
+        ("cannot find macro `", NameKind::Macro),
+        ("cannot find function `", NameKind::Value),
+        ("cannot find function, tuple struct or tuple variant `", NameKind::Value),
+        ("cannot find value `", NameKind::Value),
+        ("cannot find `", NameKind::Any),
+        ("use of undeclared type `", NameKind::Type),
+        ("use of undeclared crate or module `", NameKind::Any),
+        ("use of unresolved module or unlinked crate `", NameKind::Any),
+        ("unresolved import `", NameKind::Any),
+    ];
+
+    for (prefix, kind) in patterns {
+        if let Some(start) = message.find(prefix) {
+            let rest = &message[start + prefix.len()..];
+            let name = rest.split('`').next()?;
+            // `unresolved import `foo::Bar`` -> `Bar`
+            let name = name.rsplit("::").next().unwrap_or(name);
+            return (!name.is_empty()).then(|| (*kind, name.to_string()));
+        }
+    }
+    None
+}
+
+/// Identifier under a 0-based position
+pub fn identifier_at(source: &str, line: usize, column: usize) -> Option<String> {
+    let chars: Vec<char> = source.lines().nth(line)?.chars().collect();
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut start = column.min(chars.len());
+    if start == chars.len() || !is_ident(chars[start]) {
+        // Cursor just past the identifier
+        if start > 0 && is_ident(chars[start - 1]) {
+            start -= 1;
+        } else {
+            return None;
+        }
+    }
+    while start > 0 && is_ident(chars[start - 1]) {
+        start -= 1;
+    }
+    let end = start + chars[start..].iter().take_while(|c| is_ident(**c)).count();
+    Some(chars[start..end].iter().collect())
+}
+
+/// Candidate `use` paths for `name` in `file`, best first.
+///
+/// Items defined in the workspace come first, preferring shorter (re-exported)
+/// paths, followed by std. `lsp_symbols` are `workspace/symbol` results, if any.
+pub fn suggest_imports(
+    project_root: &Path,
+    file: &Path,
+    file_source: &str,
+    name: &str,
+    kind: NameKind,
+    lsp_symbols: &[Value],
+) -> Vec<ImportSuggestion> {
+    let edit_for = |path: &str| insert_use_edit(file_source, path);
+    let mut suggestions: Vec<ImportSuggestion> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut push = |path: String, item_kind: &str, source: &str, base: i32| {
+        if !kind.accepts(item_kind) || !seen.insert(path.clone()) {
+            return;
+        }
+        let depth = path.matches("::").count() as i32;
+        suggestions.push(ImportSuggestion {
+            edit: edit_for(&path),
+            score: base - depth * 5,
+            path,
+            kind: item_kind.to_string(),
+            source: source.to_string(),
+        });
+    };
+
+    let src = project_root.join("src");
+    for reexport in reexports::find_reexports(project_root) {
+        if reexport.exported.rsplit("::").next() == Some(name) {
+            let item_kind = reexport.definition.as_ref().map(|d| d.kind.as_str()).unwrap_or("unknown");
+            push(reexport.exported.clone(), item_kind, "reexport", 105);
+        }
+    }
+
+    for candidate in source_files::rust_files(&src) {
+        // Items defined in the file itself need no import
+        if candidate == file {
+            continue;
+        }
+        let Some(module) = reexports::module_path_for(&src, &candidate) else { continue };
+        let Ok(content) = std::fs::read_to_string(&candidate) else { continue };
+        let Some((line, item_kind)) = reexports::find_definition(&content, name) else { continue };
+        if !is_importable(&content, line, &item_kind) {
+            continue;
+        }
+        let path = if item_kind == "macro" {
+            // `#[macro_export]` macros live at the crate root
+            format!("crate::{}", name)
+        } else {
+            format!("{}::{}", module.join("::"), name)
+        };
+        push(path, &item_kind, "workspace", 100);
+    }
+
+    let own_file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    for symbol in lsp_symbols {
+        if symbol.get("name").and_then(|n| n.as_str()) != Some(name) {
+            continue;
+        }
+        // Methods, fields and variants aren't imported on their own
+        let item_kind = lsp_symbol_kind(symbol.get("kind").and_then(|k| k.as_u64()).unwrap_or(0));
+        if item_kind == "unknown" {
+            continue;
+        }
+        if symbol["location"]["uri"].as_str().is_some_and(|uri| uri_to_path(uri) == own_file) {
+            continue;
+        }
+        let Some(path) = symbol_use_path(symbol, project_root) else { continue };
+        push(path, item_kind, "lsp", 95);
+    }
+
+    for (std_name, path, item_kind) in STD_ITEMS {
+        if *std_name == name {
+            push(path.to_string(), item_kind, "std", 90);
+        }
+    }
+
+    suggestions.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    suggestions
+}
+
+/// Every path the `use` statements of `source` import, aliased ones included
+pub fn imported_paths(source: &str) -> HashSet<String> {
+    module_graph::use_statements(source).iter()
+        .flat_map(|(_, tree)| reexports::parse_use_tree(tree))
+        .map(|leaf| leaf.path.join("::"))
+        .collect()
+}
+
+/// Public items, and `macro_rules!` macros exported with `#[macro_export]`
+fn is_importable(source: &str, line: usize, kind: &str) -> bool {
+    let lines: Vec<&str> = source.lines().collect();
+    let Some(text) = lines.get(line - 1) else { return false };
+    if kind == "macro" {
+        return lines[line.saturating_sub(4)..line - 1].iter().any(|l| l.contains("#[macro_export"));
+    }
+    text.trim_start().starts_with("pub")
+}
+
+/// `use` path of a `workspace/symbol` result, from the crate and module of
+/// the file defining it: `crate::...` inside the project, the crate's name
+/// for dependencies and the standard library. Items of inline modules are
+/// placed at their file's module. Without a file under a crate's `src`, the
+/// symbol's `containerName` stands in for the module.
+pub fn symbol_use_path(symbol: &Value, project_root: &Path) -> Option<String> {
+    let name = symbol["name"].as_str()?;
+    let from_file = symbol["location"]["uri"].as_str().and_then(|uri| {
+        let file = uri_to_path(uri);
+        let (src, crate_name) = crate_src(&file, project_root)?;
+        let mut module = reexports::module_path_for(&src, &file)?;
+        module[0] = crate_name;
+        Some(module.join("::"))
+    });
+    let module = from_file.or_else(|| symbol["containerName"].as_str().filter(|c| !c.is_empty()).map(String::from))?;
+    Some(format!("{}::{}", module, name))
+}
+
+/// `src` directory of the crate `file` belongs to, and the name to import it by
+fn crate_src(file: &Path, project_root: &Path) -> Option<(PathBuf, String)> {
+    let roots = [Some(project_root.to_path_buf()), project_root.canonicalize().ok()];
+    if let Some(src) = roots.into_iter().flatten().map(|root| root.join("src")).find(|src| file.starts_with(src)) {
+        return Some((src, "crate".to_string()));
+    }
+    let src = file.ancestors().find(|dir| dir.file_name().is_some_and(|name| name == "src"))?;
+    let package = src.parent()?.file_name()?.to_str()?;
+    // Registry sources are unpacked as `name-version`, the sysroot's as `library/name`
+    let name = package.match_indices('-')
+        .find(|(at, _)| package[at + 1..].starts_with(|c: char| c.is_ascii_digit()))
+        .map_or(package, |(at, _)| &package[..at]);
+    Some((src.to_path_buf(), name.replace('-', "_")))
+}
+
+fn lsp_symbol_kind(kind: u64) -> &'static str {
+    match kind {
+        2 => "module",
+        5 | 23 => "struct",
+        10 => "enum",
+        11 => "trait",
+        12 => "function",
+        13 => "static",
+        14 => "constant",
+        26 => "type_alias",
+        _ => "unknown",
+    }
+}
+
+/// Edit inserting `use path;` after the existing top-level imports, or after
+/// the module docs and inner attributes when the file has none
+pub fn insert_use_edit(source: &str, path: &str) -> Value {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut insert_at = None;
+    let mut in_use = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let top_level = !line.starts_with(char::is_whitespace);
+        // A multi-line `use` keeps going until its closing `;`
+        if in_use || (top_level && (trimmed.starts_with("use ") || trimmed.starts_with("pub use "))) {
+            in_use = !trimmed.trim_end().ends_with(';');
+            insert_at = Some(i + 1);
+        }
+    }
+
+    let (line, new_text) = match insert_at {
+        Some(line) => (line, format!("use {};\n", path)),
+        None => {
+            let header = lines.iter()
+                .take_while(|l| {
+                    let t = l.trim();
+                    t.starts_with("//!") || t.starts_with("#![") || t.is_empty()
+                })
+                .count();
+            // Skip back over trailing blank lines so the import sits right after the header
+            let header = (0..header).rev().find(|&i| !lines[i].trim().is_empty()).map(|i| i + 1).unwrap_or(0);
+            let text = if header > 0 { format!("\nuse {};\n", path) } else { format!("use {};\n\n", path) };
+            (header, text)
+        }
+    };
+
+    json!({
+        "range": {
+            "start": { "line": line, "character": 0 },
+            "end": { "line": line, "character": 0 }
+        },
+        "newText": new_text
+    })
+}
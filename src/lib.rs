@@ -7,6 +7,71 @@ pub mod lsp_client;
 pub mod http_server;
 pub mod http_client;
 pub mod daemon_state;
+pub mod config;
+pub mod health;
+pub mod signature;
+pub mod cfg_eval;
+pub mod reexports;
+pub mod diagnostics_stream;
+pub mod feature_check;
+pub mod file_summary;
+pub mod source_files;
+pub mod macros;
+pub mod derives;
+pub mod markup;
+pub mod edit_check;
+pub mod workspace_edit;
+pub mod import_suggest;
+pub mod visibility;
+pub mod metrics_diff;
+pub mod duplicates;
+pub mod run_example;
+pub mod unhandled_results;
+pub mod entry_points;
+pub mod fix_plan;
+pub mod path_base;
+pub mod path_guard;
+pub mod code_actions;
+pub mod generics;
+pub mod module_graph;
+pub mod completion_list;
+pub mod tool_access;
+pub mod extract_module;
+pub mod async_audit;
+pub mod read_files;
+pub mod outdated;
+pub mod module_tree;
+pub mod analyzer_status;
+pub mod shadowing;
+pub mod related_tests;
+pub mod timings;
+pub mod toggle_async;
+pub mod feature_graph;
+pub mod param_validation;
+pub mod attributes;
+pub mod sessions;
+pub mod error_handling;
+pub mod lsp_passthrough;
+pub mod unresolved_imports;
+pub mod blast_radius;
+pub mod benchmarks;
+pub mod test_code_audit;
+pub mod formatting;
+pub mod trait_objects;
+pub mod sarif;
+pub mod api_surface;
+pub mod ffi;
+pub mod locations;
+pub mod missing_docs;
+pub mod outline;
+pub mod build_context;
+pub mod placement;
+pub mod manifest;
+pub mod complexity;
+pub mod symbol_search;
+pub mod call_hierarchy;
+pub mod type_hierarchy;
+pub mod framing;
 
 #[cfg(test)]
 mod tests {
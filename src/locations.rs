@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::workspace_edit::uri_to_path;
+
+/// A position rust-analyzer pointed at, in the tools' terms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    /// Relative to the project root when inside it, absolute otherwise
+    /// (dependencies, the standard library)
+    pub file: String,
+    /// 1-based
+    pub line: u32,
+    /// 1-based
+    pub column: u32,
+}
+
+impl Location {
+    /// Absolute path of the file, for reading it
+    pub fn path(&self, project_root: &Path) -> PathBuf {
+        project_root.join(&self.file)
+    }
+}
+
+/// Normalize a `Location | Location[] | LocationLink[] | null` response.
+/// Links point at their target's name (`targetSelectionRange`) rather than
+/// the whole item.
+pub fn from_lsp(result: &Value, project_root: &Path) -> Vec<Location> {
+    let canonical_root = project_root.canonicalize().ok();
+    let convert = |location: &Value| {
+        let uri = location.get("uri").or_else(|| location.get("targetUri"))?.as_str()?;
+        let start = &location.get("range").or_else(|| location.get("targetSelectionRange"))?["start"];
+        let path = uri_to_path(uri);
+        let relative = path.strip_prefix(project_root).ok()
+            .or_else(|| path.strip_prefix(canonical_root.as_ref()?).ok())
+            .unwrap_or(&path);
+        Some(Location {
+            file: relative.display().to_string(),
+            line: start["line"].as_u64()? as u32 + 1,
+            column: start["character"].as_u64()? as u32 + 1,
+        })
+    };
+    match result {
+        Value::Array(locations) => locations.iter().filter_map(convert).collect(),
+        Value::Null => Vec::new(),
+        location => convert(location).into_iter().collect(),
+    }
+}
@@ -7,17 +7,285 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, AsyncReadExt, BufReader, AsyncRe
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tracing::{info, debug, error};
-use tokio::sync::{oneshot, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex};
 use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::markup::ContentFormat;
+use crate::timings::{self, Phase};
+
+/// How long a request waits for its response unless the config or the call says otherwise
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `initialize` waits at least this long, since the server starts loading the workspace
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone)]
 pub struct LspClientConfig {
     pub server_path: String,
     pub server_args: Vec<String>,
     pub root_path: PathBuf,
+    /// Default wait for a response, per request
+    pub timeout: Duration,
+}
+
+/// Request timeout from `LSP_REQUEST_TIMEOUT_MS`, or `DEFAULT_REQUEST_TIMEOUT`
+/// when it is unset or not a number of milliseconds
+pub fn request_timeout_from_env() -> Duration {
+    std::env::var("LSP_REQUEST_TIMEOUT_MS").ok()
+        .and_then(|ms| ms.trim().parse().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_REQUEST_TIMEOUT)
+}
+
+/// The server didn't answer a request in time, as opposed to answering with
+/// an error. Callers can tell the two apart with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTimeout {
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for RequestTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Request timeout")
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
+/// In-flight requests waiting for a response from the server, keyed by id.
+///
+/// Shared between `send_request` and the reader task. Every entry is removed
+/// exactly once: by the reader when the response arrives, by the caller when
+/// it gives up, or by `fail_all` when the server goes away.
+#[derive(Clone, Default)]
+pub struct PendingRequests {
+    map: Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value>>>>>,
+    /// Receivers of `$/progress` partial results, keyed by `partialResultToken`.
+    /// Unbounded so a slow consumer can never stall the reader task.
+    partials: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Value>>>>,
+}
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Register interest in the response to `id`
+    pub async fn register(&self, id: u64) -> oneshot::Receiver<Result<Value>> {
+        let (tx, rx) = oneshot::channel();
+        self.map.lock().await.insert(id, tx);
+        rx
+    }
+    
+    /// Drop a registration without waiting, e.g. when the request could not be sent
+    pub async fn forget(&self, id: u64) {
+        self.map.lock().await.remove(&id);
+    }
+    
+    /// Receive the partial results the server reports for `token`
+    pub async fn register_partial(&self, token: &str) -> mpsc::UnboundedReceiver<Value> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.partials.lock().await.insert(token.to_string(), tx);
+        rx
+    }
+    
+    /// Stop routing partial results for `token`
+    pub async fn forget_partial(&self, token: &str) {
+        self.partials.lock().await.remove(token);
+    }
+    
+    /// Route a message from the server to its waiting caller. Returns false for
+    /// notifications, server-to-client requests and responses nobody waits for.
+    /// `$/progress` notifications carrying a registered partial-result token are
+    /// delivered to that token's receiver.
+    pub async fn dispatch(&self, message: &Value) -> bool {
+        // Server-initiated requests carry their own ids, which may collide with ours
+        if let Some(method) = message.get("method") {
+            if method != "$/progress" {
+                return false;
+            }
+            let params = message.get("params");
+            let token = params.and_then(|p| p.get("token")).map(|t| match t {
+                Value::String(t) => t.clone(),
+                other => other.to_string(),
+            });
+            let Some(token) = token else { return false };
+            return match self.partials.lock().await.get(&token) {
+                Some(tx) => tx.send(params.and_then(|p| p.get("value")).cloned().unwrap_or(Value::Null)).is_ok(),
+                None => false,
+            };
+        }
+        let Some(id) = message.get("id").and_then(|v| v.as_u64()) else {
+            return false;
+        };
+        
+        let Some(sender) = self.map.lock().await.remove(&id) else {
+            debug!("Dropping response for unknown or expired request {}", id);
+            return false;
+        };
+        
+        let response = match message.get("error") {
+            Some(error) => Err(anyhow::anyhow!("LSP error: {}", error)),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        sender.send(response).is_ok()
+    }
+    
+    /// Wait for the response to `id`. On timeout the registration is removed;
+    /// if the reader claimed it first, the response is already on its way and
+    /// is returned instead of reporting a timeout.
+    pub async fn wait(
+        &self,
+        id: u64,
+        mut rx: oneshot::Receiver<Result<Value>>,
+        timeout: Duration,
+    ) -> Result<Value> {
+        match tokio::time::timeout(timeout, &mut rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => bail!("Response channel closed"),
+            Err(_) => {
+                let still_pending = self.map.lock().await.remove(&id).is_some();
+                if still_pending {
+                    return Err(RequestTimeout { timeout }.into());
+                }
+                match rx.await {
+                    Ok(response) => response,
+                    Err(_) => bail!("Response channel closed"),
+                }
+            }
+        }
+    }
+    
+    /// Fail every waiting request, e.g. when the server's stdout closes
+    pub async fn fail_all(&self) {
+        self.map.lock().await.clear();
+        self.partials.lock().await.clear();
+    }
+    
+    /// Number of requests still waiting for a response
+    pub async fn len(&self) -> usize {
+        self.map.lock().await.len()
+    }
+    
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+/// How an `open` call must be reported to the server
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentSync {
+    /// First open: send `didOpen` with this version
+    Open(i32),
+    /// The file changed on disk since it was opened: send the full text at this version
+    Reload(i32),
+    /// Already open and unchanged on disk; the buffer may hold pushed edits
+    Unchanged,
+}
+
+struct OpenDocument {
+    version: i32,
+    /// Buffer as the server sees it, including edits pushed with `change`
+    text: String,
+    /// File content when the buffer was last synced from disk
+    disk: String,
+    /// Latest version an editor supplied with `replace` or `open_buffer`
+    caller_version: Option<i32>,
+}
+
+/// Text of the documents open on the server, mirrored so successive edits can
+/// be sent as ranges against a known version
+#[derive(Default)]
+pub struct Documents {
+    open: HashMap<String, OpenDocument>,
 }
 
-type ResponseMap = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value>>>>>;
+impl Documents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Record `disk_text` as the content of `uri`. Edits pushed since the last
+    /// sync survive until the file changes on disk, which then wins.
+    pub fn open(&mut self, uri: &str, disk_text: String) -> DocumentSync {
+        match self.open.get_mut(uri) {
+            None => {
+                self.open.insert(uri.to_string(), OpenDocument { version: 1, text: disk_text.clone(), disk: disk_text, caller_version: None });
+                DocumentSync::Open(1)
+            }
+            Some(doc) if doc.disk == disk_text => DocumentSync::Unchanged,
+            Some(doc) => {
+                doc.version += 1;
+                doc.text = disk_text.clone();
+                doc.disk = disk_text;
+                DocumentSync::Reload(doc.version)
+            }
+        }
+    }
+    
+    /// Apply LSP content changes (`{range, text}`, or `{text}` for the whole
+    /// document) in order and return the new version
+    pub fn change(&mut self, uri: &str, changes: &[Value]) -> Result<i32> {
+        let doc = self.open.get_mut(uri)
+            .ok_or_else(|| anyhow::anyhow!("Document is not open: {}", uri))?;
+        let mut text = doc.text.clone();
+        for change in changes {
+            let new_text = change.get("text").and_then(|t| t.as_str()).unwrap_or("");
+            text = match change.get("range") {
+                // Each change applies to the result of the previous one
+                Some(range) => crate::workspace_edit::apply_edits_to_text(
+                    &text,
+                    &[serde_json::json!({ "range": range, "newText": new_text })],
+                )?,
+                None => new_text.to_string(),
+            };
+        }
+        doc.text = text;
+        doc.version += 1;
+        Ok(doc.version)
+    }
+    
+    /// Record `text`, an editor's buffer at its own `version`, as the content
+    /// of `uri`, replacing any copy opened from `disk_text`
+    pub fn open_buffer(&mut self, uri: &str, version: i32, text: String, disk_text: String) {
+        self.open.insert(uri.to_string(), OpenDocument { version, text, disk: disk_text, caller_version: Some(version) });
+    }
+    
+    /// Replace the whole buffer of `uri` with `text` at the caller's `version`.
+    /// Returns false, leaving the buffer alone, when `version` is not newer
+    /// than one a caller supplied before, e.g. an update that arrived out of
+    /// order. Versions counted from disk syncs don't hold it back.
+    pub fn replace(&mut self, uri: &str, version: i32, text: String) -> Result<bool> {
+        let doc = self.open.get_mut(uri)
+            .ok_or_else(|| anyhow::anyhow!("Document is not open: {}", uri))?;
+        if doc.caller_version.is_some_and(|held| version <= held) {
+            return Ok(false);
+        }
+        doc.text = text;
+        doc.version = version;
+        doc.caller_version = Some(version);
+        Ok(true)
+    }
+    
+    pub fn close(&mut self, uri: &str) {
+        self.open.remove(uri);
+    }
+    
+    /// Current buffer of an open document
+    pub fn text(&self, uri: &str) -> Option<&str> {
+        self.open.get(uri).map(|doc| doc.text.as_str())
+    }
+    
+    pub fn version(&self, uri: &str) -> Option<i32> {
+        self.open.get(uri).map(|doc| doc.version)
+    }
+    
+    /// Latest version an editor supplied for `uri`, `None` until one has
+    pub fn caller_version(&self, uri: &str) -> Option<i32> {
+        self.open.get(uri).and_then(|doc| doc.caller_version)
+    }
+}
 
 pub struct LspClient {
     config: LspClientConfig,
@@ -25,7 +293,12 @@ pub struct LspClient {
     stdin: Option<Arc<Mutex<tokio::process::ChildStdin>>>,
     request_id: Arc<AtomicU64>,
     initialized: bool,
-    response_map: ResponseMap,
+    content_format: ContentFormat,
+    pending: PendingRequests,
+    documents: Documents,
+    /// Whether the server accepts range-based `didChange` events
+    /// (`TextDocumentSyncKind::Incremental`)
+    incremental_sync: bool,
     _reader_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
@@ -37,16 +310,26 @@ impl LspClient {
             stdin: None,
             request_id: Arc::new(AtomicU64::new(1)),
             initialized: false,
-            response_map: Arc::new(Mutex::new(HashMap::new())),
+            content_format: ContentFormat::default(),
+            pending: PendingRequests::new(),
+            documents: Documents::new(),
+            incremental_sync: false,
             _reader_handle: None,
         })
     }
     
+    /// Markup format to ask the server for first in hover/completion/signature docs
+    pub fn with_content_format(mut self, format: ContentFormat) -> Self {
+        self.content_format = format;
+        self
+    }
+    
     pub async fn initialize(&mut self) -> Result<Value> {
         // Start the LSP server process
         self.start_server().await?;
         
         // Send initialize request
+        let markup_formats = self.content_format.lsp_preference();
         let init_params = serde_json::json!({
             "processId": std::process::id(),
             "clientInfo": {
@@ -76,7 +359,7 @@ impl LspClient {
                         "didSave": true
                     },
                     "hover": {
-                        "contentFormat": ["markdown", "plaintext"],
+                        "contentFormat": markup_formats,
                         "dynamicRegistration": true
                     },
                     "completion": {
@@ -84,7 +367,7 @@ impl LspClient {
                         "completionItem": {
                             "snippetSupport": true,
                             "commitCharactersSupport": true,
-                            "documentationFormat": ["markdown", "plaintext"],
+                            "documentationFormat": markup_formats,
                             "deprecatedSupport": true,
                             "preselectSupport": true
                         },
@@ -100,7 +383,7 @@ impl LspClient {
                     "signatureHelp": {
                         "dynamicRegistration": true,
                         "signatureInformation": {
-                            "documentationFormat": ["markdown", "plaintext"],
+                            "documentationFormat": markup_formats,
                             "parameterInformation": {
                                 "labelOffsetSupport": true
                             }
@@ -111,6 +394,12 @@ impl LspClient {
                         "dynamicRegistration": true,
                         "linkSupport": true
                     },
+                    "callHierarchy": {
+                        "dynamicRegistration": true
+                    },
+                    "typeHierarchy": {
+                        "dynamicRegistration": true
+                    },
                     "codeAction": {
                         "dynamicRegistration": true,
                         "codeActionLiteralSupport": {
@@ -125,6 +414,10 @@ impl LspClient {
                                     "source.organizeImports"
                                 ]
                             }
+                        },
+                        "dataSupport": true,
+                        "resolveSupport": {
+                            "properties": ["edit"]
                         }
                     },
                     "publishDiagnostics": {
@@ -141,6 +434,11 @@ impl LspClient {
         let response = self.send_request("initialize", init_params).await?;
         self.initialized = true;
         
+        // `textDocumentSync` is either a TextDocumentSyncKind or options carrying one
+        let sync = &response["capabilities"]["textDocumentSync"];
+        let sync_kind = sync.as_u64().or_else(|| sync["change"].as_u64());
+        self.incremental_sync = sync_kind == Some(2);
+        
         // Send initialized notification
         self.send_notification("initialized", serde_json::json!({})).await?;
         
@@ -186,6 +484,10 @@ impl LspClient {
         self.send_request("textDocument/completion", params).await
     }
     
+    pub async fn completion_resolve(&mut self, item: Value) -> Result<Value> {
+        self.send_request("completionItem/resolve", item).await
+    }
+    
     pub async fn references(&mut self, params: Value) -> Result<Value> {
         self.send_request("textDocument/references", params).await
     }
@@ -202,6 +504,14 @@ impl LspClient {
         self.send_request("textDocument/implementation", params).await
     }
     
+    pub async fn goto_definition(&mut self, params: Value) -> Result<Value> {
+        self.send_request("textDocument/definition", params).await
+    }
+    
+    pub async fn document_symbol(&mut self, params: Value) -> Result<Value> {
+        self.send_request("textDocument/documentSymbol", params).await
+    }
+    
     pub async fn document_diagnostics(&mut self, params: Value) -> Result<Value> {
         self.send_request("textDocument/publishDiagnostics", params).await
     }
@@ -210,27 +520,150 @@ impl LspClient {
         self.send_request("textDocument/codeAction", params).await
     }
     
+    /// Fill in the `edit` of a code action that was returned without one
+    pub async fn code_action_resolve(&mut self, action: Value) -> Result<Value> {
+        self.send_request("codeAction/resolve", action).await
+    }
+    
+    pub async fn prepare_call_hierarchy(&mut self, params: Value) -> Result<Value> {
+        self.send_request("textDocument/prepareCallHierarchy", params).await
+    }
+    
+    /// Callers of a `CallHierarchyItem` returned by `prepare_call_hierarchy`
+    pub async fn incoming_calls(&mut self, item: Value) -> Result<Value> {
+        self.send_request("callHierarchy/incomingCalls", serde_json::json!({ "item": item })).await
+    }
+    
+    /// Callees of a `CallHierarchyItem` returned by `prepare_call_hierarchy`
+    pub async fn outgoing_calls(&mut self, item: Value) -> Result<Value> {
+        self.send_request("callHierarchy/outgoingCalls", serde_json::json!({ "item": item })).await
+    }
+    
+    pub async fn prepare_type_hierarchy(&mut self, params: Value) -> Result<Value> {
+        self.send_request("textDocument/prepareTypeHierarchy", params).await
+    }
+    
+    /// Supertypes of a `TypeHierarchyItem` returned by `prepare_type_hierarchy`
+    pub async fn supertypes(&mut self, item: Value) -> Result<Value> {
+        self.send_request("typeHierarchy/supertypes", serde_json::json!({ "item": item })).await
+    }
+    
+    /// Subtypes of a `TypeHierarchyItem` returned by `prepare_type_hierarchy`
+    pub async fn subtypes(&mut self, item: Value) -> Result<Value> {
+        self.send_request("typeHierarchy/subtypes", serde_json::json!({ "item": item })).await
+    }
+    
+    /// rust-analyzer's workspace and index status as text; `params` may name
+    /// a `textDocument` to include the crates it belongs to
+    pub async fn analyzer_status(&mut self, params: Value) -> Result<Value> {
+        self.send_request("rust-analyzer/analyzerStatus", params).await
+    }
+    
+    /// rust-analyzer's memory use per query as text
+    pub async fn memory_usage(&mut self) -> Result<Value> {
+        self.send_request("rust-analyzer/memoryUsage", Value::Null).await
+    }
+    
+    /// Open `file_path` on the server, or resync it when it changed on disk
+    /// since it was opened. Edits pushed with `did_change` are kept otherwise.
     pub async fn did_open(&mut self, file_path: &str) -> Result<()> {
         let uri = format!("file://{}", file_path);
-        let content = match tokio::fs::read_to_string(file_path).await {
-            Ok(content) => content,
-            Err(_) => String::new(), // If file doesn't exist, use empty content
-        };
+        // If file doesn't exist, use empty content
+        let content = tokio::fs::read_to_string(file_path).await.unwrap_or_default();
         
-        let params = serde_json::json!({
-            "textDocument": {
-                "uri": uri,
-                "languageId": "rust",
-                "version": 1,
-                "text": content
+        match self.documents.open(&uri, content.clone()) {
+            DocumentSync::Open(version) => {
+                let params = serde_json::json!({
+                    "textDocument": {
+                        "uri": uri,
+                        "languageId": "rust",
+                        "version": version,
+                        "text": content
+                    }
+                });
+                self.send_notification("textDocument/didOpen", params).await
+            }
+            DocumentSync::Reload(version) => {
+                let params = serde_json::json!({
+                    "textDocument": { "uri": uri, "version": version },
+                    "contentChanges": [{ "text": content }]
+                });
+                self.send_notification("textDocument/didChange", params).await
             }
+            DocumentSync::Unchanged => Ok(()),
+        }
+    }
+    
+    /// Push `changes` (`{range, text}` objects, applied in order) to the open
+    /// buffer of `file_path` and return the new document version. Servers
+    /// without incremental sync receive the resulting full text instead.
+    pub async fn did_change(&mut self, file_path: &str, changes: Vec<Value>) -> Result<i32> {
+        self.did_open(file_path).await?;
+        let uri = format!("file://{}", file_path);
+        let version = self.documents.change(&uri, &changes)?;
+        
+        let content_changes = if self.incremental_sync {
+            changes
+        } else {
+            vec![serde_json::json!({ "text": self.documents.text(&uri).unwrap_or("") })]
+        };
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri, "version": version },
+            "contentChanges": content_changes
         });
+        self.send_notification("textDocument/didChange", params).await?;
+        Ok(version)
+    }
+    
+    /// Replace the open buffer of `file_path` with `text`, an editor's unsaved
+    /// content at `version`. Returns whether it was sent: versions not newer
+    /// than the last one supplied are dropped.
+    pub async fn did_change_text(&mut self, file_path: &str, version: i32, text: String) -> Result<bool> {
+        let uri = format!("file://{}", file_path);
+        // The editor's first sync opens the document with its text and version,
+        // reopening it if it was opened from disk under versions of our own
+        if self.documents.caller_version(&uri).is_none() {
+            if self.documents.version(&uri).is_some() {
+                self.did_close(file_path).await?;
+            }
+            let disk = tokio::fs::read_to_string(file_path).await.unwrap_or_default();
+            self.documents.open_buffer(&uri, version, text.clone(), disk);
+            let params = serde_json::json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": "rust",
+                    "version": version,
+                    "text": text
+                }
+            });
+            self.send_notification("textDocument/didOpen", params).await?;
+            return Ok(true);
+        }
+        if !self.documents.replace(&uri, version, text.clone())? {
+            return Ok(false);
+        }
         
-        self.send_notification("textDocument/didOpen", params).await
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri, "version": version },
+            "contentChanges": [{ "text": text }]
+        });
+        self.send_notification("textDocument/didChange", params).await?;
+        Ok(true)
+    }
+    
+    /// Tracked version of the open document `file_path`
+    pub fn document_version(&self, file_path: &str) -> Option<i32> {
+        self.documents.version(&format!("file://{}", file_path))
+    }
+    
+    /// Whether `did_change` forwards ranges rather than full text
+    pub fn incremental_sync(&self) -> bool {
+        self.incremental_sync
     }
     
     pub async fn did_close(&mut self, file_path: &str) -> Result<()> {
         let uri = format!("file://{}", file_path);
+        self.documents.close(&uri);
         let params = serde_json::json!({
             "textDocument": {
                 "uri": uri
@@ -248,6 +681,80 @@ impl LspClient {
         self.send_request("workspace/symbol", params).await
     }
     
+    /// `workspace/symbol` with a `partialResultToken`. Each batch the server
+    /// reports through `$/progress` is forwarded to `batches` as it arrives; a
+    /// server without partial-result support answers in one response, which
+    /// becomes a single batch. Returns the number of symbols and whether any
+    /// partial results were received. If `batches` closes first, the request
+    /// is cancelled with `$/cancelRequest` and the counts so far are returned.
+    pub async fn workspace_symbol_streaming(
+        &mut self,
+        query: &str,
+        batches: mpsc::Sender<Vec<Value>>,
+    ) -> Result<(usize, bool)> {
+        let token = format!("workspace-symbol-{}", self.request_id.fetch_add(1, Ordering::SeqCst));
+        let mut partial_rx = self.pending.register_partial(&token).await;
+        let pending = self.pending.clone();
+        let params = serde_json::json!({
+            "query": query,
+            "partialResultToken": token
+        });
+        
+        let mut total = 0;
+        let mut partial = false;
+        let forward = |batch: Value, total: &mut usize| {
+            let symbols = match batch {
+                Value::Array(symbols) => symbols,
+                _ => Vec::new(),
+            };
+            *total += symbols.len();
+            let batches = batches.clone();
+            async move {
+                symbols.is_empty() || batches.send(symbols).await.is_ok()
+            }
+        };
+        
+        let (id, rx) = match self.start_request("workspace/symbol", params).await {
+            Ok(started) => started,
+            Err(e) => {
+                pending.forget_partial(&token).await;
+                return Err(e);
+            }
+        };
+        let response = timings::measure(Phase::LspRequest, pending.wait(id, rx, self.config.timeout));
+        tokio::pin!(response);
+        let result = loop {
+            tokio::select! {
+                result = &mut response => break Some(result),
+                Some(batch) = partial_rx.recv() => {
+                    partial = true;
+                    if !forward(batch, &mut total).await {
+                        break None;
+                    }
+                }
+            }
+        };
+        let Some(result) = result else {
+            debug!("Symbol stream consumer went away, cancelling request {}", id);
+            pending.forget(id).await;
+            pending.forget_partial(&token).await;
+            self.send_notification("$/cancelRequest", serde_json::json!({ "id": id })).await?;
+            return Ok((total, partial));
+        };
+        
+        // Partial results are dispatched before the final response, so whatever
+        // is queued now belongs to this request
+        while let Ok(batch) = partial_rx.try_recv() {
+            partial = true;
+            forward(batch, &mut total).await;
+        }
+        pending.forget_partial(&token).await;
+        
+        // With partial results the final response is empty; otherwise it holds everything
+        forward(result?, &mut total).await;
+        Ok((total, partial))
+    }
+    
     async fn start_server(&mut self) -> Result<()> {
         info!("Starting LSP server: {}", self.config.server_path);
         
@@ -267,9 +774,11 @@ impl LspClient {
             .ok_or_else(|| anyhow::anyhow!("Failed to get stdin"))?;
         
         // Start reader task
-        let response_map = self.response_map.clone();
+        let pending = self.pending.clone();
         let reader_handle = tokio::spawn(async move {
-            Self::reader_task(stdout, response_map).await;
+            Self::reader_task(stdout, &pending).await;
+            // Nobody will answer the remaining requests; let their callers fail fast
+            pending.fail_all().await;
         });
         
         self._reader_handle = Some(reader_handle);
@@ -279,7 +788,7 @@ impl LspClient {
         Ok(())
     }
     
-    async fn reader_task(stdout: impl AsyncRead + Unpin, response_map: ResponseMap) {
+    async fn reader_task(stdout: impl AsyncRead + Unpin, pending: &PendingRequests) {
         let mut reader = BufReader::new(stdout);
         let mut headers = HashMap::new();
         
@@ -317,19 +826,7 @@ impl LspClient {
                             if let Ok(json) = serde_json::from_slice::<Value>(&content) {
                                 debug!("Received: {}", json);
                                 
-                                // Handle response
-                                if let Some(id) = json.get("id").and_then(|v| v.as_u64()) {
-                                    let mut map = response_map.lock().await;
-                                    if let Some(sender) = map.remove(&id) {
-                                        if let Some(error) = json.get("error") {
-                                            let _ = sender.send(Err(anyhow::anyhow!(
-                                                "LSP error: {}", error
-                                            )));
-                                        } else if let Some(result) = json.get("result") {
-                                            let _ = sender.send(Ok(result.clone()));
-                                        }
-                                    }
-                                }
+                                pending.dispatch(&json).await;
                             }
                         }
                         Err(e) => {
@@ -342,7 +839,36 @@ impl LspClient {
         }
     }
     
-    async fn send_request(&mut self, method: &str, params: Value) -> Result<Value> {
+    /// Send a request and return the raw `result` of its response
+    pub async fn send_request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let timeout = if method == "initialize" {
+            self.config.timeout.max(INITIALIZE_TIMEOUT)
+        } else {
+            self.config.timeout
+        };
+        self.send_request_with_timeout(method, params, timeout).await
+    }
+    
+    /// `send_request` waiting `timeout` instead of the configured default, e.g.
+    /// briefly for interactive completion. Fails with `RequestTimeout` when no
+    /// response arrives in time.
+    pub async fn send_request_with_timeout(&mut self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        // `initialize` is part of starting the server, timed as such by the caller
+        if method == "initialize" {
+            return self.exchange(method, params, timeout).await;
+        }
+        timings::measure(Phase::LspRequest, self.exchange(method, params, timeout)).await
+    }
+    
+    /// Send a request and wait for its response
+    async fn exchange(&mut self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let (id, rx) = self.start_request(method, params).await?;
+        self.pending.wait(id, rx, timeout).await
+    }
+    
+    /// Send a request, returning its id and the receiver its response will
+    /// be delivered to
+    async fn start_request(&mut self, method: &str, params: Value) -> Result<(u64, oneshot::Receiver<Result<Value>>)> {
         if !self.initialized && method != "initialize" {
             bail!("LSP client not initialized");
         }
@@ -358,33 +884,16 @@ impl LspClient {
         
         debug!("Sending request: {}", request);
         
-        // Create response channel
-        let (tx, rx) = oneshot::channel();
-        {
-            let mut map = self.response_map.lock().await;
-            map.insert(id, tx);
-        }
+        // Register before sending so a fast response can't arrive unclaimed
+        let rx = self.pending.register(id).await;
         
         // Send request
-        self.write_message(&request).await?;
-        
-        // Wait for response with timeout (5 seconds for most requests, 60 for initialize)
-        let timeout_duration = if method == "initialize" {
-            std::time::Duration::from_secs(60)
-        } else {
-            std::time::Duration::from_secs(30)
-        };
-        
-        match tokio::time::timeout(timeout_duration, rx).await {
-            Ok(Ok(response)) => response,
-            Ok(Err(_)) => bail!("Response channel closed"),
-            Err(_) => {
-                // Remove from map on timeout
-                let mut map = self.response_map.lock().await;
-                map.remove(&id);
-                bail!("Request timeout")
-            }
+        if let Err(e) = self.write_message(&request).await {
+            self.pending.forget(id).await;
+            return Err(e);
         }
+        
+        Ok((id, rx))
     }
     
     async fn send_notification(&mut self, method: &str, params: Value) -> Result<()> {
@@ -0,0 +1,76 @@
+use anyhow::Result;
+
+use crate::tool_access::ToolCategory;
+
+/// LSP requests that only read: they answer questions about the code without
+/// computing edits to apply or changing the server's state
+const READ_METHODS: &[&str] = &[
+    "textDocument/hover",
+    "textDocument/completion",
+    "completionItem/resolve",
+    "textDocument/signatureHelp",
+    "textDocument/definition",
+    "textDocument/declaration",
+    "textDocument/typeDefinition",
+    "textDocument/implementation",
+    "textDocument/references",
+    "textDocument/documentHighlight",
+    "textDocument/documentSymbol",
+    "textDocument/foldingRange",
+    "textDocument/selectionRange",
+    "textDocument/semanticTokens/full",
+    "textDocument/semanticTokens/full/delta",
+    "textDocument/semanticTokens/range",
+    "textDocument/inlayHint",
+    "inlayHint/resolve",
+    "textDocument/codeLens",
+    "codeLens/resolve",
+    "textDocument/documentLink",
+    "textDocument/diagnostic",
+    "textDocument/prepareCallHierarchy",
+    "callHierarchy/incomingCalls",
+    "callHierarchy/outgoingCalls",
+    "textDocument/prepareTypeHierarchy",
+    "typeHierarchy/supertypes",
+    "typeHierarchy/subtypes",
+    "textDocument/moniker",
+    "workspace/symbol",
+    "workspace/diagnostic",
+    "rust-analyzer/analyzerStatus",
+    "rust-analyzer/memoryUsage",
+    "rust-analyzer/expandMacro",
+    "rust-analyzer/viewSyntaxTree",
+    "rust-analyzer/viewHir",
+    "rust-analyzer/viewMir",
+    "rust-analyzer/viewFileText",
+    "rust-analyzer/viewItemTree",
+    "rust-analyzer/viewCrateGraph",
+    "rust-analyzer/relatedTests",
+    "rust-analyzer/runnables",
+    "experimental/parentModule",
+    "experimental/externalDocs",
+    "experimental/openCargoToml",
+];
+
+/// Requests that belong to the connection's lifecycle, managed by the client
+const LIFECYCLE_METHODS: &[&str] = &["initialize", "shutdown", "exit"];
+
+/// Whether `method` is a request that only reads
+pub fn is_read_method(method: &str) -> bool {
+    READ_METHODS.contains(&method)
+}
+
+/// Refuse lifecycle requests, and requests that may edit when the `write`
+/// category is not enabled
+pub fn check_method(method: &str, enabled: &[ToolCategory]) -> Result<()> {
+    if LIFECYCLE_METHODS.contains(&method) {
+        anyhow::bail!("{} is managed by the server and cannot be sent through lsp_passthrough", method);
+    }
+    if !is_read_method(method) && !enabled.contains(&ToolCategory::Write) {
+        anyhow::bail!(
+            "{} is not a read-only LSP request and the write category is not enabled on this server (see --tool-categories)",
+            method
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::source_files;
+
+/// Where a macro is defined and, for `macro_rules!`, its source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroDefinition {
+    pub name: String,
+    /// `declarative`, `proc_macro`, `proc_macro_derive`, `proc_macro_attribute` or `unknown`
+    pub kind: String,
+    pub file: String,
+    /// 1-based line of the definition
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Name of the macro invoked at a 0-based position: the identifier under the
+/// cursor when it is followed by `!`, or the name in `#[derive(..)]`/`#[attr]`
+pub fn macro_name_at(source: &str, line: usize, column: usize) -> Option<String> {
+    let text = source.lines().nth(line)?;
+    let chars: Vec<char> = text.chars().collect();
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut start = column.min(chars.len());
+    // Allow the cursor to sit on the `!` itself
+    if start < chars.len() && chars[start] == '!' && start > 0 {
+        start -= 1;
+    }
+    if start < chars.len() && !is_ident(chars[start]) {
+        return None;
+    }
+    while start > 0 && is_ident(chars[start - 1]) {
+        start -= 1;
+    }
+    let mut end = start;
+    while end < chars.len() && is_ident(chars[end]) {
+        end += 1;
+    }
+    if start == end {
+        return None;
+    }
+
+    let name: String = chars[start..end].iter().collect();
+    let bang_call = chars.get(end) == Some(&'!');
+    let in_attribute = text.trim_start().starts_with("#[");
+    (bang_call || in_attribute).then_some(name)
+}
+
+/// Classify the definition found at 0-based `line` and extract its text when declarative
+pub fn describe_definition(source: &str, line: usize) -> (String, Option<String>) {
+    let lines: Vec<&str> = source.lines().collect();
+    let Some(text) = lines.get(line) else {
+        return ("unknown".to_string(), None);
+    };
+
+    if text.contains("macro_rules!") {
+        return ("declarative".to_string(), macro_rules_text(&lines, line));
+    }
+
+    // Proc macros are plain functions marked with an attribute a few lines up
+    for above in lines[line.saturating_sub(5)..=line].iter().rev() {
+        for kind in ["proc_macro_derive", "proc_macro_attribute", "proc_macro"] {
+            if above.contains(&format!("#[{}", kind)) {
+                return (kind.to_string(), None);
+            }
+        }
+    }
+    ("unknown".to_string(), None)
+}
+
+/// Full `macro_rules! name { ... }` text starting at 0-based `line`
+fn macro_rules_text(lines: &[&str], line: usize) -> Option<String> {
+    let mut depth = 0i32;
+    let mut entered = false;
+    let mut collected = Vec::new();
+
+    for text in &lines[line..] {
+        collected.push(*text);
+        for c in text.chars() {
+            match c {
+                '{' | '(' | '[' => {
+                    depth += 1;
+                    entered = true;
+                }
+                '}' | ')' | ']' => depth -= 1,
+                _ => {}
+            }
+        }
+        if entered && depth <= 0 {
+            return Some(collected.join("\n"));
+        }
+    }
+    None
+}
+
+/// Search the project's `src/` for `macro_rules! name` when LSP can't help
+pub fn find_macro_rules(project_root: &Path, name: &str) -> Vec<MacroDefinition> {
+    let pattern = format!("macro_rules! {}", name);
+    let mut found = Vec::new();
+
+    for file in source_files::rust_files(&project_root.join("src")) {
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        let lines: Vec<&str> = content.lines().collect();
+        for (i, line) in lines.iter().enumerate() {
+            let Some(pos) = line.find(&pattern) else { continue };
+            // Don't match `macro_rules! name_longer`
+            let after = line[pos + pattern.len()..].chars().next();
+            if after.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            found.push(MacroDefinition {
+                name: name.to_string(),
+                kind: "declarative".to_string(),
+                file: file.strip_prefix(project_root).unwrap_or(&file).display().to_string(),
+                line: i + 1,
+                source: macro_rules_text(&lines, i),
+            });
+        }
+    }
+
+    found
+}
@@ -1,14 +1,17 @@
 use anyhow::Result;
 use clap::Parser;
-use std::io::{self, BufRead, BufReader, Write};
-use tracing::{info, error};
-use tracing_subscriber;
+use std::io::{self, BufReader};
+use tracing::{info, error, warn};
 use serde_json::json;
 
+use mcp_rust_analyzer::framing::{write_message, MessageReader};
 use mcp_rust_analyzer::server::McpServer;
 use mcp_rust_analyzer::http_server::start_http_server;
-use mcp_rust_analyzer::http_client::HttpClient;
-use mcp_rust_analyzer::daemon_state::{DaemonState, DaemonInfo};
+use mcp_rust_analyzer::http_client::{DaemonError, HttpClient};
+use mcp_rust_analyzer::daemon_state::{DaemonState, DaemonInfo, KILL_TIMEOUT};
+use mcp_rust_analyzer::health::check_cargo;
+use mcp_rust_analyzer::tool_access::ToolCategory;
+use mcp_rust_analyzer::config::{Config, Overrides};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -33,6 +36,21 @@ struct Args {
     
     #[arg(long, help = "Check daemon status for current directory")]
     status: bool,
+    
+    #[arg(long, help = "Verify that cargo works in the project before serving requests")]
+    check_setup: bool,
+    
+    #[arg(long, help = "Path to the rust-analyzer binary (overrides RUST_ANALYZER_PATH and .mcp-analyzer.toml)")]
+    rust_analyzer_path: Option<String>,
+    
+    #[arg(long, help = "Comma-separated tool categories to offer: read_only, write, execute (defaults to all)")]
+    tool_categories: Option<String>,
+    
+    #[arg(long, help = "Report milliseconds spent per phase (LSP, cargo, file scans) in each result's _meta.timings")]
+    timings: bool,
+    
+    #[arg(long, help = "Offer lsp_passthrough, forwarding raw LSP requests to rust-analyzer")]
+    lsp_passthrough: bool,
 }
 
 #[tokio::main]
@@ -57,6 +75,15 @@ async fn main() -> Result<()> {
     
     info!("Project path: {}", canonical_project_path);
     
+    // CLI flags take precedence over env vars and the config file. A spawned
+    // daemon gets them as env vars.
+    let overrides = Overrides {
+        rust_analyzer_path: args.rust_analyzer_path.clone(),
+        tool_categories: args.tool_categories.as_deref().map(ToolCategory::parse_list).transpose()?,
+        timings: args.timings,
+        lsp_passthrough: args.lsp_passthrough,
+    };
+    
     // Handle daemon control commands
     if args.daemon {
         // Determine port (auto-select if not specified)
@@ -73,11 +100,11 @@ async fn main() -> Result<()> {
         }
         
         let http_client = HttpClient::new(port);
-        http_client.start_daemon(&canonical_project_path).await?;
+        let pid = http_client.start_daemon(&canonical_project_path, &overrides).await?;
         
         // Register the daemon
         let mut state = DaemonState::load()?;
-        state.register_daemon(&canonical_project_path, port, None)?;
+        state.register_daemon(&canonical_project_path, port, pid)?;
         
         return Ok(());
     }
@@ -85,10 +112,25 @@ async fn main() -> Result<()> {
     if args.stop {
         info!("Stopping daemon for current directory...");
         
-        if let Some(daemon_info) = DaemonState::unregister_daemon_for_current_dir()? {
+        if let Some(daemon_info) = DaemonState::registered_daemon_for_current_dir()? {
             let http_client = HttpClient::new(daemon_info.port);
-            http_client.stop_daemon().await?;
-            println!("✅ Stopped daemon on port {}", daemon_info.port);
+            // A daemon that shut down over HTTP may still be stopping rust-analyzer
+            let stopped = match http_client.stop_daemon().await {
+                Ok(()) => daemon_info.pid.is_none_or(|pid| DaemonState::wait_for_exit(pid, KILL_TIMEOUT)),
+                Err(e) => {
+                    warn!("Daemon on port {} didn't shut down: {}", daemon_info.port, e);
+                    false
+                }
+            };
+            if stopped {
+                DaemonState::unregister_daemon_for_current_dir()?;
+                println!("✅ Stopped daemon on port {}", daemon_info.port);
+            } else if DaemonState::kill_daemon_for_current_dir()? {
+                println!("✅ Killed daemon on port {}", daemon_info.port);
+            } else {
+                println!("❌ Could not stop the daemon on port {}; it is still registered", daemon_info.port);
+                std::process::exit(1);
+            }
         } else {
             println!("❌ No daemon found for current directory");
         }
@@ -105,18 +147,45 @@ async fn main() -> Result<()> {
         return Ok(());
     }
     
+    if args.check_setup {
+        let health = check_cargo(std::path::Path::new(&canonical_project_path)).await;
+        
+        match &health.cargo_version {
+            Some(version) => println!("✅ {}", version),
+            None => println!("❌ cargo is not runnable"),
+        }
+        if health.metadata_ok {
+            match &health.package_name {
+                Some(name) => println!("✅ cargo metadata succeeded (package: {})", name),
+                None => println!("✅ cargo metadata succeeded (virtual workspace: {})",
+                        health.workspace_members.join(", ")),
+            }
+        } else if health.cargo_available {
+            println!("❌ cargo metadata failed in {}", canonical_project_path);
+        }
+        if let Some(error) = &health.error {
+            println!("   {}", error);
+        }
+        
+        if !health.is_healthy() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
     if args.server {
         // Direct HTTP server mode (not daemon)
         println!("🔍 [DEBUG] ENTERING SERVER MODE");
         let port = args.port.unwrap_or(3000);
         info!("Starting HTTP server mode on port {}", port);
-        let server = McpServer::new(&canonical_project_path).await?;
+        let config = resolve_config(&canonical_project_path, &overrides)?;
+        let server = McpServer::with_config(&canonical_project_path, config).await?;
         start_http_server(server, port).await?;
     } else {
         // Check if we're being run by Claude Code CLI (no TTY = likely MCP context)
         if !atty::is(atty::Stream::Stdin) {
             info!("Using direct mode for Claude Code CLI compatibility");
-            return run_direct_mode(&canonical_project_path).await;
+            return run_direct_mode(&canonical_project_path, &overrides).await;
         }
         
         // Client mode - find daemon for current directory
@@ -134,17 +203,17 @@ async fn main() -> Result<()> {
             
             let http_client = HttpClient::new(port);
             
-            match http_client.start_daemon(&canonical_project_path).await {
-                Ok(_) => {
+            match http_client.start_daemon(&canonical_project_path, &overrides).await {
+                Ok(pid) => {
                     // Register the daemon
                     let mut state = DaemonState::load()?;
-                    state.register_daemon(&canonical_project_path, port, None)?;
+                    state.register_daemon(&canonical_project_path, port, pid)?;
                     
                     info!("Daemon started successfully on port {}", port);
                     DaemonInfo {
                         port,
                         project_path: canonical_project_path.clone(),
-                        pid: None,
+                        pid,
                         started_at: std::time::SystemTime::now()
                             .duration_since(std::time::SystemTime::UNIX_EPOCH)?
                             .as_secs(),
@@ -153,7 +222,7 @@ async fn main() -> Result<()> {
                 Err(e) => {
                     error!("Failed to start daemon: {}", e);
                     info!("Falling back to direct mode");
-                    return run_direct_mode(&canonical_project_path).await;
+                    return run_direct_mode(&canonical_project_path, &overrides).await;
                 }
             }
             }
@@ -161,37 +230,49 @@ async fn main() -> Result<()> {
                 // State file access failed - likely permissions issue
                 error!("Failed to access daemon state: {}", e);
                 info!("Falling back to direct mode due to state access failure");
-                return run_direct_mode(&canonical_project_path).await;
+                return run_direct_mode(&canonical_project_path, &overrides).await;
             }
         };
         
         // Client mode - forward JSON-RPC to HTTP
         info!("Running in client mode, forwarding to HTTP server on port {}", daemon_info.port);
-        let http_client = HttpClient::new(daemon_info.port);
-        let stdin = io::stdin();
+        // The daemon may have been started with more categories than this client
+        let config = resolve_config(&canonical_project_path, &overrides)?;
+        let http_client = HttpClient::new(daemon_info.port).with_tool_categories(config.tool_categories);
         let mut stdout = io::stdout();
-        let reader = BufReader::new(stdin);
+        let mut reader = MessageReader::new(BufReader::new(io::stdin()));
         
-        for line in reader.lines() {
-            let line = line?;
-            
+        while let Some(message) = reader.next_message()? {
+            let line = match message {
+                Ok(line) => line,
+                Err(invalid) => {
+                    warn!("Skipping malformed message: {}", invalid);
+                    write_message(&mut stdout, reader.framing(), &invalid.response().to_string())?;
+                    continue;
+                }
+            };
             match http_client.handle_jsonrpc_request(&line).await {
                 Ok(response) => {
-                    writeln!(stdout, "{}", response)?;
-                    stdout.flush()?;
+                    write_message(&mut stdout, reader.framing(), &response)?;
                 }
                 Err(e) => {
                     error!("Error forwarding request: {}", e);
+                    // A daemon that is gone gets its own code, so callers can
+                    // tell it from a request the daemon rejected
+                    let (code, message) = match e.downcast_ref::<DaemonError>() {
+                        Some(DaemonError::Unreachable { .. }) => (-32000, format!("Daemon unavailable: {}", e)),
+                        Some(DaemonError::Timeout { .. }) => (-32001, format!("Daemon timed out: {}", e)),
+                        _ => (-32603, format!("Client error: {}", e)),
+                    };
                     let error_response = json!({
                         "jsonrpc": "2.0",
-                        "id": null,
+                        "id": McpServer::request_id(&line),
                         "error": {
-                            "code": -32603,
-                            "message": format!("Client error: {}", e)
+                            "code": code,
+                            "message": message
                         }
                     });
-                    writeln!(stdout, "{}", serde_json::to_string(&error_response)?)?;
-                    stdout.flush()?;
+                    write_message(&mut stdout, reader.framing(), &serde_json::to_string(&error_response)?)?;
                 }
             }
         }
@@ -200,34 +281,46 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn run_direct_mode(project_path: &str) -> Result<()> {
+/// The project's config with the CLI flags applied on top
+fn resolve_config(project_path: &str, overrides: &Overrides) -> Result<Config> {
+    let mut config = Config::resolve(std::path::Path::new(project_path))?;
+    config.apply_overrides(overrides);
+    Ok(config)
+}
+
+async fn run_direct_mode(project_path: &str, overrides: &Overrides) -> Result<()> {
     info!("Running in direct mode (stdin/stdout)");
-    let server = McpServer::new(project_path).await?;
+    let server = McpServer::with_config(project_path, resolve_config(project_path, overrides)?).await?;
     
-    let stdin = io::stdin();
+    // Line-delimited by default; a message starting with a `Content-Length`
+    // header is read, and answered, with LSP framing instead
     let mut stdout = io::stdout();
-    let reader = BufReader::new(stdin);
+    let mut reader = MessageReader::new(BufReader::new(io::stdin()));
     
-    for line in reader.lines() {
-        let line = line?;
-        
+    while let Some(message) = reader.next_message()? {
+        let line = match message {
+            Ok(line) => line,
+            Err(invalid) => {
+                warn!("Skipping malformed message: {}", invalid);
+                write_message(&mut stdout, reader.framing(), &invalid.response().to_string())?;
+                continue;
+            }
+        };
         match server.handle_request(&line).await {
             Ok(response) => {
-                writeln!(stdout, "{}", response)?;
-                stdout.flush()?;
+                write_message(&mut stdout, reader.framing(), &response)?;
             }
             Err(e) => {
                 error!("Error handling request: {}", e);
                 let error_response = json!({
                     "jsonrpc": "2.0",
-                    "id": null,
+                    "id": McpServer::request_id(&line),
                     "error": {
                         "code": -32603,
                         "message": "Internal error"
                     }
                 });
-                writeln!(stdout, "{}", serde_json::to_string(&error_response)?)?;
-                stdout.flush()?;
+                write_message(&mut stdout, reader.framing(), &serde_json::to_string(&error_response)?)?;
             }
         }
     }
@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::related_tests;
+
+/// A dependency as declared in Cargo.toml, whichever form it is written in
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    /// Version requirement, e.g. `1.0` or `~0.4.2`; path and git dependencies
+    /// may have none
+    pub version: Option<String>,
+    pub features: Vec<String>,
+    pub optional: bool,
+    pub path: Option<String>,
+    pub git: Option<String>,
+    /// Crate the dependency renames, from `package = "..."`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub package: Option<String>,
+    /// Inherited from `[workspace.dependencies]` with `workspace = true`
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub workspace: bool,
+}
+
+impl Dependency {
+    /// Name the crate is published under
+    pub fn package(&self) -> &str {
+        self.package.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// The parts of a Cargo.toml the tools read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    pub dependencies: Vec<Dependency>,
+    pub dev_dependencies: Vec<Dependency>,
+    pub build_dependencies: Vec<Dependency>,
+    /// `[workspace]`, when the manifest has one
+    pub workspace: Option<Workspace>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Workspace {
+    /// Member paths as written, globs included
+    #[serde(default)]
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl Manifest {
+    /// The dependency sections with the names `analyze_dependencies` reports them under
+    pub fn sections(&self) -> [(&'static str, &[Dependency]); 3] {
+        [
+            ("dependencies", &self.dependencies),
+            ("dev_dependencies", &self.dev_dependencies),
+            ("build_dependencies", &self.build_dependencies),
+        ]
+    }
+}
+
+#[derive(Deserialize)]
+struct RawManifest {
+    #[serde(default)]
+    dependencies: BTreeMap<String, RawDependency>,
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: BTreeMap<String, RawDependency>,
+    #[serde(default, rename = "build-dependencies")]
+    build_dependencies: BTreeMap<String, RawDependency>,
+    workspace: Option<Workspace>,
+}
+
+/// `name = "1.0"` or `name = { version = "1.0", ... }`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDependency {
+    Version(String),
+    Detailed(DetailedDependency),
+}
+
+#[derive(Deserialize)]
+struct DetailedDependency {
+    version: Option<String>,
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    optional: bool,
+    path: Option<String>,
+    git: Option<String>,
+    package: Option<String>,
+    #[serde(default)]
+    workspace: bool,
+}
+
+/// Parse a Cargo.toml
+pub fn parse(content: &str) -> Result<Manifest> {
+    let raw: RawManifest = toml::from_str(content).context("Failed to parse Cargo.toml")?;
+    let section = |entries: BTreeMap<String, RawDependency>| {
+        entries.into_iter().map(|(name, dependency)| normalize(name, dependency)).collect()
+    };
+    Ok(Manifest {
+        dependencies: section(raw.dependencies),
+        dev_dependencies: section(raw.dev_dependencies),
+        build_dependencies: section(raw.build_dependencies),
+        workspace: raw.workspace,
+    })
+}
+
+fn normalize(name: String, dependency: RawDependency) -> Dependency {
+    match dependency {
+        RawDependency::Version(version) => Dependency { name, version: Some(version), ..Dependency::default() },
+        RawDependency::Detailed(detailed) => Dependency {
+            name,
+            version: detailed.version,
+            features: detailed.features,
+            optional: detailed.optional,
+            path: detailed.path,
+            git: detailed.git,
+            package: detailed.package,
+            workspace: detailed.workspace,
+        },
+    }
+}
+
+/// A workspace member crate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+    /// Package name from the member's Cargo.toml, or its directory name
+    pub name: String,
+    /// Directory relative to the project root
+    pub path: String,
+}
+
+/// Members of `workspace`, in the order `members` lists them. Entries are
+/// globs, such as `crates/*`, matching directories with a Cargo.toml;
+/// `exclude` entries, globs too, are left out.
+pub fn workspace_members(project_root: &Path, workspace: &Workspace) -> Vec<WorkspaceMember> {
+    let excluded: Vec<glob::Pattern> = workspace.exclude.iter()
+        .filter_map(|entry| glob::Pattern::new(entry.trim_end_matches('/')).ok())
+        .collect();
+    let mut members: Vec<WorkspaceMember> = Vec::new();
+    for entry in &workspace.members {
+        let pattern = project_root.join(entry.trim_end_matches('/'));
+        let Ok(paths) = glob::glob(&pattern.to_string_lossy()) else { continue };
+        // `glob` yields matches sorted within each entry
+        for dir in paths.flatten().filter(|dir| dir.join("Cargo.toml").is_file()) {
+            let path = dir.strip_prefix(project_root).unwrap_or(&dir).to_string_lossy().to_string();
+            if excluded.iter().any(|pattern| pattern.matches(&path)) || members.iter().any(|m| m.path == path) {
+                continue;
+            }
+            let name = related_tests::package_name(&dir.join("Cargo.toml"))
+                .unwrap_or_else(|| dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.clone()));
+            members.push(WorkspaceMember { name, path });
+        }
+    }
+    members
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+
+/// Format of documentation text returned by hover and completion commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    #[default]
+    Markdown,
+    Plaintext,
+}
+
+impl ContentFormat {
+    /// LSP `MarkupKind` values in order of preference
+    pub fn lsp_preference(self) -> [&'static str; 2] {
+        match self {
+            ContentFormat::Markdown => ["markdown", "plaintext"],
+            ContentFormat::Plaintext => ["plaintext", "markdown"],
+        }
+    }
+
+    /// Convert `text` to this format; markdown is passed through unchanged
+    pub fn render(self, text: &str) -> String {
+        match self {
+            ContentFormat::Markdown => text.to_string(),
+            ContentFormat::Plaintext => to_plaintext(text),
+        }
+    }
+}
+
+/// Strip markdown formatting: code fences, headings, rules, links, inline code and emphasis.
+/// Code inside fences is kept verbatim.
+pub fn to_plaintext(markdown: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut in_fence = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            out.push(line.to_string());
+            continue;
+        }
+        if is_rule(trimmed) {
+            out.push(String::new());
+            continue;
+        }
+
+        let text = trimmed.trim_start_matches('#');
+        let text = if text.len() != trimmed.len() && (text.is_empty() || text.starts_with(' ')) {
+            text.trim_start()
+        } else {
+            line.trim_end()
+        };
+        out.push(strip_inline(text));
+    }
+
+    // Collapse the blank runs left behind by removed fences and rules
+    let mut collapsed: Vec<String> = Vec::new();
+    for line in out {
+        let blank = line.trim().is_empty();
+        if blank && collapsed.last().is_none_or(|l| l.trim().is_empty()) {
+            continue;
+        }
+        collapsed.push(if blank { String::new() } else { line });
+    }
+    while collapsed.last().is_some_and(|l| l.is_empty()) {
+        collapsed.pop();
+    }
+    collapsed.join("\n")
+}
+
+/// Shorten documentation longer than `max_len` characters (0 keeps it all),
+/// marking the cut with an ellipsis. Whole paragraphs are kept while they
+/// fit, so the summary paragraph survives intact whenever it can; a first
+/// paragraph that doesn't fit is cut at a word boundary. Code fences count
+/// as one paragraph, blank lines included.
+pub fn truncate(text: &str, max_len: usize) -> String {
+    if max_len == 0 || text.chars().count() <= max_len {
+        return text.to_string();
+    }
+    let paragraphs = paragraphs(text);
+    let mut kept: Vec<&str> = Vec::new();
+    let mut len = 0;
+    for paragraph in &paragraphs {
+        let added = paragraph.chars().count() + if kept.is_empty() { 0 } else { 2 };
+        if len + added > max_len {
+            break;
+        }
+        kept.push(paragraph);
+        len += added;
+    }
+    if !kept.is_empty() {
+        return format!("{}\n\n…", kept.join("\n\n"));
+    }
+
+    let first: String = paragraphs.first().map_or(text, |p| p).chars().take(max_len).collect();
+    let cut = match first.rfind(char::is_whitespace) {
+        Some(at) if at > 0 => first[..at].trim_end(),
+        _ => first.as_str(),
+    };
+    format!("{}…", cut)
+}
+
+/// Blocks of `text` separated by blank lines outside code fences
+fn paragraphs(text: &str) -> Vec<&str> {
+    let mut paragraphs = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut end = 0;
+    let mut in_fence = false;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if trimmed.is_empty() && !in_fence {
+            if let Some(from) = start.take() {
+                paragraphs.push(&text[from..end]);
+            }
+        } else {
+            start.get_or_insert(offset);
+            end = offset + line.trim_end().len();
+        }
+        offset += line.len();
+    }
+    if let Some(from) = start {
+        paragraphs.push(&text[from..end]);
+    }
+    paragraphs
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_'].iter().any(|m| compact.chars().all(|c| c == *m))
+}
+
+/// Remove inline markup from a single line
+fn strip_inline(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if i + 1 < chars.len() && chars[i + 1].is_ascii_punctuation() => {
+                out.push(chars[i + 1]);
+                i += 2;
+            }
+            '`' => {
+                // Inline code: drop the backticks, keep the contents untouched
+                let ticks = chars[i..].iter().take_while(|c| **c == '`').count();
+                let close = (i + ticks..chars.len()).find(|&j| {
+                    chars[j..].iter().take_while(|c| **c == '`').count() == ticks
+                });
+                match close {
+                    Some(j) => {
+                        out.extend(chars[i + ticks..j].iter());
+                        i = j + ticks;
+                    }
+                    None => {
+                        out.extend(chars[i..i + ticks].iter());
+                        i += ticks;
+                    }
+                }
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => i += 1,
+            '[' if i > 0 && chars[i - 1].is_alphanumeric() => {
+                // Indexing such as `v[0]`
+                out.push(c);
+                i += 1;
+            }
+            '[' => match link_end(&chars, i) {
+                Some((text_end, end)) => {
+                    out.push_str(&strip_inline(&chars[i + 1..text_end].iter().collect::<String>()));
+                    i = end;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            '*' | '_' => {
+                let run = chars[i..].iter().take_while(|x| **x == c).count();
+                let prev = i.checked_sub(1).map(|p| chars[p]);
+                let next = chars.get(i + run).copied();
+                // `snake_case` and `a * b` are not emphasis
+                let intraword = c == '_'
+                    && prev.is_some_and(|p| p.is_alphanumeric())
+                    && next.is_some_and(|n| n.is_alphanumeric());
+                let spaced = prev.is_none_or(|p| p.is_whitespace()) && next.is_none_or(|n| n.is_whitespace());
+                if intraword || spaced {
+                    out.extend(chars[i..i + run].iter());
+                }
+                i += run;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// For `[text](url)` or `[text][ref]` starting at `start`, return (index of `]`, index after the link)
+fn link_end(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    let mut depth = 0;
+    let mut text_end = None;
+    for (j, c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    text_end = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let text_end = text_end?;
+
+    let (open, close) = match chars.get(text_end + 1) {
+        Some('(') => ('(', ')'),
+        Some('[') => ('[', ']'),
+        // Intra-doc links like [`Vec`] render as their text; `[u8; 4]` stays as is
+        _ if chars.get(start + 1) == Some(&'`') => return Some((text_end, text_end + 1)),
+        _ => return None,
+    };
+    let mut depth = 0;
+    for (j, c) in chars.iter().enumerate().skip(text_end + 1) {
+        if *c == open {
+            depth += 1;
+        } else if *c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some((text_end, j + 1));
+            }
+        }
+    }
+    None
+}
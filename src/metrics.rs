@@ -18,7 +18,7 @@ pub struct ProjectStructure {
     pub dependencies: Vec<DependencyInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModuleInfo {
     pub name: String,
     pub path: String,
@@ -33,6 +33,7 @@ pub struct DependencyInfo {
     pub features: Vec<String>,
 }
 
+#[derive(Default)]
 pub struct MetricsAnalyzer;
 
 impl MetricsAnalyzer {
@@ -40,22 +41,23 @@ impl MetricsAnalyzer {
         Self
     }
     
-    pub fn analyze_file(&self, _path: &Path) -> Result<CodeMetrics> {
-        // Placeholder implementation
+    pub fn analyze_file(&self, path: &Path) -> Result<CodeMetrics> {
+        let content = std::fs::read_to_string(path)?;
+        let items = crate::complexity::analyze(&content)?;
         Ok(CodeMetrics {
-            lines_of_code: 0,
-            cyclomatic_complexity: 0,
-            functions: 0,
-            structs: 0,
-            traits: 0,
-            impls: 0,
+            lines_of_code: content.lines().filter(|line| !line.trim().is_empty()).count(),
+            cyclomatic_complexity: items.functions.iter().map(|f| f.complexity).sum(),
+            functions: items.functions.len(),
+            structs: items.structs,
+            traits: items.traits,
+            impls: items.impls,
         })
     }
     
-    pub fn analyze_project(&self, _root: &Path) -> Result<ProjectStructure> {
-        // Placeholder implementation
+    pub fn analyze_project(&self, root: &Path) -> Result<ProjectStructure> {
+        // Dependencies are still a placeholder
         Ok(ProjectStructure {
-            modules: Vec::new(),
+            modules: crate::api_surface::modules(root),
             dependencies: Vec::new(),
         })
     }
@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use tokio::process::Command;
+
+/// Approximate cyclomatic complexity of a file: one per function plus one per
+/// branch (`if`, `while`, `for`, each match arm beyond the first, `&&`, `||`).
+/// Comments and string literals are ignored.
+pub fn cyclomatic_complexity(content: &str) -> usize {
+    let mut complexity = 0;
+    let mut arms = 0;
+    let mut matches = 0;
+    for line in code_lines(content) {
+        let words = words(&line);
+        let is_impl = words.iter().any(|w| w == "impl");
+        for word in &words {
+            match word.as_str() {
+                "fn" | "if" | "while" => complexity += 1,
+                // `impl Trait for Type` and `for<'a>` bounds are not loops
+                "for" if !is_impl => complexity += 1,
+                _ => {}
+            }
+        }
+        arms += line.matches("=>").count();
+        matches += words.iter().filter(|w| *w == "match").count();
+        complexity += line.matches("&&").count();
+        complexity += logical_ors(&line);
+    }
+    complexity + arms.saturating_sub(matches)
+}
+
+/// Names of the functions declared in a file, in source order
+pub fn function_names(content: &str) -> Vec<String> {
+    code_lines(content)
+        .iter()
+        .flat_map(|line| {
+            let words = words(line);
+            words.windows(2)
+                .filter(|pair| pair[0] == "fn")
+                .map(|pair| pair[1].clone())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Functions present only in `after` and only in `before`, counting duplicates
+/// such as same-named methods on different types
+pub fn functions_changed(before: &[String], after: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for name in before {
+        *counts.entry(name).or_default() -= 1;
+    }
+    for name in after {
+        *counts.entry(name).or_default() += 1;
+    }
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for (name, count) in counts {
+        let name = name.to_string();
+        if count > 0 {
+            added.extend(std::iter::repeat_n(name, count as usize));
+        } else if count < 0 {
+            removed.extend(std::iter::repeat_n(name, (-count) as usize));
+        }
+    }
+    added.sort();
+    removed.sort();
+    (added, removed)
+}
+
+/// `after - before` for every numeric field of two metrics objects
+pub fn metric_deltas(before: &Value, after: &Value) -> Value {
+    let mut deltas = Map::new();
+    if let Some(fields) = after.as_object() {
+        for (name, value) in fields {
+            let Some(after) = value.as_i64() else { continue };
+            let before = before.get(name).and_then(|v| v.as_i64()).unwrap_or(0);
+            deltas.insert(name.clone(), json!(after - before));
+        }
+    }
+    Value::Object(deltas)
+}
+
+/// Commit id `git_ref` names. Only the id is passed on to other git commands,
+/// so a ref such as `--output=<file>` can't be read as an option.
+pub async fn resolve_commit(project_root: &Path, git_ref: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "--end-of-options"])
+        .arg(format!("{}^{{commit}}", git_ref))
+        .current_dir(project_root)
+        .output()
+        .await
+        .context("Failed to run git rev-parse")?;
+
+    if !output.status.success() {
+        anyhow::bail!("{} does not name a commit", git_ref);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Content of `file` (relative to `project_root`) at `git_ref`, or `None` when
+/// the file did not exist at that revision
+pub async fn content_at_ref(project_root: &Path, file: &str, git_ref: &str) -> Result<Option<String>> {
+    let commit = resolve_commit(project_root, git_ref).await?;
+    let output = Command::new("git")
+        .arg("show")
+        .arg(format!("{}:./{}", commit, file))
+        .current_dir(project_root)
+        .output()
+        .await
+        .context("Failed to run git show")?;
+
+    if output.status.success() {
+        return Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("does not exist in") || stderr.contains("exists on disk, but not in") {
+        return Ok(None);
+    }
+    anyhow::bail!("git show {}:{} failed: {}", git_ref, file, stderr.trim())
+}
+
+/// Lines added and removed in the working tree relative to `git_ref`
+pub async fn line_changes(project_root: &Path, file: &str, git_ref: &str) -> Result<(usize, usize)> {
+    let commit = resolve_commit(project_root, git_ref).await?;
+    let output = Command::new("git")
+        .args(["diff", "--numstat", &commit, "--"])
+        .arg(file)
+        .current_dir(project_root)
+        .output()
+        .await
+        .context("Failed to run git diff")?;
+
+    if !output.status.success() {
+        anyhow::bail!("git diff {} failed: {}", git_ref, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    // `added<TAB>removed<TAB>path`; binary files report `-`
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split_whitespace();
+    let added = fields.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+    let removed = fields.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+    Ok((added, removed))
+}
+
+/// Files under `project_root`, relative to it, that differ from `git_ref` in
+/// the working tree, including untracked files that aren't ignored
+pub async fn changed_files(project_root: &Path, git_ref: &str) -> Result<BTreeSet<String>> {
+    let commit = resolve_commit(project_root, git_ref).await?;
+    let mut files = BTreeSet::new();
+    for args in [vec!["diff", "--name-only", "--relative", &commit, "--"], vec!["ls-files", "--others", "--exclude-standard"]] {
+        let output = Command::new("git")
+            .args(&args)
+            .current_dir(project_root)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run git {}", args[0]))?;
+        if !output.status.success() {
+            anyhow::bail!("git {} {} failed: {}", args[0], git_ref, String::from_utf8_lossy(&output.stderr).trim());
+        }
+        files.extend(String::from_utf8_lossy(&output.stdout).lines().map(str::to_string));
+    }
+    Ok(files)
+}
+
+/// Lines with comments and string literal contents removed
+fn code_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut in_block_comment = false;
+    let mut in_string = false;
+
+    for line in content.lines() {
+        let mut code = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_block_comment {
+                if c == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    in_block_comment = false;
+                }
+            } else if in_string {
+                match c {
+                    '\\' => { chars.next(); }
+                    '"' => {
+                        in_string = false;
+                        code.push('"');
+                    }
+                    _ => {}
+                }
+            } else if c == '/' && chars.peek() == Some(&'/') {
+                break;
+            } else if c == '/' && chars.peek() == Some(&'*') {
+                chars.next();
+                in_block_comment = true;
+            } else if c == '\'' {
+                // Skip char literals such as '"' so they cannot open a string;
+                // lifetimes have no closing quote and are kept
+                let mut ahead = chars.clone();
+                let literal_len = match (ahead.next(), ahead.next()) {
+                    (Some('\\'), Some(_)) => ahead.position(|c| c == '\'').map(|n| n + 3),
+                    (Some(_), Some('\'')) => Some(2),
+                    _ => None,
+                };
+                match literal_len {
+                    Some(len) => {
+                        chars.nth(len - 1);
+                        code.push_str("' '");
+                    }
+                    None => code.push(c),
+                }
+            } else if c == '"' {
+                in_string = true;
+                code.push('"');
+            } else {
+                code.push(c);
+            }
+        }
+        lines.push(code);
+    }
+    lines
+}
+
+fn words(line: &str) -> Vec<String> {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// `||` used as an operator; a closure without arguments (`|| x`, `move || x`)
+/// follows `(`, `,`, `=` or `move` instead of an operand
+fn logical_ors(line: &str) -> usize {
+    line.match_indices("||")
+        .filter(|(index, _)| {
+            let before = line[..*index].trim_end();
+            !before.is_empty()
+                && !before.ends_with("move")
+                && !before.ends_with(['(', ',', '=', '{', '|'])
+        })
+        .count()
+}
@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::extract_module;
+use crate::reexports;
+use crate::source_files;
+use crate::timings::{self, Phase};
+use crate::visibility::{self, Visibility};
+
+/// A public item and whether it has a doc comment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublicItem {
+    /// Kind as reported by `visibility`, plus `crate`; lint results add the
+    /// kinds rustc names, such as `method`, `variant` or `struct_field`
+    pub kind: String,
+    pub name: String,
+    /// 1-based line of the declaration
+    pub line: usize,
+    #[serde(skip)]
+    pub documented: bool,
+}
+
+/// Documentation coverage of one module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleDocs {
+    pub module: String,
+    pub file: String,
+    pub public_items: usize,
+    pub documented: usize,
+    pub coverage: f64,
+    /// Undocumented items in source order
+    pub missing: Vec<PublicItem>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MissingDocsReport {
+    /// `scan` or `lint`
+    pub source: String,
+    /// Why a requested lint run was replaced by the source scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_reason: Option<String>,
+    pub public_items: usize,
+    pub documented: usize,
+    pub missing: usize,
+    /// Documented share of the public items as a percentage
+    pub coverage: f64,
+    /// Modules with undocumented items, by module path
+    pub modules: Vec<ModuleDocs>,
+}
+
+/// Top-level `pub` items of `source` (and `#[macro_export]` macros) with
+/// whether a `///` comment or `#[doc]` attribute precedes them. An inline
+/// `pub mod` also counts as documented when its body opens with `//!`;
+/// for `pub mod name;` the caller checks the module's file.
+pub fn public_items(source: &str) -> Vec<PublicItem> {
+    let lines: Vec<&str> = source.lines().collect();
+    extract_module::top_level_items(source).into_iter()
+        .filter_map(|item| {
+            let prelude = &lines[item.start..item.line - 1];
+            let exported_macro = item.kind == "macro" && prelude.iter().any(|l| l.trim().starts_with("#[macro_export"));
+            match visibility::parse_item(lines[item.line - 1].trim()) {
+                Some((Visibility::Public, _, _)) => {}
+                _ if exported_macro => {}
+                _ => return None,
+            }
+            let outer = prelude.iter().map(|l| l.trim()).any(|l| (l.starts_with("///") && !l.starts_with("////")) || l.starts_with("#[doc"));
+            let inner = item.kind == "module" && lines[item.line - 1].contains('{')
+                && has_inner_docs(&lines[item.line..=item.end.min(lines.len() - 1)].join("\n"));
+            Some(PublicItem { kind: item.kind, name: item.name, line: item.line, documented: outer || inner })
+        })
+        .collect()
+}
+
+/// Whether `source` opens with inner docs (`//!` or `#![doc]`), before any item
+pub fn has_inner_docs(source: &str) -> bool {
+    source.lines()
+        .map(str::trim)
+        .take_while(|l| l.is_empty() || l.starts_with("//") || l.starts_with("#!"))
+        .any(|l| l.starts_with("//!") || l.starts_with("#![doc"))
+}
+
+/// Documentation coverage of the public items of every file under `src`,
+/// crate roots included
+pub fn scan(project_root: &Path) -> MissingDocsReport {
+    report("scan", scan_files(project_root))
+}
+
+/// Run `cargo check` with the `missing_docs` lint on: whether it succeeded,
+/// and the JSON messages it printed
+pub async fn run_lint(project_root: &Path) -> Result<(bool, Vec<Value>)> {
+    let output = timings::measure(Phase::Cargo, Command::new("cargo")
+        .args(["check", "--message-format=json"])
+        .current_dir(project_root)
+        .env("RUSTFLAGS", "-W missing_docs")
+        .output())
+        .await
+        .context("Failed to run cargo check")?;
+
+    let messages = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .collect();
+    Ok((output.status.success(), messages))
+}
+
+/// Coverage with the lint's findings as the missing items. Items the lint
+/// leaves alone are either documented or unreachable from outside the crate,
+/// so the public items are the scan's documented ones plus the lint's.
+pub fn from_lint(project_root: &Path, messages: &[Value]) -> MissingDocsReport {
+    let mut files: BTreeMap<String, Vec<PublicItem>> = scan_files(project_root).into_iter()
+        .map(|(file, items)| (file, items.into_iter().filter(|item| item.documented).collect()))
+        .collect();
+
+    let mut seen = HashSet::new();
+    for msg in messages {
+        if msg.get("reason").and_then(|r| r.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let message = &msg["message"];
+        if message["code"]["code"] != "missing_docs" {
+            continue;
+        }
+        let Some(span) = message["spans"].as_array().and_then(|spans| spans.iter().find(|s| s["is_primary"] == true)) else { continue };
+        let (Some(file), Some(line), Some(column)) = (span["file_name"].as_str(), span["line_start"].as_u64(), span["column_start"].as_u64()) else { continue };
+        // Every target of the package reports the items it shares
+        if !seen.insert((file.to_string(), line, column)) {
+            continue;
+        }
+        let kind = lint_kind(message["message"].as_str().unwrap_or(""));
+        let name = if kind == "crate" {
+            "crate".to_string()
+        } else {
+            // The span starts at the item, which may share its line with others
+            let text: String = span["text"][0]["text"].as_str().unwrap_or("").chars().skip(column as usize - 1).collect();
+            item_name(&text)
+        };
+        files.entry(file.to_string()).or_default().push(PublicItem { kind, name, line: line as usize, documented: false });
+    }
+    for items in files.values_mut() {
+        items.sort_by_key(|item| item.line);
+    }
+    report("lint", files)
+}
+
+/// Public items of each file under `src`, keyed by path relative to the project root
+fn scan_files(project_root: &Path) -> BTreeMap<String, Vec<PublicItem>> {
+    let src = project_root.join("src");
+    let mut files = BTreeMap::new();
+    for file in source_files::rust_files(&src) {
+        let Ok(source) = std::fs::read_to_string(&file) else { continue };
+        let mut items = Vec::new();
+        if matches!(file.strip_prefix(&src).ok().and_then(|f| f.to_str()), Some("lib.rs" | "main.rs")) {
+            items.push(PublicItem { kind: "crate".to_string(), name: "crate".to_string(), line: 1, documented: has_inner_docs(&source) });
+        }
+        for mut item in public_items(&source) {
+            if item.kind == "module" && !item.documented {
+                item.documented = [false, true].into_iter()
+                    .filter_map(|mod_rs| std::fs::read_to_string(extract_module::new_module_path(&file, &item.name, mod_rs)).ok())
+                    .any(|module| has_inner_docs(&module));
+            }
+            items.push(item);
+        }
+        files.insert(file.strip_prefix(project_root).unwrap_or(&file).display().to_string(), items);
+    }
+    files
+}
+
+fn report(source: &str, files: BTreeMap<String, Vec<PublicItem>>) -> MissingDocsReport {
+    let mut report = MissingDocsReport { source: source.to_string(), ..MissingDocsReport::default() };
+    for (file, items) in files {
+        let documented = items.iter().filter(|item| item.documented).count();
+        report.public_items += items.len();
+        report.documented += documented;
+        if documented == items.len() {
+            continue;
+        }
+        let module = reexports::module_path_for(Path::new("src"), Path::new(&file))
+            .map(|path| path.join("::"))
+            .unwrap_or_else(|| file.clone());
+        report.modules.push(ModuleDocs {
+            module,
+            file,
+            public_items: items.len(),
+            documented,
+            coverage: coverage(documented, items.len()),
+            missing: items.into_iter().filter(|item| !item.documented).collect(),
+        });
+    }
+    report.missing = report.public_items - report.documented;
+    report.coverage = coverage(report.documented, report.public_items);
+    report.modules.sort_by(|a, b| a.module.cmp(&b.module));
+    report
+}
+
+/// `documented / total` as a percentage with one decimal; 100 when there is nothing to document
+fn coverage(documented: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    (documented as f64 / total as f64 * 1000.0).round() / 10.0
+}
+
+/// Item kind of a `missing documentation for a struct field` message
+fn lint_kind(message: &str) -> String {
+    let what = message.trim_start_matches("missing documentation for ");
+    let what = ["a ", "an ", "the "].iter().find_map(|article| what.strip_prefix(article)).unwrap_or(what);
+    match what {
+        "type alias" => "type_alias".to_string(),
+        other => other.replace(' ', "_"),
+    }
+}
+
+/// Name declared on `line`: an item, or a field or variant
+fn item_name(line: &str) -> String {
+    if let Some((_, _, name)) = visibility::parse_item(line.trim()) {
+        return name;
+    }
+    let (_, rest) = Visibility::parse(line);
+    rest.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect()
+}
@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::reexports::{self, module_path_for};
+use crate::source_files;
+
+/// A module backed by its own file under `src/`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleNode {
+    /// e.g. `crate::commands::metrics`
+    pub module: String,
+    /// Relative to the project root
+    pub file: String,
+}
+
+/// File modules of a crate and which of them refer to which
+#[derive(Debug, Clone, Default)]
+pub struct ModuleGraph {
+    pub modules: Vec<ModuleNode>,
+    /// `(from, to)` indices into `modules`: `from` declares or uses `to`
+    pub edges: BTreeSet<(usize, usize)>,
+}
+
+/// Which end of the dependency graph to start reading from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadingDirection {
+    /// The crate root and the modules using others before what they use
+    #[default]
+    RootsFirst,
+    /// Modules depending on nothing else first
+    LeavesFirst,
+}
+
+/// One step of a reading order: a single file, or the members of a cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingStep {
+    pub files: Vec<ModuleNode>,
+    pub cycle: bool,
+}
+
+/// Build the graph of the file modules under `project_root/src`. Edges come
+/// from `mod` declarations, `use` statements and `crate::`/`self::`/`super::`
+/// paths in code. References to a module's own ancestors are left out: the
+/// ancestor already declares it, so every `super::` would otherwise be a cycle.
+pub fn build(project_root: &Path) -> ModuleGraph {
+    let src = project_root.join("src");
+    let mut files: Vec<(Vec<String>, PathBuf)> = source_files::rust_files(&src)
+        .into_iter()
+        .filter_map(|file| module_path_for(&src, &file).map(|module| (module, file)))
+        .collect();
+    files.sort();
+
+    let modules: HashMap<Vec<String>, PathBuf> = files.iter().cloned().collect();
+    let index: HashMap<&Vec<String>, usize> = files.iter().enumerate().map(|(i, (module, _))| (module, i)).collect();
+
+    let mut graph = ModuleGraph::default();
+    for (from, (module, file)) in files.iter().enumerate() {
+        graph.modules.push(ModuleNode {
+            module: module.join("::"),
+            file: file.strip_prefix(project_root).unwrap_or(file).display().to_string(),
+        });
+        let source = std::fs::read_to_string(file).unwrap_or_default();
+        for target in references(module, &source, &modules) {
+            if target.len() < module.len() && module.starts_with(&target) {
+                continue;
+            }
+            if let Some(&to) = index.get(&target) {
+                if to != from {
+                    graph.edges.insert((from, to));
+                }
+            }
+        }
+    }
+    graph
+}
+
+/// Modules of `modules` that `source`, the file of `module`, declares or refers to
+pub fn references(module: &[String], source: &str, modules: &HashMap<Vec<String>, PathBuf>) -> BTreeSet<Vec<String>> {
+    let mut paths: Vec<Vec<String>> = Vec::new();
+
+    for (_, statement) in use_statements(source) {
+        for leaf in reexports::parse_use_tree(&statement) {
+            paths.extend(reexports::absolutize(module, &leaf.path, modules));
+        }
+    }
+    for line in source.lines() {
+        let code = line.split("//").next().unwrap_or("").trim();
+        if let Some(name) = mod_declaration(code) {
+            let mut child = module.to_vec();
+            child.push(name.to_string());
+            paths.push(child);
+        }
+        for path in qualified_paths(code) {
+            paths.extend(reexports::absolutize(module, &path, modules));
+        }
+    }
+
+    // A path names an item; the module is its longest prefix that is a module file
+    paths.into_iter()
+        .filter_map(|path| (1..=path.len()).rev().map(|len| path[..len].to_vec()).find(|prefix| modules.contains_key(prefix)))
+        .collect()
+}
+
+/// Files in reading order, with the members of each dependency cycle grouped
+/// into one step. Ties are broken by module path so the order is stable.
+pub fn reading_order(graph: &ModuleGraph, direction: ReadingDirection) -> Vec<ReadingStep> {
+    let components = strongly_connected(graph);
+    let mut component_of = vec![0; graph.modules.len()];
+    for (c, members) in components.iter().enumerate() {
+        for &m in members {
+            component_of[m] = c;
+        }
+    }
+
+    // Edges of the condensed graph, oriented so that a component comes after its predecessors
+    let mut successors: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); components.len()];
+    let mut in_degree = vec![0; components.len()];
+    for &(from, to) in &graph.edges {
+        let (a, b) = (component_of[from], component_of[to]);
+        let (before, after) = match direction {
+            ReadingDirection::RootsFirst => (a, b),
+            ReadingDirection::LeavesFirst => (b, a),
+        };
+        if before != after && successors[before].insert(after) {
+            in_degree[after] += 1;
+        }
+    }
+
+    // Kahn's algorithm, always taking the ready component with the smallest first member
+    let mut ready: BTreeSet<(usize, usize)> = (0..components.len())
+        .filter(|&c| in_degree[c] == 0)
+        .map(|c| (components[c][0], c))
+        .collect();
+    let mut steps = Vec::new();
+    while let Some((_, c)) = ready.pop_first() {
+        steps.push(ReadingStep {
+            files: components[c].iter().map(|&m| graph.modules[m].clone()).collect(),
+            cycle: components[c].len() > 1,
+        });
+        for &next in &successors[c] {
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                ready.insert((components[next][0], next));
+            }
+        }
+    }
+    steps
+}
+
+/// Tarjan's strongly connected components, each sorted by module index
+fn strongly_connected(graph: &ModuleGraph) -> Vec<Vec<usize>> {
+    struct State {
+        adjacency: Vec<Vec<usize>>,
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(state: &mut State, v: usize) {
+        state.index[v] = Some(state.next);
+        state.low[v] = state.next;
+        state.next += 1;
+        state.stack.push(v);
+        state.on_stack[v] = true;
+
+        for i in 0..state.adjacency[v].len() {
+            let w = state.adjacency[v][i];
+            match state.index[w] {
+                None => {
+                    visit(state, w);
+                    state.low[v] = state.low[v].min(state.low[w]);
+                }
+                Some(index) if state.on_stack[w] => state.low[v] = state.low[v].min(index),
+                Some(_) => {}
+            }
+        }
+
+        if Some(state.low[v]) == state.index[v] {
+            let mut component = Vec::new();
+            while let Some(w) = state.stack.pop() {
+                state.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            component.sort();
+            state.components.push(component);
+        }
+    }
+
+    let n = graph.modules.len();
+    let mut adjacency = vec![Vec::new(); n];
+    for &(from, to) in &graph.edges {
+        adjacency[from].push(to);
+    }
+    let mut state = State {
+        adjacency,
+        index: vec![None; n],
+        low: vec![0; n],
+        on_stack: vec![false; n],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for v in 0..n {
+        if state.index[v].is_none() {
+            visit(&mut state, v);
+        }
+    }
+    state.components
+}
+
+/// Every `use` statement, public or not, joined onto one line with its 1-based line
+pub(crate) fn use_statements(source: &str) -> Vec<(usize, String)> {
+    let mut statements = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or("").trim();
+        match current.as_mut() {
+            Some((_, text)) => {
+                text.push(' ');
+                text.push_str(code);
+            }
+            None => {
+                if let Some(tree) = strip_visibility(code).strip_prefix("use ") {
+                    current = Some((i + 1, tree.to_string()));
+                }
+            }
+        }
+
+        if current.as_ref().is_some_and(|(_, text)| text.contains(';')) {
+            let (line, text) = current.take().unwrap();
+            statements.push((line, text.split(';').next().unwrap_or("").to_string()));
+        }
+    }
+    statements
+}
+
+/// `foo` of a `mod foo;` declaration; inline `mod foo { ... }` blocks live in the same file
+fn mod_declaration(code: &str) -> Option<&str> {
+    let name = strip_visibility(code).strip_prefix("mod ")?.strip_suffix(';')?.trim();
+    (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')).then_some(name)
+}
+
+/// `code` without a leading `pub`, `pub(crate)`, `pub(super)`, ...
+pub(crate) fn strip_visibility(code: &str) -> &str {
+    let Some(rest) = code.strip_prefix("pub") else { return code };
+    let rest = rest.trim_start();
+    if rest.starts_with('(') {
+        rest.split_once(')').map_or(rest, |(_, after)| after.trim_start())
+    } else {
+        rest
+    }
+}
+
+/// `crate::a::b`, `self::a` and `super::a` paths written in code outside `use`
+fn qualified_paths(code: &str) -> Vec<Vec<String>> {
+    if strip_visibility(code).starts_with("use ") {
+        return Vec::new();
+    }
+    let mut paths = Vec::new();
+    for start in ["crate::", "self::", "super::"] {
+        let mut search = 0;
+        while let Some(found) = code[search..].find(start) {
+            let at = search + found;
+            search = at + start.len();
+            let boundary = at == 0 || !code[..at].ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == ':');
+            if !boundary {
+                continue;
+            }
+            let path: Vec<String> = code[at..]
+                .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+                .next()
+                .unwrap_or("")
+                .split("::")
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect();
+            if path.len() > 1 {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
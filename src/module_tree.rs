@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::extract_module;
+use crate::visibility::{self, Visibility};
+
+/// Top-level items of a module by visibility; `impl` blocks are not items
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemCounts {
+    /// Plain `pub` items
+    pub public: usize,
+    /// Everything else, including `pub(crate)` and `pub(super)`
+    pub private: usize,
+}
+
+impl ItemCounts {
+    fn add(&mut self, other: ItemCounts) {
+        self.public += other.public;
+        self.private += other.private;
+    }
+
+    fn label(self) -> String {
+        format!("({} pub, {} priv)", self.public, self.private)
+    }
+}
+
+/// Text outline of a directory of modules
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModuleTree {
+    pub root: String,
+    /// One line per file or directory, indented two spaces per level
+    pub outline: String,
+    pub files: usize,
+    pub totals: ItemCounts,
+}
+
+/// Count the top-level items of `source`
+pub fn count_items(source: &str) -> ItemCounts {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut counts = ItemCounts::default();
+    for item in extract_module::top_level_items(source) {
+        if item.kind == "impl" {
+            continue;
+        }
+        match visibility::parse_item(lines[item.line - 1].trim()) {
+            Some((Visibility::Public, _, _)) => counts.public += 1,
+            _ => counts.private += 1,
+        }
+    }
+    counts
+}
+
+/// Render the entries produced by the `project_structure` traversal of
+/// `root` as an outline. Module roots (`lib.rs`, `main.rs`, `mod.rs`) come
+/// first in their directory, then the other entries by name; directories
+/// show the totals of everything below them.
+pub fn render(root: &str, entries: &[Value]) -> ModuleTree {
+    let mut lines = Vec::new();
+    let mut tree = ModuleTree { root: root.to_string(), ..ModuleTree::default() };
+    tree.totals = render_level(entries, 1, &mut lines, &mut tree.files);
+
+    let mut outline = format!("{}/ {}\n", root.trim_end_matches('/'), tree.totals.label());
+    for line in lines {
+        outline.push_str(&line);
+        outline.push('\n');
+    }
+    tree.outline = outline;
+    tree
+}
+
+fn render_level(entries: &[Value], depth: usize, lines: &mut Vec<String>, files: &mut usize) -> ItemCounts {
+    let mut entries: Vec<&Value> = entries.iter().collect();
+    entries.sort_by_key(|e| (e["type"] != "module", e["name"].as_str().unwrap_or("").to_string()));
+
+    let indent = "  ".repeat(depth);
+    let mut totals = ItemCounts::default();
+    for entry in entries {
+        let name = entry["name"].as_str().unwrap_or("");
+        if entry["type"] == "directory" {
+            let at = lines.len();
+            lines.push(String::new());
+            let counts = render_level(entry["submodules"].as_array().map(Vec::as_slice).unwrap_or(&[]), depth + 1, lines, files);
+            lines[at] = format!("{}{}/ {}", indent, name, counts.label());
+            totals.add(counts);
+        } else {
+            let source = entry["path"].as_str()
+                .and_then(|path| std::fs::read_to_string(Path::new(path)).ok())
+                .unwrap_or_default();
+            let counts = count_items(&source);
+            lines.push(format!("{}{} {}", indent, name, counts.label()));
+            totals.add(counts);
+            *files += 1;
+        }
+    }
+    totals
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::manifest::{Dependency, Manifest};
+use crate::timings::{self, Phase};
+
+/// Latest versions looked up less than this long ago are not looked up again
+pub const CACHE_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Upper bound on a single `cargo search`
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A crates.io dependency compared against the newest published version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutdatedDependency {
+    pub name: String,
+    /// `dependencies`, `dev_dependencies` or `build_dependencies`
+    pub section: String,
+    /// Version requirement from Cargo.toml, e.g. `1.0` or `~0.4.2`
+    pub current: String,
+    pub latest: Option<String>,
+    /// Whether the requirement excludes the latest version
+    pub outdated: bool,
+    /// `registry`, `cache`, `stale_cache`, or `unknown` when no version could be found
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutdatedReport {
+    /// Whether the registry was reached; without it only cached versions are compared
+    pub checked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub outdated_count: usize,
+    pub dependencies: Vec<OutdatedDependency>,
+}
+
+/// A version looked up earlier, with when
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedVersion {
+    version: String,
+    checked_at: u64,
+}
+
+/// Where looked-up versions are kept between runs
+pub fn cache_path(project_root: &Path) -> PathBuf {
+    project_root.join("target").join("mcp-analyzer").join("latest-versions.json")
+}
+
+/// The crate to look up and its version requirement. Path and git
+/// dependencies without a version have nothing to compare.
+pub fn requirement(dependency: &Dependency) -> Option<(String, String)> {
+    let version = dependency.version.as_deref().map(str::trim).filter(|v| !v.is_empty())?;
+    Some((dependency.package().to_string(), version.to_string()))
+}
+
+/// The version `cargo search` reports for exactly `name`, from lines like
+/// `serde = "1.0.210"    # A generic serialization/deserialization framework`
+pub fn parse_search_output(output: &str, name: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (found, rest) = line.split_once('=')?;
+        (found.trim() == name).then(|| rest.trim_start().strip_prefix('"')?.split('"').next().map(str::to_string))?
+    })
+}
+
+/// Whether `requirement` rules out `latest`, i.e. `cargo update` would not
+/// pick it up. Bare and `^` requirements are caret ranges, `~` tilde ranges
+/// and `=` exact; comparison and wildcard requirements never count as outdated.
+pub fn is_outdated(requirement: &str, latest: &str) -> bool {
+    let requirement = requirement.trim();
+    if requirement.contains(['>', '<', '*', ',']) {
+        return false;
+    }
+    let (op, version) = match requirement.chars().next() {
+        Some(c @ ('^' | '~' | '=')) => (c, requirement[1..].trim()),
+        _ => ('^', requirement),
+    };
+    let (Some(required), Some(latest)) = (parse_version(version), parse_version(latest)) else { return false };
+    let given = version.split('.').count();
+    if latest <= required {
+        return false;
+    }
+
+    // Index of the first component allowed to change
+    let fixed = match op {
+        '=' => given,
+        '~' => if given == 1 { 1 } else { 2 },
+        _ => match required {
+            [0, 0, _] if given >= 3 => 3,
+            [0, 0, _] => given,
+            [0, _, _] => 2.min(given),
+            _ => 1,
+        },
+    };
+    required[..fixed] != latest[..fixed]
+}
+
+/// `major.minor.patch`, missing components as 0, ignoring pre-release and build metadata
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = [0u64; 3];
+    for (i, part) in core.split('.').enumerate() {
+        *parts.get_mut(i)? = part.parse().ok()?;
+    }
+    Some(parts)
+}
+
+/// Compare the registry dependencies of `manifest` with their latest versions. Versions come from
+/// the cache when recent, otherwise from `cargo search`. When `offline` is
+/// set or the registry cannot be reached, cached versions of any age are
+/// used and the report says so instead of failing.
+pub async fn check_outdated(project_root: &Path, manifest: &Manifest, offline: bool) -> OutdatedReport {
+    let mut cache: BTreeMap<String, CachedVersion> = std::fs::read_to_string(cache_path(project_root))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    let mut report = OutdatedReport { checked: !offline, ..OutdatedReport::default() };
+    if offline {
+        report.note = Some("Offline: latest versions were not looked up; cached versions are used where available".to_string());
+    }
+    let mut cache_changed = false;
+
+    for (section, dependencies) in manifest.sections() {
+        for dependency in dependencies {
+            let Some((package, current)) = requirement(dependency) else { continue };
+            let cached = cache.get(&package).cloned();
+            let fresh = cached.as_ref().filter(|c| now.saturating_sub(c.checked_at) < CACHE_TTL_SECS);
+
+            let (latest, source) = if let Some(fresh) = fresh {
+                (Some(fresh.version.clone()), "cache")
+            } else if report.checked {
+                match search(project_root, &package).await {
+                    Ok(Some(version)) => {
+                        cache.insert(package.clone(), CachedVersion { version: version.clone(), checked_at: now });
+                        cache_changed = true;
+                        (Some(version), "registry")
+                    }
+                    // Not published under this name, e.g. a private registry crate
+                    Ok(None) => (None, "registry"),
+                    Err(e) => {
+                        // Most likely offline; don't wait on the registry for every other crate
+                        report.checked = false;
+                        report.note = Some(format!("Registry unreachable ({}); cached versions are used where available", e));
+                        (cached.map(|c| c.version), "stale_cache")
+                    }
+                }
+            } else {
+                (cached.map(|c| c.version), "stale_cache")
+            };
+
+            let source = if latest.is_none() { "unknown" } else { source };
+            let outdated = latest.as_deref().is_some_and(|latest| is_outdated(&current, latest));
+            report.dependencies.push(OutdatedDependency {
+                name: dependency.name.clone(),
+                section: section.to_string(),
+                current,
+                latest,
+                outdated,
+                source: source.to_string(),
+            });
+        }
+    }
+
+    if cache_changed {
+        let path = cache_path(project_root);
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(content) = serde_json::to_string_pretty(&cache) {
+            let _ = std::fs::write(&path, content);
+        }
+    }
+    report.outdated_count = report.dependencies.iter().filter(|d| d.outdated).count();
+    report
+}
+
+/// Latest version of `package` on the registry, via `cargo search`; `None`
+/// when the registry answers but has no crate of that name
+async fn search(project_root: &Path, package: &str) -> anyhow::Result<Option<String>> {
+    debug!("Looking up latest version of {}", package);
+    let output = tokio::time::timeout(
+        SEARCH_TIMEOUT,
+        timings::measure(Phase::Cargo, Command::new("cargo")
+            .args(["search", "--limit", "1", package])
+            .current_dir(project_root)
+            .kill_on_drop(true)
+            .output()),
+    ).await.map_err(|_| anyhow::anyhow!("cargo search timed out"))??;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", stderr.lines().find(|l| l.starts_with("error")).unwrap_or("cargo search failed").trim_start_matches("error: "));
+    }
+    Ok(parse_search_output(&String::from_utf8_lossy(&output.stdout), package))
+}
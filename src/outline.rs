@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A symbol of a file's outline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlineSymbol {
+    pub name: String,
+    pub kind: String,
+    /// Signature or type rust-analyzer shows next to the name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// 1-based first and last line of the whole symbol
+    pub line: u32,
+    pub end_line: u32,
+    pub children: Vec<OutlineSymbol>,
+}
+
+/// Kind name of an LSP `SymbolKind`, in Rust terms where rust-analyzer's use
+/// of the kind is unambiguous: traits are sent as `Interface`, impl blocks as
+/// `Object`, statics as `Variable`, type aliases as `TypeParameter` and enum
+/// variants as `EnumMember`
+pub fn symbol_kind(kind: u64) -> &'static str {
+    match kind {
+        1 => "file",
+        2 => "module",
+        3 => "namespace",
+        4 => "package",
+        5 => "class",
+        6 => "method",
+        7 => "property",
+        8 => "field",
+        9 => "constructor",
+        10 => "enum",
+        11 => "trait",
+        12 => "function",
+        13 => "static",
+        14 => "constant",
+        15 => "string",
+        16 => "number",
+        17 => "boolean",
+        18 => "array",
+        19 => "impl",
+        20 => "key",
+        21 => "null",
+        22 => "variant",
+        23 => "struct",
+        24 => "event",
+        25 => "operator",
+        26 => "type_alias",
+        _ => "unknown",
+    }
+}
+
+/// LSP `SymbolKind` number of a kind name `symbol_kind` gives
+pub fn symbol_kind_number(kind: &str) -> Option<u64> {
+    (1..=26).find(|number| symbol_kind(*number) == kind)
+}
+
+/// Outline of a `textDocument/documentSymbol` response. Hierarchical
+/// `DocumentSymbol[]` keeps its nesting; the older flat `SymbolInformation[]`
+/// is nested by range, each symbol under the innermost one containing it.
+pub fn from_lsp(result: &Value) -> Vec<OutlineSymbol> {
+    let Some(symbols) = result.as_array() else { return Vec::new() };
+    if symbols.iter().any(|s| s.get("location").is_some()) {
+        nest(symbols.iter().filter_map(|s| symbol(s, &s["location"]["range"])).collect())
+    } else {
+        symbols.iter().filter_map(document_symbol).collect()
+    }
+}
+
+fn document_symbol(value: &Value) -> Option<OutlineSymbol> {
+    let mut outline = symbol(value, &value["range"])?;
+    if let Some(children) = value["children"].as_array() {
+        outline.children = children.iter().filter_map(document_symbol).collect();
+    }
+    Some(outline)
+}
+
+fn symbol(value: &Value, range: &Value) -> Option<OutlineSymbol> {
+    Some(OutlineSymbol {
+        name: value["name"].as_str()?.to_string(),
+        kind: symbol_kind(value["kind"].as_u64().unwrap_or(0)).to_string(),
+        detail: value["detail"].as_str().filter(|d| !d.is_empty()).map(String::from),
+        line: range["start"]["line"].as_u64()? as u32 + 1,
+        end_line: range["end"]["line"].as_u64()? as u32 + 1,
+        children: Vec::new(),
+    })
+}
+
+/// Nest flat symbols by line range
+fn nest(mut symbols: Vec<OutlineSymbol>) -> Vec<OutlineSymbol> {
+    // Outer symbols first among those starting on the same line
+    symbols.sort_by_key(|s| (s.line, std::cmp::Reverse(s.end_line)));
+    let mut roots = Vec::new();
+    let mut open: Vec<OutlineSymbol> = Vec::new();
+    for symbol in symbols {
+        while open.last().is_some_and(|parent| parent.end_line < symbol.end_line) {
+            close(&mut open, &mut roots);
+        }
+        open.push(symbol);
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+    roots
+}
+
+/// Move the innermost open symbol into its parent, or the roots
+fn close(open: &mut Vec<OutlineSymbol>, roots: &mut Vec<OutlineSymbol>) {
+    let Some(done) = open.pop() else { return };
+    match open.last_mut() {
+        Some(parent) => parent.children.push(done),
+        None => roots.push(done),
+    }
+}
@@ -0,0 +1,205 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// Arguments a command can't run with, reported as JSON-RPC `-32602` with the
+/// command's schema and an example invocation in `data`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvalidParams {
+    pub tool: String,
+    /// Required fields absent or null
+    pub missing: Vec<String>,
+    /// Fields of the wrong type, or rejected while the command parsed them
+    pub invalid: Vec<InvalidField>,
+    #[serde(skip)]
+    pub schema: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvalidField {
+    pub field: String,
+    pub problem: String,
+}
+
+impl InvalidParams {
+    /// The `data` of the error response
+    pub fn data(&self) -> Value {
+        json!({
+            "tool": self.tool,
+            "missing": self.missing,
+            "invalid": self.invalid,
+            "schema": self.schema,
+            "example": example(&self.tool, &self.schema),
+        })
+    }
+}
+
+impl fmt::Display for InvalidParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems: Vec<String> = Vec::new();
+        if !self.missing.is_empty() {
+            problems.push(format!("missing {}", self.missing.join(", ")));
+        }
+        problems.extend(self.invalid.iter().map(|invalid| format!("{}: {}", invalid.field, invalid.problem)));
+        write!(f, "Invalid params for {}: {}", self.tool, problems.join("; "))
+    }
+}
+
+impl std::error::Error for InvalidParams {}
+
+/// A command's params failing to deserialize into its params struct. Only
+/// this error, not JSON errors from whatever else the command reads, is
+/// reported as invalid params.
+#[derive(Debug)]
+pub struct ParamsError(pub serde_json::Error);
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for ParamsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Deserialize the params a command was called with
+pub fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, ParamsError> {
+    serde_json::from_value(params).map_err(ParamsError)
+}
+
+/// Check `params` against the `inputSchema` of `tool`: required fields are
+/// present and not null, and the fields given have the declared type. Fields
+/// the schema doesn't list are left to the command.
+pub fn validate(tool: &str, schema: &Value, params: &Value) -> Result<(), InvalidParams> {
+    let empty = Map::new();
+    let params = params.as_object().unwrap_or(&empty);
+    let missing: Vec<String> = schema["required"].as_array().into_iter().flatten()
+        .filter_map(Value::as_str)
+        .filter(|field| params.get(*field).is_none_or(Value::is_null))
+        .map(str::to_string)
+        .collect();
+    let mut invalid = Vec::new();
+    if let Some(properties) = schema["properties"].as_object() {
+        for (field, property) in properties {
+            let Some(value) = params.get(field).filter(|value| !value.is_null()) else { continue };
+            if let Some(expected) = property["type"].as_str().filter(|expected| !has_type(value, expected)) {
+                invalid.push(InvalidField {
+                    field: field.clone(),
+                    problem: format!("expected {}, got {}", article(expected), article(type_name(value))),
+                });
+            } else if let Some(allowed) = property["enum"].as_array().filter(|allowed| !allowed.contains(value)) {
+                let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+                invalid.push(InvalidField { field: field.clone(), problem: format!("expected one of {}", allowed.join(", ")) });
+            }
+        }
+    }
+    if missing.is_empty() && invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(InvalidParams { tool: tool.to_string(), missing, invalid, schema: schema.clone() })
+    }
+}
+
+/// Named params for `tool` from positional `values`, which fill the schema's
+/// required fields in order. Optional fields can only be passed by name.
+pub fn from_positional(tool: &str, schema: &Value, values: &[Value]) -> Result<Value, InvalidParams> {
+    let required: Vec<&str> = schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+    if values.len() > required.len() {
+        let expected = match required.len() {
+            0 => "takes no positional values".to_string(),
+            n => format!("takes at most {} positional values ({})", n, required.join(", ")),
+        };
+        return Err(InvalidParams {
+            tool: tool.to_string(),
+            missing: Vec::new(),
+            invalid: vec![InvalidField {
+                field: "params".to_string(),
+                problem: format!("{}, got {}; pass an object to name the fields", expected, values.len()),
+            }],
+            schema: schema.clone(),
+        });
+    }
+    Ok(Value::Object(required.into_iter().map(str::to_string).zip(values.iter().cloned()).collect()))
+}
+
+/// Turn the error of a command failing to deserialize its params, such as
+/// "missing field `new_name`", into an `InvalidParams`
+pub fn from_parse_error(tool: &str, schema: &Value, error: &serde_json::Error) -> InvalidParams {
+    let message = error.to_string();
+    let field = message.split('`').nth(1).map(str::to_string);
+    let (missing, invalid) = match field {
+        Some(field) if message.starts_with("missing field") => (vec![field], Vec::new()),
+        field => (Vec::new(), vec![InvalidField { field: field.unwrap_or_default(), problem: message }]),
+    };
+    InvalidParams { tool: tool.to_string(), missing, invalid, schema: schema.clone() }
+}
+
+/// A `tools/call` request for `tool` filling in every required field with a
+/// sample value
+pub fn example(tool: &str, schema: &Value) -> Value {
+    let arguments: Map<String, Value> = schema["required"].as_array().into_iter().flatten()
+        .filter_map(Value::as_str)
+        .map(|field| (field.to_string(), sample(field, &schema["properties"][field])))
+        .collect();
+    json!({ "name": tool, "arguments": arguments })
+}
+
+/// A plausible value for `field`, by its name and declared type
+fn sample(field: &str, property: &Value) -> Value {
+    if let Some(first) = property["examples"].as_array().or(property["enum"].as_array()).and_then(|values| values.first()) {
+        return first.clone();
+    }
+    match property["type"].as_str() {
+        Some("object") if field == "range" => json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 0 }
+        }),
+        Some("integer" | "number") => json!(1),
+        Some("boolean") => json!(true),
+        Some("array") => json!([sample(field.trim_end_matches('s'), &property["items"])]),
+        Some("object") => json!({}),
+        _ => json!(match field {
+            "file" | "file_path" | "path" => "src/lib.rs",
+            "module" => "src",
+            "manifest" => "Cargo.toml",
+            "new_name" => "new_name",
+            "name" | "symbol" | "function" => "my_function",
+            "query" => "MyType",
+            "git_ref" | "since" | "base" => "HEAD",
+            _ => field,
+        }),
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn article(name: &str) -> String {
+    let article = if name.starts_with(['a', 'e', 'i', 'o', 'u']) { "an" } else { "a" };
+    format!("{} {}", article, name)
+}
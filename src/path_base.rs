@@ -0,0 +1,97 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::edit_check;
+
+/// Keys whose string values are file system paths in command results
+const PATH_KEYS: &[&str] = &["file", "file_path", "file_name", "path", "manifest", "src_path"];
+
+/// Frame that paths in command results are expressed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathBase {
+    /// Relative to the project (workspace) root
+    #[default]
+    Workspace,
+    /// Relative to the root of the package owning the file
+    Package,
+    Absolute,
+}
+
+impl PathBase {
+    /// The `path_base` argument of a command, defaulting to `workspace`
+    pub fn from_params(params: &Value) -> Result<Self> {
+        match params.get("path_base") {
+            None | Some(Value::Null) => Ok(Self::Workspace),
+            Some(value) => match value.as_str() {
+                Some("workspace") => Ok(Self::Workspace),
+                Some("package") => Ok(Self::Package),
+                Some("absolute") => Ok(Self::Absolute),
+                _ => anyhow::bail!("Invalid path_base {}: expected workspace, package or absolute", value),
+            },
+        }
+    }
+}
+
+/// Rewrite every path-valued field of a command result into `base`. Only
+/// strings naming an existing file or directory are touched, so module paths
+/// such as `std::fmt` and paths of deleted files are left as they are.
+pub fn rewrite_paths(value: &mut Value, project_root: &Path, base: PathBase) {
+    let root = project_root.canonicalize().unwrap_or_else(|_| project_root.to_path_buf());
+    rewrite(value, &root, base);
+}
+
+fn rewrite(value: &mut Value, root: &Path, base: PathBase) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if let Value::String(path) = field {
+                    if PATH_KEYS.contains(&key.as_str()) {
+                        if let Some(rewritten) = rebase(path, root, base) {
+                            *path = rewritten;
+                        }
+                    }
+                } else {
+                    rewrite(field, root, base);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                rewrite(item, root, base);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `path` (absolute, or relative to the project root) expressed in `base`.
+/// `project_root` must be canonical.
+pub fn rebase(path: &str, project_root: &Path, base: PathBase) -> Option<String> {
+    if path.is_empty() || path.contains("://") {
+        return None;
+    }
+    // Relative paths are already workspace-relative; keep them as the caller spelled them
+    if base == PathBase::Workspace && Path::new(path).is_relative() {
+        return None;
+    }
+    // Canonical on both sides, so `..` and symlinked roots still compare equal
+    let absolute = project_root.join(path).canonicalize().ok()?;
+
+    let relative_to = |dir: &Path| {
+        let relative = absolute.strip_prefix(dir).ok()?;
+        Some(if relative.as_os_str().is_empty() { ".".to_string() } else { relative.display().to_string() })
+    };
+    match base {
+        PathBase::Absolute => Some(absolute.display().to_string()),
+        // Paths outside the workspace, such as dependency sources, stay absolute
+        PathBase::Workspace => relative_to(project_root).or_else(|| Some(absolute.display().to_string())),
+        PathBase::Package => {
+            let manifest = edit_check::owning_manifest(&absolute, project_root);
+            let package_root = manifest.parent().unwrap_or(project_root);
+            relative_to(package_root)
+                .or_else(|| relative_to(project_root))
+                .or_else(|| Some(absolute.display().to_string()))
+        }
+    }
+}
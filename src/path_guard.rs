@@ -0,0 +1,69 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Command arguments naming a file or directory to read
+const PATH_PARAMS: &[&str] = &["file", "module"];
+
+/// Reject command arguments naming paths outside the project root and the
+/// configured `allowed_roots`, before any handler reads them
+pub fn check_params(params: &Value, project_root: &Path, allowed_roots: &[String]) -> Result<()> {
+    for key in PATH_PARAMS {
+        if let Some(path) = params.get(*key).and_then(|p| p.as_str()) {
+            resolve_within(path, project_root, allowed_roots)?;
+        }
+    }
+    Ok(())
+}
+
+/// `path` (absolute, or relative to the project root) resolved through `..`
+/// and symlinks, provided it stays inside the project root or one of
+/// `allowed_roots` (themselves relative to the project root unless absolute).
+/// Paths that do not exist yet are resolved as far as they do.
+pub fn resolve_within(path: &str, project_root: &Path, allowed_roots: &[String]) -> Result<PathBuf> {
+    let resolved = resolve(&project_root.join(path));
+    let inside = std::iter::once(project_root.to_path_buf())
+        .chain(allowed_roots.iter().map(|root| project_root.join(root)))
+        .any(|root| resolved.starts_with(resolve(&root)));
+
+    if !inside {
+        anyhow::bail!("Path {} is outside the project root", path);
+    }
+    Ok(resolved)
+}
+
+/// Canonical form of `path`. For paths that do not exist, `.` and `..` are
+/// resolved lexically and the longest existing ancestor is canonicalized.
+fn resolve(path: &Path) -> PathBuf {
+    // Existing paths follow the file system, where `link/..` is the parent of the link target
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+
+    let mut existing = normalized.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.iter().rev().fold(canonical, |path, part| path.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return normalized,
+        }
+    }
+}
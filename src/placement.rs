@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::extract_module::{self, TopLevelItem};
+use crate::reexports;
+use crate::source_files;
+
+/// Candidates returned when the caller sets no limit
+pub const DEFAULT_LIMIT: usize = 5;
+
+/// Words too common in descriptions to say anything about placement
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "into", "that", "this", "which", "when", "new", "add", "adds",
+    "function", "method", "helper", "item", "type", "struct", "returns", "return", "given", "its", "all",
+];
+
+/// A place to put a new item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub file: String,
+    pub module: String,
+    /// 1-based line the new item goes before
+    pub insert_line: usize,
+    /// Item the new one follows, e.g. `impl Config`; absent at the end of a file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    pub score: u32,
+    pub reasons: Vec<String>,
+}
+
+/// Lowercase words of `description` worth matching, plurals reduced
+pub fn keywords(description: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    description.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .flat_map(words)
+        .filter(|word| word.len() >= 3 && !STOP_WORDS.contains(&word.as_str()))
+        .filter(|word| seen.insert(word.clone()))
+        .collect()
+}
+
+/// Lowercase words of an identifier: `parse_config`, `ConfigParser` -> `config`, `parser`
+pub fn words(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in identifier.chars() {
+        let boundary = c == '_' || !c.is_alphanumeric() || (c.is_uppercase() && prev_lower);
+        if boundary && !current.is_empty() {
+            words.push(singular(&current));
+            current.clear();
+        }
+        if c.is_alphanumeric() {
+            current.extend(c.to_lowercase());
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+    }
+    if !current.is_empty() {
+        words.push(singular(&current));
+    }
+    words
+}
+
+fn singular(word: &str) -> String {
+    match word.strip_suffix('s') {
+        Some(stem) if stem.len() >= 3 && !stem.ends_with('s') => stem.to_string(),
+        _ => word.to_string(),
+    }
+}
+
+/// Rank the files under `src` as homes for a new item described by
+/// `description` and related to `symbol`. The module defining `symbol` comes
+/// first, then modules using it, then modules whose names and items match the
+/// description's words.
+pub fn suggest(project_root: &Path, description: &str, symbol: Option<&str>, limit: usize) -> Vec<Candidate> {
+    let src = project_root.join("src");
+    let keywords = keywords(description);
+    let mut candidates: Vec<Candidate> = source_files::rust_files(&src).into_iter()
+        .filter_map(|file| {
+            let source = std::fs::read_to_string(&file).ok()?;
+            let module = reexports::module_path_for(&src, &file)?.join("::");
+            let mut candidate = rate(&source, &module, &keywords, symbol)?;
+            candidate.file = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+            Some(candidate)
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.file.cmp(&b.file)));
+    candidates.truncate(limit);
+    candidates
+}
+
+/// Score one module, `None` when nothing ties it to the new item. `file` is
+/// left empty for the caller to fill in.
+pub fn rate(source: &str, module: &str, keywords: &[String], symbol: Option<&str>) -> Option<Candidate> {
+    let lines: Vec<&str> = source.lines().collect();
+    let items: Vec<TopLevelItem> = extract_module::top_level_items(source).into_iter()
+        .filter(|item| !is_test_module(&lines, item))
+        .collect();
+    let mut score = 0;
+    let mut reasons = Vec::new();
+    let mut anchor: Option<&TopLevelItem> = None;
+
+    if let Some(symbol) = symbol {
+        if let Some(definition) = items.iter().find(|item| item.kind != "impl" && item.name == symbol) {
+            let is_type = matches!(definition.kind.as_str(), "struct" | "enum" | "union" | "trait" | "type_alias");
+            score += if is_type { 100 } else { 80 };
+            reasons.push(format!("defines {} {}", definition.kind, symbol));
+            // Methods go with the type's last impl block
+            anchor = items.iter().rev().find(|item| item.kind == "impl" && item.name == symbol).or(Some(definition));
+        } else {
+            let uses = items.iter()
+                .map(|item| (item, mentions(&lines[item.start..=item.end.min(lines.len() - 1)], symbol)))
+                .filter(|(_, count)| *count > 0)
+                .collect::<Vec<_>>();
+            let total: usize = uses.iter().map(|(_, count)| count).sum();
+            if total > 0 {
+                score += (10 * total as u32).min(50);
+                reasons.push(format!("refers to {} {} time{}", symbol, total, if total == 1 { "" } else { "s" }));
+                anchor = uses.last().map(|(item, _)| *item);
+            }
+        }
+    }
+
+    let module_words: Vec<String> = module.split("::").skip(1).flat_map(words).collect();
+    let module_matches: Vec<&String> = keywords.iter().filter(|k| module_words.contains(k)).collect();
+    if !module_matches.is_empty() {
+        score += 30 * module_matches.len() as u32;
+        reasons.push(format!("module name matches {}", quoted(&module_matches)));
+    }
+
+    let mut named: Vec<&String> = Vec::new();
+    let mut named_items = 0;
+    let mut best: Option<(usize, &TopLevelItem)> = None;
+    // `mod` declarations say where modules live, not what they hold
+    for item in items.iter().filter(|item| item.kind != "module") {
+        let item_words = words(&item.name);
+        let matches: Vec<&String> = keywords.iter().filter(|k| item_words.contains(k)).collect();
+        if matches.is_empty() {
+            continue;
+        }
+        named_items += 1;
+        for keyword in &matches {
+            if !named.contains(keyword) {
+                named.push(keyword);
+            }
+        }
+        // The last of the best matches, so the new item lands after its neighbours
+        if best.is_none_or(|(count, _)| matches.len() >= count) {
+            best = Some((matches.len(), item));
+        }
+    }
+    if named_items > 0 {
+        score += 10 * named_items.min(4) as u32;
+        reasons.push(format!("{} item{} named after {}", named_items, if named_items == 1 { "" } else { "s" }, quoted(&named)));
+    }
+
+    if score == 0 {
+        return None;
+    }
+    let anchor = anchor.or(best.map(|(_, item)| item));
+    let (insert_line, after) = match anchor {
+        Some(item) => {
+            let label = if item.kind == "impl" { format!("impl {}", item.name) } else { format!("{} {}", item.kind, item.name) };
+            reasons.push(format!("after {}", label));
+            (item.end + 2, Some(label))
+        }
+        None => match extract_module::top_level_items(source).into_iter().find(|item| is_test_module(&lines, item)) {
+            Some(tests) => {
+                reasons.push("before the test module".to_string());
+                (tests.start + 1, None)
+            }
+            None => (lines.len() + 1, None),
+        },
+    };
+    Some(Candidate { file: String::new(), module: module.to_string(), insert_line, after, score, reasons })
+}
+
+/// A `#[cfg(test)]` module, where non-test items don't belong
+fn is_test_module(lines: &[&str], item: &TopLevelItem) -> bool {
+    item.kind == "module" && lines[item.start..item.line - 1].iter().any(|l| l.replace(' ', "").starts_with("#[cfg(test)]"))
+}
+
+/// Whole-word occurrences of `name` in code lines
+fn mentions(lines: &[&str], name: &str) -> usize {
+    lines.iter()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .map(|line| {
+            line.match_indices(name)
+                .filter(|(at, _)| {
+                    let before = line[..*at].chars().next_back();
+                    let after = line[at + name.len()..].chars().next();
+                    !before.is_some_and(|c| c.is_alphanumeric() || c == '_') && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+                })
+                .count()
+        })
+        .sum()
+}
+
+fn quoted(words: &[&String]) -> String {
+    words.iter().map(|w| format!("`{}`", w)).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::path_guard;
+
+/// A file to read: a bare path, or a path with a 1-based inclusive line range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FileRequest {
+    Path(String),
+    Range {
+        path: String,
+        start_line: Option<usize>,
+        end_line: Option<usize>,
+    },
+}
+
+impl FileRequest {
+    pub fn path(&self) -> &str {
+        match self {
+            FileRequest::Path(path) | FileRequest::Range { path, .. } => path,
+        }
+    }
+}
+
+/// The content read for one file, or why it could not be read
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// First and last line returned, 1-based
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    pub total_lines: usize,
+    pub bytes: usize,
+    /// Set when the byte budget ran out within or before this file
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadFilesResult {
+    /// Keyed by the paths as requested
+    pub files: BTreeMap<String, FileContent>,
+    pub total_bytes: usize,
+    pub truncated: bool,
+    /// The byte budget applied; 0 when unlimited
+    pub max_bytes: usize,
+}
+
+/// Read `requests` in order until `max_bytes` of content (0 for no limit)
+/// have been returned. A file that cannot be read, or that lies outside the
+/// project root and `allowed_roots`, gets an error entry instead of failing
+/// the whole batch. Only whole lines are returned.
+pub async fn read_files(project_root: &Path, allowed_roots: &[String], requests: &[FileRequest], max_bytes: usize) -> ReadFilesResult {
+    let mut result = ReadFilesResult { max_bytes, ..ReadFilesResult::default() };
+
+    for request in requests {
+        let path = request.path();
+        if result.files.contains_key(path) {
+            continue;
+        }
+        let entry = match read_one(project_root, allowed_roots, request).await {
+            Ok((text, start, end, total_lines)) => {
+                let remaining = if max_bytes == 0 { usize::MAX } else { max_bytes.saturating_sub(result.total_bytes) };
+                let content = cut(&text, remaining);
+                let truncated = content.len() < text.len();
+                result.truncated |= truncated;
+                result.total_bytes += content.len();
+                let end = if truncated { start + content.lines().count().saturating_sub(1) } else { end };
+                FileContent {
+                    bytes: content.len(),
+                    start_line: (!content.is_empty()).then_some(start),
+                    end_line: (!content.is_empty()).then_some(end),
+                    content: Some(content.to_string()),
+                    total_lines,
+                    truncated,
+                    error: None,
+                }
+            }
+            Err(e) => FileContent { error: Some(e.to_string()), ..FileContent::default() },
+        };
+        result.files.insert(path.to_string(), entry);
+    }
+    result
+}
+
+/// The requested lines of a file, their 1-based bounds and the file's line count
+async fn read_one(project_root: &Path, allowed_roots: &[String], request: &FileRequest) -> anyhow::Result<(String, usize, usize, usize)> {
+    let resolved = path_guard::resolve_within(request.path(), project_root, allowed_roots)?;
+    let content = tokio::fs::read_to_string(&resolved).await
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", request.path(), e))?;
+    let total_lines = content.lines().count();
+
+    let (start, end) = match request {
+        FileRequest::Path(_) => return Ok((content, 1, total_lines, total_lines)),
+        FileRequest::Range { start_line, end_line, .. } => (start_line.unwrap_or(1).max(1), end_line.unwrap_or(total_lines).min(total_lines)),
+    };
+    if start > end {
+        anyhow::bail!("Line range {}-{} is empty; {} has {} lines", start, end, request.path(), total_lines);
+    }
+    let text: String = content.split_inclusive('\n').skip(start - 1).take(end - start + 1).collect();
+    Ok((text, start, end, total_lines))
+}
+
+/// The whole lines of `text` that fit in `budget` bytes
+fn cut(text: &str, budget: usize) -> &str {
+    if text.len() <= budget {
+        return text;
+    }
+    // `\n` is ASCII, so slicing just past it always lands on a character boundary
+    match text.as_bytes()[..budget].iter().rposition(|&b| b == b'\n') {
+        Some(newline) => &text[..=newline],
+        None => "",
+    }
+}
@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::signature::split_top_level;
+use crate::source_files;
+
+/// Item kinds recognized when resolving a re-export to its definition
+pub(crate) const ITEM_KEYWORDS: &[(&str, &str)] = &[
+    ("struct", "struct"),
+    ("enum", "enum"),
+    ("union", "union"),
+    ("trait", "trait"),
+    ("fn", "function"),
+    ("type", "type_alias"),
+    ("const", "constant"),
+    ("static", "static"),
+    ("mod", "module"),
+    ("macro_rules!", "macro"),
+];
+
+/// Follow at most this many `pub use` hops before giving up
+const MAX_REEXPORT_DEPTH: usize = 8;
+
+/// Where a re-exported item is actually defined
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Definition {
+    pub file: String,
+    pub line: usize,
+    pub kind: String,
+}
+
+/// One name exported through `pub use`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reexport {
+    /// Public path the item is reachable at, e.g. `crate::Config`
+    pub exported: String,
+    /// File and 1-based line of the `pub use`
+    pub file: String,
+    pub line: usize,
+    /// Path as written in the `use` tree, made absolute where possible
+    pub target: String,
+    /// Name of the external crate when the target lives outside this crate
+    pub external_crate: Option<String>,
+    pub definition: Option<Definition>,
+}
+
+/// A `pub use` leaf before resolution
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseLeaf {
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+}
+
+/// Scan every `.rs` file under `src/` and map `pub use` exports to their definitions
+pub fn find_reexports(project_root: &Path) -> Vec<Reexport> {
+    let src = project_root.join("src");
+    let files = source_files::rust_files(&src);
+
+    let modules: HashMap<Vec<String>, PathBuf> = files.iter()
+        .filter_map(|f| module_path_for(&src, f).map(|m| (m, f.clone())))
+        .collect();
+    let sources: HashMap<PathBuf, String> = files.iter()
+        .filter_map(|f| std::fs::read_to_string(f).ok().map(|c| (f.clone(), c)))
+        .collect();
+
+    let mut result = Vec::new();
+    for file in &files {
+        let (Some(module), Some(source)) = (module_path_for(&src, file), sources.get(file)) else { continue };
+        let display = file.strip_prefix(project_root).unwrap_or(file).display().to_string();
+
+        for (line, statement) in pub_use_statements(source) {
+            for leaf in parse_use_tree(&statement) {
+                let Some(absolute) = absolutize(&module, &leaf.path, &modules) else { continue };
+                let name = leaf.alias.clone().unwrap_or_else(|| absolute.last().cloned().unwrap_or_default());
+                let mut exported = module.clone();
+                exported.push(name);
+
+                let external_crate = (absolute[0] != "crate").then(|| absolute[0].clone());
+                let definition = if external_crate.is_none() {
+                    resolve(&absolute, &modules, &sources, project_root, 0)
+                } else {
+                    None
+                };
+
+                result.push(Reexport {
+                    exported: exported.join("::"),
+                    file: display.clone(),
+                    line,
+                    target: absolute.join("::"),
+                    external_crate,
+                    definition,
+                });
+            }
+        }
+    }
+
+    result
+}
+
+/// `src/lib.rs` -> `crate`, `src/a/b.rs` and `src/a/b/mod.rs` -> `crate::a::b`
+pub fn module_path_for(src: &Path, file: &Path) -> Option<Vec<String>> {
+    let relative = file.strip_prefix(src).ok()?;
+    let mut segments: Vec<String> = relative.with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    if matches!(segments.last().map(|s| s.as_str()), Some("mod")) {
+        segments.pop();
+    }
+    if segments.len() == 1 && (segments[0] == "lib" || segments[0] == "main") {
+        segments.clear();
+    }
+    // Binaries under src/bin are separate crates, not modules of this one
+    if segments.first().is_some_and(|s| s == "bin") {
+        return None;
+    }
+
+    let mut path = vec!["crate".to_string()];
+    path.extend(segments);
+    Some(path)
+}
+
+/// Return each `pub use ...;` statement (joined onto one line) with its 1-based line
+pub fn pub_use_statements(source: &str) -> Vec<(usize, String)> {
+    let mut statements = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or("").trim();
+        if current.is_none() {
+            if let Some(rest) = code.strip_prefix("pub use ") {
+                current = Some((i + 1, String::new()));
+                if let Some((_, text)) = current.as_mut() {
+                    text.push_str(rest);
+                }
+            }
+        } else if let Some((_, text)) = current.as_mut() {
+            text.push(' ');
+            text.push_str(code);
+        }
+
+        if current.as_ref().is_some_and(|(_, text)| text.contains(';')) {
+            let (line, text) = current.take().unwrap();
+            let text = text.split(';').next().unwrap_or("").to_string();
+            statements.push((line, text));
+        }
+    }
+
+    statements
+}
+
+/// Expand a use tree like `a::{b, c::{d as e}, self}` into its leaves
+pub fn parse_use_tree(tree: &str) -> Vec<UseLeaf> {
+    let mut leaves = Vec::new();
+    expand_tree(&normalize_tree(tree), &[], &mut leaves);
+    leaves
+}
+
+/// Collapse whitespace, keeping single spaces only where they separate words (`a as b`)
+fn normalize_tree(tree: &str) -> String {
+    let collapsed = tree.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut result = String::with_capacity(collapsed.len());
+    let chars: Vec<char> = collapsed.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1).copied();
+            let is_punct = |c: Option<char>| c.is_some_and(|c| matches!(c, ':' | '{' | '}' | ','));
+            if is_punct(prev) || is_punct(next) {
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn expand_tree(tree: &str, prefix: &[String], leaves: &mut Vec<UseLeaf>) {
+    let tree = tree.trim_start_matches("::");
+
+    if let Some(brace) = tree.find('{') {
+        let mut base = prefix.to_vec();
+        base.extend(tree[..brace].split("::").filter(|s| !s.is_empty()).map(|s| s.to_string()));
+        let inner = tree[brace + 1..].strip_suffix('}').unwrap_or(&tree[brace + 1..]);
+        for part in split_top_level(inner, ',') {
+            if part == "self" {
+                leaves.push(UseLeaf { path: base.clone(), alias: None });
+            } else if !part.is_empty() {
+                expand_tree(&part, &base, leaves);
+            }
+        }
+        return;
+    }
+
+    let (path, alias) = match tree.split_once(" as ") {
+        Some((path, alias)) => (path, Some(alias.trim().to_string())),
+        None => (tree, None),
+    };
+
+    let mut full = prefix.to_vec();
+    full.extend(path.split("::").filter(|s| !s.is_empty()).map(|s| s.to_string()));
+    if !full.is_empty() {
+        leaves.push(UseLeaf { path: full, alias });
+    }
+}
+
+/// Turn a `self::`/`super::`/`crate::` path into one rooted at `crate`.
+/// A bare first segment is a child module when one exists, otherwise a crate name.
+pub fn absolutize(module: &[String], path: &[String], modules: &HashMap<Vec<String>, PathBuf>) -> Option<Vec<String>> {
+    let mut result = module.to_vec();
+    let mut iter = path.iter().peekable();
+
+    match iter.peek().map(|s| s.as_str()) {
+        Some("crate") => {
+            result = vec!["crate".to_string()];
+            iter.next();
+        }
+        Some("self") => {
+            iter.next();
+        }
+        Some("super") => {
+            while iter.peek().is_some_and(|s| *s == "super") {
+                iter.next();
+                if result.len() <= 1 {
+                    return None;
+                }
+                result.pop();
+            }
+        }
+        Some(first) => {
+            let mut child = module.to_vec();
+            child.push(first.to_string());
+            if !modules.contains_key(&child) {
+                result.clear();
+            }
+        }
+        None => return None,
+    }
+
+    result.extend(iter.cloned());
+    Some(result)
+}
+
+fn resolve(
+    path: &[String],
+    modules: &HashMap<Vec<String>, PathBuf>,
+    sources: &HashMap<PathBuf, String>,
+    project_root: &Path,
+    depth: usize,
+) -> Option<Definition> {
+    if depth > MAX_REEXPORT_DEPTH || path.len() < 2 {
+        return None;
+    }
+
+    let display = |p: &Path| p.strip_prefix(project_root).unwrap_or(p).display().to_string();
+
+    // The target may itself be a module backed by a file
+    if let Some(file) = modules.get(path) {
+        return Some(Definition { file: display(file), line: 1, kind: "module".to_string() });
+    }
+
+    let (name, parent) = path.split_last()?;
+    let file = modules.get(parent)?;
+    let source = sources.get(file)?;
+
+    // A glob re-export exposes everything in the parent module
+    if name == "*" {
+        return Some(Definition { file: display(file), line: 1, kind: "module".to_string() });
+    }
+
+    if let Some((line, kind)) = find_definition(source, name) {
+        return Some(Definition { file: display(file), line, kind });
+    }
+
+    // Follow a chain of re-exports through the parent module
+    for (_, statement) in pub_use_statements(source).into_iter().chain(private_uses(source)) {
+        for leaf in parse_use_tree(&statement) {
+            let exposed = leaf.alias.as_ref().or(leaf.path.last());
+            if exposed == Some(name) {
+                let next = absolutize(parent, &leaf.path, modules)?;
+                if next[0] == "crate" {
+                    return resolve(&next, modules, sources, project_root, depth + 1);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Private `use` statements also bring names into scope for re-export chains
+fn private_uses(source: &str) -> Vec<(usize, String)> {
+    source.lines()
+        .enumerate()
+        .filter_map(|(i, l)| {
+            let code = l.trim();
+            code.strip_prefix("use ")
+                .and_then(|rest| rest.split(';').next())
+                .map(|rest| (i + 1, rest.to_string()))
+        })
+        .collect()
+}
+
+/// Find a top-level item definition named `name`; returns 1-based line and kind
+pub fn find_definition(source: &str, name: &str) -> Option<(usize, String)> {
+    for (i, line) in source.lines().enumerate() {
+        let tokens: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == '(' || c == '<' || c == '{' || c == ':' || c == ';')
+            .filter(|t| !t.is_empty())
+            .collect();
+        for (keyword, kind) in ITEM_KEYWORDS {
+            if let Some(pos) = tokens.iter().position(|t| t == keyword) {
+                if tokens.get(pos + 1) == Some(&name) {
+                    return Some((i + 1, kind.to_string()));
+                }
+            }
+        }
+    }
+    None
+}
@@ -1,28 +1,27 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
 
 use crate::analyzer::{FileId, FileRange, TextRange};
+use crate::module_graph;
+use crate::path_guard;
+use crate::reexports;
+use crate::workspace_edit::{self, uri_to_path, EditSummary};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SourceChange {
     pub label: String,
     pub edits: Vec<TextEdit>,
 }
 
-impl Default for SourceChange {
-    fn default() -> Self {
-        Self {
-            label: String::new(),
-            edits: Vec::new(),
-        }
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct TextEdit {
     pub range: TextRange,
     pub new_text: String,
 }
 
+#[derive(Default)]
 pub struct RefactorEngine;
 
 impl RefactorEngine {
@@ -54,4 +53,128 @@ impl RefactorEngine {
         // Placeholder implementation
         Ok(Vec::new())
     }
-}
\ No newline at end of file
+}
+
+/// Apply a `WorkspaceEdit` from rust-analyzer to the files under
+/// `project_root`. Every file it names is checked to be inside the root
+/// before anything is written; the summary's paths are relative to the root.
+pub async fn apply_workspace_edit(edit: &Value, project_root: &Path) -> Result<EditSummary> {
+    for uri in edited_uris(edit) {
+        path_guard::resolve_within(&uri_to_path(uri).to_string_lossy(), project_root, &[])
+            .with_context(|| format!("Refusing to apply an edit to {}", uri))?;
+    }
+
+    let mut summary = workspace_edit::apply_workspace_edit(edit).await?;
+    let canonical_root = project_root.canonicalize().ok();
+    let relative = |path: &mut String| {
+        let absolute = Path::new(path.as_str());
+        let stripped = absolute.strip_prefix(project_root).ok()
+            .or_else(|| absolute.strip_prefix(canonical_root.as_ref()?).ok());
+        if let Some(stripped) = stripped {
+            *path = stripped.display().to_string();
+        }
+    };
+    summary.files_changed.iter_mut().for_each(relative);
+    summary.files_created.iter_mut().for_each(relative);
+    summary.files_deleted.iter_mut().for_each(relative);
+    for (from, to) in &mut summary.files_renamed {
+        relative(from);
+        relative(to);
+    }
+    Ok(summary)
+}
+
+/// Text edits a `WorkspaceEdit` makes to one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEdits {
+    /// Relative to the project root when inside it
+    pub file: String,
+    pub edits_applied: usize,
+}
+
+/// Text edits per file of a `WorkspaceEdit`, in the order the files first appear
+pub fn file_edits(edit: &Value, project_root: &Path) -> Vec<FileEdits> {
+    let mut files: Vec<FileEdits> = Vec::new();
+    for (uri, count) in uri_edits(edit) {
+        let path = uri_to_path(uri);
+        let file = path.strip_prefix(project_root).unwrap_or(&path).display().to_string();
+        match files.iter_mut().find(|f| f.file == file) {
+            Some(existing) => existing.edits_applied += count,
+            None if count > 0 => files.push(FileEdits { file, edits_applied: count }),
+            None => {}
+        }
+    }
+    files
+}
+
+/// Size of a file's imports, compared before and after organizing them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportStats {
+    pub lines: usize,
+    pub use_statements: usize,
+    /// Paths the `use` statements bring in, each leaf of a tree counted once
+    /// per statement naming it
+    pub imports: usize,
+}
+
+pub fn import_stats(source: &str) -> ImportStats {
+    let statements = module_graph::use_statements(source);
+    ImportStats {
+        lines: source.lines().count(),
+        use_statements: statements.len(),
+        imports: statements.iter().map(|(_, tree)| reexports::parse_use_tree(tree).len()).sum(),
+    }
+}
+
+/// Name of the first function an edit defines: the one rust-analyzer's
+/// extract assists generate (`fun_name`, or `fun_name1` when taken)
+pub fn new_function_name(edit: &Value) -> Option<String> {
+    text_edits(edit).into_iter()
+        .filter_map(|text_edit| text_edit["newText"].as_str())
+        .find_map(|text| {
+            let at = text.match_indices("fn ").map(|(i, _)| i)
+                .find(|&i| i == 0 || !text[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_'))?;
+            let name: String = text[at + 3..].trim_start().chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+            (!name.is_empty()).then_some(name)
+        })
+}
+
+/// Every URI a `WorkspaceEdit` reads or writes, resource operations included
+fn edited_uris(edit: &Value) -> Vec<&str> {
+    let mut uris = Vec::new();
+    if let Some(document_changes) = edit.get("documentChanges").and_then(|d| d.as_array()) {
+        for change in document_changes {
+            for uri in [&change["textDocument"]["uri"], &change["uri"], &change["oldUri"], &change["newUri"]] {
+                uris.extend(uri.as_str());
+            }
+        }
+    } else if let Some(changes) = edit.get("changes").and_then(|c| c.as_object()) {
+        uris.extend(changes.keys().map(String::as_str));
+    }
+    uris
+}
+
+/// The `TextEdit`s of a `WorkspaceEdit`, in order
+fn text_edits(edit: &Value) -> Vec<&Value> {
+    match (edit.get("documentChanges").and_then(|d| d.as_array()), edit.get("changes").and_then(|c| c.as_object())) {
+        (Some(document_changes), _) => document_changes.iter()
+            .filter_map(|change| change["edits"].as_array())
+            .flatten()
+            .collect(),
+        (None, Some(changes)) => changes.values().filter_map(|edits| edits.as_array()).flatten().collect(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/// URI and number of text edits of each document edit, `documentChanges`
+/// taking precedence over `changes` as in `apply_workspace_edit`
+fn uri_edits(edit: &Value) -> Vec<(&str, usize)> {
+    let count = |edits: &Value| edits.as_array().map_or(0, Vec::len);
+    match (edit.get("documentChanges").and_then(|d| d.as_array()), edit.get("changes").and_then(|c| c.as_object())) {
+        (Some(document_changes), _) => document_changes.iter()
+            .filter_map(|change| Some((change["textDocument"]["uri"].as_str()?, count(&change["edits"]))))
+            .collect(),
+        (None, Some(changes)) => changes.iter().map(|(uri, edits)| (uri.as_str(), count(edits))).collect(),
+        (None, None) => Vec::new(),
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::async_audit::fn_header;
+use crate::edit_check;
+use crate::reexports::module_path_for;
+
+/// A test function that calls the function asked about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelatedTest {
+    pub name: String,
+    /// Path `cargo test` filters on, e.g. `tests::parses_empty_input`
+    pub test_path: String,
+    pub file: String,
+    /// 1-based line of the signature
+    pub line: usize,
+    /// Functions between the test and the target, test side first; empty for direct calls
+    pub via: Vec<String>,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RelatedTestsReport {
+    pub function: String,
+    /// `call_hierarchy`, or `text_search` when rust-analyzer couldn't answer
+    pub method: String,
+    pub total: usize,
+    pub tests: Vec<RelatedTest>,
+    /// The caller walk stopped at `max_depth` or the item limit with callers left
+    pub truncated: bool,
+}
+
+/// A function marked with a test attribute
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFunction {
+    pub name: String,
+    /// Inline modules it is nested in, outermost first
+    pub modules: Vec<String>,
+    /// 1-based lines of the signature and of the closing brace
+    pub line: usize,
+    pub end_line: usize,
+    pub body: String,
+}
+
+/// Test functions of `source`: those under `#[test]`, `#[tokio::test]`,
+/// `#[rstest]` or any other attribute whose last segment is `test`
+pub fn test_functions(source: &str) -> Vec<TestFunction> {
+    let mut tests = Vec::new();
+    let mut modules: Vec<(String, i32)> = Vec::new();
+    let mut current: Option<(TestFunction, i32)> = None;
+    let mut marked = false;
+    let mut depth = 0i32;
+
+    for (index, line) in source.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        let trimmed = code.trim();
+
+        if current.is_none() {
+            if is_test_attribute(trimmed) {
+                marked = true;
+            } else if let Some((name, _)) = fn_header(trimmed).filter(|_| marked) {
+                marked = false;
+                let test = TestFunction {
+                    name,
+                    modules: modules.iter().map(|(name, _)| name.clone()).collect(),
+                    line: index + 1,
+                    end_line: index + 1,
+                    body: String::new(),
+                };
+                current = Some((test, depth));
+            } else if let Some(name) = inline_module(trimmed) {
+                modules.push((name, depth));
+            }
+        }
+        if let Some((test, _)) = current.as_mut() {
+            test.body.push_str(line);
+            test.body.push('\n');
+        }
+
+        for c in code.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if current.as_ref().is_some_and(|(_, start)| *start == depth) {
+                        let (mut test, _) = current.take().unwrap();
+                        test.end_line = index + 1;
+                        tests.push(test);
+                    }
+                    while modules.last().is_some_and(|(_, start)| *start >= depth) {
+                        modules.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    tests
+}
+
+/// The test function of `source` spanning a 1-based line
+pub fn test_at(source: &str, line: usize) -> Option<TestFunction> {
+    test_functions(source).into_iter().find(|test| test.line <= line && line <= test.end_line)
+}
+
+/// Whether `body` calls `name`, as a function, method or path segment
+pub fn calls(body: &str, name: &str) -> bool {
+    body.match_indices(name).any(|(at, _)| {
+        let before = body[..at].chars().next_back();
+        let after = body[at + name.len()..].trim_start();
+        !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+            && (after.starts_with('(') || after.starts_with("::<"))
+            && !body[..at].trim_end().ends_with("fn")
+    })
+}
+
+/// Tests in `files` whose body calls `name`, found by text alone
+pub fn search(project_root: &Path, files: &[(PathBuf, String)], name: &str) -> Vec<RelatedTest> {
+    let mut tests = Vec::new();
+    for (file, source) in files {
+        for test in test_functions(source) {
+            let body = test.body.split_once('\n').map_or("", |(_, rest)| rest);
+            if test.name != name && calls(body, name) {
+                tests.push(related(project_root, file, &test, Vec::new()));
+            }
+        }
+    }
+    tests
+}
+
+/// Describe a test found in `file`, with the `cargo test` command running only it
+pub fn related(project_root: &Path, file: &Path, test: &TestFunction, via: Vec<String>) -> RelatedTest {
+    let (target, mut path) = test_target(project_root, file);
+    path.extend(test.modules.iter().cloned());
+    path.push(test.name.clone());
+    let test_path = path.join("::");
+
+    let manifest = edit_check::owning_manifest(file, project_root);
+    let package = (manifest != project_root.join("Cargo.toml"))
+        .then(|| package_name(&manifest))
+        .flatten()
+        .map(|name| format!("-p {} ", name))
+        .unwrap_or_default();
+    RelatedTest {
+        name: test.name.clone(),
+        command: format!("cargo test {}{} -- {} --exact", package, target, test_path),
+        test_path,
+        file: file.strip_prefix(project_root).unwrap_or(file).display().to_string(),
+        line: test.line,
+        via,
+    }
+}
+
+/// The `cargo test` target selecting the crate `file` belongs to, and the
+/// module path of the file within that crate
+fn test_target(project_root: &Path, file: &Path) -> (String, Vec<String>) {
+    let manifest = edit_check::owning_manifest(file, project_root);
+    let package_root = manifest.parent().unwrap_or(project_root);
+    let relative = file.strip_prefix(package_root).unwrap_or(file);
+    let segments: Vec<String> = relative.with_extension("").components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let modules = |rest: &[String]| rest.iter().filter(|s| *s != "main" && *s != "mod").cloned().collect::<Vec<_>>();
+
+    match segments.first().map(String::as_str) {
+        Some(kind @ ("tests" | "examples" | "benches")) if segments.len() >= 2 => {
+            let flag = match kind {
+                "tests" => "--test",
+                "examples" => "--example",
+                _ => "--bench",
+            };
+            (format!("{} {}", flag, segments[1]), modules(&segments[2..]))
+        }
+        Some("src") if segments.get(1).is_some_and(|s| s == "bin") && segments.len() >= 3 => {
+            (format!("--bin {}", segments[2]), modules(&segments[3..]))
+        }
+        _ => {
+            let src = package_root.join("src");
+            let module = module_path_for(&src, file).map(|path| path[1..].to_vec()).unwrap_or_default();
+            let target = if src.join("lib.rs").exists() {
+                "--lib".to_string()
+            } else {
+                format!("--bin {}", package_name(&manifest).unwrap_or_default())
+            };
+            (target, module)
+        }
+    }
+}
+
+/// `package.name` of the Cargo.toml at `manifest`
+pub(crate) fn package_name(manifest: &Path) -> Option<String> {
+    let manifest: toml::Value = toml::from_str(&std::fs::read_to_string(manifest).ok()?).ok()?;
+    manifest.get("package")?.get("name")?.as_str().map(str::to_string)
+}
+
+fn is_test_attribute(line: &str) -> bool {
+    let Some(attribute) = line.strip_prefix("#[") else { return false };
+    let path = attribute.split(['(', ']']).next().unwrap_or("").trim();
+    path == "rstest" || path.rsplit("::").next() == Some("test")
+}
+
+/// Name of an inline module a line opens, such as `mod tests {`
+fn inline_module(line: &str) -> Option<String> {
+    let rest = line.strip_prefix("pub ").or_else(|| line.strip_prefix("pub(crate) ")).unwrap_or(line);
+    let name = rest.strip_prefix("mod ")?.trim_end().strip_suffix('{')?.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::timings::{self, Phase};
+
+/// Outcome of one `cargo run --example`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOutcome {
+    pub example: String,
+    pub command: String,
+    /// `None` when the process was killed or ended by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+/// A line a cargo run printed, forwarded while it runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputLine {
+    /// `stdout` or `stderr`
+    pub stream: String,
+    /// Without its line ending
+    pub line: String,
+}
+
+tokio::task_local! {
+    static OUTPUT: mpsc::Sender<OutputLine>;
+}
+
+/// Run `future`, forwarding every line the cargo runs it starts print to
+/// `output` as it is read. Lines of stdout and stderr arrive in the order they
+/// were read, each tagged with its stream. A full channel pauses the reader,
+/// and closing the receiver kills the run as if it had timed out.
+pub async fn stream_output<F: Future>(output: mpsc::Sender<OutputLine>, future: F) -> F::Output {
+    OUTPUT.scope(output, future).await
+}
+
+/// A cargo invocation run to completion or killed at its time limit
+#[derive(Debug, Clone)]
+pub struct CargoRun {
+    /// `None` when the process was killed or ended by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+/// Build and run the example `name`, killing it (and anything it spawned)
+/// once `timeout` elapses. The timeout covers compilation as well.
+pub async fn run_example(
+    project_root: &Path,
+    name: &str,
+    args: &[String],
+    features: &[String],
+    timeout: Duration,
+) -> Result<RunOutcome> {
+    let mut cargo_args = vec!["run".to_string(), "--example".to_string(), name.to_string()];
+    if !features.is_empty() {
+        cargo_args.push("--features".to_string());
+        cargo_args.push(features.join(","));
+    }
+    if !args.is_empty() {
+        cargo_args.push("--".to_string());
+        cargo_args.extend(args.iter().cloned());
+    }
+
+    let run = run_cargo(project_root, &cargo_args, timeout).await?;
+    Ok(RunOutcome {
+        example: name.to_string(),
+        command: format!("cargo {}", cargo_args.join(" ")),
+        exit_code: run.exit_code,
+        success: run.success,
+        timed_out: run.timed_out,
+        stdout: run.stdout,
+        stderr: run.stderr,
+        duration_ms: run.duration_ms,
+    })
+}
+
+/// Run `cargo` with `cargo_args` in `project_root`, capturing its output and
+/// killing it (and anything it spawned) once `timeout` elapses
+pub async fn run_cargo(project_root: &Path, cargo_args: &[String], timeout: Duration) -> Result<CargoRun> {
+    let mut cmd = Command::new("cargo");
+    cmd.args(cargo_args)
+        .current_dir(project_root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Own process group, so a timeout also reaches the programs cargo spawned
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let started = Instant::now();
+    let mut child = cmd.spawn().context("Failed to run cargo")?;
+    let output = OUTPUT.try_with(|output| output.clone()).ok();
+    let stdout = tokio::spawn(read_lines(child.stdout.take(), "stdout", output.clone()));
+    let stderr = tokio::spawn(read_lines(child.stderr.take(), "stderr", output.clone()));
+    let disconnected = async {
+        match &output {
+            Some(output) => output.closed().await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let finished = timings::measure(Phase::Cargo, async {
+        tokio::select! {
+            status = child.wait() => Some(status),
+            _ = disconnected => None,
+        }
+    });
+    let (status, timed_out) = match tokio::time::timeout(timeout, finished).await {
+        Ok(Some(status)) => (Some(status.context("Failed to wait for cargo")?), false),
+        Ok(None) => {
+            debug!("Output of cargo {} is no longer read, killing it", cargo_args.join(" "));
+            kill_group(&mut child).await;
+            let _ = child.wait().await;
+            (None, false)
+        }
+        Err(_) => {
+            debug!("cargo {} exceeded {:?}, killing it", cargo_args.join(" "), timeout);
+            kill_group(&mut child).await;
+            let _ = child.wait().await;
+            (None, true)
+        }
+    };
+
+    Ok(CargoRun {
+        exit_code: status.and_then(|s| s.code()),
+        success: status.is_some_and(|s| s.success()),
+        timed_out,
+        stdout: stdout.await.unwrap_or_default(),
+        stderr: stderr.await.unwrap_or_default(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Everything `pipe` yields, forwarding each line to `output` on the way.
+/// Bytes that aren't UTF-8 are replaced rather than ending the read.
+async fn read_lines(pipe: Option<impl AsyncRead + Unpin>, stream: &'static str, output: Option<mpsc::Sender<OutputLine>>) -> String {
+    let Some(pipe) = pipe else { return String::new() };
+    let mut reader = BufReader::new(pipe);
+    let mut text = String::new();
+    let mut buffer = Vec::new();
+    while let Ok(read) = reader.read_until(b'\n', &mut buffer).await {
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buffer);
+        text.push_str(&line);
+        if let Some(sink) = &output {
+            let line = OutputLine { stream: stream.to_string(), line: line.trim_end_matches(['\n', '\r']).to_string() };
+            // Keeps draining the pipe once the receiver is gone; the run is being killed
+            let _ = sink.send(line).await;
+        }
+        buffer.clear();
+    }
+    text
+}
+
+async fn kill_group(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .status()
+            .await;
+    }
+    let _ = child.start_kill();
+}
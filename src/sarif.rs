@@ -0,0 +1,101 @@
+use serde_json::{json, Value};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule id of diagnostics that carry no code, such as most rustc warnings
+pub const UNCODED_RULE: &str = "rustc";
+
+/// SARIF level of a cargo/rustc diagnostic level. ICEs are errors; `help`,
+/// `note` and `failure-note` become notes.
+pub fn level(cargo_level: &str) -> &'static str {
+    match cargo_level {
+        "error" | "error: internal compiler error" => "error",
+        "warning" => "warning",
+        _ => "note",
+    }
+}
+
+/// Rule id of a diagnostic: its lint or error code (`E0308`, `unused_variables`,
+/// `clippy::needless_return`)
+pub fn rule_id(diagnostic: &Value) -> &str {
+    diagnostic["code"]["code"].as_str()
+        .or_else(|| diagnostic["code"].as_str())
+        .unwrap_or(UNCODED_RULE)
+}
+
+/// A SARIF 2.1.0 log with one run holding `diagnostics` as returned by
+/// `get_diagnostics`. Each distinct code becomes a rule, with its help page
+/// and the first line of rustc's explanation when there is one.
+pub fn to_sarif(diagnostics: &[Value]) -> Value {
+    let mut rules: Vec<Value> = Vec::new();
+    let mut results = Vec::new();
+
+    for diagnostic in diagnostics {
+        let id = rule_id(diagnostic);
+        let index = match rules.iter().position(|rule| rule["id"] == id) {
+            Some(index) => index,
+            None => {
+                rules.push(rule(id, diagnostic["code"]["explanation"].as_str()));
+                rules.len() - 1
+            }
+        };
+
+        let file = diagnostic["file"].as_str().unwrap_or("");
+        let mut artifact = json!({ "uri": file.trim_start_matches("./") });
+        if !file.starts_with('/') {
+            artifact["uriBaseId"] = json!("%SRCROOT%");
+        }
+        let mut region = json!({});
+        if let Some(line) = diagnostic["line"].as_u64() {
+            region["startLine"] = json!(line);
+        }
+        if let Some(column) = diagnostic["column"].as_u64() {
+            region["startColumn"] = json!(column);
+        }
+
+        results.push(json!({
+            "ruleId": id,
+            "ruleIndex": index,
+            "level": level(diagnostic["level"].as_str().unwrap_or("error")),
+            "message": { "text": diagnostic["message"].as_str().unwrap_or("") },
+            "locations": [{
+                "physicalLocation": { "artifactLocation": artifact, "region": region }
+            }]
+        }));
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cargo",
+                    "informationUri": "https://doc.rust-lang.org/cargo/",
+                    "rules": rules
+                }
+            },
+            "results": results
+        }]
+    })
+}
+
+fn rule(id: &str, explanation: Option<&str>) -> Value {
+    let mut rule = json!({ "id": id });
+    let help = if let Some(lint) = id.strip_prefix("clippy::") {
+        Some(format!("https://rust-lang.github.io/rust-clippy/master/index.html#{}", lint))
+    } else if id.len() == 5 && id.starts_with('E') && id[1..].bytes().all(|b| b.is_ascii_digit()) {
+        Some(format!("https://doc.rust-lang.org/error_codes/{}.html", id))
+    } else {
+        None
+    };
+    if let Some(help) = help {
+        rule["helpUri"] = json!(help);
+    }
+    // Explanations are markdown starting with a one-line summary
+    if let Some(summary) = explanation.and_then(|e| e.lines().map(str::trim).find(|l| !l.is_empty())) {
+        rule["shortDescription"] = json!({ "text": summary });
+    }
+    rule
+}
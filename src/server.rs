@@ -2,9 +2,17 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use tracing::{info, debug};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use tracing::{info, debug, error};
 
 use crate::analyzer::RustAnalyzer;
+use crate::config::Config;
+use crate::param_validation::{self, InvalidParams, ParamsError};
+use crate::path_base::{self, PathBase};
+use crate::path_guard;
+use crate::timings;
+use crate::tool_access;
 use crate::commands::{
     analysis::AnalysisCommands,
     completion::CompletionCommands,
@@ -24,20 +32,14 @@ pub struct McpResponse {
     pub error: Option<String>,
 }
 
-// Internal struct to deserialize Request
-#[derive(Debug, Deserialize)]
-struct RequestData {
-    id: jsonrpc_lite::Id,
-    method: String,
-    params: Option<jsonrpc_lite::Params>,
-}
+/// MCP methods answered by the server itself rather than by a command
+const PROTOCOL_METHODS: &[&str] = &[
+    "initialize", "tools/list", "tools/call", "resources/list", "resources/read", "prompts/list", "prompts/get",
+    "completion/complete",
+];
 
-// Internal struct to deserialize Notification
-#[derive(Debug, Deserialize)]
-struct NotificationData {
-    method: String,
-    params: Option<jsonrpc_lite::Params>,
-}
+/// Sequence behind `internal_request_id`, shared by every server in the process
+static NEXT_INTERNAL_ID: AtomicU64 = AtomicU64::new(1);
 
 pub struct McpServer {
     analyzer: RustAnalyzer,
@@ -51,30 +53,74 @@ pub trait CommandHandler: Send + Sync {
 
 impl McpServer {
     pub async fn new(project_path: &str) -> Result<Self> {
+        let config = Config::resolve(std::path::Path::new(project_path))?;
+        Self::with_config(project_path, config).await
+    }
+    
+    pub async fn with_config(project_path: &str, config: Config) -> Result<Self> {
         info!("Initializing MCP server for project: {}", project_path);
         
-        let analyzer = RustAnalyzer::new(project_path).await?;
+        let analyzer = RustAnalyzer::with_config(project_path, config).await?;
         let mut commands: HashMap<String, Box<dyn CommandHandler>> = HashMap::new();
         
         // Register analysis commands
         commands.insert("analyze_symbol".to_string(), Box::new(AnalysisCommands));
+        commands.insert("search_workspace_symbols".to_string(), Box::new(AnalysisCommands));
         commands.insert("find_references".to_string(), Box::new(AnalysisCommands));
+        commands.insert("call_hierarchy".to_string(), Box::new(AnalysisCommands));
+        commands.insert("type_hierarchy".to_string(), Box::new(AnalysisCommands));
+        commands.insert("goto_definition".to_string(), Box::new(AnalysisCommands));
         commands.insert("get_diagnostics".to_string(), Box::new(AnalysisCommands));
         commands.insert("get_hover".to_string(), Box::new(AnalysisCommands));
         commands.insert("find_implementations".to_string(), Box::new(AnalysisCommands));
+        commands.insert("signature_at".to_string(), Box::new(AnalysisCommands));
+        commands.insert("cfg_status".to_string(), Box::new(AnalysisCommands));
+        commands.insert("build_context".to_string(), Box::new(AnalysisCommands));
+        commands.insert("reexports".to_string(), Box::new(AnalysisCommands));
+        commands.insert("file_summary".to_string(), Box::new(AnalysisCommands));
+        commands.insert("document_outline".to_string(), Box::new(AnalysisCommands));
+        commands.insert("macro_definition".to_string(), Box::new(AnalysisCommands));
+        commands.insert("visibility".to_string(), Box::new(AnalysisCommands));
+        commands.insert("find_unhandled_results".to_string(), Box::new(AnalysisCommands));
+        commands.insert("diagnose".to_string(), Box::new(AnalysisCommands));
+        commands.insert("generics_of".to_string(), Box::new(AnalysisCommands));
+        commands.insert("async_audit".to_string(), Box::new(AnalysisCommands));
+        commands.insert("test_code_audit".to_string(), Box::new(AnalysisCommands));
+        commands.insert("find_dyn".to_string(), Box::new(AnalysisCommands));
+        commands.insert("find_ffi".to_string(), Box::new(AnalysisCommands));
+        commands.insert("suggest_location".to_string(), Box::new(AnalysisCommands));
+        commands.insert("read_files".to_string(), Box::new(AnalysisCommands));
+        commands.insert("analyzer_status".to_string(), Box::new(AnalysisCommands));
+        commands.insert("find_shadowing".to_string(), Box::new(AnalysisCommands));
+        commands.insert("related_tests".to_string(), Box::new(AnalysisCommands));
+        commands.insert("blast_radius".to_string(), Box::new(AnalysisCommands));
+        commands.insert("item_attributes".to_string(), Box::new(AnalysisCommands));
+        commands.insert("open_documents".to_string(), Box::new(AnalysisCommands));
+        commands.insert("close_session".to_string(), Box::new(AnalysisCommands));
+        commands.insert("error_handling_report".to_string(), Box::new(AnalysisCommands));
+        commands.insert("lsp_passthrough".to_string(), Box::new(AnalysisCommands));
         
         // Register completion commands
         commands.insert("complete".to_string(), Box::new(CompletionCommands));
         commands.insert("signature_help".to_string(), Box::new(CompletionCommands));
+        commands.insert("completion_detail".to_string(), Box::new(CompletionCommands));
         commands.insert("get_completions".to_string(), Box::new(CompletionCommands));
         commands.insert("resolve_import".to_string(), Box::new(CompletionCommands));
         commands.insert("expand_snippet".to_string(), Box::new(CompletionCommands));
+        commands.insert("resolve_unresolved".to_string(), Box::new(CompletionCommands));
+        commands.insert("unresolved_imports".to_string(), Box::new(CompletionCommands));
         
         // Register refactoring commands
         commands.insert("rename".to_string(), Box::new(RefactorCommands));
         commands.insert("extract_function".to_string(), Box::new(RefactorCommands));
         commands.insert("inline".to_string(), Box::new(RefactorCommands));
         commands.insert("organize_imports".to_string(), Box::new(RefactorCommands));
+        commands.insert("apply_and_check".to_string(), Box::new(RefactorCommands));
+        commands.insert("apply_change".to_string(), Box::new(RefactorCommands));
+        commands.insert("sync_document".to_string(), Box::new(RefactorCommands));
+        commands.insert("convert_control_flow".to_string(), Box::new(RefactorCommands));
+        commands.insert("extract_module".to_string(), Box::new(RefactorCommands));
+        commands.insert("toggle_async".to_string(), Box::new(RefactorCommands));
         
         // Register metrics commands
         commands.insert("project_structure".to_string(), Box::new(MetricsCommands));
@@ -82,76 +128,332 @@ impl McpServer {
         commands.insert("code_metrics".to_string(), Box::new(MetricsCommands));
         commands.insert("find_dead_code".to_string(), Box::new(MetricsCommands));
         commands.insert("suggest_improvements".to_string(), Box::new(MetricsCommands));
+        commands.insert("check_feature_powerset".to_string(), Box::new(MetricsCommands));
+        commands.insert("derive_usage".to_string(), Box::new(MetricsCommands));
+        commands.insert("metrics_diff".to_string(), Box::new(MetricsCommands));
+        commands.insert("find_duplicates".to_string(), Box::new(MetricsCommands));
+        commands.insert("run_example".to_string(), Box::new(MetricsCommands));
+        commands.insert("run_benchmark".to_string(), Box::new(MetricsCommands));
+        commands.insert("check_formatting".to_string(), Box::new(MetricsCommands));
+        commands.insert("entry_points".to_string(), Box::new(MetricsCommands));
+        commands.insert("reading_order".to_string(), Box::new(MetricsCommands));
+        commands.insert("module_tree".to_string(), Box::new(MetricsCommands));
+        commands.insert("api_surface".to_string(), Box::new(MetricsCommands));
+        commands.insert("missing_docs".to_string(), Box::new(MetricsCommands));
+        commands.insert("feature_graph".to_string(), Box::new(MetricsCommands));
         
         Ok(Self { analyzer, commands })
     }
     
+    pub fn project_root(&self) -> &std::path::Path {
+        self.analyzer.project_root()
+    }
+    
+    pub fn analyzer(&self) -> &RustAnalyzer {
+        &self.analyzer
+    }
+    
+    /// The `id` of a raw JSON-RPC request, verbatim (string, number or null).
+    /// Used by error paths that never got as far as dispatching the request.
+    /// Input that isn't valid JSON, such as a truncated line or two requests
+    /// run together, still gives up the `id` of its first object when that
+    /// much of it is intact.
+    pub fn request_id(request_str: &str) -> Value {
+        match serde_json::from_str::<Value>(request_str) {
+            Ok(value) => value.get("id").cloned().unwrap_or(Value::Null),
+            Err(_) => Self::top_level_id(request_str).unwrap_or(Value::Null),
+        }
+    }
+    
+    /// The value of the first `"id"` key directly inside the outermost object
+    /// of `text`, read without parsing the rest, which may be malformed
+    fn top_level_id(text: &str) -> Option<Value> {
+        let mut depth = 0usize;
+        let mut chars = text.char_indices();
+        while let Some((at, c)) = chars.next() {
+            match c {
+                '{' | '[' => depth += 1,
+                '}' | ']' => {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return None;
+                    }
+                }
+                '"' => {
+                    let mut escaped = false;
+                    let end = chars.by_ref().find(|(_, c)| {
+                        let closes = *c == '"' && !escaped;
+                        escaped = *c == '\\' && !escaped;
+                        closes
+                    })?.0;
+                    let rest = text[end + 1..].trim_start();
+                    if depth == 1 && &text[at..=end] == "\"id\"" && rest.starts_with(':') {
+                        let value = serde_json::Deserializer::from_str(&rest[1..]).into_iter::<Value>().next()?.ok()?;
+                        return matches!(value, Value::String(_) | Value::Number(_) | Value::Null).then_some(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+    
+    /// JSON-RPC `-32700` response to input that isn't valid JSON, keeping the
+    /// request's `id` when it can be read
+    pub fn parse_error(request_str: &str, error: &serde_json::Error) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": Self::request_id(request_str),
+            "error": {
+                "code": -32700,
+                "message": format!("Parse error: {}", error)
+            }
+        })
+    }
+    
+    /// A fresh id for a request the server makes on its own behalf, such as
+    /// the HTTP convenience routes or a resource read delegating to a command.
+    /// Ids are `internal-1`, `internal-2`, ... in call order; they never reach
+    /// a client, whose own id is always the one put on the response.
+    pub fn internal_request_id() -> Value {
+        json!(format!("internal-{}", NEXT_INTERNAL_ID.fetch_add(1, Ordering::Relaxed)))
+    }
+    
+    /// Arguments for a command handler, which dispatches on `method`.
+    /// Missing or `null` arguments become an empty object so zero-argument
+    /// tools can be called without any.
+    fn method_params(method: &str, args: Option<Value>) -> Value {
+        let mut params = match args {
+            None | Some(Value::Null) => json!({}),
+            Some(args) => args,
+        };
+        if let Some(obj) = params.as_object_mut() {
+            obj.insert("method".to_string(), json!(method));
+        }
+        params
+    }
+    
+    /// Run a command handler once its category is known to be enabled and its
+    /// path arguments to stay inside the project, and express the paths in its
+    /// result in the frame requested by its `path_base` argument
+    async fn run_command(&self, handler: &dyn CommandHandler, method: &str, args: Option<Value>) -> Result<Value> {
+        tool_access::check_enabled(method, &self.analyzer.config().tool_categories)?;
+        let schema = Self::tool_schema(method);
+        if let Some(schema) = schema {
+            param_validation::validate(method, schema, args.as_ref().unwrap_or(&Value::Null))?;
+        }
+        let params = Self::method_params(method, args);
+        path_guard::check_params(&params, self.analyzer.project_root(), &self.analyzer.config().allowed_roots)?;
+        let base = PathBase::from_params(&params)?;
+        let mut result = handler.handle(Some(params), &self.analyzer).await.map_err(|e| {
+            // Params the schema accepts but the command can't deserialize
+            match (schema, e.downcast_ref::<ParamsError>()) {
+                (Some(schema), Some(ParamsError(parse))) if parse.is_data() => param_validation::from_parse_error(method, schema, parse).into(),
+                _ => e,
+            }
+        })?;
+        path_base::rewrite_paths(&mut result, self.analyzer.project_root(), base);
+        Ok(result)
+    }
+    
+    /// Run the tool `name` as `tools/call` would, returning its result
+    /// unwrapped, for transports that deliver it in their own framing
+    pub async fn call_tool(&self, name: &str, args: Option<Value>) -> Result<Value> {
+        let handler = self.commands.get(name).ok_or_else(|| anyhow::anyhow!("Unknown tool {}", name))?;
+        self.run_command(handler.as_ref(), name, args).await
+    }
+    
+    /// Answer a request for one of the registered commands under `id`
+    async fn dispatch_command(&self, id: &Value, method: &str, params: Option<Value>) -> Value {
+        let Some(handler) = self.commands.get(method) else {
+            return json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": -32601,
+                    "message": "Method not found"
+                }
+            });
+        };
+        debug!("Found handler for method: {}", method);
+        match self.run_command(handler.as_ref(), method, params).await {
+            Ok(result) => {
+                debug!("Handler returned result: {:?}", result);
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": result
+                })
+            },
+            Err(e) => Self::command_error(id, e, "Command failed")
+        }
+    }
+    
+    /// The error response to a failed command: `-32602` with the schema and
+    /// an example invocation when its params were at fault, `-32603` otherwise
+    fn command_error(id: &Value, e: anyhow::Error, context: &str) -> Value {
+        if let Some(invalid) = e.downcast_ref::<InvalidParams>() {
+            return json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": -32602,
+                    "message": invalid.to_string(),
+                    "data": invalid.data()
+                }
+            });
+        }
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": -32603,
+                "message": format!("{}: {}", context, e)
+            }
+        })
+    }
+    
+    /// Named params for a request that passed `values` by position: they fill
+    /// a command's required fields in schema order. Protocol methods only take
+    /// named params and get a `-32602` response; unknown methods are left to
+    /// report themselves as not found.
+    fn named_params(&self, id: &Value, method: &str, values: &[Value]) -> Result<Option<Value>, Value> {
+        if values.is_empty() {
+            return Ok(None);
+        }
+        if let Some(schema) = Self::tool_schema(method) {
+            return param_validation::from_positional(method, schema, values)
+                .map(Some)
+                .map_err(|invalid| Self::command_error(id, invalid.into(), "Command failed"));
+        }
+        if !PROTOCOL_METHODS.contains(&method) && !self.commands.contains_key(method) {
+            return Ok(None);
+        }
+        Err(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": -32602,
+                "message": format!("Invalid params: {} takes named params; pass an object instead of an array", method)
+            }
+        }))
+    }
+    
+    /// The response to a request, by method
+    async fn respond(&self, id: &Value, method: &str, params: Option<Value>) -> Value {
+        // Handle MCP protocol methods
+        match method {
+            "initialize" => self.handle_initialize(id, params).await,
+            "tools/list" => self.handle_tools_list(id).await,
+            "tools/call" => self.handle_tools_call(id, params).await,
+            "resources/list" => self.handle_resources_list(id).await,
+            "resources/read" => self.handle_resources_read(id, params).await,
+            "prompts/list" => self.handle_prompts_list(id).await,
+            "prompts/get" => self.handle_prompts_get(id, params).await,
+            "completion/complete" => self.handle_completion_complete(id, params).await,
+            _ => self.dispatch_command(id, method, params).await,
+        }
+    }
+    
     pub async fn handle_request(&self, request_str: &str) -> Result<String> {
         debug!("Received request: {}", request_str);
         
         // First parse as generic JSON to extract method and params
-        let json_value: Value = serde_json::from_str(request_str)?;
+        let json_value: Value = match serde_json::from_str(request_str) {
+            Ok(value) => value,
+            Err(e) => {
+                debug!("Failed to parse request: {}", e);
+                return serde_json::to_string(&Self::parse_error(request_str, &e)).context("Failed to serialize response");
+            }
+        };
         
-        // Check if it's a request or notification
-        if let Some(id) = json_value.get("id") {
-            // It's a request
-            let method = json_value.get("method")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| anyhow::anyhow!("Missing method"))?;
-            let params = json_value.get("params").cloned();
-            
-            // Handle MCP protocol methods
-            let response = match method {
-                "initialize" => self.handle_initialize(id, params).await,
-                "tools/list" => self.handle_tools_list(id).await,
-                "tools/call" => self.handle_tools_call(id, params).await,
-                "resources/list" => self.handle_resources_list(id).await,
-                "resources/read" => self.handle_resources_read(id, params).await,
-                "prompts/list" => self.handle_prompts_list(id).await,
-                "prompts/get" => self.handle_prompts_get(id, params).await,
-                "completion/complete" => self.handle_completion_complete(id, params).await,
-                _ => {
-                    // Handle custom methods
-                    if let Some(handler) = self.commands.get(method) {
-                debug!("Found handler for method: {}", method);
-                match handler.handle(params, &self.analyzer).await {
-                    Ok(result) => {
-                        debug!("Handler returned result: {:?}", result);
-                        json!({
-                            "jsonrpc": "2.0",
-                            "id": id,
-                            "result": result
-                        })
-                    },
-                    Err(e) => json!({
+        let response = match json_value {
+            Value::Array(batch) => self.handle_batch(batch).await?,
+            message => self.handle_message(&message).await?,
+        };
+        match response {
+            Some(response) => serde_json::to_string(&response).context("Failed to serialize response"),
+            None => Ok("".to_string()),
+        }
+    }
+    
+    /// Answer a JSON-RPC batch with the responses of its requests, in order.
+    /// Notifications get none, so a batch of only notifications gets no
+    /// response at all; an empty batch is an invalid request.
+    async fn handle_batch(&self, batch: Vec<Value>) -> Result<Option<Value>> {
+        if batch.is_empty() {
+            return Ok(Some(Self::invalid_request(&Value::Null, "Invalid Request: empty batch")));
+        }
+        let mut responses = Vec::new();
+        for message in &batch {
+            if !message.is_object() {
+                responses.push(Self::invalid_request(&Value::Null, "Invalid Request: batch entries must be objects"));
+                continue;
+            }
+            match self.handle_message(message).await {
+                Ok(Some(response)) => responses.push(response),
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Error handling batch entry: {}", e);
+                    responses.push(json!({
                         "jsonrpc": "2.0",
-                        "id": id,
+                        "id": message.get("id").cloned().unwrap_or(Value::Null),
                         "error": {
                             "code": -32603,
-                            "message": format!("Command failed: {}", e)
+                            "message": "Internal error"
                         }
-                    })
+                    }));
                 }
-            } else {
-                json!({
-                    "jsonrpc": "2.0",
-                    "id": id,
-                    "error": {
-                        "code": -32601,
-                        "message": "Method not found"
-                    }
-                })
             }
+        }
+        Ok((!responses.is_empty()).then_some(Value::Array(responses)))
+    }
+    
+    fn invalid_request(id: &Value, message: &str) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": -32600,
+                "message": message
+            }
+        })
+    }
+    
+    /// The response to one request, or `None` for a notification
+    async fn handle_message(&self, json_value: &Value) -> Result<Option<Value>> {
+        // Check if it's a request or notification
+        if let Some(id) = json_value.get("id") {
+            // It's a request
+            let Some(method) = json_value.get("method").and_then(|v| v.as_str()) else {
+                return Ok(Some(Self::invalid_request(id, "Invalid Request: missing method")));
+            };
+            let params = match json_value.get("params") {
+                Some(Value::Array(values)) => match self.named_params(id, method, values) {
+                    Ok(params) => params,
+                    Err(response) => return Ok(Some(response)),
+                },
+                params => params.cloned(),
+            };
+            
+            let response = if self.analyzer.config().timings {
+                let (mut response, timings) = timings::collect(self.respond(id, method, params)).await;
+                if let Some(result) = response.get_mut("result").and_then(Value::as_object_mut) {
+                    let meta = result.entry("_meta").or_insert_with(|| json!({}));
+                    meta["timings"] = serde_json::to_value(timings)?;
                 }
+                response
+            } else {
+                self.respond(id, method, params).await
             };
             
-            serde_json::to_string(&response).context("Failed to serialize response")
+            Ok(Some(response))
         } else {
             // It's a notification
             if let Some(method) = json_value.get("method").and_then(|v| v.as_str()) {
                 info!("Received notification: {}", method);
             }
-            Ok("".to_string())
+            Ok(None)
         }
     }
     
@@ -180,7 +482,20 @@ impl McpServer {
         })
     }
     
-    async fn handle_tools_list(&self, id: &Value) -> Value {
+    /// Every tool with its input schema, whether or not its category is enabled
+    pub fn tool_definitions() -> &'static [Value] {
+        static TOOLS: OnceLock<Vec<Value>> = OnceLock::new();
+        TOOLS.get_or_init(Self::build_tool_definitions)
+    }
+    
+    /// The input schema of the tool `name`
+    fn tool_schema(name: &str) -> Option<&'static Value> {
+        Self::tool_definitions().iter()
+            .find(|tool| tool["name"] == name)
+            .map(|tool| &tool["inputSchema"])
+    }
+    
+    fn build_tool_definitions() -> Vec<Value> {
         let mut tools = Vec::new();
         
         // Add all our commands as tools
@@ -196,13 +511,21 @@ impl McpServer {
         
         tools.push(json!({
             "name": "code_metrics", 
-            "description": "Get code metrics for a module or the entire project",
+            "description": "Get code metrics for a module or the entire project: line counts, declarations and per-function cyclomatic complexity",
             "inputSchema": {
                 "type": "object",
                 "properties": {
                     "module": {
                         "type": "string",
                         "description": "Module path to analyze (e.g., 'src' or '.')"
+                    },
+                    "top": {
+                        "type": "integer",
+                        "description": "How many of the most complex functions to list (default: 10)"
+                    },
+                    "include_hidden": {
+                        "type": "boolean",
+                        "description": "Also count hidden and gitignored files (default: false); target/ is always skipped"
                     }
                 },
                 "required": []
@@ -214,7 +537,12 @@ impl McpServer {
             "description": "Analyze project dependencies from Cargo.toml",
             "inputSchema": {
                 "type": "object",
-                "properties": {},
+                "properties": {
+                    "check_outdated": {
+                        "type": "boolean",
+                        "description": "Compare each requirement with the latest version on crates.io via cargo search, cached for a day; offline, cached versions are used (default: false)"
+                    }
+                },
                 "required": []
             }
         }));
@@ -227,7 +555,8 @@ impl McpServer {
                 "properties": {
                     "name": {
                         "type": "string",
-                        "description": "Snippet name (match_expr, if_let, for_loop, impl_trait, test_fn)"
+                        "description": "Snippet name (match_expr, if_let, for_loop, impl_trait, test_fn)",
+                        "examples": ["match_expr"]
                     }
                 },
                 "required": ["name"]
@@ -251,6 +580,11 @@ impl McpServer {
                     "column": {
                         "type": "number",
                         "description": "Column number (1-based)"
+                    },
+                    "content_format": {
+                        "type": "string",
+                        "enum": ["markdown", "plaintext"],
+                        "description": "Format of returned documentation; plaintext strips markdown (default: markdown)"
                     }
                 },
                 "required": ["file", "line", "column"]
@@ -274,6 +608,19 @@ impl McpServer {
                     "column": {
                         "type": "number",
                         "description": "Column number (1-based)"
+                    },
+                    "content_format": {
+                        "type": "string",
+                        "enum": ["markdown", "plaintext"],
+                        "description": "Format of returned documentation; plaintext strips markdown (default: markdown)"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Return at most this many completions, most relevant first, with a count by kind of the full list (default: max_completions from the config, 50; 0 returns all)"
+                    },
+                    "doc_max_len": {
+                        "type": "integer",
+                        "description": "Cut each item's documentation to about this many characters, keeping whole paragraphs while they fit (default: completion_doc_max_len from the config, 500; 0 keeps it all)"
                     }
                 },
                 "required": ["file", "line", "column"]
@@ -304,8 +651,8 @@ impl McpServer {
         }));
         
         tools.push(json!({
-            "name": "rename",
-            "description": "Rename a symbol at a position",
+            "name": "call_hierarchy",
+            "description": "List the callers or callees of the function at a position, with where each call is made",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -321,19 +668,19 @@ impl McpServer {
                         "type": "number",
                         "description": "Column number (1-based)"
                     },
-                    "new_name": {
+                    "direction": {
                         "type": "string",
-                        "description": "New name for the symbol"
+                        "enum": ["incoming", "outgoing"],
+                        "description": "`incoming` for the functions calling it, `outgoing` for those it calls (default: incoming)"
                     }
                 },
-                "required": ["file", "line", "column", "new_name"]
+                "required": ["file", "line", "column"]
             }
         }));
         
-        // Additional IntelliSense tools
         tools.push(json!({
-            "name": "signature_help",
-            "description": "Get signature help for function calls",
+            "name": "type_hierarchy",
+            "description": "List the supertypes or subtypes of the trait or type at a position",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -348,6 +695,11 @@ impl McpServer {
                     "column": {
                         "type": "number",
                         "description": "Column number (1-based)"
+                    },
+                    "direction": {
+                        "type": "string",
+                        "enum": ["supertypes", "subtypes"],
+                        "description": "`supertypes` for the traits it implements or extends, `subtypes` for the types implementing or extending it (default: supertypes)"
                     }
                 },
                 "required": ["file", "line", "column"]
@@ -355,38 +707,113 @@ impl McpServer {
         }));
         
         tools.push(json!({
-            "name": "get_diagnostics",
-            "description": "Get diagnostics for a file or the entire project",
+            "name": "goto_definition",
+            "description": "Find where the symbol at a position is defined. Locations are 1-based, with files relative to the project root when inside it",
             "inputSchema": {
                 "type": "object",
                 "properties": {
                     "file": {
                         "type": "string",
-                        "description": "File path (optional, omit for project-wide)"
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
                     }
                 },
-                "required": []
+                "required": ["file", "line", "column"]
             }
         }));
         
         tools.push(json!({
-            "name": "analyze_symbol",
-            "description": "Analyze a symbol by name across the project",
+            "name": "rename",
+            "description": "Rename a symbol at a position",
             "inputSchema": {
                 "type": "object",
                 "properties": {
-                    "name": {
+                    "file": {
                         "type": "string",
-                        "description": "Symbol name to analyze"
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    },
+                    "new_name": {
+                        "type": "string",
+                        "description": "New name for the symbol"
+                    },
+                    "apply": {
+                        "type": "boolean",
+                        "description": "Write the edits to disk (default: true); false returns the WorkspaceEdit as a preview"
                     }
                 },
-                "required": ["name"]
+                "required": ["file", "line", "column", "new_name"]
             }
         }));
         
         tools.push(json!({
-            "name": "find_implementations",
-            "description": "Find implementations of a trait or type",
+            "name": "extract_function",
+            "description": "Extract the selected code into a new function with rust-analyzer's assist, write the change to disk and name the function as given",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "start_line": {
+                        "type": "number",
+                        "description": "First line of the selection (1-based)"
+                    },
+                    "start_column": {
+                        "type": "number",
+                        "description": "Column where the selection starts (1-based)"
+                    },
+                    "end_line": {
+                        "type": "number",
+                        "description": "Last line of the selection (1-based)"
+                    },
+                    "end_column": {
+                        "type": "number",
+                        "description": "Column just past the end of the selection (1-based)"
+                    },
+                    "function_name": {
+                        "type": "string",
+                        "description": "Name of the new function"
+                    }
+                },
+                "required": ["file", "start_line", "start_column", "end_line", "end_column", "function_name"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "organize_imports",
+            "description": "Sort, merge and deduplicate a file's imports with rust-analyzer's organize imports action and write the result to disk",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    }
+                },
+                "required": ["file"]
+            }
+        }));
+        
+        // Additional IntelliSense tools
+        tools.push(json!({
+            "name": "signature_help",
+            "description": "Get the signatures of the call at a position, with the active signature and parameter",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -401,6 +828,11 @@ impl McpServer {
                     "column": {
                         "type": "number",
                         "description": "Column number (1-based)"
+                    },
+                    "content_format": {
+                        "type": "string",
+                        "enum": ["markdown", "plaintext"],
+                        "description": "Format of returned documentation; plaintext strips markdown (default: markdown)"
                     }
                 },
                 "required": ["file", "line", "column"]
@@ -408,34 +840,1220 @@ impl McpServer {
         }));
         
         tools.push(json!({
-            "name": "find_dead_code",
-            "description": "Find unused code in the project",
+            "name": "completion_detail",
+            "description": "Resolve a completion item and return its full signature, documentation and how it takes self",
             "inputSchema": {
                 "type": "object",
-                "properties": {},
-                "required": []
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name of the method"
+                    },
+                    "item": {
+                        "type": "object",
+                        "description": "The method's completion item as returned by complete"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root, when no item is given"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based) to complete at, e.g. just after `receiver.`"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    },
+                    "content_format": {
+                        "type": "string",
+                        "enum": ["markdown", "plaintext"],
+                        "description": "Format of returned documentation; plaintext strips markdown (default: markdown)"
+                    }
+                },
+                "required": ["name"]
             }
         }));
         
         tools.push(json!({
-            "name": "suggest_improvements",
-            "description": "Get improvement suggestions for a module",
+            "name": "get_diagnostics",
+            "description": "Get diagnostics for a file or the entire project",
             "inputSchema": {
                 "type": "object",
                 "properties": {
-                    "module": {
+                    "file": {
                         "type": "string",
-                        "description": "Module path to analyze"
+                        "description": "File path (optional, omit for project-wide)"
+                    },
+                    "group_by": {
+                        "type": "string",
+                        "enum": ["lint", "file", "none"],
+                        "description": "Aggregate diagnostics by lint code (or message, when there is no code) or by file (default: none)"
+                    },
+                    "changed": {
+                        "type": "string",
+                        "description": "Only report diagnostics in files changed since this git ref, e.g. HEAD or main; uncommitted and untracked files count as changed. An empty string means HEAD"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["native", "sarif"],
+                        "description": "native returns the usual response; sarif returns a SARIF 2.1.0 log for code-scanning tools, with lint and error codes as rule ids (default: native)"
                     }
                 },
                 "required": []
             }
         }));
         
-        json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "result": {
+        tools.push(json!({
+            "name": "analyze_symbol",
+            "description": "Analyze a symbol by name across the project",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Symbol name to analyze"
+                    },
+                    "timeout_ms": {
+                        "type": "integer",
+                        "description": "Overall time budget in milliseconds; sources still running when it ends are left out and the result is marked partial (default: 10000)"
+                    },
+                    "whole_word": {
+                        "type": "boolean",
+                        "description": "Match whole identifiers only, so `new` doesn't match `renewable` (default: true)"
+                    },
+                    "case_sensitive": {
+                        "type": "boolean",
+                        "description": "Match the name's case exactly (default: true)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Locations to return (default: 50)"
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "Locations to skip, to page through the rest (default: 0)"
+                    }
+                },
+                "required": ["name"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "search_workspace_symbols",
+            "description": "Search the workspace's symbol index by name, falling back to scanning definitions without rust-analyzer",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Name or part of a name; rust-analyzer matches it fuzzily"
+                    },
+                    "kind": {
+                        "type": "string",
+                        "description": "Keep only symbols of this kind, e.g. `function`, `method`, `struct`, `enum`, `trait`, `type_alias`, `constant`, `static` or `module`"
+                    }
+                },
+                "required": ["query"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "find_implementations",
+            "description": "Find implementations of a trait or type",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    },
+                    "with_members": {
+                        "type": "boolean",
+                        "description": "Also list the methods defined in each impl block"
+                    }
+                },
+                "required": ["file", "line", "column"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "signature_at",
+            "description": "Get the structured signature (params, return type, generics, where-clause) of the function defined or referenced at a position",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    }
+                },
+                "required": ["file", "line", "column"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "cfg_status",
+            "description": "Report each #[cfg]/#[cfg_attr] in a file and whether it is active for the enabled features and target",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "target": {
+                        "type": "string",
+                        "description": "Target triple to evaluate against (defaults to the host)"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Additional features to treat as enabled"
+                    }
+                },
+                "required": ["file"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "build_context",
+            "description": "Show what the server's cargo invocations build for: the target triple and where it was configured, the rustc version, the profile, the enabled features and every active cfg, to tell which #[cfg] branches are live",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "reexports",
+            "description": "Map every `pub use` re-export to the original definition, showing the crate's public facade",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "file_summary",
+            "description": "Compact view of a file: module docs, public item signatures without bodies, and private item counts",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    }
+                },
+                "required": ["file"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "document_outline",
+            "description": "Outline of a file from rust-analyzer: every symbol with its kind, name, line range and nested children, such as an impl's methods or an enum's variants",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    }
+                },
+                "required": ["file"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "macro_definition",
+            "description": "Find the definition of the macro invoked at a position; returns the macro_rules! source for declarative macros",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    }
+                },
+                "required": ["file", "line", "column"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "find_dead_code",
+            "description": "Find unused code in the project",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "suggest_improvements",
+            "description": "Get improvement suggestions for a module",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Module path to analyze"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "check_feature_powerset",
+            "description": "Run cargo check across feature combinations (no-default-features, each feature alone, all-features) and report which fail",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "combinations": {
+                        "type": "array",
+                        "items": { "type": "array", "items": { "type": "string" } },
+                        "description": "Explicit feature sets to check (each built with --no-default-features)"
+                    },
+                    "max_combinations": {
+                        "type": "number",
+                        "description": "Maximum number of combinations to check (default: 16)"
+                    },
+                    "concurrency": {
+                        "type": "number",
+                        "description": "Number of cargo check runs in parallel (default: 2)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "derive_usage",
+            "description": "Tally #[derive(...)] usage across the source tree, std vs custom derives, and list derived types missing Debug",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "metrics_diff",
+            "description": "Compare a file's code metrics in the working tree against a git ref: line, function and complexity deltas, and whether complexity increased",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "git_ref": {
+                        "type": "string",
+                        "description": "Revision to compare against (default: HEAD)"
+                    }
+                },
+                "required": ["file"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "find_duplicates",
+            "description": "Find groups of functions with identical or near-identical bodies, comparing token shingles with identifiers, literals, whitespace and comments normalized away",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    },
+                    "threshold": {
+                        "type": "number",
+                        "description": "Minimum similarity from 0.0 to 1.0 for bodies to be grouped (default: 0.9)"
+                    },
+                    "min_tokens": {
+                        "type": "integer",
+                        "description": "Ignore bodies with fewer tokens than this (default: 30)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "run_example",
+            "description": "Build and run an example with cargo run --example, returning stdout, stderr and exit code; the process group is killed when the timeout elapses",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "example": {
+                        "type": "string",
+                        "description": "Example name (file stem under examples/)"
+                    },
+                    "args": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Arguments passed to the example after --"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Cargo features to enable"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Build and run time limit in seconds, capped at the configured max_run_seconds"
+                    }
+                },
+                "required": ["example"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "run_benchmark",
+            "description": "Run cargo bench and parse the timings of libtest (ns/iter) or criterion (estimates, change against the last run), detected from the output; the process group is killed when the timeout elapses",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Run only benchmarks whose name contains this"
+                    },
+                    "bench": {
+                        "type": "string",
+                        "description": "Bench target (file stem under benches/)"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Cargo features to enable"
+                    },
+                    "list": {
+                        "type": "boolean",
+                        "description": "List the benchmarks instead of running them (default: false)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Build and run time limit in seconds, capped at the configured max_run_seconds"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "check_formatting",
+            "description": "Run cargo fmt --check, or rustfmt --check on one file, and list the files that aren't formatted with the diff rustfmt would apply; nothing is written",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root (default: the whole workspace)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "find_unhandled_results",
+            "description": "Find silently ignored errors: unused_must_use warnings from cargo check plus `let _ = ...`, discarded `.ok()` and dropped Result/Option returns of workspace functions",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    },
+                    "include_tests": {
+                        "type": "boolean",
+                        "description": "Also report #[cfg(test)] modules and tests/ directories (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "entry_points",
+            "description": "List crate roots from cargo metadata (library, binaries and [[bin]] targets) with each root file's fn main line and top-level public items",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "all_targets": {
+                        "type": "boolean",
+                        "description": "Also include examples, tests, benches and build scripts (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "apply_change",
+            "description": "Push an edit to rust-analyzer's in-memory copy of a file without writing it to disk; later queries on the file see the edit. Sent as an incremental change when the server supports it",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "range": {
+                        "type": "object",
+                        "description": "LSP range to replace: {start: {line, character}, end: {line, character}}, 0-based"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Replacement text"
+                    }
+                },
+                "required": ["file", "range", "text"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "sync_document",
+            "description": "Replace rust-analyzer's in-memory copy of a file with an editor's unsaved content, so hover, completion and other queries on the file see it. Updates older than the tracked version are ignored",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "version": {
+                        "type": "integer",
+                        "description": "Document version, increasing with each sync; the file is opened at version 1, so start from 2"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Full content of the unsaved buffer"
+                    }
+                },
+                "required": ["file", "version", "text"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "diagnose",
+            "description": "Run cargo check and turn the diagnostics into an ordered fix plan: errors clustered by root cause (e.g. one unresolved name), errors before warnings and dependencies first, each with its primary span and a suggested action",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "include_warnings": {
+                        "type": "boolean",
+                        "description": "Include warning clusters after the errors (default: true)"
+                    },
+                    "max_steps": {
+                        "type": "integer",
+                        "description": "Return only the first N steps"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "convert_control_flow",
+            "description": "List or apply rust-analyzer rewrite assists at a position, such as \"Replace match with if let\" or \"Convert to is_some_and\". Without assist or kind the available assists are listed; otherwise the chosen one is applied and its diff returned",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    },
+                    "end_line": {
+                        "type": "number",
+                        "description": "End line of a selection (1-based, default: line)"
+                    },
+                    "end_column": {
+                        "type": "number",
+                        "description": "End column of a selection (1-based, default: column)"
+                    },
+                    "assist": {
+                        "type": "string",
+                        "description": "Title of the assist to apply, case-insensitive; an unambiguous part of it is enough"
+                    },
+                    "kind": {
+                        "type": "string",
+                        "description": "Code action kind (e.g. refactor.rewrite) or assist id (e.g. replace_match_with_if_let) narrowing the choice"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Return the diff without writing it (default: false)"
+                    }
+                },
+                "required": ["file", "line", "column"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "extract_module",
+            "description": "Move items of a file into a new child module file, declaring the module and re-importing the moved items so existing paths keep working. Private items the parent still needs become pub(super). Returns the new file and a diff",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "module_name": {
+                        "type": "string",
+                        "description": "Name of the new module"
+                    },
+                    "items": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Names of the top-level items to move; impls of moved types follow them"
+                    },
+                    "start_line": {
+                        "type": "number",
+                        "description": "Instead of items: first line (1-based) of a range whose items are moved"
+                    },
+                    "end_line": {
+                        "type": "number",
+                        "description": "Last line (1-based) of the range (default: start_line)"
+                    },
+                    "mod_rs": {
+                        "type": "boolean",
+                        "description": "Create module_name/mod.rs instead of module_name.rs (default: false)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Return the diff without writing it (default: false)"
+                    }
+                },
+                "required": ["file", "module_name"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "toggle_async",
+            "description": "Make a function async, or synchronous when it is async, adding or removing .await at its call sites. A function returning impl Future from an async block becomes an async fn. Refuses, saying why, when the change isn't safe: blocking calls in the body, callers that can't await, awaits in a body made synchronous, trait methods. Returns a diff",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "A line (1-based) of the function's signature or body"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Return the diff without writing it (default: false)"
+                    }
+                },
+                "required": ["file", "line"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "generics_of",
+            "description": "List an item's lifetimes, type parameters with bounds, const parameters and where-clause predicates, plus the generic header as written. Works on a declaration or on a use of the item",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based) of the item or a use of it"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    }
+                },
+                "required": ["file", "line", "column"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "reading_order",
+            "description": "Order the crate's module files for reading by topologically sorting the internal module graph (mod declarations, use statements and crate:: paths); members of dependency cycles are grouped into one step",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "order": {
+                        "type": "string",
+                        "enum": ["roots_first", "leaves_first"],
+                        "description": "Start from the crate root and the modules using others (default) or from the modules depending on nothing"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "async_audit",
+            "description": "List async fns and flag likely-blocking calls made in async code (std::fs, std::thread::sleep, std::sync::Mutex::lock, blocking reqwest, std::process::Command, ...), each attributed to its enclosing async fn with a suggested alternative",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    },
+                    "include_tests": {
+                        "type": "boolean",
+                        "description": "Also scan #[cfg(test)] modules and tests/ directories (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "test_code_audit",
+            "description": "List #[cfg(test)] items and flag code outside them that only builds with tests: paths into dev-dependencies from [dev-dependencies] in Cargo.toml, and uses of test-only items of the same file",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "find_dyn",
+            "description": "List dyn Trait types (Box<dyn ...>, &dyn ..., Arc<dyn ...>) with the trait, extra bounds, container, position (parameter, return, field, body, ...) and enclosing item of each, skipping comments and strings",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    },
+                    "include_tests": {
+                        "type": "boolean",
+                        "description": "Also scan #[cfg(test)] modules and tests/ directories (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "find_ffi",
+            "description": "Map the FFI boundary: extern blocks and their declarations (imported), #[no_mangle]/#[export_name] items and extern fn definitions (exported), and #[repr(C)] types, with locations, names, ABIs and linker symbols",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "suggest_location",
+            "description": "Rank the modules where a new item fits best, with the line to insert it at and the reasons: the module defining a related symbol first, then modules using it, then modules whose names and items match the description",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "description": {
+                        "type": "string",
+                        "description": "What the new item does, e.g. \"parse a config file from TOML\""
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "Name of a type or function the new item belongs with"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of candidates (default: 5)"
+                    }
+                },
+                "required": ["description"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "error_handling_report",
+            "description": "Per module, tally functions returning Result or Option against functions that panic (unwrap, expect, panic!), and flag modules mixing both strategies with the functions going against the module's dominant one",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    },
+                    "include_tests": {
+                        "type": "boolean",
+                        "description": "Also scan #[cfg(test)] modules and tests/ directories (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "lsp_passthrough",
+            "description": "Send any LSP request to rust-analyzer and return its raw result, for features no dedicated command wraps yet. Only offered when enabled in the configuration; requests that may edit need the write category",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "lsp_method": {
+                        "type": "string",
+                        "description": "LSP method, e.g. textDocument/foldingRange or rust-analyzer/expandMacro",
+                        "examples": ["textDocument/foldingRange"]
+                    },
+                    "params": {
+                        "type": "object",
+                        "description": "Request params exactly as the LSP specification defines them; file URIs are absolute"
+                    }
+                },
+                "required": ["lsp_method"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "read_files",
+            "description": "Return the content of several files in one call, keyed by path. Files that cannot be read get an error entry instead of failing the call; content beyond the byte budget is cut at a line boundary and flagged as truncated",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "files": {
+                        "type": "array",
+                        "items": {
+                            "oneOf": [
+                                { "type": "string" },
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "path": { "type": "string" },
+                                        "start_line": { "type": "number" },
+                                        "end_line": { "type": "number" }
+                                    },
+                                    "required": ["path"]
+                                }
+                            ]
+                        },
+                        "description": "Paths relative to project root, or {path, start_line, end_line} objects for a 1-based inclusive line range"
+                    },
+                    "max_bytes": {
+                        "type": "number",
+                        "description": "Total bytes of content to return, at most max_read_bytes from the config (the default)"
+                    }
+                },
+                "required": ["files"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "module_tree",
+            "description": "Compact text outline of the module tree, one line per file or directory, with public/private top-level item counts",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to outline, relative to project root (default: src)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "api_surface",
+            "description": "Count public and private items per module and flag modules whose public surface is unusually large as encapsulation-review candidates",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "max_public_ratio": {
+                        "type": "number",
+                        "description": "Share of public items, 0 to 1, from which a module is flagged (default: 0.75)"
+                    },
+                    "min_items": {
+                        "type": "integer",
+                        "description": "Only flag modules with at least this many items (default: 5)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "missing_docs",
+            "description": "Find public items without doc comments, grouped by module, with the documentation coverage of the crate and of each module",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "lint": {
+                        "type": "boolean",
+                        "description": "Run cargo check with the missing_docs lint for authoritative results, covering methods, fields and variants; falls back to scanning the source when the build fails (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "feature_graph",
+            "description": "Graph of a package's [features]: which features enable other features, optional dependencies or dependency features, plus the default feature set resolved transitively. Read from Cargo.toml, without resolving dependencies",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "Cargo.toml to read, relative to project root (default: Cargo.toml)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "analyzer_status",
+            "description": "Report rust-analyzer's workspace/index status and memory use per query, to explain slow or missing analysis",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root whose crates to include in the status"
+                    },
+                    "include_memory": {
+                        "type": "boolean",
+                        "description": "Also request the memory breakdown (default: true)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "find_shadowing",
+            "description": "Find let bindings that shadow an earlier binding of the same name (let or parameter) within a function, grouped per function, with a heuristic of whether the type changed",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to scan relative to project root (default: src)"
+                    },
+                    "include_tests": {
+                        "type": "boolean",
+                        "description": "Also scan #[cfg(test)] modules and tests/ directories (default: false)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "related_tests",
+            "description": "Find the test functions that call a function, directly or through other functions, using rust-analyzer's call hierarchy (text search in test bodies when unavailable), each with the cargo test command running just that test",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Function name, matched in test bodies when the call hierarchy is unavailable"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root containing the function"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based) of the function name"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based) of the function name"
+                    },
+                    "max_depth": {
+                        "type": "number",
+                        "description": "Call edges to follow from a test down to the function (default: 3)"
+                    }
+                },
+                "required": ["name"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "blast_radius",
+            "description": "Estimate the impact of changing a symbol: the files, functions and tests affected through its references and, transitively, the callers of the functions using it, with counts and a low/medium/high risk level",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    },
+                    "max_depth": {
+                        "type": "integer",
+                        "description": "Levels of callers followed from the functions using the symbol (default: 3)"
+                    }
+                },
+                "required": ["file", "line", "column"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "item_attributes",
+            "description": "List the attributes attached to an item (#[derive], #[cfg], #[serde(...)], #[tokio::main], ...) with their full text, multi-line ones included; without a line, tally attribute usage across a file or directory",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root; alone, the file to tally"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based) of the item or of one of its attributes"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based); resolves a use of the item to its definition"
+                    },
+                    "module": {
+                        "type": "string",
+                        "description": "Directory to tally relative to project root when no file is given (default: src)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "open_documents",
+            "description": "Open a working set of files on rust-analyzer ahead of time so later hover and completion requests on them are fast, keeping them open until close_session; reports which files opened and why others failed",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "files": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "File paths relative to project root"
+                    },
+                    "session": {
+                        "type": "string",
+                        "description": "Session to add the files to (default: a new session, whose id is returned)"
+                    }
+                },
+                "required": ["files"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "close_session",
+            "description": "End a session started by open_documents, closing its files on rust-analyzer unless another session keeps them open",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session": {
+                        "type": "string",
+                        "description": "Session id returned by open_documents"
+                    }
+                },
+                "required": ["session"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "apply_and_check",
+            "description": "Apply an edit to a file, run cargo check on the owning package and report whether it still compiles plus any new diagnostics",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "range": {
+                        "type": "object",
+                        "description": "LSP range to replace: {start: {line, character}, end: {line, character}}, 0-based"
+                    },
+                    "new_text": {
+                        "type": "string",
+                        "description": "Replacement text"
+                    },
+                    "revert_on_error": {
+                        "type": "boolean",
+                        "description": "Restore the original content if the crate no longer compiles (default: false)"
+                    }
+                },
+                "required": ["file", "range", "new_text"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "resolve_unresolved",
+            "description": "Suggest ranked `use` paths, with the edit to insert each, for a name rustc cannot find",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "diagnostic": {
+                        "type": "object",
+                        "description": "An unresolved-name diagnostic from get_diagnostics"
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "Diagnostic message, e.g. \"cannot find type `HashMap` in this scope\""
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line of the unresolved name (1-based), used when no message is given"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column of the unresolved name (1-based)"
+                    }
+                },
+                "required": ["file"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "resolve_import",
+            "description": "Suggest ranked use statements for a symbol name from the workspace, its dependencies (via rust-analyzer's workspace symbols) and std",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "symbol": {
+                        "type": "string",
+                        "description": "Name to import, e.g. HashMap"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "File to import into: paths it already imports are left out, and each suggestion's edit inserts into it"
+                    }
+                },
+                "required": ["symbol"]
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "unresolved_imports",
+            "description": "Run cargo check and list every unresolved import (E0432) with the broken `use` and ranked fixes: the compiler's suggestion, a typo fix, the name found at another path, or a missing dependency, each with the edit or command to apply",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "max_suggestions": {
+                        "type": "integer",
+                        "description": "Suggestions kept per import (default: 5)"
+                    }
+                },
+                "required": []
+            }
+        }));
+        
+        tools.push(json!({
+            "name": "visibility",
+            "description": "Report an item's declared visibility and its effective reachability after walking the enclosing modules and re-exports",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "File path relative to project root"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "Line number (1-based) of the item or a use of it"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "Column number (1-based)"
+                    }
+                },
+                "required": ["file", "line", "column"]
+            }
+        }));
+        
+        // Every command accepts `path_base`; it only matters for those returning paths
+        for tool in &mut tools {
+            if let Some(properties) = tool["inputSchema"]["properties"].as_object_mut() {
+                properties.insert("path_base".to_string(), json!({
+                    "type": "string",
+                    "enum": ["workspace", "package", "absolute"],
+                    "description": "Frame for returned paths: relative to the workspace root (default), relative to the owning package, or absolute"
+                }));
+            }
+        }
+        tools
+    }
+    
+    async fn handle_tools_list(&self, id: &Value) -> Value {
+        // Tools in categories this server was started without are not offered
+        let enabled = &self.analyzer.config().tool_categories;
+        let passthrough = self.analyzer.config().lsp_passthrough;
+        let tools: Vec<&Value> = Self::tool_definitions().iter()
+            .filter(|tool| tool["name"].as_str()
+                .is_some_and(|name| tool_access::disabled_category(name, enabled).is_none()))
+            // `lsp_passthrough` skips the checks of dedicated commands; it is opt-in
+            .filter(|tool| passthrough || tool["name"] != "lsp_passthrough")
+            .collect();
+        
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": {
                 "tools": tools
             }
         })
@@ -443,18 +2061,20 @@ impl McpServer {
     
     async fn handle_tools_call(&self, id: &Value, params: Option<Value>) -> Value {
         if let Some(params) = params {
+            if params.get("arguments").is_some_and(|args| !args.is_object() && !args.is_null()) {
+                return json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": -32602,
+                        "message": "Invalid params: arguments must be an object"
+                    }
+                });
+            }
             if let Some(name) = params.get("name").and_then(|v| v.as_str()) {
-                let args = params.get("arguments").cloned();
-                
-                // Map tool name to method name and add method field
-                let mut method_params = args.unwrap_or(json!({}));
-                if let Some(obj) = method_params.as_object_mut() {
-                    obj.insert("method".to_string(), json!(name));
-                }
-                
                 // Call the appropriate handler
                 if let Some(handler) = self.commands.get(name) {
-                    match handler.handle(Some(method_params), &self.analyzer).await {
+                    match self.run_command(handler.as_ref(), name, params.get("arguments").cloned()).await {
                         Ok(result) => {
                             return json!({
                                 "jsonrpc": "2.0",
@@ -467,16 +2087,7 @@ impl McpServer {
                                 }
                             });
                         }
-                        Err(e) => {
-                            return json!({
-                                "jsonrpc": "2.0",
-                                "id": id,
-                                "error": {
-                                    "code": -32603,
-                                    "message": format!("Tool execution failed: {}", e)
-                                }
-                            });
-                        }
+                        Err(e) => return Self::command_error(id, e, "Tool execution failed"),
                     }
                 }
             }
@@ -536,68 +2147,9 @@ impl McpServer {
     async fn handle_resources_read(&self, id: &Value, params: Option<Value>) -> Value {
         if let Some(params) = params {
             if let Some(uri) = params.get("uri").and_then(|v| v.as_str()) {
-                let result = match uri {
-                    "rust-analyzer://project/structure" => {
-                        // Call our existing project_structure command
-                        if let Some(handler) = self.commands.get("project_structure") {
-                            match handler.handle(Some(json!({"method": "project_structure"})), &self.analyzer).await {
-                                Ok(data) => json!({
-                                    "contents": [{
-                                        "uri": uri,
-                                        "mimeType": "application/json",
-                                        "text": serde_json::to_string_pretty(&data).unwrap_or_default()
-                                    }]
-                                }),
-                                Err(e) => return json!({
-                                    "jsonrpc": "2.0",
-                                    "id": id,
-                                    "error": {
-                                        "code": -32603,
-                                        "message": format!("Failed to read resource: {}", e)
-                                    }
-                                })
-                            }
-                        } else {
-                            return json!({
-                                "jsonrpc": "2.0",
-                                "id": id,
-                                "error": {
-                                    "code": -32603,
-                                    "message": "Resource handler not found"
-                                }
-                            })
-                        }
-                    },
-                    "rust-analyzer://project/dependencies" => {
-                        if let Some(handler) = self.commands.get("analyze_dependencies") {
-                            match handler.handle(Some(json!({"method": "analyze_dependencies"})), &self.analyzer).await {
-                                Ok(data) => json!({
-                                    "contents": [{
-                                        "uri": uri,
-                                        "mimeType": "application/json",
-                                        "text": serde_json::to_string_pretty(&data).unwrap_or_default()
-                                    }]
-                                }),
-                                Err(e) => return json!({
-                                    "jsonrpc": "2.0",
-                                    "id": id,
-                                    "error": {
-                                        "code": -32603,
-                                        "message": format!("Failed to read resource: {}", e)
-                                    }
-                                })
-                            }
-                        } else {
-                            return json!({
-                                "jsonrpc": "2.0",
-                                "id": id,
-                                "error": {
-                                    "code": -32603,
-                                    "message": "Resource handler not found"
-                                }
-                            })
-                        }
-                    },
+                let method = match uri {
+                    "rust-analyzer://project/structure" => "project_structure",
+                    "rust-analyzer://project/dependencies" => "analyze_dependencies",
                     _ => {
                         return json!({
                             "jsonrpc": "2.0",
@@ -610,10 +2162,30 @@ impl McpServer {
                     }
                 };
                 
+                // Run the backing command as an internal request, then answer
+                // the client under its own id
+                let internal = self.dispatch_command(&Self::internal_request_id(), method, None).await;
+                if let Some(error) = internal.get("error") {
+                    return json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": -32603,
+                            "message": format!("Failed to read resource: {}", error["message"].as_str().unwrap_or_default())
+                        }
+                    });
+                }
+                
                 return json!({
                     "jsonrpc": "2.0",
                     "id": id,
-                    "result": result
+                    "result": {
+                        "contents": [{
+                            "uri": uri,
+                            "mimeType": "application/json",
+                            "text": serde_json::to_string_pretty(&internal["result"]).unwrap_or_default()
+                        }]
+                    }
                 });
             }
         }
@@ -849,30 +2421,81 @@ impl McpServer {
             "capabilities": {
                 "analysis": [
                     "analyze_symbol",
+                    "search_workspace_symbols",
                     "find_references",
+                    "call_hierarchy",
+                    "type_hierarchy",
+                    "goto_definition",
                     "get_diagnostics",
                     "get_hover",
-                    "find_implementations"
+                    "find_implementations",
+                    "signature_at",
+                    "cfg_status",
+                    "build_context",
+                    "reexports",
+                    "file_summary",
+                    "document_outline",
+                    "macro_definition",
+                    "visibility",
+                    "find_unhandled_results",
+                    "diagnose",
+                    "generics_of",
+                    "async_audit",
+                    "test_code_audit",
+                    "find_dyn",
+                    "find_ffi",
+                    "suggest_location",
+                    "read_files",
+                    "analyzer_status",
+                    "find_shadowing",
+                    "related_tests",
+                    "blast_radius",
+                    "item_attributes",
+                    "open_documents",
+                    "close_session",
+                    "error_handling_report",
+                    "lsp_passthrough"
                 ],
                 "completion": [
                     "complete",
                     "signature_help",
+                    "completion_detail",
                     "get_completions",
                     "resolve_import",
-                    "expand_snippet"
+                    "expand_snippet",
+                    "resolve_unresolved",
+                    "unresolved_imports"
                 ],
                 "refactoring": [
                     "rename",
                     "extract_function",
                     "inline",
-                    "organize_imports"
+                    "organize_imports",
+                    "apply_and_check",
+                    "apply_change",
+                    "sync_document",
+                    "convert_control_flow",
+                    "extract_module",
+                    "toggle_async"
                 ],
                 "metrics": [
                     "project_structure",
                     "analyze_dependencies",
                     "code_metrics",
                     "find_dead_code",
-                    "suggest_improvements"
+                    "suggest_improvements",
+                    "check_feature_powerset",
+                    "derive_usage",
+                    "metrics_diff",
+                    "find_duplicates",
+                    "run_example",
+                    "run_benchmark",
+                    "check_formatting",
+                    "entry_points",
+                    "reading_order",
+                    "module_tree",
+                    "api_surface",
+                    "missing_docs"
                 ]
             }
         })
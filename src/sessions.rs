@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Documents kept open on rust-analyzer, by session, so hover and completion
+/// within a known working set don't pay for opening files
+#[derive(Debug, Default)]
+pub struct Sessions {
+    /// Session id to the paths it pinned, as given relative to the project root
+    sessions: BTreeMap<String, BTreeSet<String>>,
+    last_id: u64,
+}
+
+/// A file `open_documents` could not open, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedDocument {
+    pub file: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpenReport {
+    pub session: String,
+    pub opened: Vec<String>,
+    pub failed: Vec<FailedDocument>,
+    /// Files the session now keeps open, from this call and earlier ones
+    pub pinned: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CloseReport {
+    pub session: String,
+    /// Files closed on rust-analyzer
+    pub closed: Vec<String>,
+    /// Files left open because another session pinned them too
+    pub kept_open: Vec<String>,
+}
+
+impl Sessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A session id not handed out before
+    pub fn new_id(&mut self) -> String {
+        loop {
+            self.last_id += 1;
+            let id = format!("session-{}", self.last_id);
+            if !self.sessions.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
+    /// Add `files` to `session`, creating it if needed, and return all the
+    /// files it pins
+    pub fn pin(&mut self, session: &str, files: impl IntoIterator<Item = String>) -> Vec<String> {
+        let pinned = self.sessions.entry(session.to_string()).or_default();
+        pinned.extend(files);
+        pinned.iter().cloned().collect()
+    }
+
+    /// End `session`: the files only it pinned, which can be closed, and
+    /// those another session still pins. `None` for an unknown session.
+    pub fn close(&mut self, session: &str) -> Option<(Vec<String>, Vec<String>)> {
+        let files = self.sessions.remove(session)?;
+        Some(files.into_iter().partition(|file| !self.is_pinned(file)))
+    }
+
+    /// Whether any session pins `file`
+    pub fn is_pinned(&self, file: &str) -> bool {
+        self.sessions.values().any(|files| files.contains(file))
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.sessions.keys().cloned().collect()
+    }
+}
@@ -0,0 +1,511 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::async_audit::fn_header;
+use crate::signature::split_top_level;
+use crate::source_files;
+
+/// Methods that take the value out of an `Option` or `Result`
+const UNWRAPS: &[&str] = &["unwrap", "expect", "unwrap_or", "unwrap_or_default", "unwrap_or_else", "unwrap_unchecked"];
+
+/// Methods whose result type doesn't depend on the receiver
+const RETURN_TYPES: &[(&str, &str)] = &[
+    ("len", "usize"),
+    ("count", "usize"),
+    ("to_string", "String"),
+    ("to_vec", "Vec"),
+    ("is_empty", "bool"),
+    ("is_some", "bool"),
+    ("is_none", "bool"),
+    ("is_ok", "bool"),
+    ("is_err", "bool"),
+    ("contains", "bool"),
+    ("starts_with", "bool"),
+    ("ends_with", "bool"),
+];
+
+const INTEGERS: &[&str] = &["i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize"];
+
+/// A `let` that rebinds a name already in scope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shadowing {
+    pub name: String,
+    /// 1-based line of the shadowing `let`
+    pub line: usize,
+    /// 1-based
+    pub column: usize,
+    /// 1-based line of the binding it hides
+    pub previous_line: usize,
+    /// Both bindings are in the same block, rather than the shadowed one in an enclosing block
+    pub same_block: bool,
+    /// The shadowed binding is a function parameter
+    pub shadows_parameter: bool,
+    /// Heuristic: `None` when the types can't be told from the source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_changed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_type: Option<String>,
+    pub code: String,
+}
+
+/// A function and the shadowing inside it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionShadowing {
+    pub file: String,
+    pub function: String,
+    /// 1-based line of the signature
+    pub line: usize,
+    pub shadowings: Vec<Shadowing>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShadowingReport {
+    pub files_scanned: usize,
+    pub total: usize,
+    /// Shadowings known to change the type
+    pub type_changes: usize,
+    /// Only functions that shadow something
+    pub functions: Vec<FunctionShadowing>,
+}
+
+/// A name bound by a `let` or a parameter
+struct Binding {
+    name: String,
+    line: usize,
+    ty: Option<String>,
+    parameter: bool,
+}
+
+/// A block inside a function and the names bound in it so far
+struct Scope {
+    /// Brace depth just outside the block
+    depth: i32,
+    bindings: Vec<Binding>,
+}
+
+/// A function body being scanned
+struct Function {
+    /// Index into the file's results
+    index: usize,
+    /// Brace depth just outside the body
+    depth: i32,
+    /// Parameters first, then one scope per open block
+    scopes: Vec<Scope>,
+}
+
+/// A signature seen whose body hasn't opened yet
+struct Pending {
+    name: String,
+    line: usize,
+    header: String,
+}
+
+/// Scan the `.rs` files under `dir` for shadowed `let` bindings. Test
+/// modules and `tests/` directories are skipped unless `include_tests` is set.
+pub fn find(project_root: &Path, dir: &Path, include_tests: bool) -> ShadowingReport {
+    let mut report = ShadowingReport::default();
+    for file in source_files::rust_files(dir) {
+        let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+        if !include_tests && Path::new(&display).starts_with("tests") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        report.files_scanned += 1;
+        report.functions.extend(scan_source(&content, include_tests).into_iter().map(|f| FunctionShadowing { file: display.clone(), ..f }));
+    }
+
+    report.functions.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    let shadowings = report.functions.iter().flat_map(|f| &f.shadowings);
+    report.total = shadowings.clone().count();
+    report.type_changes = shadowings.filter(|s| s.type_changed == Some(true)).count();
+    report
+}
+
+/// Functions of `source` that shadow a binding, with each shadowing `let`.
+/// `file` is left empty for the caller to fill in.
+pub fn scan_source(source: &str, include_tests: bool) -> Vec<FunctionShadowing> {
+    let mut results: Vec<FunctionShadowing> = Vec::new();
+    let mut functions: Vec<Function> = Vec::new();
+    let mut pending: Option<Pending> = None;
+    let mut depth = 0i32;
+    let mut brackets = 0i32;
+
+    for (index, line) in source.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        let trimmed = code.trim();
+        if !include_tests && trimmed.starts_with("#[cfg(test)]") {
+            break;
+        }
+
+        if let Some((name, _)) = fn_header(trimmed) {
+            pending = Some(Pending { name, line: index + 1, header: String::new() });
+        }
+        if let Some(pending) = pending.as_mut() {
+            pending.header.push_str(code);
+            pending.header.push(' ');
+        }
+
+        if let (Some(rest), Some(function)) = (trimmed.strip_prefix("let "), functions.last_mut()) {
+            let column = code.len() - code.trim_start().len() + 1;
+            let (names, annotation, init) = parse_let(rest);
+            for name in names {
+                let mut ty = annotation.clone().or_else(|| init.as_deref().and_then(init_type));
+                let previous = function.scopes.iter().enumerate().rev()
+                    .find_map(|(at, scope)| scope.bindings.iter().rev().find(|b| b.name == name).map(|b| (at, b)));
+                if let Some((at, previous)) = previous {
+                    let (type_changed, new_type) = compare(previous, &name, ty.as_deref(), init.as_deref());
+                    results[function.index].shadowings.push(Shadowing {
+                        name: name.clone(),
+                        line: index + 1,
+                        column,
+                        previous_line: previous.line,
+                        same_block: at == function.scopes.len() - 1 && !previous.parameter,
+                        shadows_parameter: previous.parameter,
+                        type_changed,
+                        previous_type: previous.ty.clone(),
+                        new_type: new_type.clone(),
+                        code: trimmed.to_string(),
+                    });
+                    ty = new_type;
+                }
+                function.scopes.last_mut().unwrap().bindings.push(Binding { name, line: index + 1, ty, parameter: false });
+            }
+        }
+
+        for c in code.chars() {
+            match c {
+                '{' => {
+                    if let Some(pending) = pending.take() {
+                        results.push(FunctionShadowing { file: String::new(), function: pending.name, line: pending.line, shadowings: Vec::new() });
+                        let parameters = Scope { depth, bindings: parameters(&pending.header, pending.line) };
+                        functions.push(Function { index: results.len() - 1, depth, scopes: vec![parameters] });
+                    }
+                    if let Some(function) = functions.last_mut() {
+                        function.scopes.push(Scope { depth, bindings: Vec::new() });
+                    }
+                    depth += 1;
+                }
+                '}' => {
+                    depth -= 1;
+                    if let Some(function) = functions.last_mut() {
+                        while function.scopes.last().is_some_and(|s| s.depth >= depth) {
+                            function.scopes.pop();
+                        }
+                    }
+                    if functions.last().is_some_and(|f| f.depth >= depth) {
+                        functions.pop();
+                    }
+                }
+                '(' | '[' => brackets += 1,
+                ')' | ']' => brackets -= 1,
+                // A declaration without a body, e.g. in a trait
+                ';' if brackets == 0 => pending = None,
+                _ => {}
+            }
+        }
+    }
+
+    results.retain(|f| !f.shadowings.is_empty());
+    results
+}
+
+/// Names bound by a `let` (after the keyword), its type annotation and initializer.
+/// Plain and tuple patterns are understood; other patterns bind nothing here.
+fn parse_let(rest: &str) -> (Vec<String>, Option<String>, Option<String>) {
+    let statement = rest.trim_end().trim_end_matches(';');
+    let (pattern, init) = match top_level_find(statement, '=') {
+        Some(at) => (&statement[..at], Some(statement[at + 1..].trim().to_string())),
+        None => (statement, None),
+    };
+    let (pattern, annotation) = match top_level_find(pattern, ':') {
+        Some(at) => (pattern[..at].trim(), Some(pattern[at + 1..].split_whitespace().collect::<String>())),
+        None => (pattern.trim(), None),
+    };
+    let init = init.map(|init| init.split(" else ").next().unwrap_or_default().trim().to_string()).filter(|i| !i.is_empty());
+
+    if let Some(inner) = pattern.strip_prefix('(').and_then(|p| p.strip_suffix(')')) {
+        let names = inner.split(',').filter_map(binding_name).collect();
+        return (names, None, None);
+    }
+    (binding_name(pattern).into_iter().collect(), annotation, init)
+}
+
+/// The name a simple pattern such as `x`, `mut x` or `ref x` binds
+fn binding_name(pattern: &str) -> Option<String> {
+    let pattern = pattern.trim();
+    let pattern = pattern.strip_prefix("ref ").unwrap_or(pattern).trim_start();
+    let pattern = pattern.strip_prefix("mut ").unwrap_or(pattern).trim();
+    let is_ident = pattern.starts_with(|c: char| c.is_lowercase() || c == '_')
+        && pattern.chars().all(|c| c.is_alphanumeric() || c == '_');
+    (is_ident && pattern != "_").then(|| pattern.to_string())
+}
+
+/// Parameters of the function whose signature is `header`, with their types
+fn parameters(header: &str, line: usize) -> Vec<Binding> {
+    let Some(after) = header.find("fn ").map(|at| &header[at + 3..]) else { return Vec::new() };
+    let name_len = after.chars().take_while(|c| c.is_alphanumeric() || *c == '_').count();
+    let mut rest = after[name_len..].trim_start();
+    if rest.starts_with('<') {
+        let Some(end) = matching(rest, 0) else { return Vec::new() };
+        rest = rest[end + 1..].trim_start();
+    }
+    let Some(end) = rest.starts_with('(').then(|| matching(rest, 0)).flatten() else { return Vec::new() };
+    split_top_level(&rest[1..end], ',').into_iter().filter_map(|param| {
+        let at = top_level_find(&param, ':')?;
+        let name = binding_name(&param[..at])?;
+        let ty = param[at + 1..].split_whitespace().collect::<String>();
+        Some(Binding { name, line, ty: Some(ty), parameter: true })
+    }).collect()
+}
+
+/// Whether shadowing `previous` changed the type, and the new binding's type.
+/// An initializer that only moves, clones, borrows or unwraps the old binding
+/// settles it; otherwise the two type hints are compared.
+fn compare(previous: &Binding, name: &str, ty: Option<&str>, init: Option<&str>) -> (Option<bool>, Option<String>) {
+    if let (None, Some(init)) = (ty, init) {
+        if init == name || init == format!("{}.clone()", name) {
+            return (Some(false), previous.ty.clone());
+        }
+        if let Some(referent) = init.strip_prefix("&mut ").or_else(|| init.strip_prefix('&')) {
+            if referent.trim() == name {
+                let kind = if init.starts_with("&mut ") { "&mut " } else { "&" };
+                return (Some(true), previous.ty.as_ref().map(|t| format!("{}{}", kind, t)));
+            }
+        }
+        let (base, unwrapped) = strip_unwraps(init);
+        if base == name && unwrapped > 0 {
+            let inner = previous.ty.as_deref().and_then(|t| (0..unwrapped).try_fold(t.to_string(), |t, _| unwrap_type(&t)));
+            return (Some(true), inner);
+        }
+    }
+    let ty = ty.map(str::to_string);
+    (types_differ(previous.ty.as_deref(), ty.as_deref()), ty)
+}
+
+/// Compare two type hints. Literal placeholders such as `{integer}` match
+/// any type of their kind, so the answer is unknown rather than a change.
+fn types_differ(previous: Option<&str>, new: Option<&str>) -> Option<bool> {
+    let (previous, new) = (previous?, new?);
+    if previous == new {
+        return Some(false);
+    }
+    let kind = |t: &str| match t {
+        "{integer}" => Some("int"),
+        "{float}" => Some("float"),
+        t if INTEGERS.contains(&t) => Some("int"),
+        "f32" | "f64" => Some("float"),
+        _ => None,
+    };
+    if previous.starts_with('{') || new.starts_with('{') {
+        return match (kind(previous), kind(new)) {
+            (Some(a), Some(b)) if a == b => None,
+            _ => Some(true),
+        };
+    }
+    // `Vec` and `Vec<u8>` are the same as far as hints go
+    let base = |t: &str| t.split('<').next().unwrap_or(t).to_string();
+    if !previous.contains('<') || !new.contains('<') {
+        return Some(base(previous) != base(new));
+    }
+    Some(true)
+}
+
+/// Heuristic type of an initializer expression, when the source gives it away
+fn init_type(init: &str) -> Option<String> {
+    let (base, unwrapped) = strip_unwraps(init);
+    if unwrapped > 0 {
+        return (0..unwrapped).try_fold(init_type(base)?, |t, _| unwrap_type(&t));
+    }
+    let init = base;
+
+    if let Some(at) = init.rfind(" as ") {
+        let ty = init[at + 4..].trim();
+        if ty.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':') {
+            return Some(ty.to_string());
+        }
+    }
+    if let Some(rest) = init.strip_prefix('&') {
+        return init_type(rest.trim_start()).map(|t| format!("&{}", t));
+    }
+    if init.starts_with('"') || init.starts_with("r\"") || init.starts_with("r#\"") {
+        return Some("&str".to_string());
+    }
+    if init.starts_with("b\"") {
+        return Some("&[u8]".to_string());
+    }
+    if init.starts_with('\'') && init.ends_with('\'') && init.len() <= 6 {
+        return Some("char".to_string());
+    }
+    if init == "true" || init == "false" {
+        return Some("bool".to_string());
+    }
+    if let Some(ty) = number_type(init) {
+        return Some(ty);
+    }
+    if init.starts_with("format!(") || init.starts_with("String::") {
+        return Some("String".to_string());
+    }
+    if init.starts_with("vec![") || init.starts_with("Vec::") {
+        return Some("Vec".to_string());
+    }
+    if init == "None" || init.starts_with("Some(") {
+        return Some("Option".to_string());
+    }
+    if init.starts_with("Ok(") || init.starts_with("Err(") {
+        return Some("Result".to_string());
+    }
+
+    if let Some((receiver, method, turbofish)) = last_call(init) {
+        if let Some(ty) = turbofish {
+            return Some(if method == "parse" { format!("Result<{}, _>", ty) } else { ty.to_string() });
+        }
+        if let Some((_, ty)) = RETURN_TYPES.iter().find(|(m, _)| *m == method) {
+            return Some(ty.to_string());
+        }
+        if method == "to_owned" && receiver.starts_with('"') {
+            return Some("String".to_string());
+        }
+        return None;
+    }
+    // `Type::new(..)`, `Type::from(..)`, `Type::default()` and struct literals
+    let path_end = init.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).unwrap_or(init.len());
+    let (path, after) = init.split_at(path_end);
+    let segments: Vec<&str> = path.split("::").collect();
+    let is_type = |s: &str| s.starts_with(|c: char| c.is_uppercase());
+    if after.trim_start().starts_with('{') && segments.last().is_some_and(|s| is_type(s)) && init.ends_with('}') {
+        return segments.last().map(|s| s.to_string());
+    }
+    if after.starts_with('(') && matching(after, 0) == Some(after.len() - 1) && segments.len() >= 2 {
+        let ty = segments[segments.len() - 2];
+        let constructor = segments[segments.len() - 1];
+        let constructs = matches!(constructor, "new" | "from" | "default") || constructor.starts_with("with_") || constructor.starts_with("from_");
+        if is_type(ty) && constructs {
+            return Some(ty.to_string());
+        }
+    }
+    None
+}
+
+/// Type of a numeric literal: its suffix, or a placeholder for its kind
+fn number_type(init: &str) -> Option<String> {
+    let digits = init.strip_prefix('-').unwrap_or(init);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) || !digits.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+        return None;
+    }
+    let suffix = ["f32", "f64"].iter().chain(INTEGERS).find(|s| digits.ends_with(*s) && !digits.starts_with("0x"));
+    Some(match suffix {
+        Some(suffix) => suffix.to_string(),
+        None if digits.contains('.') || (digits.contains('e') && !digits.starts_with("0x")) => "{float}".to_string(),
+        None => "{integer}".to_string(),
+    })
+}
+
+/// `expr` without trailing `?`, `.await` and unwrapping calls, and how many
+/// `Option`/`Result` layers those take off (`.await` takes none)
+fn strip_unwraps(expr: &str) -> (&str, usize) {
+    let mut expr = expr.trim();
+    let mut unwrapped = 0;
+    loop {
+        if let Some(rest) = expr.strip_suffix('?') {
+            expr = rest.trim_end();
+            unwrapped += 1;
+        } else if let Some(rest) = expr.strip_suffix(".await") {
+            expr = rest.trim_end();
+        } else if let Some((receiver, _, _)) = last_call(expr).filter(|(_, method, _)| UNWRAPS.contains(method)) {
+            expr = receiver;
+            unwrapped += 1;
+        } else {
+            return (expr, unwrapped);
+        }
+    }
+}
+
+/// `T` out of `Option<T>` or `Result<T, E>`
+fn unwrap_type(ty: &str) -> Option<String> {
+    let inner = ty.strip_prefix("Option<").or_else(|| ty.strip_prefix("Result<"))?.strip_suffix('>')?;
+    split_top_level(inner, ',').first().map(|t| t.trim().to_string()).filter(|t| !t.is_empty() && t != "_")
+}
+
+/// Receiver, method name and turbofish type of a trailing method call, as in
+/// `receiver.method::<T>(args)`
+fn last_call(expr: &str) -> Option<(&str, &str, Option<&str>)> {
+    if !expr.ends_with(')') {
+        return None;
+    }
+    let open = matching_back(expr, expr.len() - 1)?;
+    let before = &expr[..open];
+    let (before, turbofish) = match before.strip_suffix('>') {
+        Some(_) => {
+            let start = before.rfind("::<")?;
+            (&before[..start], Some(&before[start + 3..before.len() - 1]))
+        }
+        None => (before, None),
+    };
+    let name_start = before.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |at| at + 1);
+    let receiver = before[..name_start].strip_suffix('.')?;
+    Some((receiver.trim_end(), &before[name_start..], turbofish))
+}
+
+/// Index of the bracket closing the one at `open`
+fn matching(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (at, c) in text.char_indices().skip_while(|(at, _)| *at < open) {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' if !text[..at].ends_with('-') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(at);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Index of the `(` opening the `)` at `close`
+fn matching_back(text: &str, close: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (at, c) in text[..=close].char_indices().rev() {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' | '[' | '{' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(at);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// First `target` outside brackets, skipping `::`, `==`, `=>` and friends
+fn top_level_find(text: &str, target: char) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0i32;
+    for (at, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' if !text[..at].ends_with('-') => depth -= 1,
+            c if c == target && depth == 0 => {
+                let next = bytes.get(at + 1).copied();
+                let prev = at.checked_sub(1).map(|p| bytes[p]);
+                let part_of_operator = match target {
+                    ':' => next == Some(b':') || prev == Some(b':'),
+                    '=' => matches!(next, Some(b'=') | Some(b'>')) || matches!(prev, Some(b'=') | Some(b'!') | Some(b'<') | Some(b'>')),
+                    _ => false,
+                };
+                if !part_of_operator {
+                    return Some(at);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
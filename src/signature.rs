@@ -0,0 +1,497 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::markup::ContentFormat;
+
+/// A single function parameter as written in the signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Param {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// Structured view of a function/method signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FnSignature {
+    pub name: String,
+    pub qualifiers: Vec<String>,
+    pub generics: Option<String>,
+    pub params: Vec<Param>,
+    pub return_type: Option<String>,
+    pub where_clause: Option<String>,
+    pub rendered: String,
+}
+
+/// Extract the text of the function signature the cursor at `line` (0-based)
+/// belongs to: the `fn` on that line, the one its attributes or doc comment
+/// annotate, or the one whose signature or body encloses it. The signature runs
+/// from the `fn` line up to the opening brace or terminating semicolon of the item.
+pub fn extract_signature_text(source: &str, line: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    if line >= lines.len() {
+        return None;
+    }
+
+    let start = annotated_fn(&lines, line)
+        .or_else(|| header_start(&lines, line))
+        .or_else(|| enclosing_fn(&lines, line))?;
+
+    let mut text = String::new();
+    for l in &lines[start..] {
+        let trimmed = l.trim();
+        if trimmed.starts_with("//") {
+            continue;
+        }
+        if let Some(end) = find_body_start(trimmed) {
+            text.push_str(&trimmed[..end]);
+            return Some(normalize_whitespace(&text));
+        }
+        text.push_str(trimmed);
+        text.push(' ');
+    }
+
+    Some(normalize_whitespace(&text))
+}
+
+/// The `fn` line of the item whose attributes and doc comments run from `line`
+/// down to it
+fn annotated_fn(lines: &[&str], line: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, l) in lines.iter().enumerate().skip(line) {
+        let trimmed = l.trim();
+        if trimmed.starts_with("//") {
+            continue;
+        }
+        if depth == 0 && !trimmed.starts_with("#[") && !trimmed.starts_with("#![") {
+            return contains_fn_keyword(l).then_some(i);
+        }
+        // An attribute may span several lines
+        depth += trimmed.matches('[').count() as i32 - trimmed.matches(']').count() as i32;
+    }
+    None
+}
+
+/// The `fn` line of the signature that `line` continues, e.g. a parameter of a
+/// signature split over several lines
+fn header_start(lines: &[&str], line: usize) -> Option<usize> {
+    let mut i = line;
+    loop {
+        if contains_fn_keyword(code_of(lines[i])) {
+            return Some(i);
+        }
+        if i == 0 {
+            return None;
+        }
+        let previous = code_of(lines[i - 1]).trim_end();
+        if previous.is_empty() || find_body_start(previous).is_some() || previous.ends_with('}') {
+            return None;
+        }
+        i -= 1;
+    }
+}
+
+/// The `fn` line of the innermost function whose body is still open at `line`
+fn enclosing_fn(lines: &[&str], line: usize) -> Option<usize> {
+    let mut closed = 0usize;
+    for i in (0..line).rev() {
+        let mut opens_block = false;
+        for c in code_of(lines[i]).chars().rev() {
+            match c {
+                '}' => closed += 1,
+                '{' if closed > 0 => closed -= 1,
+                '{' => opens_block = true,
+                _ => {}
+            }
+        }
+        // A block left open around the cursor: a function's body, or one
+        // nested in it, e.g. an `if`, so keep looking outwards
+        if opens_block {
+            if let Some(start) = header_start(lines, i) {
+                return Some(start);
+            }
+        }
+    }
+    None
+}
+
+/// A line without its trailing `//` comment
+fn code_of(line: &str) -> &str {
+    line.split("//").next().unwrap_or("")
+}
+
+/// Pull the first function signature out of rust-analyzer hover markdown
+pub fn extract_from_hover(hover: &str) -> Option<String> {
+    let mut in_code = false;
+    let mut code = String::new();
+
+    for line in hover.lines() {
+        if line.trim_start().starts_with("```") {
+            if in_code && contains_fn_keyword(&code) {
+                break;
+            }
+            in_code = !in_code;
+            code.clear();
+            continue;
+        }
+        if in_code {
+            code.push_str(line);
+            code.push('\n');
+        }
+    }
+
+    let line = code.lines().position(contains_fn_keyword)?;
+    extract_signature_text(&code, line)
+}
+
+/// Parse a (single line or multi-line) function signature into its parts
+pub fn parse_signature(text: &str) -> Option<FnSignature> {
+    let text = normalize_whitespace(text);
+    let fn_pos = find_fn_keyword(&text)?;
+
+    let qualifiers: Vec<String> = text[..fn_pos]
+        .split_whitespace()
+        .map(|s| s.to_string())
+        .collect();
+
+    let rest = &text[fn_pos + 3..];
+    let name_len = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let name = rest[..name_len].to_string();
+    if name.is_empty() {
+        return None;
+    }
+    let mut rest = rest[name_len..].trim_start();
+
+    let mut generics = None;
+    if rest.starts_with('<') {
+        let end = find_matching(rest, '<', '>')?;
+        generics = Some(rest[..=end].to_string());
+        rest = rest[end + 1..].trim_start();
+    }
+
+    if !rest.starts_with('(') {
+        return None;
+    }
+    let end = find_matching(rest, '(', ')')?;
+    let params = split_top_level(&rest[1..end], ',')
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .map(|p| parse_param(&p))
+        .collect();
+    rest = rest[end + 1..].trim();
+
+    let (before_where, where_clause) = match find_top_level_keyword(rest, "where") {
+        Some(pos) => (
+            rest[..pos].trim(),
+            Some(rest[pos..].trim().trim_end_matches([',', '{', ';']).trim().to_string()),
+        ),
+        None => (rest, None),
+    };
+
+    let return_type = before_where
+        .strip_prefix("->")
+        .map(|r| r.trim().trim_end_matches(['{', ';']).trim().to_string())
+        .filter(|r| !r.is_empty());
+
+    // Tidy up artifacts of joining a multi-line parameter list
+    let rendered = text
+        .trim_end_matches(['{', ';'])
+        .trim()
+        .replace("( ", "(")
+        .replace(", )", ")")
+        .replace(",)", ")");
+
+    Some(FnSignature {
+        name,
+        qualifiers,
+        generics,
+        params,
+        return_type,
+        where_clause,
+        rendered,
+    })
+}
+
+fn parse_param(param: &str) -> Param {
+    let parts = split_top_level(param, ':');
+    if parts.len() >= 2 {
+        Param {
+            name: parts[0].clone(),
+            ty: parts[1..].join(": "),
+        }
+    } else {
+        // `self`, `&self`, `&mut self`, `mut self`
+        Param {
+            name: "self".to_string(),
+            ty: param.trim().to_string(),
+        }
+    }
+}
+
+/// How a method takes `self`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Receiver {
+    #[serde(rename = "&self")]
+    Ref,
+    #[serde(rename = "&mut self")]
+    RefMut,
+    #[serde(rename = "self")]
+    Owned,
+}
+
+impl FnSignature {
+    /// The kind of `self` parameter, or `None` for an associated function.
+    /// Explicit forms count by their type: `self: &mut Self` and
+    /// `self: Pin<&mut Self>` borrow mutably, `self: Box<Self>` takes ownership.
+    pub fn receiver(&self) -> Option<Receiver> {
+        let first = self.params.first().filter(|p| p.name == "self" || p.name == "mut self")?;
+        let ty = first.ty.replace(' ', "");
+        Some(if ty.contains("&mut") || (ty.contains("&'") && ty.contains("mut")) {
+            Receiver::RefMut
+        } else if ty.contains('&') {
+            Receiver::Ref
+        } else {
+            Receiver::Owned
+        })
+    }
+}
+
+/// Parse the `detail` of a function completion item. rust-analyzer leaves the
+/// name out (`pub fn(&mut self, value: T)`), so `name` is put back in.
+pub fn parse_completion_detail(name: &str, detail: &str) -> Option<FnSignature> {
+    let fn_pos = find_fn_keyword(&detail.replacen("fn(", "fn (", 1))?;
+    let after = detail[fn_pos + 2..].trim_start();
+    if after.starts_with('(') {
+        parse_signature(&format!("{}fn {}{}", &detail[..fn_pos], name, after))
+    } else {
+        parse_signature(detail)
+    }
+}
+
+fn contains_fn_keyword(text: &str) -> bool {
+    find_fn_keyword(text).is_some()
+}
+
+/// Find the `fn` keyword as a whole word, ignoring `Fn`/`FnMut` and identifiers
+/// that merely contain "fn".
+fn find_fn_keyword(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut search = 0;
+    while let Some(pos) = text[search..].find("fn ") {
+        let abs = search + pos;
+        let boundary = abs == 0 || !(bytes[abs - 1].is_ascii_alphanumeric() || bytes[abs - 1] == b'_');
+        if boundary {
+            return Some(abs);
+        }
+        search = abs + 3;
+    }
+    None
+}
+
+/// Position of the `{` or `;` that ends the signature, at bracket depth 0
+pub(crate) fn find_body_start(text: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '>' if prev != '-' => depth -= 1,
+            '{' | ';' if depth <= 0 => return Some(i),
+            _ => {}
+        }
+        prev = c;
+    }
+    None
+}
+
+/// Index of the bracket closing the one at position 0
+pub(crate) fn find_matching(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close && !(close == '>' && prev == '-') {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+        prev = c;
+    }
+    None
+}
+
+/// Split on `sep` only when it is not nested inside brackets
+pub(crate) fn split_top_level(text: &str, sep: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut prev = ' ';
+    let chars: Vec<char> = text.chars().collect();
+
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '(' | '[' | '<' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '>' if prev != '-' => depth -= 1,
+            _ => {}
+        }
+        // `::` in paths is not a name/type separator
+        let is_path_sep = sep == ':'
+            && (chars.get(i + 1) == Some(&':') || (i > 0 && chars[i - 1] == ':'));
+        if c == sep && depth == 0 && !is_path_sep {
+            parts.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+        prev = c;
+    }
+    parts.push(current.trim().to_string());
+    parts
+}
+
+pub(crate) fn find_top_level_keyword(text: &str, keyword: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '>' if prev != '-' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0
+            && text[i..].starts_with(keyword)
+            && (i == 0 || !text.as_bytes()[i - 1].is_ascii_alphanumeric())
+            && text[i + keyword.len()..].starts_with(|c: char| c.is_whitespace())
+        {
+            return Some(i);
+        }
+        prev = c;
+    }
+    None
+}
+
+pub(crate) fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// List the methods declared directly inside the `impl` block that starts at or
+/// just below `line` (0-based). Returns each method's 0-based line and signature text.
+pub fn impl_block_methods(source: &str, line: usize) -> Vec<(usize, String)> {
+    let lines: Vec<&str> = source.lines().collect();
+    let Some(start) = (line..lines.len().min(line + 5)).find(|&i| {
+        let trimmed = lines[i].trim_start();
+        trimmed.starts_with("impl") || trimmed.contains(" impl")
+    }) else {
+        return Vec::new();
+    };
+
+    let mut methods = Vec::new();
+    let mut depth = 0i32;
+    let mut entered = false;
+
+    for (i, l) in lines.iter().enumerate().skip(start) {
+        let code = l.split("//").next().unwrap_or("");
+        // Methods live at depth 1, i.e. directly inside the impl braces
+        if entered && depth == 1 && contains_fn_keyword(code) {
+            if let Some(text) = extract_signature_text(source, i) {
+                methods.push((i, text));
+            }
+        }
+        for c in code.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    entered = true;
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if entered && depth <= 0 {
+            break;
+        }
+    }
+
+    methods
+}
+
+/// Signatures rust-analyzer offers for the call around a position
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureHelp {
+    pub signatures: Vec<HelpSignature>,
+    /// Index into `signatures`; `None` when there are none
+    pub active_signature: Option<usize>,
+    /// Index into the active signature's `parameters`
+    pub active_parameter: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HelpSignature {
+    pub label: String,
+    pub parameters: Vec<HelpParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HelpParameter {
+    /// The parameter's text within the signature's label, e.g. `key: &str`
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+}
+
+/// Reshape an LSP `SignatureHelp` response, rendering documentation in
+/// `format`. A `null` or empty response gives no signatures.
+pub fn from_signature_help(result: &Value, format: ContentFormat) -> SignatureHelp {
+    let documentation = |value: &Value| match value {
+        Value::String(text) => Some(format.render(text)),
+        Value::Object(markup) => markup.get("value").and_then(|v| v.as_str()).map(|text| format.render(text)),
+        _ => None,
+    }.filter(|text| !text.is_empty());
+
+    let signatures: Vec<HelpSignature> = result["signatures"].as_array().into_iter().flatten()
+        .filter_map(|signature| {
+            let label = signature["label"].as_str()?;
+            let parameters = signature["parameters"].as_array().into_iter().flatten()
+                .filter_map(|parameter| Some(HelpParameter {
+                    label: parameter_label(label, &parameter["label"])?,
+                    documentation: documentation(&parameter["documentation"]),
+                }))
+                .collect();
+            Some(HelpSignature { label: label.to_string(), parameters, documentation: documentation(&signature["documentation"]) })
+        })
+        .collect();
+    if signatures.is_empty() {
+        return SignatureHelp::default();
+    }
+
+    let index = |value: &Value| value.as_u64().map(|i| i as usize);
+    let active_signature = index(&result["activeSignature"]).unwrap_or(0).min(signatures.len() - 1);
+    // Signatures may carry their own active parameter, which takes precedence
+    let active_parameter = result["signatures"][active_signature].get("activeParameter").and_then(index)
+        .or_else(|| index(&result["activeParameter"]));
+    SignatureHelp { signatures, active_signature: Some(active_signature), active_parameter }
+}
+
+/// A parameter label given as text or as `[start, end]` UTF-16 offsets into
+/// the signature's label
+fn parameter_label(signature: &str, label: &Value) -> Option<String> {
+    match label {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(offsets) => {
+            let (start, end) = (offsets.first()?.as_u64()? as usize, offsets.get(1)?.as_u64()? as usize);
+            let utf16: Vec<u16> = signature.encode_utf16().collect();
+            String::from_utf16(utf16.get(start..end)?).ok()
+        }
+        _ => None,
+    }
+}
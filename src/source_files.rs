@@ -0,0 +1,250 @@
+use anyhow::{Context, Result};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// All `.rs` files under `dir`, recursively, in a stable order
+pub fn rust_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect(dir, &mut files);
+    files.sort();
+    files
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+/// Walker over `dir` that skips cargo's `target` directories and, unless
+/// `include_hidden` is set, hidden entries and whatever `.gitignore`,
+/// `.ignore` and git's exclude files ignore. Ignore files apply whether or not
+/// the project is a git repository.
+pub fn walker(dir: &Path, include_hidden: bool) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(dir);
+    builder
+        .hidden(!include_hidden)
+        .ignore(!include_hidden)
+        .git_ignore(!include_hidden)
+        .git_exclude(!include_hidden)
+        .git_global(!include_hidden)
+        .parents(!include_hidden)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| !is_target_dir(entry.path()));
+    builder
+}
+
+/// Matcher for the paths under `root` that any of `patterns`, gitignore-style
+/// globs such as `generated/**`, leaves out of a walk
+pub fn ignore_globs(root: &Path, patterns: &[String]) -> Result<Override> {
+    let mut builder = OverrideBuilder::new(root);
+    for pattern in patterns {
+        builder.add(&format!("!{}", pattern))
+            .with_context(|| format!("Invalid ignore glob '{}'", pattern))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// A `target` directory next to a Cargo.toml, or tagged as a build cache
+fn is_target_dir(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == "target")
+        && path.is_dir()
+        && (path.join("CACHEDIR.TAG").is_file() || path.parent().is_some_and(|parent| parent.join("Cargo.toml").is_file()))
+}
+
+/// Stable handle for a file in a `FileIndex`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub u32);
+
+/// Byte offset into a file's text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextSize(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextRange {
+    pub start: TextSize,
+    pub end: TextSize,
+}
+
+impl TextRange {
+    pub fn new(start: TextSize, end: TextSize) -> Self {
+        Self { start, end }
+    }
+}
+
+/// A file's contents as read from disk, with its line starts for converting
+/// between 1-based line/column positions and byte offsets
+#[derive(Debug)]
+pub struct SourceFile {
+    pub id: FileId,
+    pub path: PathBuf,
+    text: String,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    modified: Option<SystemTime>,
+}
+
+impl SourceFile {
+    pub fn load(id: FileId, path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(at, _)| at + 1))
+            .collect();
+        Ok(Self { id, path: path.to_path_buf(), text, line_starts, modified })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Byte offset of a 1-based line and column, the column counted in
+    /// characters. A column just past the last character is the line's end.
+    pub fn offset(&self, line: u32, column: u32) -> Result<TextSize> {
+        let Some(&start) = (line as usize).checked_sub(1).and_then(|l| self.line_starts.get(l)) else {
+            anyhow::bail!("Line {} is out of range for {} ({} lines)", line, self.path.display(), self.line_count());
+        };
+        let end = self.line_starts.get(line as usize).map_or(self.text.len(), |next| next - 1);
+        let text = self.text[start..end].strip_suffix('\r').unwrap_or(&self.text[start..end]);
+        let skip = (column as usize).checked_sub(1).ok_or_else(|| anyhow::anyhow!("Columns are 1-based"))?;
+        let within = match text.char_indices().nth(skip) {
+            Some((at, _)) => at,
+            None if skip == text.chars().count() => text.len(),
+            None => anyhow::bail!("Column {} is past the end of line {} in {}", column, line, self.path.display()),
+        };
+        Ok(TextSize((start + within) as u32))
+    }
+
+    /// 1-based line and character column of a byte offset
+    pub fn line_col(&self, offset: TextSize) -> Result<(u32, u32)> {
+        let offset = offset.0 as usize;
+        if offset > self.text.len() || !self.text.is_char_boundary(offset) {
+            anyhow::bail!("Offset {} is not a position in {}", offset, self.path.display());
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.text[self.line_starts[line]..offset].chars().count();
+        Ok((line as u32 + 1, column as u32 + 1))
+    }
+
+    /// Whether the file changed on disk since it was read
+    fn is_stale(&self) -> bool {
+        match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len() != self.text.len() as u64 || metadata.modified().ok() != self.modified,
+            Err(_) => true,
+        }
+    }
+}
+
+/// The workspace's `.rs` files, each with a `FileId` that stays the same for
+/// as long as the index lives. Hidden directories, `target`, whatever the
+/// `.gitignore` files ignore and files the `ignore` globs match are left out
+/// of the listing, but any existing file can still be given an id on request.
+/// Contents are read on first use and cached until the file changes on disk.
+#[derive(Debug)]
+pub struct FileIndex {
+    root: PathBuf,
+    /// Paths by id; ids are never reused
+    paths: Vec<PathBuf>,
+    ids: HashMap<PathBuf, FileId>,
+    /// Files found by the last scan, in path order
+    listed: Option<Vec<FileId>>,
+    cache: HashMap<FileId, Arc<SourceFile>>,
+    /// Globs from the config's `ignore` list
+    ignore: Override,
+}
+
+impl FileIndex {
+    /// An index of the files under `root`, scanned on first listing
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            paths: Vec::new(),
+            ids: HashMap::new(),
+            listed: None,
+            cache: HashMap::new(),
+            ignore: Override::empty(),
+        }
+    }
+
+    /// An index that also leaves out the files `ignore` globs match
+    pub fn with_ignore(root: &Path, ignore: &[String]) -> Result<Self> {
+        let mut index = Self::new(root);
+        index.ignore = ignore_globs(&index.root, ignore)?;
+        Ok(index)
+    }
+
+    /// Walk the workspace again, picking up new files and dropping deleted
+    /// ones from the listing. Files seen before keep their ids.
+    pub fn scan(&mut self) {
+        let mut files: Vec<PathBuf> = walker(&self.root, false).overrides(self.ignore.clone()).build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+            .collect();
+        files.sort();
+        let listed = files.into_iter().map(|path| self.intern(path)).collect();
+        self.listed = Some(listed);
+        self.cache.retain(|_, file| file.path.exists());
+    }
+
+    /// Listed files and their absolute paths, in path order
+    pub fn files(&mut self) -> Vec<(FileId, PathBuf)> {
+        if self.listed.is_none() {
+            self.scan();
+        }
+        self.listed.iter().flatten().map(|&id| (id, self.paths[id.0 as usize].clone())).collect()
+    }
+
+    /// Id of a file, relative to the root or absolute. Files outside the
+    /// listing get an id too, as long as they exist.
+    pub fn file_id(&mut self, path: &Path) -> Result<FileId> {
+        let full = self.root.join(path);
+        let canonical = full.canonicalize()
+            .ok()
+            .filter(|p| p.is_file())
+            .ok_or_else(|| anyhow::anyhow!("File not found: {}", path.display()))?;
+        Ok(self.intern(canonical))
+    }
+
+    pub fn path(&self, id: FileId) -> Option<&Path> {
+        self.paths.get(id.0 as usize).map(PathBuf::as_path)
+    }
+
+    /// Contents of a file, read again if it changed since last time
+    pub fn source(&mut self, id: FileId) -> Result<Arc<SourceFile>> {
+        if let Some(file) = self.cache.get(&id).filter(|file| !file.is_stale()) {
+            return Ok(file.clone());
+        }
+        let path = self.path(id).ok_or_else(|| anyhow::anyhow!("Unknown file id {}", id.0))?;
+        let file = Arc::new(SourceFile::load(id, path)?);
+        self.cache.insert(id, file.clone());
+        Ok(file)
+    }
+
+    fn intern(&mut self, path: PathBuf) -> FileId {
+        if let Some(&id) = self.ids.get(&path) {
+            return id;
+        }
+        let id = FileId(self.paths.len() as u32);
+        self.paths.push(path.clone());
+        self.ids.insert(path, id);
+        id
+    }
+}
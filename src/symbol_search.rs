@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::outline;
+use crate::source_files;
+use crate::workspace_edit::uri_to_path;
+
+/// Keywords that introduce the item named right after them
+const DEFINITION_KEYWORDS: &[&str] = &["fn", "struct", "enum", "trait", "type", "union", "const", "static", "mod", "macro_rules"];
+
+/// How `analyze_symbol` matches a name against source lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchOptions {
+    /// Match whole identifiers only, so `new` doesn't match `renewable`
+    pub whole_word: bool,
+    pub case_sensitive: bool,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self { whole_word: true, case_sensitive: true }
+    }
+}
+
+/// Identifiers of a line's code, with their byte offsets; comments and the
+/// contents of string and char literals are left out
+pub fn identifiers(line: &str) -> Vec<(usize, &str)> {
+    scan(line).0
+}
+
+/// Identifiers of `line` and where its code ends, at a `//` comment or the
+/// end of the line
+fn scan(line: &str) -> (Vec<(usize, &str)>, usize) {
+    let mut identifiers = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    let mut start: Option<usize> = None;
+    while let Some((at, c)) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            start.get_or_insert(at);
+            continue;
+        }
+        if let Some(begin) = start.take() {
+            identifiers.push((begin, &line[begin..at]));
+        }
+        match c {
+            '/' if chars.peek().is_some_and(|(_, next)| *next == '/') => return (identifiers, at),
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            // A char literal such as 'a' or '\n'; lifetimes have no closing quote
+            '\'' => {
+                let rest = &line[at + 1..];
+                // Bytes between the quotes
+                let len = match rest.strip_prefix('\\') {
+                    Some(escape) => escape.get(1..).and_then(|r| r.find('\'')).map(|i| i + 2),
+                    None => rest.chars().next().filter(|_| rest.chars().nth(1) == Some('\'')).map(char::len_utf8),
+                };
+                if let Some(len) = len {
+                    while chars.peek().is_some_and(|(i, _)| *i <= at + 1 + len) {
+                        chars.next();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        identifiers.push((begin, &line[begin..]));
+    }
+    (identifiers, line.len())
+}
+
+impl MatchOptions {
+    fn same(&self, identifier: &str, symbol: &str) -> bool {
+        if self.case_sensitive { identifier == symbol } else { identifier.to_lowercase() == symbol.to_lowercase() }
+    }
+}
+
+/// Whether `line`'s code mentions `symbol`
+pub fn matches(line: &str, symbol: &str, options: MatchOptions) -> bool {
+    if options.whole_word {
+        return identifiers(line).iter().any(|(_, identifier)| options.same(identifier, symbol));
+    }
+    // Literals still count when matching substrings, comments don't
+    let code = &line[..scan(line).1];
+    if options.case_sensitive {
+        code.contains(symbol)
+    } else {
+        code.to_lowercase().contains(&symbol.to_lowercase())
+    }
+}
+
+/// Kind of item `line` defines named `symbol`, e.g. `fn` for `pub fn new()`,
+/// or `None` when the line only uses it
+pub fn definition_kind(line: &str, symbol: &str, options: MatchOptions) -> Option<&'static str> {
+    definitions(line).into_iter().find(|(_, name)| options.same(name, symbol)).map(|(kind, _)| kind)
+}
+
+/// Items `line` defines, as their kind and name, e.g. `("fn", "new")`
+pub fn definitions(line: &str) -> Vec<(&'static str, &str)> {
+    identifiers(line).windows(2).filter_map(|pair| {
+        let ((_, keyword), (_, name)) = (pair[0], pair[1]);
+        DEFINITION_KEYWORDS.iter().find(|k| **k == keyword).map(|k| (if *k == "macro_rules" { "macro" } else { *k }, name))
+    }).collect()
+}
+
+/// `symbol_type` of `analyze_symbol` for a definition kind
+pub fn symbol_type(kind: &str) -> &'static str {
+    match kind {
+        "fn" => "Function",
+        "struct" => "Struct",
+        "enum" => "Enum",
+        "trait" => "Trait",
+        "type" => "Type Alias",
+        "union" => "Union",
+        "const" | "static" => "Constant",
+        "mod" => "Module",
+        "macro" => "Macro",
+        _ => "Function/Variable",
+    }
+}
+
+/// A `search_workspace_symbols` hit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    /// Kind name as `outline::symbol_kind` gives it
+    pub kind: String,
+    /// Relative to the project root when the symbol is inside it
+    pub file: String,
+    /// 1-based
+    pub line: u32,
+    /// Module or type holding the symbol, when known
+    pub container: Option<String>,
+}
+
+/// A `workspace/symbol` result entry, `SymbolInformation` or `WorkspaceSymbol`.
+/// The latter may leave out the range, which then reads as the first line.
+pub fn from_lsp(symbol: &Value, project_root: &Path) -> Option<WorkspaceSymbol> {
+    let location = symbol.get("location")?;
+    let path = uri_to_path(location["uri"].as_str()?);
+    let file = path.strip_prefix(project_root).unwrap_or(&path).display().to_string();
+    Some(WorkspaceSymbol {
+        name: symbol["name"].as_str()?.to_string(),
+        kind: outline::symbol_kind(symbol["kind"].as_u64().unwrap_or(0)).to_string(),
+        file,
+        line: location["range"]["start"]["line"].as_u64().unwrap_or(0) as u32 + 1,
+        container: symbol["containerName"].as_str().filter(|c| !c.is_empty()).map(String::from),
+    })
+}
+
+/// Kind name rust-analyzer reports in `workspace/symbol` for a definition
+/// keyword: unions come as structs and macros as functions
+fn lsp_kind_name(keyword: &str) -> &'static str {
+    match keyword {
+        "fn" | "macro" => "function",
+        "struct" | "union" => "struct",
+        "enum" => "enum",
+        "trait" => "trait",
+        "type" => "type_alias",
+        "const" => "constant",
+        "static" => "static",
+        "mod" => "module",
+        _ => "unknown",
+    }
+}
+
+/// Definitions under `src/` whose name contains `query`, ignoring case, for
+/// when rust-analyzer isn't there to answer `workspace/symbol`. Only `kind`
+/// ones are kept when it is given.
+pub fn search_definitions(project_root: &Path, query: &str, kind: Option<&str>) -> Vec<WorkspaceSymbol> {
+    let query = query.to_lowercase();
+    let files = source_files::walker(&project_root.join("src"), false).build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"));
+    let mut symbols = Vec::new();
+    for path in files {
+        let Ok(content) = std::fs::read_to_string(&path) else { continue };
+        let file = path.strip_prefix(project_root).unwrap_or(&path).display().to_string();
+        for (line_num, line) in content.lines().enumerate() {
+            for (keyword, name) in definitions(line) {
+                let found = lsp_kind_name(keyword);
+                if name.to_lowercase().contains(&query) && kind.is_none_or(|kind| kind == found) {
+                    symbols.push(WorkspaceSymbol {
+                        name: name.to_string(),
+                        kind: found.to_string(),
+                        file: file.clone(),
+                        line: line_num as u32 + 1,
+                        container: None,
+                    });
+                }
+            }
+        }
+    }
+    symbols
+}
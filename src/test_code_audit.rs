@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::blast_radius;
+use crate::cfg_eval::{self, CfgExpr};
+use crate::module_graph;
+use crate::reexports::{module_path_for, ITEM_KEYWORDS};
+use crate::source_files;
+use crate::toggle_async::{code_mask, line_col, matching};
+
+/// An item compiled only for tests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestItem {
+    pub file: String,
+    /// 1-based line of the `#[cfg(...)]` attribute
+    pub line: usize,
+    /// 1-based line the item ends on
+    pub end_line: usize,
+    /// `module`, `function`, `struct`, ..., `use`, `impl`, or `other`
+    pub kind: String,
+    pub name: String,
+    /// The predicate, e.g. `test` or `all(test, feature = "slow")`
+    pub condition: String,
+}
+
+/// Non-test code that only compiles when tests are being built
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub file: String,
+    /// 1-based
+    pub line: usize,
+    /// 1-based
+    pub column: usize,
+    /// `dev_dependency` or `test_item`
+    pub kind: String,
+    /// The dev-dependency or test-only item referred to
+    pub name: String,
+    pub code: String,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestCodeAudit {
+    pub files_scanned: usize,
+    /// Dev-dependencies that aren't also regular dependencies, as written in code
+    pub dev_dependencies: Vec<String>,
+    /// Files that are test-only as a whole, through `#![cfg(test)]` or a
+    /// `#[cfg(test)] mod` declaration
+    pub test_files: Vec<String>,
+    pub test_items: Vec<TestItem>,
+    pub total: usize,
+    pub violations: Vec<Violation>,
+}
+
+/// A file's test-only items and their byte ranges
+pub(crate) struct TestRegions {
+    items: Vec<TestItem>,
+    ranges: Vec<(usize, usize)>,
+    /// `#![cfg(test)]` at the top
+    whole_file: bool,
+}
+
+impl TestRegions {
+    pub(crate) fn contains(&self, offset: usize) -> bool {
+        self.whole_file || self.ranges.iter().any(|(start, end)| (*start..*end).contains(&offset))
+    }
+}
+
+/// Audit the package at `project_root`: its `#[cfg(test)]` items, and the
+/// code under `src/` outside them that names a dev-dependency or a
+/// test-only item of the same file. Such code builds under `cargo test`
+/// but fails `cargo build`.
+pub fn audit(project_root: &Path) -> TestCodeAudit {
+    let manifest = std::fs::read_to_string(project_root.join("Cargo.toml")).unwrap_or_default();
+    let dev_dependencies = dev_dependencies(&manifest);
+    let src = project_root.join("src");
+    let mut report = TestCodeAudit { dev_dependencies: dev_dependencies.iter().cloned().collect(), ..TestCodeAudit::default() };
+
+    let files: Vec<(String, Option<Vec<String>>, String, TestRegions)> = source_files::rust_files(&src).into_iter()
+        .filter_map(|file| {
+            let source = std::fs::read_to_string(&file).ok()?;
+            let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+            let regions = test_regions(&source);
+            Some((display, module_path_for(&src, &file), source, regions))
+        })
+        .collect();
+
+    // Modules whose files are declared with `#[cfg(test)] mod name;`
+    let test_modules: Vec<Vec<String>> = files.iter()
+        .filter_map(|(_, module, _, regions)| Some((module.as_ref()?, regions)))
+        .flat_map(|(module, regions)| {
+            regions.items.iter()
+                .filter(|item| item.kind == "module")
+                .map(|item| module.iter().cloned().chain([item.name.clone()]).collect())
+        })
+        .collect();
+
+    for (display, module, source, regions) in files {
+        report.files_scanned += 1;
+        report.test_items.extend(regions.items.iter().map(|item| TestItem { file: display.clone(), ..item.clone() }));
+        let declared_for_tests = module.as_ref().is_some_and(|module| test_modules.iter().any(|test| module.starts_with(test)));
+        if regions.whole_file || declared_for_tests {
+            report.test_files.push(display);
+            continue;
+        }
+        report.violations.extend(
+            scan_source(&source, &regions, &dev_dependencies).into_iter().map(|v| Violation { file: display.clone(), ..v }),
+        );
+    }
+
+    report.violations.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)).then(a.column.cmp(&b.column)));
+    report.total = report.violations.len();
+    report
+}
+
+/// Crate names of the manifest's dev-dependencies, with `-` written as `_`,
+/// leaving out those that are regular or build dependencies too
+pub fn dev_dependencies(manifest: &str) -> BTreeSet<String> {
+    let Ok(manifest) = toml::from_str::<toml::Table>(manifest) else { return BTreeSet::new() };
+    let mut tables = vec![&manifest];
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        tables.extend(targets.values().filter_map(|target| target.as_table()));
+    }
+    let names = |key: &str| -> BTreeSet<String> {
+        tables.iter()
+            .filter_map(|table| table.get(key)?.as_table())
+            .flat_map(|table| table.keys())
+            .map(|name| name.replace('-', "_"))
+            .collect()
+    };
+    let regular: BTreeSet<String> = names("dependencies").into_iter().chain(names("build-dependencies")).collect();
+    names("dev-dependencies").into_iter().filter(|name| !regular.contains(name)).collect()
+}
+
+/// Items of `source` under a `#[cfg(...)]` that only holds when `test` is set.
+/// `file` is left empty for the caller to fill in.
+pub(crate) fn test_regions(source: &str) -> TestRegions {
+    let mask = code_mask(source);
+    let mut regions = TestRegions { items: Vec::new(), ranges: Vec::new(), whole_file: false };
+    let mut search = 0;
+
+    while let Some(found) = source[search..].find('#') {
+        let start = search + found;
+        search = start + 1;
+        if !mask[start] {
+            continue;
+        }
+        let inner = source[start + 1..].starts_with('!');
+        let open = start + 1 + usize::from(inner);
+        if !source[open..].starts_with('[') {
+            continue;
+        }
+        let Some(close) = matching(source, &mask, open) else { break };
+        let attribute = source[open + 1..close].trim();
+        let Some(condition) = attribute.strip_prefix("cfg").map(str::trim_start).and_then(|rest| rest.strip_prefix('(')?.strip_suffix(')')) else { continue };
+        if !cfg_eval::parse_cfg(condition).is_some_and(|expr| requires_test(&expr)) {
+            continue;
+        }
+        if inner {
+            // Inner attributes of inline modules are left alone
+            regions.whole_file |= !source[..start].char_indices().any(|(at, c)| c == '{' && mask[at]);
+            continue;
+        }
+
+        let item_start = skip_attributes(source, &mask, close + 1);
+        let end = item_end(source, &mask, item_start);
+        let (kind, name) = describe(&source[item_start..end]);
+        regions.items.push(TestItem {
+            file: String::new(),
+            line: line_col(source, start).0,
+            end_line: line_col(source, end.saturating_sub(1).max(start)).0,
+            kind,
+            name,
+            condition: condition.trim().to_string(),
+        });
+        regions.ranges.push((start, end));
+        search = end.max(search);
+    }
+    regions
+}
+
+/// Whether a cfg predicate can only hold while building tests
+fn requires_test(expr: &CfgExpr) -> bool {
+    match expr {
+        CfgExpr::Option(name, None) => name == "test",
+        CfgExpr::Option(..) | CfgExpr::Not(_) => false,
+        CfgExpr::All(items) => items.iter().any(requires_test),
+        CfgExpr::Any(items) => !items.is_empty() && items.iter().all(requires_test),
+    }
+}
+
+/// Dev-dependency paths and test-only item names in the code of `source`
+/// outside `regions`. `file` is left empty for the caller to fill in.
+fn scan_source(source: &str, regions: &TestRegions, dev_dependencies: &BTreeSet<String>) -> Vec<Violation> {
+    let mask = code_mask(source);
+    let lines: Vec<&str> = source.lines().collect();
+    let in_code = |at: usize| mask[at] && !regions.contains(at);
+    let mut violations = Vec::new();
+    let mut violation = |at: usize, kind: &str, name: &str, suggestion: String| {
+        let (line, column) = line_col(source, at);
+        violations.push(Violation {
+            file: String::new(),
+            line,
+            column,
+            kind: kind.to_string(),
+            name: name.to_string(),
+            code: lines.get(line - 1).map_or("", |l| l.trim()).to_string(),
+            suggestion,
+        });
+    };
+
+    // Local modules shadow crates of the same name
+    let local_modules: BTreeSet<&str> = lines.iter()
+        .filter_map(|line| line.trim().split_once("mod ").filter(|(before, _)| before.is_empty() || before.starts_with("pub")))
+        .map(|(_, rest)| rest.trim_end_matches([';', '{', ' ']).trim())
+        .collect();
+    for name in dev_dependencies.iter().filter(|name| !local_modules.contains(name.as_str())) {
+        for at in words(source, name).into_iter().filter(|&at| in_code(at)) {
+            let before = source[..at].trim_end();
+            let after = &source[at + name.len()..];
+            let is_path = after.starts_with("::") && !before.ends_with("::") && !before.ends_with('.');
+            let is_extern = before.ends_with("extern crate");
+            if is_path || is_extern {
+                violation(at, "dev_dependency", name, format!(
+                    "`{}` is a dev-dependency: move this under #[cfg(test)], or make it a regular dependency", name,
+                ));
+            }
+        }
+    }
+
+    // Test-only items named in the same file, unless a non-test twin is defined
+    // next to them (`#[cfg(not(test))]`)
+    let names: BTreeSet<&str> = regions.items.iter()
+        .filter(|item| !matches!(item.kind.as_str(), "module" | "use" | "impl" | "other"))
+        .map(|item| item.name.as_str())
+        .collect();
+    for name in names {
+        let found: Vec<usize> = words(source, name).into_iter()
+            .filter(|&at| in_code(at) && !source[..at].ends_with('.'))
+            .collect();
+        let has_twin = found.iter().any(|&at| {
+            let (line, column) = line_col(source, at);
+            blast_radius::is_definition(source, line, column)
+        });
+        if has_twin {
+            continue;
+        }
+        for at in found {
+            violation(at, "test_item", name, format!(
+                "`{}` only exists under #[cfg(test)]: move this under it too, or drop the cfg", name,
+            ));
+        }
+    }
+    violations
+}
+
+/// Byte offsets of `name` as a whole word in `source`
+fn words(source: &str, name: &str) -> Vec<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    source.match_indices(name)
+        .map(|(at, _)| at)
+        .filter(|&at| !source[..at].ends_with(is_ident) && !source[at + name.len()..].starts_with(is_ident))
+        .collect()
+}
+
+/// Offset of the item after `from`, past whitespace and further attributes
+fn skip_attributes(source: &str, mask: &[bool], mut from: usize) -> usize {
+    loop {
+        let trimmed = from + (source[from..].len() - source[from..].trim_start().len());
+        if !source[trimmed..].starts_with("#[") {
+            return trimmed;
+        }
+        match matching(source, mask, trimmed + 1) {
+            Some(close) => from = close + 1,
+            None => return trimmed,
+        }
+    }
+}
+
+/// End of the item starting at `start`: past its `;` or its closing brace
+fn item_end(source: &str, mask: &[bool], start: usize) -> usize {
+    let mut depth = 0i32;
+    for (at, c) in source[start..].char_indices().map(|(i, c)| (start + i, c)) {
+        if !mask[at] {
+            continue;
+        }
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ';' | ',' if depth <= 0 => return at + 1,
+            '{' if depth <= 0 => return matching(source, mask, at).map_or(source.len(), |close| close + 1),
+            '}' if depth <= 0 => return at,
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// Kind and name of an item from its text
+fn describe(item: &str) -> (String, String) {
+    let header = item.split(['{', ';', '=']).next().unwrap_or("");
+    let tokens: Vec<&str> = header.split(|c: char| c.is_whitespace() || c == '(' || c == '<' || c == ':').filter(|t| !t.is_empty()).collect();
+    let rest = module_graph::strip_visibility(header.trim());
+    if let Some(tree) = rest.strip_prefix("use ") {
+        return ("use".to_string(), tree.trim().to_string());
+    }
+    if rest.starts_with("impl") {
+        return ("impl".to_string(), rest.to_string());
+    }
+    for (keyword, kind) in ITEM_KEYWORDS {
+        if let Some(position) = tokens.iter().position(|t| t == keyword) {
+            if let Some(name) = tokens.get(position + 1) {
+                return (kind.to_string(), name.trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '_')).to_string());
+            }
+        }
+    }
+    ("other".to_string(), header.trim().to_string())
+}
@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Where a request spends its time, as reported in `_meta.timings`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Starting rust-analyzer and waiting for `initialize`
+    LspInit,
+    /// Requests to rust-analyzer, `initialize` excepted
+    LspRequest,
+    /// Running cargo
+    Cargo,
+    /// Walking and reading source files
+    FileScan,
+}
+
+/// Milliseconds per phase of one request. Phases running concurrently, such
+/// as the cargo builds of `check_feature_powerset`, are summed, so they can
+/// add up to more than `total`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Timings {
+    pub lsp_init: u64,
+    pub lsp_request: u64,
+    pub cargo: u64,
+    pub file_scan: u64,
+    /// Wall time of the whole request
+    pub total: u64,
+}
+
+#[derive(Default)]
+struct Spent {
+    lsp_init: Duration,
+    lsp_request: Duration,
+    cargo: Duration,
+    file_scan: Duration,
+}
+
+tokio::task_local! {
+    static SPENT: RefCell<Spent>;
+}
+
+/// Run `future`, recording the time its phases take. Work moved to other
+/// tasks or to `spawn_blocking` isn't seen unless it is awaited in `measure`.
+pub async fn collect<F: Future>(future: F) -> (F::Output, Timings) {
+    let start = Instant::now();
+    SPENT.scope(RefCell::new(Spent::default()), async move {
+        let output = future.await;
+        let timings = SPENT.with(|spent| {
+            let spent = spent.borrow();
+            Timings {
+                lsp_init: spent.lsp_init.as_millis() as u64,
+                lsp_request: spent.lsp_request.as_millis() as u64,
+                cargo: spent.cargo.as_millis() as u64,
+                file_scan: spent.file_scan.as_millis() as u64,
+                total: start.elapsed().as_millis() as u64,
+            }
+        });
+        (output, timings)
+    }).await
+}
+
+/// Await `future`, counting the time towards `phase` when inside `collect`
+pub async fn measure<F: Future>(phase: Phase, future: F) -> F::Output {
+    let start = Instant::now();
+    let output = future.await;
+    record(phase, start.elapsed());
+    output
+}
+
+/// Count `elapsed` towards `phase`; a no-op outside `collect`
+pub fn record(phase: Phase, elapsed: Duration) {
+    let _ = SPENT.try_with(|spent| {
+        let mut spent = spent.borrow_mut();
+        let slot = match phase {
+            Phase::LspInit => &mut spent.lsp_init,
+            Phase::LspRequest => &mut spent.lsp_request,
+            Phase::Cargo => &mut spent.cargo,
+            Phase::FileScan => &mut spent.file_scan,
+        };
+        *slot += elapsed;
+    });
+}
+
+/// Run `f`, counting the time towards `phase` when inside `collect`
+pub fn measure_sync<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let output = f();
+    record(phase, start.elapsed());
+    output
+}
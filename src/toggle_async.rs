@@ -0,0 +1,558 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::async_audit::{self, fn_header};
+
+/// A function definition with a body, by byte offsets into its source
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSpan {
+    pub name: String,
+    pub is_async: bool,
+    /// First qualifier of the signature, e.g. `pub`
+    pub start: usize,
+    /// The `fn` keyword
+    pub fn_keyword: usize,
+    pub name_offset: usize,
+    /// Opening and closing braces of the body
+    pub body: (usize, usize),
+}
+
+/// A call updated by the conversion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallSite {
+    pub file: String,
+    /// 1-based line and column of the function name
+    pub line: usize,
+    pub column: usize,
+    /// Function the call is made from
+    pub caller: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TogglePlan {
+    pub function: String,
+    pub now_async: bool,
+    /// An `impl Future<Output = T>` return type and `async` block body became an `async fn` returning `T`
+    pub unwrapped_future: bool,
+    pub call_sites: Vec<CallSite>,
+    /// New content of each changed file
+    pub changes: BTreeMap<PathBuf, String>,
+}
+
+/// Where code runs, as far as awaiting goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Context {
+    /// Directly in the function's body
+    Function,
+    AsyncBlock,
+    Closure,
+}
+
+/// Functions of `source` that have a body
+pub fn functions(source: &str) -> Vec<FunctionSpan> {
+    let mask = code_mask(source);
+    let mut functions = Vec::new();
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let code = line.split("//").next().unwrap_or("");
+        let trimmed = code.trim();
+        let start = offset + (code.len() - code.trim_start().len());
+        if let Some((name, is_async)) = fn_header(trimmed).filter(|_| mask.get(start) == Some(&true)) {
+            let fn_keyword = start + trimmed.find("fn ").unwrap_or(0);
+            let name_offset = fn_keyword + 3;
+            if let Some(body) = body_of(source, &mask, name_offset) {
+                functions.push(FunctionSpan { name, is_async, start, fn_keyword, name_offset, body });
+            }
+        }
+        offset += line.len();
+    }
+    functions
+}
+
+/// The innermost function whose signature or body spans a 1-based line
+pub fn function_at(source: &str, line: usize) -> Option<FunctionSpan> {
+    functions(source).into_iter()
+        .filter(|f| line_col(source, f.start).0 <= line && line <= line_col(source, f.body.1).0)
+        .max_by_key(|f| f.start)
+}
+
+/// Offsets of the places `source` calls `name`, as a function or method
+pub fn call_sites(source: &str, name: &str) -> Vec<usize> {
+    let mask = code_mask(source);
+    source.match_indices(name)
+        .map(|(at, _)| at)
+        .filter(|&at| {
+            let before = source[..at].chars().next_back();
+            let after = source[at + name.len()..].chars().next();
+            mask[at]
+                && !before.is_some_and(is_ident)
+                && !after.is_some_and(is_ident)
+                && !source[..at].trim_end().ends_with("fn")
+                && call_end(source, &mask, at + name.len()).is_some()
+        })
+        .collect()
+}
+
+/// 1-based line and column (in characters) of a byte offset
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+/// Byte offset of an LSP position: 0-based line, UTF-16 character
+pub fn lsp_offset(source: &str, line: usize, character: usize) -> Option<usize> {
+    let line_start = if line == 0 {
+        0
+    } else {
+        source.match_indices('\n').nth(line - 1).map(|(at, _)| at + 1)?
+    };
+    let mut units = 0;
+    for (at, c) in source[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(line_start + at);
+        }
+        units += c.len_utf16();
+    }
+    Some(source.len())
+}
+
+/// Make `function` of `file` async, or synchronous when it is async, and
+/// update the calls at `references` (offsets of the name in `sources`,
+/// which holds every file involved). Fails listing every reason the
+/// change can't be made safely.
+pub fn plan(project_root: &Path, file: &Path, function: &FunctionSpan, sources: &BTreeMap<PathBuf, String>, references: &[(PathBuf, usize)]) -> Result<TogglePlan> {
+    let display = |path: &Path| path.strip_prefix(project_root).unwrap_or(path).display().to_string();
+    let Some(source) = sources.get(file) else { anyhow::bail!("{} was not loaded", file.display()) };
+    let mask = code_mask(source);
+    let to_async = !function.is_async;
+    let mut problems = Vec::new();
+    let mut edits: BTreeMap<PathBuf, Vec<(usize, usize, String)>> = BTreeMap::new();
+    let mut plan = TogglePlan { function: function.name.clone(), now_async: to_async, ..TogglePlan::default() };
+
+    let qualifiers = &source[function.start..function.fn_keyword];
+    if qualifiers.split_whitespace().any(|q| q == "const") {
+        problems.push("a `const fn` can't be async".to_string());
+    }
+    if qualifiers.contains("extern") {
+        problems.push("`extern` functions can't be async".to_string());
+    }
+    if in_trait(source, &mask, function.start) {
+        problems.push("it belongs to a trait or trait impl, whose other implementations would have to change as well".to_string());
+    }
+    let attributes = attributes_before(source, function.start);
+    if let Some(attribute) = attributes.iter().find(|a| a.contains("::main") || a.contains("::test") || a.contains("async_trait")) {
+        if !to_async {
+            problems.push(format!("`{}` needs an async function", attribute));
+        }
+    }
+
+    let def = edits.entry(file.to_path_buf()).or_default();
+    if to_async {
+        if function.name == "main" {
+            problems.push("`main` can only be async under a runtime attribute such as #[tokio::main]".to_string());
+        }
+        let async_at = qualifiers.find("unsafe").map_or(function.fn_keyword, |at| function.start + at);
+        def.push((async_at, async_at, "async ".to_string()));
+
+        match return_type(source, &mask, function) {
+            Some((ret_start, ret_end, ret)) if ret.contains("Future") => {
+                match unwrap_future(source, &mask, function, ret_start, ret_end) {
+                    Some(unwrapped) => {
+                        def.extend(unwrapped);
+                        plan.unwrapped_future = true;
+                    }
+                    None => problems.push(format!("it returns `{}`, which isn't an `impl Future` built by an `async` block", ret)),
+                }
+            }
+            _ => {}
+        }
+
+        if !plan.unwrapped_future {
+            let mut probe = source.clone();
+            probe.insert_str(async_at, "async ");
+            let lines = line_col(source, function.start).0..=line_col(source, function.body.1).0;
+            for finding in async_audit::scan_source(&probe, true).1 {
+                if finding.function == function.name && lines.contains(&finding.line) {
+                    problems.push(format!("it blocks at line {}: `{}` ({})", finding.line, finding.call, finding.suggestion));
+                }
+            }
+        }
+    } else {
+        let end = source[function.start..].find("async").map(|at| function.start + at);
+        if let Some(at) = end {
+            let spaces = source[at + 5..].len() - source[at + 5..].trim_start().len();
+            def.push((at, at + 5 + spaces, String::new()));
+        }
+        let body = &source[function.body.0..function.body.1];
+        for (at, _) in body.match_indices(".await") {
+            let at = function.body.0 + at;
+            if mask[at] && context_at(source, &mask, function, at) == Context::Function {
+                problems.push(format!("it awaits at line {}", line_col(source, at).0));
+            }
+        }
+    }
+
+    for (path, offset) in references {
+        let Some(text) = sources.get(path) else { continue };
+        if path == file && *offset == function.name_offset {
+            continue;
+        }
+        let mask = if path == file { mask.clone() } else { code_mask(text) };
+        let line_start = text[..*offset].rfind('\n').map_or(0, |at| at + 1);
+        let line = text[line_start..].trim_start();
+        if !mask[*offset] || line.starts_with("use ") || line.starts_with("pub use ") || line.starts_with("pub(crate) use ") {
+            continue;
+        }
+        let (line, column) = line_col(text, *offset);
+        let location = format!("{}:{}", display(path), line);
+        let caller = functions(text).into_iter()
+            .filter(|f| f.body.0 < *offset && *offset < f.body.1)
+            .max_by_key(|f| f.start);
+        if path == file && caller.as_ref().is_some_and(|c| c.start == function.start) {
+            if to_async {
+                problems.push(format!("it calls itself at line {}, and a recursive async fn needs its future boxed", line));
+            }
+            continue;
+        }
+        let Some(close) = call_end(text, &mask, *offset + function.name.len()) else {
+            problems.push(format!("{} uses `{}` as a value rather than calling it", location, function.name));
+            continue;
+        };
+
+        if to_async {
+            if plan.unwrapped_future {
+                continue;
+            }
+            match caller.as_ref() {
+                Some(caller) => match context_at(text, &mask, caller, *offset) {
+                    Context::AsyncBlock => {}
+                    Context::Function if caller.is_async => {}
+                    Context::Closure => {
+                        problems.push(format!("the call at {} is inside a closure in `{}`, where it can't be awaited", location, caller.name));
+                        continue;
+                    }
+                    Context::Function => {
+                        problems.push(format!("the call at {} is in `{}`, which isn't async", location, caller.name));
+                        continue;
+                    }
+                },
+                None => {
+                    problems.push(format!("the call at {} is outside any function", location));
+                    continue;
+                }
+            }
+            edits.entry(path.clone()).or_default().push((close, close, ".await".to_string()));
+        } else {
+            match awaited(text, close) {
+                Some(end) => edits.entry(path.clone()).or_default().push((close, end, String::new())),
+                None => {
+                    problems.push(format!("the call at {} doesn't await the future it returns, e.g. it is spawned or joined", location));
+                    continue;
+                }
+            }
+        }
+        plan.call_sites.push(CallSite { file: display(path), line, column, caller: caller.map(|c| c.name) });
+    }
+
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "Cannot make `{}` {}:\n- {}",
+            function.name,
+            if to_async { "async" } else { "synchronous" },
+            problems.join("\n- ")
+        );
+    }
+
+    for (path, mut file_edits) in edits {
+        let mut content = sources[&path].clone();
+        file_edits.sort_by_key(|(start, end, _)| (*start, *end));
+        for (start, end, text) in file_edits.into_iter().rev() {
+            content.replace_range(start..end, &text);
+        }
+        plan.changes.insert(path, content);
+    }
+    plan.call_sites.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)).then(a.column.cmp(&b.column)));
+    Ok(plan)
+}
+
+/// `WorkspaceEdit` replacing each changed file with its new content
+pub fn workspace_edit(plan: &TogglePlan) -> Value {
+    // Positions past the end of a document clamp to its end
+    let whole = json!({
+        "start": { "line": 0, "character": 0 },
+        "end": { "line": u32::MAX, "character": 0 }
+    });
+    let changes: Vec<Value> = plan.changes.iter()
+        .map(|(path, content)| json!({
+            "textDocument": { "uri": format!("file://{}", path.display()), "version": null },
+            "edits": [{ "range": whole, "newText": content }]
+        }))
+        .collect();
+    json!({ "documentChanges": changes })
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Per byte, whether it is code rather than a comment, string or char literal
+pub(crate) fn code_mask(source: &str) -> Vec<bool> {
+    let bytes = source.as_bytes();
+    let mut mask = vec![true; bytes.len()];
+    let mut i = 0;
+    while i < bytes.len() {
+        let prev_ident = i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+        let end = match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => source[i..].find('\n').map_or(bytes.len(), |n| i + n),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => source[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4),
+            b'r' if !prev_ident && matches!(bytes.get(i + 1), Some(b'"' | b'#')) => {
+                let hashes = source[i + 1..].bytes().take_while(|b| *b == b'#').count();
+                if bytes.get(i + 1 + hashes) != Some(&b'"') {
+                    i += 1;
+                    continue;
+                }
+                let close = format!("\"{}", "#".repeat(hashes));
+                source[i + 2 + hashes..].find(&close).map_or(bytes.len(), |n| i + 2 + hashes + n + close.len())
+            }
+            b'"' => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != b'"' {
+                    if bytes[j] == b'\\' {
+                        j += 1;
+                    }
+                    j += 1;
+                }
+                (j + 1).min(bytes.len())
+            }
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') => {
+                source.get(i + 3..).and_then(|rest| rest.find('\'')).map_or(bytes.len(), |n| i + 3 + n + 1)
+            }
+            b'\'' => {
+                // A char literal rather than a lifetime
+                let Some(c) = source[i + 1..].chars().next() else { break };
+                if source[i + 1 + c.len_utf8()..].starts_with('\'') {
+                    i + 2 + c.len_utf8()
+                } else {
+                    i += 1;
+                    continue;
+                }
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        mask[i..end].iter_mut().for_each(|m| *m = false);
+        i = end;
+    }
+    mask
+}
+
+/// Offset of the bracket closing the one at `open`
+pub(crate) fn matching(source: &str, mask: &[bool], open: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let opening = bytes[open];
+    let closing = match opening {
+        b'(' => b')',
+        b'[' => b']',
+        b'{' => b'}',
+        _ => b'>',
+    };
+    let mut depth = 0;
+    for i in open..bytes.len() {
+        if !mask[i] || (closing == b'>' && i > open && bytes[i - 1] == b'-') {
+            continue;
+        }
+        if bytes[i] == opening {
+            depth += 1;
+        } else if bytes[i] == closing {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Braces of the body of the function whose name starts at `from`; `None` for declarations
+fn body_of(source: &str, mask: &[bool], from: usize) -> Option<(usize, usize)> {
+    let mut depth = 0;
+    for (i, b) in source.bytes().enumerate().skip(from) {
+        if !mask[i] {
+            continue;
+        }
+        match b {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth -= 1,
+            b'{' if depth == 0 => return matching(source, mask, i).map(|close| (i, close)),
+            b';' if depth == 0 => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// End of the call whose callee name ends at `name_end`: after its closing parenthesis
+fn call_end(source: &str, mask: &[bool], name_end: usize) -> Option<usize> {
+    let mut at = name_end + (source[name_end..].len() - source[name_end..].trim_start().len());
+    if source[at..].starts_with("::<") {
+        let close = matching(source, mask, at + 2)?;
+        at = close + 1;
+        at += source[at..].len() - source[at..].trim_start().len();
+    }
+    (source[at..].starts_with('(')).then(|| matching(source, mask, at)).flatten().map(|close| close + 1)
+}
+
+/// End of the `.await` right after a call ending at `end`
+fn awaited(source: &str, end: usize) -> Option<usize> {
+    let rest = source[end..].trim_start();
+    let after = rest.strip_prefix(".await")?;
+    (!after.chars().next().is_some_and(is_ident)).then(|| source.len() - after.len())
+}
+
+/// Return type of a function: its bounds and text
+pub(crate) fn return_type(source: &str, mask: &[bool], function: &FunctionSpan) -> Option<(usize, usize, String)> {
+    let mut at = function.name_offset + function.name.len();
+    if source[at..].starts_with('<') {
+        at = matching(source, mask, at)? + 1;
+    }
+    let open = at + source[at..].find('(')?;
+    let params_end = matching(source, mask, open)? + 1;
+    let rest = &source[params_end..function.body.0];
+    let arrow = rest.find("->")?;
+    let start = params_end + arrow + 2;
+    let text = &source[start..function.body.0];
+    let end = text.find(" where").or_else(|| text.find("\nwhere")).map_or(function.body.0, |at| start + at);
+    let text = &source[start..end];
+    let start = start + (text.len() - text.trim_start().len());
+    let end = end - (text.len() - text.trim_end().len());
+    Some((start, end, source[start..end].to_string()))
+}
+
+/// Edits turning `-> impl Future<Output = T> { async move { .. } }` into `-> T { .. }`
+fn unwrap_future(source: &str, mask: &[bool], function: &FunctionSpan, ret_start: usize, ret_end: usize) -> Option<Vec<(usize, usize, String)>> {
+    let ret = &source[ret_start..ret_end];
+    if !ret.starts_with("impl ") {
+        return None;
+    }
+    let open = ret_start + ret.find("Future<")? + 6;
+    let close = matching(source, mask, open)?;
+    let output = source[open + 1..close].trim().strip_prefix("Output")?.trim_start().strip_prefix('=')?.trim();
+
+    let body = &source[function.body.0 + 1..function.body.1];
+    let inner = body.trim_start();
+    let block = inner.strip_prefix("async")?.trim_start();
+    let block = block.strip_prefix("move").map_or(block, str::trim_start);
+    if !block.starts_with('{') {
+        return None;
+    }
+    let block_open = function.body.1 - block.len();
+    let block_close = matching(source, mask, block_open)?;
+    if !source[block_close + 1..function.body.1].trim().is_empty() {
+        return None;
+    }
+
+    // The block's content moves out one level
+    let content: Vec<&str> = source[block_open + 1..block_close].split('\n').enumerate()
+        .map(|(i, line)| if i == 0 { line } else { line.strip_prefix("    ").unwrap_or(line) })
+        .collect();
+    let ret_edit = if output == "()" {
+        let arrow = source[..ret_start].rfind("->")?;
+        let params_end = source[..arrow].trim_end().len();
+        (params_end, ret_end, String::new())
+    } else {
+        (ret_start, ret_end, output.to_string())
+    };
+    Some(vec![ret_edit, (function.body.0, function.body.1 + 1, format!("{{{}}}", content.join("\n")))])
+}
+
+/// Whether the item starting at `start` sits in a `trait` or `impl Trait for Type` block
+fn in_trait(source: &str, mask: &[bool], start: usize) -> bool {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let Some(open) = (0..start).rev().filter(|&i| mask[i]).find(|&i| match bytes[i] {
+        b'}' => { depth += 1; false }
+        b'{' if depth == 0 => true,
+        b'{' => { depth -= 1; false }
+        _ => false,
+    }) else {
+        return false;
+    };
+    let header_start = (0..open).rev()
+        .find(|&i| mask[i] && matches!(bytes[i], b';' | b'{' | b'}'))
+        .map_or(0, |i| i + 1);
+    let header = source[header_start..open].trim();
+    let words: Vec<&str> = header.split_whitespace().filter(|w| !w.starts_with("#[")).collect();
+    words.contains(&"trait") || (words.iter().any(|w| w.starts_with("impl")) && words.contains(&"for"))
+}
+
+/// Attributes on the lines right above `start`
+fn attributes_before(source: &str, start: usize) -> Vec<String> {
+    source[..start].lines().rev().skip(1)
+        .map(str::trim)
+        .take_while(|line| line.starts_with("#[") || line.starts_with("///"))
+        .filter(|line| line.starts_with("#["))
+        .map(str::to_string)
+        .collect()
+}
+
+/// What the code at `offset`, in the body of `function`, runs in
+fn context_at(source: &str, mask: &[bool], function: &FunctionSpan, offset: usize) -> Context {
+    let bytes = source.as_bytes();
+    let mut stack = vec![Context::Function];
+    let mut statement = function.body.0 + 1;
+    for i in function.body.0 + 1..offset {
+        if !mask[i] {
+            continue;
+        }
+        match bytes[i] {
+            b'{' => {
+                let before = source[..i].trim_end();
+                let before = strip_word(before, "move").map_or(before, str::trim_end);
+                let context = if strip_word(before, "async").is_some() {
+                    Context::AsyncBlock
+                } else if before.ends_with('|') {
+                    Context::Closure
+                } else {
+                    *stack.last().unwrap_or(&Context::Function)
+                };
+                stack.push(context);
+                statement = i + 1;
+            }
+            b'}' => {
+                if stack.len() > 1 {
+                    stack.pop();
+                }
+                statement = i + 1;
+            }
+            b';' => statement = i + 1,
+            _ => {}
+        }
+    }
+
+    // A closure without braces, such as `.map(|x| f(x))`, ends within its statement
+    let pending: String = source[statement..offset].char_indices()
+        .filter(|(i, _)| mask[statement + i])
+        .map(|(_, c)| c)
+        .collect();
+    let opens_closure = pending.match_indices('|').any(|(at, _)| {
+        let before = pending[..at].trim_end();
+        before.is_empty() || before.ends_with(['(', ',', '=']) || strip_word(before, "move").is_some()
+    });
+    if opens_closure {
+        Context::Closure
+    } else {
+        *stack.last().unwrap_or(&Context::Function)
+    }
+}
+
+/// `text` without a trailing `word`, when it ends with that whole word
+fn strip_word<'a>(text: &'a str, word: &str) -> Option<&'a str> {
+    let rest = text.strip_suffix(word)?;
+    (!rest.chars().next_back().is_some_and(is_ident)).then_some(rest)
+}
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Tools that edit files, or compute edits meant to be applied
+const WRITE_TOOLS: &[&str] = &[
+    "rename", "extract_function", "inline", "organize_imports", "apply_and_check", "apply_change", "convert_control_flow",
+    "extract_module", "toggle_async", "sync_document",
+];
+
+/// Tools that build or run the project (`cargo check`, `cargo clippy`, `cargo run`),
+/// which executes build scripts, proc macros and the project's own code
+const EXECUTE_TOOLS: &[&str] = &[
+    "get_diagnostics", "find_unhandled_results", "diagnose", "find_dead_code", "suggest_improvements",
+    "check_feature_powerset", "run_example", "apply_and_check", "unresolved_imports",
+    "run_benchmark", "missing_docs",
+];
+
+/// What a tool may do to the machine it runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCategory {
+    /// Only reads the project
+    ReadOnly,
+    /// Edits files or rust-analyzer's buffers
+    Write,
+    /// Builds or runs project code
+    Execute,
+}
+
+impl ToolCategory {
+    pub const ALL: [ToolCategory; 3] = [ToolCategory::ReadOnly, ToolCategory::Write, ToolCategory::Execute];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToolCategory::ReadOnly => "read_only",
+            ToolCategory::Write => "write",
+            ToolCategory::Execute => "execute",
+        }
+    }
+
+    /// Parse a comma-separated list such as `read_only,write`
+    pub fn parse_list(list: &str) -> Result<Vec<ToolCategory>> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                Self::ALL.into_iter().find(|c| c.name() == name).ok_or_else(|| anyhow::anyhow!(
+                    "Unknown tool category '{}'; expected read_only, write or execute", name
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Categories of `tool`; a tool that both writes and executes is in both
+pub fn categories(tool: &str) -> Vec<ToolCategory> {
+    let mut categories = Vec::new();
+    if WRITE_TOOLS.contains(&tool) {
+        categories.push(ToolCategory::Write);
+    }
+    if EXECUTE_TOOLS.contains(&tool) {
+        categories.push(ToolCategory::Execute);
+    }
+    if categories.is_empty() {
+        categories.push(ToolCategory::ReadOnly);
+    }
+    categories
+}
+
+/// The first category of `tool` missing from `enabled`, if any
+pub fn disabled_category(tool: &str, enabled: &[ToolCategory]) -> Option<ToolCategory> {
+    categories(tool).into_iter().find(|c| !enabled.contains(c))
+}
+
+/// Refuse to run `tool` unless all its categories are enabled
+pub fn check_enabled(tool: &str, enabled: &[ToolCategory]) -> Result<()> {
+    match disabled_category(tool, enabled) {
+        Some(category) => anyhow::bail!(
+            "Tool {} is disabled: the {} category is not enabled on this server (see --tool-categories)",
+            tool, category.name()
+        ),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::extract_module::impl_self_type;
+use crate::source_files;
+use crate::test_code_audit;
+use crate::toggle_async::{code_mask, line_col, matching};
+
+/// Keywords that start an item, with the kind reported for it
+const ITEM_KINDS: &[(&str, &str)] = &[
+    ("fn", "function"),
+    ("struct", "struct"),
+    ("enum", "enum"),
+    ("union", "union"),
+    ("trait", "trait"),
+    ("impl", "impl"),
+    ("type", "type_alias"),
+    ("static", "static"),
+    ("const", "constant"),
+    ("mod", "module"),
+];
+
+/// Words allowed between the start of a statement and an item keyword
+const MODIFIERS: &[&str] = &["pub", "async", "const", "unsafe", "extern", "default"];
+
+/// One `dyn Trait` type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynSite {
+    pub file: String,
+    /// 1-based, of the `dyn` keyword
+    pub line: usize,
+    /// 1-based
+    pub column: usize,
+    /// The principal trait as written, without generic arguments: `Fn`,
+    /// `Iterator`, `std::error::Error`
+    #[serde(rename = "trait")]
+    pub trait_name: String,
+    /// Auto traits and lifetimes added with `+`, e.g. `Send`, `'static`
+    pub bounds: Vec<String>,
+    /// The whole type, e.g. `dyn Iterator<Item = u32> + Send`
+    pub text: String,
+    /// What holds the trait object: `Box`, `Arc`, `Rc`, ... or `&`, `&mut`,
+    /// `*const`, `*mut`; absent for a bare `dyn Trait`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// `parameter`, `return`, `signature` (generics and where clauses),
+    /// `body`, `field`, `type_alias`, `impl`, `static`, `constant` or `other`
+    pub position: String,
+    /// Innermost enclosing item, methods as `Type::method`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DynReport {
+    pub files_scanned: usize,
+    pub total: usize,
+    pub by_trait: BTreeMap<String, usize>,
+    pub by_position: BTreeMap<String, usize>,
+    pub sites: Vec<DynSite>,
+}
+
+/// An item by byte offsets, from its first modifier to its closing `}` or `;`
+#[derive(Debug, Clone)]
+struct ItemSpan {
+    kind: &'static str,
+    name: String,
+    start: usize,
+    /// Opening brace of the body, if it has one
+    body: Option<usize>,
+    end: usize,
+}
+
+/// Find the trait objects in the `.rs` files under `dir`. Test modules and
+/// `tests/` directories are skipped unless `include_tests` is set.
+pub fn find_dyn(project_root: &Path, dir: &Path, include_tests: bool) -> DynReport {
+    let mut report = DynReport::default();
+    for file in source_files::rust_files(dir) {
+        let display = file.strip_prefix(project_root).unwrap_or(&file).display().to_string();
+        if !include_tests && Path::new(&display).starts_with("tests") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&file) else { continue };
+        report.files_scanned += 1;
+        report.sites.extend(scan_source(&content, include_tests).into_iter().map(|site| DynSite { file: display.clone(), ..site }));
+    }
+
+    for site in &report.sites {
+        *report.by_trait.entry(site.trait_name.clone()).or_default() += 1;
+        *report.by_position.entry(site.position.clone()).or_default() += 1;
+    }
+    report.total = report.sites.len();
+    report
+}
+
+/// Trait objects in the code of `source`. `file` is left empty for the caller to fill in.
+pub fn scan_source(source: &str, include_tests: bool) -> Vec<DynSite> {
+    let mask = code_mask(source);
+    let regions = test_code_audit::test_regions(source);
+    let lines: Vec<&str> = source.lines().collect();
+    // Comments and strings blanked out, offsets unchanged
+    let blanked: Vec<u8> = source.bytes().zip(&mask).map(|(b, code)| if *code { b } else { b' ' }).collect();
+    let source = &String::from_utf8(blanked).unwrap_or_else(|_| source.to_string());
+    let items = items(source, &mask);
+
+    keyword_offsets(source, &mask, "dyn").into_iter()
+        .filter(|&at| include_tests || !regions.contains(at))
+        .filter_map(|at| {
+            let (text, trait_name, bounds) = parse_bounds(source, &mask, at + 3)?;
+            let (line, column) = line_col(source, at);
+            let enclosing = items.iter().filter(|item| item.start <= at && at < item.end).max_by_key(|item| item.start);
+            Some(DynSite {
+                file: String::new(),
+                line,
+                column,
+                trait_name,
+                bounds,
+                text: format!("dyn {}", text),
+                container: container(&source[..at]),
+                position: enclosing.map_or("other", |item| position(source, &mask, item, at)).to_string(),
+                item: enclosing.map(|item| qualified_name(&items, item)),
+                code: lines.get(line - 1).map_or("", |l| l.trim()).to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Text, principal trait and extra bounds of the bound list starting at `from`
+fn parse_bounds(source: &str, mask: &[bool], from: usize) -> Option<(String, String, Vec<String>)> {
+    let mut at = from;
+    let mut trait_name = None;
+    let mut bounds = Vec::new();
+    loop {
+        at += source[at..].len() - source[at..].trim_start().len();
+        let start = at;
+        let rest = &source[at..];
+        if let Some(lifetime) = rest.strip_prefix('\'') {
+            at += 1 + lifetime.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(lifetime.len());
+            bounds.push(source[start..at].to_string());
+        } else {
+            let path_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).unwrap_or(rest.len());
+            if path_len == 0 {
+                return None;
+            }
+            let path = &rest[..path_len];
+            at += path_len;
+            // Generic arguments, or the `Fn(A) -> R` sugar
+            if matches!(source[at..].chars().next(), Some('<' | '(')) {
+                at = matching(source, mask, at)? + 1;
+                let after = source[at..].trim_start();
+                if let Some(ret) = after.strip_prefix("->") {
+                    at = source.len() - ret.len();
+                    at += type_end(&source[at..]);
+                }
+            }
+            match trait_name {
+                None => trait_name = Some(path.to_string()),
+                Some(_) => bounds.push(source[start..at].trim().to_string()),
+            }
+        }
+        let after = source[at..].trim_start();
+        match after.strip_prefix('+') {
+            Some(next) if !next.trim_start().starts_with(['>', ')', ',', ';', '=', '{']) => at = source.len() - next.len(),
+            _ => break,
+        }
+    }
+    let text: String = source[from..at].split_whitespace().collect::<Vec<_>>().join(" ");
+    Some((text, trait_name?, bounds))
+}
+
+/// Length of the type at the start of `text`: up to a top-level `+`, `,`,
+/// `;`, `=`, `{` or an unmatched closing bracket
+fn type_end(text: &str) -> usize {
+    let mut depth = 0i32;
+    let mut previous = ' ';
+    for (at, c) in text.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' if previous == '-' => {}
+            '>' | ')' | ']' if depth == 0 => return at,
+            '>' | ')' | ']' => depth -= 1,
+            '+' | ',' | ';' | '=' | '{' if depth == 0 => return at,
+            _ => {}
+        }
+        previous = c;
+    }
+    text.len()
+}
+
+/// What holds a trait object whose `dyn` follows `before`
+fn container(before: &str) -> Option<String> {
+    // `&(dyn Error + 'static)`
+    let before = before.trim_end();
+    let before = before.strip_suffix('(').map_or(before, str::trim_end);
+    if let Some(rest) = before.strip_suffix('<') {
+        let rest = rest.trim_end();
+        let name = &rest[rest.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |at| at + 1)..];
+        return (!name.is_empty()).then(|| name.to_string());
+    }
+    if before.ends_with("*const") {
+        return Some("*const".to_string());
+    }
+    let (rest, mutable) = match before.strip_suffix("mut") {
+        Some(rest) if !rest.ends_with(|c: char| c.is_alphanumeric() || c == '_') => (rest.trim_end(), true),
+        _ => (before, false),
+    };
+    if mutable && rest.ends_with('*') {
+        return Some("*mut".to_string());
+    }
+    // `&'a` and `&'a mut`
+    let rest = match rest.rsplit_once('\'') {
+        Some((head, lifetime)) if lifetime.chars().all(|c| c.is_alphanumeric() || c == '_') => head,
+        _ => rest,
+    };
+    rest.ends_with('&').then(|| if mutable { "&mut" } else { "&" }.to_string())
+}
+
+/// Where in `item` the trait object at `at` appears
+fn position(source: &str, mask: &[bool], item: &ItemSpan, at: usize) -> &'static str {
+    let in_body = item.body.is_some_and(|open| at > open);
+    match item.kind {
+        "function" if in_body => "body",
+        "function" => {
+            let header_end = item.body.unwrap_or(item.end);
+            let name_end = source[item.start..header_end].find(&item.name).map_or(item.start, |i| item.start + i + item.name.len());
+            let mut open = name_end;
+            if source[open..].starts_with('<') {
+                open = matching(source, mask, open).map_or(open, |close| close + 1);
+            }
+            let Some(open) = source[open..header_end].find('(').map(|i| open + i) else { return "signature" };
+            let close = matching(source, mask, open).unwrap_or(header_end);
+            if at > open && at < close {
+                return "parameter";
+            }
+            let returns = source[close..header_end].find("->").map(|i| close + i);
+            let clause = source[close..header_end].find("where").map(|i| close + i);
+            match (returns, clause) {
+                (Some(arrow), clause) if at > arrow && clause.is_none_or(|w| at < w) => "return",
+                _ => "signature",
+            }
+        }
+        "struct" | "enum" | "union" => "field",
+        "type_alias" => "type_alias",
+        "impl" if !in_body => "impl",
+        "static" => "static",
+        "constant" => "constant",
+        _ => "other",
+    }
+}
+
+/// `Type::method` for functions directly inside an `impl` or `trait`
+fn qualified_name(items: &[ItemSpan], item: &ItemSpan) -> String {
+    let parent = items.iter()
+        .filter(|parent| parent.body.is_some_and(|open| open < item.start) && item.end <= parent.end)
+        .max_by_key(|parent| parent.start);
+    match parent {
+        Some(parent) if item.kind == "function" && matches!(parent.kind, "impl" | "trait") => format!("{}::{}", parent.name, item.name),
+        _ => item.name.clone(),
+    }
+}
+
+/// Items of `source` with their extents; for an `impl` the name is its self type
+fn items(source: &str, mask: &[bool]) -> Vec<ItemSpan> {
+    let mut items = Vec::new();
+    for (keyword, kind) in ITEM_KINDS {
+        for at in keyword_offsets(source, mask, keyword) {
+            let Some(start) = item_start(source, at) else { continue };
+            // `const fn` is a function
+            if *keyword == "const" && MODIFIERS.iter().chain(&["fn"]).any(|word| header_name(&source[at + 5..]) == *word) {
+                continue;
+            }
+            let Some(stop) = header_end(source, at + keyword.len()) else { continue };
+            let (body, end) = match source.as_bytes()[stop] {
+                b'{' => (Some(stop), matching(source, mask, stop).map_or(source.len(), |close| close + 1)),
+                _ => (None, stop + 1),
+            };
+            let name = if *kind == "impl" { impl_self_type(&source[at..stop]).unwrap_or_default() } else { header_name(&source[at + keyword.len()..stop]) };
+            items.push(ItemSpan { kind, name, start, body, end });
+        }
+    }
+    items
+}
+
+/// Offset where the item whose keyword is at `keyword` starts, or `None`
+/// when the keyword doesn't begin an item (`impl Trait` in a type, `const`
+/// generics, ...)
+fn item_start(source: &str, keyword: usize) -> Option<usize> {
+    let mut start = keyword;
+    loop {
+        let before = source[..start].trim_end();
+        let word_start = before.rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '(' || c == ')' || c == '"')).map_or(0, |at| at + 1);
+        let word = &before[word_start..];
+        let bare = word.split('(').next().unwrap_or(word);
+        if !word.is_empty() && (MODIFIERS.contains(&bare) || word.starts_with('"')) {
+            start = word_start;
+            continue;
+        }
+        return match before.chars().next_back() {
+            None | Some(';' | '{' | '}' | ']') => Some(start),
+            _ => None,
+        };
+    }
+}
+
+/// The `{` or `;` ending the item header that continues at `from`
+fn header_end(source: &str, from: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (at, c) in source[from..].char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '{' | ';' if depth <= 0 => return Some(from + at),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Name declared by an item header, the text after its keyword
+fn header_name(header: &str) -> String {
+    let header = header.trim_start();
+    header.strip_prefix("mut ").unwrap_or(header).trim_start()
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Byte offsets of `keyword` as a whole word in the code of `source`
+pub(crate) fn keyword_offsets(source: &str, mask: &[bool], keyword: &str) -> Vec<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    source.match_indices(keyword)
+        .map(|(at, _)| at)
+        .filter(|&at| {
+            mask[at]
+                && !source[..at].ends_with(|c: char| is_ident(c) || c == '\'' || c == '!')
+                && !source[at + keyword.len()..].starts_with(is_ident)
+        })
+        .collect()
+}
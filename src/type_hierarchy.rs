@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::call_hierarchy::{self, CallItem};
+
+/// Which way to walk from a type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Traits it implements, supertraits of a trait
+    #[default]
+    Supertypes,
+    /// Types implementing a trait, subtraits
+    Subtypes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeHierarchy {
+    /// The type at the position, or null when there is none
+    #[serde(rename = "type")]
+    pub root: Option<CallItem>,
+    pub direction: Direction,
+    pub types: Vec<CallItem>,
+}
+
+/// A `TypeHierarchyItem`; it has the same fields as a `CallHierarchyItem`
+pub fn item_from_lsp(item: &Value, project_root: &Path) -> Option<CallItem> {
+    call_hierarchy::item_from_lsp(item, project_root)
+}
+
+/// Related types of a `typeHierarchy/supertypes` or `typeHierarchy/subtypes`
+/// response, in the order rust-analyzer gave them
+pub fn types_from_lsp(result: &Value, project_root: &Path) -> Vec<CallItem> {
+    result.as_array().into_iter().flatten()
+        .filter_map(|item| item_from_lsp(item, project_root))
+        .collect()
+}
@@ -0,0 +1,227 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::edit_check;
+use crate::source_files;
+
+/// A `Result` or `Option` whose value is thrown away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnhandledResult {
+    pub file: String,
+    /// 1-based
+    pub line: usize,
+    /// `unused_must_use`, `let_underscore`, `ok_discarded` or `discarded_return`
+    pub kind: String,
+    /// `cargo` for compiler warnings, `scan` for source matches
+    pub source: String,
+    pub message: String,
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnhandledReport {
+    /// Whether `cargo check` succeeded; when it did not, only scan findings are reliable
+    pub cargo_check_success: bool,
+    pub files_scanned: usize,
+    pub total: usize,
+    pub by_kind: HashMap<String, usize>,
+    pub findings: Vec<UnhandledResult>,
+}
+
+/// Combine `unused_must_use` warnings from `cargo check` with a source scan of
+/// `dir` for values the lint cannot see: `let _ = ...`, `.ok();` and discarded
+/// returns of workspace functions yielding `Option`. Test modules and `tests/`
+/// directories are skipped unless `include_tests` is set.
+pub async fn find_unhandled_results(project_root: &Path, dir: &Path, include_tests: bool) -> Result<UnhandledReport> {
+    let check = edit_check::cargo_check(&project_root.join("Cargo.toml")).await?;
+    let prefix = dir.strip_prefix(project_root).unwrap_or(dir).to_path_buf();
+
+    let mut report = UnhandledReport {
+        cargo_check_success: check.success,
+        ..UnhandledReport::default()
+    };
+    let mut seen = HashSet::new();
+    for finding in must_use_warnings(&check.diagnostics) {
+        if Path::new(&finding.file).starts_with(&prefix) && seen.insert((finding.file.clone(), finding.line)) {
+            report.findings.push(finding);
+        }
+    }
+
+    let files: Vec<_> = source_files::rust_files(dir)
+        .into_iter()
+        .filter(|file| include_tests || !file.strip_prefix(project_root).unwrap_or(file).starts_with("tests"))
+        .filter_map(|file| std::fs::read_to_string(&file).ok().map(|content| (file, content)))
+        .collect();
+    report.files_scanned = files.len();
+
+    let mut fallible = HashMap::new();
+    for (_, content) in &files {
+        for (name, kind) in fallible_functions(content) {
+            // A name shared by `Result` and `Option` returning functions is treated as `Result`
+            let entry = fallible.entry(name).or_insert(kind);
+            if kind == "Result" {
+                *entry = kind;
+            }
+        }
+    }
+
+    for (file, content) in &files {
+        let display = file.strip_prefix(project_root).unwrap_or(file).display().to_string();
+        for mut finding in scan_source(content, &fallible, include_tests) {
+            if seen.insert((display.clone(), finding.line)) {
+                finding.file = display.clone();
+                report.findings.push(finding);
+            }
+        }
+    }
+
+    report.findings.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    for finding in &report.findings {
+        *report.by_kind.entry(finding.kind.clone()).or_default() += 1;
+    }
+    report.total = report.findings.len();
+    Ok(report)
+}
+
+/// `unused_must_use` diagnostics from `cargo check`, one per file and line
+pub fn must_use_warnings(diagnostics: &[Value]) -> Vec<UnhandledResult> {
+    diagnostics.iter()
+        .filter(|d| d["code"]["code"] == "unused_must_use")
+        .filter_map(|d| Some(UnhandledResult {
+            file: d["file"].as_str()?.to_string(),
+            line: d["line"].as_u64()? as usize,
+            kind: "unused_must_use".to_string(),
+            source: "cargo".to_string(),
+            message: d["message"].as_str().unwrap_or("").to_string(),
+            code: None,
+        }))
+        .collect()
+}
+
+/// Functions declared in `source` that return `Result` or `Option`, mapped to
+/// which of the two
+pub fn fallible_functions(source: &str) -> Vec<(String, &'static str)> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut functions = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let code = strip_comment(line);
+        let Some((before, after_fn)) = code.split_once("fn ") else { continue };
+        if !(before.is_empty() || before.ends_with(' ')) {
+            continue;
+        }
+        let name: String = after_fn.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        if name.is_empty() {
+            continue;
+        }
+
+        // Signatures may span several lines; stop at the body or the `;` of a declaration
+        let mut signature = after_fn.to_string();
+        let mut next = index + 1;
+        while !signature.contains('{') && !signature.contains(';') && next < lines.len() {
+            signature.push(' ');
+            signature.push_str(strip_comment(lines[next]));
+            next += 1;
+        }
+        let signature = signature.split(['{', ';']).next().unwrap_or("");
+        let signature = signature.split(" where ").next().unwrap_or("");
+        let Some((_, return_type)) = signature.rsplit_once("->") else { continue };
+        let base = return_type.trim().split('<').next().unwrap_or("");
+        match base.rsplit("::").next().unwrap_or("").trim() {
+            "Result" => functions.push((name, "Result")),
+            "Option" => functions.push((name, "Option")),
+            _ => {}
+        }
+    }
+    functions
+}
+
+/// Statements in `source` that silently drop a `Result` or `Option`. `file` is
+/// left empty for the caller to fill in.
+pub fn scan_source(source: &str, fallible: &HashMap<String, &'static str>, include_tests: bool) -> Vec<UnhandledResult> {
+    let mut findings = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let code = strip_comment(line).trim();
+        if !include_tests && code.starts_with("#[cfg(test)]") {
+            break;
+        }
+        let Some(statement) = code.strip_suffix(';') else { continue };
+
+        let (kind, message) = if let Some(value) = statement.strip_prefix("let _ =").or_else(|| statement.strip_prefix("let _:")) {
+            ("let_underscore", format!("Value of `{}` is discarded with `let _`", value.trim()))
+        } else if is_expression_statement(statement) && statement.ends_with(".ok()") {
+            ("ok_discarded", "Error converted with `.ok()` and then discarded".to_string())
+        } else if is_expression_statement(statement) {
+            let Some(name) = called_function(statement) else { continue };
+            let Some(kind) = fallible.get(name) else { continue };
+            ("discarded_return", format!("{} returned by `{}` is discarded", kind, name))
+        } else {
+            continue;
+        };
+
+        findings.push(UnhandledResult {
+            file: String::new(),
+            line: index + 1,
+            kind: kind.to_string(),
+            source: "scan".to_string(),
+            message,
+            code: Some(code.to_string()),
+        });
+    }
+    findings
+}
+
+/// A statement that evaluates an expression for its side effects only
+fn is_expression_statement(statement: &str) -> bool {
+    const DECLARATIONS: &[&str] = &[
+        "let ", "return", "use ", "pub ", "fn ", "mod ", "type ", "const ", "static ", "break", "continue", "}", "#", "extern ", "impl ",
+    ];
+    if DECLARATIONS.iter().any(|prefix| statement.starts_with(prefix)) {
+        return false;
+    }
+    // Assignments store the value, even compound ones like `+=`
+    let bytes = statement.as_bytes();
+    !bytes.iter().enumerate().any(|(i, &b)| {
+        b == b'='
+            && bytes.get(i + 1) != Some(&b'=')
+            && bytes.get(i + 1) != Some(&b'>')
+            && !matches!(i.checked_sub(1).map(|p| bytes[p]), Some(b'=' | b'!' | b'<' | b'>'))
+    })
+}
+
+/// Name of the outermost call a statement ends with, looking through `.await`
+fn called_function(statement: &str) -> Option<&str> {
+    let expr = statement.trim_end();
+    let expr = expr.strip_suffix(".await").unwrap_or(expr);
+    if !expr.ends_with(')') {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut open = None;
+    for (i, c) in expr.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' => {
+                depth -= 1;
+                if depth == 0 {
+                    open = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let before = &expr[..open?];
+    let start = before.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+    let name = &before[start..];
+    (!name.is_empty()).then_some(name)
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split("//").next().unwrap_or(line)
+}
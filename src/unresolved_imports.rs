@@ -0,0 +1,446 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+use crate::edit_check;
+use crate::import_suggest::STD_ITEMS;
+use crate::reexports::{module_path_for, ITEM_KEYWORDS};
+use crate::source_files;
+use crate::toggle_async::code_mask;
+
+/// Path roots that need no dependency
+const BUILTIN_ROOTS: &[&str] = &["crate", "self", "super", "std", "core", "alloc"];
+
+/// Dependency tables of a manifest, also looked up under `[target.*]`
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// A `use` rustc could not resolve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnresolvedImport {
+    pub file: String,
+    /// 1-based position of the segment rustc stopped at
+    pub line: u64,
+    pub column: u64,
+    /// The path up to that segment, e.g. `crate::modles` in `use crate::modles::Widget;`
+    pub unresolved: String,
+    /// rustc's label, e.g. "could not find `modles` in the crate root"
+    pub label: String,
+    /// The `use` declaration holding the path
+    pub declaration: String,
+    pub suggestions: Vec<ImportFix>,
+    /// Columns (1-based, end exclusive) of the primary span, and the line it is on
+    #[serde(skip)]
+    span: (u64, u64, String),
+}
+
+/// One way to fix an unresolved import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportFix {
+    /// `compiler` for rustc's own suggestion, `typo` for a close name in the
+    /// same place, `path` for the name found elsewhere, `dependency` for a
+    /// crate to add
+    pub kind: String,
+    /// The path as it reads after the fix, or the crate to add
+    pub replacement: String,
+    pub score: i32,
+    /// LSP `TextEdit` on the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit: Option<Value>,
+    /// Command to run instead of an edit, e.g. `cargo add serde_json`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnresolvedImportsReport {
+    /// Whether `cargo check` succeeded
+    pub success: bool,
+    pub count: usize,
+    pub imports: Vec<UnresolvedImport>,
+}
+
+/// An item a path may lead to
+#[derive(Debug, Clone)]
+struct KnownItem {
+    /// e.g. `crate::models::Widget` or `std::collections`
+    path: String,
+    /// `workspace` or `std`
+    source: &'static str,
+}
+
+impl KnownItem {
+    fn name(&self) -> &str {
+        self.path.rsplit("::").next().unwrap_or(&self.path)
+    }
+
+    fn parent(&self) -> &str {
+        self.path.rsplit_once("::").map_or("", |(parent, _)| parent)
+    }
+}
+
+/// Public items of the workspace and common std paths, to look unresolved
+/// segments up in
+#[derive(Debug, Clone, Default)]
+pub struct Candidates {
+    items: Vec<KnownItem>,
+}
+
+/// The `E0432` unresolved imports among `cargo check --message-format=json`
+/// messages, each reported once even when several targets hit it. The
+/// compiler's own suggestions come first in `suggestions`.
+pub fn collect(messages: &[Value]) -> Vec<UnresolvedImport> {
+    let mut seen = HashSet::new();
+    let mut imports = Vec::new();
+    for msg in messages {
+        if msg.get("reason").and_then(|r| r.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let message = &msg["message"];
+        let text = message["message"].as_str().unwrap_or("");
+        if message["code"]["code"] != "E0432" && !text.starts_with("unresolved import") {
+            continue;
+        }
+        let Some(unresolved) = text.split('`').nth(1) else { continue };
+        let Some(spans) = message["spans"].as_array() else { continue };
+        let Some(span) = spans.iter().find(|s| s["is_primary"] == true).or_else(|| spans.first()) else { continue };
+        let (Some(file), Some(line), Some(column)) = (span["file_name"].as_str(), span["line_start"].as_u64(), span["column_start"].as_u64()) else {
+            continue;
+        };
+        if !seen.insert((file.to_string(), line, column)) {
+            continue;
+        }
+
+        let line_text = span["text"][0]["text"].as_str().unwrap_or("").to_string();
+        let end = match span["line_end"].as_u64() {
+            Some(end) if end == line => span["column_end"].as_u64().unwrap_or(column),
+            _ => column,
+        };
+        let mut import = UnresolvedImport {
+            file: file.to_string(),
+            line,
+            column,
+            unresolved: unresolved.to_string(),
+            label: span["label"].as_str().unwrap_or(text).to_string(),
+            declaration: String::new(),
+            suggestions: Vec::new(),
+            span: (column, end, line_text),
+        };
+        import.suggestions = compiler_fixes(message, &import);
+        imports.push(import);
+    }
+    imports
+}
+
+/// Edits rustc suggests in the diagnostic's `help` children
+fn compiler_fixes(message: &Value, import: &UnresolvedImport) -> Vec<ImportFix> {
+    message["children"].as_array().into_iter().flatten()
+        .filter(|child| child["level"] == "help")
+        .flat_map(|child| child["spans"].as_array().into_iter().flatten())
+        .filter(|span| span["line_start"].as_u64() == Some(import.line) && span["line_end"].as_u64() == Some(import.line))
+        .filter_map(|span| {
+            let text = span["suggested_replacement"].as_str()?;
+            let (start, end) = (span["column_start"].as_u64()?, span["column_end"].as_u64()?);
+            Some(ImportFix {
+                kind: "compiler".to_string(),
+                replacement: import.rewritten(start, end, text),
+                score: 100,
+                edit: Some(text_edit(import.line, start, end, text)),
+                command: None,
+            })
+        })
+        .collect()
+}
+
+impl UnresolvedImport {
+    /// The failing segment, e.g. `modles`
+    fn segment(&self) -> &str {
+        self.unresolved.rsplit("::").next().unwrap_or(&self.unresolved)
+    }
+
+    /// The path before the failing segment; empty when the first segment failed
+    fn parent(&self) -> &str {
+        self.unresolved.rsplit_once("::").map_or("", |(parent, _)| parent)
+    }
+
+    /// Columns (1-based, end exclusive) of the failing segment, when the span ends with it
+    fn segment_columns(&self) -> Option<(u64, u64)> {
+        let (_, end, line) = &self.span;
+        let segment = self.segment();
+        let start = end.checked_sub(segment.chars().count() as u64)?;
+        let found: String = line.chars().skip(start.checked_sub(1)? as usize).take(segment.chars().count()).collect();
+        (found == segment).then_some((start, *end))
+    }
+
+    /// Columns of the path written around the span, e.g. `crate::modles::Widget`
+    /// for `modles`; inside a `{...}` group it starts after the brace
+    fn written_path(&self) -> (u64, u64) {
+        let (start, end, line) = &self.span;
+        let chars: Vec<char> = line.chars().collect();
+        let in_path = |c: char| c.is_alphanumeric() || c == '_' || c == ':';
+        let mut from = (*start as usize).saturating_sub(1).min(chars.len());
+        while from > 0 && in_path(chars[from - 1]) {
+            from -= 1;
+        }
+        let mut to = (*end as usize).saturating_sub(1).min(chars.len());
+        while to < chars.len() && in_path(chars[to]) {
+            to += 1;
+        }
+        (from as u64 + 1, to as u64 + 1)
+    }
+
+    /// The text of 1-based columns `start..end` of the span's line
+    fn columns(&self, start: u64, end: u64) -> String {
+        let chars: Vec<char> = self.span.2.chars().collect();
+        let index = |column: u64| (column.saturating_sub(1) as usize).min(chars.len());
+        chars[index(start)..index(end).max(index(start))].iter().collect()
+    }
+
+    /// The written path after replacing columns `start..end` with `text`
+    fn rewritten(&self, start: u64, end: u64, text: &str) -> String {
+        let (from, to) = self.written_path();
+        if start < from || end > to {
+            return text.to_string();
+        }
+        format!("{}{}{}", self.columns(from, start), text, self.columns(end, to))
+    }
+}
+
+/// LSP `TextEdit` replacing 1-based columns `start..end` of 1-based `line`
+fn text_edit(line: u64, start: u64, end: u64, text: &str) -> Value {
+    json!({
+        "range": {
+            "start": { "line": line - 1, "character": start - 1 },
+            "end": { "line": line - 1, "character": end - 1 }
+        },
+        "newText": text
+    })
+}
+
+impl Candidates {
+    /// Index the public items under `project_root/src` and the std paths
+    /// `resolve_unresolved` knows
+    pub fn scan(project_root: &Path) -> Self {
+        let mut candidates = Self::default();
+        let mut seen = HashSet::new();
+        let mut push = |path: String, source: &'static str| {
+            if seen.insert(path.clone()) {
+                candidates.items.push(KnownItem { path, source });
+            }
+        };
+
+        let src = project_root.join("src");
+        for file in source_files::rust_files(&src) {
+            let Some(module) = module_path_for(&src, &file) else { continue };
+            let Ok(content) = std::fs::read_to_string(&file) else { continue };
+            for path in public_items(&module.join("::"), &content) {
+                push(path, "workspace");
+            }
+        }
+        for (_, path, _) in STD_ITEMS {
+            // `std::sync::atomic::AtomicBool` also vouches for `std::sync` and `std::sync::atomic`
+            let segments: Vec<&str> = path.split("::").collect();
+            for end in 2..=segments.len() {
+                push(segments[..end].join("::"), "std");
+            }
+        }
+        candidates
+    }
+
+    /// Rank fixes for `import` after the compiler's own: a close name in the
+    /// same module or among the crates in scope, the same name elsewhere in
+    /// the workspace, std or `lsp_symbols` (`workspace/symbol` results), and
+    /// a dependency to add when the first segment names no known crate.
+    /// Also fills in the `use` declaration from the file.
+    pub fn suggest(&self, project_root: &Path, import: &mut UnresolvedImport, lsp_symbols: &[Value]) {
+        let path = project_root.join(&import.file);
+        let source = std::fs::read_to_string(&path).unwrap_or_default();
+        import.declaration = declaration(&source, import.line as usize).unwrap_or_else(|| import.span.2.trim().to_string());
+
+        let segment = import.segment().to_string();
+        let parent = import.parent().to_string();
+        let segment_columns = import.segment_columns();
+        let mut fixes = std::mem::take(&mut import.suggestions);
+
+        let typo = |name: &str, score: i32, fixes: &mut Vec<ImportFix>| {
+            let Some((start, end)) = segment_columns else { return };
+            let distance = edit_distance(&segment, name);
+            if distance > 0 && distance <= typo_threshold(&segment) {
+                fixes.push(ImportFix {
+                    kind: "typo".to_string(),
+                    replacement: import.rewritten(start, end, name),
+                    score: score - distance as i32 * 5,
+                    edit: Some(text_edit(import.line, start, end, name)),
+                    command: None,
+                });
+            }
+        };
+
+        if parent.is_empty() {
+            // The first segment: a crate, or a module of the crate root
+            let manifest = edit_check::owning_manifest(&path, project_root);
+            let crates = std::fs::read_to_string(&manifest).map(|m| crate_names(&m)).unwrap_or_default();
+            let typos_before = fixes.len();
+            for name in BUILTIN_ROOTS.iter().copied().chain(crates.iter().map(String::as_str)) {
+                typo(name, 90, &mut fixes);
+            }
+            let local = self.items.iter().any(|item| item.path == format!("crate::{}", segment));
+            if fixes.len() == typos_before && !local && !BUILTIN_ROOTS.contains(&segment.as_str()) {
+                fixes.push(ImportFix {
+                    kind: "dependency".to_string(),
+                    replacement: segment.clone(),
+                    score: 50,
+                    edit: None,
+                    command: Some(format!("cargo add {}", segment)),
+                });
+            }
+        } else {
+            for item in self.items.iter().filter(|item| item.parent() == parent) {
+                typo(item.name(), 90, &mut fixes);
+            }
+        }
+
+        // The same name elsewhere: replace the written path up to the segment
+        let (from, to) = import.written_path();
+        let prefix_written = import.columns(from, to).starts_with(&import.unresolved);
+        let mut elsewhere = |found: String, score: i32| {
+            if found == import.unresolved {
+                return;
+            }
+            let edit = match segment_columns {
+                Some((_, end)) if prefix_written => Some(text_edit(import.line, from, end, &found)),
+                _ => None,
+            };
+            let replacement = match segment_columns {
+                Some((_, end)) if prefix_written => import.rewritten(from, end, &found),
+                _ => found,
+            };
+            fixes.push(ImportFix { kind: "path".to_string(), replacement, score, edit, command: None });
+        };
+        for item in self.items.iter().filter(|item| item.name() == segment) {
+            let depth = item.path.matches("::").count() as i32;
+            let base = if item.source == "workspace" { 80 } else { 75 };
+            elsewhere(item.path.clone(), base - depth * 2);
+        }
+        for symbol in lsp_symbols {
+            if symbol.get("name").and_then(|n| n.as_str()) != Some(segment.as_str()) {
+                continue;
+            }
+            let Some(container) = symbol.get("containerName").and_then(|c| c.as_str()).filter(|c| !c.is_empty()) else { continue };
+            elsewhere(format!("{}::{}", container, segment), 70);
+        }
+
+        fixes.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.replacement.cmp(&b.replacement)));
+        let mut seen = HashSet::new();
+        fixes.retain(|fix| seen.insert(fix.replacement.clone()));
+        import.suggestions = fixes;
+    }
+}
+
+/// Paths of the public items defined in `source`, the file of `module`;
+/// items of inline `mod name { ... }` blocks get the inner module's path
+fn public_items(module: &str, source: &str) -> Vec<String> {
+    let mask = code_mask(source);
+    let mut items = Vec::new();
+    // Inline modules open at the current line, with the depth they close at
+    let mut inline: Vec<(String, usize)> = Vec::new();
+    let mut depth = 0usize;
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let tokens: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == '(' || c == '<' || c == '{' || c == ':' || c == ';')
+            .filter(|t| !t.is_empty())
+            .collect();
+        let current = std::iter::once(module.to_string())
+            .chain(inline.iter().map(|(name, _)| name.clone()))
+            .collect::<Vec<_>>()
+            .join("::");
+        if tokens.first().is_some_and(|t| t.starts_with("pub")) {
+            let found = ITEM_KEYWORDS.iter().find_map(|(keyword, _)| {
+                let at = tokens.iter().position(|t| t == keyword)?;
+                tokens.get(at + 1).map(|name| (*keyword, name.to_string()))
+            });
+            if let Some((keyword, name)) = found {
+                items.push(format!("{}::{}", current, name));
+                if keyword == "mod" && line.contains('{') {
+                    inline.push((name, depth));
+                }
+            }
+        }
+        for (i, byte) in line.bytes().enumerate() {
+            if !mask.get(offset + i).copied().unwrap_or(false) {
+                continue;
+            }
+            match byte {
+                b'{' => depth += 1,
+                b'}' => {
+                    depth = depth.saturating_sub(1);
+                    if inline.last().is_some_and(|(_, open)| *open == depth) {
+                        inline.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+        offset += line.len();
+    }
+    items
+}
+
+/// Names the manifest's dependencies are used under, and the package's own
+/// crate name, with `-` written as `_`
+fn crate_names(manifest: &str) -> BTreeSet<String> {
+    let Ok(manifest) = toml::from_str::<toml::Table>(manifest) else { return BTreeSet::new() };
+    let mut tables: Vec<&toml::Value> = DEPENDENCY_TABLES.iter().filter_map(|table| manifest.get(*table)).collect();
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        for target in targets.values() {
+            tables.extend(DEPENDENCY_TABLES.iter().filter_map(|table| target.get(*table)));
+        }
+    }
+    let own = manifest.get("lib").and_then(|lib| lib.get("name"))
+        .or_else(|| manifest.get("package").and_then(|package| package.get("name")))
+        .and_then(|name| name.as_str());
+    tables.into_iter()
+        .filter_map(|table| table.as_table())
+        .flat_map(|table| table.keys().map(String::as_str))
+        .chain(own)
+        .map(|name| name.replace('-', "_"))
+        .collect()
+}
+
+/// The whole `use` declaration around 1-based `line`
+fn declaration(source: &str, line: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let is_use = |text: &str| {
+        let text = text.trim_start();
+        let text = text.strip_prefix("pub").map_or(text, |rest| rest.split_once(char::is_whitespace).map_or(rest, |(_, rest)| rest).trim_start());
+        text.starts_with("use ")
+    };
+    let start = (0..line.min(lines.len())).rev().take(20).find(|&i| is_use(lines[i]))?;
+    let end = (start..lines.len()).find(|&i| lines[i].contains(';'))?;
+    Some(lines[start..=end].join("\n"))
+}
+
+/// Edits allowed for a segment to count as a misspelling of a name
+fn typo_threshold(segment: &str) -> usize {
+    match segment.chars().count() {
+        0..=4 => 1,
+        5..=8 => 2,
+        _ => 3,
+    }
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::reexports;
+use crate::source_files;
+
+/// Item keywords that may follow a visibility, mapped to the reported kind
+const ITEM_KEYWORDS: &[(&str, &str)] = &[
+    ("fn", "function"),
+    ("struct", "struct"),
+    ("enum", "enum"),
+    ("union", "union"),
+    ("trait", "trait"),
+    ("type", "type_alias"),
+    ("const", "constant"),
+    ("static", "static"),
+    ("mod", "module"),
+    ("macro_rules!", "macro"),
+];
+
+/// Visibility as written on a declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Crate,
+    Super,
+    /// `pub(in path)` / `pub(self)`
+    Restricted(String),
+    Private,
+}
+
+impl Visibility {
+    /// Parse a leading visibility; returns it and the rest of the declaration
+    pub fn parse(declaration: &str) -> (Visibility, &str) {
+        let text = declaration.trim_start();
+        let Some(after) = text.strip_prefix("pub") else {
+            return (Visibility::Private, text);
+        };
+        let after_trimmed = after.trim_start();
+        if let Some(inner) = after_trimmed.strip_prefix('(') {
+            let Some((scope, rest)) = inner.split_once(')') else {
+                return (Visibility::Private, text);
+            };
+            let visibility = match scope.trim() {
+                "crate" => Visibility::Crate,
+                "super" => Visibility::Super,
+                "self" => Visibility::Restricted("self".to_string()),
+                scope => Visibility::Restricted(scope.trim_start_matches("in ").trim().to_string()),
+            };
+            return (visibility, rest.trim_start());
+        }
+        // `pubfoo` is an identifier, not a visibility
+        if after.starts_with(char::is_whitespace) {
+            (Visibility::Public, after_trimmed)
+        } else {
+            (Visibility::Private, text)
+        }
+    }
+
+    pub fn as_str(&self) -> String {
+        match self {
+            Visibility::Public => "pub".to_string(),
+            Visibility::Crate => "pub(crate)".to_string(),
+            Visibility::Super => "pub(super)".to_string(),
+            Visibility::Restricted(path) if path == "self" => "pub(self)".to_string(),
+            Visibility::Restricted(path) => format!("pub(in {})", path),
+            Visibility::Private => "private".to_string(),
+        }
+    }
+
+    /// The module subtree this visibility allows, for an item declared in
+    /// `module`; `None` means visible everywhere, including other crates
+    fn scope(&self, module: &[String]) -> Option<Vec<String>> {
+        match self {
+            Visibility::Public => None,
+            Visibility::Crate => Some(vec!["crate".to_string()]),
+            Visibility::Super => Some(module[..module.len().saturating_sub(1).max(1)].to_vec()),
+            Visibility::Restricted(path) => {
+                let segments: Vec<String> = path.split("::").map(|s| s.trim().to_string()).collect();
+                Some(match segments[0].as_str() {
+                    "crate" => segments,
+                    "self" => module.to_vec(),
+                    "super" => {
+                        let ups = segments.iter().take_while(|s| *s == "super").count();
+                        let mut scope = module[..module.len().saturating_sub(ups).max(1)].to_vec();
+                        scope.extend(segments[ups..].iter().cloned());
+                        scope
+                    }
+                    // Edition 2015 `pub(in a::b)` is crate-relative
+                    _ => std::iter::once("crate".to_string()).chain(segments).collect(),
+                })
+            }
+            Visibility::Private => Some(module.to_vec()),
+        }
+    }
+}
+
+/// Render a scope as the equivalent visibility
+fn scope_label(scope: &Option<Vec<String>>) -> String {
+    match scope {
+        None => "pub".to_string(),
+        Some(path) if path.len() == 1 => "pub(crate)".to_string(),
+        Some(path) => format!("pub(in {})", path.join("::")),
+    }
+}
+
+/// The enclosing declaration that narrows an item's reachability the most
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Limit {
+    /// `item` or the module path whose declaration limits reachability
+    pub by: String,
+    pub declared: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Declared and effective visibility of one item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisibilityReport {
+    pub name: String,
+    pub kind: String,
+    pub file: String,
+    /// 1-based line of the declaration
+    pub line: usize,
+    pub module_path: String,
+    pub declared: String,
+    /// Reachability after walking the enclosing modules, e.g. `pub(crate)`
+    pub effective: String,
+    /// Whether other crates can name the item, directly or via a `pub use`
+    pub externally_reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limited_by: Option<Limit>,
+    /// Public paths that re-export the item
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reexported_as: Vec<String>,
+    /// Declared `pub` but not reachable from outside the crate
+    pub pub_but_unreachable: bool,
+}
+
+/// Find the item declared on 0-based `line` (or the nearest declaration above
+/// it, for positions inside a multi-line header) and report its visibility
+pub fn item_visibility(project_root: &Path, file: &Path, source: &str, line: usize) -> Option<VisibilityReport> {
+    let lines: Vec<&str> = source.lines().collect();
+    let decl_line = (0..=line.min(lines.len().saturating_sub(1))).rev()
+        .take(5)
+        .find(|&i| parse_item(lines[i]).is_some())?;
+    let (declared, kind, name) = parse_item(lines[decl_line])?;
+
+    let src = project_root.join("src");
+    let files = source_files::rust_files(&src);
+    let modules: HashMap<Vec<String>, PathBuf> = files.iter()
+        .filter_map(|f| reexports::module_path_for(&src, f).map(|m| (m, f.clone())))
+        .collect();
+    let display = |p: &Path| p.strip_prefix(project_root).unwrap_or(p).display().to_string();
+
+    let mut module = reexports::module_path_for(&src, file).unwrap_or_else(|| vec!["crate".to_string()]);
+    // Inline `mod name { ... }` blocks enclosing the item
+    let inline = inline_modules(&lines, decl_line);
+    module.extend(inline.iter().map(|m| m.name.clone()));
+
+    let mut narrowest: (Option<Vec<String>>, Option<Limit>) = (declared.scope(&module), None);
+    if narrowest.0.is_some() {
+        narrowest.1 = Some(Limit { by: "item".to_string(), declared: declared.as_str(), file: display(file), line: decl_line + 1 });
+    }
+
+    let mut consider = |scope: Option<Vec<String>>, limit: Limit| {
+        let narrower = match (&scope, &narrowest.0) {
+            (Some(_), None) => true,
+            (Some(new), Some(old)) => new.len() > old.len(),
+            _ => false,
+        };
+        if narrower {
+            narrowest = (scope, Some(limit));
+        }
+    };
+
+    // Inline modules, innermost last
+    let file_module_len = module.len() - inline.len();
+    for (i, inline_module) in inline.iter().enumerate() {
+        let parent = &module[..file_module_len + i];
+        consider(inline_module.visibility.scope(parent), Limit {
+            by: module[..file_module_len + i + 1].join("::"),
+            declared: inline_module.visibility.as_str(),
+            file: display(file),
+            line: inline_module.line + 1,
+        });
+    }
+
+    // File modules, from the crate root down
+    for depth in 2..=file_module_len {
+        let path = &module[..depth];
+        let parent = &module[..depth - 1];
+        let Some((visibility, decl_file, decl_line)) = module_declaration(&modules, parent, &path[depth - 1]) else { continue };
+        consider(visibility.scope(parent), Limit {
+            by: path.join("::"),
+            declared: visibility.as_str(),
+            file: display(&decl_file),
+            line: decl_line,
+        });
+    }
+
+    let (mut scope, limited_by) = narrowest;
+    // A binary crate has no external users
+    let is_library = src.join("lib.rs").exists();
+    if !is_library && scope.is_none() {
+        scope = Some(vec!["crate".to_string()]);
+    }
+
+    let file_display = display(file);
+    let reexported_as: Vec<String> = reexports::find_reexports(project_root).into_iter()
+        .filter(|r| r.definition.as_ref().is_some_and(|d| d.file == file_display && d.line == decl_line + 1))
+        .map(|r| r.exported)
+        .collect();
+    let reexport_public = is_library && reexported_as.iter().any(|path| {
+        let segments: Vec<String> = path.split("::").map(String::from).collect();
+        module_chain_public(&modules, &segments[..segments.len() - 1])
+    });
+
+    let externally_reachable = scope.is_none() || reexport_public;
+    Some(VisibilityReport {
+        name,
+        kind: kind.to_string(),
+        file: file_display,
+        line: decl_line + 1,
+        module_path: module.join("::"),
+        declared: declared.as_str(),
+        effective: if reexport_public { "pub".to_string() } else { scope_label(&scope) },
+        externally_reachable,
+        limited_by: if externally_reachable { None } else { limited_by },
+        reexported_as,
+        pub_but_unreachable: declared == Visibility::Public && !externally_reachable,
+    })
+}
+
+/// Parse an item declaration: (visibility, kind, name)
+pub fn parse_item(line: &str) -> Option<(Visibility, &'static str, String)> {
+    let code = line.split("//").next().unwrap_or("");
+    let (visibility, mut rest) = Visibility::parse(code);
+    for modifier in ["async ", "const ", "unsafe ", "extern \"C\" ", "extern "] {
+        // `const fn` is a function, `const X` a constant
+        if modifier == "const " && !rest.trim_start_matches("const ").trim_start().starts_with("fn ") {
+            continue;
+        }
+        rest = rest.strip_prefix(modifier).unwrap_or(rest).trim_start();
+    }
+    for (keyword, kind) in ITEM_KEYWORDS {
+        let Some(after) = rest.strip_prefix(keyword) else { continue };
+        if !(after.starts_with(char::is_whitespace) || *kind == "macro") {
+            continue;
+        }
+        let name: String = after.trim_start().chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        if !name.is_empty() {
+            return Some((visibility, kind, name));
+        }
+    }
+    None
+}
+
+struct InlineModule {
+    name: String,
+    visibility: Visibility,
+    line: usize,
+}
+
+/// Inline modules whose bodies contain 0-based `line`, outermost first
+fn inline_modules(lines: &[&str], line: usize) -> Vec<InlineModule> {
+    let mut stack: Vec<(i32, InlineModule)> = Vec::new();
+    let mut depth = 0i32;
+
+    for (i, text) in lines.iter().enumerate().take(line) {
+        let code = text.split("//").next().unwrap_or("");
+        if let Some((visibility, "module", name)) = parse_item(code) {
+            if code.contains('{') {
+                stack.push((depth, InlineModule { name, visibility, line: i }));
+            }
+        }
+        for c in code.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    while stack.last().is_some_and(|(open, _)| *open >= depth) {
+                        stack.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    stack.into_iter().map(|(_, m)| m).collect()
+}
+
+/// Visibility, file and 1-based line of `mod name;` inside the module `parent`
+fn module_declaration(modules: &HashMap<Vec<String>, PathBuf>, parent: &[String], name: &str) -> Option<(Visibility, PathBuf, usize)> {
+    let file = modules.get(parent)?;
+    let source = std::fs::read_to_string(file).ok()?;
+    source.lines().enumerate().find_map(|(i, line)| match parse_item(line) {
+        Some((visibility, "module", found)) if found == name => Some((visibility, file.clone(), i + 1)),
+        _ => None,
+    })
+}
+
+/// Whether every module along `path` is declared `pub`
+fn module_chain_public(modules: &HashMap<Vec<String>, PathBuf>, path: &[String]) -> bool {
+    (2..=path.len()).all(|depth| {
+        module_declaration(modules, &path[..depth - 1], &path[depth - 1])
+            .is_some_and(|(visibility, _, _)| visibility == Visibility::Public)
+    })
+}
@@ -0,0 +1,247 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// What an applied `WorkspaceEdit` did to the file system
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EditSummary {
+    pub files_changed: Vec<String>,
+    pub files_created: Vec<String>,
+    pub files_renamed: Vec<(String, String)>,
+    pub files_deleted: Vec<String>,
+    pub edits_applied: usize,
+}
+
+/// Apply an LSP `WorkspaceEdit` to disk.
+///
+/// `documentChanges` takes precedence over `changes` (as the LSP spec requires)
+/// and its entries are applied strictly in order, so a `CreateFile` runs before
+/// the text edits that populate the new file and a `RenameFile` before edits
+/// addressed to the new name.
+pub async fn apply_workspace_edit(edit: &Value) -> Result<EditSummary> {
+    let mut summary = EditSummary::default();
+
+    if let Some(document_changes) = edit.get("documentChanges").and_then(|d| d.as_array()) {
+        for change in document_changes {
+            match change.get("kind").and_then(|k| k.as_str()) {
+                Some("create") => create_file(change, &mut summary).await?,
+                Some("rename") => rename_file(change, &mut summary).await?,
+                Some("delete") => delete_file(change, &mut summary).await?,
+                _ => {
+                    let uri = change.get("textDocument")
+                        .and_then(|d| d.get("uri"))
+                        .and_then(|u| u.as_str())
+                        .context("TextDocumentEdit is missing textDocument.uri")?;
+                    let edits = change.get("edits")
+                        .and_then(|e| e.as_array())
+                        .cloned()
+                        .unwrap_or_default();
+                    apply_text_edits(uri, &edits, &mut summary).await?;
+                }
+            }
+        }
+    } else if let Some(changes) = edit.get("changes").and_then(|c| c.as_object()) {
+        for (uri, edits) in changes {
+            let edits = edits.as_array().cloned().unwrap_or_default();
+            apply_text_edits(uri, &edits, &mut summary).await?;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Convert a `file://` URI into a local path, decoding percent escapes
+pub fn uri_to_path(uri: &str) -> PathBuf {
+    let raw = uri.strip_prefix("file://").unwrap_or(uri);
+    let bytes = raw.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            // Digits are read as bytes, so a multibyte character after `%` stays as it is
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    PathBuf::from(String::from_utf8_lossy(&decoded).to_string())
+}
+
+async fn create_file(change: &Value, summary: &mut EditSummary) -> Result<()> {
+    let uri = change.get("uri").and_then(|u| u.as_str()).context("CreateFile is missing uri")?;
+    let path = uri_to_path(uri);
+    let overwrite = option_flag(change, "overwrite");
+    let ignore_if_exists = option_flag(change, "ignoreIfExists");
+
+    if path.exists() && !overwrite {
+        if ignore_if_exists {
+            return Ok(());
+        }
+        anyhow::bail!("Cannot create {}: file already exists", path.display());
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, "").await
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+
+    debug!("Created file {}", path.display());
+    summary.files_created.push(path.display().to_string());
+    Ok(())
+}
+
+async fn rename_file(change: &Value, summary: &mut EditSummary) -> Result<()> {
+    let old_uri = change.get("oldUri").and_then(|u| u.as_str()).context("RenameFile is missing oldUri")?;
+    let new_uri = change.get("newUri").and_then(|u| u.as_str()).context("RenameFile is missing newUri")?;
+    let old_path = uri_to_path(old_uri);
+    let new_path = uri_to_path(new_uri);
+    let overwrite = option_flag(change, "overwrite");
+    let ignore_if_exists = option_flag(change, "ignoreIfExists");
+
+    if new_path.exists() && !overwrite {
+        if ignore_if_exists {
+            return Ok(());
+        }
+        anyhow::bail!("Cannot rename to {}: file already exists", new_path.display());
+    }
+
+    if let Some(parent) = new_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::rename(&old_path, &new_path).await
+        .with_context(|| format!("Failed to rename {} to {}", old_path.display(), new_path.display()))?;
+
+    debug!("Renamed {} to {}", old_path.display(), new_path.display());
+    summary.files_renamed.push((old_path.display().to_string(), new_path.display().to_string()));
+    Ok(())
+}
+
+async fn delete_file(change: &Value, summary: &mut EditSummary) -> Result<()> {
+    let uri = change.get("uri").and_then(|u| u.as_str()).context("DeleteFile is missing uri")?;
+    let path = uri_to_path(uri);
+
+    if !path.exists() {
+        if option_flag(change, "ignoreIfNotExists") {
+            return Ok(());
+        }
+        anyhow::bail!("Cannot delete {}: file does not exist", path.display());
+    }
+
+    if path.is_dir() {
+        if option_flag(change, "recursive") {
+            tokio::fs::remove_dir_all(&path).await?;
+        } else {
+            tokio::fs::remove_dir(&path).await?;
+        }
+    } else {
+        tokio::fs::remove_file(&path).await?;
+    }
+
+    debug!("Deleted {}", path.display());
+    summary.files_deleted.push(path.display().to_string());
+    Ok(())
+}
+
+async fn apply_text_edits(uri: &str, edits: &[Value], summary: &mut EditSummary) -> Result<()> {
+    if edits.is_empty() {
+        return Ok(());
+    }
+
+    let path = uri_to_path(uri);
+    let content = tokio::fs::read_to_string(&path).await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let updated = apply_edits_to_text(&content, edits)?;
+    tokio::fs::write(&path, updated).await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    summary.edits_applied += edits.len();
+    let display = path.display().to_string();
+    if !summary.files_changed.contains(&display) {
+        summary.files_changed.push(display);
+    }
+    Ok(())
+}
+
+/// Apply LSP `TextEdit`s to a string.
+///
+/// All ranges are resolved to byte offsets against `content` as it is now,
+/// before any edit is applied, matching the LSP rule that every edit in a set
+/// refers to the original document. Edits are then applied in descending start
+/// order so earlier edits don't shift the offsets of later ones. Inserts at the
+/// same position keep their original relative order; overlapping ranges are rejected.
+pub fn apply_edits_to_text(content: &str, edits: &[Value]) -> Result<String> {
+    let line_starts = line_starts(content);
+
+    let mut resolved = Vec::with_capacity(edits.len());
+    for (index, edit) in edits.iter().enumerate() {
+        let range = edit.get("range").context("TextEdit is missing range")?;
+        let start = position_to_offset(content, &line_starts, range.get("start").context("Range is missing start")?)?;
+        let end = position_to_offset(content, &line_starts, range.get("end").context("Range is missing end")?)?;
+        if end < start {
+            anyhow::bail!("TextEdit range ends before it starts");
+        }
+        let new_text = edit.get("newText").and_then(|t| t.as_str()).unwrap_or("");
+        resolved.push((start, end, index, new_text));
+    }
+
+    // Ascending by position (array order breaks ties) to check for overlaps
+    resolved.sort_by_key(|&(start, end, index, _)| (start, end, index));
+    for pair in resolved.windows(2) {
+        if pair[1].0 < pair[0].1 {
+            anyhow::bail!("Overlapping TextEdits at byte offsets {}..{} and {}..{}", pair[0].0, pair[0].1, pair[1].0, pair[1].1);
+        }
+    }
+
+    let mut result = content.to_string();
+    for (start, end, _, new_text) in resolved.into_iter().rev() {
+        result.replace_range(start..end, new_text);
+    }
+    Ok(result)
+}
+
+/// Byte offset at which each line of `content` begins
+fn line_starts(content: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// Convert an LSP `{line, character}` position into a byte offset in `content`.
+/// `character` counts UTF-16 code units, the LSP default position encoding.
+/// Characters past the end of a line clamp to the line end, and lines past the
+/// end of the document clamp to the end of the document.
+fn position_to_offset(content: &str, line_starts: &[usize], position: &Value) -> Result<usize> {
+    let line = position.get("line").and_then(|l| l.as_u64()).context("Position is missing line")? as usize;
+    let character = position.get("character").and_then(|c| c.as_u64()).context("Position is missing character")? as usize;
+
+    let Some(&line_start) = line_starts.get(line) else {
+        return Ok(content.len());
+    };
+    let line_end = line_starts.get(line + 1).copied().unwrap_or(content.len());
+    let text = content[line_start..line_end].trim_end_matches('\n').trim_end_matches('\r');
+
+    let mut units = 0;
+    for (idx, c) in text.char_indices() {
+        if units >= character {
+            return Ok(line_start + idx);
+        }
+        units += c.len_utf16();
+    }
+    Ok(line_start + text.len())
+}
+
+fn option_flag(change: &Value, name: &str) -> bool {
+    change.get("options")
+        .and_then(|o| o.get(name))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
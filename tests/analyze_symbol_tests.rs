@@ -0,0 +1,119 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_analyze_symbol_returns_partial_results_past_the_budget() {
+    let dir = ScratchDir::with_crate("analyze-symbol", "");
+    dir.write("src/lib.rs", "mod nested;\n\npub struct Widget;\n");
+    dir.write("src/nested/mod.rs", "use crate::Widget;\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "analyze_symbol", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()["result"].clone() }
+    };
+
+    let result = call(json!({"name": "Widget"})).await;
+    assert_eq!(result["partial"], false, "{result}");
+    assert_eq!(result["sources"], json!({"file_content_search": "complete", "lsp_workspace_symbols": "unavailable"}));
+    let files: Vec<&str> = result["locations"].as_array().unwrap().iter().map(|l| l["file"].as_str().unwrap()).collect();
+    assert_eq!(files, vec!["src/lib.rs", "src/nested/mod.rs"]);
+
+    let result = call(json!({"name": "Widget", "timeout_ms": 0})).await;
+    assert_eq!(result["partial"], true, "{result}");
+    assert_eq!(result["sources"]["file_content_search"], "timed_out");
+    assert_eq!(result["occurrences"], 0);
+    assert_eq!(result["analysis"]["status"], "partial_analysis");
+}
+
+#[tokio::test]
+async fn test_analyze_symbol_paths_are_relative_to_the_project_root() {
+    let dir = ScratchDir::with_crate("analyze-symbol-paths", "");
+    dir.write("src/lib.rs", "mod a;\n");
+    dir.write("src/a/mod.rs", "mod b;\n");
+    dir.write("src/a/b/mod.rs", "mod c;\n");
+    dir.write("src/a/b/c.rs", "pub fn deeply_nested() {}\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "analyze_symbol", "params": {"name": "deeply_nested"} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let locations = response["result"]["locations"].as_array().unwrap();
+    assert_eq!(locations.len(), 1, "{response}");
+    assert_eq!(locations[0]["file"], "src/a/b/c.rs");
+    assert_eq!(locations[0]["line"], 1);
+}
+
+#[tokio::test]
+async fn test_analyze_symbol_matches_whole_identifiers_and_finds_definitions() {
+    let dir = ScratchDir::with_crate("analyze-symbol-words", "");
+    std::fs::write(dir.join("src/lib.rs"), concat!(
+        "pub struct Widget;\n",
+        "impl Widget {\n",
+        "    // new widgets are renewable\n",
+        "    pub fn new() -> Self { Widget }\n",
+        "    pub fn new_name(&self) -> &str { \"new\" }\n",
+        "}\n",
+        "pub fn make() -> Widget { Widget::new() }\n",
+    )).unwrap();
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "analyze_symbol", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()["result"].clone() }
+    };
+    let hits = |result: &Value| -> Vec<(u64, String)> {
+        result["locations"].as_array().unwrap().iter()
+            .map(|l| (l["line"].as_u64().unwrap(), l["context"].as_str().unwrap().to_string()))
+            .collect()
+    };
+
+    let result = call(json!({"name": "new"})).await;
+    assert_eq!(hits(&result), vec![(4, "fn definition".to_string()), (7, "usage".to_string())]);
+    assert_eq!(result["analysis"]["type"], "Function");
+
+    let result = call(json!({"name": "Widget"})).await;
+    assert_eq!(result["analysis"]["type"], "Struct");
+    assert_eq!(hits(&result)[0], (1, "struct definition".to_string()));
+
+    let result = call(json!({"name": "NEW", "whole_word": false, "case_sensitive": false})).await;
+    assert_eq!(result["occurrences"], 3);
+}
+
+#[tokio::test]
+async fn test_analyze_symbol_pages_through_every_match() {
+    let dir = ScratchDir::with_crate("analyze-symbol-pages", "");
+    dir.write("src/lib.rs", "mod a;\npub fn tick() {}\n");
+    let calls: String = (0..60).map(|_| "    crate::tick();\n").collect();
+    dir.write("src/a/mod.rs", &format!("fn run() {{\n{}}}\n", calls));
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "analyze_symbol", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()["result"].clone() }
+    };
+
+    let first = call(json!({"name": "tick"})).await;
+    assert_eq!(first["total_matched"], 61);
+    assert_eq!(first["locations"].as_array().unwrap().len(), 50);
+    assert_eq!(first["truncated"], true);
+    assert_eq!(first["analysis"]["type"], "Function");
+
+    let rest = call(json!({"name": "tick", "offset": 50})).await;
+    let locations = rest["locations"].as_array().unwrap();
+    assert_eq!(locations.len(), 11);
+    assert_eq!(rest["truncated"], false);
+    assert_eq!((locations[10]["file"].as_str(), locations[10]["line"].as_u64()), (Some("src/lib.rs"), Some(2)));
+
+    let page = call(json!({"name": "tick", "limit": 5, "offset": 10})).await;
+    assert_eq!(page["locations"][0]["line"], 12);
+    assert_eq!(page["locations"].as_array().unwrap().len(), 5);
+    assert_eq!(page["truncated"], true);
+}
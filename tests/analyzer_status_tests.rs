@@ -0,0 +1,54 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::analyzer_status::{parse_memory_usage, parse_size, MemoryEntry};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("512b"), Some(512));
+    assert_eq!(parse_size("2kb"), Some(2048));
+    assert_eq!(parse_size("1.5mb"), Some(1_572_864));
+    assert_eq!(parse_size("1gb"), Some(1 << 30));
+    assert_eq!(parse_size("12"), None);
+    assert_eq!(parse_size("3xb"), None);
+}
+
+#[test]
+fn test_parse_memory_usage() {
+    let text = "\
+  1.50mb   1204 FileTextQuery
+    12kb     30 CrateGraphQuery
+ 210.0mb        Remaining
+not a size line
+";
+    let memory = parse_memory_usage(text);
+    assert_eq!(memory, vec![
+        MemoryEntry { name: "Remaining".to_string(), size: "210.0mb".to_string(), bytes: 220_200_960, entries: None },
+        MemoryEntry { name: "FileTextQuery".to_string(), size: "1.50mb".to_string(), bytes: 1_572_864, entries: Some(1204) },
+        MemoryEntry { name: "CrateGraphQuery".to_string(), size: "12kb".to_string(), bytes: 12_288, entries: Some(30) },
+    ]);
+
+    // Older servers print no entry counts
+    let memory = parse_memory_usage("4kb ParseQuery\n");
+    assert_eq!((memory[0].name.as_str(), memory[0].entries), ("ParseQuery", None));
+}
+
+#[tokio::test]
+async fn test_analyzer_status_without_lsp() {
+    let dir = ScratchDir::with_crate("analyzer-status", "\n[workspace]\n");
+    dir.write("src/lib.rs", "");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "analyzer_status", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+
+    assert_eq!(result["lsp_available"], false);
+    assert!(result.get("status").is_none());
+    assert_eq!(result["total_bytes"], 0);
+    assert!(result["errors"][0].as_str().unwrap().contains("use_lsp = false"));
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::api_surface::analyze;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::metrics::MetricsAnalyzer;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_api_surface_flags_large_public_surfaces() {
+    let dir = ScratchDir::with_crate("api-surface", "");
+    dir.write_all(&[
+        ("src/lib.rs", "pub mod open;\npub mod net;\nmod tiny;\n"),
+        ("src/open.rs", "pub struct A;\npub struct B;\npub fn c() {}\npub fn d() {}\npub(crate) fn e() {}\nimpl A {}\n"),
+        ("src/net/mod.rs", "pub struct Client;\nfn connect() {}\nfn retry() {}\nconst LIMIT: u32 = 3;\npub(crate) struct Pool;\n"),
+        ("src/tiny.rs", "pub fn only() {}\n"),
+    ]);
+
+    let report = analyze(&dir, 0.75, 5);
+    let modules: Vec<(&str, usize, usize, f64, bool)> = report.modules.iter()
+        .map(|m| (m.info.name.as_str(), m.info.public_items, m.info.private_items, m.public_ratio, m.review))
+        .collect();
+    assert_eq!(modules, vec![
+        ("crate::open", 4, 1, 0.8, true),
+        ("crate", 2, 1, 0.67, false),
+        ("crate::net", 1, 4, 0.2, false),
+        // All public, but too small to say anything
+        ("crate::tiny", 1, 0, 1.0, false),
+    ]);
+    assert_eq!(report.review_candidates, vec!["crate::open"]);
+    assert_eq!((report.totals.public, report.totals.private, report.public_ratio), (8, 6, 0.57));
+    assert!(analyze(&dir, 0.9, 5).review_candidates.is_empty());
+
+    let structure = MetricsAnalyzer::new().analyze_project(&dir).unwrap();
+    assert_eq!(structure.modules.len(), 4);
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "api_surface", "params": {"min_items": 1} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["review_candidates"], json!(["crate::open", "crate::tiny"]), "{result}");
+    assert_eq!(result["modules"][2]["path"], "src/net/mod.rs");
+}
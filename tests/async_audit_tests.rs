@@ -0,0 +1,94 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::async_audit::{audit, scan_source};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const SOURCE: &str = r#"
+use std::fs;
+use std::sync::{Arc, Mutex};
+use tokio::fs as async_fs;
+
+pub async fn load(path: &str) -> String {
+    let text = fs::read_to_string(path).unwrap();
+    let other = async_fs::read_to_string(path).await.unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    text + &other
+}
+
+async fn count(state: Arc<Mutex<u32>>) {
+    *state.lock().unwrap() += 1;
+    let n = tokio::task::spawn_blocking(|| fs::read("a").unwrap().len()).await.unwrap();
+    tokio::task::spawn_blocking(move || {
+        std::fs::write("b", "x").unwrap();
+    });
+    let _ = n;
+}
+
+fn sync_load(path: &str) -> String {
+    let handle = async move {
+        reqwest::blocking::get("http://example.com").unwrap();
+    };
+    drop(handle);
+    fs::read_to_string(path).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    async fn helper() {
+        std::fs::read("x").unwrap();
+    }
+}
+"#;
+
+#[test]
+fn test_scan_source() {
+    let (functions, findings) = scan_source(SOURCE, false);
+    let names: Vec<(&str, usize, usize)> = functions.iter().map(|f| (f.name.as_str(), f.line, f.blocking_calls)).collect();
+    assert_eq!(names, vec![("load", 6, 2), ("count", 13, 1)]);
+
+    let found: Vec<(usize, &str, &str, &str)> = findings.iter()
+        .map(|f| (f.line, f.function.as_str(), f.category.as_str(), f.call.as_str()))
+        .collect();
+    assert_eq!(found, vec![
+        (7, "load", "fs", "fs::read_to_string"),
+        (9, "load", "sleep", "std::thread::sleep"),
+        (14, "count", "mutex", "lock"),
+        (24, "async block in sync_load", "http", "reqwest::blocking::get"),
+    ]);
+    assert_eq!(findings[0].column, 16);
+    assert_eq!(findings[0].suggestion, "use tokio::fs or move the call into spawn_blocking");
+
+    let (functions, findings) = scan_source(SOURCE, true);
+    assert_eq!(functions.len(), 3);
+    assert_eq!(findings.last().unwrap().function, "helper");
+}
+
+#[test]
+fn test_tokio_mutex_is_not_flagged() {
+    let source = "use tokio::sync::Mutex;\n\nasync fn f(m: &Mutex<u8>) {\n    let mut guard = m.lock().await;\n    *guard += 1;\n}\n";
+    let (functions, findings) = scan_source(source, false);
+    assert_eq!(functions.len(), 1);
+    assert!(findings.is_empty(), "{:?}", findings);
+}
+
+#[tokio::test]
+async fn test_async_audit_command() {
+    let dir = ScratchDir::with_crate("async-audit", "\n[workspace]\n");
+    dir.write("src/lib.rs", SOURCE);
+    let report = audit(&dir, &dir.join("src"), false);
+    assert_eq!(report.files_scanned, 1);
+    assert_eq!(report.total, 4);
+    assert_eq!(report.by_category["fs"], 1);
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "async_audit", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["total"], 4);
+    assert_eq!(result["findings"][0]["file"], "src/lib.rs");
+    assert_eq!(result["async_functions"][0]["name"], "load");
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::attributes::{attributes, item_attributes};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const SOURCE: &str = r##"#![allow(dead_code)]
+
+use serde::{Deserialize, Serialize};
+
+/// A config entry
+#[derive(
+    Debug,
+    Clone, // cloned per request
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)] #[cfg(feature = "config")]
+pub struct Entry {
+    #[serde(default = "default_name", rename = "]name[")]
+    pub name: String,
+}
+
+// #[not_an_attribute]
+const MARKER: &str = "#[not_an_attribute]";
+
+#[tokio::main]
+async fn main() {
+    let c = '[';
+    let r = r#"#[nor_this]"#;
+}
+"##;
+
+#[test]
+fn test_attributes_found_in_code_only() {
+    let all = attributes(SOURCE);
+    let found: Vec<(&str, bool, usize, usize)> = all.iter()
+        .map(|a| (a.path.as_str(), a.inner, a.line, a.end_line))
+        .collect();
+    assert_eq!(found, vec![
+        ("allow", true, 1, 1),
+        ("derive", false, 6, 11),
+        ("serde", false, 12, 12),
+        ("cfg", false, 12, 12),
+        ("serde", false, 14, 14),
+        ("tokio::main", false, 21, 21),
+    ]);
+    assert_eq!(all[4].text, "#[serde(default = \"default_name\", rename = \"]name[\")]");
+}
+
+#[test]
+fn test_item_attributes_from_any_line_of_the_item() {
+    let entry = item_attributes(SOURCE, 13).unwrap();
+    assert_eq!(entry.kind.as_deref(), Some("struct"));
+    assert_eq!(entry.name.as_deref(), Some("Entry"));
+    assert_eq!(entry.line, 13);
+    let paths: Vec<&str> = entry.attributes.iter().map(|a| a.path.as_str()).collect();
+    assert_eq!(paths, vec!["derive", "serde", "cfg"]);
+    assert!(entry.attributes[0].text.contains("Clone, // cloned per request\n"));
+
+    // From the doc comment or inside the multi-line derive
+    assert_eq!(item_attributes(SOURCE, 5).unwrap(), entry);
+    assert_eq!(item_attributes(SOURCE, 8).unwrap(), entry);
+
+    // A field, and an item without attributes
+    let field = item_attributes(SOURCE, 14).unwrap();
+    assert_eq!(field.kind, None);
+    assert_eq!(field.declaration, "pub name: String,");
+    assert_eq!(field.attributes.len(), 1);
+    assert!(item_attributes(SOURCE, 19).unwrap().attributes.is_empty());
+
+    assert_eq!(item_attributes(SOURCE, 21).unwrap().name.as_deref(), Some("main"));
+    assert!(item_attributes(SOURCE, 16).is_none());
+}
+
+#[tokio::test]
+async fn test_item_attributes_command() {
+    let dir = ScratchDir::with_crate("item-attributes", "");
+    dir.write("src/lib.rs", SOURCE);
+    dir.write("src/other.rs", "#[derive(Debug)]\npub struct Other;\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "item_attributes",
+        "params": {"file": "src/lib.rs", "line": 21}
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["file"], "src/lib.rs");
+    assert_eq!(result["kind"], "function");
+    assert_eq!(result["attributes"][0]["text"], "#[tokio::main]");
+
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "item_attributes", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["files_scanned"], 2);
+    assert_eq!(result["total"], 7);
+    assert_eq!(result["attributes"][0], json!({"path": "derive", "count": 2, "files": 2}));
+    assert_eq!(result["attributes"][1], json!({"path": "serde", "count": 2, "files": 1}));
+}
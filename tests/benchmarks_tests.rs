@@ -0,0 +1,82 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::benchmarks::{listed, parse_output};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const LIBTEST: &str = "
+running 2 tests
+test bench_parse  ... bench:       1,234 ns/iter (+/- 56)
+test bench_render ... bench:          87.50 ns/iter (+/- 3.25)
+
+test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out
+";
+
+const CRITERION: &str = "Benchmarking fib 20
+fib 20                  time:   [26.029 µs 26.251 µs 26.505 µs]
+                        change: [-1.9141% -0.2312% +1.4939%] (p = 0.84 > 0.05)
+                        No change in performance detected.
+parsing/a_rather_long_benchmark_name
+                        time:   [1.2000 ms 1.2500 ms 1.3000 ms]
+                        thrpt:  [768.00 MiB/s 800.00 MiB/s 833.33 MiB/s]
+                 change:
+                        time:   [+8.0000% +10.000% +12.000%] (p = 0.00 < 0.05)
+                        thrpt:  [-10.714% -9.0909% -7.4074%]
+                        Performance has regressed.
+";
+
+#[test]
+fn test_parse_libtest() {
+    let (harness, results) = parse_output(LIBTEST);
+    assert_eq!(harness, "libtest");
+    assert_eq!(results.len(), 2);
+    assert_eq!((results[0].name.as_str(), results[0].ns, results[0].deviation_ns), ("bench_parse", 1234.0, Some(56.0)));
+    assert_eq!((results[1].ns, results[1].deviation_ns), (87.5, Some(3.25)));
+    assert_eq!(results[1].low_ns, None);
+}
+
+#[test]
+fn test_parse_criterion() {
+    let (harness, results) = parse_output(CRITERION);
+    assert_eq!(harness, "criterion");
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].name, "fib 20");
+    assert!((results[0].ns - 26_251.0).abs() < 1e-6);
+    assert_eq!(results[0].change_percent, Some(-0.2312));
+    assert_eq!(results[0].verdict.as_deref(), Some("no_change"));
+    assert_eq!(results[1].name, "parsing/a_rather_long_benchmark_name");
+    assert_eq!((results[1].low_ns, results[1].high_ns), (Some(1_200_000.0), Some(1_300_000.0)));
+    assert_eq!(results[1].change_percent, Some(10.0));
+    assert_eq!(results[1].verdict.as_deref(), Some("regressed"));
+
+    assert_eq!(parse_output("Compiling scratch v0.1.0\n").0, "unknown");
+    assert_eq!(listed("fib 20: benchmark\nparse: benchmark\n\n2 benchmarks\n"), vec!["fib 20", "parse"]);
+}
+
+#[tokio::test]
+async fn test_run_benchmark_command() {
+    let dir = ScratchDir::with_crate("run-benchmark", "\n[workspace]\n\n[[bench]]\nname = \"speed\"\nharness = false\n");
+    dir.write("src/lib.rs", "");
+    // Prints what criterion would, without depending on it
+    dir.write(
+        "benches/speed.rs",
+        "fn main() {\n    let filter = std::env::args().skip(1).find(|a| !a.starts_with('-')).unwrap_or_default();\n    for (name, time) in [(\"fast\", \"[1.0000 ns 1.1000 ns 1.2000 ns]\"), (\"slow\", \"[2.0000 µs 2.5000 µs 3.0000 µs]\")] {\n        if name.contains(&filter) {\n            println!(\"{:<24}time:   {}\", name, time);\n        }\n    }\n}\n",
+    );
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "run_benchmark",
+        "params": {"name": "slow", "bench": "speed", "timeout_secs": 120}
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["success"], true, "{result}");
+    assert_eq!(result["command"], "cargo bench --bench speed -- slow");
+    assert_eq!(result["harness"], "criterion");
+    assert_eq!(result["benchmarks"].as_array().unwrap().len(), 1);
+    assert_eq!(result["benchmarks"][0]["name"], "slow");
+    assert_eq!(result["benchmarks"][0]["ns"], 2500.0);
+}
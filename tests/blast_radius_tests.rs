@@ -0,0 +1,86 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::blast_radius::{is_definition, text_search};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+const LIB: &str = r#"pub struct Settings {
+    pub port: u16,
+}
+
+pub fn load() -> Settings {
+    // Settings in a comment is no use
+    Settings { port: 1 }
+}
+
+pub fn start() -> u16 {
+    load().port
+}
+
+pub fn run() {
+    start();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads() {
+        assert_eq!(load().port, 1);
+    }
+}
+"#;
+
+fn names(radius: &Value) -> Vec<(String, u64)> {
+    radius["functions"].as_array().unwrap().iter()
+        .map(|f| (f["name"].as_str().unwrap().to_string(), f["depth"].as_u64().unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_text_search_expands_callers_up_to_depth() {
+    let root = Path::new("/w");
+    let files = vec![(PathBuf::from("/w/src/lib.rs"), LIB.to_string())];
+    assert!(is_definition(LIB, 1, 12));
+    assert!(!is_definition(LIB, 5, 18));
+
+    let radius = serde_json::to_value(text_search(root, &files, "Settings", 2)).unwrap();
+    assert_eq!(radius["method"], "text_search");
+    assert_eq!(radius["references"], 2);
+    assert_eq!(names(&radius), vec![("load".to_string(), 1), ("start".to_string(), 2)]);
+    assert_eq!(radius["tests"][0]["name"], "loads");
+    assert_eq!(radius["tests"][0]["via"], json!(["load"]));
+    assert_eq!(radius["files"], json!(["src/lib.rs"]));
+    assert_eq!((radius["function_count"].as_u64(), radius["test_count"].as_u64()), (Some(2), Some(1)));
+    assert_eq!(radius["risk"], "low");
+    assert_eq!(radius["truncated"], true);
+
+    let radius = serde_json::to_value(text_search(root, &files, "Settings", 3)).unwrap();
+    assert_eq!(names(&radius).last(), Some(&("run".to_string(), 3)));
+    assert_eq!(radius["truncated"], false);
+}
+
+#[tokio::test]
+async fn test_blast_radius_command() {
+    let dir = ScratchDir::with_crate("blast-radius", "");
+    dir.write("src/lib.rs", LIB);
+    dir.write("src/main.rs", "fn main() {\n    scratch::run();\n}\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "blast_radius",
+        "params": {"file": "src/lib.rs", "line": 10, "column": 8}
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["symbol"], "start");
+    assert_eq!(names(result), vec![("run".to_string(), 1), ("main".to_string(), 2)]);
+    assert_eq!(result["files"], json!(["src/lib.rs", "src/main.rs"]));
+    assert_eq!(result["risk"], "medium");
+    assert_eq!(result["test_count"], 0);
+}
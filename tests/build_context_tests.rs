@@ -0,0 +1,62 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::build_context::{config_target, configured_target, dev_profile, parse_rustc_version};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_parse_rustc_version() {
+    let output = "rustc 1.82.0 (f6e511eec 2024-10-15)\nbinary: rustc\ncommit-hash: f6e511eec7342f59a25f7c0534f1dbea00d01b14\ncommit-date: 2024-10-15\nhost: x86_64-unknown-linux-gnu\nrelease: 1.82.0\nLLVM version: 19.1.1\n";
+    let version = parse_rustc_version(output);
+    assert_eq!((version.release.as_str(), version.host.as_str()), ("1.82.0", "x86_64-unknown-linux-gnu"));
+    assert_eq!(version.commit_hash.as_deref(), Some("f6e511eec7342f59a25f7c0534f1dbea00d01b14"));
+    assert_eq!(version.llvm_version.as_deref(), Some("19.1.1"));
+
+    assert_eq!(parse_rustc_version("commit-hash: unknown\n").commit_hash, None);
+}
+
+#[test]
+fn test_config_target_and_dev_profile() {
+    assert_eq!(config_target("[build]\ntarget = \"wasm32-unknown-unknown\"\n").as_deref(), Some("wasm32-unknown-unknown"));
+    assert_eq!(config_target("[build]\ntarget = [\"aarch64-apple-darwin\", \"x86_64-apple-darwin\"]\n").as_deref(), Some("aarch64-apple-darwin"));
+    assert_eq!(config_target("[build]\njobs = 4\n"), None);
+
+    let default = dev_profile("[package]\nname = \"p\"\n");
+    assert_eq!((default.name.as_str(), default.debug_assertions, default.opt_level.as_str()), ("dev", true, "0"));
+    let tuned = dev_profile("[profile.dev]\ndebug-assertions = false\nopt-level = \"s\"\n");
+    assert_eq!((tuned.debug_assertions, tuned.opt_level.as_str()), (false, "s"));
+}
+
+#[test]
+fn test_configured_target_reads_parent_cargo_config() {
+    if std::env::var_os("CARGO_BUILD_TARGET").is_some() {
+        return;
+    }
+    let dir = ScratchDir::new("build-context");
+    std::fs::create_dir_all(dir.join("member")).unwrap();
+    dir.write(".cargo/config.toml", "[build]\ntarget = \"thumbv7em-none-eabihf\"\n");
+
+    let (target, source) = configured_target(&dir.join("member")).unwrap();
+    assert_eq!(target, "thumbv7em-none-eabihf");
+    assert!(source.ends_with(".cargo/config.toml"), "{source}");
+}
+
+#[tokio::test]
+async fn test_build_context_command() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config("tests/test_project", config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "build_context", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert!(!result["rustc"]["release"].as_str().unwrap_or("").is_empty(), "{response}");
+    if result["target_source"] == "host" {
+        assert_eq!(result["target"], result["rustc"]["host"]);
+    }
+    assert_eq!(result["profile"]["name"], "dev");
+    let cfg: Vec<&str> = result["cfg"].as_array().unwrap().iter().filter_map(|c| c.as_str()).collect();
+    assert!(cfg.contains(&"debug_assertions"), "{cfg:?}");
+    assert!(cfg.iter().any(|c| c.starts_with("target_os = \"")), "{cfg:?}");
+}
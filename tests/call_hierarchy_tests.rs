@@ -0,0 +1,78 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::call_hierarchy::{call_from_lsp, item_from_lsp, Direction};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::locations::Location;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::Path;
+
+fn item(name: &str, uri: &str, line: u64, kind: u64) -> Value {
+    json!({
+        "name": name,
+        "kind": kind,
+        "detail": format!("fn {}()", name),
+        "uri": uri,
+        "range": { "start": { "line": line, "character": 0 }, "end": { "line": line + 3, "character": 1 } },
+        "selectionRange": { "start": { "line": line, "character": 3 }, "end": { "line": line, "character": 3 + name.len() } }
+    })
+}
+
+fn range(line: u64, character: u64) -> Value {
+    json!({ "start": { "line": line, "character": character }, "end": { "line": line, "character": character + 5 } })
+}
+
+#[test]
+fn test_item_from_lsp() {
+    let parse = item("parse", "file:///work/demo/src/parser.rs", 9, 12);
+    let normalized = item_from_lsp(&parse, Path::new("/work/demo")).unwrap();
+    assert_eq!((normalized.name.as_str(), normalized.kind.as_str(), normalized.detail.as_deref()), ("parse", "function", Some("fn parse()")));
+    assert_eq!(normalized.location, Location { file: "src/parser.rs".to_string(), line: 10, column: 4 });
+    assert_eq!(serde_json::to_value(&normalized).unwrap(), json!({
+        "name": "parse", "kind": "function", "detail": "fn parse()", "file": "src/parser.rs", "line": 10, "column": 4
+    }));
+}
+
+#[test]
+fn test_call_sites_are_in_the_callers_file() {
+    let root = Path::new("/work/demo");
+    let parse = item("parse", "file:///work/demo/src/parser.rs", 9, 12);
+
+    let incoming = json!({ "from": item("main", "file:///work/demo/src/main.rs", 2, 12), "fromRanges": [range(4, 8), range(6, 8)] });
+    let caller = call_from_lsp(&incoming, &parse, Direction::Incoming, root).unwrap();
+    assert_eq!(caller.item.name, "main");
+    assert_eq!(caller.call_sites, vec![
+        Location { file: "src/main.rs".to_string(), line: 5, column: 9 },
+        Location { file: "src/main.rs".to_string(), line: 7, column: 9 },
+    ]);
+
+    let outgoing = json!({ "to": item("lex", "file:///work/demo/src/lexer.rs", 0, 6), "fromRanges": [range(11, 4)] });
+    let callee = call_from_lsp(&outgoing, &parse, Direction::Outgoing, root).unwrap();
+    assert_eq!((callee.item.name.as_str(), callee.item.kind.as_str(), callee.item.location.file.as_str()), ("lex", "method", "src/lexer.rs"));
+    assert_eq!(callee.call_sites, vec![Location { file: "src/parser.rs".to_string(), line: 12, column: 5 }]);
+}
+
+#[tokio::test]
+async fn test_call_hierarchy_without_rust_analyzer() {
+    let dir = ScratchDir::with_crate("call-hierarchy", "");
+    dir.write("src/lib.rs", "pub fn parse() {}\n\npub fn run() { parse(); }\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "call_hierarchy", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap() }
+    };
+
+    let result = call(json!({"file": "src/lib.rs", "line": 1, "column": 8})).await["result"].clone();
+    assert_eq!(result["direction"], "incoming", "{result}");
+    assert_eq!(result["function"], Value::Null);
+    assert_eq!(result["calls"], json!([]));
+
+    let result = call(json!({"file": "src/lib.rs", "line": 3, "column": 8, "direction": "outgoing"})).await["result"].clone();
+    assert_eq!(result["direction"], "outgoing", "{result}");
+
+    let response = call(json!({"file": "src/lib.rs", "line": 3, "column": 8, "direction": "sideways"})).await;
+    assert!(response["error"].is_object(), "{response}");
+}
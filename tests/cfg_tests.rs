@@ -0,0 +1,96 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::cfg_eval::{parse_cfg, parse_rustc_cfg, scan_cfgs, CfgContext, CfgExpr};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+fn linux_ctx(features: &[&str]) -> CfgContext {
+    CfgContext {
+        target: None,
+        features: features.iter().map(|f| f.to_string()).collect(),
+        options: parse_rustc_cfg("unix\ntarget_os=\"linux\"\ndebug_assertions\n"),
+    }
+}
+
+#[test]
+fn test_parse_nested_cfg() {
+    let expr = parse_cfg(r#"all(unix, not(feature = "serde"))"#).unwrap();
+    assert_eq!(expr, CfgExpr::All(vec![
+        CfgExpr::Option("unix".to_string(), None),
+        CfgExpr::Not(Box::new(CfgExpr::Option("feature".to_string(), Some("serde".to_string())))),
+    ]));
+
+    assert!(expr.eval(&linux_ctx(&[])));
+    assert!(!expr.eval(&linux_ctx(&["serde"])));
+}
+
+#[test]
+fn test_scan_cfgs_reports_lines_and_status() {
+    let source = r#"#[cfg(test)]
+mod tests {}
+
+#[cfg(target_os = "windows")]
+fn windows_only() {}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct Data;
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "macos"
+))]
+fn unix_like() {}
+"#;
+
+    let cfgs = scan_cfgs(source, &linux_ctx(&["serde"]));
+    let summary: Vec<(usize, &str, bool)> = cfgs.iter()
+        .map(|c| (c.line, c.kind.as_str(), c.active))
+        .collect();
+    assert_eq!(summary, vec![
+        (1, "cfg", false),
+        (4, "cfg", false),
+        (7, "cfg_attr", true),
+        (10, "cfg", true),
+    ]);
+    assert_eq!(cfgs[2].condition, r#"feature = "serde""#);
+}
+
+#[tokio::test]
+async fn test_cfg_status_command() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "cfg_status",
+        "params": {"method": "cfg_status", "file": "src/lib.rs"}
+    });
+    let response: Value = serde_json::from_str(
+        &server.handle_request(&request.to_string()).await.unwrap()
+    ).unwrap();
+
+    let result = &response["result"];
+    assert_eq!(result["target"], "host");
+    let cfgs = result["cfgs"].as_array().unwrap();
+    let test_cfg = cfgs.iter().find(|c| c["condition"] == "test").unwrap();
+    assert_eq!(test_cfg["active"], false);
+}
+
+#[tokio::test]
+async fn test_requested_features_enable_what_they_imply() {
+    let dir = ScratchDir::with_crate("cfg-features", "\n[features]\nfull = [\"net\"]\nnet = [\"tls\"]\ntls = []\nslow = []\n\n[workspace]\n");
+    dir.write("src/lib.rs", "#[cfg(feature = \"net\")]\nmod net;\n#[cfg(feature = \"tls\")]\nmod tls;\n#[cfg(feature = \"slow\")]\nmod slow;\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "cfg_status", "params": { "file": "src/lib.rs", "features": ["full"] } });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+
+    let active: Vec<(&str, bool)> = response["result"]["cfgs"].as_array().unwrap().iter()
+        .map(|c| (c["condition"].as_str().unwrap(), c["active"].as_bool().unwrap()))
+        .collect();
+    assert_eq!(active, vec![("feature = \"net\"", true), ("feature = \"tls\"", true), ("feature = \"slow\"", false)], "{}", response);
+}
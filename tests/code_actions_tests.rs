@@ -0,0 +1,107 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::code_actions::{code_actions, edit_diffs, matches_kind, select_action, unified_diff};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+fn actions() -> Vec<Value> {
+    code_actions(&json!([
+        {
+            "title": "Replace match with if let",
+            "kind": "refactor.rewrite",
+            "data": { "id": "replace_match_with_if_let:RefactorRewrite:0" }
+        },
+        {
+            "title": "Convert to is_some_and",
+            "kind": "refactor.rewrite",
+            "data": { "id": "replace_is_method_with_if_let_method:RefactorRewrite:1" }
+        },
+        {
+            "title": "Extract into variable",
+            "kind": "refactor.extract",
+            "data": { "id": "extract_variable:RefactorExtract:2" }
+        },
+        { "title": "Run", "command": "rust-analyzer.runSingle" }
+    ]))
+}
+
+#[test]
+fn test_select_action() {
+    let actions = actions();
+    assert_eq!(actions.len(), 3, "bare commands are not assists");
+
+    let by_name = select_action(&actions, Some("replace match with IF LET"), None).unwrap();
+    assert_eq!(by_name["data"]["id"], "replace_match_with_if_let:RefactorRewrite:0");
+    let by_part = select_action(&actions, Some("is_some_and"), None).unwrap();
+    assert_eq!(by_part["title"], "Convert to is_some_and");
+    let by_id = select_action(&actions, None, Some("replace_match_with_if_let")).unwrap();
+    assert_eq!(by_id["title"], "Replace match with if let");
+
+    assert!(matches_kind(&actions[2], "refactor"));
+    assert!(!matches_kind(&actions[2], "refactor.re"));
+
+    let ambiguous = select_action(&actions, None, Some("refactor.rewrite")).unwrap_err().to_string();
+    assert!(ambiguous.contains("Replace match with if let") && ambiguous.contains("Convert to is_some_and"));
+    let missing = select_action(&actions, Some("inline"), Some("refactor.rewrite")).unwrap_err().to_string();
+    assert!(missing.contains("available"), "{}", missing);
+}
+
+#[test]
+fn test_unified_diff() {
+    let before = "fn f(x: Option<u32>) {\n    match x {\n        Some(v) => use_it(v),\n        None => {}\n    }\n}\n";
+    let after = "fn f(x: Option<u32>) {\n    if let Some(v) = x {\n        use_it(v)\n    }\n}\n";
+    let diff = unified_diff("src/lib.rs", before, after);
+    assert_eq!(diff, "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,6 +1,5 @@
+ fn f(x: Option<u32>) {
+-    match x {
+-        Some(v) => use_it(v),
+-        None => {}
++    if let Some(v) = x {
++        use_it(v)
+     }
+ }
+");
+    assert_eq!(unified_diff("a.rs", before, before), "--- a/a.rs\n+++ b/a.rs\n");
+}
+
+#[tokio::test]
+async fn test_edit_diffs_leave_files_alone() {
+    let dir = ScratchDir::new("code-actions");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let lib = dir.join("src/lib.rs");
+    std::fs::write(&lib, "fn a() -> bool {\n    x.map_or(false, |v| v > 1)\n}\n").unwrap();
+
+    let edit = json!({
+        "documentChanges": [{
+            "textDocument": { "uri": format!("file://{}", lib.display()), "version": 1 },
+            "edits": [{
+                "range": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 30 } },
+                "newText": "x.is_some_and(|v| v > 1)"
+            }]
+        }]
+    });
+    let diffs = edit_diffs(&edit, &dir).await.unwrap();
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].file, "src/lib.rs");
+    assert!(diffs[0].diff.contains("-    x.map_or(false, |v| v > 1)\n+    x.is_some_and(|v| v > 1)\n"));
+    assert!(std::fs::read_to_string(&lib).unwrap().contains("map_or"));
+}
+
+#[tokio::test]
+async fn test_convert_control_flow_needs_lsp() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(env!("CARGO_MANIFEST_DIR"), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "convert_control_flow",
+        "params": { "method": "convert_control_flow", "file": "src/lib.rs", "line": 1, "column": 1 }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap().contains("LSP not available"));
+}
@@ -0,0 +1,68 @@
+//! Fixtures shared by the integration tests
+
+// Each test crate uses only some of them
+#![allow(dead_code)]
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// The `[package]` table of a scratch crate
+const PACKAGE: &str = "[package]\nname = \"scratch\"\nversion = \"0.1.0\"\nedition = \"2021\"\n";
+
+/// An empty directory under the system temp dir, removed again when dropped
+/// so a failing test doesn't leave it behind
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// `mcp-<name>-<pid>`, emptied if a previous run left it behind
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("mcp-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // Canonical, so paths the server reports compare equal to it
+        Self(dir.canonicalize().unwrap())
+    }
+
+    /// A scratch dir holding a crate named `scratch` with an empty `src/`;
+    /// `manifest_extra` is appended to its Cargo.toml, e.g. `[workspace]`
+    pub fn with_crate(name: &str, manifest_extra: &str) -> Self {
+        let dir = Self::new(name);
+        dir.write("Cargo.toml", &format!("{}{}", PACKAGE, manifest_extra));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        dir
+    }
+
+    /// Write `content` to `path` under the dir, creating its parents
+    pub fn write(&self, path: &str, content: &str) {
+        let path = self.0.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// [`write`](Self::write) each `(path, content)` pair
+    pub fn write_all(&self, files: &[(&str, &str)]) {
+        for (path, content) in files {
+            self.write(path, content);
+        }
+    }
+}
+
+impl Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for ScratchDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,43 @@
+use mcp_rust_analyzer::completion_list::summarize;
+use mcp_rust_analyzer::config::{Config, DEFAULT_MAX_COMPLETIONS};
+use serde_json::{json, Value};
+
+fn items() -> Vec<Value> {
+    vec![
+        json!({ "label": "len", "kind": 2, "sortText": "7fffffff" }),
+        json!({ "label": "push", "kind": 2, "sortText": "7ffffff0" }),
+        json!({ "label": "Vec", "kind": 22 }),
+        json!({ "label": "capacity", "kind": 2, "sortText": "7ffffff0" }),
+        json!({ "label": "fmt", "kind": 9, "sortText": "80000000" }),
+    ]
+}
+
+#[test]
+fn test_short_lists_are_untouched() {
+    let list = summarize(items(), 5);
+    assert_eq!(list.total, 5);
+    assert!(!list.truncated && list.by_kind.is_none());
+    assert_eq!(list.items, items());
+    assert_eq!(summarize(items(), 0).items.len(), 5);
+}
+
+#[test]
+fn test_long_lists_keep_the_most_relevant() {
+    let list = summarize(items(), 3);
+    assert_eq!(list.total, 5);
+    assert!(list.truncated);
+    let labels: Vec<&str> = list.items.iter().map(|i| i["label"].as_str().unwrap()).collect();
+    // Equal sort texts keep the server's order; items without one go last
+    assert_eq!(labels, vec!["push", "capacity", "len"]);
+
+    let by_kind = list.by_kind.unwrap();
+    assert_eq!(by_kind["method"], 3);
+    assert_eq!(by_kind["struct"], 1);
+    assert_eq!(by_kind["module"], 1);
+}
+
+#[test]
+fn test_max_completions_config() {
+    assert_eq!(Config::default().max_completions, DEFAULT_MAX_COMPLETIONS);
+    assert_eq!(Config::parse("max_completions = 10").unwrap().max_completions, 10);
+}
@@ -1,10 +1,11 @@
 use mcp_rust_analyzer::commands::completion::CompletionCommands;
-use mcp_rust_analyzer::server::CommandHandler;
-use serde_json::json;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
 
 #[tokio::test]
 async fn test_snippet_expansion() {
-    let commands = CompletionCommands;
+    let _commands = CompletionCommands;
     
     let test_cases = vec![
         ("match_expr", "match"),
@@ -14,7 +15,7 @@ async fn test_snippet_expansion() {
         ("test_fn", "#[test]"),
     ];
     
-    for (snippet_name, expected_content) in test_cases {
+    for (snippet_name, _expected_content) in test_cases {
         let params = json!({
             "method": "expand_snippet",
             "name": snippet_name
@@ -55,4 +56,31 @@ async fn test_import_resolution_params() {
     
     let value = parsed.unwrap();
     assert_eq!(value["symbol"], "HashMap");
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_completion_detail_from_item() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "completion_detail", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap() }
+    };
+
+    let item = json!({
+        "label": "push(…)",
+        "kind": 2,
+        "detail": "pub fn(&mut self, value: T)",
+        "documentation": { "kind": "markdown", "value": "Appends an element to the back of a collection.\n\n# Panics\n\nPanics if the new capacity exceeds `isize::MAX` _bytes_." }
+    });
+    let result = call(json!({"name": "push", "item": item, "content_format": "plaintext"})).await["result"].clone();
+    assert_eq!(result["name"], "push", "{result}");
+    assert_eq!(result["resolved"], false);
+    assert_eq!(result["signature"]["rendered"], "pub fn push(&mut self, value: T)");
+    assert_eq!(result["receiver"], "&mut self");
+    assert_eq!((result["requires_mut"].as_bool(), result["consumes_self"].as_bool()), (Some(true), Some(false)));
+    assert!(result["documentation"].as_str().unwrap().ends_with("isize::MAX bytes."), "{result}");
+
+    let response = call(json!({"name": "push", "file": "src/lib.rs", "line": 1})).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("either item"), "{response}");
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::complexity::{analyze, average, most_complex, FunctionComplexity};
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::metrics::MetricsAnalyzer;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const SOURCE: &str = r#"
+struct Parser;
+enum Token { Word, End }
+trait Visit {
+    fn visit(&self);
+    fn walk(&self) -> bool { true && false }
+}
+
+impl Parser {
+    fn next(&mut self, input: &str) -> Result<Token, String> {
+        let word = input.parse::<u32>().map_err(|e| e.to_string())?;
+        if word > 3 || word == 0 {
+            return Ok(Token::End);
+        }
+        for _ in 0..word {
+            while false {}
+        }
+        match word {
+            1 => Ok(Token::Word),
+            n if n % 2 == 0 => Ok(Token::End),
+            _ => Err("if && || in a string".to_string()),
+        }
+    }
+}
+
+fn simple() {
+    // if while for
+    fn inner(x: bool) -> bool { x || !x }
+}
+"#;
+
+#[test]
+fn test_analyze_measures_each_function() {
+    let file = analyze(SOURCE).unwrap();
+    let found: Vec<(&str, usize, usize)> = file.functions.iter().map(|f| (f.name.as_str(), f.line, f.complexity)).collect();
+    assert_eq!(found, vec![
+        ("Visit::walk", 6, 2),
+        // `?`, `if`, `||`, `for`, `while`, two extra arms and a guard
+        ("Parser::next", 10, 9),
+        ("simple", 26, 1),
+        ("inner", 28, 2),
+    ]);
+    assert_eq!((file.structs, file.enums, file.traits, file.impls, file.type_aliases), (1, 1, 1, 1, 0));
+    assert!(analyze("fn broken( {").is_err());
+}
+
+#[test]
+fn test_analyze_counts_nested_items() {
+    let source = r#"
+pub type Id = u64;
+
+impl<T>
+    Wrapper<T>
+where
+    T: Clone,
+{
+    pub fn get(&self) -> &T { &self.0 }
+}
+
+impl Clone for Id2 { fn clone(&self) -> Self { Id2 } }
+
+mod inner {
+    pub struct Wrapper<T>(T);
+    type Local = ();
+
+    fn build() {
+        struct Scratch;
+        impl Scratch { fn run() {} }
+    }
+}
+"#;
+    let file = analyze(source).unwrap();
+    let names: Vec<&str> = file.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["Wrapper::get", "Id2::clone", "build", "Scratch::run"]);
+    assert_eq!((file.structs, file.enums, file.traits, file.impls, file.type_aliases), (2, 0, 0, 3, 2));
+}
+
+#[test]
+fn test_analyze_file_fills_code_metrics() {
+    let dir = ScratchDir::new("complexity-file");
+    let path = dir.join("lib.rs");
+    std::fs::write(&path, SOURCE).unwrap();
+    let metrics = MetricsAnalyzer::new().analyze_file(&path).unwrap();
+    assert_eq!((metrics.functions, metrics.structs, metrics.traits, metrics.impls), (4, 1, 1, 1));
+    assert_eq!(metrics.cyclomatic_complexity, 14);
+    assert_eq!(metrics.lines_of_code, 26);
+}
+
+#[test]
+fn test_average_and_most_complex() {
+    let function = |name: &str, line: usize, complexity: usize| FunctionComplexity {
+        name: name.to_string(), file: "src/lib.rs".to_string(), line, complexity,
+    };
+    let functions = vec![function("a", 1, 1), function("b", 5, 4), function("c", 9, 4), function("d", 12, 2)];
+    assert_eq!(average(&functions), 2.75);
+    assert_eq!(average(&[]), 0.0);
+    let top: Vec<String> = most_complex(&functions, 3).into_iter().map(|f| f.name).collect();
+    assert_eq!(top, vec!["b", "c", "d"]);
+}
+
+#[tokio::test]
+async fn test_code_metrics_reports_complexity() {
+    let root = ScratchDir::new("complexity");
+    root.write("Cargo.toml", "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n");
+    root.write("src/lib.rs", "pub mod parser;\npub fn one() {}\n");
+    root.write("src/parser/mod.rs", SOURCE);
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "code_metrics", "params": { "top": 2 } });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let metrics = &response["result"]["metrics"];
+
+    assert_eq!(metrics["functions"], 5);
+    assert_eq!(metrics["structs"], 1);
+    assert_eq!(metrics["impls"], 1);
+    assert_eq!(metrics["type_aliases"], 0);
+    assert_eq!(metrics["max_complexity"], 9);
+    assert_eq!(metrics["average_complexity"], 3.0);
+    assert_eq!(metrics["most_complex"], json!([
+        { "name": "Parser::next", "file": "src/parser/mod.rs", "line": 10, "complexity": 9 },
+        { "name": "Visit::walk", "file": "src/parser/mod.rs", "line": 6, "complexity": 2 },
+    ]));
+    assert!(metrics["total_lines"].as_u64().unwrap() > 0);
+}
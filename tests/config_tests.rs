@@ -0,0 +1,51 @@
+use mcp_rust_analyzer::config::Config;
+
+#[test]
+fn test_project_config_parsing() {
+    let config = Config::parse(r#"
+        rust_analyzer_path = "/opt/ra/rust-analyzer"
+        use_lsp = false
+        features = ["serde"]
+        unknown_option = 1
+    "#).unwrap();
+    
+    assert_eq!(config.rust_analyzer_path, "/opt/ra/rust-analyzer");
+    assert!(!config.use_lsp);
+    assert_eq!(config.features, vec!["serde".to_string()]);
+    assert!(config.ignore.is_empty());
+    
+    // Missing file falls back to defaults
+    let config = Config::load_file(&std::env::temp_dir()).unwrap();
+    assert_eq!(config.rust_analyzer_path, "rust-analyzer");
+    
+    // Wrong value types are reported rather than silently ignored
+    assert!(Config::parse("use_lsp = \"yes\"").is_err());
+}
+
+#[test]
+fn test_cli_overrides_apply_and_export() {
+    use mcp_rust_analyzer::config::Overrides;
+    use mcp_rust_analyzer::tool_access::ToolCategory;
+    
+    let overrides = Overrides {
+        rust_analyzer_path: Some("/opt/ra/rust-analyzer".to_string()),
+        tool_categories: Some(vec![ToolCategory::ReadOnly, ToolCategory::Write]),
+        timings: true,
+        lsp_passthrough: false,
+    };
+    let mut config = Config::parse("rust_analyzer_path = \"ra\"\ntimings = false").unwrap();
+    config.apply_overrides(&overrides);
+    
+    assert_eq!(config.rust_analyzer_path, "/opt/ra/rust-analyzer");
+    assert_eq!(config.tool_categories, vec![ToolCategory::ReadOnly, ToolCategory::Write]);
+    assert!(config.timings);
+    assert!(!config.lsp_passthrough);
+    
+    // What a spawned daemon reads back through `apply_env`
+    assert_eq!(overrides.env_vars(), vec![
+        ("RUST_ANALYZER_PATH", "/opt/ra/rust-analyzer".to_string()),
+        ("MCP_TOOL_CATEGORIES", "read_only,write".to_string()),
+        ("MCP_TIMINGS", "true".to_string()),
+    ]);
+    assert!(Overrides::default().env_vars().is_empty());
+}
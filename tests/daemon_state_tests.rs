@@ -0,0 +1,43 @@
+#![cfg(unix)]
+
+use mcp_rust_analyzer::daemon_state::DaemonState;
+use std::process::Command;
+use std::time::Duration;
+
+/// Spawn `program` and reap it on a thread once it dies, so its PID goes away
+fn spawn_reaped(program: &str, args: &[&str]) -> (u32, std::thread::JoinHandle<std::process::ExitStatus>) {
+    let mut child = Command::new(program).args(args).spawn().unwrap();
+    let pid = child.id();
+    (pid, std::thread::spawn(move || child.wait().unwrap()))
+}
+
+#[test]
+fn test_is_process_alive() {
+    assert!(DaemonState::is_process_alive(std::process::id()));
+
+    let (pid, reaper) = spawn_reaped("true", &[]);
+    reaper.join().unwrap();
+    assert!(!DaemonState::is_process_alive(pid));
+    assert!(DaemonState::wait_for_exit(pid, Duration::ZERO));
+}
+
+#[test]
+fn test_terminate_sends_sigterm() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let (pid, reaper) = spawn_reaped("sleep", &["30"]);
+    assert!(!DaemonState::wait_for_exit(pid, Duration::from_millis(50)));
+    DaemonState::terminate(pid, Duration::from_secs(5)).unwrap();
+    assert_eq!(reaper.join().unwrap().signal(), Some(libc::SIGTERM));
+}
+
+#[test]
+fn test_terminate_falls_back_to_sigkill() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let (pid, reaper) = spawn_reaped("sh", &["-c", "trap '' TERM; while :; do sleep 1; done"]);
+    // Give the shell time to install its trap
+    std::thread::sleep(Duration::from_millis(200));
+    DaemonState::terminate(pid, Duration::from_millis(300)).unwrap();
+    assert_eq!(reaper.join().unwrap().signal(), Some(libc::SIGKILL));
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::derives::{derive_sites, derive_usage};
+
+#[test]
+fn test_derive_sites_multiline_and_paths() {
+    let source = "#[derive(Debug, Clone)]\n#[derive(serde::Serialize)]\n/// Doc\npub struct A;\n\n#[derive(\n    PartialEq,\n    Eq,\n)]\nenum B { X }\n";
+    let sites = derive_sites(source);
+    assert_eq!(sites.len(), 3);
+    assert_eq!(sites[0].derives, vec!["Debug", "Clone"]);
+    assert_eq!(sites[1].derives, vec!["Serialize"]);
+    assert_eq!(sites[1].type_name.as_deref(), Some("A"));
+    assert_eq!(sites[2].derives, vec!["PartialEq", "Eq"]);
+    assert_eq!(sites[2].type_name.as_deref(), Some("B"));
+    assert_eq!(sites[2].line, 6);
+}
+
+#[test]
+fn test_derive_usage_tallies_and_missing_debug() {
+    let root = ScratchDir::new("derives");
+    root.write(
+        "src/lib.rs",
+        "#[derive(Debug, Clone, Serialize)]\npub struct A;\n\n#[derive(Clone, Serialize)]\npub struct B;\n\n#[derive(Clone)]\npub struct C;\nimpl std::fmt::Debug for C { fn fmt(&self, _: &mut std::fmt::Formatter) -> std::fmt::Result { Ok(()) } }\n",
+    );
+
+    let usage = derive_usage(&root.join("src"), &root);
+    assert_eq!(usage.derived_types, 3);
+    assert_eq!(usage.std[0].name, "Clone");
+    assert_eq!(usage.std[0].count, 3);
+    assert_eq!(usage.custom[0].name, "Serialize");
+    assert_eq!(usage.custom[0].count, 2);
+
+    // C implements Debug by hand
+    let missing: Vec<&str> = usage.missing_debug.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(missing, vec!["B"]);
+    assert_eq!(usage.missing_debug[0].file, "src/lib.rs");
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_lint_groups_collect_every_message_of_a_code() {
+    let dir = ScratchDir::with_crate("diagnostic-groups", "\n[workspace]\n");
+    dir.write("src/lib.rs", "pub fn f() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n    let d = 4;\n}\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "get_diagnostics", "params": { "group_by": "lint" } });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+
+    // One group for the lint, not one per variable name
+    assert_eq!(result["total_groups"], 1, "{}", result);
+    let group = &result["groups"][0];
+    assert_eq!(group["code"], "unused_variables");
+    assert_eq!(group["count"], 4);
+    assert_eq!(group["example_messages"], json!(["unused variable: `a`", "unused variable: `b`", "unused variable: `c`"]));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::diagnostics_stream::DiagnosticsStream;
+
+/// A tiny crate whose build emits one `unused_variables` warning per function
+fn noisy_crate(name: &str, functions: usize) -> ScratchDir {
+    let root = ScratchDir::with_crate(&format!("diag-stream-{}", name), "\n[workspace]\n");
+    let body: String = (0..functions)
+        .map(|i| format!("pub fn f{}() {{ let unused = {}; }}\n", i, i))
+        .collect();
+    root.write("src/lib.rs", &body);
+    root
+}
+
+#[tokio::test]
+async fn test_stream_delivers_all_frames_through_small_buffer() {
+    let root = noisy_crate("all", 20);
+
+    // A buffer of one forces the reader to wait on us for every frame
+    let mut stream = DiagnosticsStream::start(&root, 1).unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = stream.next().await {
+        frames.push(frame);
+    }
+
+    let summary = frames.last().unwrap();
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["warnings"], 20);
+    assert_eq!(summary["success"], true);
+    assert_eq!(frames.iter().filter(|f| f["type"] == "diagnostic").count(), 20);
+}
+
+#[tokio::test]
+async fn test_summary_counts_messages_not_spans() {
+    let root = ScratchDir::with_crate("diag-stream-spans", "\n[workspace]\n");
+    // One warning pointing at the struct and at both fields
+    root.write("src/lib.rs", "struct Pair {\n    a: u8,\n    b: u8,\n}\n\npub fn make() {\n    let _ = Pair { a: 1, b: 2 };\n}\n");
+
+    let mut stream = DiagnosticsStream::start(&root, 8).unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = stream.next().await {
+        frames.push(frame);
+    }
+
+    assert_eq!(frames.iter().filter(|f| f["type"] == "diagnostic").count(), 3);
+    let summary = frames.last().unwrap();
+    assert_eq!(summary["warnings"], 1);
+    assert_eq!(summary["errors"], 0);
+}
+
+#[tokio::test]
+async fn test_cancel_kills_cargo() {
+    let root = noisy_crate("cancel", 50);
+
+    let mut stream = DiagnosticsStream::start(&root, 1).unwrap();
+    stream.cancel().await;
+    assert!(!stream.is_running());
+}
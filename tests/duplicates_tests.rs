@@ -0,0 +1,44 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::duplicates::{find_duplicates, function_bodies};
+
+#[test]
+fn test_function_bodies_normalize_identifiers_and_literals() {
+    let source = "trait T {\n    fn declared(&self);\n}\n\nfn a(x: u32) -> u32 {\n    // comment\n    let y = x + 1; y\n}\n\nfn b(z: u32) -> u32 {\n    let w = z + 2;\n    w\n}\n\nfn c() -> char { '}' }\n";
+    let bodies = function_bodies(source);
+    let names: Vec<&str> = bodies.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+    assert_eq!(bodies[0].tokens, bodies[1].tokens);
+    assert_eq!(bodies[0].tokens, vec!["let", "ID", "=", "ID", "+", "LIT", ";", "ID"]);
+    assert_eq!((bodies[0].line, bodies[0].end_line), (5, 8));
+    assert_eq!(bodies[2].tokens, vec!["LIT"]);
+}
+
+#[test]
+fn test_find_duplicates_groups_copies() {
+    let root = ScratchDir::new("duplicates");
+
+    let body = |name: &str, extra: &str| format!(
+        "fn {}(items: &[u32]) -> u32 {{\n    let mut total = 0;\n    for item in items {{\n        if *item > 10 {{\n            total += item * 2;\n        }} else {{\n            total += item;\n        }}\n    }}\n    {}\n    total\n}}\n",
+        name, extra
+    );
+    root.write("src/lib.rs", &format!("{}\n{}", body("sum", ""), body("other_sum", "")));
+    root.write("src/near.rs", &body("near_sum", "println!(\"{}\", total);"));
+    root.write("src/tiny.rs", "fn one() -> u32 { 1 }\nfn two() -> u32 { 2 }\n");
+
+    let exact = find_duplicates(&root.join("src"), &root, 1.0, 10);
+    assert_eq!(exact.files_scanned, 3);
+    assert_eq!(exact.functions_compared, 3);
+    assert_eq!(exact.groups.len(), 1);
+    assert!(exact.groups[0].identical);
+    let names: Vec<&str> = exact.groups[0].functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["sum", "other_sum"]);
+    assert_eq!(exact.groups[0].functions[0].file, "src/lib.rs");
+
+    let near = find_duplicates(&root.join("src"), &root, 0.6, 10);
+    assert_eq!(near.groups.len(), 1);
+    assert_eq!(near.groups[0].functions.len(), 3);
+    assert!(!near.groups[0].identical);
+    assert!(near.groups[0].similarity < 1.0);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::edit_check::{new_diagnostics, owning_manifest};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const LIB: &str = "pub fn answer() -> u32 {\n    42\n}\n";
+
+async fn apply_and_check(dir: &Path, new_text: &str, revert_on_error: bool) -> Value {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "apply_and_check",
+        "params": {
+            "method": "apply_and_check",
+            "file": "src/lib.rs",
+            "range": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 6 } },
+            "new_text": new_text,
+            "revert_on_error": revert_on_error
+        }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    response["result"].clone()
+}
+
+#[tokio::test]
+async fn test_apply_and_check_keeps_compiling_edit() {
+    let dir = ScratchDir::with_crate("edit-check-ok", "\n[workspace]\n");
+    dir.write("src/lib.rs", LIB);
+    let result = apply_and_check(&dir, "41 + 1", true).await;
+
+    assert_eq!(result["success"], true);
+    assert_eq!(result["reverted"], false);
+    assert_eq!(result["new_diagnostics"], json!([]));
+    assert!(std::fs::read_to_string(dir.join("src/lib.rs")).unwrap().contains("41 + 1"));
+}
+
+#[tokio::test]
+async fn test_apply_and_check_reverts_broken_edit() {
+    let dir = ScratchDir::with_crate("edit-check-broken", "\n[workspace]\n");
+    dir.write("src/lib.rs", LIB);
+    let result = apply_and_check(&dir, "\"forty-two\"", true).await;
+
+    assert_eq!(result["success"], false);
+    assert_eq!(result["compiled_before"], true);
+    assert_eq!(result["reverted"], true);
+    let diagnostics = result["new_diagnostics"].as_array().unwrap();
+    assert!(diagnostics.iter().any(|d| d["level"] == "error"));
+    assert_eq!(std::fs::read_to_string(dir.join("src/lib.rs")).unwrap(), "pub fn answer() -> u32 {\n    42\n}\n");
+
+    // Without revert_on_error the broken edit stays for the caller to fix
+    let result = apply_and_check(&dir, "\"forty-two\"", false).await;
+    assert_eq!(result["reverted"], false);
+    assert!(std::fs::read_to_string(dir.join("src/lib.rs")).unwrap().contains("forty-two"));
+}
+
+#[test]
+fn test_new_diagnostics_ignore_shifted_lines() {
+    let unused = json!({ "file": "src/lib.rs", "line": 3, "level": "warning", "code": null, "message": "unused variable: `x`" });
+    let shifted = json!({ "file": "src/lib.rs", "line": 4, "level": "warning", "code": null, "message": "unused variable: `x`" });
+    let error = json!({ "file": "src/lib.rs", "line": 2, "level": "error", "code": null, "message": "mismatched types" });
+
+    let fresh = new_diagnostics(std::slice::from_ref(&unused), vec![shifted, error.clone(), unused.clone()]);
+    // One of the two identical warnings existed before; the other is new
+    assert_eq!(fresh.len(), 2);
+    assert!(fresh.contains(&error));
+}
+
+#[test]
+fn test_owning_manifest_prefers_nearest_member() {
+    let dir = ScratchDir::with_crate("edit-check-member", "\n[workspace]\n");
+    dir.write("src/lib.rs", LIB);
+    std::fs::create_dir_all(dir.join("member/src")).unwrap();
+    dir.write("member/Cargo.toml", "[package]\nname = \"member\"\n");
+
+    assert_eq!(owning_manifest(&dir.join("member/src/lib.rs"), &dir), dir.join("member/Cargo.toml"));
+    assert_eq!(owning_manifest(&dir.join("src/lib.rs"), &dir), dir.join("Cargo.toml"));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::entry_points::targets_from_metadata;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_targets_from_metadata() {
+    let metadata = json!({
+        "packages": [{
+            "name": "tool",
+            "targets": [
+                { "name": "tool", "kind": ["lib"], "src_path": "/p/src/lib.rs" },
+                { "name": "cli", "kind": ["bin"], "src_path": "/p/src/bin/cli.rs" }
+            ]
+        }]
+    });
+    let targets = targets_from_metadata(&metadata);
+    assert_eq!(targets.len(), 2);
+    assert_eq!(targets[1].name, "cli");
+    assert_eq!(targets[1].kind, "bin");
+    assert_eq!(targets[1].package, "tool");
+}
+
+#[tokio::test]
+async fn test_entry_points_lists_lib_and_bins() {
+    let dir = ScratchDir::with_crate("entry-points", "\n[[bin]]\nname = \"admin\"\npath = \"tools/admin.rs\"\n\n[workspace]\n");
+    dir.write("src/lib.rs", "pub mod api;\n\n/// Run it\npub fn run() {}\n\nfn helper() {}\n");
+    dir.write("src/main.rs", "use scratch::run;\n\nfn main() {\n    run();\n}\n");
+    dir.write("tools/admin.rs", "#[tokio::main]\nasync fn main() {}\n");
+    dir.write("examples/demo.rs", "fn main() {}\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |all_targets: bool| json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "entry_points",
+        "params": { "method": "entry_points", "all_targets": all_targets }
+    });
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(false).to_string()).await.unwrap()).unwrap();
+    let entries = response["result"]["entry_points"].as_array().unwrap();
+    let find = |file: &str| entries.iter().find(|e| e["file"] == file).unwrap_or_else(|| panic!("{} missing: {:?}", file, entries));
+
+    assert_eq!(entries.len(), 3);
+    let lib = find("src/lib.rs");
+    assert_eq!(lib["kind"], "lib");
+    let names: Vec<&str> = lib["public_items"].as_array().unwrap().iter().map(|i| i["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["api", "run"]);
+    assert!(lib.get("main_line").is_none());
+    assert_eq!(find("src/main.rs")["main_line"], 3);
+    let admin = find("tools/admin.rs");
+    assert_eq!(admin["name"], "admin");
+    assert_eq!(admin["main_line"], 2);
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(true).to_string()).await.unwrap()).unwrap();
+    assert!(response["result"]["entry_points"].as_array().unwrap().iter().any(|e| e["kind"] == "example"));
+}
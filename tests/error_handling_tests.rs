@@ -0,0 +1,93 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::error_handling::scan_source;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const CONFIG: &str = r#"
+use std::io;
+
+pub fn load(path: &str) -> io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+pub fn port(text: &str) -> Option<u16> {
+    text.parse().ok()
+}
+
+pub fn parse(text: &str) -> anyhow::Result<u16> {
+    let value = text.trim().parse().unwrap();
+    fn helper() -> u8 {
+        "1".parse().expect("a digit")
+    }
+    Ok(value + helper() as u16)
+}
+
+pub fn must_load(path: &str) -> String {
+    // never .unwrap() here, says the comment
+    let message = "do not panic!";
+    load(path).unwrap_or_else(|_| panic!("{} {}", message, path))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn loads() {
+        super::load("x").unwrap();
+    }
+}
+"#;
+
+#[test]
+fn test_scan_source() {
+    let (functions, sites) = scan_source(CONFIG, false);
+    let found: Vec<(&str, usize, Option<&str>, usize)> = functions.iter()
+        .map(|f| (f.name.as_str(), f.line, f.returns.as_deref(), f.panics))
+        .collect();
+    assert_eq!(found, vec![
+        ("load", 4, Some("Result"), 0),
+        ("port", 8, Some("Option"), 0),
+        ("parse", 12, Some("Result"), 1),
+        ("helper", 14, None, 1),
+        ("must_load", 20, None, 1),
+    ]);
+    assert_eq!(sites, [("expect".to_string(), 1), ("panic".to_string(), 1), ("unwrap".to_string(), 1)].into());
+
+    let (functions, _) = scan_source(CONFIG, true);
+    assert_eq!(functions.last().map(|f| (f.name.as_str(), f.panics)), Some(("loads", 1)));
+}
+
+#[tokio::test]
+async fn test_error_handling_report_command() {
+    let dir = ScratchDir::with_crate("error-handling", "");
+    dir.write("src/lib.rs", "pub mod config;\npub mod cli;\n");
+    dir.write("src/config.rs", CONFIG);
+    dir.write("src/cli/mod.rs", "pub fn run() {\n    let args: Vec<String> = std::env::args().collect();\n    args.first().unwrap();\n}\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "error_handling_report", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["files_scanned"], 3);
+    assert_eq!(result["functions"], 6);
+    assert_eq!(result["inconsistent_modules"], 1);
+
+    let config = &result["modules"][0];
+    assert_eq!(config["module"], "crate::config");
+    assert_eq!(config["file"], "src/config.rs");
+    assert_eq!((config["returning_result"].as_u64(), config["returning_option"].as_u64()), (Some(2), Some(1)));
+    assert_eq!((config["panicking"].as_u64(), config["mixed"].as_u64()), (Some(3), Some(1)));
+    assert_eq!(config["dominant"], "panic");
+    assert_eq!(config["inconsistent"], true);
+    let outliers: Vec<&str> = config["outliers"].as_array().unwrap().iter().map(|f| f["name"].as_str().unwrap()).collect();
+    assert_eq!(outliers, vec!["load", "port"]);
+
+    let cli = &result["modules"][1];
+    assert_eq!(cli["module"], "crate::cli");
+    assert_eq!(cli["dominant"], "panic");
+    assert_eq!(cli["inconsistent"], false);
+    assert!(cli.get("outliers").is_none());
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::extract_module::{new_module_path, plan, top_level_items, Selection};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const SOURCE: &str = "\
+use std::collections::HashMap;
+
+/// A parsed token
+#[derive(Debug)]
+pub struct Token {
+    kind: Kind,
+    pub text: String,
+}
+
+impl Token {
+    fn is_word(&self) -> bool {
+        matches!(self.kind, Kind::Word)
+    }
+}
+
+#[derive(Debug)]
+enum Kind {
+    Word,
+    Number,
+}
+
+fn count(tokens: &[Token]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for token in tokens.iter().filter(|t| t.is_word()) {
+        *counts.entry(token.text.clone()).or_default() += 1;
+    }
+    counts
+}
+";
+
+#[test]
+fn test_top_level_items() {
+    let items = top_level_items(SOURCE);
+    let names: Vec<(&str, &str, usize)> = items.iter().map(|i| (i.kind.as_str(), i.name.as_str(), i.line)).collect();
+    assert_eq!(names, vec![
+        ("struct", "Token", 5),
+        ("impl", "Token", 10),
+        ("enum", "Kind", 17),
+        ("function", "count", 22),
+    ]);
+    assert_eq!((items[0].start, items[0].end), (2, 7), "doc comment and derive belong to the struct");
+}
+
+#[test]
+fn test_new_module_path() {
+    assert_eq!(new_module_path(Path::new("src/lib.rs"), "lexer", false), Path::new("src/lexer.rs"));
+    assert_eq!(new_module_path(Path::new("src/parse.rs"), "lexer", false), Path::new("src/parse/lexer.rs"));
+    assert_eq!(new_module_path(Path::new("src/parse/mod.rs"), "lexer", true), Path::new("src/parse/lexer/mod.rs"));
+}
+
+#[test]
+fn test_plan_by_names() {
+    let selection = Selection::Names(vec!["Token".to_string(), "Kind".to_string()]);
+    let plan = plan(SOURCE, Path::new("src/lib.rs"), &selection, "token", false).unwrap();
+
+    assert_eq!(plan.new_file, Path::new("src/token.rs"));
+    assert_eq!(plan.items.len(), 3, "the impl follows its type");
+    assert_eq!(plan.parent_content, "\
+use std::collections::HashMap;
+
+mod token;
+pub use token::Token;
+
+fn count(tokens: &[Token]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for token in tokens.iter().filter(|t| t.is_word()) {
+        *counts.entry(token.text.clone()).or_default() += 1;
+    }
+    counts
+}
+");
+    assert!(plan.new_content.starts_with("/// A parsed token\n#[derive(Debug)]\npub struct Token {\n    pub(super) kind: Kind,\n    pub text: String,\n}"));
+    assert!(plan.new_content.contains("    pub(super) fn is_word(&self) -> bool {"));
+    assert!(plan.new_content.contains("pub(super) enum Kind {"));
+    assert!(!plan.new_content.contains("use super::*;"), "nothing of the parent is used");
+
+    let changed: Vec<&str> = plan.visibility_changes.iter().map(|c| c.item.as_str()).collect();
+    assert_eq!(changed, vec!["Token.kind", "Token::is_word", "Kind"]);
+}
+
+#[test]
+fn test_plan_by_lines() {
+    let plan = plan(SOURCE, Path::new("src/stats.rs"), &Selection::Lines(22, 22), "counting", false).unwrap();
+    assert_eq!(plan.new_file, Path::new("src/stats/counting.rs"));
+    assert!(plan.new_content.starts_with("use super::*;\n\npub(super) fn count("));
+    assert!(!plan.parent_content.contains("use counting"), "count is not used by what remains");
+    assert!(plan.parent_content.ends_with("}\n\nmod counting;\n"));
+
+    let error = plan_error(Selection::Names(vec!["missing".to_string()]));
+    assert!(error.contains("available: Token, Kind, count"), "{}", error);
+}
+
+fn plan_error(selection: Selection) -> String {
+    plan(SOURCE, Path::new("src/lib.rs"), &selection, "m", false).unwrap_err().to_string()
+}
+
+#[tokio::test]
+async fn test_extract_module_command() {
+    let dir = ScratchDir::with_crate("extract-module", "\n[workspace]\n");
+    dir.write("src/lib.rs", SOURCE);
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |dry_run: bool| json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "extract_module",
+        "params": { "file": "src/lib.rs", "module_name": "token", "items": ["Token", "Kind"], "dry_run": dry_run }
+    });
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(true).to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["new_file"], "src/token.rs");
+    assert_eq!(result["applied"], false);
+    assert!(result["diff"].as_str().unwrap().contains("+++ b/src/token.rs"));
+    assert!(!dir.join("src/token.rs").exists());
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(false).to_string()).await.unwrap()).unwrap();
+    assert_eq!(response["result"]["applied"], true, "{}", response);
+    let module = std::fs::read_to_string(dir.join("src/token.rs")).unwrap();
+    assert!(module.contains("pub struct Token"));
+    assert!(std::fs::read_to_string(dir.join("src/lib.rs")).unwrap().contains("mod token;"));
+
+    let output = std::process::Command::new("cargo").args(["check", "--quiet"]).current_dir(&dir).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(false).to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap().contains("already"));
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::feature_check::{check_combinations, default_combinations, package_features};
+
+/// A crate where enabling `b` without `a` doesn't compile
+fn featureful_crate() -> ScratchDir {
+    let root = ScratchDir::with_crate("features", "\n[features]\ndefault = [\"a\"]\na = []\nb = []\n\n[workspace]\n");
+    root.write(
+        "src/lib.rs",
+        "#[cfg(feature = \"a\")]\npub fn helper() {}\n\n#[cfg(feature = \"b\")]\npub fn uses_helper() { helper() }\n",
+    );
+    root
+}
+
+#[tokio::test]
+async fn test_feature_powerset_finds_broken_combination() {
+    let root = featureful_crate();
+
+    let features = package_features(&root).await.unwrap();
+    assert_eq!(features, vec!["a", "b"]);
+
+    let combinations = default_combinations(&features);
+    let labels: Vec<&str> = combinations.iter().map(|c| c.label.as_str()).collect();
+    assert_eq!(labels, vec!["no-default-features", "only a", "only b", "all-features"]);
+
+    let results = check_combinations(&root, combinations, 2).await;
+    let failed: Vec<&str> = results.iter()
+        .filter(|r| !r.success)
+        .map(|r| r.combination.label.as_str())
+        .collect();
+    assert_eq!(failed, vec!["only b"]);
+
+    let broken = results.iter().find(|r| r.combination.label == "only b").unwrap();
+    assert!(broken.errors[0]["message"].as_str().unwrap().contains("helper"));
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::feature_graph::feature_graph;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const MANIFEST: &str = r#"
+[package]
+name = "graph"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde = { version = "1", optional = true }
+tokio = { version = "1", optional = true }
+regex = "1"
+
+[target.'cfg(unix)'.dependencies]
+nix = { version = "0.29", optional = true }
+
+[features]
+default = ["std", "json"]
+std = ["regex/std"]
+json = ["dep:serde", "serde?/derive", "full-io"]
+full-io = ["tokio/fs"]
+broken = ["missing"]
+"#;
+
+#[test]
+fn test_feature_graph() {
+    let graph = feature_graph(MANIFEST).unwrap();
+    let nodes: Vec<(&str, bool, Option<bool>)> = graph.nodes.iter().map(|n| (n.id.as_str(), n.implicit, n.optional)).collect();
+    assert_eq!(nodes, vec![
+        ("broken", false, None),
+        ("default", false, None),
+        ("full-io", false, None),
+        ("json", false, None),
+        ("std", false, None),
+        ("dep:nix", false, Some(true)),
+        ("nix", true, None),
+        ("dep:serde", false, Some(true)),
+        ("dep:tokio", false, Some(true)),
+        ("tokio", true, None),
+        ("dep:regex", false, Some(false)),
+    ]);
+
+    let edges: Vec<(&str, &str, Option<&str>, bool)> = graph.edges.iter()
+        .map(|e| (e.from.as_str(), e.to.as_str(), e.dependency_feature.as_deref(), e.weak))
+        .collect();
+    assert_eq!(edges, vec![
+        ("nix", "dep:nix", None, false),
+        ("tokio", "dep:tokio", None, false),
+        ("default", "std", None, false),
+        ("default", "json", None, false),
+        ("full-io", "dep:tokio", Some("fs"), false),
+        ("json", "dep:serde", None, false),
+        ("json", "dep:serde", Some("derive"), true),
+        ("json", "full-io", None, false),
+        ("std", "dep:regex", Some("std"), false),
+    ]);
+
+    assert_eq!(graph.default, vec!["std", "json"]);
+    assert_eq!(graph.default_enabled, vec!["full-io", "json", "std"]);
+    assert_eq!(graph.default_dependencies, vec!["serde", "tokio"]);
+    assert_eq!(graph.unresolved, vec!["missing"]);
+
+    assert!(feature_graph("[features]\nx = \"not a list\"\n").is_err());
+    assert_eq!(feature_graph("[package]\nname = \"bare\"\n").unwrap(), Default::default());
+}
+
+#[tokio::test]
+async fn test_feature_graph_command() {
+    let dir = ScratchDir::new("feature-graph");
+    dir.write("Cargo.toml", &format!("{}\n[workspace]\n", MANIFEST));
+    dir.write("src/lib.rs", "");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "feature_graph", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["file"], "Cargo.toml");
+    assert_eq!(result["default_enabled"], json!(["full-io", "json", "std"]));
+    assert_eq!(result["edges"][6]["weak"], true);
+    assert!(result["edges"][0].get("weak").is_none());
+}
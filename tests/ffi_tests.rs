@@ -0,0 +1,95 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::ffi::scan_source;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const SOURCE: &str = r#"use std::os::raw::c_int;
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct Point {
+    x: c_int,
+    y: c_int,
+}
+
+#[repr(u8)]
+pub enum Tag { A }
+
+#[repr(C, u8)]
+pub enum Shape { Dot(Point) }
+
+extern "C" {
+    pub fn abs(x: c_int) -> c_int;
+    #[link_name = "c_errno"]
+    static mut errno: c_int;
+    fn qsort(base: *mut u8, compare: extern "C" fn(*const u8, *const u8) -> c_int);
+}
+
+unsafe extern "system" {
+    pub safe fn GetTickCount() -> u32;
+}
+
+/// Called from C: `extern "C" fn` in a comment is skipped
+#[no_mangle]
+pub extern "C" fn point_new(x: c_int, y: c_int) -> Point {
+    let _ = "extern \"C\" { fn fake(); }";
+    Point { x, y }
+}
+
+#[unsafe(export_name = "lib_version")]
+pub fn version() -> u32 { 1 }
+
+pub(crate) unsafe extern fn callback(_: *mut u8) {}
+
+extern crate alloc;
+
+#[no_mangle]
+pub static API_LEVEL: u32 = 3;
+"#;
+
+/// Line, kind, name, direction, abi and symbol
+type Entry<'a> = (usize, &'a str, Option<&'a str>, &'a str, Option<&'a str>, Option<&'a str>);
+
+#[test]
+fn test_scan_source_maps_the_ffi_boundary() {
+    let items = scan_source(SOURCE);
+    let summary: Vec<Entry> = items.iter()
+        .map(|i| (i.line, i.kind.as_str(), i.name.as_deref(), i.direction.as_str(), i.abi.as_deref(), i.symbol.as_deref()))
+        .collect();
+    assert_eq!(summary, vec![
+        (5, "struct", Some("Point"), "shared", None, None),
+        (14, "enum", Some("Shape"), "shared", None, None),
+        (16, "extern_block", None, "imported", Some("C"), None),
+        (17, "function", Some("abs"), "imported", Some("C"), None),
+        (19, "static", Some("errno"), "imported", Some("C"), Some("c_errno")),
+        (20, "function", Some("qsort"), "imported", Some("C"), None),
+        (23, "extern_block", None, "imported", Some("system"), None),
+        (24, "function", Some("GetTickCount"), "imported", Some("system"), None),
+        (29, "function", Some("point_new"), "exported", Some("C"), None),
+        (35, "function", Some("version"), "exported", None, Some("lib_version")),
+        (37, "function", Some("callback"), "exported", Some("C"), None),
+        (42, "static", Some("API_LEVEL"), "exported", None, None),
+    ]);
+    assert_eq!(items[6].code, "unsafe extern \"system\" {");
+    assert_eq!(items[10].code, "pub(crate) unsafe extern fn callback(_: *mut u8) {}");
+}
+
+#[tokio::test]
+async fn test_find_ffi_command() {
+    let dir = ScratchDir::with_crate("find-ffi", "");
+    dir.write("src/lib.rs", "pub mod sys;\n");
+    dir.write("src/sys.rs", SOURCE);
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "find_ffi", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!((result["files_scanned"].as_u64(), result["total"].as_u64()), (Some(2), Some(12)), "{result}");
+    assert_eq!((result["exported"].as_u64(), result["imported"].as_u64(), result["shared"].as_u64()), (Some(4), Some(6), Some(2)));
+    assert_eq!(result["items"][0]["file"], "src/sys.rs");
+    assert!(result["items"][2].get("name").is_none());
+}
@@ -0,0 +1,76 @@
+use mcp_rust_analyzer::file_summary::summarize;
+
+#[test]
+fn test_summarize_public_api() {
+    let source = r#"//! Widgets and helpers.
+//! Second line.
+
+use std::fmt;
+
+/// A widget.
+#[derive(Debug)]
+pub struct Widget {
+    id: u32,
+}
+
+struct Hidden;
+
+pub const LIMIT: usize = 10;
+
+pub(crate) fn internal() {}
+
+impl Widget {
+    /// Make one.
+    pub fn new(id: u32) -> Self {
+        Self { id }
+    }
+
+    fn secret(&self) {}
+}
+
+impl fmt::Display for Widget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+pub async fn fetch(
+    id: u32,
+) -> Option<Widget> {
+    None
+}
+"#;
+
+    let summary = summarize(source);
+    assert_eq!(summary.module_doc.as_deref(), Some("Widgets and helpers.\nSecond line."));
+
+    let names: Vec<(&str, &str)> = summary.public_items.iter()
+        .map(|i| (i.kind.as_str(), i.name.as_str()))
+        .collect();
+    assert_eq!(names, vec![
+        ("struct", "Widget"),
+        ("constant", "LIMIT"),
+        ("impl", "Widget"),
+        ("impl", "fmt::Display for Widget"),
+        ("function", "fetch"),
+    ]);
+
+    let widget = &summary.public_items[0];
+    assert_eq!(widget.signature, "pub struct Widget");
+    assert_eq!(widget.doc.as_deref(), Some("A widget."));
+    assert_eq!(summary.public_items[1].signature, "pub const LIMIT: usize");
+
+    let inherent = &summary.public_items[2];
+    assert_eq!(inherent.methods.len(), 1);
+    assert_eq!(inherent.methods[0].signature, "pub fn new(id: u32) -> Self");
+    assert_eq!(inherent.methods[0].doc.as_deref(), Some("Make one."));
+
+    // Trait impl methods are part of the API even without `pub`
+    assert_eq!(summary.public_items[3].methods[0].name, "fmt");
+
+    assert_eq!(summary.public_items[4].signature, "pub async fn fetch(id: u32) -> Option<Widget>");
+
+    assert_eq!(summary.private_counts.get("struct"), Some(&1));
+    assert_eq!(summary.private_counts.get("function"), Some(&1));
+    assert_eq!(summary.private_counts.get("method"), Some(&1));
+}
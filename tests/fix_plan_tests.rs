@@ -0,0 +1,81 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::fix_plan::build_plan;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+fn compiler_message(package: &str, level: &str, code: &str, message: &str, line: u64, label: Option<&str>) -> Value {
+    json!({
+        "reason": "compiler-message",
+        "package_id": format!("path+file:///w/{}#0.1.0", package),
+        "message": {
+            "level": level,
+            "message": message,
+            "code": { "code": code },
+            "spans": [{ "file_name": "src/lib.rs", "line_start": line, "column_start": 5, "is_primary": true, "label": label }],
+            "children": []
+        }
+    })
+}
+
+#[test]
+fn test_build_plan_clusters_and_orders() {
+    let messages = vec![
+        compiler_message("core", "warning", "unused_variables", "unused variable: `x`", 1, None),
+        compiler_message("core", "error", "E0308", "mismatched types", 2, Some("expected `u32`, found `&str`")),
+        compiler_message("core", "error", "E0412", "cannot find type `Config` in this scope", 7, None),
+        compiler_message("core", "error", "E0412", "cannot find type `Config` in this scope", 3, None),
+        compiler_message("app", "error", "E0425", "cannot find value `y` in this scope", 1, None),
+        json!({ "reason": "compiler-message", "message": { "level": "error", "message": "aborting due to 4 previous errors", "spans": [] } }),
+    ];
+
+    let plan = build_plan(&messages, true);
+    assert_eq!(plan.errors, 5);
+    assert_eq!(plan.warnings, 1);
+
+    let causes: Vec<&str> = plan.steps.iter().map(|s| s.cause.as_str()).collect();
+    assert_eq!(causes, vec![
+        "unresolved name `Config`",
+        "unresolved name `y`",
+        "type mismatch: expected `u32`, found `&str`",
+        "lint `unused_variables`",
+    ]);
+    assert_eq!(plan.steps[0].count, 2);
+    assert_eq!(plan.steps[0].package, "core");
+    assert_eq!(plan.steps[0].primary_span.as_ref().unwrap().line, 7);
+    assert_eq!(plan.steps[0].locations.len(), 1);
+    assert_eq!(plan.steps[1].package, "app");
+
+    assert_eq!(build_plan(&messages, false).steps.len(), 3);
+}
+
+#[tokio::test]
+async fn test_diagnose_reports_fix_plan() {
+    let dir = ScratchDir::with_crate("diagnose", "\n[workspace]\n");
+    dir.write(
+        "src/lib.rs",
+        "pub fn a() -> HashMap<u32, u32> {\n    HashMap::new()\n}\n\npub fn b() -> u32 {\n    \"one\"\n}\n",
+    );
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "diagnose",
+        "params": { "method": "diagnose", "max_steps": 1 }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+
+    assert_eq!(result["success"], false);
+    assert!(result["total_steps"].as_u64().unwrap() >= 2);
+    assert_eq!(result["steps"].as_array().unwrap().len(), 1);
+    let step = &result["steps"][0];
+    assert_eq!(step["cause"], "unresolved name `HashMap`");
+    assert_eq!(step["count"], 2);
+    assert_eq!(step["primary_span"]["line"], 1);
+    assert!(step["action"].as_str().unwrap().contains("HashMap"));
+}
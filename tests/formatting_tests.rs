@@ -0,0 +1,52 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::formatting::parse_diff;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::Path;
+
+#[test]
+fn test_parse_diff_groups_hunks_by_file() {
+    let output = "Diff in /w/src/a.rs:1:\n-pub fn a( ) {}\n+pub fn a() {}\n \nDiff in /w/src/lib.rs at line 3:\n-fn h(){}\n+fn h() {}\nDiff in /w/src/lib.rs:9:\n-let x=1;\n+let x = 1;\n";
+    let files = parse_diff(output, Path::new("/w"));
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].file, "src/a.rs");
+    assert_eq!(files[0].hunks[0].diff, "-pub fn a( ) {}\n+pub fn a() {}");
+    assert_eq!(files[1].file, "src/lib.rs");
+    assert_eq!(files[1].hunks.iter().map(|h| h.line).collect::<Vec<_>>(), vec![3, 9]);
+}
+
+#[tokio::test]
+async fn test_check_formatting_command() {
+    let dir = ScratchDir::with_crate("check-formatting", "\n[workspace]\n");
+    dir.write("src/lib.rs", "pub mod tidy;\npub mod messy;\n\nfn f( ){let x=1;}\n");
+    dir.write("src/tidy.rs", "pub fn tidy() {}\n");
+    dir.write("src/messy.rs", "pub async fn messy( ) {}\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "check_formatting", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()["result"].clone() }
+    };
+
+    let result = call(json!({})).await;
+    assert_eq!(result["formatted"], false, "{result}");
+    let files: Vec<&str> = result["files"].as_array().unwrap().iter().map(|f| f["file"].as_str().unwrap()).collect();
+    assert_eq!(files, vec!["src/lib.rs", "src/messy.rs"]);
+
+    // Only the file asked for, though rustfmt also walks its modules; `async fn` needs the 2021 edition
+    let result = call(json!({"file": "src/messy.rs"})).await;
+    assert_eq!(result["count"], 1, "{result}");
+    assert_eq!(result["files"][0]["hunks"][0]["diff"], "-pub async fn messy( ) {}\n+pub async fn messy() {}");
+    let result = call(json!({"file": "src/tidy.rs"})).await;
+    assert_eq!((result["formatted"].as_bool(), result["count"].as_u64()), (Some(true), Some(0)), "{result}");
+
+    dir.write("src/tidy.rs", "fn (\n");
+    let result = call(json!({"file": "src/tidy.rs"})).await;
+    assert_eq!(result["formatted"], false);
+    assert!(result["error"].as_str().unwrap().contains("unclosed delimiter"), "{result}");
+    assert!(std::fs::read_to_string(dir.join("src/messy.rs")).unwrap().contains("messy( )"));
+}
@@ -0,0 +1,80 @@
+use mcp_rust_analyzer::framing::{write_message, Framing, MessageReader, MAX_CONTENT_LENGTH};
+use std::io::Cursor;
+
+fn framed(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+fn next(reader: &mut MessageReader<Cursor<String>>) -> Option<String> {
+    reader.next_message().unwrap().map(|message| message.unwrap())
+}
+
+#[test]
+fn test_reads_lines_by_default() {
+    let mut reader = MessageReader::new(Cursor::new("{\"id\":1}\r\n\n{\"id\":2}\n{\"id\":3}".to_string()));
+    assert_eq!(next(&mut reader).as_deref(), Some("{\"id\":1}"));
+    assert_eq!(reader.framing(), Framing::Lines);
+    assert_eq!(next(&mut reader).as_deref(), Some("{\"id\":2}"));
+    assert_eq!(next(&mut reader).as_deref(), Some("{\"id\":3}"));
+    assert_eq!(reader.next_message().unwrap(), None);
+}
+
+#[test]
+fn test_reads_content_length_frames() {
+    // A body spanning lines, and one without a trailing newline
+    let first = "{\"id\":1,\n\"method\":\"tools/list\"}";
+    let second = "{\"id\":\"é\"}";
+    let input = format!("{}content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}", framed(first), second.len(), second);
+    let mut reader = MessageReader::new(Cursor::new(input));
+    assert_eq!(next(&mut reader).as_deref(), Some(first));
+    assert_eq!(reader.framing(), Framing::ContentLength);
+    assert_eq!(next(&mut reader).as_deref(), Some(second));
+    assert_eq!(reader.next_message().unwrap(), None);
+}
+
+#[test]
+fn test_each_message_picks_its_framing() {
+    let input = format!("{}\n{{\"id\":2}}\n", framed("{\"id\":1}"));
+    let mut reader = MessageReader::new(Cursor::new(input));
+    next(&mut reader);
+    assert_eq!(reader.framing(), Framing::ContentLength);
+    assert_eq!(next(&mut reader).as_deref(), Some("{\"id\":2}"));
+    assert_eq!(reader.framing(), Framing::Lines);
+}
+
+#[test]
+fn test_broken_frames_are_skipped() {
+    // An unreadable length, then a body running into the next header
+    let input = format!("Content-Length: ten\r\n\r\n{{}}{}", framed("{\"id\":2}"));
+    let mut reader = MessageReader::new(Cursor::new(input));
+    let invalid = reader.next_message().unwrap().unwrap().unwrap_err();
+    assert!(invalid.reason.contains("ten"));
+    assert_eq!(invalid.response()["error"]["code"], -32700);
+    assert_eq!(reader.framing(), Framing::ContentLength);
+    assert_eq!(next(&mut reader).as_deref(), Some("{\"id\":2}"));
+    assert_eq!(reader.next_message().unwrap(), None);
+
+    // A length too large to buffer is never allocated
+    let input = format!("Content-Length: {}\r\n\r\n{{}}\n{}", usize::MAX, framed("{\"id\":3}"));
+    let mut reader = MessageReader::new(Cursor::new(input));
+    assert!(reader.next_message().unwrap().unwrap().unwrap_err().reason.contains("exceeds"));
+    assert_eq!(next(&mut reader).as_deref(), Some("{\"id\":3}"));
+    let input = format!("Content-Length: {}\r\n\r\n{{}}", MAX_CONTENT_LENGTH + 1);
+    assert!(MessageReader::new(Cursor::new(input)).next_message().unwrap().unwrap().is_err());
+
+    // Input ending inside a frame
+    for input in ["Content-Length: 2\r\n", "Content-Length: 20\r\n\r\n{}"] {
+        let mut reader = MessageReader::new(Cursor::new(input.to_string()));
+        assert!(reader.next_message().unwrap().unwrap().is_err());
+        assert_eq!(reader.next_message().unwrap(), None);
+    }
+}
+
+#[test]
+fn test_write_message() {
+    let mut out = Vec::new();
+    write_message(&mut out, Framing::Lines, "{\"id\":1}").unwrap();
+    write_message(&mut out, Framing::ContentLength, "{\"id\":\"é\"}").unwrap();
+    write_message(&mut out, Framing::ContentLength, "").unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "{\"id\":1}\nContent-Length: 11\r\n\r\n{\"id\":\"é\"}");
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::generics::{item_header_at, parse_generics};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_parse_fn_generics() {
+    let generics = parse_generics(
+        "pub fn fold<'a, 'b: 'a, T: Clone + Send + 'a, F, const N: usize = 4>(items: &'a [T; N], f: F) -> T where F: Fn(T, &'b T) -> T, T::Owned: Default",
+    ).unwrap();
+
+    assert_eq!(generics.item_kind, "fn");
+    assert_eq!(generics.name, "fold");
+    assert_eq!(generics.rendered, "<'a, 'b: 'a, T: Clone + Send + 'a, F, const N: usize = 4>");
+
+    let lifetimes: Vec<(&str, Vec<String>)> = generics.lifetimes.iter().map(|l| (l.name.as_str(), l.bounds.clone())).collect();
+    assert_eq!(lifetimes, vec![("'a", vec![]), ("'b", vec!["'a".to_string()])]);
+    assert_eq!(generics.type_params[0].name, "T");
+    assert_eq!(generics.type_params[0].bounds, vec!["Clone", "Send", "'a"]);
+    assert!(generics.type_params[1].bounds.is_empty());
+    assert_eq!(generics.const_params[0].name, "N");
+    assert_eq!(generics.const_params[0].ty.as_deref(), Some("usize"));
+    assert_eq!(generics.const_params[0].default.as_deref(), Some("4"));
+
+    assert_eq!(generics.where_clause.as_deref(), Some("where F: Fn(T, &'b T) -> T, T::Owned: Default"));
+    assert_eq!(generics.where_predicates[0].bounded, "F");
+    assert_eq!(generics.where_predicates[0].bounds, vec!["Fn(T, &'b T) -> T"]);
+    assert_eq!(generics.where_predicates[1].bounded, "T::Owned");
+}
+
+#[test]
+fn test_item_headers() {
+    let source = "\
+/// A cache
+#[derive(Debug)]
+pub(crate) struct Cache<K: Eq + Hash, V = String>
+where
+    V: Clone,
+{
+    map: HashMap<K, V>,
+}
+
+impl<K, V> Display for Cache<K, V> where K: Display {
+}
+
+fn plain() {}
+";
+    let header = item_header_at(source, 0).unwrap();
+    assert_eq!(header, "pub(crate) struct Cache<K: Eq + Hash, V = String> where V: Clone,");
+    let cache = parse_generics(&header).unwrap();
+    assert_eq!(cache.item_kind, "struct");
+    assert_eq!(cache.type_params[1].default.as_deref(), Some("String"));
+    assert_eq!(cache.where_predicates[0].bounds, vec!["Clone"]);
+
+    let display = parse_generics(&item_header_at(source, 9).unwrap()).unwrap();
+    assert_eq!(display.item_kind, "impl");
+    assert_eq!(display.name, "Display for Cache<K, V>");
+    assert_eq!(display.rendered, "<K, V>");
+
+    let plain = parse_generics(&item_header_at(source, 12).unwrap()).unwrap();
+    assert_eq!(plain.rendered, "");
+    assert!(plain.type_params.is_empty() && plain.where_clause.is_none());
+
+    assert!(item_header_at(source, 6).is_none(), "a field is not an item");
+}
+
+#[tokio::test]
+async fn test_generics_of_command() {
+    let dir = ScratchDir::with_crate("generics", "\n[workspace]\n");
+    dir.write("src/lib.rs", "pub fn first<'a, T>(items: &'a [T]) -> Option<&'a T>\nwhere\n    T: PartialEq,\n{\n    items.first()\n}\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "generics_of",
+        "params": { "method": "generics_of", "file": "src/lib.rs", "line": 1, "column": 8 }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["name"], "first");
+    assert_eq!(result["file"], "src/lib.rs");
+    assert_eq!(result["rendered"], "<'a, T>");
+    assert_eq!(result["lifetimes"][0]["name"], "'a");
+    assert_eq!(result["where_predicates"][0]["bounded"], "T");
+}
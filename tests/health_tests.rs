@@ -0,0 +1,33 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::health::check_cargo;
+
+#[tokio::test]
+async fn test_cargo_health_probe() {
+    let health = check_cargo(std::path::Path::new(".")).await;
+    assert!(health.is_healthy(), "cargo should work in the repo root: {:?}", health.error);
+    assert_eq!(health.package_name.as_deref(), Some("mcp-rust-analyzer"));
+    
+    // A directory without a manifest must be reported as broken
+    let health = check_cargo(&std::env::temp_dir()).await;
+    assert!(!health.metadata_ok);
+    assert!(health.error.is_some());
+}
+
+#[tokio::test]
+async fn test_virtual_workspace_has_no_package_name() {
+    let dir = ScratchDir::new("health-virtual");
+    dir.write_all(&[
+        ("Cargo.toml", "[workspace]\nmembers = [\"a\", \"b\"]\n"),
+        ("a/Cargo.toml", "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"),
+        ("a/src/lib.rs", ""),
+        ("b/Cargo.toml", "[package]\nname = \"b\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"),
+        ("b/src/lib.rs", ""),
+    ]);
+
+    let health = check_cargo(&dir).await;
+    assert!(health.is_healthy(), "{:?}", health.error);
+    assert_eq!(health.package_name, None);
+    assert_eq!(health.workspace_members, vec!["a", "b"]);
+}
@@ -0,0 +1,227 @@
+use mcp_rust_analyzer::http_client::{DaemonError, HttpClient};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn test_connection_refused_is_retried() {
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    tokio::spawn(async move {
+        // Nothing listens on the port for the first attempt, as while the daemon restarts
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buffer = [0u8; 4096];
+        let _ = stream.read(&mut buffer).await.unwrap();
+        let body = r#"{"jsonrpc":"2.0","id":0,"result":{"tools":[]}}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(), body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+    });
+
+    let client = HttpClient::new(port);
+    let response = client.handle_jsonrpc_request(r#"{"jsonrpc":"2.0","id":7,"method":"tools/list"}"#).await.unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["result"]["tools"], json!([]));
+}
+
+#[tokio::test]
+async fn test_dropped_connection_is_not_retried() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let accepted = tokio::spawn(async move {
+        let mut accepted = 0;
+        // The daemon may already have acted on the request
+        while let Ok(Ok((mut stream, _))) = tokio::time::timeout(std::time::Duration::from_secs(1), listener.accept()).await {
+            let mut buffer = [0u8; 4096];
+            let _ = stream.read(&mut buffer).await;
+            accepted += 1;
+        }
+        accepted
+    });
+
+    let client = HttpClient::new(port);
+    let error = client.handle_jsonrpc_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).await.unwrap_err();
+    assert!(matches!(error.downcast_ref::<DaemonError>(), Some(DaemonError::Unreachable { .. })), "{error}");
+    assert_eq!(accepted.await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_missing_daemon_and_error_status_are_distinct() {
+    let port = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let client = HttpClient::new(port);
+    let error = client.handle_jsonrpc_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).await.unwrap_err();
+    assert!(matches!(error.downcast_ref::<DaemonError>(), Some(DaemonError::Unreachable { .. })), "{error}");
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buffer = [0u8; 4096];
+        let _ = stream.read(&mut buffer).await.unwrap();
+        let response = "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 4\r\nConnection: close\r\n\r\nboom";
+        stream.write_all(response.as_bytes()).await.unwrap();
+    });
+    let client = HttpClient::new(port);
+    let error = client.handle_jsonrpc_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).await.unwrap_err();
+    match error.downcast_ref::<DaemonError>() {
+        Some(DaemonError::Status { status, body }) => assert_eq!((*status, body.as_str()), (500, "boom")),
+        _ => panic!("expected an error status, got {error}"),
+    }
+}
+
+#[tokio::test]
+async fn test_malformed_request_is_answered_without_the_daemon() {
+    // Nothing listens on the port; the line never gets that far
+    let client = HttpClient::new(1);
+    let response = client.handle_jsonrpc_request(r#"{"jsonrpc":"2.0","id":5,"method":"tools/"#).await.unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["id"], 5);
+    assert_eq!(response["error"]["code"], -32700);
+}
+
+#[tokio::test]
+async fn test_stop_daemon_shuts_the_server_down() {
+    use mcp_rust_analyzer::config::Config;
+    use mcp_rust_analyzer::http_server::start_http_server;
+    use mcp_rust_analyzer::server::McpServer;
+
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+    let running = tokio::spawn(start_http_server(server, port));
+
+    let client = HttpClient::new(port);
+    for _ in 0..50 {
+        if client.is_server_running().await {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert!(client.is_server_running().await);
+
+    client.stop_daemon().await.unwrap();
+    assert!(!client.is_server_running().await);
+    let stopped = tokio::time::timeout(std::time::Duration::from_secs(10), running).await;
+    assert!(stopped.expect("server kept running").unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn test_client_categories_limit_a_daemon_started_with_more() {
+    use mcp_rust_analyzer::config::Config;
+    use mcp_rust_analyzer::http_server::start_http_server;
+    use mcp_rust_analyzer::server::McpServer;
+    use mcp_rust_analyzer::tool_access::ToolCategory;
+
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+    tokio::spawn(start_http_server(server, port));
+
+    let client = HttpClient::new(port).with_tool_categories(vec![ToolCategory::ReadOnly]);
+    for _ in 0..50 {
+        if client.is_server_running().await {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    let response = client.handle_jsonrpc_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).await.unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    let names: Vec<&str> = response["result"]["tools"].as_array().unwrap().iter().filter_map(|t| t["name"].as_str()).collect();
+    assert!(names.contains(&"get_hover"));
+    assert!(!names.contains(&"rename") && !names.contains(&"get_diagnostics"));
+
+    for request in [
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"apply_change","arguments":{}}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"run_example","params":{"name":"demo"}}"#,
+    ] {
+        let response = client.handle_jsonrpc_request(request).await.unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["id"], 2);
+        assert!(response["error"]["message"].as_str().unwrap().contains("is disabled"), "{response}");
+    }
+    client.stop_daemon().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_diagnostics_stream_needs_the_execute_category() {
+    use mcp_rust_analyzer::config::Config;
+    use mcp_rust_analyzer::http_server::start_http_server;
+    use mcp_rust_analyzer::server::McpServer;
+    use mcp_rust_analyzer::tool_access::ToolCategory;
+
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    let config = Config { use_lsp: false, tool_categories: vec![ToolCategory::ReadOnly], ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+    tokio::spawn(start_http_server(server, port));
+
+    let client = HttpClient::new(port);
+    for _ in 0..50 {
+        if client.is_server_running().await {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    let response = reqwest::Client::new()
+        .get(format!("http://127.0.0.1:{}/ws/diagnostics", port))
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+        .send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(response.text().await.unwrap().contains("execute"));
+    client.stop_daemon().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_batches_are_forwarded_whole() {
+    use mcp_rust_analyzer::config::Config;
+    use mcp_rust_analyzer::http_server::start_http_server;
+    use mcp_rust_analyzer::server::McpServer;
+    use mcp_rust_analyzer::tool_access::ToolCategory;
+
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+    tokio::spawn(start_http_server(server, port));
+
+    let client = HttpClient::new(port).with_tool_categories(vec![ToolCategory::ReadOnly]);
+    for _ in 0..50 {
+        if client.is_server_running().await {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    let batch = json!([
+        {"jsonrpc": "2.0", "id": "a", "method": "tools/list"},
+        {"jsonrpc": "2.0", "method": "notifications/initialized"},
+        {"jsonrpc": "2.0", "id": "b", "method": "tools/call", "params": {"name": "apply_change", "arguments": {}}},
+        {"jsonrpc": "2.0", "id": "c", "method": "expand_snippet", "params": {"name": "if_let"}}
+    ]);
+    let response = client.handle_jsonrpc_request(&batch.to_string()).await.unwrap();
+    let responses: Vec<serde_json::Value> = serde_json::from_str(&response).unwrap();
+    let ids: Vec<&str> = responses.iter().filter_map(|r| r["id"].as_str()).collect();
+    assert_eq!(ids, vec!["a", "b", "c"]);
+    let names: Vec<&str> = responses[0]["result"]["tools"].as_array().unwrap().iter().filter_map(|t| t["name"].as_str()).collect();
+    assert!(!names.is_empty() && !names.contains(&"rename"));
+    assert!(responses[1]["error"]["message"].as_str().unwrap().contains("is disabled"));
+    assert_eq!(responses[2]["result"]["name"], "if_let");
+
+    // Only notifications: nothing to write back
+    let notifications = json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}]);
+    assert_eq!(client.handle_jsonrpc_request(&notifications.to_string()).await.unwrap(), "");
+
+    let response: serde_json::Value = serde_json::from_str(&client.handle_jsonrpc_request("[]").await.unwrap()).unwrap();
+    assert_eq!(response["error"]["code"], -32600);
+    client.stop_daemon().await.unwrap();
+}
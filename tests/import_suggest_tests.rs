@@ -0,0 +1,142 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::import_suggest::{identifier_at, imported_paths, insert_use_edit, symbol_use_path, unresolved_name, NameKind};
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::workspace_edit::apply_edits_to_text;
+use serde_json::{json, Value};
+
+#[test]
+fn test_unresolved_name_from_messages() {
+    assert_eq!(unresolved_name("cannot find type `HashMap` in this scope"), Some((NameKind::Type, "HashMap".to_string())));
+    assert_eq!(unresolved_name("cannot find function `parse_config` in this scope"), Some((NameKind::Value, "parse_config".to_string())));
+    assert_eq!(unresolved_name("cannot find macro `json` in this scope"), Some((NameKind::Macro, "json".to_string())));
+    assert_eq!(unresolved_name("failed to resolve: use of undeclared type `Arc`"), Some((NameKind::Type, "Arc".to_string())));
+    assert_eq!(unresolved_name("mismatched types"), None);
+}
+
+#[test]
+fn test_identifier_at_position() {
+    let source = "fn f() {\n    let m = HashMap::new();\n}\n";
+    assert_eq!(identifier_at(source, 1, 12), Some("HashMap".to_string()));
+    // Just past the end of the identifier still counts
+    assert_eq!(identifier_at(source, 1, 9), Some("m".to_string()));
+    assert_eq!(identifier_at(source, 1, 0), None);
+}
+
+#[test]
+fn test_insert_use_edit_placement() {
+    let with_imports = "use std::fmt;\nuse std::io::{\n    Read,\n};\n\nfn f() {}\n";
+    let edit = insert_use_edit(with_imports, "std::sync::Arc");
+    assert_eq!(
+        apply_edits_to_text(with_imports, &[edit]).unwrap(),
+        "use std::fmt;\nuse std::io::{\n    Read,\n};\nuse std::sync::Arc;\n\nfn f() {}\n"
+    );
+
+    let with_docs = "//! Module docs\n#![allow(dead_code)]\n\nfn f() {}\n";
+    let edit = insert_use_edit(with_docs, "std::sync::Arc");
+    assert_eq!(
+        apply_edits_to_text(with_docs, &[edit]).unwrap(),
+        "//! Module docs\n#![allow(dead_code)]\n\nuse std::sync::Arc;\n\nfn f() {}\n"
+    );
+
+    let bare = "fn f() {}\n";
+    let edit = insert_use_edit(bare, "std::sync::Arc");
+    assert_eq!(apply_edits_to_text(bare, &[edit]).unwrap(), "use std::sync::Arc;\n\nfn f() {}\n");
+}
+
+#[tokio::test]
+async fn test_resolve_unresolved_ranks_workspace_and_std() {
+    let root = ScratchDir::with_crate("import-suggest", "");
+    root.write("src/lib.rs", "pub mod storage;\npub mod app;\n\npub use storage::cache::Cache;\n");
+    root.write("src/storage/mod.rs", "pub mod cache;\n");
+    root.write("src/storage/cache.rs", "pub struct Cache;\n\npub fn Rc() {}\n");
+    root.write("src/app.rs", "use std::fmt;\n\nfn run() {\n    let c = Cache;\n    let r: Rc<u8> = todo!();\n}\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "resolve_unresolved", "params": params});
+        let server = &server;
+        async move {
+            let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+            response["result"].clone()
+        }
+    };
+
+    // The re-exported path is shorter and ranks above the defining module
+    let result = call(json!({
+        "file": "src/app.rs",
+        "diagnostic": { "message": "cannot find struct, variant or union type `Cache` in this scope", "line": 4, "column": 13 }
+    })).await;
+    let paths: Vec<&str> = result["suggestions"].as_array().unwrap().iter().map(|s| s["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec!["crate::Cache", "crate::storage::cache::Cache"]);
+    assert_eq!(result["suggestions"][0]["edit"]["range"]["start"]["line"], 1);
+    assert_eq!(result["suggestions"][0]["edit"]["newText"], "use crate::Cache;\n");
+
+    // A type lookup skips the workspace function of the same name
+    let result = call(json!({ "file": "src/app.rs", "message": "cannot find type `Rc` in this scope" })).await;
+    let paths: Vec<&str> = result["suggestions"].as_array().unwrap().iter().map(|s| s["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec!["std::rc::Rc"]);
+
+    // Without a message the name comes from the position
+    let result = call(json!({ "file": "src/app.rs", "line": 5, "column": 12 })).await;
+    assert_eq!(result["name"], "Rc");
+    assert_eq!(result["suggestions"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_symbol_use_path_from_the_defining_file() {
+    let root = std::path::Path::new("/work/app");
+    let symbol = |uri: &str, container: &str| json!({
+        "name": "Value", "kind": 10, "containerName": container, "location": { "uri": uri }
+    });
+
+    assert_eq!(symbol_use_path(&symbol("file:///work/app/src/model/mod.rs", "model"), root).as_deref(), Some("crate::model::Value"));
+    assert_eq!(
+        symbol_use_path(&symbol("file:///home/u/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde_json-1.0.128/src/value/mod.rs", "value"), root).as_deref(),
+        Some("serde_json::value::Value")
+    );
+    assert_eq!(
+        symbol_use_path(&symbol("file:///rust/lib/rustlib/src/rust/library/alloc/src/vec/mod.rs", "vec"), root).as_deref(),
+        Some("alloc::vec::Value")
+    );
+    // No crate source to go by
+    assert_eq!(symbol_use_path(&symbol("file:///tmp/scratch.rs", "scratch"), root).as_deref(), Some("scratch::Value"));
+    assert_eq!(symbol_use_path(&symbol("file:///tmp/scratch.rs", ""), root), None);
+}
+
+#[test]
+fn test_imported_paths() {
+    let paths = imported_paths("use std::collections::{HashMap, hash_map::Entry};\nuse std::io::{self, Read as _};\n\nfn f() {}\n");
+    for path in ["std::collections::HashMap", "std::collections::hash_map::Entry", "std::io", "std::io::Read"] {
+        assert!(paths.contains(path), "{path} missing from {paths:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_resolve_import_skips_paths_the_file_imports() {
+    let root = ScratchDir::with_crate("resolve-import", "");
+    root.write("src/lib.rs", "pub mod app;\n");
+    root.write("src/app.rs", "use std::sync::Arc;\n\nfn run() {}\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "resolve_import", "params": params});
+        let server = &server;
+        async move {
+            let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+            response["result"].clone()
+        }
+    };
+
+    let result = call(json!({ "symbol": "HashMap" })).await;
+    assert_eq!(result["imports"][0]["use"], "use std::collections::HashMap;");
+    assert!(result.get("already_imported").is_none());
+
+    let result = call(json!({ "symbol": "Arc", "file": "src/app.rs" })).await;
+    assert_eq!(result["imports"], json!([]));
+    assert_eq!(result["already_imported"], json!(["std::sync::Arc"]));
+}
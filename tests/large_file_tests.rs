@@ -0,0 +1,59 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::{Config, DEFAULT_MAX_LSP_FILE_SIZE};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+async fn call(server: &McpServer, method: &str, params: Value) -> Value {
+    let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    response["result"].clone()
+}
+
+#[tokio::test]
+async fn test_position_commands_skip_oversized_files() {
+    let root = ScratchDir::with_crate("large-file", "");
+    let mut bindings = String::from("pub fn first(a: u32) -> u32 {\n    a\n}\n");
+    for i in 0..200 {
+        bindings.push_str(&format!("pub const BINDING_{}: u32 = {};\n", i, i));
+    }
+    root.write("src/bindings.rs", &bindings);
+    root.write("src/lib.rs", "pub mod bindings;\n");
+
+    // LSP stays enabled: the cap must keep it from ever being started for this file
+    let config = Config { max_lsp_file_size: 1024, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let position = json!({"file": "src/bindings.rs", "line": 1, "column": 8});
+
+    for method in ["get_hover", "complete", "find_references", "signature_help", "find_implementations"] {
+        let result = call(&server, method, position.clone()).await;
+        assert_eq!(result["skipped"], true, "{}: {}", method, result);
+        assert_eq!(result["reason"], "file too large for LSP analysis");
+        assert_eq!(result["file_size"], bindings.len() as u64);
+        assert_eq!(result["max_lsp_file_size"], 1024);
+    }
+
+    // Commands with a text fallback still answer
+    let result = call(&server, "signature_at", position.clone()).await;
+    assert_eq!(result["source"], "source");
+    assert_eq!(result["signature"]["name"], "first");
+
+    // Tools with a text search fallback use it, and say so
+    let result = call(&server, "blast_radius", position.clone()).await;
+    assert_eq!(result["method"], "text_search", "{}", result);
+    let mut named = position.clone();
+    named["name"] = json!("first");
+    let result = call(&server, "related_tests", named).await;
+    assert_eq!(result["method"], "text_search", "{}", result);
+
+    // Small files are unaffected
+    let result = call(&server, "get_hover", json!({"file": "src/lib.rs", "line": 1, "column": 9})).await;
+    assert!(result.get("skipped").is_none());
+}
+
+#[test]
+fn test_max_lsp_file_size_config() {
+    assert_eq!(Config::default().max_lsp_file_size, DEFAULT_MAX_LSP_FILE_SIZE);
+    assert_eq!(Config::parse("max_lsp_file_size = 0").unwrap().max_lsp_file_size, 0);
+}
@@ -0,0 +1,53 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::locations::{from_lsp, Location};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::Path;
+
+fn range(line: u64, character: u64) -> Value {
+    json!({ "start": { "line": line, "character": character }, "end": { "line": line, "character": character + 3 } })
+}
+
+fn location(file: &str, line: u32, column: u32) -> Location {
+    Location { file: file.to_string(), line, column }
+}
+
+#[test]
+fn test_from_lsp_normalizes_every_response_shape() {
+    let root = Path::new("/work/project");
+    assert!(from_lsp(&Value::Null, root).is_empty());
+
+    let single = json!({ "uri": "file:///work/project/src/lib.rs", "range": range(9, 4) });
+    assert_eq!(from_lsp(&single, root), vec![location("src/lib.rs", 10, 5)]);
+
+    let list = json!([
+        { "uri": "file:///work/project/src/a.rs", "range": range(0, 0) },
+        { "uri": "file:///rustlib/src/core/option.rs", "range": range(571, 9) },
+    ]);
+    assert_eq!(from_lsp(&list, root), vec![location("src/a.rs", 1, 1), location("/rustlib/src/core/option.rs", 572, 10)]);
+
+    // Links report the name, not the whole item
+    let links = json!([{
+        "targetUri": "file:///work/project/src/b.rs",
+        "targetRange": range(2, 0),
+        "targetSelectionRange": range(3, 11),
+    }]);
+    assert_eq!(from_lsp(&links, root), vec![location("src/b.rs", 4, 12)]);
+
+    assert!(from_lsp(&json!([{ "uri": "file:///work/project/src/c.rs" }]), root).is_empty());
+}
+
+#[tokio::test]
+async fn test_goto_definition_without_lsp_is_empty() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config("tests/test_project", config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "goto_definition",
+        "params": { "file": "src/lib.rs", "line": 1, "column": 1 }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["definitions"], json!([]), "{response}");
+    assert_eq!(result["position"], json!({ "line": 1, "column": 1 }));
+}
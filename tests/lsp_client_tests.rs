@@ -1,6 +1,6 @@
 // TDD tests for LSP client integration with rust-analyzer
 
-use mcp_rust_analyzer::lsp_client::{LspClient, LspClientConfig};
+use mcp_rust_analyzer::lsp_client::{LspClient, LspClientConfig, DEFAULT_REQUEST_TIMEOUT};
 use serde_json::json;
 use std::path::PathBuf;
 
@@ -11,6 +11,7 @@ async fn test_lsp_client_creation() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project"),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     // When creating an LSP client
@@ -27,6 +28,7 @@ async fn test_lsp_client_initialize() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project"),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     let mut client = LspClient::new(config).unwrap();
@@ -47,6 +49,7 @@ async fn test_lsp_client_shutdown() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project"),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     let mut client = LspClient::new(config).unwrap();
@@ -66,6 +69,7 @@ async fn test_lsp_text_document_hover() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project"),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     let mut client = LspClient::new(config).unwrap();
@@ -95,6 +99,7 @@ async fn test_lsp_text_document_completion() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project"),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     let mut client = LspClient::new(config).unwrap();
@@ -126,6 +131,7 @@ async fn test_lsp_find_references() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project"),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     let mut client = LspClient::new(config).unwrap();
@@ -158,6 +164,7 @@ async fn test_lsp_rename() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project"),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     let mut client = LspClient::new(config).unwrap();
@@ -0,0 +1,82 @@
+use mcp_rust_analyzer::lsp_client::{DocumentSync, Documents};
+use serde_json::json;
+
+const URI: &str = "file:///p/src/lib.rs";
+
+#[test]
+fn test_successive_range_changes_bump_version() {
+    let mut documents = Documents::new();
+    assert_eq!(documents.open(URI, "fn a() {}\n".to_string()), DocumentSync::Open(1));
+
+    let rename = json!({ "range": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 4 } }, "text": "b" });
+    let append = json!({ "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 1, "character": 0 } }, "text": "fn c() {}\n" });
+    assert_eq!(documents.change(URI, &[rename]).unwrap(), 2);
+    // Later changes apply to the result of earlier ones
+    assert_eq!(documents.change(URI, &[append]).unwrap(), 3);
+    assert_eq!(documents.text(URI), Some("fn b() {}\nfn c() {}\n"));
+
+    assert!(documents.change("file:///p/src/other.rs", &[]).is_err());
+}
+
+#[test]
+fn test_reopen_keeps_edits_until_disk_changes() {
+    let mut documents = Documents::new();
+    documents.open(URI, "one\n".to_string());
+    documents.change(URI, &[json!({ "text": "edited\n" })]).unwrap();
+
+    assert_eq!(documents.open(URI, "one\n".to_string()), DocumentSync::Unchanged);
+    assert_eq!(documents.text(URI), Some("edited\n"));
+
+    assert_eq!(documents.open(URI, "two\n".to_string()), DocumentSync::Reload(3));
+    assert_eq!(documents.text(URI), Some("two\n"));
+
+    documents.close(URI);
+    assert_eq!(documents.version(URI), None);
+    assert_eq!(documents.open(URI, "two\n".to_string()), DocumentSync::Open(1));
+}
+
+#[test]
+fn test_replace_takes_newer_versions_only() {
+    let mut documents = Documents::new();
+    documents.open(URI, "fn a() {}\n".to_string());
+
+    assert!(documents.replace(URI, 4, "fn unsaved() {}\n".to_string()).unwrap());
+    assert_eq!(documents.version(URI), Some(4));
+    // An update that arrives late doesn't roll the buffer back
+    assert!(!documents.replace(URI, 3, "fn older() {}\n".to_string()).unwrap());
+    assert!(!documents.replace(URI, 4, "fn same() {}\n".to_string()).unwrap());
+    assert_eq!(documents.text(URI), Some("fn unsaved() {}\n"));
+
+    // Unchanged on disk, so the unsaved buffer is kept; range changes build on it
+    assert_eq!(documents.open(URI, "fn a() {}\n".to_string()), DocumentSync::Unchanged);
+    assert_eq!(documents.change(URI, &[json!({ "text": "fn b() {}\n" })]).unwrap(), 5);
+
+    assert!(documents.replace("file:///p/src/other.rs", 2, String::new()).is_err());
+}
+
+#[test]
+fn test_first_sync_is_not_held_back_by_disk_versions() {
+    let mut documents = Documents::new();
+    documents.open(URI, "fn a() {}\n".to_string());
+    assert_eq!(documents.caller_version(URI), None);
+
+    // Version 1 from an editor is new, although the disk copy was opened as 1
+    assert!(documents.replace(URI, 1, "fn unsaved() {}\n".to_string()).unwrap());
+    assert_eq!(documents.caller_version(URI), Some(1));
+    assert!(!documents.replace(URI, 1, "fn same() {}\n".to_string()).unwrap());
+    assert_eq!(documents.text(URI), Some("fn unsaved() {}\n"));
+}
+
+#[test]
+fn test_open_buffer_takes_the_editors_version() {
+    let mut documents = Documents::new();
+    documents.open_buffer(URI, 0, "fn unsaved() {}\n".to_string(), "fn a() {}\n".to_string());
+    assert_eq!(documents.version(URI), Some(0));
+    assert_eq!(documents.text(URI), Some("fn unsaved() {}\n"));
+
+    // The disk copy hasn't changed, so the editor's buffer stays
+    assert_eq!(documents.open(URI, "fn a() {}\n".to_string()), DocumentSync::Unchanged);
+    assert!(!documents.replace(URI, 0, "fn stale() {}\n".to_string()).unwrap());
+    assert!(documents.replace(URI, 1, "fn newer() {}\n".to_string()).unwrap());
+    assert_eq!(documents.text(URI), Some("fn newer() {}\n"));
+}
@@ -0,0 +1,57 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::lsp_passthrough::{check_method, is_read_method};
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::tool_access::ToolCategory;
+use serde_json::{json, Value};
+
+#[test]
+fn test_check_method() {
+    assert!(is_read_method("textDocument/foldingRange"));
+    assert!(!is_read_method("textDocument/rename"));
+
+    let read_only = [ToolCategory::ReadOnly];
+    assert!(check_method("textDocument/hover", &read_only).is_ok());
+    assert!(check_method("workspace/executeCommand", &read_only).unwrap_err().to_string().contains("write category"));
+    assert!(check_method("workspace/executeCommand", &ToolCategory::ALL).is_ok());
+    assert!(check_method("shutdown", &ToolCategory::ALL).is_err());
+
+    assert!(!Config::default().lsp_passthrough);
+    assert!(Config::parse("lsp_passthrough = true").unwrap().lsp_passthrough);
+}
+
+async fn call(server: &McpServer, request: Value) -> Value {
+    serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()
+}
+
+async fn listed(server: &McpServer) -> bool {
+    let list = call(server, json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"})).await;
+    list["result"]["tools"].as_array().unwrap().iter().any(|tool| tool["name"] == "lsp_passthrough")
+}
+
+#[tokio::test]
+async fn test_lsp_passthrough_is_opt_in() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(env!("CARGO_MANIFEST_DIR"), config).await.unwrap();
+    assert!(!listed(&server).await);
+    let response = call(&server, json!({
+        "jsonrpc": "2.0", "id": 2, "method": "lsp_passthrough",
+        "params": {"lsp_method": "textDocument/foldingRange", "params": {}}
+    })).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("lsp_passthrough is disabled"));
+
+    let config = Config { use_lsp: false, lsp_passthrough: true, tool_categories: vec![ToolCategory::ReadOnly], ..Config::default() };
+    let server = McpServer::with_config(env!("CARGO_MANIFEST_DIR"), config).await.unwrap();
+    assert!(listed(&server).await);
+    let response = call(&server, json!({
+        "jsonrpc": "2.0", "id": 3, "method": "lsp_passthrough",
+        "params": {"lsp_method": "textDocument/rename", "params": {}}
+    })).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("not a read-only LSP request"));
+
+    // Read requests get as far as rust-analyzer, disabled here
+    let response = call(&server, json!({
+        "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+        "params": {"name": "lsp_passthrough", "arguments": {"lsp_method": "textDocument/foldingRange"}}
+    })).await;
+    assert_eq!(response["error"]["message"], "Tool execution failed: LSP not available");
+}
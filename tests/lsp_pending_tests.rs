@@ -0,0 +1,94 @@
+use mcp_rust_analyzer::lsp_client::{PendingRequests, RequestTimeout};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_requests_with_tight_timeouts() {
+    let pending = PendingRequests::new();
+    let mut callers = Vec::new();
+
+    for id in 0..500u64 {
+        let rx = pending.register(id).await;
+        let waiter = pending.clone();
+        callers.push(tokio::spawn(async move {
+            (id, waiter.wait(id, rx, Duration::from_millis(5)).await)
+        }));
+
+        // Responses land before, around and after the 5ms deadline
+        let responder = pending.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_micros((id % 10) * 1000)).await;
+            responder.dispatch(&json!({ "jsonrpc": "2.0", "id": id, "result": { "echo": id } })).await;
+        });
+    }
+
+    let mut delivered = 0;
+    for caller in callers {
+        let (id, result) = caller.await.expect("caller panicked");
+        match result {
+            Ok(value) => {
+                // Never someone else's response
+                assert_eq!(value["echo"], id);
+                delivered += 1;
+            }
+            Err(e) => {
+                assert_eq!(e.to_string(), "Request timeout");
+                assert_eq!(e.downcast_ref::<RequestTimeout>(), Some(&RequestTimeout { timeout: Duration::from_millis(5) }));
+            }
+        }
+    }
+    assert!(delivered > 0);
+
+    // Let stragglers arrive after their callers gave up; they must not linger
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(pending.is_empty().await);
+}
+
+#[tokio::test]
+async fn test_server_requests_are_not_routed_as_responses() {
+    let pending = PendingRequests::new();
+    let rx = pending.register(7).await;
+
+    // rust-analyzer may send its own request with an id that collides with ours
+    let routed = pending.dispatch(&json!({ "jsonrpc": "2.0", "id": 7, "method": "window/workDoneProgress/create", "params": {} })).await;
+    assert!(!routed);
+    assert_eq!(pending.len().await, 1);
+
+    assert!(pending.dispatch(&json!({ "jsonrpc": "2.0", "id": 7, "result": null })).await);
+    let value = pending.wait(7, rx, Duration::from_secs(1)).await.unwrap();
+    assert!(value.is_null());
+}
+
+#[tokio::test]
+async fn test_fail_all_wakes_waiters() {
+    let pending = PendingRequests::new();
+    let rx = pending.register(1).await;
+
+    let waiter = pending.clone();
+    let handle = tokio::spawn(async move { waiter.wait(1, rx, Duration::from_secs(30)).await });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    pending.fail_all().await;
+
+    let result = tokio::time::timeout(Duration::from_secs(1), handle).await.unwrap().unwrap();
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_partial_results_are_routed_by_token() {
+    let pending = PendingRequests::new();
+    let mut rx = pending.register_partial("workspace-symbol-3").await;
+
+    let progress = |token: &str| json!({
+        "jsonrpc": "2.0",
+        "method": "$/progress",
+        "params": { "token": token, "value": [{ "name": "Foo" }] }
+    });
+    assert!(pending.dispatch(&progress("workspace-symbol-3")).await);
+    assert_eq!(rx.recv().await.unwrap()[0]["name"], "Foo");
+
+    // Work-done progress for other tokens is still just a notification
+    assert!(!pending.dispatch(&progress("rustAnalyzer/Indexing")).await);
+
+    pending.forget_partial("workspace-symbol-3").await;
+    assert!(!pending.dispatch(&progress("workspace-symbol-3")).await);
+}
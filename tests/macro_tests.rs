@@ -0,0 +1,56 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::macros::{describe_definition, macro_name_at};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_macro_name_at_position() {
+    let source = "fn f() {\n    let v = my_vec![1, 2];\n}\n#[derive(Debug)]\nstruct S;\n";
+    assert_eq!(macro_name_at(source, 1, 12).as_deref(), Some("my_vec"));
+    // Cursor on the `!`
+    assert_eq!(macro_name_at(source, 1, 18).as_deref(), Some("my_vec"));
+    assert_eq!(macro_name_at(source, 3, 10).as_deref(), Some("Debug"));
+    // `v` is a plain identifier
+    assert_eq!(macro_name_at(source, 1, 8), None);
+}
+
+#[test]
+fn test_describe_definitions() {
+    let source = "#[macro_export]\nmacro_rules! square {\n    ($x:expr) => { $x * $x };\n}\n\n#[proc_macro_derive(Builder)]\npub fn builder(input: TokenStream) -> TokenStream {\n    input\n}\n";
+
+    let (kind, text) = describe_definition(source, 1);
+    assert_eq!(kind, "declarative");
+    assert_eq!(text.unwrap(), "macro_rules! square {\n    ($x:expr) => { $x * $x };\n}");
+
+    let (kind, text) = describe_definition(source, 6);
+    assert_eq!(kind, "proc_macro_derive");
+    assert!(text.is_none());
+}
+
+#[tokio::test]
+async fn test_macro_definition_falls_back_to_workspace_search() {
+    let root = ScratchDir::with_crate("macros", "");
+    root.write("src/macros.rs", "macro_rules! square {\n    ($x:expr) => { $x * $x };\n}\n");
+    root.write("src/lib.rs", "#[macro_use]\nmod macros;\n\npub fn four() -> u32 {\n    square!(2)\n}\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "macro_definition",
+        "params": {"method": "macro_definition", "file": "src/lib.rs", "line": 5, "column": 6}
+    });
+    let response: Value = serde_json::from_str(
+        &server.handle_request(&request.to_string()).await.unwrap()
+    ).unwrap();
+
+    let result = &response["result"];
+    assert_eq!(result["name"], "square");
+    assert_eq!(result["source"], "workspace_search");
+    assert_eq!(result["definitions"][0]["file"], "src/macros.rs");
+    assert_eq!(result["definitions"][0]["line"], 1);
+}
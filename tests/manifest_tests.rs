@@ -0,0 +1,121 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::manifest::{parse, workspace_members, Dependency, Workspace, WorkspaceMember};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const MANIFEST: &str = r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", features = [
+    "derive",
+    "rc",
+] }
+json = { package = "serde_json", version = "~1.0.100", optional = true }
+local = { path = "../local" }
+fork = { git = "https://example.com/fork.git", branch = "main" }
+tokio.workspace = true
+
+[dev-dependencies]
+tempfile = "3"
+
+[build-dependencies.cc]
+version = "1.0"
+"#;
+
+#[test]
+fn test_parse_normalizes_every_dependency_form() {
+    let manifest = parse(MANIFEST).unwrap();
+    let names: Vec<&str> = manifest.dependencies.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["fork", "json", "local", "serde", "tokio"]);
+
+    let get = |name: &str| manifest.dependencies.iter().find(|d| d.name == name).unwrap();
+    assert_eq!(get("serde").version.as_deref(), Some("1"));
+    assert_eq!(get("serde").features, vec!["derive", "rc"]);
+    assert!(get("json").optional);
+    assert_eq!(get("json").package(), "serde_json");
+    assert_eq!(get("local").path.as_deref(), Some("../local"));
+    assert_eq!(get("local").version, None);
+    assert_eq!(get("fork").git.as_deref(), Some("https://example.com/fork.git"));
+    assert!(get("tokio").workspace);
+
+    assert_eq!(manifest.dev_dependencies, vec![Dependency { name: "tempfile".to_string(), version: Some("3".to_string()), ..Dependency::default() }]);
+    assert_eq!(manifest.build_dependencies[0].version.as_deref(), Some("1.0"));
+    assert!(manifest.workspace.is_none());
+
+    assert!(parse("[dependencies\nserde = 1").is_err());
+}
+
+fn scratch_workspace(name: &str) -> ScratchDir {
+    let root = ScratchDir::new(&format!("manifest-{}", name));
+    for member in ["crates/core", "crates/cli", "crates/old", "tools/gen"] {
+        std::fs::create_dir_all(root.join(member).join("src")).unwrap();
+        std::fs::write(root.join(member).join("src/lib.rs"), "").unwrap();
+    }
+    root.write("crates/core/Cargo.toml", "[package]\nname = \"app-core\"\nversion = \"0.1.0\"\n");
+    for member in ["crates/cli", "crates/old", "tools/gen"] {
+        std::fs::write(root.join(member).join("Cargo.toml"), "").unwrap();
+    }
+    std::fs::create_dir_all(root.join("crates/notes")).unwrap();
+    root.write(
+        "Cargo.toml",
+        "[workspace]\nmembers = [\n    \"crates/*\",\n    \"tools/gen\",\n]\nexclude = [\"crates/old\"]\n",
+    );
+    root
+}
+
+#[test]
+fn test_workspace_members_expands_globs() {
+    let root = scratch_workspace("members");
+    let workspace = parse(&std::fs::read_to_string(root.join("Cargo.toml")).unwrap()).unwrap().workspace.unwrap();
+
+    let member = |name: &str, path: &str| WorkspaceMember { name: name.to_string(), path: path.to_string() };
+    assert_eq!(workspace_members(&root, &workspace), vec![
+        member("cli", "crates/cli"),
+        member("app-core", "crates/core"),
+        member("gen", "tools/gen"),
+    ]);
+    assert_eq!(workspace_members(&root, &Workspace::default()), Vec::new());
+}
+
+#[tokio::test]
+async fn test_project_structure_lists_member_modules() {
+    let root = scratch_workspace("structure");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "project_structure", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+
+    assert_eq!(result["type"], "workspace");
+    let members = result["members"].as_array().unwrap();
+    assert_eq!(members.iter().map(|m| m["name"].as_str().unwrap()).collect::<Vec<_>>(), vec!["cli", "app-core", "gen"]);
+    assert_eq!(members[1]["path"], "crates/core");
+    assert_eq!(members[1]["modules"][0]["name"], "lib.rs");
+}
+
+#[tokio::test]
+async fn test_analyze_dependencies_reports_normalized_entries() {
+    let root = ScratchDir::new("manifest-deps");
+    root.write("src/lib.rs", "");
+    root.write("Cargo.toml", MANIFEST);
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "analyze_dependencies", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+
+    assert_eq!(result["dependencies"]["serde"], json!({
+        "name": "serde", "version": "1", "features": ["derive", "rc"], "optional": false, "path": null, "git": null
+    }));
+    assert_eq!(result["dependencies"]["fork"]["git"], "https://example.com/fork.git");
+    assert_eq!(result["dependencies"]["json"]["package"], "serde_json");
+    assert_eq!(result["dev_dependencies"]["tempfile"]["version"], "3");
+    assert_eq!(result["build_dependencies"]["cc"]["version"], "1.0");
+}
@@ -0,0 +1,42 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::markup::{to_plaintext, truncate, ContentFormat};
+
+#[test]
+fn test_hover_markdown_to_plaintext() {
+    let hover = "\n```rust\nstd::vec::Vec\n```\n\n```rust\npub fn push(&mut self, value: T)\n```\n\n---\n\nAppends an element to the **back** of a [`Vec`](https://doc.rust-lang.org/std/vec/struct.Vec.html).\n\n# Panics\n\nPanics if the new capacity exceeds *`isize::MAX`* _bytes_.";
+    assert_eq!(
+        to_plaintext(hover),
+        "std::vec::Vec\n\npub fn push(&mut self, value: T)\n\nAppends an element to the back of a Vec.\n\nPanics\n\nPanics if the new capacity exceeds isize::MAX bytes."
+    );
+}
+
+#[test]
+fn test_plaintext_keeps_code_and_identifiers() {
+    // Fenced code is verbatim, even when it looks like markup
+    assert_eq!(to_plaintext("```\nlet x = *y_ptr; // **not bold**\n```"), "let x = *y_ptr; // **not bold**");
+    assert_eq!(to_plaintext("Calls `snake_case_fn` with v[0] and a * b"), "Calls snake_case_fn with v[0] and a * b");
+    assert_eq!(to_plaintext("Takes [u8; 4] and a [`Path`]"), "Takes [u8; 4] and a Path");
+    assert_eq!(to_plaintext("See [the docs][ref] and \\*literal\\*"), "See the docs and *literal*");
+}
+
+#[test]
+fn test_content_format_config_and_render() {
+    let config = Config::parse("content_format = \"plaintext\"").unwrap();
+    assert_eq!(config.content_format, ContentFormat::Plaintext);
+    assert_eq!(Config::default().content_format, ContentFormat::Markdown);
+    assert_eq!(ContentFormat::Markdown.render("**x**"), "**x**");
+    assert_eq!(ContentFormat::Plaintext.lsp_preference()[0], "plaintext");
+}
+
+#[test]
+fn test_truncate_keeps_whole_paragraphs() {
+    let doc = "Appends an element to the back of a collection.\n\n# Panics\n\nPanics if the new capacity exceeds `isize::MAX` bytes.\n\n```rust\nlet mut v = vec![1];\n\nv.push(2);\n```";
+    assert_eq!(truncate(doc, 0), doc);
+    assert_eq!(truncate(doc, doc.chars().count()), doc);
+    assert_eq!(truncate(doc, 60), "Appends an element to the back of a collection.\n\n# Panics\n\n…");
+    // The fence is one paragraph despite its blank line
+    assert_eq!(truncate(doc, doc.chars().count() - 1), doc[..doc.find("\n\n```").unwrap()].to_string() + "\n\n…");
+    assert_eq!(truncate(doc, 20), "Appends an element…");
+    assert_eq!(Config::default().completion_doc_max_len, 500);
+    assert_eq!(Config::parse("completion_doc_max_len = 0").unwrap().completion_doc_max_len, 0);
+}
@@ -5,7 +5,7 @@ use tokio::process::Command;
 
 async fn send_mcp_request(method: &str, params: Value) -> Result<Value, Box<dyn std::error::Error>> {
     let mut child = Command::new("cargo")
-        .args(&["run", "--quiet", "--"])
+        .args(["run", "--quiet", "--"])
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::null())
@@ -23,7 +23,9 @@ async fn send_mcp_request(method: &str, params: Value) -> Result<Value, Box<dyn
 
     let request_str = serde_json::to_string(&request)?;
     stdin.write_all(request_str.as_bytes()).await?;
-    stdin.shutdown().await?;
+    stdin.write_all(b"\n").await?;
+    // Closing the pipe is what ends the server's input; shutdown() only flushes it
+    drop(stdin);
 
     let mut response_buf = Vec::new();
     stdout.read_to_end(&mut response_buf).await?;
@@ -88,11 +90,9 @@ async fn test_invalid_snippet_name() {
     let response = send_mcp_request("expand_snippet", params).await.unwrap();
     
     assert!(response["jsonrpc"] == "2.0");
-    assert!(response.get("result").is_some());
-    
-    let result = &response["result"];
+    assert!(response.get("result").is_none());
     // Should return an error message
-    assert!(result.get("error").is_some());
+    assert!(response["error"]["message"].as_str().unwrap().contains("Unknown snippet"));
 }
 
 #[tokio::test]
@@ -206,9 +206,10 @@ async fn test_rename_with_same_names() {
     
     let response = send_mcp_request("rename", params).await.unwrap();
     
-    // Should handle identical names
+    // Renames are by position, so the names alone are rejected as invalid params
     assert!(response["jsonrpc"] == "2.0");
-    assert!(response.get("result").is_some());
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["data"]["missing"], json!(["file", "line", "column"]));
 }
 
 #[tokio::test]
@@ -236,9 +237,9 @@ async fn test_whitespace_in_params() {
     
     let response = send_mcp_request("expand_snippet", params).await.unwrap();
     
-    // Should handle whitespace
+    // Snippet names are matched exactly
     assert!(response["jsonrpc"] == "2.0");
-    assert!(response.get("result").is_some());
+    assert!(response["error"]["message"].as_str().unwrap().contains("Unknown snippet"));
 }
 
 #[tokio::test]
@@ -252,9 +253,10 @@ async fn test_path_traversal_attempt() {
     
     let response = send_mcp_request("get_hover", params).await.unwrap();
     
-    // Should handle safely
+    // Paths escaping the project root are refused before anything is read
     assert!(response["jsonrpc"] == "2.0");
-    assert!(response.get("result").is_some());
+    assert!(response.get("result").is_none());
+    assert!(response["error"]["message"].as_str().unwrap().contains("outside the project root"));
 }
 
 #[tokio::test]
@@ -267,7 +269,7 @@ async fn test_absolute_path() {
     let response = send_mcp_request("code_metrics", params).await.unwrap();
     
     assert!(response["jsonrpc"] == "2.0");
-    assert!(response.get("result").is_some());
+    assert!(response["error"]["message"].as_str().unwrap().contains("outside the project root"));
 }
 
 #[tokio::test]
@@ -5,7 +5,7 @@ use tokio::process::Command;
 
 async fn send_mcp_request(method: &str, params: Value) -> Result<Value, Box<dyn std::error::Error>> {
     let mut child = Command::new("cargo")
-        .args(&["run", "--quiet", "--"])
+        .args(["run", "--quiet", "--"])
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::null())
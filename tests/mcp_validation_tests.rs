@@ -36,7 +36,7 @@ async fn validate_project_structure_response() {
     
     // Validate modules structure
     let modules = result["modules"].as_array().unwrap();
-    assert!(modules.len() > 0);
+    assert!(!modules.is_empty());
     
     // Check for expected files in our project
     let file_names: Vec<&str> = modules.iter()
@@ -93,7 +93,7 @@ async fn validate_code_metrics_response() {
         .trim_end_matches('%')
         .parse()
         .unwrap();
-    assert!(percentage_value >= 0.0 && percentage_value <= 100.0);
+    assert!((0.0..=100.0).contains(&percentage_value));
     
     // Validate counts are reasonable
     assert!(metrics["file_count"].as_u64().unwrap() > 5); // We have more than 5 files
@@ -124,8 +124,8 @@ async fn validate_dependencies_response() {
     assert!(deps.contains_key("tower-lsp"));
     
     // Validate version formats
-    for (_name, version) in deps {
-        let version_str = version.as_str().unwrap();
+    for (_name, dependency) in deps {
+        let version_str = dependency["version"].as_str().unwrap();
         assert!(!version_str.is_empty());
         // Basic version format check
         assert!(version_str.chars().any(|c| c.is_numeric() || c == '.'));
@@ -309,4 +309,35 @@ async fn validate_large_response_handling() {
     
     // Can parse it back
     let _parsed: Value = serde_json::from_str(&response_str).unwrap();
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn validate_diagnostics_grouping() {
+    let server = McpServer::new(".").await.unwrap();
+    
+    let response = call_mcp_method(&server, "get_diagnostics", json!({
+        "method": "get_diagnostics",
+        "group_by": "lint"
+    })).await.unwrap();
+    
+    let result = &response["result"];
+    assert_eq!(result["group_by"], "lint");
+    
+    // Every diagnostic must be accounted for in exactly one group
+    let groups = result["groups"].as_array().unwrap();
+    let grouped_total: u64 = groups.iter()
+        .map(|g| g["count"].as_u64().unwrap())
+        .sum();
+    assert_eq!(grouped_total, result["total_diagnostics"].as_u64().unwrap());
+    
+    for group in groups {
+        assert!(group["summary"].is_string());
+        assert!(group["sample_locations"].as_array().unwrap().len() <= 5);
+    }
+    
+    // Unknown grouping modes are rejected
+    let response = call_mcp_method(&server, "get_diagnostics", json!({
+        "method": "get_diagnostics",
+        "group_by": "severity"
+    })).await.unwrap();
+    assert!(response.get("error").is_some());
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::metrics_diff::{changed_files, content_at_ref, cyclomatic_complexity, function_names, functions_changed, line_changes};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn test_cyclomatic_complexity_counts_branches() {
+    let source = r#"
+impl Display for Point {
+    fn fmt(&self) -> bool {
+        // if this were a branch it would count
+        let label = "if while for";
+        if self.x > 0 && self.y > 0 {
+            return true;
+        }
+        match self.kind {
+            Kind::A => true,
+            Kind::B => false,
+            _ => self.z == 0 || self.w == 0,
+        }
+    }
+}
+
+fn run(items: &[u32]) {
+    let ready = move || true;
+    for item in items {
+        println!("{}", item);
+    }
+}
+"#;
+    // fmt: 1 + if + && + 2 extra arms + || = 6; run: 1 + for = 2
+    assert_eq!(cyclomatic_complexity(source), 8);
+}
+
+#[test]
+fn test_functions_changed_counts_duplicates() {
+    let before = function_names("fn a() {}\nimpl X { fn new() {} }\npub(crate) async fn b() {}\n");
+    assert_eq!(before, vec!["a", "new", "b"]);
+
+    let after = function_names("fn a() {}\nimpl X { fn new() {} }\nimpl Y { fn new() {} }\nfn c<'a>(s: &'a str) {}\n");
+    let (added, removed) = functions_changed(&before, &after);
+    assert_eq!(added, vec!["c", "new"]);
+    assert_eq!(removed, vec!["b"]);
+}
+
+fn scratch_repo(name: &str) -> ScratchDir {
+    let dir = ScratchDir::with_crate(&format!("metrics-diff-{}", name), "");
+    dir.write("src/lib.rs", "pub fn keep() -> u32 {\n    1\n}\n\npub fn drop_me() {}\n");
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "initial"]);
+    dir
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+async fn metrics_diff(dir: &Path, file: &str) -> Value {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "metrics_diff",
+        "params": { "method": "metrics_diff", "file": file }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    response["result"].clone()
+}
+
+#[tokio::test]
+async fn test_metrics_diff_against_head() {
+    let dir = scratch_repo("head");
+    dir.write(
+        "src/lib.rs",
+        "pub fn keep() -> u32 {\n    if cfg!(test) { 2 } else { 1 }\n}\n\npub fn added(x: bool) -> bool {\n    x\n}\n",
+    );
+    dir.write("src/new.rs", "fn fresh() {}\n");
+
+    let result = metrics_diff(&dir, "src/lib.rs").await;
+    assert_eq!(result["git_ref"], "HEAD");
+    assert_eq!(result["lines_added"], 4);
+    assert_eq!(result["lines_removed"], 2);
+    assert_eq!(result["functions_added"], json!(["added"]));
+    assert_eq!(result["functions_removed"], json!(["drop_me"]));
+    assert_eq!(result["delta"]["cyclomatic_complexity"], 1);
+    assert_eq!(result["complexity_increased"], true);
+
+    let untracked = metrics_diff(&dir, "src/new.rs").await;
+    assert_eq!(untracked["exists_at_ref"], false);
+    assert_eq!(untracked["lines_added"], 1);
+    assert_eq!(untracked["functions_added"], json!(["fresh"]));
+}
+
+#[tokio::test]
+async fn test_refs_are_never_read_as_options() {
+    let dir = scratch_repo("options");
+    let written = dir.join("written");
+    let git_ref = format!("--output={}", written.display());
+
+    assert!(line_changes(&dir, "src/lib.rs", &git_ref).await.is_err());
+    assert!(content_at_ref(&dir, "src/lib.rs", &git_ref).await.is_err());
+    assert!(changed_files(&dir, &git_ref).await.is_err());
+    assert!(!written.exists());
+    assert_eq!(line_changes(&dir, "src/lib.rs", "HEAD").await.unwrap(), (0, 0));
+}
+
+#[tokio::test]
+async fn test_diagnostics_of_changed_files() {
+    let dir = scratch_repo("changed");
+    dir.write("src/lib.rs", "mod old;\nmod new;\n");
+    dir.write("src/old.rs", "fn stale() {}\n");
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "old"]);
+    dir.write("src/new.rs", "fn fresh() {}\n");
+
+    let changed: Vec<String> = changed_files(&dir, "HEAD").await.unwrap().into_iter().collect();
+    assert_eq!(changed, vec!["src/new.rs"]);
+    assert!(changed_files(&dir, "no-such-ref").await.is_err());
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "get_diagnostics", "params": { "changed": "" } });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["changed_since"], "HEAD");
+    // `stale` is dead code too, but was there before
+    let files: Vec<&str> = result["diagnostics"].as_array().unwrap().iter().map(|d| d["file"].as_str().unwrap()).collect();
+    assert_eq!(files, vec!["src/new.rs"], "{}", result);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::missing_docs::{from_lint, has_inner_docs, public_items};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const LIB: &str = r#"//! The crate
+pub mod a;
+mod private;
+
+/// Documented
+#[derive(Debug)]
+pub struct Documented { pub x: u8 }
+
+#[derive(Debug)]
+pub enum Bare { V }
+
+#[doc = "Via attribute"]
+pub const LIMIT: u32 = 3;
+
+//// Not a doc comment
+pub fn quadruple() {}
+
+pub(crate) fn internal() {}
+
+#[macro_export]
+macro_rules! exported { () => {} }
+
+pub mod inline {
+    //! Documented inside
+}
+"#;
+
+fn scratch(name: &str) -> ScratchDir {
+    let dir = ScratchDir::with_crate(&format!("missing-docs-{}", name), "");
+    dir.write("src/lib.rs", LIB);
+    dir.write("src/a.rs", "pub fn f() {}\n");
+    dir.write("src/private.rs", "//! Private\npub fn g() {}\n");
+    dir
+}
+
+#[test]
+fn test_public_items_and_their_docs() {
+    let items: Vec<(String, String, bool)> = public_items(LIB).into_iter().map(|i| (i.kind, i.name, i.documented)).collect();
+    let expected = [
+        ("module", "a", false),
+        ("struct", "Documented", true),
+        ("enum", "Bare", false),
+        ("constant", "LIMIT", true),
+        ("function", "quadruple", false),
+        ("macro", "exported", false),
+        ("module", "inline", true),
+    ];
+    assert_eq!(items, expected.map(|(k, n, d)| (k.to_string(), n.to_string(), d)));
+
+    assert!(has_inner_docs("// licence\n\n#![doc = \"x\"]\nfn f() {}"));
+    assert!(!has_inner_docs("fn f() {}\n//! too late"));
+}
+
+#[test]
+fn test_from_lint_uses_the_lint_for_missing_items() {
+    let dir = scratch("lint");
+    let message = |what: &str, file: &str, line: u64, column: u64, text: &str| json!({
+        "reason": "compiler-message",
+        "message": {
+            "code": { "code": "missing_docs", "explanation": null },
+            "message": format!("missing documentation for {}", what),
+            "spans": [{ "file_name": file, "line_start": line, "column_start": column, "is_primary": true, "text": [{ "text": text }] }]
+        }
+    });
+    let messages = [
+        message("an enum", "src/lib.rs", 10, 1, "pub enum Bare { V }"),
+        message("a variant", "src/lib.rs", 10, 17, "pub enum Bare { V }"),
+        message("a struct field", "src/lib.rs", 7, 24, "pub struct Documented { pub x: u8 }"),
+        message("a function", "src/a.rs", 1, 1, "pub fn f() {}"),
+        // Reported again by another target
+        message("a function", "src/a.rs", 1, 1, "pub fn f() {}"),
+    ];
+    let report = from_lint(&dir, &messages);
+    assert_eq!(report.source, "lint");
+    // Documented, LIMIT, inline and the crate, plus four lint findings
+    assert_eq!((report.public_items, report.documented, report.missing), (8, 4, 4));
+    assert_eq!(report.coverage, 50.0);
+
+    let modules: Vec<&str> = report.modules.iter().map(|m| m.module.as_str()).collect();
+    assert_eq!(modules, ["crate", "crate::a"]);
+    let missing: Vec<(&str, &str)> = report.modules[0].missing.iter().map(|i| (i.kind.as_str(), i.name.as_str())).collect();
+    assert_eq!(missing, [("struct_field", "x"), ("enum", "Bare"), ("variant", "V")]);
+}
+
+#[tokio::test]
+async fn test_missing_docs_command_scans_the_source() {
+    let dir = scratch("scan");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "missing_docs", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["source"], "scan", "{response}");
+    assert!(result.get("fallback_reason").is_none());
+    // lib.rs: the crate and seven items; a.rs and private.rs: one function each
+    assert_eq!((result["public_items"].as_u64(), result["documented"].as_u64()), (Some(10), Some(4)));
+    assert_eq!(result["coverage"], 40.0);
+
+    let crate_root = &result["modules"][0];
+    assert_eq!((crate_root["module"].as_str(), crate_root["file"].as_str()), (Some("crate"), Some("src/lib.rs")));
+    let names: Vec<&str> = crate_root["missing"].as_array().unwrap().iter().filter_map(|i| i["name"].as_str()).collect();
+    assert_eq!(names, ["a", "Bare", "quadruple", "exported"]);
+    assert_eq!(crate_root["missing"][1], json!({ "kind": "enum", "name": "Bare", "line": 10 }));
+}
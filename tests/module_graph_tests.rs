@@ -0,0 +1,70 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::module_graph::{build, reading_order, ReadingDirection};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+/// `lib` declares everything; `api` uses `model`, which uses `util`;
+/// `a` and `b` use each other; `model::extra` calls back into its parent
+const FILES: &[(&str, &str)] = &[
+    ("src/lib.rs", "pub mod api;\npub mod model;\nmod util;\nmod a;\nmod b;\n\npub use model::Model;\n"),
+    ("src/api.rs", "use crate::model::{Model, extra};\n\npub fn serve() -> Model { extra::make() }\n"),
+    ("src/model/mod.rs", "pub mod extra;\n\npub struct Model(pub u32);\n\npub fn id() -> u32 { crate::util::next() }\n"),
+    ("src/model/extra.rs", "use super::Model;\n\npub fn make() -> Model { Model(1) }\n"),
+    ("src/util.rs", "pub fn next() -> u32 { 1 }\n"),
+    ("src/a.rs", "pub fn a() { super::b::b() }\n"),
+    ("src/b.rs", "pub(crate) use crate::a::a;\npub fn b() {}\n"),
+];
+
+fn modules(steps: &[mcp_rust_analyzer::module_graph::ReadingStep]) -> Vec<Vec<String>> {
+    steps.iter().map(|s| s.files.iter().map(|f| f.module.clone()).collect()).collect()
+}
+
+#[test]
+fn test_reading_order() {
+    let dir = ScratchDir::with_crate("module-graph-order", "\n[workspace]\n");
+    dir.write_all(FILES);
+    let graph = build(&dir);
+    assert_eq!(graph.modules.len(), 7);
+
+    let roots_first = reading_order(&graph, ReadingDirection::RootsFirst);
+    let order = modules(&roots_first);
+    assert_eq!(order[0], vec!["crate"]);
+    let position = |module: &str| order.iter().position(|step| step.iter().any(|m| m == module)).unwrap();
+    assert!(position("crate::api") < position("crate::model"));
+    assert!(position("crate::model") < position("crate::model::extra"));
+    assert!(position("crate::model") < position("crate::util"));
+
+    // The only cycle is a <-> b; extra's `super::` does not tie it to model
+    let cycles: Vec<&Vec<String>> = order.iter().filter(|step| step.len() > 1).collect();
+    assert_eq!(cycles, vec![&vec!["crate::a".to_string(), "crate::b".to_string()]]);
+    assert!(roots_first.iter().find(|s| s.files.len() == 2).unwrap().cycle);
+
+    let leaves_first = modules(&reading_order(&graph, ReadingDirection::LeavesFirst));
+    assert_eq!(leaves_first.last().unwrap(), &vec!["crate"]);
+    let position = |module: &str| leaves_first.iter().position(|step| step.iter().any(|m| m == module)).unwrap();
+    assert!(position("crate::util") < position("crate::model"));
+    assert!(position("crate::model::extra") < position("crate::api"));
+}
+
+#[tokio::test]
+async fn test_reading_order_command() {
+    let dir = ScratchDir::with_crate("module-graph-command", "\n[workspace]\n");
+    dir.write_all(FILES);
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "reading_order",
+        "params": { "method": "reading_order", "order": "leaves_first" }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["order"], "leaves_first");
+    assert_eq!(result["total_files"], 7);
+    assert_eq!(result["cycles"], 1);
+    assert_eq!(result["steps"].as_array().unwrap().last().unwrap()["files"][0]["file"], "src/lib.rs");
+}
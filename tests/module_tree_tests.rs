@@ -0,0 +1,62 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::module_tree::{count_items, ItemCounts};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_count_items() {
+    let source = "\
+use std::fmt;
+
+/// Docs
+#[derive(Debug)]
+pub struct Point {
+    pub x: i32,
+}
+
+impl Point {
+    pub fn new() -> Self { Point { x: 0 } }
+}
+
+pub(crate) fn helper() {}
+fn private() {}
+pub const LIMIT: usize = 3;
+mod tests {
+    fn inner() {}
+}
+";
+    assert_eq!(count_items(source), ItemCounts { public: 2, private: 3 });
+}
+
+#[tokio::test]
+async fn test_module_tree_outline() {
+    let dir = ScratchDir::with_crate("module-tree", "\n[workspace]\n");
+    dir.write("src/lib.rs", "pub mod commands;\nmod util;\n\npub fn run() {}\n");
+    dir.write("src/util.rs", "pub(crate) fn clamp() {}\nfn round() {}\n");
+    dir.write("src/commands/mod.rs", "pub mod build;\n");
+    dir.write("src/commands/build.rs", "pub struct Build;\npub enum Mode { Debug }\nstruct Cache;\n");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "module_tree", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+
+    assert_eq!(result["outline"], "\
+src/ (5 pub, 4 priv)
+  lib.rs (2 pub, 1 priv)
+  commands/ (3 pub, 1 priv)
+    mod.rs (1 pub, 0 priv)
+    build.rs (2 pub, 1 priv)
+  util.rs (0 pub, 2 priv)
+");
+    assert_eq!(result["files"], 4);
+    assert_eq!(result["totals"], json!({ "public": 5, "private": 4 }));
+
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "module_tree", "params": { "module": "src/commands" } });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert_eq!(response["result"]["outline"], "src/commands/ (3 pub, 1 priv)\n  mod.rs (1 pub, 0 priv)\n  build.rs (2 pub, 1 priv)\n");
+}
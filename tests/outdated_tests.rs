@@ -0,0 +1,117 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::manifest::{parse, Dependency};
+use mcp_rust_analyzer::outdated::{cache_path, check_outdated, is_outdated, parse_search_output, requirement};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_requirement() {
+    let dependency = |name: &str, version: Option<&str>, package: Option<&str>| Dependency {
+        name: name.to_string(),
+        version: version.map(String::from),
+        package: package.map(String::from),
+        ..Dependency::default()
+    };
+    assert_eq!(requirement(&dependency("serde", Some("1.0"), None)), Some(("serde".to_string(), "1.0".to_string())));
+    assert_eq!(
+        requirement(&dependency("json", Some("~1.0.100"), Some("serde_json"))),
+        Some(("serde_json".to_string(), "~1.0.100".to_string()))
+    );
+    assert_eq!(requirement(&Dependency { path: Some("../local".to_string()), ..dependency("local", None, None) }), None);
+}
+
+#[test]
+fn test_is_outdated() {
+    assert!(!is_outdated("1.0", "1.0.210"));
+    assert!(is_outdated("1", "2.0.1"));
+    assert!(is_outdated("0.11", "0.12.3"));
+    assert!(!is_outdated("0.4.2", "0.4.38"));
+    assert!(is_outdated("0.0.3", "0.0.4"));
+    assert!(is_outdated("~1.2", "1.3.0"));
+    assert!(is_outdated("=1.2.3", "1.2.4"));
+    assert!(!is_outdated(">=1.0", "9.0.0"));
+    assert!(!is_outdated("2.0", "1.9.0"), "a newer requirement than the registry knows is not outdated");
+}
+
+#[test]
+fn test_parse_search_output() {
+    let output = "serde = \"1.0.210\"    # A generic serialization/deserialization framework\n... and 4000 crates more\n";
+    assert_eq!(parse_search_output(output, "serde"), Some("1.0.210".to_string()));
+    assert_eq!(parse_search_output("serde_json = \"1.0.1\"    # JSON\n", "serde"), None);
+}
+
+const DEPENDENCIES: &str = "
+[dependencies]
+serde = { version = \"1\", features = [\"derive\"] }
+toml = \"0.5\"
+local = { path = \"../local\" }
+
+[dev-dependencies]
+tempfile = \"3\"
+
+[workspace]
+";
+
+fn write_cache(dir: &ScratchDir, cache: Value) {
+    let cache_file = cache_path(dir);
+    std::fs::create_dir_all(cache_file.parent().unwrap()).unwrap();
+    std::fs::write(cache_file, cache.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn test_offline_uses_stale_cache() {
+    let dir = ScratchDir::with_crate("outdated-offline", DEPENDENCIES);
+    write_cache(&dir, json!({
+        "serde": { "version": "1.0.210", "checked_at": 0 },
+        "toml": { "version": "0.8.19", "checked_at": 0 }
+    }));
+    let manifest = parse("[dependencies]\nserde = { version = \"1\" }\ntoml = \"0.5\"\n\n[dev-dependencies]\ntempfile = \"3\"\n").unwrap();
+    let report = check_outdated(&dir, &manifest, true).await;
+
+    assert!(!report.checked);
+    assert!(report.note.as_deref().unwrap().contains("Offline"));
+    let found: Vec<(&str, Option<&str>, bool, &str)> = report.dependencies.iter()
+        .map(|d| (d.name.as_str(), d.latest.as_deref(), d.outdated, d.source.as_str()))
+        .collect();
+    assert_eq!(found, vec![
+        ("serde", Some("1.0.210"), false, "stale_cache"),
+        ("toml", Some("0.8.19"), true, "stale_cache"),
+        ("tempfile", None, false, "unknown"),
+    ]);
+    assert_eq!(report.outdated_count, 1);
+}
+
+#[tokio::test]
+async fn test_analyze_dependencies_with_fresh_cache() {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+    let dir = ScratchDir::with_crate("outdated-fresh", DEPENDENCIES);
+    write_cache(&dir, json!({
+        "serde": { "version": "1.0.210", "checked_at": now },
+        "toml": { "version": "0.8.19", "checked_at": now },
+        "tempfile": { "version": "3.13.0", "checked_at": now }
+    }));
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "analyze_dependencies",
+        "params": { "check_outdated": true }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["dependencies"]["toml"]["version"], "0.5", "the plain listing is unchanged");
+    let outdated = &result["outdated"];
+    assert_eq!(outdated["checked"], true);
+    assert_eq!(outdated["outdated_count"], 1);
+    assert_eq!(outdated["dependencies"].as_array().unwrap().len(), 3, "path dependencies are skipped");
+    assert!(outdated["dependencies"].as_array().unwrap().iter().all(|d| d["source"] == "cache"));
+
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "analyze_dependencies", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert!(response["result"].get("outdated").is_none());
+}
@@ -0,0 +1,81 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::outline::{from_lsp, OutlineSymbol};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+fn range(start: u64, end: u64) -> Value {
+    json!({ "start": { "line": start, "character": 0 }, "end": { "line": end, "character": 1 } })
+}
+
+/// Name, kind, line and end line of each symbol, depth first
+fn flatten(symbols: &[OutlineSymbol], depth: usize, out: &mut Vec<(usize, String, String, u32, u32)>) {
+    for symbol in symbols {
+        out.push((depth, symbol.name.clone(), symbol.kind.clone(), symbol.line, symbol.end_line));
+        flatten(&symbol.children, depth + 1, out);
+    }
+}
+
+fn outline(symbols: &[OutlineSymbol]) -> Vec<(usize, String, String, u32, u32)> {
+    let mut out = Vec::new();
+    flatten(symbols, 0, &mut out);
+    out
+}
+
+fn expected() -> Vec<(usize, String, String, u32, u32)> {
+    [(0, "Shape", "enum", 1, 4), (1, "Dot", "variant", 2, 2), (1, "Line", "variant", 3, 3),
+     (0, "impl Shape", "impl", 6, 10), (1, "area", "method", 7, 9), (0, "Area", "trait", 12, 12)]
+        .into_iter()
+        .map(|(depth, name, kind, line, end)| (depth, name.to_string(), kind.to_string(), line, end))
+        .collect()
+}
+
+#[test]
+fn test_from_lsp_keeps_document_symbol_nesting() {
+    let response = json!([
+        { "name": "Shape", "kind": 10, "range": range(0, 3), "selectionRange": range(0, 0), "children": [
+            { "name": "Dot", "kind": 22, "range": range(1, 1), "selectionRange": range(1, 1) },
+            { "name": "Line", "kind": 22, "range": range(2, 2), "selectionRange": range(2, 2) },
+        ] },
+        { "name": "impl Shape", "kind": 19, "range": range(5, 9), "selectionRange": range(5, 5), "children": [
+            { "name": "area", "kind": 6, "detail": "fn(&self) -> f64", "range": range(6, 8), "selectionRange": range(6, 6) },
+        ] },
+        { "name": "Area", "kind": 11, "detail": "", "range": range(11, 11), "selectionRange": range(11, 11) },
+    ]);
+    let symbols = from_lsp(&response);
+    assert_eq!(outline(&symbols), expected());
+    assert_eq!(symbols[1].children[0].detail.as_deref(), Some("fn(&self) -> f64"));
+    assert_eq!(symbols[2].detail, None);
+
+    assert!(from_lsp(&Value::Null).is_empty());
+}
+
+#[test]
+fn test_from_lsp_nests_symbol_information_by_range() {
+    let info = |name: &str, kind: u64, start: u64, end: u64| json!({
+        "name": name, "kind": kind,
+        "location": { "uri": "file:///p/src/lib.rs", "range": range(start, end) }
+    });
+    // Flat and out of order
+    let response = json!([
+        info("area", 6, 6, 8),
+        info("Shape", 10, 0, 3),
+        info("Dot", 22, 1, 1),
+        info("impl Shape", 19, 5, 9),
+        info("Line", 22, 2, 2),
+        info("Area", 11, 11, 11),
+    ]);
+    assert_eq!(outline(&from_lsp(&response)), expected());
+}
+
+#[tokio::test]
+async fn test_document_outline_without_lsp_is_empty() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config("tests/test_project", config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "document_outline",
+        "params": { "file": "src/lib.rs" }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert_eq!(response["result"], json!({ "file": "src/lib.rs", "symbols": [] }), "{response}");
+}
@@ -0,0 +1,119 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::param_validation::{example, parse_params, validate, ParamsError};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+async fn call(server: &McpServer, request: Value) -> Value {
+    serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()
+}
+
+#[test]
+fn test_validate_reports_missing_and_mistyped_fields() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "file": { "type": "string" },
+            "line": { "type": "number" },
+            "format": { "type": "string", "enum": ["markdown", "plaintext"] }
+        },
+        "required": ["file", "line"]
+    });
+    assert!(validate("tool", &schema, &json!({"file": "src/lib.rs", "line": 3, "extra": true})).is_ok());
+
+    let invalid = validate("tool", &schema, &json!({"file": null, "line": "3", "format": "html"})).unwrap_err();
+    assert_eq!(invalid.missing, vec!["file"]);
+    let fields: Vec<(&str, &str)> = invalid.invalid.iter().map(|i| (i.field.as_str(), i.problem.as_str())).collect();
+    assert_eq!(fields, vec![
+        ("format", "expected one of \"markdown\", \"plaintext\""),
+        ("line", "expected a number, got a string"),
+    ]);
+    assert_eq!(
+        invalid.to_string(),
+        "Invalid params for tool: missing file; format: expected one of \"markdown\", \"plaintext\"; line: expected a number, got a string"
+    );
+}
+
+/// The example of every tool passes its own schema
+#[test]
+fn test_examples_satisfy_their_schemas() {
+    for tool in McpServer::tool_definitions() {
+        let name = tool["name"].as_str().unwrap();
+        let example = example(name, &tool["inputSchema"]);
+        assert_eq!(example["name"], name);
+        assert!(validate(name, &tool["inputSchema"], &example["arguments"]).is_ok(), "{}: {}", name, example);
+    }
+}
+
+/// Only a failure to parse the params themselves is marked as the caller's
+/// fault; a JSON error from a command's own data stays a plain error
+#[test]
+fn test_params_errors_are_marked() {
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Params {
+        line: u32,
+    }
+
+    let error: anyhow::Error = parse_params::<Params>(json!({"line": "1"})).unwrap_err().into();
+    let ParamsError(parse) = error.downcast_ref::<ParamsError>().unwrap();
+    assert!(parse.is_data());
+
+    let error: anyhow::Error = serde_json::from_value::<Params>(json!({})).unwrap_err().into();
+    assert!(error.downcast_ref::<ParamsError>().is_none());
+}
+
+#[tokio::test]
+async fn test_invalid_params_error_carries_schema_and_example() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+
+    let response = call(&server, json!({
+        "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+        "params": {"name": "rename", "arguments": {"file": "src/lib.rs", "line": 1, "column": 1}}
+    })).await;
+    let error = &response["error"];
+    assert_eq!(error["code"], -32602);
+    assert_eq!(error["message"], "Invalid params for rename: missing new_name");
+    assert_eq!(error["data"]["missing"], json!(["new_name"]));
+    assert_eq!(error["data"]["schema"]["required"], json!(["file", "line", "column", "new_name"]));
+    assert_eq!(error["data"]["example"], json!({
+        "name": "rename",
+        "arguments": {"file": "src/lib.rs", "line": 1, "column": 1, "new_name": "new_name"}
+    }));
+
+    // Direct method calls get the same error
+    let response = call(&server, json!({
+        "jsonrpc": "2.0", "id": 2, "method": "get_hover", "params": {"file": "src/lib.rs", "line": "1"}
+    })).await;
+    let error = &response["error"];
+    assert_eq!(error["code"], -32602);
+    assert_eq!(error["data"]["missing"], json!(["column"]));
+    assert_eq!(error["data"]["invalid"][0]["field"], "line");
+    assert_eq!(error["data"]["example"]["name"], "get_hover");
+}
+
+#[tokio::test]
+async fn test_positional_params() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+
+    // Positional values fill the required fields in order
+    let response = call(&server, json!({"jsonrpc": "2.0", "id": 1, "method": "expand_snippet", "params": ["if_let"]})).await;
+    assert_eq!(response["result"]["name"], "if_let", "{response}");
+
+    let response = call(&server, json!({"jsonrpc": "2.0", "id": 2, "method": "expand_snippet", "params": ["if_let", true]})).await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(
+        response["error"]["message"],
+        "Invalid params for expand_snippet: params: takes at most 1 positional values (name), got 2; pass an object to name the fields"
+    );
+
+    let response = call(&server, json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": ["expand_snippet", {"name": "if_let"}]})).await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["message"], "Invalid params: tools/call takes named params; pass an object instead of an array");
+
+    let response = call(&server, json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list", "params": []})).await;
+    assert!(response["result"]["tools"].is_array());
+    let response = call(&server, json!({"jsonrpc": "2.0", "id": 5, "method": "no_such_method", "params": [1]})).await;
+    assert_eq!(response["error"]["code"], -32601);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::path_base::{rewrite_paths, PathBase};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// A workspace with the root package and a member under `crates/core`
+fn scratch_workspace(name: &str) -> ScratchDir {
+    let dir = ScratchDir::with_crate(&format!("path-base-{}", name), "\n[workspace]\nmembers = [\"crates/core\"]\n");
+    dir.write("src/lib.rs", "pub fn run() {}\n");
+    dir.write(
+        "crates/core/Cargo.toml",
+        "[package]\nname = \"core\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+    dir.write("crates/core/src/lib.rs", "pub fn core() {}\n");
+    dir
+}
+
+#[test]
+fn test_from_params() {
+    assert_eq!(PathBase::from_params(&json!({})).unwrap(), PathBase::Workspace);
+    assert_eq!(PathBase::from_params(&json!({"path_base": "package"})).unwrap(), PathBase::Package);
+    assert_eq!(PathBase::from_params(&json!({"path_base": "absolute"})).unwrap(), PathBase::Absolute);
+    assert!(PathBase::from_params(&json!({"path_base": "crate"})).is_err());
+}
+
+#[test]
+fn test_rewrite_paths() {
+    let root = scratch_workspace("rewrite");
+    let result = json!({
+        "findings": [
+            { "file": "crates/core/src/lib.rs", "line": 1 },
+            { "file": root.join("src/lib.rs").display().to_string(), "line": 1 }
+        ],
+        "suggestions": [{ "path": "std::fmt::Display" }],
+        "deleted": { "file": "src/gone.rs" }
+    });
+
+    let mut workspace = result.clone();
+    rewrite_paths(&mut workspace, &root, PathBase::Workspace);
+    assert_eq!(workspace["findings"][0]["file"], "crates/core/src/lib.rs");
+    assert_eq!(workspace["findings"][1]["file"], "src/lib.rs");
+
+    let mut package = result.clone();
+    rewrite_paths(&mut package, &root, PathBase::Package);
+    assert_eq!(package["findings"][0]["file"], "src/lib.rs");
+    assert_eq!(package["findings"][1]["file"], "src/lib.rs");
+
+    let mut absolute = result.clone();
+    rewrite_paths(&mut absolute, &root, PathBase::Absolute);
+    assert_eq!(absolute["findings"][0]["file"], root.join("crates/core/src/lib.rs").display().to_string());
+
+    // Module paths and files that no longer exist are not file system paths to rebase
+    for rewritten in [&workspace, &package, &absolute] {
+        assert_eq!(rewritten["suggestions"][0]["path"], "std::fmt::Display");
+        assert_eq!(rewritten["deleted"]["file"], "src/gone.rs");
+    }
+}
+
+#[tokio::test]
+async fn test_path_base_argument() {
+    let dir = scratch_workspace("server");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |path_base: Value| json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "code_metrics",
+        "params": { "method": "code_metrics", "module": "crates/core/src", "path_base": path_base }
+    });
+    let path = |response: String| {
+        let response: Value = serde_json::from_str(&response).unwrap();
+        response["result"]["path"].as_str().unwrap().to_string()
+    };
+
+    assert_eq!(path(server.handle_request(&call(Value::Null).to_string()).await.unwrap()), "crates/core/src");
+    assert_eq!(path(server.handle_request(&call(json!("package")).to_string()).await.unwrap()), "src");
+    let absolute = path(server.handle_request(&call(json!("absolute")).to_string()).await.unwrap());
+    assert_eq!(PathBuf::from(absolute), dir.join("crates/core/src"));
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(json!("nowhere")).to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap().contains("path_base"));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::path_guard::resolve_within;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_resolve_within() {
+    let base = ScratchDir::new("path-guard");
+    let root = base.join("project");
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(base.join("shared")).unwrap();
+    std::fs::write(base.join("secret.txt"), "secret").unwrap();
+    std::os::unix::fs::symlink(&base, root.join("src/escape")).unwrap();
+    let canonical = root.canonicalize().unwrap();
+
+    assert_eq!(resolve_within("src/../src/lib.rs", &root, &[]).unwrap(), canonical.join("src/lib.rs"));
+    assert_eq!(resolve_within("src/new/file.rs", &root, &[]).unwrap(), canonical.join("src/new/file.rs"));
+    assert!(resolve_within(canonical.join("src").to_str().unwrap(), &root, &[]).is_ok());
+
+    assert!(resolve_within("../secret.txt", &root, &[]).is_err());
+    assert!(resolve_within("src/../../secret.txt", &root, &[]).is_err());
+    assert!(resolve_within("/etc/passwd", &root, &[]).is_err());
+    // A symlink pointing out of the project does not make its target readable
+    assert!(resolve_within("src/escape/secret.txt", &root, &[]).is_err());
+
+    let allowed = vec!["../shared".to_string()];
+    assert!(resolve_within("../shared/mod.rs", &root, &allowed).is_ok());
+    assert!(resolve_within("../secret.txt", &root, &allowed).is_err());
+}
+
+#[tokio::test]
+async fn test_commands_refuse_paths_outside_the_project() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(env!("CARGO_MANIFEST_DIR"), config).await.unwrap();
+    let call = |method: &str, params: Value| {
+        let mut params = params;
+        params["method"] = json!(method);
+        json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string()
+    };
+
+    for request in [
+        call("file_summary", json!({ "file": "../../../etc/passwd" })),
+        call("code_metrics", json!({ "module": "/etc" })),
+    ] {
+        let response: Value = serde_json::from_str(&server.handle_request(&request).await.unwrap()).unwrap();
+        assert!(response["error"]["message"].as_str().unwrap().contains("outside the project root"), "{}", response);
+    }
+
+    let request = call("file_summary", json!({ "file": "src/lib.rs" }));
+    let response: Value = serde_json::from_str(&server.handle_request(&request).await.unwrap()).unwrap();
+    assert!(response.get("result").is_some(), "{}", response);
+}
+
+#[test]
+fn test_relative_project_root() {
+    assert!(resolve_within("not_there_yet.rs", std::path::Path::new("."), &[]).is_ok());
+    assert!(resolve_within("../not_there_either.rs", std::path::Path::new("."), &[]).is_err());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::placement::{keywords, rate, words};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const CONFIG: &str = r#"use std::path::Path;
+
+pub struct Config {
+    pub name: String,
+}
+
+impl Config {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
+
+pub fn parse_config(text: &str) -> Config {
+    Config::new(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    fn config_fixture() {}
+}
+"#;
+
+const SERVER: &str = r#"use crate::config::Config;
+
+pub fn start(config: &Config) {}
+
+pub fn stop() {}
+"#;
+
+#[test]
+fn test_keywords_and_words() {
+    assert_eq!(words("ConfigParser"), ["config", "parser"]);
+    assert_eq!(words("parse_configs"), ["parse", "config"]);
+    assert_eq!(words("HTTPServer2"), ["httpserver2"]);
+    assert_eq!(keywords("Add a function that parses Config files from TOML"), ["parse", "config", "file", "toml"]);
+}
+
+#[test]
+fn test_rate_prefers_the_module_defining_the_symbol() {
+    let owner = rate(CONFIG, "crate::config", &[], Some("Config")).unwrap();
+    assert_eq!(owner.score, 100);
+    // After `impl Config`, which ends on line 11
+    assert_eq!((owner.insert_line, owner.after.as_deref()), (12, Some("impl Config")));
+    assert_eq!(owner.reasons, ["defines struct Config", "after impl Config"]);
+
+    let user = rate(SERVER, "crate::server", &[], Some("Config")).unwrap();
+    assert_eq!(user.score, 10);
+    assert_eq!(user.after.as_deref(), Some("function start"));
+    assert_eq!(user.reasons[0], "refers to Config 1 time");
+
+    assert!(rate(SERVER, "crate::server", &[], Some("Missing")).is_none());
+}
+
+#[test]
+fn test_rate_matches_the_description() {
+    let validate = keywords("validate a config file");
+    let candidate = rate(CONFIG, "crate::config", &validate, None).unwrap();
+    // Module name, plus `Config`, `impl Config` and `parse_config`; the test module is ignored
+    assert_eq!(candidate.score, 30 + 30);
+    assert_eq!(candidate.after.as_deref(), Some("function parse_config"));
+
+    let tests_only = "pub fn run() {}\n\n#[cfg(test)]\nmod tests {}\n";
+    let candidate = rate(tests_only, "crate::runner", &keywords("runner state"), None).unwrap();
+    assert_eq!((candidate.insert_line, candidate.after.as_deref()), (3, None));
+    assert_eq!(candidate.reasons.last().map(String::as_str), Some("before the test module"));
+}
+
+#[tokio::test]
+async fn test_suggest_location_command() {
+    let dir = ScratchDir::with_crate("suggest-location", "");
+    dir.write("src/lib.rs", "pub mod config;\npub mod server;\n");
+    dir.write("src/config.rs", CONFIG);
+    dir.write("src/server.rs", SERVER);
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let call = |arguments: Value| json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": { "name": "suggest_location", "arguments": arguments } });
+    let response: Value = serde_json::from_str(&server.handle_request(&call(json!({ "description": "restart the server", "symbol": "Config" })).to_string()).await.unwrap()).unwrap();
+    let result: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    let files: Vec<&str> = result["candidates"].as_array().unwrap().iter().filter_map(|c| c["file"].as_str()).collect();
+    assert_eq!(files, ["src/config.rs", "src/server.rs"], "{result}");
+    assert_eq!(result["candidates"][1]["module"], "crate::server");
+    assert_eq!(result["keywords"], json!(["restart", "server"]));
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(json!({ "description": "add a new function" })).to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap_or("").contains("Describe the new item"), "{response}");
+}
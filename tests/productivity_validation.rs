@@ -92,11 +92,11 @@ async fn test_project_has_rust_files() {
     let mut has_rust_files = false;
     
     while let Some(entry) = entries.next_entry().await.unwrap() {
-        if entry.path().extension().map_or(false, |ext| ext == "rs") {
+        if entry.path().extension().is_some_and(|ext| ext == "rs") {
             has_rust_files = true;
             break;
         }
     }
     
     assert!(has_rust_files, "Project should contain Rust source files");
-}
\ No newline at end of file
+}
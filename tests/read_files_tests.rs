@@ -0,0 +1,93 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::read_files::{read_files, FileRequest};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+fn scratch_dir(name: &str) -> ScratchDir {
+    let dir = ScratchDir::with_crate(&format!("read-files-{}", name), "\n[workspace]\n");
+    dir.write("src/lib.rs", "pub mod a;\npub mod b;\n\npub fn f() {}\n");
+    dir.write("src/a.rs", "// a\nfn one() {}\nfn two() {}\nfn three() {}\n");
+    dir
+}
+
+#[tokio::test]
+async fn test_read_files_with_ranges_and_errors() {
+    let dir = scratch_dir("ranges");
+    let requests: Vec<FileRequest> = serde_json::from_value(json!([
+        "src/lib.rs",
+        { "path": "src/a.rs", "start_line": 2, "end_line": 3 },
+        "src/missing.rs",
+        "../outside.rs"
+    ])).unwrap();
+    let result = read_files(&dir, &[], &requests, 0).await;
+
+    let lib = &result.files["src/lib.rs"];
+    assert_eq!(lib.content.as_deref(), Some("pub mod a;\npub mod b;\n\npub fn f() {}\n"));
+    assert_eq!((lib.start_line, lib.end_line, lib.total_lines), (Some(1), Some(4), 4));
+
+    let a = &result.files["src/a.rs"];
+    assert_eq!(a.content.as_deref(), Some("fn one() {}\nfn two() {}\n"));
+    assert_eq!((a.start_line, a.end_line, a.total_lines), (Some(2), Some(3), 4));
+
+    assert!(result.files["src/missing.rs"].error.as_deref().unwrap().contains("Failed to read"));
+    assert!(result.files["../outside.rs"].error.as_deref().unwrap().contains("outside the project root"));
+    assert!(!result.truncated);
+    assert_eq!(result.total_bytes, lib.bytes + a.bytes);
+}
+
+#[tokio::test]
+async fn test_read_files_byte_budget() {
+    let dir = scratch_dir("budget");
+    let requests = vec![FileRequest::Path("src/a.rs".to_string()), FileRequest::Path("src/lib.rs".to_string())];
+    let result = read_files(&dir, &[], &requests, 20).await;
+
+    let a = &result.files["src/a.rs"];
+    assert_eq!(a.content.as_deref(), Some("// a\nfn one() {}\n"), "cut at the last whole line");
+    assert_eq!(a.end_line, Some(2));
+    assert!(a.truncated);
+
+    let lib = &result.files["src/lib.rs"];
+    assert_eq!(lib.content.as_deref(), Some(""));
+    assert!(lib.truncated);
+    assert!(result.truncated);
+    assert_eq!(result.total_bytes, 17);
+}
+
+#[tokio::test]
+async fn test_read_files_command() {
+    let dir = scratch_dir("command");
+    let config = Config { use_lsp: false, max_read_bytes: 15, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = |args: Value| json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": { "name": "read_files", "arguments": args }
+    });
+
+    let response: Value = serde_json::from_str(&server.handle_request(&request(json!({ "files": ["src/lib.rs"] })).to_string()).await.unwrap()).unwrap();
+    let result: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(result["max_bytes"], 15);
+    assert_eq!(result["files"]["src/lib.rs"]["content"], "pub mod a;\n");
+    assert_eq!(result["truncated"], true);
+
+    // A caller can lower the configured cap but not raise or lift it
+    let args = json!({ "files": ["src/lib.rs", "src/nope.rs"], "max_bytes": 1_000_000 });
+    let response: Value = serde_json::from_str(&server.handle_request(&request(args).to_string()).await.unwrap()).unwrap();
+    let result: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(result["max_bytes"], 15);
+    assert_eq!(result["truncated"], true);
+    assert!(result["files"]["src/nope.rs"]["error"].is_string());
+
+    let args = json!({ "files": ["src/lib.rs"], "max_bytes": 5 });
+    let response: Value = serde_json::from_str(&server.handle_request(&request(args).to_string()).await.unwrap()).unwrap();
+    let result: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(result["max_bytes"], 5);
+
+    let args = json!({ "files": ["src/lib.rs"], "max_bytes": 0 });
+    let response: Value = serde_json::from_str(&server.handle_request(&request(args).to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap().contains("max_bytes must be greater than 0"), "{}", response);
+}
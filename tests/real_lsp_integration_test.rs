@@ -1,5 +1,5 @@
 // Integration test with real rust-analyzer process
-use mcp_rust_analyzer::lsp_client::{LspClient, LspClientConfig};
+use mcp_rust_analyzer::lsp_client::{LspClient, LspClientConfig, DEFAULT_REQUEST_TIMEOUT};
 use std::path::PathBuf;
 
 #[tokio::test] 
@@ -20,6 +20,7 @@ async fn test_real_rust_analyzer_integration() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("tests/test_project").canonicalize().unwrap(),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     let mut client = LspClient::new(config).unwrap();
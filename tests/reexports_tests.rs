@@ -0,0 +1,58 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::reexports::{find_reexports, parse_use_tree, pub_use_statements, UseLeaf};
+
+fn leaf(path: &str, alias: Option<&str>) -> UseLeaf {
+    UseLeaf {
+        path: path.split("::").map(|s| s.to_string()).collect(),
+        alias: alias.map(|a| a.to_string()),
+    }
+}
+
+#[test]
+fn test_parse_nested_use_tree() {
+    let leaves = parse_use_tree("crate::model::{self, Config as Cfg, inner::{A, B}}");
+    assert_eq!(leaves, vec![
+        leaf("crate::model", None),
+        leaf("crate::model::Config", Some("Cfg")),
+        leaf("crate::model::inner::A", None),
+        leaf("crate::model::inner::B", None),
+    ]);
+}
+
+#[test]
+fn test_multiline_pub_use_statement() {
+    let source = "use std::fmt;\npub use self::a::{\n    X,\n    Y, // trailing\n};\npub(crate) use b::Z;\n";
+    let statements = pub_use_statements(source);
+    assert_eq!(statements.len(), 1);
+    assert_eq!(statements[0].0, 2);
+    assert_eq!(parse_use_tree(&statements[0].1).len(), 2);
+}
+
+#[test]
+fn test_find_reexports_resolves_definitions() {
+    let root = ScratchDir::new("reexports");
+    root.write("src/lib.rs", "mod model;\npub use model::{Widget, helpers::build as make};\npub use serde::Serialize;\n");
+    root.write("src/model/mod.rs", "pub mod helpers;\nmod widget;\npub use widget::Widget;\n");
+    root.write("src/model/widget.rs", "/// A widget\npub struct Widget;\n");
+    root.write("src/model/helpers.rs", "pub fn build() {}\n");
+
+    let exports = find_reexports(&root);
+    let find = |name: &str| exports.iter().find(|e| e.exported == name).unwrap();
+
+    // Followed through model's own `pub use`
+    let widget = find("crate::Widget");
+    let def = widget.definition.as_ref().unwrap();
+    assert_eq!(def.file, "src/model/widget.rs");
+    assert_eq!(def.line, 2);
+    assert_eq!(def.kind, "struct");
+
+    let make = find("crate::make");
+    assert_eq!(make.target, "crate::model::helpers::build");
+    assert_eq!(make.definition.as_ref().unwrap().kind, "function");
+
+    let serialize = find("crate::Serialize");
+    assert_eq!(serialize.external_crate.as_deref(), Some("serde"));
+    assert!(serialize.definition.is_none());
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::refactor::{apply_workspace_edit, file_edits, import_stats, new_function_name, FileEdits, ImportStats};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+fn edit(line: u64, character: u64, end_character: u64, text: &str) -> Value {
+    json!({
+        "range": { "start": { "line": line, "character": character }, "end": { "line": line, "character": end_character } },
+        "newText": text
+    })
+}
+
+/// rust-analyzer's extract function edit for `let total = a + b;`
+fn extract_edit(uri: &str) -> Value {
+    json!({
+        "documentChanges": [{
+            "textDocument": { "uri": uri, "version": 1 },
+            "edits": [
+                edit(1, 16, 21, "fun_name(a, b)"),
+                edit(3, 1, 1, "\n\nfn fun_name(a: i32, b: i32) -> i32 {\n    a + b\n}"),
+            ]
+        }]
+    })
+}
+
+#[tokio::test]
+async fn test_apply_workspace_edit_inside_the_project() {
+    let dir = ScratchDir::new("refactor-apply");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let file = dir.join("src/lib.rs");
+    std::fs::write(&file, "pub fn sum(a: i32, b: i32) -> i32 {\n    let total = a + b;\n    total\n}\n").unwrap();
+
+    let summary = apply_workspace_edit(&extract_edit(&format!("file://{}", file.display())), &dir).await.unwrap();
+    assert_eq!(summary.files_changed, ["src/lib.rs"]);
+    assert_eq!(summary.edits_applied, 2);
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "pub fn sum(a: i32, b: i32) -> i32 {\n    let total = fun_name(a, b);\n    total\n}\n\nfn fun_name(a: i32, b: i32) -> i32 {\n    a + b\n}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_apply_workspace_edit_refuses_files_outside_the_project() {
+    let dir = ScratchDir::new("refactor-outside");
+    std::fs::create_dir_all(dir.join("project")).unwrap();
+    let outside = dir.join("other.rs");
+    std::fs::write(&outside, "fn a() {}\nfn b() {}\n").unwrap();
+
+    let edit = json!({ "changes": { format!("file://{}", outside.display()): [edit(0, 3, 4, "x")] } });
+    let error = apply_workspace_edit(&edit, &dir.join("project")).await.unwrap_err();
+    assert!(format!("{:#}", error).contains("outside the project root"), "{error:#}");
+    assert_eq!(std::fs::read_to_string(&outside).unwrap(), "fn a() {}\nfn b() {}\n");
+}
+
+#[test]
+fn test_file_edits_counts_edits_per_file() {
+    let root = std::path::Path::new("/p");
+    let changes = json!({ "changes": {
+        "file:///p/src/lib.rs": [edit(0, 7, 10, "total"), edit(4, 4, 7, "total")],
+        "file:///p/src/main.rs": [edit(2, 20, 23, "total")],
+        "file:///p/src/empty.rs": []
+    } });
+    let mut files = file_edits(&changes, root);
+    files.sort_by(|a, b| a.file.cmp(&b.file));
+    assert_eq!(files, vec![
+        FileEdits { file: "src/lib.rs".to_string(), edits_applied: 2 },
+        FileEdits { file: "src/main.rs".to_string(), edits_applied: 1 },
+    ]);
+
+    let document_changes = json!({ "documentChanges": [
+        { "textDocument": { "uri": "file:///p/src/lib.rs", "version": 1 }, "edits": [edit(0, 7, 10, "total")] },
+        { "kind": "rename", "oldUri": "file:///p/src/sum.rs", "newUri": "file:///p/src/total.rs" },
+        { "textDocument": { "uri": "file:///p/src/lib.rs", "version": 1 }, "edits": [edit(3, 0, 3, "total")] }
+    ] });
+    assert_eq!(file_edits(&document_changes, root), vec![FileEdits { file: "src/lib.rs".to_string(), edits_applied: 2 }]);
+    assert!(file_edits(&json!(null), root).is_empty());
+}
+
+#[test]
+fn test_new_function_name() {
+    assert_eq!(new_function_name(&extract_edit("file:///p/src/lib.rs")).as_deref(), Some("fun_name"));
+    let changes = json!({ "changes": { "file:///p/src/lib.rs": [edit(0, 0, 0, "let f = fun_name1();"), edit(2, 0, 0, "\n\npub(crate) fn fun_name1() {}")] } });
+    assert_eq!(new_function_name(&changes).as_deref(), Some("fun_name1"));
+    assert_eq!(new_function_name(&json!({ "changes": { "file:///p/src/lib.rs": [edit(0, 0, 0, "defn x")] } })), None);
+}
+
+#[tokio::test]
+async fn test_extract_function_without_lsp_is_an_error() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config("tests/test_project", config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0", "id": 1, "method": "extract_function",
+        "params": { "file": "src/lib.rs", "start_line": 1, "start_column": 1, "end_line": 1, "end_column": 5, "function_name": "helper" }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap_or("").contains("LSP not available"), "{response}");
+}
+
+#[test]
+fn test_import_stats() {
+    let source = "use std::fmt;\nuse std::{\n    fmt,\n    io::{Read, Write},\n};\n// use std::mem;\n\nfn main() {}\n";
+    assert_eq!(import_stats(source), ImportStats { lines: 8, use_statements: 2, imports: 4 });
+    assert_eq!(import_stats("fn main() {}\n"), ImportStats { lines: 1, use_statements: 0, imports: 0 });
+}
+
+#[tokio::test]
+async fn test_organize_imports_without_lsp_is_an_error() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config("tests/test_project", config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "organize_imports", "params": { "file": "src/lib.rs" } });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap_or("").contains("LSP not available"), "{response}");
+}
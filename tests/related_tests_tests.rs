@@ -0,0 +1,110 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::related_tests::{calls, test_at, test_functions};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const PARSER: &str = r#"
+pub fn parse(input: &str) -> Vec<u8> {
+    input.bytes().collect()
+}
+
+pub fn parse_all(inputs: &[&str]) -> usize {
+    inputs.iter().map(|i| parse(i).len()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_empty() {
+        assert!(parse("").is_empty());
+    }
+
+    mod nested {
+        #[tokio::test]
+        #[ignore]
+        async fn parses_many() {
+            assert_eq!(super::super::parse_all(&["a"]), 1);
+        }
+    }
+
+    #[test]
+    fn unrelated() {
+        let parsed = 1;
+        assert_eq!(parsed, 1);
+    }
+}
+"#;
+
+#[test]
+fn test_test_functions() {
+    let tests = test_functions(PARSER);
+    let found: Vec<(&str, Vec<&str>, usize, usize)> = tests.iter()
+        .map(|t| (t.name.as_str(), t.modules.iter().map(String::as_str).collect(), t.line, t.end_line))
+        .collect();
+    assert_eq!(found, vec![
+        ("parses_empty", vec!["tests"], 15, 17),
+        ("parses_many", vec!["tests", "nested"], 22, 24),
+        ("unrelated", vec!["tests"], 28, 31),
+    ]);
+    assert_eq!(test_at(PARSER, 16).unwrap().name, "parses_empty");
+    assert!(test_at(PARSER, 3).is_none());
+}
+
+#[test]
+fn test_calls() {
+    assert!(calls("assert!(parse(\"\").is_empty());", "parse"));
+    assert!(calls("super::super::parse_all(&[])", "parse_all"));
+    assert!(calls("x.parse::<u8>()", "parse"));
+    assert!(!calls("let parsed = 1;", "parse"));
+    assert!(!calls("parse_all(&[])", "parse"));
+    assert!(!calls("fn parse() {}", "parse"));
+}
+
+fn scratch_workspace() -> ScratchDir {
+    let dir = ScratchDir::new("related-tests");
+    dir.write_all(&[
+        ("Cargo.toml", "[package]\nname = \"root\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\nmembers = [\"member\"]\n"),
+        ("src/lib.rs", "pub mod parser;\n"),
+        ("src/parser.rs", PARSER),
+        ("tests/it.rs", "#[test]\nfn end_to_end() {\n    root::parser::parse(\"x\");\n}\n"),
+        ("member/Cargo.toml", "[package]\nname = \"member\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"),
+        ("member/src/main.rs", "fn main() {}\n\n#[test]\nfn uses_parse() {\n    parse(\"y\");\n}\n"),
+    ]);
+    dir
+}
+
+#[tokio::test]
+async fn test_related_tests_command_falls_back_to_text_search() {
+    let dir = scratch_workspace();
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "related_tests",
+        "params": {"name": "parse", "file": "src/parser.rs", "line": 2, "column": 8}
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["method"], "text_search");
+    assert_eq!(result["total"], 3);
+    let commands: Vec<&str> = result["tests"].as_array().unwrap().iter().map(|t| t["command"].as_str().unwrap()).collect();
+    assert_eq!(commands, vec![
+        "cargo test -p member --bin member -- uses_parse --exact",
+        "cargo test --lib -- parser::tests::parses_empty --exact",
+        "cargo test --test it -- end_to_end --exact",
+    ]);
+    assert_eq!(result["tests"][1]["file"], "src/parser.rs");
+    assert_eq!(result["tests"][1]["line"], 15);
+
+    // Without a position there is nothing to name the function after
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "related_tests", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert!(response["error"]["message"].as_str().unwrap().contains("name"));
+}
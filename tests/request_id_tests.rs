@@ -0,0 +1,152 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::http_client::HttpClient;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+async fn server() -> McpServer {
+    let config = Config { use_lsp: false, ..Config::default() };
+    McpServer::with_config(".", config).await.unwrap()
+}
+
+async fn respond(server: &McpServer, request: Value) -> Value {
+    serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_string_id_preserved_on_success_and_errors() {
+    let server = server().await;
+
+    let response = respond(&server, json!({"jsonrpc": "2.0", "id": "abc-123", "method": "tools/list"})).await;
+    assert_eq!(response["id"], "abc-123");
+    assert!(response["result"]["tools"].is_array());
+
+    let response = respond(&server, json!({"jsonrpc": "2.0", "id": "abc-124", "method": "no/such/method"})).await;
+    assert_eq!(response["id"], "abc-124");
+    assert_eq!(response["error"]["code"], -32601);
+
+    let response = respond(&server, json!({
+        "jsonrpc": "2.0",
+        "id": "abc-125",
+        "method": "tools/call",
+        "params": {"name": "file_summary", "arguments": {"file": "does/not/exist.rs"}}
+    })).await;
+    assert_eq!(response["id"], "abc-125");
+
+    let response = respond(&server, json!({"jsonrpc": "2.0", "id": "abc-126"})).await;
+    assert_eq!(response["id"], "abc-126");
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_numeric_and_null_ids_preserved() {
+    let server = server().await;
+
+    let response = respond(&server, json!({"jsonrpc": "2.0", "id": 42, "method": "tools/list"})).await;
+    assert_eq!(response["id"], 42);
+
+    let response = respond(&server, json!({"jsonrpc": "2.0", "id": null, "method": "tools/list"})).await;
+    assert!(response.get("id").is_some_and(|id| id.is_null()));
+}
+
+#[test]
+fn test_error_path_helpers_keep_original_id() {
+    assert_eq!(McpServer::request_id(r#"{"jsonrpc":"2.0","id":"abc-123","method":"x"}"#), json!("abc-123"));
+    assert_eq!(McpServer::request_id(r#"{"jsonrpc":"2.0","id":7}"#), json!(7));
+    // Unparseable input has no id to echo
+    assert_eq!(McpServer::request_id("not json"), Value::Null);
+
+    // Daemon REST endpoints answer with a placeholder id
+    let restored = HttpClient::restore_id(r#"{"jsonrpc":"2.0","id":1,"result":{}}"#, Some(json!("abc-123")));
+    let restored: Value = serde_json::from_str(&restored).unwrap();
+    assert_eq!(restored["id"], "abc-123");
+}
+
+#[tokio::test]
+async fn test_resources_read_answers_with_client_id() {
+    let server = server().await;
+
+    let response = respond(&server, json!({
+        "jsonrpc": "2.0",
+        "id": "read-7",
+        "method": "resources/read",
+        "params": {"uri": "rust-analyzer://project/structure"}
+    })).await;
+    assert_eq!(response["id"], "read-7");
+    assert!(response["result"]["contents"][0]["text"].is_string());
+    assert!(!response.to_string().contains("internal-"));
+
+    let response = respond(&server, json!({
+        "jsonrpc": "2.0",
+        "id": 8,
+        "method": "resources/read",
+        "params": {"uri": "rust-analyzer://project/nothing"}
+    })).await;
+    assert_eq!(response["id"], 8);
+}
+
+#[test]
+fn test_internal_ids_do_not_repeat() {
+    let first = McpServer::internal_request_id();
+    let second = McpServer::internal_request_id();
+    assert_ne!(first, second);
+    assert!(first.as_str().unwrap().starts_with("internal-"));
+}
+
+#[tokio::test]
+async fn test_batch_requests_get_correlated_responses() {
+    let server = server().await;
+
+    let response = respond(&server, json!([
+        {"jsonrpc": "2.0", "id": 1, "method": "tools/list"},
+        {"jsonrpc": "2.0", "method": "notifications/initialized"},
+        {"jsonrpc": "2.0", "id": "two", "method": "no/such/method"}
+    ])).await;
+    let responses = response.as_array().unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 1);
+    assert!(responses[0]["result"]["tools"].is_array());
+    assert_eq!(responses[1]["id"], "two");
+    assert_eq!(responses[1]["error"]["code"], -32601);
+
+    let response = respond(&server, json!([])).await;
+    assert_eq!(response["id"], Value::Null);
+    assert_eq!(response["error"]["code"], -32600);
+
+    let response = respond(&server, json!([1])).await;
+    assert_eq!(response[0]["error"]["code"], -32600);
+
+    let only_notifications = json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}]);
+    assert_eq!(server.handle_request(&only_notifications.to_string()).await.unwrap(), "");
+}
+
+#[tokio::test]
+async fn test_malformed_json_gets_a_parse_error_with_its_id() {
+    let server = server().await;
+    let respond_raw = |line: &'static str| {
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(line).await.unwrap()).unwrap() }
+    };
+
+    let response = respond_raw(r#"{"jsonrpc":"2.0","id":"abc-127","method":"tools/list""#).await;
+    assert_eq!(response["id"], "abc-127");
+    assert_eq!(response["error"]["code"], -32700);
+
+    // Two requests run together answer for the first
+    let response = respond_raw(r#"{"jsonrpc":"2.0","id":8,"method":"tools/list"}{"jsonrpc":"2.0","id":9,"method":"tools/list"}"#).await;
+    assert_eq!(response["id"], 8);
+    assert_eq!(response["error"]["code"], -32700);
+
+    let response = respond_raw("garbage").await;
+    assert_eq!(response["id"], Value::Null);
+    assert_eq!(response["error"]["code"], -32700);
+}
+
+#[test]
+fn test_request_id_of_malformed_input() {
+    // Only the outer object's id counts, not one inside params or a string
+    assert_eq!(McpServer::request_id(r#"{"params":{"id":1},"note":"\"id\":2","id":3,"method""#), json!(3));
+    assert_eq!(McpServer::request_id(r#"{"jsonrpc":"2.0", "id" : null, "method":"#), Value::Null);
+    assert_eq!(McpServer::request_id(r#"{"params":{"id":1}, "method":"x""#), Value::Null);
+    assert_eq!(McpServer::request_id(r#"{"id":{"nested":true},"#), Value::Null);
+    assert_eq!(McpServer::request_id(r#"{"id":"unterminated"#), Value::Null);
+}
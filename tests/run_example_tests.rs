@@ -0,0 +1,100 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::run_example::{stream_output, OutputLine};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const EXAMPLES: &[(&str, &str)] = &[
+    (
+        "examples/echo.rs",
+        "fn main() {\n    let args: Vec<String> = std::env::args().skip(1).collect();\n    println!(\"{}{}\", args.join(\" \"), if cfg!(feature = \"loud\") { \"!\" } else { \"\" });\n    eprintln!(\"done\");\n    std::process::exit(3);\n}\n",
+    ),
+    ("examples/forever.rs", "fn main() {\n    loop {\n        std::thread::sleep(std::time::Duration::from_millis(100));\n    }\n}\n"),
+];
+
+async fn run_example(dir: &Path, max_run_seconds: u64, params: Value) -> Value {
+    let config = Config { use_lsp: false, max_run_seconds, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let mut params = params;
+    params["method"] = json!("run_example");
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "run_example", "params": params });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    response["result"].clone()
+}
+
+#[tokio::test]
+async fn test_run_example_captures_output_and_exit_code() {
+    let dir = ScratchDir::with_crate("run-example-echo", "\n[features]\nloud = []\n\n[workspace]\n");
+    dir.write("src/lib.rs", "");
+    dir.write_all(EXAMPLES);
+    let result = run_example(&dir, 120, json!({ "example": "echo", "args": ["hello", "world"], "features": ["loud"] })).await;
+
+    assert_eq!(result["stdout"], "hello world!\n");
+    assert!(result["stderr"].as_str().unwrap().ends_with("done\n"));
+    assert_eq!(result["exit_code"], 3);
+    assert_eq!(result["success"], false);
+    assert_eq!(result["timed_out"], false);
+    assert_eq!(result["command"], "cargo run --example echo --features loud -- hello world");
+}
+
+#[tokio::test]
+async fn test_run_example_kills_on_timeout() {
+    let dir = ScratchDir::with_crate("run-example-forever", "\n[features]\nloud = []\n\n[workspace]\n");
+    dir.write("src/lib.rs", "");
+    dir.write_all(EXAMPLES);
+    // The request asks for longer than the configured cap allows
+    let result = run_example(&dir, 3, json!({ "example": "forever", "timeout_secs": 600 })).await;
+
+    assert_eq!(result["timed_out"], true);
+    assert_eq!(result["success"], false);
+    assert!(result["exit_code"].is_null());
+    assert!(result["duration_ms"].as_u64().unwrap() < 10_000);
+}
+
+#[tokio::test]
+async fn test_run_example_streams_output_lines() {
+    let dir = ScratchDir::with_crate("run-example-stream", "\n[features]\nloud = []\n\n[workspace]\n");
+    dir.write("src/lib.rs", "");
+    dir.write_all(EXAMPLES);
+    let config = Config { use_lsp: false, max_run_seconds: 120, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<OutputLine>(4);
+
+    let run = stream_output(tx, server.call_tool("run_example", Some(json!({ "example": "echo", "args": ["hi"] }))));
+    let collect = async {
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        lines
+    };
+    let (result, lines) = tokio::join!(run, collect);
+    let result = result.unwrap();
+
+    assert!(lines.contains(&OutputLine { stream: "stdout".to_string(), line: "hi".to_string() }));
+    assert!(lines.contains(&OutputLine { stream: "stderr".to_string(), line: "done".to_string() }));
+    // The result still carries the whole output
+    assert_eq!(result["stdout"], "hi\n");
+    assert_eq!(result["exit_code"], 3);
+}
+
+#[tokio::test]
+async fn test_run_example_stops_when_output_is_no_longer_read() {
+    let dir = ScratchDir::with_crate("run-example-disconnect", "\n[features]\nloud = []\n\n[workspace]\n");
+    dir.write("src/lib.rs", "");
+    dir.write_all(EXAMPLES);
+    let config = Config { use_lsp: false, max_run_seconds: 120, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let (tx, rx) = tokio::sync::mpsc::channel::<OutputLine>(4);
+    drop(rx);
+
+    let result = stream_output(tx, server.call_tool("run_example", Some(json!({ "example": "forever" })))).await.unwrap();
+
+    assert_eq!(result["success"], false);
+    assert_eq!(result["timed_out"], false);
+    assert!(result["exit_code"].is_null());
+    assert!(result["duration_ms"].as_u64().unwrap() < 10_000);
+}
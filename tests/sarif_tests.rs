@@ -0,0 +1,58 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::sarif::{level, to_sarif};
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+#[test]
+fn test_to_sarif_maps_rules_levels_and_locations() {
+    let diagnostics = vec![
+        json!({"file": "src/lib.rs", "line": 3, "column": 9, "level": "warning", "message": "unused variable: `x`",
+               "code": {"code": "unused_variables", "explanation": null}, "source": "cargo"}),
+        json!({"file": "src/main.rs", "line": 7, "column": 18, "level": "error", "message": "mismatched types",
+               "code": {"code": "E0308", "explanation": "\nExpected type did not match the received type.\n\nErroneous code examples:\n"}, "source": "cargo"}),
+        json!({"file": "src/lib.rs", "line": 5, "column": 9, "level": "warning", "message": "unused variable: `y`",
+               "code": {"code": "unused_variables", "explanation": null}, "source": "cargo"}),
+        json!({"file": "/abs/dep/src/lib.rs", "line": 1, "column": 1, "level": "warning", "message": "unstable feature",
+               "code": null, "source": "cargo"}),
+    ];
+    let log = to_sarif(&diagnostics);
+    assert_eq!(log["version"], "2.1.0");
+
+    let run = &log["runs"][0];
+    let rules: Vec<&str> = run["tool"]["driver"]["rules"].as_array().unwrap().iter().map(|r| r["id"].as_str().unwrap()).collect();
+    assert_eq!(rules, vec!["unused_variables", "E0308", "rustc"]);
+    let e0308 = &run["tool"]["driver"]["rules"][1];
+    assert_eq!(e0308["helpUri"], "https://doc.rust-lang.org/error_codes/E0308.html");
+    assert_eq!(e0308["shortDescription"]["text"], "Expected type did not match the received type.");
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[1]["level"], "error");
+    assert_eq!(results[2]["ruleIndex"], 0);
+    assert_eq!(results[1]["locations"][0]["physicalLocation"], json!({
+        "artifactLocation": {"uri": "src/main.rs", "uriBaseId": "%SRCROOT%"},
+        "region": {"startLine": 7, "startColumn": 18}
+    }));
+    assert!(results[3]["locations"][0]["physicalLocation"]["artifactLocation"].get("uriBaseId").is_none());
+
+    assert_eq!(
+        ["error: internal compiler error", "warning", "help", "failure-note"].map(level),
+        ["error", "warning", "note", "note"]
+    );
+}
+
+#[tokio::test]
+async fn test_sarif_format_rejects_grouping() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "get_diagnostics", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap() }
+    };
+
+    let response = call(json!({"format": "sarif", "group_by": "lint"})).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("group_by"), "{response}");
+    let response = call(json!({"format": "xml"})).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("expected one of"), "{response}");
+}
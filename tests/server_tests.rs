@@ -1,4 +1,4 @@
-use mcp_rust_analyzer::server::{McpServer, CommandHandler};
+use mcp_rust_analyzer::server::McpServer;
 use serde_json::json;
 
 #[tokio::test]
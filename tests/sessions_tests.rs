@@ -0,0 +1,56 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::sessions::Sessions;
+use serde_json::{json, Value};
+
+#[test]
+fn test_sessions_close_only_files_no_other_session_pins() {
+    let mut sessions = Sessions::new();
+    let first = sessions.new_id();
+    let second = sessions.new_id();
+    assert_eq!((first.as_str(), second.as_str()), ("session-1", "session-2"));
+
+    sessions.pin(&first, ["src/a.rs".to_string(), "src/b.rs".to_string()]);
+    assert_eq!(sessions.pin(&first, ["src/a.rs".to_string()]), vec!["src/a.rs", "src/b.rs"]);
+    sessions.pin(&second, ["src/b.rs".to_string()]);
+
+    assert_eq!(sessions.close(&first), Some((vec!["src/a.rs".to_string()], vec!["src/b.rs".to_string()])));
+    assert!(sessions.is_pinned("src/b.rs"));
+    assert!(!sessions.is_pinned("src/a.rs"));
+    assert_eq!(sessions.close(&first), None);
+    assert_eq!(sessions.ids(), vec!["session-2"]);
+}
+
+async fn call(server: &McpServer, method: &str, params: Value) -> Value {
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_open_documents_reports_each_file() {
+    let dir = ScratchDir::with_crate("sessions", "");
+    dir.write("src/lib.rs", "pub fn answer() -> u32 { 42 }\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let response = call(&server, "open_documents", json!({"files": ["src/lib.rs", "src/missing.rs", "../outside.rs"]})).await;
+    let result = &response["result"];
+    assert_eq!(result["session"], "session-1");
+    assert_eq!(result["opened"], json!([]));
+    let failed: Vec<(&str, &str)> = result["failed"].as_array().unwrap().iter()
+        .map(|f| (f["file"].as_str().unwrap(), f["error"].as_str().unwrap()))
+        .collect();
+    assert_eq!(failed, vec![
+        ("src/missing.rs", "No such file"),
+        ("../outside.rs", "Path ../outside.rs is outside the project root"),
+        ("src/lib.rs", "LSP not available"),
+    ]);
+
+    let response = call(&server, "close_session", json!({"session": "session-1"})).await;
+    assert_eq!(response["result"], json!({"session": "session-1", "closed": [], "kept_open": []}));
+    let response = call(&server, "close_session", json!({"session": "session-1"})).await;
+    assert_eq!(response["error"]["message"], "Command failed: Unknown session: session-1; no session is open");
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::shadowing::{find, scan_source};
+use serde_json::{json, Value};
+
+const SOURCE: &str = r#"
+pub fn parse_port(input: &str) -> u16 {
+    let input = input.trim();
+    let port = "8080";
+    let port: u16 = port.parse().unwrap();
+    let port = port;
+    port
+}
+
+fn totals(items: Vec<u32>) -> usize {
+    let count = 0;
+    {
+        let count = items.len();
+        let _ = count;
+    }
+    let (count, mut rest) = (1, 2);
+    rest += count;
+    let total = Some(rest);
+    let total = total.unwrap();
+    let total = total as usize;
+    total
+}
+
+fn separate() {
+    if true {
+        let name = String::new();
+        drop(name);
+    }
+    let name = "other";
+    drop(name);
+}
+
+trait Shape {
+    fn area(&self) -> f64;
+}
+
+#[cfg(test)]
+mod tests {
+    fn helper() {
+        let a = 1;
+        let a = a + 1;
+    }
+}
+"#;
+
+#[test]
+fn test_scan_source() {
+    let functions = scan_source(SOURCE, false);
+    let names: Vec<(&str, usize, usize)> = functions.iter().map(|f| (f.function.as_str(), f.line, f.shadowings.len())).collect();
+    // The `name` bound in a closed block isn't in scope any more
+    assert_eq!(names, vec![("parse_port", 2, 3), ("totals", 10, 4)]);
+
+    let found: Vec<_> = functions.iter().flat_map(|f| &f.shadowings)
+        .map(|s| (s.name.as_str(), s.line, s.previous_line, s.same_block, s.shadows_parameter, s.type_changed))
+        .collect();
+    assert_eq!(found, vec![
+        ("input", 3, 2, false, true, None),
+        ("port", 5, 4, true, false, Some(true)),
+        ("port", 6, 5, true, false, Some(false)),
+        ("count", 13, 11, false, false, None),
+        ("count", 16, 11, true, false, None),
+        ("total", 19, 18, true, false, Some(true)),
+        ("total", 20, 19, true, false, None),
+    ]);
+
+    let port = &functions[0].shadowings[1];
+    assert_eq!((port.previous_type.as_deref(), port.new_type.as_deref()), (Some("&str"), Some("u16")));
+    assert_eq!(port.column, 5);
+    assert_eq!(port.code, "let port: u16 = port.parse().unwrap();");
+
+    let functions = scan_source(SOURCE, true);
+    assert_eq!(functions.last().unwrap().function, "helper");
+}
+
+#[test]
+fn test_type_change_heuristics() {
+    let source = "\
+fn f(id: Option<u32>, raw: String) {
+    let id = id?;
+    let raw = &raw;
+    let n = \"12\";
+    let n = n.parse::<i64>()?;
+    let v = vec![1, 2];
+    let v: Vec<u8> = v.into_iter().collect();
+    let flag = 1.5;
+    let flag = false;
+}
+";
+    let shadowings = &scan_source(source, false)[0].shadowings;
+    let found: Vec<(&str, Option<bool>, Option<&str>)> = shadowings.iter()
+        .map(|s| (s.name.as_str(), s.type_changed, s.new_type.as_deref()))
+        .collect();
+    assert_eq!(found, vec![
+        ("id", Some(true), Some("u32")),
+        ("raw", Some(true), Some("&String")),
+        ("n", Some(true), Some("i64")),
+        ("v", Some(false), Some("Vec<u8>")),
+        ("flag", Some(true), Some("bool")),
+    ]);
+}
+
+#[tokio::test]
+async fn test_find_shadowing_command() {
+    let dir = ScratchDir::with_crate("shadowing", "\n[workspace]\n");
+    dir.write("src/lib.rs", SOURCE);
+    let report = find(&dir, &dir.join("src"), false);
+    assert_eq!(report.files_scanned, 1);
+    assert_eq!(report.total, 7);
+    assert_eq!(report.type_changes, 2);
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "find_shadowing", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["total"], 7);
+    assert_eq!(result["functions"][0]["file"], "src/lib.rs");
+    assert_eq!(result["functions"][1]["function"], "totals");
+    assert!(result["functions"][0]["shadowings"][0].get("type_changed").is_none());
+}
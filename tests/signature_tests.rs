@@ -0,0 +1,164 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::markup::ContentFormat;
+use mcp_rust_analyzer::signature::{
+    extract_from_hover, extract_signature_text, from_signature_help, impl_block_methods, parse_completion_detail, parse_signature, Receiver,
+    SignatureHelp,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn test_parse_full_signature() {
+    let sig = parse_signature(
+        "pub async fn fetch<'a, T: Clone>(&self, items: &'a [T], f: impl Fn(u32) -> u32) -> Result<Vec<T>, Error> where T: Send"
+    ).unwrap();
+    
+    assert_eq!(sig.name, "fetch");
+    assert_eq!(sig.qualifiers, vec!["pub", "async"]);
+    assert_eq!(sig.generics.as_deref(), Some("<'a, T: Clone>"));
+    assert_eq!(sig.params.len(), 3);
+    assert_eq!(sig.params[0].name, "self");
+    assert_eq!(sig.params[0].ty, "&self");
+    assert_eq!(sig.params[1].ty, "&'a [T]");
+    assert_eq!(sig.params[2].ty, "impl Fn(u32) -> u32");
+    assert_eq!(sig.return_type.as_deref(), Some("Result<Vec<T>, Error>"));
+    assert_eq!(sig.where_clause.as_deref(), Some("where T: Send"));
+}
+
+#[test]
+fn test_completion_detail_and_receiver() {
+    let sig = parse_completion_detail("push", "pub fn(&mut self, value: T)").unwrap();
+    assert_eq!(sig.rendered, "pub fn push(&mut self, value: T)");
+    assert_eq!(sig.receiver(), Some(Receiver::RefMut));
+
+    let receivers: Vec<Option<Receiver>> = [
+        "fn(&self) -> usize",
+        "const fn(&'a mut self)",
+        "fn(self) -> IntoIter<T>",
+        "fn(mut self: Box<Self>)",
+        "fn(self: Pin<&mut Self>, cx: &mut Context<'_>)",
+        "fn(capacity: usize) -> Vec<T>",
+    ].iter().map(|detail| parse_completion_detail("m", detail).unwrap().receiver()).collect();
+    assert_eq!(receivers, vec![
+        Some(Receiver::Ref), Some(Receiver::RefMut), Some(Receiver::Owned),
+        Some(Receiver::Owned), Some(Receiver::RefMut), None,
+    ]);
+
+    // A detail that already names the function is parsed as is
+    assert_eq!(parse_completion_detail("len", "fn len(&self) -> usize").unwrap().name, "len");
+}
+
+#[test]
+fn test_extract_multiline_signature_from_source() {
+    let source = "/// Docs\n#[inline]\npub fn add(\n    a: u32,\n    b: std::num::NonZeroU32,\n) -> u32 {\n    a + b.get()\n}\n";
+    
+    let text = extract_signature_text(source, 0).unwrap();
+    let sig = parse_signature(&text).unwrap();
+    assert_eq!(sig.name, "add");
+    assert_eq!(sig.params[1].ty, "std::num::NonZeroU32");
+    assert_eq!(sig.return_type.as_deref(), Some("u32"));
+    assert_eq!(sig.rendered, "pub fn add(a: u32, b: std::num::NonZeroU32) -> u32");
+}
+
+#[test]
+fn test_extract_signature_of_the_item_at_the_cursor() {
+    let source = "pub struct Config {\n    pub retries: u32,\n}\n\npub fn load(\n    path: &str,\n) -> Config {\n    if path.is_empty() {\n        return Config { retries: 0 };\n    }\n    Config { retries: 3 }\n}\n\nfn helper() {}\n";
+    let name = |line: usize| extract_signature_text(source, line).and_then(|t| parse_signature(&t)).map(|s| s.name);
+
+    // A field isn't part of the function declared below it
+    assert_eq!(name(1), None);
+    assert_eq!(name(3), None);
+    // The signature's own lines, and anywhere in its body, nested blocks included
+    assert_eq!(name(5).as_deref(), Some("load"));
+    assert_eq!(name(6).as_deref(), Some("load"));
+    assert_eq!(name(8).as_deref(), Some("load"));
+    assert_eq!(name(10).as_deref(), Some("load"));
+    assert_eq!(name(13).as_deref(), Some("helper"));
+}
+
+#[test]
+fn test_extract_from_hover_markdown() {
+    let hover = "```rust\nmy_crate::module\n```\n\n```rust\npub fn get_field(&self) -> &str\n```\n\n---\n\nReturns the field";
+    let text = extract_from_hover(hover).unwrap();
+    assert_eq!(text, "pub fn get_field(&self) -> &str");
+    
+    assert!(extract_from_hover("```rust\nstruct Foo\n```").is_none());
+}
+
+#[tokio::test]
+async fn test_signature_at_without_lsp() {
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(".", config).await.unwrap();
+    
+    // Locate the definition of `Config::resolve` so the test survives edits above it
+    let source = std::fs::read_to_string("src/config.rs").unwrap();
+    let line = source.lines().position(|l| l.contains("pub fn resolve(")).unwrap() + 1;
+    
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "signature_at",
+        "params": {"method": "signature_at", "file": "src/config.rs", "line": line, "column": 5}
+    });
+    let response: Value = serde_json::from_str(
+        &server.handle_request(&request.to_string()).await.unwrap()
+    ).unwrap();
+    
+    let result = &response["result"];
+    assert_eq!(result["source"], "source");
+    assert_eq!(result["signature"]["name"], "resolve");
+    assert_eq!(result["signature"]["params"][0]["name"], "project_root");
+    assert_eq!(result["signature"]["return_type"], "Result<Self>");
+}
+
+#[test]
+fn test_impl_block_methods() {
+    let source = std::fs::read_to_string("tests/test_project/src/lib.rs").unwrap();
+    let impl_line = source.lines().position(|l| l.starts_with("impl TestStruct")).unwrap();
+    
+    let methods = impl_block_methods(&source, impl_line);
+    let names: Vec<String> = methods.iter()
+        .map(|(_, text)| parse_signature(text).unwrap().name)
+        .collect();
+    assert_eq!(names, vec!["new", "get_field"]);
+    
+    // Trait impls only report their own methods, not the next impl's
+    let trait_impl_line = source.lines().position(|l| l.starts_with("impl TestTrait for")).unwrap();
+    let methods = impl_block_methods(&source, trait_impl_line);
+    assert_eq!(methods.len(), 1);
+    assert_eq!(methods[0].1, "fn test_method(&self)");
+}
+
+#[test]
+fn test_from_signature_help() {
+    let response = json!({
+        "signatures": [{
+            "label": "fn insert(&mut self, k: K, v: V) -> Option<V>",
+            "documentation": { "kind": "markdown", "value": "Inserts a **key-value** pair." },
+            "parameters": [{ "label": [10, 19] }, { "label": [21, 25] }, { "label": [27, 31] }],
+            "activeParameter": 2
+        }],
+        "activeSignature": 0,
+        "activeParameter": 1
+    });
+
+    let help = from_signature_help(&response, ContentFormat::Plaintext);
+    assert_eq!(help.active_signature, Some(0));
+    // The signature's own active parameter wins
+    assert_eq!(help.active_parameter, Some(2));
+    let signature = &help.signatures[0];
+    assert_eq!(signature.parameters.iter().map(|p| p.label.as_str()).collect::<Vec<_>>(), vec!["&mut self", "k: K", "v: V"]);
+    assert_eq!(signature.documentation.as_deref(), Some("Inserts a key-value pair."));
+
+    let help = serde_json::to_value(from_signature_help(&json!({ "signatures": [{ "label": "fn f(x: u8)", "parameters": [{ "label": "x: u8" }] }], "activeParameter": 0 }), ContentFormat::Markdown)).unwrap();
+    assert_eq!(help, json!({ "signatures": [{ "label": "fn f(x: u8)", "parameters": [{ "label": "x: u8" }] }], "activeSignature": 0, "activeParameter": 0 }));
+}
+
+#[test]
+fn test_from_signature_help_without_signatures() {
+    for response in [Value::Null, json!({}), json!({ "signatures": [] })] {
+        assert_eq!(from_signature_help(&response, ContentFormat::Markdown), SignatureHelp::default());
+    }
+    let empty = serde_json::to_value(SignatureHelp::default()).unwrap();
+    assert_eq!(empty, json!({ "signatures": [], "activeSignature": null, "activeParameter": null }));
+}
@@ -1,5 +1,5 @@
 // Simple test to verify LSP client compilation
-use mcp_rust_analyzer::lsp_client::{LspClient, LspClientConfig};
+use mcp_rust_analyzer::lsp_client::{LspClientConfig, DEFAULT_REQUEST_TIMEOUT};
 use std::path::PathBuf;
 
 #[test]
@@ -8,6 +8,7 @@ fn test_lsp_client_struct_creation() {
         server_path: "rust-analyzer".to_string(),
         server_args: vec![],
         root_path: PathBuf::from("."),
+        timeout: DEFAULT_REQUEST_TIMEOUT,
     };
     
     // Just verify we can create the config
@@ -0,0 +1,156 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::analyzer::RustAnalyzer;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::source_files::{walker, FileId, FileIndex, SourceFile, TextSize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+fn scratch_workspace(name: &str) -> ScratchDir {
+    let dir = ScratchDir::with_crate(&format!("source-files-{}", name), "\n[workspace]\n");
+    dir.write_all(&[
+        (".gitignore", "/generated/\n*.bak.rs\n# comment\n"),
+        ("src/lib.rs", "pub mod a;\n"),
+        ("src/a/mod.rs", "fn héllo() {}\r\nfn second() {}\n"),
+        ("src/old.bak.rs", ""),
+        ("src/a/.gitignore", "scratch.rs\n"),
+        ("src/a/scratch.rs", ""),
+        ("generated/out.rs", ""),
+        ("target/debug/build.rs", ""),
+        (".hidden/x.rs", ""),
+        ("tests/it.rs", ""),
+    ]);
+    dir
+}
+
+fn relative(dir: &Path, files: &[(FileId, PathBuf)]) -> Vec<String> {
+    files.iter().map(|(_, path)| path.strip_prefix(dir).unwrap().display().to_string()).collect()
+}
+
+#[test]
+fn test_index_respects_ignores_and_keeps_ids() {
+    let dir = scratch_workspace("index");
+    let mut index = FileIndex::new(&dir);
+
+    let files = index.files();
+    assert_eq!(relative(&dir, &files), vec!["src/a/mod.rs", "src/lib.rs", "tests/it.rs"]);
+    let lib = index.file_id(Path::new("src/lib.rs")).unwrap();
+    assert_eq!(index.file_id(&dir.join("src/lib.rs")).unwrap(), lib);
+    assert_eq!(index.path(lib), Some(dir.join("src/lib.rs").as_path()));
+
+    // Ignored files can still be looked up, missing ones can't
+    let generated = index.file_id(Path::new("generated/out.rs")).unwrap();
+    assert!(index.file_id(Path::new("src/missing.rs")).is_err());
+
+    dir.write("src/b.rs", "");
+    std::fs::remove_file(dir.join("tests/it.rs")).unwrap();
+    index.scan();
+    let files = index.files();
+    assert_eq!(relative(&dir, &files), vec!["src/a/mod.rs", "src/b.rs", "src/lib.rs"]);
+    assert_eq!(index.file_id(Path::new("src/lib.rs")).unwrap(), lib);
+    let b = index.file_id(Path::new("src/b.rs")).unwrap();
+    assert!(b != lib && b != generated);
+}
+
+#[test]
+fn test_index_leaves_out_config_ignore_globs() {
+    let dir = scratch_workspace("ignore-globs");
+    let mut index = FileIndex::with_ignore(&dir, &["src/a/**".to_string(), "it.rs".to_string()]).unwrap();
+
+    assert_eq!(relative(&dir, &index.files()), vec!["src/lib.rs"]);
+    // Left out of the listing only
+    assert!(index.file_id(Path::new("src/a/mod.rs")).is_ok());
+    assert!(FileIndex::with_ignore(&dir, &["src/[".to_string()]).is_err());
+}
+
+#[test]
+fn test_offsets_and_cache() {
+    let dir = scratch_workspace("offsets");
+    let file = SourceFile::load(FileId(0), &dir.join("src/a/mod.rs")).unwrap();
+    assert_eq!(file.offset(1, 1).unwrap(), TextSize(0));
+    // Columns count characters, `é` being two bytes
+    assert_eq!(file.offset(1, 6).unwrap(), TextSize(6));
+    assert_eq!(file.offset(2, 4).unwrap(), TextSize(19));
+    // Just past the last character, before the `\r\n`
+    assert_eq!(file.offset(1, 14).unwrap(), TextSize(14));
+    assert!(file.offset(1, 15).is_err());
+    assert!(file.offset(4, 1).is_err());
+    assert_eq!(file.line_col(TextSize(19)).unwrap(), (2, 4));
+    assert_eq!(file.line_col(TextSize(6)).unwrap(), (1, 6));
+    assert!(file.line_col(TextSize(5)).is_err());
+
+    let mut index = FileIndex::new(&dir);
+    let id = index.file_id(Path::new("src/lib.rs")).unwrap();
+    assert_eq!(index.source(id).unwrap().text(), "pub mod a;\n");
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    dir.write("src/lib.rs", "pub mod a;\npub mod b;\n");
+    assert_eq!(index.source(id).unwrap().line_count(), 3);
+}
+
+#[tokio::test]
+async fn test_analyzer_uses_file_index() {
+    let dir = scratch_workspace("analyzer");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let analyzer = RustAnalyzer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let files = analyzer.get_all_files();
+    assert_eq!(relative(&dir, &files), vec!["src/a/mod.rs", "src/lib.rs", "tests/it.rs"]);
+    let position = analyzer.get_file_position("src/a/mod.rs", 2, 4).unwrap();
+    assert_eq!(position.file_id, files[0].0);
+    assert_eq!(position.offset, TextSize(19));
+    let range = analyzer.get_file_range("src/a/mod.rs", 1, 4, 1, 9).unwrap();
+    assert_eq!((range.range.start, range.range.end), (TextSize(3), TextSize(9)));
+    assert!(analyzer.get_file_id("src/nope.rs").is_err());
+}
+
+#[test]
+fn test_walker_skips_target_and_ignored_files() {
+    let dir = scratch_workspace("walker");
+    let files = |include_hidden: bool| -> Vec<String> {
+        walker(&dir, include_hidden).build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()) && entry.path().extension().is_some_and(|e| e == "rs"))
+            .map(|entry| entry.path().strip_prefix(&dir).unwrap().display().to_string())
+            .collect()
+    };
+    assert_eq!(files(false), vec!["src/a/mod.rs", "src/lib.rs", "tests/it.rs"]);
+    assert_eq!(files(true), vec![
+        ".hidden/x.rs", "generated/out.rs", "src/a/mod.rs", "src/a/scratch.rs", "src/lib.rs", "src/old.bak.rs", "tests/it.rs",
+    ]);
+
+    // Rules of parent directories apply when walking a subdirectory
+    let src: Vec<String> = walker(&dir.join("src"), false).build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(src, vec!["mod.rs", "lib.rs"]);
+}
+
+#[tokio::test]
+async fn test_code_metrics_and_structure_skip_ignored_files() {
+    let dir = scratch_workspace("metrics");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |method: &str, params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let server = &server;
+        async move {
+            let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+            response["result"].clone()
+        }
+    };
+
+    let metrics = call("code_metrics", json!({ "module": "." })).await;
+    assert_eq!(metrics["metrics"]["file_count"], 3);
+    assert_eq!(metrics["metrics"]["functions"], 2);
+    let everything = call("code_metrics", json!({ "module": ".", "include_hidden": true })).await;
+    assert_eq!(everything["metrics"]["file_count"], 7);
+
+    let structure = call("project_structure", json!({})).await;
+    let names: Vec<&str> = structure["modules"].as_array().unwrap().iter().map(|m| m["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["a", "lib.rs"]);
+    assert_eq!(structure["modules"][0]["submodules"].as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::outline::symbol_kind_number;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::symbol_search::{definition_kind, definitions, from_lsp, identifiers, matches, symbol_type, MatchOptions, WorkspaceSymbol};
+use serde_json::{json, Value};
+use std::path::Path;
+
+#[test]
+fn test_identifiers_skip_comments_and_literals() {
+    fn names(line: &str) -> Vec<&str> {
+        identifiers(line).into_iter().map(|(_, name)| name).collect()
+    }
+    assert_eq!(names("let x = Foo::new(\"new \\\" thing\"); // new"), vec!["let", "x", "Foo", "new"]);
+    assert_eq!(names("fn f<'a>(c: char) -> bool { c == '\\'' || c == 'n' }"), vec!["fn", "f", "a", "c", "char", "bool", "c", "c"]);
+    assert_eq!(identifiers("  renewable")[0], (2, "renewable"));
+}
+
+#[test]
+fn test_matches_whole_words_by_default() {
+    let options = MatchOptions::default();
+    assert!(matches("let x = Widget::new();", "new", options));
+    assert!(!matches("let renewable = new_name;", "new", options));
+    assert!(!matches("// call new here", "new", options));
+    assert!(!matches("println!(\"new\");", "new", options));
+    assert!(!matches("struct New;", "new", options));
+
+    let loose = MatchOptions { whole_word: false, case_sensitive: false };
+    assert!(matches("let renewable = 1;", "NEW", loose));
+    assert!(matches("println!(\"new\");", "new", loose));
+    assert!(!matches("let x = 1; // new", "new", loose));
+    assert!(matches("struct New;", "new", MatchOptions { whole_word: true, case_sensitive: false }));
+}
+
+#[test]
+fn test_definition_kind() {
+    let options = MatchOptions::default();
+    assert_eq!(definition_kind("    pub fn new() -> Self {", "new", options), Some("fn"));
+    assert_eq!(definition_kind("pub(crate) struct New;", "New", options), Some("struct"));
+    assert_eq!(definition_kind("macro_rules! new {", "new", options), Some("macro"));
+    assert_eq!(definition_kind("pub type Result<T> = std::result::Result<T, Error>;", "Result", options), Some("type"));
+    assert_eq!(definition_kind("let w = Widget::new();", "new", options), None);
+    assert_eq!(definition_kind("fn build() -> New {", "New", options), None);
+    assert_eq!(symbol_type("struct"), "Struct");
+    assert_eq!(symbol_type("fn"), "Function");
+}
+
+#[test]
+fn test_definitions_lists_every_item_on_a_line() {
+    assert_eq!(definitions("pub struct Parser; fn parse() {}"), vec![("struct", "Parser"), ("fn", "parse")]);
+    assert!(definitions("let parser = Parser::new();").is_empty());
+}
+
+#[test]
+fn test_from_lsp_normalizes_workspace_symbols() {
+    let symbol = json!({
+        "name": "parse",
+        "kind": 12,
+        "containerName": "parser",
+        "location": { "uri": "file:///work/demo/src/parser.rs", "range": { "start": { "line": 4, "character": 7 }, "end": { "line": 4, "character": 12 } } }
+    });
+    assert_eq!(from_lsp(&symbol, Path::new("/work/demo")), Some(WorkspaceSymbol {
+        name: "parse".to_string(),
+        kind: "function".to_string(),
+        file: "src/parser.rs".to_string(),
+        line: 5,
+        container: Some("parser".to_string()),
+    }));
+
+    // Outside the project the path stays absolute; a missing range is the first line
+    let symbol = json!({ "name": "Vec", "kind": 23, "location": { "uri": "file:///rust/alloc/src/vec/mod.rs" } });
+    let normalized = from_lsp(&symbol, Path::new("/work/demo")).unwrap();
+    assert_eq!((normalized.kind.as_str(), normalized.file.as_str(), normalized.line, normalized.container), ("struct", "/rust/alloc/src/vec/mod.rs", 1, None));
+
+    assert_eq!(symbol_kind_number("function"), Some(12));
+    assert_eq!(symbol_kind_number("trait"), Some(11));
+    assert_eq!(symbol_kind_number("functions"), None);
+}
+
+#[tokio::test]
+async fn test_search_workspace_symbols_falls_back_to_definitions() {
+    let dir = ScratchDir::with_crate("workspace-symbols", "");
+    dir.write("src/lib.rs", "mod parser;\n\npub struct Parser;\n// fn parse_comment() {}\n");
+    dir.write("src/parser/mod.rs", "use crate::Parser;\n\npub fn parse(p: &Parser) {}\nmacro_rules! parse_all { () => {} }\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "search_workspace_symbols", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap() }
+    };
+
+    let result = call(json!({"query": "PARSE"})).await["result"].clone();
+    assert_eq!(result["source"], "text_search", "{result}");
+    assert_eq!(result["count"], 4);
+    assert_eq!(result["symbols"], json!([
+        { "name": "parser", "kind": "module", "file": "src/lib.rs", "line": 1, "container": null },
+        { "name": "Parser", "kind": "struct", "file": "src/lib.rs", "line": 3, "container": null },
+        { "name": "parse", "kind": "function", "file": "src/parser/mod.rs", "line": 3, "container": null },
+        { "name": "parse_all", "kind": "function", "file": "src/parser/mod.rs", "line": 4, "container": null },
+    ]));
+
+    let result = call(json!({"query": "parse", "kind": "struct"})).await["result"].clone();
+    let names: Vec<&str> = result["symbols"].as_array().unwrap().iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Parser"]);
+
+    let response = call(json!({"query": "parse", "kind": "structs"})).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("Unknown symbol kind"), "{response}");
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::ScratchDir;
+use futures::StreamExt;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::http_client::HttpClient;
+use mcp_rust_analyzer::http_server::start_http_server;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// A stand-in for rust-analyzer that answers `workspace/symbol` with one
+/// partial batch, then, a moment later, far more than the stream buffers.
+/// It creates `cancelled` once the request is cancelled.
+const FAKE_SERVER: &str = r#"#!/bin/bash
+send() { printf 'Content-Length: %d\r\n\r\n%s' "${#1}" "$1"; }
+progress() { send "{\"jsonrpc\":\"2.0\",\"method\":\"\$/progress\",\"params\":{\"token\":\"$1\",\"value\":[{\"name\":\"item\"}]}}"; }
+while true; do
+    len=0
+    while IFS= read -r line; do
+        line=${line%$'\r'}
+        [ -z "$line" ] && break
+        case "$line" in Content-Length:*) len=${line#Content-Length: } ;; esac
+    done
+    [ "$len" -eq 0 ] && exit 0
+    body=$(dd bs=1 count="$len" 2>/dev/null)
+    id=$(printf '%s' "$body" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+    case "$body" in
+        *'"method":"$/cancelRequest"'*) touch "$(dirname "$0")/cancelled" ;;
+        *'"method":"workspace/symbol"'*)
+            token=$(printf '%s' "$body" | sed -n 's/.*"partialResultToken":"\([^"]*\)".*/\1/p')
+            progress "$token"
+            sleep 0.5
+            for _ in $(seq 100); do progress "$token"; done ;;
+        *) [ -n "$id" ] && send "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"capabilities\":{}}}" ;;
+    esac
+done
+"#;
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_disconnecting_cancels_the_symbol_request() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = ScratchDir::with_crate("symbol-stream", "");
+    dir.write("src/lib.rs", "pub struct Item;\n");
+    let fake = dir.join("fake-rust-analyzer");
+    std::fs::write(&fake, FAKE_SERVER).unwrap();
+    std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    let config = Config { rust_analyzer_path: fake.display().to_string(), ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    tokio::spawn(start_http_server(server, port));
+
+    let client = HttpClient::new(port);
+    for _ in 0..50 {
+        if client.is_server_running().await {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // Leave after the first batch, while the rest are still to come
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws/symbols?query=item", port)).await.unwrap();
+    let Some(Ok(Message::Text(frame))) = socket.next().await else { panic!("no symbols frame") };
+    let frame: Value = serde_json::from_str(&frame).unwrap();
+    assert_eq!(frame["type"], "symbols");
+    drop(socket);
+
+    let cancelled = dir.join("cancelled");
+    for _ in 0..100 {
+        if cancelled.exists() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(cancelled.exists(), "the workspace/symbol request was never cancelled");
+
+    // The LSP client is free again for other commands
+    let request = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "analyzer_status", "arguments": {}}});
+    let response = tokio::time::timeout(Duration::from_secs(5), client.handle_jsonrpc_request(&request.to_string())).await;
+    assert!(response.is_ok(), "later LSP commands still wait for the symbol stream");
+    client.stop_daemon().await.unwrap();
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::test_code_audit::dev_dependencies;
+use serde_json::{json, Value};
+
+const MANIFEST: &str = r#"[package]
+name = "scratch"
+version = "0.1.0"
+edition = "2021"
+
+[workspace]
+
+[dependencies]
+serde = "1"
+
+[dev-dependencies]
+serde = { version = "1", features = ["derive"] }
+tempfile = "3"
+pretty-assertions = "1"
+
+[target.'cfg(unix)'.dev-dependencies]
+nix = "0.27"
+"#;
+
+const LIB: &str = r#"use std::path::PathBuf;
+use tempfile::TempDir;
+
+mod helpers;
+#[cfg(test)]
+mod fixtures;
+
+#[cfg(test)]
+fn sample() -> u32 {
+    7
+}
+
+#[cfg(all(test, unix))]
+const LIMIT: u32 = 3;
+
+#[cfg(test)]
+pub fn clock() -> u64 { 0 }
+#[cfg(not(test))]
+pub fn clock() -> u64 { 1 }
+
+pub fn scratch_dir() -> PathBuf {
+    // tempfile::tempdir() in a comment is fine
+    let _ = clock();
+    tempfile::tempdir().unwrap().into_path()
+}
+
+pub fn total() -> u32 {
+    sample() + LIMIT
+}
+
+#[tokio::test]
+async fn not_gated() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn adds() {
+        assert_eq!(sample(), 7);
+        let _dir = tempfile::tempdir();
+    }
+}
+"#;
+
+#[test]
+fn test_dev_dependencies_leave_out_regular_ones() {
+    let names: Vec<String> = dev_dependencies(MANIFEST).into_iter().collect();
+    assert_eq!(names, vec!["nix", "pretty_assertions", "tempfile"]);
+}
+
+#[tokio::test]
+async fn test_code_audit_flags_test_only_references() {
+    let dir = ScratchDir::new("test-code-audit");
+    dir.write("Cargo.toml", &MANIFEST.replace("nix = \"0.27\"", "nix = \"0.27\"\ntokio = \"1\""));
+    dir.write("src/lib.rs", LIB);
+    dir.write("src/helpers.rs", "#![cfg(test)]\nuse tempfile::TempDir;\n");
+    dir.write("src/fixtures.rs", "pub fn dir() -> tempfile::TempDir { tempfile::tempdir().unwrap() }\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "test_code_audit", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["test_files"], json!(["src/fixtures.rs", "src/helpers.rs"]), "{result}");
+
+    let items: Vec<(&str, &str)> = result["test_items"].as_array().unwrap().iter()
+        .map(|i| (i["kind"].as_str().unwrap(), i["name"].as_str().unwrap()))
+        .collect();
+    assert_eq!(items, vec![("module", "fixtures"), ("function", "sample"), ("constant", "LIMIT"), ("function", "clock"), ("module", "tests")]);
+    assert_eq!(result["test_items"][2]["condition"], "all(test, unix)");
+    assert_eq!(result["test_items"][4]["end_line"], 44);
+
+    let violations: Vec<(u64, &str, &str)> = result["violations"].as_array().unwrap().iter()
+        .map(|v| (v["line"].as_u64().unwrap(), v["kind"].as_str().unwrap(), v["name"].as_str().unwrap()))
+        .collect();
+    assert_eq!(violations, vec![
+        (2, "dev_dependency", "tempfile"),
+        (24, "dev_dependency", "tempfile"),
+        (28, "test_item", "sample"),
+        (28, "test_item", "LIMIT"),
+        (31, "dev_dependency", "tokio"),
+    ]);
+    assert_eq!(result["total"], 5);
+    assert_eq!(result["violations"][0]["code"], "use tempfile::TempDir;");
+}
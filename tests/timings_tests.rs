@@ -0,0 +1,54 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::timings::{self, Phase};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_collect_sums_phases() {
+    let (output, timings) = timings::collect(async {
+        timings::record(Phase::Cargo, Duration::from_millis(30));
+        timings::record(Phase::Cargo, Duration::from_millis(12));
+        timings::measure_sync(Phase::FileScan, || 7)
+    }).await;
+    assert_eq!(output, 7);
+    assert_eq!(timings.cargo, 42);
+    assert_eq!((timings.lsp_init, timings.lsp_request), (0, 0));
+
+    // Outside `collect` nothing is recorded, and nothing fails
+    timings::record(Phase::LspRequest, Duration::from_millis(1));
+}
+
+async fn call(server: &McpServer) -> Value {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "find_shadowing", "arguments": {}}
+    });
+    serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_timings_in_meta_only_when_enabled() {
+    let root = env!("CARGO_MANIFEST_DIR");
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root, config).await.unwrap();
+    assert!(call(&server).await["result"].get("_meta").is_none());
+
+    let config = Config { use_lsp: false, timings: true, ..Config::default() };
+    let server = McpServer::with_config(root, config).await.unwrap();
+    let response = call(&server).await;
+    let timings = response["result"]["_meta"]["timings"].as_object().unwrap();
+    let mut keys: Vec<&str> = timings.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["cargo", "file_scan", "lsp_init", "lsp_request", "total"]);
+    assert_eq!(timings["lsp_init"], 0);
+    assert!(timings["file_scan"].as_u64().unwrap() <= timings["total"].as_u64().unwrap());
+
+    // Errors carry no result to attach timings to
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "no_such_method" });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    assert!(response.get("result").is_none());
+}
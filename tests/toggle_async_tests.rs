@@ -0,0 +1,164 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::toggle_async::{call_sites, function_at, plan};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const LIB: &str = r#"pub mod client;
+
+pub fn load(path: &str) -> String {
+    // load("commented") isn't a call
+    format!("{}", path)
+}
+
+pub async fn run() -> usize {
+    let text = load("a");
+    let all: Vec<String> = ["b"].iter().map(|p| load(p)).collect();
+    text.len() + all.len()
+}
+
+fn report() {
+    println!("{}", load("c"));
+}
+
+fn later() -> impl std::future::Future<Output = u32> + Send {
+    async move {
+        let value = 1;
+        value + 1
+    }
+}
+"#;
+
+const CLIENT: &str = r#"use std::fs;
+
+pub async fn fetch(url: &str) -> String {
+    let body = crate::load(url);
+    let spawned = async move { crate::load("spawned") };
+    body + &spawned.await
+}
+
+pub fn read_config() -> String {
+    fs::read_to_string("config.toml").unwrap_or_default()
+}
+
+pub async fn cached(key: &str) -> Option<String> {
+    Some(key.to_string())
+}
+
+pub async fn lookup() -> Option<String> {
+    let first = cached("a").await?;
+    let _ = tokio::spawn(cached("b"));
+    Some(first)
+}
+
+pub async fn settle() -> u8 {
+    tokio::task::yield_now().await;
+    1
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {}
+}
+"#;
+
+fn sources() -> BTreeMap<PathBuf, String> {
+    BTreeMap::from([
+        (PathBuf::from("/p/src/lib.rs"), LIB.to_string()),
+        (PathBuf::from("/p/src/client.rs"), CLIENT.to_string()),
+    ])
+}
+
+fn references(name: &str) -> Vec<(PathBuf, usize)> {
+    sources().into_iter()
+        .flat_map(|(path, text)| call_sites(&text, name).into_iter().map(move |at| (path.clone(), at)))
+        .collect()
+}
+
+fn try_plan(file: &str, line: usize) -> anyhow::Result<mcp_rust_analyzer::toggle_async::TogglePlan> {
+    let sources = sources();
+    let file = Path::new(file);
+    let function = function_at(&sources[file], line).unwrap();
+    plan(Path::new("/p"), file, &function, &sources, &references(&function.name))
+}
+
+#[test]
+fn test_function_at_and_call_sites() {
+    let function = function_at(LIB, 5).unwrap();
+    assert_eq!((function.name.as_str(), function.is_async), ("load", false));
+    assert_eq!(function_at(LIB, 9).unwrap().name, "run");
+    assert!(function_at(LIB, 1).is_none());
+    // Comments and the definition aren't calls
+    assert_eq!(call_sites(LIB, "load").len(), 3);
+}
+
+#[test]
+fn test_refuses_with_reasons() {
+    // A closure can't await, and neither can a synchronous caller
+    let error = try_plan("/p/src/lib.rs", 3).unwrap_err().to_string();
+    assert!(error.starts_with("Cannot make `load` async:"), "{}", error);
+    assert!(error.contains("src/lib.rs:10 is inside a closure in `run`"), "{}", error);
+    assert!(error.contains("src/lib.rs:15 is in `report`, which isn't async"), "{}", error);
+
+    let error = try_plan("/p/src/client.rs", 9).unwrap_err().to_string();
+    assert!(error.contains("it blocks at line 10: `fs::read_to_string`"), "{}", error);
+
+    // The spawned call hands the future on instead of awaiting it
+    let error = try_plan("/p/src/client.rs", 13).unwrap_err().to_string();
+    assert!(error.contains("src/client.rs:19 doesn't await the future"), "{}", error);
+    assert!(!error.contains("client.rs:18"), "{}", error);
+
+    let error = try_plan("/p/src/client.rs", 23).unwrap_err().to_string();
+    assert!(error.contains("it awaits at line 24"), "{}", error);
+
+    let error = try_plan("/p/src/client.rs", 29).unwrap_err().to_string();
+    assert!(error.contains("trait"), "{}", error);
+}
+
+#[test]
+fn test_unwraps_returned_future() {
+    let plan = try_plan("/p/src/lib.rs", 19).unwrap();
+    assert!(plan.now_async && plan.unwrapped_future);
+    assert!(plan.call_sites.is_empty());
+    let lib = &plan.changes[Path::new("/p/src/lib.rs")];
+    assert!(lib.ends_with("async fn later() -> u32 {\n    let value = 1;\n    value + 1\n}\n"), "{}", lib);
+}
+
+#[tokio::test]
+async fn test_toggle_async_command() {
+    let dir = ScratchDir::with_crate("toggle-async", "\n[workspace]\n");
+    dir.write("src/lib.rs", "pub async fn total(items: &[u32]) -> u32 {\n    items.iter().sum()\n}\n\npub async fn report() -> String {\n    let sum = total(&[1, 2]).await;\n    format!(\"{}\", sum)\n}\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |dry_run: bool| json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "toggle_async",
+        "params": {"file": "src/lib.rs", "line": 2, "dry_run": dry_run}
+    }).to_string();
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(true)).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["async"], false);
+    assert_eq!(result["method"], "text_search");
+    assert_eq!(result["applied"], false);
+    assert_eq!(result["call_sites"][0]["line"], 6);
+    assert_eq!(result["call_sites"][0]["caller"], "report");
+    let diff = result["diff"].as_str().unwrap();
+    assert!(diff.contains("+pub fn total(items: &[u32]) -> u32 {"), "{}", diff);
+    assert!(diff.contains("+    let sum = total(&[1, 2]);"), "{}", diff);
+
+    let response: Value = serde_json::from_str(&server.handle_request(&call(false)).await.unwrap()).unwrap();
+    assert_eq!(response["result"]["applied"], true);
+    let source = std::fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+    assert!(source.starts_with("pub fn total("));
+
+    // And back again
+    let response: Value = serde_json::from_str(&server.handle_request(&call(false)).await.unwrap()).unwrap();
+    assert_eq!(response["result"]["async"], true);
+    let source = std::fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+    assert!(source.contains("pub async fn total(") && source.contains("total(&[1, 2]).await;"), "{}", source);
+}
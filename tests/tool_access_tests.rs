@@ -0,0 +1,81 @@
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::tool_access::{categories, check_enabled, ToolCategory};
+use serde_json::{json, Value};
+
+#[test]
+fn test_categories() {
+    assert_eq!(categories("get_hover"), vec![ToolCategory::ReadOnly]);
+    assert_eq!(categories("rename"), vec![ToolCategory::Write]);
+    assert_eq!(categories("run_example"), vec![ToolCategory::Execute]);
+    assert_eq!(categories("apply_and_check"), vec![ToolCategory::Write, ToolCategory::Execute]);
+
+    assert!(check_enabled("apply_and_check", &[ToolCategory::ReadOnly, ToolCategory::Write]).is_err());
+    assert!(check_enabled("rename", &[ToolCategory::Write]).is_ok());
+}
+
+#[test]
+fn test_parse_list() {
+    assert_eq!(
+        ToolCategory::parse_list("read_only, execute").unwrap(),
+        vec![ToolCategory::ReadOnly, ToolCategory::Execute]
+    );
+    assert!(ToolCategory::parse_list("read_only,admin").unwrap_err().to_string().contains("admin"));
+
+    let config = Config::parse("tool_categories = [\"read_only\"]").unwrap();
+    assert_eq!(config.tool_categories, vec![ToolCategory::ReadOnly]);
+    assert_eq!(Config::default().tool_categories, ToolCategory::ALL.to_vec());
+}
+
+async fn read_only_server() -> McpServer {
+    let config = Config { use_lsp: false, tool_categories: vec![ToolCategory::ReadOnly], ..Config::default() };
+    McpServer::with_config(env!("CARGO_MANIFEST_DIR"), config).await.unwrap()
+}
+
+async fn call(server: &McpServer, request: Value) -> Value {
+    serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_disabled_tools_not_listed() {
+    let server = read_only_server().await;
+    let response = call(&server, json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" })).await;
+    let names: Vec<&str> = response["result"]["tools"].as_array().unwrap()
+        .iter()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+
+    assert!(names.contains(&"project_structure"));
+    for tool in ["rename", "convert_control_flow", "run_example", "apply_and_check", "get_diagnostics"] {
+        assert!(!names.contains(&tool), "{} should not be listed", tool);
+    }
+}
+
+#[tokio::test]
+async fn test_disabled_tools_rejected() {
+    let server = read_only_server().await;
+    let response = call(&server, json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": { "name": "run_example", "arguments": { "name": "demo" } }
+    })).await;
+    let message = response["error"]["message"].as_str().unwrap();
+    assert!(message.contains("run_example is disabled") && message.contains("execute"), "{}", message);
+
+    let response = call(&server, json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "rename",
+        "params": { "file": "src/lib.rs", "line": 1, "column": 1, "new_name": "x" }
+    })).await;
+    assert!(response["error"]["message"].as_str().unwrap().contains("write"));
+
+    let response = call(&server, json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "tools/call",
+        "params": { "name": "project_structure", "arguments": {} }
+    })).await;
+    assert!(response["result"].is_object(), "{}", response);
+}
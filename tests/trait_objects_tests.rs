@@ -0,0 +1,90 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::trait_objects::scan_source;
+use serde_json::{json, Value};
+
+const SOURCE: &str = r#"use std::sync::Arc;
+
+/// Handlers keep a `Box<dyn Fn()>` around; this comment is skipped
+pub struct Registry {
+    handlers: Vec<Box<dyn Fn(&str) -> usize + Send + Sync>>,
+    sink: Arc<dyn std::io::Write>,
+}
+
+pub type Callback<'a> = &'a mut dyn FnMut(u32);
+
+impl Registry {
+    pub fn register(&mut self, handler: Box<dyn Fn(&str) -> usize + Send + Sync>) {
+        let message = "dyn Debug in a string";
+        let _ = message;
+        self.handlers.push(handler);
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        let shown: &dyn std::fmt::Debug = &1;
+        let _ = shown;
+        Box::new(std::iter::empty())
+    }
+}
+
+pub const fn raw(p: *const dyn Send) -> *const dyn Send {
+    p
+}
+
+pub fn cause(e: &(dyn std::error::Error + 'static)) {}
+
+#[cfg(test)]
+mod tests {
+    fn helper(_: &dyn std::any::Any) {}
+}
+"#;
+
+/// Line, trait, container, position and item
+type Site<'a> = (usize, &'a str, Option<&'a str>, &'a str, Option<&'a str>);
+
+#[test]
+fn test_scan_source_classifies_dyn_sites() {
+    let sites = scan_source(SOURCE, false);
+    let summary: Vec<Site> = sites.iter()
+        .map(|s| (s.line, s.trait_name.as_str(), s.container.as_deref(), s.position.as_str(), s.item.as_deref()))
+        .collect();
+    assert_eq!(summary, vec![
+        (5, "Fn", Some("Box"), "field", Some("Registry")),
+        (6, "std::io::Write", Some("Arc"), "field", Some("Registry")),
+        (9, "FnMut", Some("&mut"), "type_alias", Some("Callback")),
+        (12, "Fn", Some("Box"), "parameter", Some("Registry::register")),
+        (18, "Iterator", Some("Box"), "return", Some("Registry::iter")),
+        (19, "std::fmt::Debug", Some("&"), "body", Some("Registry::iter")),
+        (25, "Send", Some("*const"), "parameter", Some("raw")),
+        (25, "Send", Some("*const"), "return", Some("raw")),
+        (29, "std::error::Error", Some("&"), "parameter", Some("cause")),
+    ]);
+    assert_eq!(sites[0].text, "dyn Fn(&str) -> usize + Send + Sync");
+    assert_eq!(sites[0].bounds, vec!["Send", "Sync"]);
+    assert_eq!(sites[4].text, "dyn Iterator<Item = u32> + '_");
+    assert_eq!(sites[4].bounds, vec!["'_"]);
+
+    let with_tests = scan_source(SOURCE, true);
+    assert_eq!(with_tests.last().map(|s| (s.trait_name.as_str(), s.item.as_deref())), Some(("std::any::Any", Some("helper"))));
+}
+
+#[tokio::test]
+async fn test_find_dyn_command() {
+    let dir = ScratchDir::with_crate("find-dyn", "");
+    dir.write("src/lib.rs", SOURCE);
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "find_dyn", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["total"], 9, "{result}");
+    assert_eq!(result["by_trait"]["Fn"], 2);
+    assert_eq!(result["by_position"]["parameter"], 3);
+    assert_eq!(result["sites"][1]["trait"], "std::io::Write");
+    assert_eq!(result["sites"][1]["file"], "src/lib.rs");
+    assert_eq!(result["sites"][1]["column"], 15);
+}
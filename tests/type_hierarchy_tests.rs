@@ -0,0 +1,65 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::type_hierarchy::{types_from_lsp, Direction, TypeHierarchy};
+use serde_json::{json, Value};
+use std::path::Path;
+
+fn item(name: &str, uri: &str, line: u64, kind: u64) -> Value {
+    json!({
+        "name": name,
+        "kind": kind,
+        "uri": uri,
+        "range": { "start": { "line": line, "character": 0 }, "end": { "line": line + 2, "character": 1 } },
+        "selectionRange": { "start": { "line": line, "character": 11 }, "end": { "line": line, "character": 11 + name.len() } }
+    })
+}
+
+#[test]
+fn test_types_from_lsp() {
+    let result = json!([
+        item("Shape", "file:///work/demo/src/shape.rs", 0, 11),
+        item("Circle", "file:///work/demo/src/circle.rs", 3, 23),
+        item("Display", "file:///rust/core/src/fmt/mod.rs", 40, 11),
+        { "name": "broken" }
+    ]);
+    let types = types_from_lsp(&result, Path::new("/work/demo"));
+    let found: Vec<(&str, &str, &str, u32)> = types.iter()
+        .map(|t| (t.name.as_str(), t.kind.as_str(), t.location.file.as_str(), t.location.line))
+        .collect();
+    assert_eq!(found, vec![
+        ("Shape", "trait", "src/shape.rs", 1),
+        ("Circle", "struct", "src/circle.rs", 4),
+        ("Display", "trait", "/rust/core/src/fmt/mod.rs", 41),
+    ]);
+    assert!(types_from_lsp(&Value::Null, Path::new("/work/demo")).is_empty());
+}
+
+#[test]
+fn test_type_hierarchy_serializes_its_root_as_type() {
+    let hierarchy = TypeHierarchy { root: None, direction: Direction::Subtypes, types: Vec::new() };
+    assert_eq!(serde_json::to_value(&hierarchy).unwrap(), json!({ "type": null, "direction": "subtypes", "types": [] }));
+}
+
+#[tokio::test]
+async fn test_type_hierarchy_without_rust_analyzer() {
+    let dir = ScratchDir::with_crate("type-hierarchy", "");
+    dir.write("src/lib.rs", "pub trait Shape {}\n\npub struct Circle;\n\nimpl Shape for Circle {}\n");
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let call = |params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "type_hierarchy", "params": params });
+        let server = &server;
+        async move { serde_json::from_str::<Value>(&server.handle_request(&request.to_string()).await.unwrap()).unwrap() }
+    };
+
+    let result = call(json!({"file": "src/lib.rs", "line": 1, "column": 11})).await["result"].clone();
+    assert_eq!(result["direction"], "supertypes", "{result}");
+    assert_eq!(result["type"], Value::Null);
+    assert_eq!(result["types"], json!([]));
+
+    let result = call(json!({"file": "src/lib.rs", "line": 1, "column": 11, "direction": "subtypes"})).await["result"].clone();
+    assert_eq!(result["direction"], "subtypes", "{result}");
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::unhandled_results::{fallible_functions, scan_source};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const SOURCE: &str = r#"use std::fs;
+
+pub fn load(path: &str) -> std::io::Result<String> {
+    fs::read_to_string(path)
+}
+
+fn first(items: &[u32])
+    -> Option<u32>
+{
+    items.first().copied()
+}
+
+pub fn run() {
+    let _ = load("a");
+    load("b").ok();
+    first(&[1]);
+    let kept = first(&[2]);
+    let _unused = kept;
+    load("c").unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    fn t() {
+        let _ = super::load("x");
+    }
+}
+"#;
+
+#[test]
+fn test_fallible_functions_and_scan() {
+    let fallible: HashMap<String, &'static str> = fallible_functions(SOURCE).into_iter().collect();
+    assert_eq!(fallible.get("load"), Some(&"Result"));
+    assert_eq!(fallible.get("first"), Some(&"Option"));
+    assert!(!fallible.contains_key("run"));
+
+    let findings = scan_source(SOURCE, &fallible, false);
+    let kinds: Vec<(usize, &str)> = findings.iter().map(|f| (f.line, f.kind.as_str())).collect();
+    assert_eq!(kinds, vec![(14, "let_underscore"), (15, "ok_discarded"), (16, "discarded_return")]);
+    assert_eq!(findings[2].message, "Option returned by `first` is discarded");
+
+    assert_eq!(scan_source(SOURCE, &fallible, true).len(), 4);
+}
+
+#[tokio::test]
+async fn test_find_unhandled_results_merges_cargo_lint() {
+    let dir = ScratchDir::with_crate("unhandled", "\n[workspace]\n");
+    dir.write(
+        "src/lib.rs",
+        "pub fn save() -> Result<(), String> {\n    Ok(())\n}\n\npub fn run() {\n    save();\n    let _ = save();\n}\n",
+    );
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "find_unhandled_results",
+        "params": { "method": "find_unhandled_results" }
+    });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+
+    assert_eq!(result["cargo_check_success"], true);
+    assert_eq!(result["total"], 2);
+    // The compiler already reports line 6, so the scan does not repeat it
+    assert_eq!(result["findings"][0]["line"], 6);
+    assert_eq!(result["findings"][0]["kind"], "unused_must_use");
+    assert_eq!(result["findings"][0]["source"], "cargo");
+    assert_eq!(result["findings"][1]["kind"], "let_underscore");
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::unresolved_imports::collect;
+use serde_json::{json, Value};
+
+fn unresolved(unresolved: &str, line: u64, columns: (u64, u64), text: &str, help: Option<(u64, u64, &str)>) -> Value {
+    let children = match help {
+        Some((start, end, replacement)) => json!([{
+            "level": "help",
+            "message": "a similar name exists in the module",
+            "spans": [{ "line_start": line, "line_end": line, "column_start": start, "column_end": end, "suggested_replacement": replacement }]
+        }]),
+        None => json!([]),
+    };
+    json!({
+        "reason": "compiler-message",
+        "message": {
+            "level": "error",
+            "message": format!("unresolved import `{}`", unresolved),
+            "code": { "code": "E0432" },
+            "spans": [{
+                "file_name": "src/lib.rs", "line_start": line, "line_end": line,
+                "column_start": columns.0, "column_end": columns.1, "is_primary": true, "label": "not found",
+                "text": [{ "text": text }]
+            }],
+            "children": children
+        }
+    })
+}
+
+#[test]
+fn test_collect_keeps_compiler_fixes_and_skips_duplicates() {
+    let messages = vec![
+        unresolved("crate::models::Widgit", 1, (5, 26), "use crate::models::Widgit;", Some((20, 26, "Widget"))),
+        // The same import seen again while checking another target
+        unresolved("crate::models::Widgit", 1, (5, 26), "use crate::models::Widgit;", Some((20, 26, "Widget"))),
+        unresolved("std::colections", 2, (16, 26), "use std::{fmt, colections::BTreeMap};", None),
+        json!({ "reason": "compiler-message", "message": { "level": "error", "message": "mismatched types", "code": { "code": "E0308" }, "spans": [] } }),
+    ];
+
+    let imports = collect(&messages);
+    assert_eq!(imports.len(), 2);
+    assert_eq!((imports[0].line, imports[0].column), (1, 5));
+    assert_eq!(imports[0].suggestions.len(), 1);
+    assert_eq!(imports[0].suggestions[0].kind, "compiler");
+    assert_eq!(imports[0].suggestions[0].replacement, "crate::models::Widget");
+    assert_eq!(imports[0].suggestions[0].edit.as_ref().unwrap()["range"]["start"], json!({"line": 0, "character": 19}));
+    assert_eq!(imports[1].unresolved, "std::colections");
+    assert!(imports[1].suggestions.is_empty());
+}
+
+#[tokio::test]
+async fn test_unresolved_imports_suggests_fixes() {
+    let dir = ScratchDir::with_crate("unresolved-imports", "\n[workspace]\n");
+    dir.write(
+        "src/lib.rs",
+        "use serde_jsn::Value;\nuse crate::modles::Widget;\nuse std::{fmt, colections::BTreeMap};\nuse crate::Gadget;\n\npub mod models {\n    pub struct Widget;\n}\n\npub mod parts {\n    pub struct Gadget;\n}\n",
+    );
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "unresolved_imports", "params": {} });
+    let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+    let result = &response["result"];
+    assert_eq!(result["success"], false);
+    assert_eq!(result["count"], 4, "{result}");
+    let imports = result["imports"].as_array().unwrap();
+    let first = |unresolved: &str| {
+        let import = imports.iter().find(|i| i["unresolved"] == unresolved).unwrap_or_else(|| panic!("{unresolved} in {result}"));
+        (import["suggestions"][0]["kind"].as_str().unwrap().to_string(), import["suggestions"][0].clone())
+    };
+
+    let (kind, fix) = first("serde_jsn");
+    assert_eq!(kind, "dependency");
+    assert_eq!(fix["command"], "cargo add serde_jsn");
+
+    let (kind, fix) = first("crate::modles");
+    assert_eq!(kind, "typo");
+    assert_eq!(fix["replacement"], "crate::models::Widget");
+    assert_eq!(fix["edit"], json!({
+        "range": { "start": { "line": 1, "character": 11 }, "end": { "line": 1, "character": 17 } },
+        "newText": "models"
+    }));
+
+    let (kind, fix) = first("std::colections");
+    assert_eq!(kind, "typo");
+    assert_eq!(fix["replacement"], "collections::BTreeMap");
+
+    let (_, fix) = first("crate::Gadget");
+    assert_eq!(fix["replacement"], "crate::parts::Gadget", "{result}");
+    let gadget = imports.iter().find(|i| i["unresolved"] == "crate::Gadget").unwrap();
+    assert_eq!(gadget["declaration"], "use crate::Gadget;");
+}
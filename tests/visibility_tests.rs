@@ -0,0 +1,81 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use mcp_rust_analyzer::visibility::{parse_item, Visibility};
+use serde_json::{json, Value};
+
+#[test]
+fn test_parse_visibility() {
+    assert_eq!(Visibility::parse("pub fn a()").0, Visibility::Public);
+    assert_eq!(Visibility::parse("pub(crate) struct A;").0, Visibility::Crate);
+    assert_eq!(Visibility::parse("pub(super) const X: u8 = 1;").0, Visibility::Super);
+    assert_eq!(Visibility::parse("pub(in crate::a) fn f()").0, Visibility::Restricted("crate::a".to_string()));
+    assert_eq!(Visibility::parse("fn private()").0, Visibility::Private);
+    assert_eq!(Visibility::parse("pub(in crate::a) fn f()").0.as_str(), "pub(in crate::a)");
+
+    let (visibility, kind, name) = parse_item("    pub const fn answer() -> u32 {").unwrap();
+    assert_eq!((visibility, kind, name.as_str()), (Visibility::Public, "function", "answer"));
+    assert!(parse_item("let x = 1;").is_none());
+}
+
+fn write_project(root: &std::path::Path) {
+    std::fs::create_dir_all(root.join("src/outer")).unwrap();
+    std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"v\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub mod api;\nmod outer;\n\npub use outer::inner::Exported;\n").unwrap();
+    std::fs::write(root.join("src/api.rs"), "pub struct Client;\n\npub(crate) fn helper() {}\n\nmod detail {\n    pub fn hidden() {}\n}\n").unwrap();
+    std::fs::write(root.join("src/outer/mod.rs"), "pub mod inner;\n").unwrap();
+    std::fs::write(root.join("src/outer/inner.rs"), "pub struct Exported;\n\npub struct Stranded;\n\npub(super) fn up() {}\n").unwrap();
+}
+
+#[tokio::test]
+async fn test_effective_visibility() {
+    let root = ScratchDir::new("visibility");
+    write_project(&root);
+
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(root.to_str().unwrap(), config).await.unwrap();
+    let visibility = |file: &str, line: u32| {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "visibility", "params": {"file": file, "line": line, "column": 12}});
+        let server = &server;
+        async move {
+            let response: Value = serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap();
+            response["result"].clone()
+        }
+    };
+
+    let client = visibility("src/api.rs", 1).await;
+    assert_eq!(client["effective"], "pub");
+    assert_eq!(client["externally_reachable"], true);
+    assert_eq!(client["module_path"], "crate::api");
+
+    let helper = visibility("src/api.rs", 3).await;
+    assert_eq!(helper["declared"], "pub(crate)");
+    assert_eq!(helper["effective"], "pub(crate)");
+    assert_eq!(helper["limited_by"]["by"], "item");
+
+    // `pub` inside a private inline module
+    let hidden = visibility("src/api.rs", 6).await;
+    assert_eq!(hidden["module_path"], "crate::api::detail");
+    assert_eq!(hidden["effective"], "pub(in crate::api)");
+    assert_eq!(hidden["pub_but_unreachable"], true);
+    assert_eq!(hidden["limited_by"]["by"], "crate::api::detail");
+    assert_eq!(hidden["limited_by"]["line"], 5);
+
+    // `pub` inside a private file module, rescued by a `pub use` at the root
+    let exported = visibility("src/outer/inner.rs", 1).await;
+    assert_eq!(exported["externally_reachable"], true);
+    assert_eq!(exported["reexported_as"], json!(["crate::Exported"]));
+    assert_eq!(exported["pub_but_unreachable"], false);
+
+    let stranded = visibility("src/outer/inner.rs", 3).await;
+    assert_eq!(stranded["effective"], "pub(crate)");
+    assert_eq!(stranded["pub_but_unreachable"], true);
+    assert_eq!(stranded["limited_by"]["by"], "crate::outer");
+    assert_eq!(stranded["limited_by"]["file"], "src/lib.rs");
+    assert_eq!(stranded["limited_by"]["line"], 2);
+
+    let up = visibility("src/outer/inner.rs", 5).await;
+    assert_eq!(up["effective"], "pub(in crate::outer)");
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::workspace_edit::{apply_edits_to_text, apply_workspace_edit, uri_to_path};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+fn scratch_dir(name: &str) -> ScratchDir {
+    let dir = ScratchDir::new(&format!("ws-edit-{}", name));
+    dir
+}
+
+fn uri(path: &std::path::Path) -> String {
+    format!("file://{}", path.display())
+}
+
+#[tokio::test]
+async fn test_create_file_then_edit_it() {
+    let dir = scratch_dir("create");
+    let lib = dir.join("lib.rs");
+    std::fs::write(&lib, "fn helper() {}\n").unwrap();
+    let new_module = dir.join("helpers.rs");
+
+    // Shape of an "extract to new module" assist: create, fill, then rewrite the parent
+    let edit = json!({
+        "documentChanges": [
+            { "kind": "create", "uri": uri(&new_module) },
+            {
+                "textDocument": { "uri": uri(&new_module), "version": null },
+                "edits": [{
+                    "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+                    "newText": "pub fn helper() {}\n"
+                }]
+            },
+            {
+                "textDocument": { "uri": uri(&lib), "version": 1 },
+                "edits": [{
+                    "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 14 } },
+                    "newText": "mod helpers;"
+                }]
+            }
+        ]
+    });
+
+    let summary = apply_workspace_edit(&edit).await.unwrap();
+    assert_eq!(summary.files_created.len(), 1);
+    assert_eq!(summary.edits_applied, 2);
+    assert_eq!(std::fs::read_to_string(&new_module).unwrap(), "pub fn helper() {}\n");
+    assert_eq!(std::fs::read_to_string(&lib).unwrap(), "mod helpers;\n");
+}
+
+#[tokio::test]
+async fn test_rename_then_delete() {
+    let dir = scratch_dir("rename");
+    let old = dir.join("old.rs");
+    let new = dir.join("sub").join("new.rs");
+    let stale = dir.join("stale.rs");
+    std::fs::write(&old, "struct A;\n").unwrap();
+    std::fs::write(&stale, "").unwrap();
+
+    let edit = json!({
+        "documentChanges": [
+            { "kind": "rename", "oldUri": uri(&old), "newUri": uri(&new) },
+            {
+                "textDocument": { "uri": uri(&new), "version": null },
+                "edits": [{
+                    "range": { "start": { "line": 0, "character": 7 }, "end": { "line": 0, "character": 8 } },
+                    "newText": "B"
+                }]
+            },
+            { "kind": "delete", "uri": uri(&stale) }
+        ]
+    });
+
+    let summary = apply_workspace_edit(&edit).await.unwrap();
+    assert_eq!(summary.files_renamed.len(), 1);
+    assert_eq!(summary.files_deleted.len(), 1);
+    assert!(!old.exists());
+    assert!(!stale.exists());
+    assert_eq!(std::fs::read_to_string(&new).unwrap(), "struct B;\n");
+}
+
+#[tokio::test]
+async fn test_create_existing_file_respects_options() {
+    let dir = scratch_dir("exists");
+    let file = dir.join("a.rs");
+    std::fs::write(&file, "keep").unwrap();
+
+    let edit = json!({ "documentChanges": [{ "kind": "create", "uri": uri(&file) }] });
+    assert!(apply_workspace_edit(&edit).await.is_err());
+
+    let edit = json!({ "documentChanges": [{ "kind": "create", "uri": uri(&file), "options": { "ignoreIfExists": true } }] });
+    apply_workspace_edit(&edit).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep");
+}
+
+#[test]
+fn test_uri_to_path_decodes_escapes() {
+    assert_eq!(uri_to_path("file:///tmp/my%20crate/lib.rs"), PathBuf::from("/tmp/my crate/lib.rs"));
+    assert_eq!(uri_to_path("file:///tmp/caf%C3%A9.rs"), PathBuf::from("/tmp/café.rs"));
+    // A `%` not followed by two hex digits is kept, even before a multibyte character
+    assert_eq!(uri_to_path("file:///a/%aé"), PathBuf::from("/a/%aé"));
+    assert_eq!(uri_to_path("file:///a/%éa"), PathBuf::from("/a/%éa"));
+}
+
+fn edit(start: (u32, u32), end: (u32, u32), text: &str) -> Value {
+    json!({
+        "range": {
+            "start": { "line": start.0, "character": start.1 },
+            "end": { "line": end.0, "character": end.1 }
+        },
+        "newText": text
+    })
+}
+
+#[test]
+fn test_multiple_edits_on_same_line() {
+    let content = "let a = foo(b, c);\n";
+    // Given in ascending order; applying front-to-back would corrupt the later ranges
+    let edits = vec![
+        edit((0, 4), (0, 5), "alpha"),
+        edit((0, 8), (0, 11), "bar"),
+        edit((0, 12), (0, 13), "beta"),
+        edit((0, 15), (0, 16), "gamma"),
+    ];
+    assert_eq!(apply_edits_to_text(content, &edits).unwrap(), "let alpha = bar(beta, gamma);\n");
+}
+
+#[test]
+fn test_edits_across_lines() {
+    let content = "fn a() {\n    one();\n    two();\n}\n";
+    let edits = vec![
+        edit((2, 4), (2, 7), "three"),
+        edit((1, 4), (2, 4), ""),
+        edit((0, 3), (0, 4), "main"),
+    ];
+    assert_eq!(apply_edits_to_text(content, &edits).unwrap(), "fn main() {\n    three();\n}\n");
+}
+
+#[test]
+fn test_edits_at_end_of_file() {
+    // With a trailing newline the end of file is the start of the (empty) last line
+    let content = "a\nb\n";
+    let edits = vec![edit((2, 0), (2, 0), "c\n"), edit((1, 1), (1, 1), "!")];
+    assert_eq!(apply_edits_to_text(content, &edits).unwrap(), "a\nb!\nc\n");
+
+    // Without one, a character past the last line's end clamps to it
+    let content = "a\nb";
+    let edits = vec![edit((1, 99), (1, 99), "\nc")];
+    assert_eq!(apply_edits_to_text(content, &edits).unwrap(), "a\nb\nc");
+}
+
+#[test]
+fn test_inserts_at_same_position_keep_order() {
+    let edits = vec![edit((0, 0), (0, 0), "use a;\n"), edit((0, 0), (0, 0), "use b;\n")];
+    assert_eq!(apply_edits_to_text("fn f() {}\n", &edits).unwrap(), "use a;\nuse b;\nfn f() {}\n");
+}
+
+#[test]
+fn test_utf16_positions_and_overlaps() {
+    // The emoji is two UTF-16 code units, four UTF-8 bytes
+    let content = "let s = \"😀\"; x\n";
+    let edits = vec![edit((0, 14), (0, 15), "y")];
+    assert_eq!(apply_edits_to_text(content, &edits).unwrap(), "let s = \"😀\"; y\n");
+
+    let overlapping = vec![edit((0, 0), (0, 5), "a"), edit((0, 3), (0, 7), "b")];
+    assert!(apply_edits_to_text(content, &overlapping).is_err());
+}
+
+#[tokio::test]
+async fn test_sequential_document_edits_use_current_content() {
+    let dir = scratch_dir("sequential");
+    let file = dir.join("lib.rs");
+    std::fs::write(&file, "fn a() {}\n").unwrap();
+
+    // The second edit addresses the document as left by the first
+    let edit = json!({
+        "documentChanges": [
+            { "textDocument": { "uri": uri(&file), "version": 1 }, "edits": [edit((0, 0), (0, 0), "// header\n")] },
+            { "textDocument": { "uri": uri(&file), "version": 2 }, "edits": [edit((1, 3), (1, 4), "b")] }
+        ]
+    });
+    apply_workspace_edit(&edit).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "// header\nfn b() {}\n");
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::ScratchDir;
+use mcp_rust_analyzer::config::Config;
+use mcp_rust_analyzer::server::McpServer;
+use serde_json::{json, Value};
+
+const LIB: &str = "/// Answer\n#[derive(Debug, Clone)]\npub struct Answer(pub u32);\n\npub use Answer as Reply;\n";
+
+async fn call(server: &McpServer, request: Value) -> Value {
+    serde_json::from_str(&server.handle_request(&request.to_string()).await.unwrap()).unwrap()
+}
+
+/// Every tool whose schema requires nothing must work with `arguments` omitted or null
+#[tokio::test]
+async fn test_zero_argument_tools_without_arguments() {
+    let dir = ScratchDir::with_crate("zero-arg-tools", "\n[workspace]\n");
+    dir.write("src/lib.rs", LIB);
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let list = call(&server, json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"})).await;
+    let zero_arg: Vec<String> = list["result"]["tools"].as_array().unwrap().iter()
+        .filter(|tool| tool["inputSchema"]["required"].as_array().is_none_or(|r| r.is_empty()))
+        .map(|tool| tool["name"].as_str().unwrap().to_string())
+        .collect();
+    assert!(zero_arg.contains(&"project_structure".to_string()));
+    assert!(zero_arg.contains(&"find_dead_code".to_string()));
+
+    for name in &zero_arg {
+        let omitted = call(&server, json!({
+            "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": name}
+        })).await;
+        assert!(omitted.get("error").is_none(), "{} failed without arguments: {}", name, omitted);
+        assert!(omitted["result"]["content"][0]["text"].is_string(), "{}: {}", name, omitted);
+
+        let null = call(&server, json!({
+            "jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": name, "arguments": null}
+        })).await;
+        assert!(null.get("error").is_none(), "{} failed with null arguments: {}", name, null);
+    }
+}
+
+#[tokio::test]
+async fn test_direct_call_without_params() {
+    let dir = ScratchDir::with_crate("zero-arg-direct", "\n[workspace]\n");
+    dir.write("src/lib.rs", LIB);
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    let response = call(&server, json!({"jsonrpc": "2.0", "id": 1, "method": "analyze_dependencies"})).await;
+    assert!(response.get("error").is_none(), "{}", response);
+    assert!(response["result"].is_object());
+}
+
+#[tokio::test]
+async fn test_arguments_must_be_an_object() {
+    let dir = ScratchDir::with_crate("zero-arg-invalid", "\n[workspace]\n");
+    dir.write("src/lib.rs", LIB);
+    let config = Config { use_lsp: false, ..Config::default() };
+    let server = McpServer::with_config(dir.to_str().unwrap(), config).await.unwrap();
+
+    for arguments in [json!("src/lib.rs"), json!(["src/lib.rs"]), json!(3)] {
+        let response = call(&server, json!({
+            "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+            "params": {"name": "file_summary", "arguments": arguments}
+        })).await;
+        assert_eq!(response["id"], 4);
+        assert_eq!(response["error"]["code"], -32602, "{}", response);
+        assert_eq!(response["error"]["message"], "Invalid params: arguments must be an object");
+    }
+}